@@ -0,0 +1,307 @@
+use std::time::{Duration, Instant};
+
+use openfs_config::HumanBytes;
+use openfs_core::VfsMetrics;
+use openfs_remote::{grep, GrepOptions, Vfs};
+use serde_json::{json, Value};
+
+use crate::output::{print_json, OutputFormat};
+
+/// Options for `openfs bench`.
+pub struct BenchArgs {
+    /// Workloads to run, in order.
+    pub workloads: Vec<Workload>,
+    /// How long each workload runs.
+    pub duration: Duration,
+    /// Concurrent workers per workload.
+    pub concurrency: usize,
+    /// Object sizes and their relative weights.
+    pub sizes: Vec<(u64, u32)>,
+    /// Number of files in the working set.
+    pub files: usize,
+    /// Leave the benchmark files in place afterwards.
+    pub keep: bool,
+    /// Output format.
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workload {
+    Read,
+    Write,
+    List,
+    Grep,
+}
+
+impl Workload {
+    /// Parse a `--workloads` entry.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "read" => Ok(Workload::Read),
+            "write" => Ok(Workload::Write),
+            "list" => Ok(Workload::List),
+            "grep" => Ok(Workload::Grep),
+            other => Err(format!(
+                "unknown workload '{}' (read, write, list, grep)",
+                other
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Workload::Read => "read",
+            Workload::Write => "write",
+            Workload::List => "list",
+            Workload::Grep => "grep",
+        }
+    }
+}
+
+/// Parse one `--sizes` entry: a size with an optional relative weight, such
+/// as `4kb:8` (the weight defaults to 1).
+pub fn parse_size(value: &str) -> Result<(u64, u32), String> {
+    let (size, weight) = match value.split_once(':') {
+        Some((size, weight)) => (
+            size,
+            weight
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid weight in size: {}", value))?,
+        ),
+        None => (value, 1),
+    };
+    let size: HumanBytes = size.parse()?;
+    Ok((size.as_bytes(), weight))
+}
+
+/// Results of one workload against one mount.
+struct BenchResult {
+    workload: Workload,
+    ops: u64,
+    errors: u64,
+    bytes: u64,
+    elapsed: Duration,
+    latencies: Vec<Duration>,
+}
+
+impl BenchResult {
+    fn ops_per_sec(&self) -> f64 {
+        self.ops as f64 / self.elapsed.as_secs_f64()
+    }
+
+    fn mb_per_sec(&self) -> f64 {
+        self.bytes as f64 / (1024.0 * 1024.0) / self.elapsed.as_secs_f64()
+    }
+
+    /// Latency percentile in milliseconds.
+    fn percentile_ms(&self, p: f64) -> Option<f64> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let idx =
+            ((self.latencies.len() as f64 * p / 100.0) as usize).min(self.latencies.len() - 1);
+        Some(self.latencies[idx].as_secs_f64() * 1000.0)
+    }
+
+    fn to_json(&self, mount: &str) -> Value {
+        json!({
+            "mount": mount,
+            "workload": self.workload.name(),
+            "ops": self.ops,
+            "errors": self.errors,
+            "bytes": self.bytes,
+            "duration_secs": self.elapsed.as_secs_f64(),
+            "ops_per_sec": self.ops_per_sec(),
+            "mb_per_sec": self.mb_per_sec(),
+            "latency_ms": {
+                "p50": self.percentile_ms(50.0),
+                "p90": self.percentile_ms(90.0),
+                "p99": self.percentile_ms(99.0),
+                "max": self.percentile_ms(100.0),
+            },
+        })
+    }
+}
+
+pub async fn run(
+    vfs: &Vfs,
+    paths: &[String],
+    args: BenchArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = Vec::new();
+    for path in paths {
+        let dir = format!(
+            "{}/.openfs-bench-{}",
+            path.trim_end_matches('/'),
+            std::process::id()
+        );
+        let files = working_set(&dir, &args);
+        let setup_bytes: u64 = files.iter().map(|(_, size)| size).sum();
+        for (file, size) in &files {
+            vfs.write(file, &payload(*size)).await?;
+        }
+        if !args.output.is_json() {
+            println!(
+                "{}: {} files, {} bytes, {} workers, {:?} per workload",
+                path,
+                files.len(),
+                setup_bytes,
+                args.concurrency,
+                args.duration
+            );
+            println!(
+                "  {:<6} {:>9} {:>10} {:>9} {:>7} {:>9} {:>9} {:>9} {:>9}",
+                "op", "ops", "ops/s", "MB/s", "errors", "p50 ms", "p90 ms", "p99 ms", "max ms"
+            );
+        }
+
+        let mut results = Vec::new();
+        for workload in &args.workloads {
+            let result = run_workload(vfs, *workload, &dir, &files, setup_bytes, &args).await;
+            if !args.output.is_json() {
+                print_result(&result);
+            }
+            results.push(result);
+        }
+
+        if !args.keep {
+            for (file, _) in &files {
+                let _ = vfs.delete(file).await;
+            }
+            let _ = vfs.delete(&dir).await;
+        }
+        report.extend(results.iter().map(|r| r.to_json(path)));
+    }
+
+    if args.output.is_json() {
+        print_json(&json!({ "results": report }))?;
+    }
+    Ok(())
+}
+
+/// Paths and sizes of the benchmark files, following the size weights.
+fn working_set(dir: &str, args: &BenchArgs) -> Vec<(String, u64)> {
+    let mut slots: Vec<u64> = args
+        .sizes
+        .iter()
+        .flat_map(|(size, weight)| std::iter::repeat_n(*size, *weight as usize))
+        .collect();
+    if slots.is_empty() {
+        slots.push(4096);
+    }
+    (0..args.files.max(1))
+        .map(|i| (format!("{}/file-{:05}.txt", dir, i), slots[i % slots.len()]))
+        .collect()
+}
+
+/// Text content of `size` bytes, so grep has lines to scan.
+fn payload(size: u64) -> Vec<u8> {
+    let mut content = Vec::with_capacity(size as usize);
+    let mut line = 0usize;
+    while (content.len() as u64) < size {
+        content.extend_from_slice(format!("openfs bench line {:08}\n", line).as_bytes());
+        line += 1;
+    }
+    content.truncate(size as usize);
+    content
+}
+
+async fn run_workload(
+    vfs: &Vfs,
+    workload: Workload,
+    dir: &str,
+    files: &[(String, u64)],
+    setup_bytes: u64,
+    args: &BenchArgs,
+) -> BenchResult {
+    let metrics = VfsMetrics::new();
+    let start = Instant::now();
+    let deadline = start + args.duration;
+    let concurrency = args.concurrency.max(1);
+
+    let workers = (0..concurrency).map(|worker| {
+        let metrics = &metrics;
+        async move {
+            let mut latencies = Vec::new();
+            // Workers start at different files to avoid hammering one key.
+            let mut next = worker * files.len() / concurrency;
+            while Instant::now() < deadline {
+                let (file, size) = &files[next % files.len()];
+                next += 1;
+                let op_start = Instant::now();
+                match workload {
+                    Workload::Read => match vfs.read(file).await {
+                        Ok(content) => metrics.record_read(content.len() as u64),
+                        Err(_) => metrics.record_read_error(),
+                    },
+                    Workload::Write => match vfs.write(file, &payload(*size)).await {
+                        Ok(()) => metrics.record_write(*size),
+                        Err(_) => metrics.record_write_error(),
+                    },
+                    Workload::List => match vfs.list(dir).await {
+                        Ok(_) => metrics.record_list(),
+                        Err(_) => metrics.record_list_error(),
+                    },
+                    Workload::Grep => {
+                        let options = GrepOptions {
+                            recursive: true,
+                            ..Default::default()
+                        };
+                        // Each grep scans the whole working set.
+                        match grep(vfs, "line 0000000[0-9]", dir, &options).await {
+                            Ok(_) => metrics.record_read(setup_bytes),
+                            Err(_) => metrics.record_read_error(),
+                        }
+                    }
+                }
+                latencies.push(op_start.elapsed());
+            }
+            latencies
+        }
+    });
+    let mut latencies: Vec<Duration> = futures::future::join_all(workers)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    let elapsed = start.elapsed();
+    latencies.sort();
+
+    let snapshot = metrics.snapshot().await;
+    let (ops, errors, bytes) = match workload {
+        Workload::Read | Workload::Grep => {
+            (snapshot.reads, snapshot.read_errors, snapshot.read_bytes)
+        }
+        Workload::Write => (snapshot.writes, snapshot.write_errors, snapshot.write_bytes),
+        Workload::List => (snapshot.lists, snapshot.list_errors, 0),
+    };
+    BenchResult {
+        workload,
+        ops,
+        errors,
+        bytes,
+        elapsed,
+        latencies,
+    }
+}
+
+fn print_result(result: &BenchResult) {
+    let ms = |p: f64| {
+        result
+            .percentile_ms(p)
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    println!(
+        "  {:<6} {:>9} {:>10.1} {:>9.2} {:>7} {:>9} {:>9} {:>9} {:>9}",
+        result.workload.name(),
+        result.ops,
+        result.ops_per_sec(),
+        result.mb_per_sec(),
+        result.errors,
+        ms(50.0),
+        ms(90.0),
+        ms(99.0),
+        ms(100.0)
+    );
+}
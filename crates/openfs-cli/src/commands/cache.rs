@@ -0,0 +1,31 @@
+use openfs_remote::Vfs;
+
+use crate::output::{print_json, OutputFormat};
+
+pub async fn run_warm(
+    vfs: &Vfs,
+    patterns: &[String],
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut warmed = 0;
+    let mut failed = 0;
+    for pattern in patterns {
+        let (ok, errors) = vfs.prefetch_glob(pattern).await?;
+        warmed += ok;
+        failed += errors;
+    }
+
+    if output.is_json() {
+        return print_json(&serde_json::json!({
+            "warmed": warmed,
+            "failed": failed,
+        }));
+    }
+
+    println!("Warmed {} file(s), {} failed.", warmed, failed);
+    if vfs.disk_cache_stats().is_empty() {
+        // Only a disk cache outlives this process.
+        println!("No disk cache is configured, so the warmed contents are not kept.");
+    }
+    Ok(())
+}
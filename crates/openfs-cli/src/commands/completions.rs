@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use clap_complete::Shell;
+use openfs_remote::Vfs;
+
+/// Completes VFS paths in bash by calling `openfs complete-paths`. Options
+/// that take local files keep the default completion.
+const BASH_PATHS: &str = r#"
+_openfs_vfs() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$cur" == /* && "$prev" != @(-c|--config|--to|--out-dir) ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(openfs complete-paths -- "$cur" 2>/dev/null))
+        [[ "${COMPREPLY[0]}" == */ ]] && compopt -o nospace
+        return 0
+    fi
+    _openfs "$@"
+}
+complete -F _openfs_vfs -o bashdefault -o default openfs
+"#;
+
+const ZSH_PATHS: &str = r#"
+_openfs_vfs() {
+    if [[ "$PREFIX" == /* && "$words[CURRENT-1]" != (-c|--config|--to|--out-dir) ]]; then
+        local -a paths
+        paths=(${(f)"$(openfs complete-paths -- "$PREFIX" 2>/dev/null)"})
+        compadd -S '' -- ${(M)paths:#*/}
+        compadd -- ${paths:#*/}
+        return
+    fi
+    _openfs "$@"
+}
+compdef _openfs_vfs openfs
+"#;
+
+const FISH_PATHS: &str = r#"
+complete -c openfs -f -n 'string match -q -- "/*" (commandline -ct)' -a '(openfs complete-paths -- (commandline -ct) 2>/dev/null)'
+"#;
+
+/// Print the completion script for `shell`.
+///
+/// bash, zsh and fish also complete VFS paths (words starting with `/`) from
+/// the configured mounts.
+pub fn run(shell: Shell, mut cmd: clap::Command) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stdout = std::io::stdout();
+    clap_complete::generate(shell, &mut cmd, "openfs", &mut stdout);
+    let paths = match shell {
+        Shell::Bash => BASH_PATHS,
+        Shell::Zsh => ZSH_PATHS,
+        Shell::Fish => FISH_PATHS,
+        _ => "",
+    };
+    stdout.write_all(paths.as_bytes())?;
+    Ok(())
+}
+
+/// Print the VFS paths that complete `prefix`, one per line. Directories end
+/// with `/`.
+pub async fn complete_paths(vfs: &Vfs, prefix: &str) -> Result<(), Box<dyn std::error::Error>> {
+    for path in path_completions(vfs, prefix).await {
+        println!("{}", path);
+    }
+    Ok(())
+}
+
+/// The VFS paths that complete the absolute `prefix`, sorted. Directories
+/// end with `/`.
+pub async fn path_completions(vfs: &Vfs, prefix: &str) -> Vec<String> {
+    let (dir, partial) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("/", prefix),
+    };
+    let list_dir = if dir.len() > 1 {
+        dir.trim_end_matches('/')
+    } else {
+        dir
+    };
+
+    let names: Vec<(String, bool)> = match vfs.list(list_dir).await {
+        Ok(entries) => entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.is_dir))
+            .collect(),
+        // Without a root mount, "/" holds the mount points.
+        Err(_) if list_dir == "/" => {
+            let config = vfs.effective_config();
+            let mount_paths = config.mounts.iter().map(|mount| &mount.path);
+            mount_paths
+                .chain(config.views.iter().map(|view| &view.path))
+                .filter_map(|path| path.trim_start_matches('/').split('/').next())
+                .filter(|name| !name.is_empty())
+                .map(|name| (name.to_string(), true))
+                .collect()
+        }
+        Err(_) => return Vec::new(),
+    };
+
+    let mut matches: Vec<String> = names
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(partial))
+        .map(|(name, is_dir)| format!("{}{}{}", dir, name, if is_dir { "/" } else { "" }))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+}
@@ -1,11 +1,15 @@
+use openfs_config::{ConfigFormat, VfsConfig};
 use openfs_remote::Vfs;
 
-pub async fn run(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(vfs: &Vfs, format: ConfigFormat) -> Result<(), Box<dyn std::error::Error>> {
     let config = vfs.effective_config();
+    println!("{}", config.to_string_as(format)?);
 
-    // Print as YAML for readability
-    let yaml = serde_yaml::to_string(config)?;
-    println!("{}", yaml);
+    Ok(())
+}
+
+pub fn run_schema() -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(&VfsConfig::schema())?);
 
     Ok(())
 }
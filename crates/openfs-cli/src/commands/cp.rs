@@ -1,9 +1,74 @@
 use openfs_remote::Vfs;
 
-pub async fn run(vfs: &Vfs, src: &str, dst: &str) -> Result<(), Box<dyn std::error::Error>> {
+use super::diff::tree_files;
+use crate::transfer::{Transfer, TransferArgs, TransferOp};
+
+pub async fn run(
+    vfs: &Vfs,
+    src: &str,
+    dst: &str,
+    recursive: bool,
+    args: &TransferArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if vfs.stat(src).await?.is_dir {
+        if !recursive {
+            return Err(format!("{} is a directory (use -r to copy it)", src).into());
+        }
+        return copy_tree(vfs, src, dst, args).await;
+    }
+
     let content = vfs.read(src).await?;
     vfs.write(dst, &content).await?;
     println!("Copied {} -> {} ({} bytes)", src, dst, content.len());
 
     Ok(())
 }
+
+async fn copy_tree(
+    vfs: &Vfs,
+    src: &str,
+    dst: &str,
+    args: &TransferArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let src = src.trim_end_matches('/');
+    let dst = dst.trim_end_matches('/');
+    let files = tree_files(vfs, src).await?;
+    let ops: Vec<TransferOp> = files
+        .iter()
+        .map(|(relative, entry)| TransferOp::Copy {
+            src: entry.path.clone(),
+            dst: format!("{}/{}", dst, relative),
+            size: entry.size.unwrap_or(0),
+        })
+        .collect();
+    let total = files.values().filter_map(|entry| entry.size).sum();
+
+    let transfer = Transfer::new(args, Some(total));
+    let mut copied = 0usize;
+    let mut bytes = 0u64;
+    let mut failed = 0usize;
+    transfer
+        .run(vfs, &ops, |i, result| match result {
+            Ok(size) => {
+                copied += 1;
+                bytes += size;
+            }
+            Err(e) => {
+                if let TransferOp::Copy { src, .. } = &ops[i] {
+                    eprintln!("error: {}: {}", src, e);
+                }
+                failed += 1;
+            }
+        })
+        .await;
+    transfer.finish();
+
+    println!(
+        "Copied {} -> {} ({} files, {} bytes)",
+        src, dst, copied, bytes
+    );
+    if failed > 0 {
+        return Err(format!("{} file(s) failed to copy", failed).into());
+    }
+    Ok(())
+}
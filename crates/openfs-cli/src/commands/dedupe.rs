@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use openfs_core::Entry;
+use openfs_remote::Vfs;
+use serde_json::json;
+
+use crate::output::{print_json, OutputFormat};
+
+/// Files with identical content.
+struct DuplicateSet {
+    checksum: String,
+    size: u64,
+    /// Sorted paths; the first is the one kept.
+    paths: Vec<String>,
+}
+
+impl DuplicateSet {
+    fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+pub async fn run(
+    vfs: &Vfs,
+    path: Option<String>,
+    delete: bool,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.unwrap_or_else(|| "/".to_string());
+    let entries = match vfs.walk(&path).await {
+        Ok(entries) => entries,
+        // Without a root mount, "/" is the set of mounts.
+        Err(_) if path == "/" => {
+            let mut entries = Vec::new();
+            for mount in &vfs.effective_config().mounts {
+                entries.extend(vfs.walk(&mount.path).await?);
+            }
+            entries
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let files: Vec<Entry> = entries.into_iter().filter(|entry| !entry.is_dir).collect();
+
+    // Only files sharing a size can be duplicates, so most are never read.
+    let mut by_size: BTreeMap<u64, Vec<Entry>> = BTreeMap::new();
+    for entry in files {
+        by_size
+            .entry(entry.size.unwrap_or(0))
+            .or_default()
+            .push(entry);
+    }
+
+    let mut sets = Vec::new();
+    for (size, entries) in by_size {
+        if entries.len() < 2 || size == 0 {
+            continue;
+        }
+        let mut by_checksum: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for entry in entries {
+            let checksum = vfs.checksum(&entry.path).await?;
+            by_checksum.entry(checksum).or_default().push(entry.path);
+        }
+        sets.extend(
+            by_checksum
+                .into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(checksum, mut paths)| {
+                    paths.sort();
+                    DuplicateSet {
+                        checksum,
+                        size,
+                        paths,
+                    }
+                }),
+        );
+    }
+    sets.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.paths.cmp(&b.paths)));
+
+    let mut deleted = Vec::new();
+    if delete {
+        for set in &sets {
+            for duplicate in &set.paths[1..] {
+                match vfs.delete(duplicate).await {
+                    Ok(()) => deleted.push(duplicate.clone()),
+                    Err(e) => eprintln!("error: {}: {}", duplicate, e),
+                }
+            }
+        }
+    }
+
+    let wasted: u64 = sets.iter().map(DuplicateSet::wasted).sum();
+    if output.is_json() {
+        let sets: Vec<_> = sets
+            .iter()
+            .map(|set| {
+                json!({
+                    "checksum": set.checksum,
+                    "size": set.size,
+                    "wasted": set.wasted(),
+                    "paths": set.paths,
+                })
+            })
+            .collect();
+        return print_json(&json!({
+            "path": path,
+            "sets": sets,
+            "wasted": wasted,
+            "deleted": deleted,
+        }));
+    }
+
+    if sets.is_empty() {
+        println!("No duplicates under {}", path);
+        return Ok(());
+    }
+    for set in &sets {
+        println!(
+            "{} x {} bytes ({} wasted)",
+            set.paths.len(),
+            set.size,
+            set.wasted()
+        );
+        for (i, duplicate) in set.paths.iter().enumerate() {
+            let marker = if i == 0 {
+                "keep"
+            } else if delete {
+                "del "
+            } else {
+                "    "
+            };
+            println!("  {} {}", marker, duplicate);
+        }
+    }
+    println!(
+        "{} duplicate sets, {} bytes wasted{}",
+        sets.len(),
+        wasted,
+        if delete {
+            format!(", {} files deleted", deleted.len())
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use openfs_core::{unified_diff, Entry, VfsError};
+use openfs_remote::Vfs;
+
+pub async fn run(
+    vfs: &Vfs,
+    a: &str,
+    b: &str,
+    context: usize,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let a_entry = vfs.stat(a).await?;
+    let b_entry = vfs.stat(b).await?;
+
+    match (a_entry.is_dir, b_entry.is_dir) {
+        (false, false) => diff_files(vfs, a, b, context, json).await,
+        (true, true) => diff_trees(vfs, a, b, json).await,
+        _ => Err(format!("Cannot compare a file with a directory: {} and {}", a, b).into()),
+    }
+}
+
+async fn diff_files(
+    vfs: &Vfs,
+    a: &str,
+    b: &str,
+    context: usize,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let a_content = vfs.read(a).await?;
+    let b_content = vfs.read(b).await?;
+    let identical = a_content == b_content;
+
+    let diff = match (
+        std::str::from_utf8(&a_content),
+        std::str::from_utf8(&b_content),
+    ) {
+        (Ok(a_text), Ok(b_text)) => Some(unified_diff(a_text, b_text, a, b, context)),
+        _ => None,
+    };
+
+    if json {
+        let result = serde_json::json!({
+            "type": "file",
+            "a": a,
+            "b": b,
+            "identical": identical,
+            "binary": diff.is_none(),
+            "diff": diff,
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else if !identical {
+        match diff {
+            Some(diff) => print!("{}", diff),
+            None => println!("Binary files {} and {} differ", a, b),
+        }
+    }
+
+    Ok(())
+}
+
+async fn diff_trees(
+    vfs: &Vfs,
+    a: &str,
+    b: &str,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let a_files = tree_files(vfs, a).await?;
+    let b_files = tree_files(vfs, b).await?;
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for (relative, a_entry) in &a_files {
+        match b_files.get(relative) {
+            None => removed.push(relative.clone()),
+            Some(b_entry) => {
+                // Sizes settle most comparisons without reading either file.
+                let differs = a_entry.size != b_entry.size
+                    || vfs.checksum(&a_entry.path).await? != vfs.checksum(&b_entry.path).await?;
+                if differs {
+                    changed.push(relative.clone());
+                }
+            }
+        }
+    }
+    for relative in b_files.keys() {
+        if !a_files.contains_key(relative) {
+            added.push(relative.clone());
+        }
+    }
+
+    if json {
+        let result = serde_json::json!({
+            "type": "tree",
+            "a": a,
+            "b": b,
+            "added": added,
+            "removed": removed,
+            "changed": changed,
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        let mut lines: Vec<(&str, char)> = added
+            .iter()
+            .map(|p| (p.as_str(), 'A'))
+            .chain(removed.iter().map(|p| (p.as_str(), 'D')))
+            .chain(changed.iter().map(|p| (p.as_str(), 'M')))
+            .collect();
+        lines.sort();
+        for (path, status) in lines {
+            println!("{} {}", status, path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Files below `root`, keyed by path relative to it.
+pub(crate) async fn tree_files(vfs: &Vfs, root: &str) -> Result<BTreeMap<String, Entry>, VfsError> {
+    let prefix = root.trim_end_matches('/');
+    Ok(vfs
+        .walk(root)
+        .await?
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| {
+            let relative = entry.path[prefix.len()..]
+                .trim_start_matches('/')
+                .to_string();
+            (relative, entry)
+        })
+        .collect())
+}
@@ -0,0 +1,76 @@
+use openfs_remote::{DirUsage, Vfs};
+
+pub async fn run(
+    vfs: &Vfs,
+    path: Option<String>,
+    max_depth: Option<usize>,
+    sort: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_deref().unwrap_or("/");
+    let mut usage = match vfs.usage(path).await {
+        Ok(usage) => usage,
+        // Without a root mount, "/" is the set of mounts.
+        Err(_) if path == "/" => mounts_usage(vfs).await?,
+        Err(e) => return Err(e.into()),
+    };
+
+    match sort.as_deref() {
+        None | Some("name") => sort_usage(&mut usage, &|a, b| a.path.cmp(&b.path)),
+        Some("size") => sort_usage(&mut usage, &|a, b| b.bytes.cmp(&a.bytes)),
+        Some("files") => sort_usage(&mut usage, &|a, b| b.files.cmp(&a.files)),
+        Some(other) => {
+            return Err(format!("Invalid sort '{}': expected name, size or files", other).into())
+        }
+    }
+
+    print_usage(&usage, 0, max_depth.unwrap_or(usize::MAX));
+    Ok(())
+}
+
+async fn mounts_usage(vfs: &Vfs) -> Result<DirUsage, Box<dyn std::error::Error>> {
+    let mut usage = DirUsage {
+        path: "/".to_string(),
+        ..Default::default()
+    };
+    for mount in &vfs.effective_config().mounts {
+        let child = vfs.usage(&mount.path).await?;
+        usage.bytes += child.bytes;
+        usage.files += child.files;
+        usage.children.push(child);
+    }
+    Ok(usage)
+}
+
+fn sort_usage(usage: &mut DirUsage, cmp: &dyn Fn(&DirUsage, &DirUsage) -> std::cmp::Ordering) {
+    usage.children.sort_by(|a, b| cmp(a, b));
+    for child in &mut usage.children {
+        sort_usage(child, cmp);
+    }
+}
+
+/// Print subdirectories before their parent, like `du`.
+fn print_usage(usage: &DirUsage, depth: usize, max_depth: usize) {
+    if depth < max_depth {
+        for child in &usage.children {
+            print_usage(child, depth + 1, max_depth);
+        }
+    }
+    println!(
+        "{:>8}  {:>8}  {}",
+        format_size(usage.bytes),
+        usage.files,
+        usage.path
+    );
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1}G", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}K", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::stream::{self, StreamExt};
+use openfs_core::Entry;
+use openfs_remote::Vfs;
+
+use crate::transfer::{Transfer, TransferArgs};
+
+/// Whether `path` names a gzip-compressed archive.
+fn is_gzip_path(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".gz") || name.ends_with(".tgz")
+}
+
+pub async fn run(
+    vfs: &Vfs,
+    path: &str,
+    to: &Path,
+    args: &TransferArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = vfs.walk(path).await?;
+    let total = entries.iter().filter_map(|entry| entry.size).sum();
+    let transfer = Transfer::new(args, Some(total));
+
+    let file = File::create(to)?;
+    let (files, bytes) = if is_gzip_path(to) {
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let counts = append_entries(vfs, path, entries, &transfer, args, &mut builder).await?;
+        builder.into_inner()?.finish()?;
+        counts
+    } else {
+        let mut builder = tar::Builder::new(file);
+        let counts = append_entries(vfs, path, entries, &transfer, args, &mut builder).await?;
+        builder.into_inner()?.flush()?;
+        counts
+    };
+    transfer.finish();
+
+    println!(
+        "Exported {} files ({} bytes) from {} to {}",
+        files,
+        bytes,
+        path,
+        to.display()
+    );
+    Ok(())
+}
+
+/// Append the entries below `path` to the archive, returning the file count
+/// and total bytes.
+///
+/// Up to `--jobs` files are read ahead in parallel; they are appended in walk
+/// order.
+async fn append_entries<W: Write>(
+    vfs: &Vfs,
+    path: &str,
+    entries: Vec<Entry>,
+    transfer: &Transfer,
+    args: &TransferArgs,
+    builder: &mut tar::Builder<W>,
+) -> Result<(usize, u64), Box<dyn std::error::Error>> {
+    let root = path.trim_end_matches('/');
+    let mut files = 0usize;
+    let mut bytes = 0u64;
+    let mut reads = stream::iter(entries.into_iter().map(|entry| async move {
+        let content = if entry.is_dir {
+            None
+        } else {
+            Some(transfer.read(vfs, &entry.path).await)
+        };
+        (entry, content)
+    }))
+    .buffered(args.jobs.max(1));
+
+    while let Some((entry, content)) = reads.next().await {
+        let relative = entry.path[root.len()..].trim_start_matches('/');
+        if relative.is_empty() {
+            continue;
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(
+            entry
+                .modified
+                .map(|m| m.timestamp().max(0) as u64)
+                .unwrap_or(0),
+        );
+        match content {
+            None => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                builder.append_data(&mut header, format!("{}/", relative), std::io::empty())?;
+            }
+            Some(content) => {
+                let content = content?;
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(content.len() as u64);
+                builder.append_data(&mut header, relative, content.as_slice())?;
+                transfer.advance(content.len() as u64);
+                files += 1;
+                bytes += content.len() as u64;
+            }
+        }
+    }
+    Ok((files, bytes))
+}
@@ -1,18 +1,27 @@
 use openfs_remote::Vfs;
 use regex::Regex;
+use serde_json::Value;
+
+use crate::output::{entry_json, print_json, OutputFormat};
 
 pub async fn run(
     vfs: &Vfs,
     path: Option<String>,
     pattern: &str,
     file_type: Option<String>,
+    output: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let path = path.as_deref().unwrap_or("/");
     let regex = Regex::new(pattern)?;
     let type_filter = file_type.as_deref();
 
-    find_recursive(vfs, path, &regex, type_filter).await?;
+    // Matches are printed as found, or collected for JSON output.
+    let mut matches = output.is_json().then(Vec::new);
+    find_recursive(vfs, path, &regex, type_filter, &mut matches).await?;
 
+    if let Some(matches) = matches {
+        print_json(&Value::Array(matches))?;
+    }
     Ok(())
 }
 
@@ -22,6 +31,7 @@ async fn find_recursive(
     path: &str,
     pattern: &Regex,
     type_filter: Option<&str>,
+    matches: &mut Option<Vec<Value>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let entries = match vfs.list(path).await {
         Ok(e) => e,
@@ -45,11 +55,14 @@ async fn find_recursive(
         };
 
         if matches_type && pattern.is_match(&entry.name) {
-            println!("{}", full_path);
+            match matches {
+                Some(matches) => matches.push(entry_json(&full_path, &entry)),
+                None => println!("{}", full_path),
+            }
         }
 
         if entry.is_dir {
-            find_recursive(vfs, &full_path, pattern, type_filter).await?;
+            find_recursive(vfs, &full_path, pattern, type_filter, matches).await?;
         }
     }
 
@@ -1,47 +1,87 @@
-use openfs_remote::Vfs;
+use openfs_core::Entry;
+use openfs_remote::{TrigramIndex, TrigramQuery, Vfs};
 use regex::Regex;
+use serde_json::Value;
+
+use crate::output::{print_json, OutputFormat};
 
 pub async fn run(
     vfs: &Vfs,
     pattern: &str,
     path: Option<String>,
     recursive: bool,
+    use_index: bool,
+    output: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let path = path.as_deref().unwrap_or("/");
     let regex = Regex::new(pattern)?;
+    let index = if use_index {
+        let index = TrigramIndex::open(&TrigramIndex::default_path()?)?;
+        Some((index, TrigramQuery::from_regex(pattern)))
+    } else {
+        None
+    };
 
+    // Matches are printed as found, or collected for JSON output.
+    let mut matches = output.is_json().then(Vec::new);
     if recursive {
-        grep_recursive(vfs, path, &regex).await?;
+        grep_recursive(vfs, path, &regex, index.as_ref(), &mut matches).await?;
     } else {
         // Single file
-        grep_file(vfs, path, &regex).await?;
+        if let Ok(content) = vfs.read(path).await {
+            grep_content(path, &content, &regex, &mut matches);
+        }
     }
 
+    if let Some(matches) = matches {
+        print_json(&Value::Array(matches))?;
+    }
     Ok(())
 }
 
+/// Grep a listed file, unless the trigram index rules it out.
 async fn grep_file(
     vfs: &Vfs,
     path: &str,
+    entry: &Entry,
     pattern: &Regex,
+    index: Option<&(TrigramIndex, TrigramQuery)>,
+    matches: &mut Option<Vec<Value>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some((index, query)) = index {
+        if !index.may_match(path, entry, query) {
+            return Ok(());
+        }
+    }
     let content = match vfs.read(path).await {
         Ok(c) => c,
         Err(_) => return Ok(()),
     };
+    if let Some((index, _)) = index {
+        index.update(path, entry, &content)?;
+    }
+    grep_content(path, &content, pattern, matches);
+    Ok(())
+}
 
-    let text = match std::str::from_utf8(&content) {
+fn grep_content(path: &str, content: &[u8], pattern: &Regex, matches: &mut Option<Vec<Value>>) {
+    let text = match std::str::from_utf8(content) {
         Ok(t) => t,
-        Err(_) => return Ok(()), // Skip binary files
+        Err(_) => return, // Skip binary files
     };
 
     for (line_num, line) in text.lines().enumerate() {
         if pattern.is_match(line) {
-            println!("{}:{}:{}", path, line_num + 1, line);
+            match matches {
+                Some(matches) => matches.push(serde_json::json!({
+                    "path": path,
+                    "line_number": line_num + 1,
+                    "line": line,
+                })),
+                None => println!("{}:{}:{}", path, line_num + 1, line),
+            }
         }
     }
-
-    Ok(())
 }
 
 #[async_recursion::async_recursion]
@@ -49,6 +89,8 @@ async fn grep_recursive(
     vfs: &Vfs,
     path: &str,
     pattern: &Regex,
+    index: Option<&(TrigramIndex, TrigramQuery)>,
+    matches: &mut Option<Vec<Value>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let entries = match vfs.list(path).await {
         Ok(e) => e,
@@ -63,9 +105,9 @@ async fn grep_recursive(
         };
 
         if entry.is_dir {
-            grep_recursive(vfs, &full_path, pattern).await?;
+            grep_recursive(vfs, &full_path, pattern, index, matches).await?;
         } else {
-            grep_file(vfs, &full_path, pattern).await?;
+            grep_file(vfs, &full_path, &entry, pattern, index, matches).await?;
         }
     }
 
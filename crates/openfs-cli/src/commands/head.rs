@@ -0,0 +1,18 @@
+use std::io::{self, Write};
+
+use openfs_remote::{LineRange, Vfs};
+
+pub async fn run(
+    vfs: &Vfs,
+    path: &str,
+    lines: usize,
+    bytes: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match bytes {
+        Some(bytes) => vfs.read_range(path, 0, Some(bytes)).await?,
+        None => vfs.read_lines(path, LineRange::Head(lines)).await?,
+    };
+
+    io::stdout().write_all(&content)?;
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Component, Path};
+
+use chrono::DateTime;
+use flate2::read::GzDecoder;
+use openfs_remote::Vfs;
+
+use crate::transfer::{Transfer, TransferArgs, TransferOp};
+
+/// Buffered file content before a batch of writes is started.
+const BATCH_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Counters for an import.
+#[derive(Default)]
+struct Imported {
+    files: usize,
+    bytes: u64,
+    mtimes_kept: usize,
+    failed: usize,
+}
+
+pub async fn run(
+    vfs: &Vfs,
+    archive: &Path,
+    to: &str,
+    args: &TransferArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dst = to.trim_end_matches('/');
+    let mut reader = BufReader::new(File::open(archive)?);
+    // Detect gzip by its magic bytes rather than the file name.
+    let gzip = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    let reader: Box<dyn Read> = if gzip {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+    let mut archive = tar::Archive::new(reader);
+
+    // The archive is read in order; files are written in parallel batches.
+    let transfer = Transfer::new(args, None);
+    let mut imported = Imported::default();
+    let mut batch = Vec::new();
+    let mut mtimes = Vec::new();
+    let mut batch_bytes = 0u64;
+    let mut skipped = 0usize;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            // Directories are created with their first file.
+            continue;
+        }
+        let relative = entry.path()?.into_owned();
+        if !entry_type.is_file() {
+            eprintln!("Skipping {} (not a regular file)", relative.display());
+            skipped += 1;
+            continue;
+        }
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!("Unsafe path in archive: {}", relative.display()).into());
+        }
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        batch_bytes += content.len() as u64;
+        mtimes.push(entry.header().mtime().unwrap_or(0));
+        batch.push(TransferOp::Write {
+            dst: format!(
+                "{}/{}",
+                dst,
+                relative.to_string_lossy().trim_start_matches("./")
+            ),
+            content,
+        });
+
+        if batch_bytes >= BATCH_BYTES || batch.len() >= args.jobs.max(1) * 4 {
+            write_batch(vfs, &transfer, &batch, &mtimes, &mut imported).await?;
+            batch.clear();
+            mtimes.clear();
+            batch_bytes = 0;
+        }
+    }
+    write_batch(vfs, &transfer, &batch, &mtimes, &mut imported).await?;
+    transfer.finish();
+
+    println!(
+        "Imported {} files ({} bytes) into {}",
+        imported.files, imported.bytes, to
+    );
+    if imported.files > 0 && imported.mtimes_kept < imported.files {
+        println!(
+            "Modification times kept for {} of {} files (the backend sets the rest)",
+            imported.mtimes_kept, imported.files
+        );
+    }
+    if skipped > 0 {
+        println!("Skipped {} entries that are not regular files", skipped);
+    }
+    if imported.failed > 0 {
+        return Err(format!("{} file(s) failed to import", imported.failed).into());
+    }
+    Ok(())
+}
+
+/// Write a batch, then restore the archived modification times.
+async fn write_batch(
+    vfs: &Vfs,
+    transfer: &Transfer,
+    batch: &[TransferOp],
+    mtimes: &[u64],
+    imported: &mut Imported,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut written = Vec::new();
+    transfer
+        .run(vfs, batch, |i, result| match result {
+            Ok(size) => {
+                imported.files += 1;
+                imported.bytes += size;
+                written.push(i);
+            }
+            Err(e) => {
+                if let TransferOp::Write { dst, .. } = &batch[i] {
+                    eprintln!("error: {}: {}", dst, e);
+                }
+                imported.failed += 1;
+            }
+        })
+        .await;
+
+    for i in written {
+        let (TransferOp::Write { dst, .. }, mtime) = (&batch[i], mtimes[i]) else {
+            continue;
+        };
+        if let Some(modified) = DateTime::from_timestamp(mtime as i64, 0).filter(|_| mtime > 0) {
+            if vfs.set_modified(dst, modified).await? {
+                imported.mtimes_kept += 1;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,49 +1,87 @@
 use std::sync::Arc;
 
-use openfs_config::BackendConfig;
-use openfs_core::ChromaStore;
+use openfs_config::{BackendConfig, MountConfig, VfsConfig};
+use openfs_core::{ChromaStore, VfsError};
 use openfs_local::{
-    BulkIndexResult, ChunkerConfig, FileInfo, IndexState, IndexingPipeline, PipelineConfig,
+    content_hash, detect_language, BulkIndexResult, ChunkerConfig, FileInfo, IndexProgress,
+    IndexState, IndexingPipeline, PipelineConfig,
 };
 use openfs_remote::{ChromaHttpBackend, Vfs};
 
-pub async fn run(
-    vfs: &Vfs,
-    path: Option<String>,
-    chroma_endpoint: Option<String>,
-    collection: Option<String>,
-    recursive: bool,
-    chunker: Option<String>,
-    chunk_size: Option<usize>,
-    incremental: bool,
-    force: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+/// Arguments for the index command.
+pub struct IndexArgs {
+    /// Path to index (file or directory).
+    pub path: Option<String>,
+    /// Chroma endpoint URL.
+    pub chroma_endpoint: Option<String>,
+    /// Collection name for storing vectors.
+    pub collection: Option<String>,
+    /// Index recursively for directories.
+    pub recursive: bool,
+    /// Chunking strategy.
+    pub chunker: Option<String>,
+    /// Chunk size in characters.
+    pub chunk_size: Option<usize>,
+    /// Only re-index changed files.
+    pub incremental: bool,
+    /// Ignore incremental state.
+    pub force: bool,
+    /// Summarizer provider for per-file summaries.
+    pub summarizer: Option<String>,
+    /// Embedding requests in flight at once.
+    pub concurrency: Option<usize>,
+    /// Embedding request budget per minute.
+    pub requests_per_minute: Option<usize>,
+    /// Embedding token budget per minute.
+    pub tokens_per_minute: Option<usize>,
+    /// Index every mount with `index.enabled: true` using its own settings.
+    pub all: bool,
+}
+
+pub async fn run(vfs: &Vfs, args: IndexArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.all {
+        return run_all(vfs, args).await;
+    }
+    let IndexArgs {
+        path,
+        chroma_endpoint,
+        collection,
+        recursive,
+        chunker,
+        chunk_size,
+        incremental,
+        force,
+        summarizer,
+        concurrency,
+        requests_per_minute,
+        tokens_per_minute,
+        all: _,
+    } = args;
     let path = path.unwrap_or_else(|| "/".to_string());
 
     // Guard: only index from local (fs/memory) backends
     let config = vfs.effective_config();
     for mount in &config.mounts {
         if path.starts_with(&mount.path) || mount.path.starts_with(&path) {
-            if let Some(backend_name) = &mount.backend {
-                if let Some(backend_config) = config.backends.get(backend_name) {
-                    match backend_config {
-                        BackendConfig::Fs(_) | BackendConfig::Memory(_) => {}
-                        _ => {
-                            eprintln!(
-                                "Warning: skipping mount '{}' — indexing is only supported for local (fs) backends, not '{}'",
-                                mount.path, backend_name
-                            );
-                            eprintln!("Remote backends (for example S3) should use their own indexing systems.");
-                            continue;
-                        }
-                    }
-                }
+            if let Some(backend_name) = remote_backend(&config, mount) {
+                eprintln!(
+                    "Warning: skipping mount '{}' — indexing is only supported for local (fs) backends, not '{}'",
+                    mount.path, backend_name
+                );
+                eprintln!(
+                    "Remote backends (for example S3) should use their own indexing systems."
+                );
+                continue;
             }
         }
     }
 
-    // Set up pipeline config
-    let mut config = PipelineConfig::default();
+    // Start from the owning mount's `index:` policy, if any; flags override it.
+    let (mut config, mount_collection) = match mount_pipeline_config(&config, &path)? {
+        Some(mount_config) => mount_config,
+        None => return Ok(()),
+    };
+    let collection = collection.or(mount_collection);
 
     if let Some(strategy) = chunker {
         config.chunker_strategy = strategy;
@@ -57,23 +95,20 @@ pub async fn run(
         };
     }
 
-    let pipeline = IndexingPipeline::new(config)?;
+    if summarizer.is_some() {
+        config.summarizer_provider = summarizer;
+    }
+    apply_embedding_limits(
+        &mut config,
+        concurrency,
+        requests_per_minute,
+        tokens_per_minute,
+    );
 
-    // Set up Chroma backend if specified
-    let pipeline = if let Some(endpoint) = chroma_endpoint {
-        let collection_name = collection.unwrap_or_else(|| "openfs_index".to_string());
-        println!(
-            "Connecting to Chroma at {} (collection: {})",
-            endpoint, collection_name
-        );
-        let chroma = ChromaHttpBackend::new(&endpoint, &collection_name, None, None, None)
-            .await
-            .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
-        pipeline.with_chroma(Arc::new(chroma) as Arc<dyn ChromaStore>)
-    } else {
-        println!("No Chroma endpoint specified, indexing to memory only");
-        pipeline
-    };
+    let pipeline = IndexingPipeline::new(config)?;
+    let pipeline = connect_chroma(pipeline, chroma_endpoint, collection).await?;
+    let stored_schema = pipeline.stored_schema_version().await?;
+    warn_stale_schema(stored_schema.as_deref(), 0, &pipeline.schema_version());
 
     // Check if path is a file or directory
     let entry = vfs.stat(&path).await?;
@@ -85,11 +120,18 @@ pub async fn run(
                 path, recursive
             );
             let result = index_directory_incremental(vfs, &pipeline, &path, recursive).await?;
+            if result.stale_files == 0 {
+                pipeline.record_schema_version().await?;
+            }
 
             println!("\nIncremental indexing complete:");
             println!("  New files: {}", result.new_files);
             println!("  Modified files: {}", result.modified_files);
             println!("  Deleted files: {}", result.deleted_files);
+            println!(
+                "  Renamed files: {} ({} chunks reused)",
+                result.renamed_files, result.chunks_reused
+            );
             println!("  Unchanged files (skipped): {}", result.unchanged_files);
             println!("  Total chunks: {}", result.total_chunks);
             println!("  Duration: {}ms", result.duration_ms);
@@ -118,6 +160,9 @@ pub async fn run(
 
             // Index using VFS as the backend wrapper
             let result = index_directory_via_vfs(vfs, &pipeline, &path, recursive).await?;
+            if force || stored_schema.is_none() {
+                pipeline.record_schema_version().await?;
+            }
 
             println!("\nIndexing complete:");
             println!("  Files processed: {}", result.files_processed);
@@ -132,6 +177,8 @@ pub async fn run(
                 }
             }
         }
+    } else if !pipeline.accepts(&path, entry.size) {
+        println!("Skipping {}: excluded by the mount's index policy", path);
     } else {
         println!("Indexing file: {}", path);
 
@@ -146,12 +193,383 @@ pub async fn run(
     Ok(())
 }
 
+/// Files, chunks and errors from indexing one mount with `--all`.
+struct MountIndexSummary {
+    mount: String,
+    collection: String,
+    files: usize,
+    chunks: usize,
+    errors: usize,
+    duration_ms: u64,
+}
+
+/// Index every mount with `index.enabled: true`, each with its own chunker,
+/// embedder and collection, and print a summary table.
+async fn run_all(vfs: &Vfs, args: IndexArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = vfs.effective_config();
+    let mounts: Vec<&MountConfig> = config
+        .mounts
+        .iter()
+        .filter(|mount| mount.index.as_ref().is_some_and(|index| index.enabled))
+        .collect();
+    if mounts.is_empty() {
+        println!("No mounts have indexing enabled (index.enabled: true)");
+        return Ok(());
+    }
+
+    if args.force {
+        let state_path = IndexState::default_path(std::path::Path::new("."));
+        if state_path.exists() {
+            std::fs::remove_file(&state_path)?;
+            println!("Removed existing index state");
+        }
+    }
+
+    let mut summaries = Vec::new();
+    let mut failed_mounts = 0;
+    for mount in mounts {
+        if let Some(backend_name) = remote_backend(&config, mount) {
+            eprintln!(
+                "Warning: skipping mount '{}' — indexing is only supported for local (fs) backends, not '{}'",
+                mount.path, backend_name
+            );
+            continue;
+        }
+        println!("\n==> Indexing mount {}", mount.path);
+        match index_mount(vfs, mount, &args).await {
+            Ok(summary) => summaries.push(summary),
+            Err(e) => {
+                eprintln!("Error: failed to index mount '{}': {}", mount.path, e);
+                failed_mounts += 1;
+            }
+        }
+    }
+
+    println!("\nIndexed {} mounts:", summaries.len());
+    println!(
+        "  {:<24} {:<20} {:>8} {:>8} {:>7} {:>9}",
+        "mount", "collection", "files", "chunks", "errors", "ms"
+    );
+    for s in &summaries {
+        println!(
+            "  {:<24} {:<20} {:>8} {:>8} {:>7} {:>9}",
+            s.mount, s.collection, s.files, s.chunks, s.errors, s.duration_ms
+        );
+    }
+    println!(
+        "  {:<24} {:<20} {:>8} {:>8} {:>7} {:>9}",
+        "total",
+        "",
+        summaries.iter().map(|s| s.files).sum::<usize>(),
+        summaries.iter().map(|s| s.chunks).sum::<usize>(),
+        summaries.iter().map(|s| s.errors).sum::<usize>(),
+        summaries.iter().map(|s| s.duration_ms).sum::<u64>(),
+    );
+
+    if failed_mounts > 0 {
+        return Err(format!("{} mounts failed to index", failed_mounts).into());
+    }
+    Ok(())
+}
+
+/// Index one mount with its `index:` settings for `openfs index --all`.
+async fn index_mount(
+    vfs: &Vfs,
+    mount: &MountConfig,
+    args: &IndexArgs,
+) -> Result<MountIndexSummary, Box<dyn std::error::Error>> {
+    let (mut config, collection) =
+        match mount_pipeline_config(&vfs.effective_config(), &mount.path)? {
+            Some(mount_config) => mount_config,
+            None => return Err("indexing is disabled".into()),
+        };
+    if args.summarizer.is_some() {
+        config.summarizer_provider = args.summarizer.clone();
+    }
+    apply_embedding_limits(
+        &mut config,
+        args.concurrency,
+        args.requests_per_minute,
+        args.tokens_per_minute,
+    );
+    let collection_name = collection
+        .clone()
+        .unwrap_or_else(|| "openfs_index".to_string());
+
+    let pipeline = IndexingPipeline::new(config)?;
+    let pipeline = connect_chroma(pipeline, args.chroma_endpoint.clone(), collection).await?;
+    let stored_schema = pipeline.stored_schema_version().await?;
+    warn_stale_schema(stored_schema.as_deref(), 0, &pipeline.schema_version());
+
+    let (files, chunks, errors, duration_ms) = if args.incremental && !args.force {
+        let result =
+            index_directory_incremental(vfs, &pipeline, &mount.path, args.recursive).await?;
+        if result.stale_files == 0 {
+            pipeline.record_schema_version().await?;
+        }
+        (
+            result.new_files + result.modified_files + result.renamed_files,
+            result.total_chunks,
+            result.errors,
+            result.duration_ms,
+        )
+    } else {
+        let result = index_directory_via_vfs(vfs, &pipeline, &mount.path, args.recursive).await?;
+        if args.force || stored_schema.is_none() {
+            pipeline.record_schema_version().await?;
+        }
+        (
+            result.files_processed,
+            result.total_chunks,
+            result.errors,
+            result.duration_ms,
+        )
+    };
+    for (path, error) in &errors {
+        eprintln!("  {}: {}", path, error);
+    }
+
+    Ok(MountIndexSummary {
+        mount: mount.path.clone(),
+        collection: collection_name,
+        files,
+        chunks,
+        errors: errors.len(),
+        duration_ms,
+    })
+}
+
+/// Arguments for `openfs index migrate`.
+pub struct MigrateArgs {
+    /// Only migrate files at or under this path.
+    pub path: Option<String>,
+    /// Chroma endpoint URL.
+    pub chroma_endpoint: Option<String>,
+    /// Collection name for storing vectors.
+    pub collection: Option<String>,
+    /// List the files that would be re-indexed without touching them.
+    pub dry_run: bool,
+}
+
+/// Re-chunk and re-embed the files indexed under an older index schema.
+///
+/// Files are found through the incremental index state, so only files whose
+/// chunker, embedder or summarizer settings (or chunk metadata layout)
+/// changed are rewritten.
+pub async fn run_migrate(vfs: &Vfs, args: MigrateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.path.unwrap_or_else(|| "/".to_string());
+    let state_path = IndexState::default_path(std::path::Path::new("."));
+    if !state_path.exists() {
+        return Err(format!(
+            "No index state at {}; run `openfs index --incremental` first",
+            state_path.display()
+        )
+        .into());
+    }
+    let mut state =
+        IndexState::load(&state_path).map_err(|e| format!("Failed to load index state: {}", e))?;
+
+    let (config, mount_collection) = match mount_pipeline_config(&vfs.effective_config(), &path)? {
+        Some(mount_config) => mount_config,
+        None => return Ok(()),
+    };
+    let pipeline = IndexingPipeline::new(config)?;
+    let schema = pipeline.schema_version();
+    let stale: Vec<String> = state
+        .stale_files(&schema)
+        .into_iter()
+        .filter(|file| under_path(file, &path))
+        .collect();
+
+    if args.dry_run {
+        println!("{} files to migrate to schema {}:", stale.len(), schema);
+        for file in &stale {
+            println!("  {}", file);
+        }
+        return Ok(());
+    }
+
+    let collection = args.collection.or(mount_collection);
+    let pipeline = connect_chroma(pipeline, args.chroma_endpoint, collection).await?;
+    println!("Migrating {} files to schema {}", stale.len(), schema);
+
+    state.set_schema_version(&schema);
+    let mut errors = Vec::new();
+    for file in &stale {
+        if let Err(e) = pipeline.delete_file(file).await {
+            errors.push((file.clone(), e.to_string()));
+            continue;
+        }
+        let entry = match vfs.stat(file).await {
+            Ok(entry) => entry,
+            Err(VfsError::NotFound(_)) => {
+                state.remove_file(file);
+                continue;
+            }
+            Err(e) => {
+                errors.push((file.clone(), e.to_string()));
+                continue;
+            }
+        };
+        let indexed = match vfs.read(file).await {
+            Ok(content) => pipeline.index_file(file, &content).await,
+            Err(e) => Err(e),
+        };
+        match indexed {
+            Ok(result) => state.record_indexed(
+                file,
+                entry.size.unwrap_or(0),
+                entry.modified,
+                result.chunks_created,
+            ),
+            Err(e) => errors.push((file.clone(), e.to_string())),
+        }
+    }
+
+    state
+        .save(&state_path)
+        .map_err(|e| format!("Failed to save index state: {}", e))?;
+    let remaining = state.stale_files(&schema).len();
+    if remaining == 0 {
+        pipeline.record_schema_version().await?;
+    }
+
+    println!("\nMigration complete:");
+    println!("  Files re-indexed: {}", stale.len() - errors.len());
+    println!("  Files still on an older schema: {}", remaining);
+    if !errors.is_empty() {
+        println!("\nErrors:");
+        for (path, error) in errors {
+            println!("  {}: {}", path, error);
+        }
+    }
+    Ok(())
+}
+
+/// Warn when indexed chunks were written under another schema than `current`.
+///
+/// `stale_files` counts tracked files indexed under an older schema.
+pub(crate) fn warn_stale_schema(stored: Option<&str>, stale_files: usize, current: &str) {
+    let mut stale = false;
+    if let Some(stored) = stored.filter(|stored| *stored != current) {
+        eprintln!(
+            "Warning: the collection was indexed with schema {}, but the current settings use {}",
+            stored, current
+        );
+        stale = true;
+    }
+    if stale_files > 0 {
+        eprintln!(
+            "Warning: {} files were indexed under an older schema",
+            stale_files
+        );
+        stale = true;
+    }
+    if stale {
+        eprintln!("Run `openfs index migrate` to re-index them.");
+    }
+}
+
+/// The backend name of a mount that indexing cannot read locally, such as
+/// S3; `None` for fs and memory backends.
+fn remote_backend<'a>(config: &VfsConfig, mount: &'a MountConfig) -> Option<&'a str> {
+    let backend_name = mount.backend.as_deref()?;
+    match config.backends.get(backend_name)? {
+        BackendConfig::Fs(_) | BackendConfig::Memory(_) => None,
+        _ => Some(backend_name),
+    }
+}
+
+/// Pipeline settings and Chroma collection for a mount.
+type MountPipeline = (PipelineConfig, Option<String>);
+
+/// Pipeline settings and collection for `path` from the owning mount's
+/// `index:` policy, or defaults. `None` if the mount disables indexing.
+fn mount_pipeline_config(
+    config: &VfsConfig,
+    path: &str,
+) -> Result<Option<MountPipeline>, Box<dyn std::error::Error>> {
+    let mount = config.mount_for_path(path);
+    match mount.and_then(|m| Some((m, m.index.as_ref()?))) {
+        Some((mount, index)) if !index.enabled => {
+            println!(
+                "Indexing is disabled for mount '{}' (index.enabled: false)",
+                mount.path
+            );
+            Ok(None)
+        }
+        Some((mount, index)) => Ok(Some((
+            PipelineConfig::from_index_config(&mount.path, index)?,
+            index.collection.clone(),
+        ))),
+        None => Ok(Some((PipelineConfig::default(), None))),
+    }
+}
+
+/// Override the mount's embedding concurrency and rate limits with flags.
+fn apply_embedding_limits(
+    config: &mut PipelineConfig,
+    concurrency: Option<usize>,
+    requests_per_minute: Option<usize>,
+    tokens_per_minute: Option<usize>,
+) {
+    if let Some(concurrency) = concurrency {
+        config.concurrency = concurrency;
+    }
+    if requests_per_minute.is_some() {
+        config.rate_limit.requests_per_minute = requests_per_minute;
+    }
+    if tokens_per_minute.is_some() {
+        config.rate_limit.tokens_per_minute = tokens_per_minute;
+    }
+}
+
+/// Connect the pipeline to Chroma if an endpoint was given.
+async fn connect_chroma(
+    pipeline: IndexingPipeline,
+    chroma_endpoint: Option<String>,
+    collection: Option<String>,
+) -> Result<IndexingPipeline, Box<dyn std::error::Error>> {
+    let Some(endpoint) = chroma_endpoint else {
+        println!("No Chroma endpoint specified, indexing to memory only");
+        return Ok(pipeline);
+    };
+    let collection_name = collection.unwrap_or_else(|| "openfs_index".to_string());
+    println!(
+        "Connecting to Chroma at {} (collection: {})",
+        endpoint, collection_name
+    );
+    let chroma = ChromaHttpBackend::new(&endpoint, &collection_name, None, None, None)
+        .await
+        .map_err(|e| format!("Failed to connect to Chroma: {}", e))?
+        .follow_active()
+        .await
+        .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
+    Ok(pipeline.with_chroma(Arc::new(chroma) as Arc<dyn ChromaStore>))
+}
+
+/// Whether `file` is `dir` or inside it.
+fn under_path(file: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    dir.is_empty()
+        || file == dir
+        || file
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
 /// Result of an incremental indexing run via the CLI.
 struct IncrementalRunResult {
     new_files: usize,
     modified_files: usize,
     deleted_files: usize,
     unchanged_files: usize,
+    /// Files moved with unchanged content, whose chunks were moved.
+    renamed_files: usize,
+    /// Chunks moved by renames instead of re-embedded.
+    chunks_reused: usize,
+    /// Tracked files still indexed under an older schema.
+    stale_files: usize,
     total_chunks: usize,
     duration_ms: u64,
     errors: Vec<(String, String)>,
@@ -180,15 +598,21 @@ async fn index_directory_incremental(
     } else {
         IndexState::new()
     };
+    let schema = pipeline.schema_version();
+    state.set_schema_version(&schema);
 
     // Collect current file info via VFS
     let mut current_files = Vec::new();
-    collect_file_info_via_vfs(vfs, dir_path, recursive, &mut current_files).await?;
+    collect_file_info_via_vfs(vfs, pipeline, dir_path, recursive, &mut current_files).await?;
 
     println!("Found {} files, computing delta...", current_files.len());
 
-    // Compute delta
-    let delta = state.compute_delta(&current_files);
+    // Compute delta. The state may track other directories and mounts too;
+    // only files under `dir_path` can have been deleted from it.
+    let mut delta = state.compute_delta(&current_files);
+    delta
+        .deleted_files
+        .retain(|file| under_path(file, dir_path));
 
     println!(
         "Delta: {} new, {} modified, {} deleted, {} unchanged",
@@ -211,28 +635,77 @@ async fn index_directory_incremental(
         .collect();
 
     let total_to_index = files_to_index.len();
+    let mut progress = IndexProgress {
+        files_total: total_to_index,
+        ..Default::default()
+    };
 
-    for (i, path) in files_to_index.iter().enumerate() {
-        if (i + 1) % 10 == 0 || i + 1 == total_to_index {
-            print!("\rProcessing {}/{}", i + 1, total_to_index);
-        }
+    // New files with a deleted file's content are renames; move their chunks.
+    let mut rename_candidates = state.rename_candidates(&delta.deleted_files);
+    let mut renamed: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut chunks_reused = 0;
 
+    for (idx, path) in files_to_index.iter().enumerate() {
         match vfs.read(path).await {
-            Ok(content) => match pipeline.index_file(path, &content).await {
-                Ok(result) => {
-                    total_chunks += result.chunks_created;
-                    if let Some(info) = file_info_map.get(path.as_str()) {
-                        state.record_indexed(path, info.size, info.mtime, result.chunks_created);
+            Ok(content) => {
+                progress.bytes_processed += content.len() as u64;
+                let hash = content_hash(&content);
+                let info = file_info_map.get(path.as_str());
+                let text = String::from_utf8_lossy(&content);
+                let from = (idx < delta.new_files.len())
+                    .then(|| rename_candidates.remove(&hash))
+                    .flatten()
+                    .filter(|from| detect_language(from, &text) == detect_language(path, &text));
+                let moved = match from {
+                    Some(from) => match pipeline.rename_file(&from, path).await {
+                        Ok(moved) => {
+                            chunks_reused += moved;
+                            progress.files_processed += 1;
+                            if let Some(info) = info {
+                                state.record_renamed(&from, path, info.size, info.mtime);
+                            }
+                            renamed.insert(from)
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "Warning: Failed to move chunks from {} to {}, re-indexing: {}",
+                                from, path, e
+                            );
+                            false
+                        }
+                    },
+                    None => false,
+                };
+                if !moved {
+                    match pipeline.index_file(path, &content).await {
+                        Ok(result) => {
+                            total_chunks += result.chunks_created;
+                            progress.files_processed += 1;
+                            if let Some(info) = info {
+                                state.record_indexed_with_hash(
+                                    path,
+                                    info.size,
+                                    info.mtime,
+                                    result.chunks_created,
+                                    hash,
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            progress.files_failed += 1;
+                            errors.push((path.clone(), e.to_string()));
+                        }
                     }
                 }
-                Err(e) => {
-                    errors.push((path.clone(), e.to_string()));
-                }
-            },
+            }
             Err(e) => {
+                progress.files_failed += 1;
                 errors.push((path.clone(), e.to_string()));
             }
         }
+        progress.chunks_embedded = total_chunks;
+        progress.elapsed = start.elapsed();
+        print_progress(&progress);
     }
 
     if total_to_index > 0 {
@@ -240,7 +713,7 @@ async fn index_directory_incremental(
     }
 
     // Clean up deleted files from state
-    for path in &delta.deleted_files {
+    for path in delta.deleted_files.iter().filter(|p| !renamed.contains(*p)) {
         if let Err(e) = pipeline.delete_file(path).await {
             eprintln!("Warning: Failed to clean up index for {}: {}", path, e);
         }
@@ -252,14 +725,23 @@ async fn index_directory_incremental(
         .save(&state_path)
         .map_err(|e| format!("Failed to save index state: {}", e))?;
     println!("Index state saved to {}", state_path.display());
+    let stale_files = state
+        .stale_files(&schema)
+        .iter()
+        .filter(|file| under_path(file, dir_path))
+        .count();
+    warn_stale_schema(None, stale_files, &schema);
 
     let duration_ms = start.elapsed().as_millis() as u64;
 
     Ok(IncrementalRunResult {
-        new_files: delta.new_files.len(),
+        new_files: delta.new_files.len() - renamed.len(),
         modified_files: delta.modified_files.len(),
-        deleted_files: delta.deleted_files.len(),
+        deleted_files: delta.deleted_files.len() - renamed.len(),
         unchanged_files: delta.unchanged_files.len(),
+        renamed_files: renamed.len(),
+        chunks_reused,
+        stale_files,
         total_chunks,
         duration_ms,
         errors,
@@ -270,6 +752,7 @@ async fn index_directory_incremental(
 #[async_recursion::async_recursion]
 async fn collect_file_info_via_vfs(
     vfs: &Vfs,
+    pipeline: &IndexingPipeline,
     dir_path: &str,
     recursive: bool,
     files: &mut Vec<FileInfo>,
@@ -277,13 +760,14 @@ async fn collect_file_info_via_vfs(
     let entries = vfs.list(dir_path).await?;
 
     for entry in entries {
+        let path = vfs_entry_path(dir_path, &entry.name);
         if entry.is_dir {
             if recursive {
-                collect_file_info_via_vfs(vfs, &entry.path, recursive, files).await?;
+                collect_file_info_via_vfs(vfs, pipeline, &path, recursive, files).await?;
             }
-        } else if is_indexable(&entry.path) {
+        } else if is_indexable(&path) && pipeline.accepts(&path, entry.size) {
             files.push(FileInfo {
-                path: entry.path,
+                path,
                 size: entry.size.unwrap_or(0),
                 mtime: entry.modified,
                 content_hash: None,
@@ -311,32 +795,40 @@ async fn index_directory_via_vfs(
 
     // Collect files to index
     let mut paths_to_index = Vec::new();
-    collect_files_via_vfs(vfs, dir_path, recursive, &mut paths_to_index).await?;
+    collect_files_via_vfs(vfs, pipeline, dir_path, recursive, &mut paths_to_index).await?;
 
     println!("Found {} files to index", paths_to_index.len());
+    let mut progress = IndexProgress {
+        files_total: paths_to_index.len(),
+        ..Default::default()
+    };
 
-    for (i, path) in paths_to_index.iter().enumerate() {
-        // Simple progress indicator
-        if (i + 1) % 10 == 0 || i + 1 == paths_to_index.len() {
-            print!("\rProcessing {}/{}", i + 1, paths_to_index.len());
-        }
-
+    for path in &paths_to_index {
         match vfs.read(path).await {
-            Ok(content) => match pipeline.index_file(path, &content).await {
-                Ok(result) => {
-                    files_processed += 1;
-                    total_chunks += result.chunks_created;
-                }
-                Err(e) => {
-                    errors.push((path.clone(), e.to_string()));
-                    files_skipped += 1;
+            Ok(content) => {
+                progress.bytes_processed += content.len() as u64;
+                match pipeline.index_file(path, &content).await {
+                    Ok(result) => {
+                        files_processed += 1;
+                        total_chunks += result.chunks_created;
+                    }
+                    Err(e) => {
+                        errors.push((path.clone(), e.to_string()));
+                        files_skipped += 1;
+                        progress.files_failed += 1;
+                    }
                 }
-            },
+            }
             Err(e) => {
                 errors.push((path.clone(), e.to_string()));
                 files_skipped += 1;
+                progress.files_failed += 1;
             }
         }
+        progress.files_processed = files_processed;
+        progress.chunks_embedded = total_chunks;
+        progress.elapsed = start.elapsed();
+        print_progress(&progress);
     }
     println!(); // newline after progress
 
@@ -355,6 +847,7 @@ async fn index_directory_via_vfs(
 #[async_recursion::async_recursion]
 async fn collect_files_via_vfs(
     vfs: &Vfs,
+    pipeline: &IndexingPipeline,
     dir_path: &str,
     recursive: bool,
     paths: &mut Vec<String>,
@@ -362,14 +855,15 @@ async fn collect_files_via_vfs(
     let entries = vfs.list(dir_path).await?;
 
     for entry in entries {
+        let path = vfs_entry_path(dir_path, &entry.name);
         if entry.is_dir {
             if recursive {
-                collect_files_via_vfs(vfs, &entry.path, recursive, paths).await?;
+                collect_files_via_vfs(vfs, pipeline, &path, recursive, paths).await?;
             }
         } else {
             // Only index text files (simple extension check)
-            if is_indexable(&entry.path) {
-                paths.push(entry.path);
+            if is_indexable(&path) && pipeline.accepts(&path, entry.size) {
+                paths.push(path);
             }
         }
     }
@@ -377,6 +871,61 @@ async fn collect_files_via_vfs(
     Ok(())
 }
 
+/// Full VFS path of a listed entry (backends report mount-relative paths).
+fn vfs_entry_path(dir_path: &str, name: &str) -> String {
+    format!("{}/{}", dir_path.trim_end_matches('/'), name)
+}
+
+/// Redraw a single-line progress bar with throughput and ETA.
+fn print_progress(progress: &IndexProgress) {
+    use std::io::Write;
+
+    const WIDTH: usize = 30;
+    let filled = (progress.fraction() * WIDTH as f64).round() as usize;
+    let eta = progress
+        .eta()
+        .map(format_eta)
+        .unwrap_or_else(|| "--:--".to_string());
+    let failed = if progress.files_failed > 0 {
+        format!(", {} failed", progress.files_failed)
+    } else {
+        String::new()
+    };
+    print!(
+        "\r[{}{}] {}/{} files{}, {} chunks, {}, ETA {}  ",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        progress.files_done(),
+        progress.files_total,
+        failed,
+        progress.chunks_embedded,
+        format_size(progress.bytes_processed),
+        eta
+    );
+    let _ = std::io::stdout().flush();
+}
+
+fn format_eta(eta: std::time::Duration) -> String {
+    let secs = eta.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
 /// Check if a file should be indexed (simple extension-based check).
 fn is_indexable(path: &str) -> bool {
     let extensions = [
@@ -0,0 +1,114 @@
+use openfs_local::{WorkQueue, WorkQueueConfig};
+use std::path::PathBuf;
+
+/// Run the `index dlq list` command.
+pub async fn run_list(queue: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let queue_paths = resolve_queue_paths(queue)?;
+    if queue_paths.is_empty() {
+        println!("No indexing work queues found.");
+        return Ok(());
+    }
+
+    let mut total = 0usize;
+    for queue_path in queue_paths {
+        let queue = WorkQueue::open(&queue_path, WorkQueueConfig::default())?;
+        let items = queue.dead_letter_items()?;
+        total += items.len();
+
+        println!(
+            "{}: {} dead letters, {} pending",
+            queue_path.display(),
+            items.len(),
+            queue.pending_count()?
+        );
+        for item in &items {
+            let dead_at = chrono::DateTime::from_timestamp(item.dead_at as i64, 0)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| item.dead_at.to_string());
+            println!(
+                "  [{}] {} (attempts: {}, dead since: {}, error: {})",
+                item.id,
+                item.path,
+                item.attempts,
+                dead_at,
+                item.last_error.as_deref().unwrap_or("none")
+            );
+        }
+    }
+
+    println!();
+    println!("Total dead letters: {}", total);
+    Ok(())
+}
+
+/// Run the `index dlq retry` command.
+///
+/// Requeues dead letters at user priority; a running `openfs indexd` or
+/// `openfs watch --auto-index` picks them up on its next pass.
+pub async fn run_retry(
+    path: Option<String>,
+    all: bool,
+    queue: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if path.is_none() && !all {
+        return Err("Specify a path to retry, or --all to retry every dead letter".into());
+    }
+
+    let queue_paths = resolve_queue_paths(queue)?;
+    let mut total = 0usize;
+    for queue_path in queue_paths {
+        let queue = WorkQueue::open(&queue_path, WorkQueueConfig::default())?;
+        let requeued = match &path {
+            Some(path) => queue.retry_dead_letter(path)?,
+            None => queue.retry_all_dead_letters()?,
+        };
+        if requeued > 0 {
+            println!("{}: {} requeued", queue_path.display(), requeued);
+        }
+        total += requeued;
+    }
+
+    match (&path, total) {
+        (Some(path), 0) => Err(format!("No dead letter found for '{}'", path).into()),
+        _ => {
+            println!("Requeued {} dead letters", total);
+            Ok(())
+        }
+    }
+}
+
+/// Find the work queues used by `openfs watch --auto-index` and `openfs indexd`.
+fn resolve_queue_paths(queue: Option<PathBuf>) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if let Some(queue) = queue {
+        if !queue.is_file() {
+            return Err(format!("Work queue not found: {}", queue.display()).into());
+        }
+        return Ok(vec![queue]);
+    }
+
+    let mut paths = Vec::new();
+
+    let watch_queue = PathBuf::from(".openfs_watch_queue.db");
+    if watch_queue.is_file() {
+        paths.push(watch_queue);
+    }
+
+    let indexd_dir = PathBuf::from(".openfs").join("indexd");
+    if indexd_dir.is_dir() {
+        let mut daemon_queues = Vec::new();
+        for entry in std::fs::read_dir(&indexd_dir)? {
+            let path = entry?.path();
+            let is_queue = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.ends_with(".queue.db"));
+            if is_queue && path.is_file() {
+                daemon_queues.push(path);
+            }
+        }
+        daemon_queues.sort();
+        paths.extend(daemon_queues);
+    }
+
+    Ok(paths)
+}
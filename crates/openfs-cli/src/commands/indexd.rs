@@ -0,0 +1,663 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use openfs_config::{BackendConfig, MountConfig, MountMode, VfsConfig};
+use openfs_core::{Backend, BackendError, ChromaStore, Entry, VfsError};
+use openfs_local::{
+    ChangeKind, FileChange, IncrementalIndexer, IndexingPipeline, PersistentEvent,
+    PersistentIndexWorker, PipelineConfig, ReembedMigration, WatchEngine, WorkQueue,
+    WorkQueueConfig,
+};
+use openfs_remote::{ChromaHttpBackend, Vfs};
+use tokio::sync::{broadcast, mpsc};
+
+/// Arguments for the indexd command.
+pub struct IndexdArgs {
+    /// Config file re-read on SIGHUP.
+    pub config_path: PathBuf,
+    /// Profile applied when the config is re-read.
+    pub profile: Option<String>,
+    /// Only index mounts at or under this path.
+    pub path: Option<String>,
+    /// Chroma endpoint URL.
+    pub chroma_endpoint: Option<String>,
+    /// Directory holding work queues, index state and the status file.
+    pub state_dir: Option<PathBuf>,
+    /// Seconds between status file updates.
+    pub status_interval_secs: u64,
+}
+
+/// Exposes a [`Vfs`] through the [`Backend`] trait so the persistent worker
+/// reads files through the mount table, with paths in VFS form.
+struct VfsBackend(Arc<Vfs>);
+
+fn to_backend_error(e: VfsError) -> BackendError {
+    match e {
+        VfsError::NotFound(path) => BackendError::NotFound(path),
+        VfsError::Io(e) => BackendError::Io(e),
+        other => BackendError::Other(other.to_string()),
+    }
+}
+
+#[async_trait]
+impl Backend for VfsBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        self.0.read(path).await.map_err(to_backend_error)
+    }
+    async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.0.write(path, content).await.map_err(to_backend_error)
+    }
+    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.0.append(path, content).await.map_err(to_backend_error)
+    }
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        self.0.delete(path).await.map_err(to_backend_error)
+    }
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        // Backends report mount-relative paths; the indexer needs VFS paths.
+        let entries = self.0.list(path).await.map_err(to_backend_error)?;
+        Ok(entries
+            .into_iter()
+            .map(|mut entry| {
+                entry.path = format!("{}/{}", path.trim_end_matches('/'), entry.name);
+                entry
+            })
+            .collect())
+    }
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        self.0.exists(path).await.map_err(to_backend_error)
+    }
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        self.0.stat(path).await.map_err(to_backend_error)
+    }
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
+        self.0.rename(from, to).await.map_err(to_backend_error)
+    }
+}
+
+/// A running worker for one indexed mount.
+struct MountWorker {
+    mount: MountConfig,
+    /// Directory watched for changes; `None` for a remote mount, whose
+    /// changes come from the VFS's scheduled pulls.
+    fs_root: Option<PathBuf>,
+    queue_path: PathBuf,
+    worker: PersistentIndexWorker,
+    handle: tokio::task::JoinHandle<()>,
+    // Kept alive for the lifetime of the worker; dropping it stops the watch.
+    _engine: Option<WatchEngine>,
+}
+
+impl MountWorker {
+    /// Drain the work queue and wait for the worker to finish.
+    async fn stop(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Err(e) = self.worker.shutdown().await {
+            eprintln!(
+                "Warning: failed to stop worker for {}: {}",
+                self.mount.path, e
+            );
+        }
+        (&mut self.handle).await?;
+        Ok(())
+    }
+}
+
+/// The daemon's workers, keyed by an id that tags their watch events.
+struct Workers {
+    backend: Arc<VfsBackend>,
+    state_dir: PathBuf,
+    chroma_endpoint: Option<String>,
+    change_tx: mpsc::Sender<(usize, FileChange)>,
+    workers: BTreeMap<usize, MountWorker>,
+    next_id: usize,
+}
+
+impl Workers {
+    /// Start a worker for `mount` and queue a rescan to catch up on changes.
+    async fn start(
+        &mut self,
+        mount: &MountConfig,
+        fs_root: Option<PathBuf>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (worker, rx) = spawn_mount_worker(
+            Arc::clone(&self.backend),
+            mount,
+            fs_root,
+            &self.state_dir,
+            self.chroma_endpoint.as_deref(),
+        )
+        .await?;
+
+        worker
+            .worker
+            .send(PersistentEvent::Rescan {
+                path: mount.path.clone(),
+            })
+            .await?;
+
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(mut rx) = rx {
+            let tx = self.change_tx.clone();
+            tokio::spawn(async move {
+                while let Some(change) = rx.recv().await {
+                    if tx.send((id, change)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        match &worker.fs_root {
+            Some(fs_root) => println!(
+                "Indexing {} (fs root: {}, queue: {})",
+                worker.mount.path,
+                fs_root.display(),
+                worker.queue_path.display()
+            ),
+            None => println!(
+                "Indexing {} (remote changes, queue: {})",
+                worker.mount.path,
+                worker.queue_path.display()
+            ),
+        }
+        self.workers.insert(id, worker);
+        Ok(())
+    }
+
+    /// Stop workers whose mount is gone or changed, and start workers for
+    /// mounts that have none.
+    async fn reconcile(
+        &mut self,
+        vfs: &Vfs,
+        scope: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let wanted = indexable_mounts(vfs, scope);
+        let stale: Vec<usize> = self
+            .workers
+            .iter()
+            .filter(|(_, worker)| {
+                !wanted.iter().any(|(mount, fs_root)| {
+                    *fs_root == worker.fs_root && same_mount(mount, &worker.mount)
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in stale {
+            if let Some(worker) = self.workers.remove(&id) {
+                println!("Stopping {}", worker.mount.path);
+                worker.stop().await?;
+            }
+        }
+
+        for (mount, fs_root) in wanted {
+            let running = self
+                .workers
+                .values()
+                .any(|worker| worker.mount.path == mount.path);
+            if !running {
+                self.start(&mount, fs_root).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn same_mount(a: &MountConfig, b: &MountConfig) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Mounts under `scope` that indexd can index, with their fs roots. Remote
+/// mounts that pull changes on a schedule have none.
+fn indexable_mounts(vfs: &Vfs, scope: &str) -> Vec<(MountConfig, Option<PathBuf>)> {
+    let config = vfs.effective_config();
+    let mut mounts = Vec::new();
+    for mount in &config.mounts {
+        if !path_within(&mount.path, scope) && !path_within(scope, &mount.path) {
+            continue;
+        }
+        if mount.index.as_ref().is_some_and(|index| !index.enabled) {
+            println!(
+                "Skipping {}: indexing disabled (index.enabled: false)",
+                mount.path
+            );
+            continue;
+        }
+        let is_local = mount
+            .backend
+            .as_ref()
+            .and_then(|name| config.backends.get(name))
+            .is_some_and(|backend| matches!(backend, BackendConfig::Fs(_)));
+        let pulls_changes = matches!(mount.mode, Some(MountMode::PullMirror | MountMode::TwoWay));
+        match vfs.resolve_fs_path(&mount.path) {
+            Some(root) if is_local => {
+                mounts.push((mount.clone(), Some(root.canonicalize().unwrap_or(root))))
+            }
+            _ if pulls_changes => mounts.push((mount.clone(), None)),
+            _ => println!(
+                "Skipping {}: not a local (fs), pull_mirror or two_way mount",
+                mount.path
+            ),
+        }
+    }
+    mounts
+}
+
+/// SIGHUP, on platforms that have it.
+struct Hangup {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl Hangup {
+    fn new() -> std::io::Result<Self> {
+        Ok(Hangup {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+pub async fn run(config: VfsConfig, args: IndexdArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let vfs = Arc::new(Vfs::from_config(config).await?);
+    let scope = normalize_path(args.path.as_deref().unwrap_or("/"));
+    let state_dir = args
+        .state_dir
+        .unwrap_or_else(|| PathBuf::from(".openfs").join("indexd"));
+    std::fs::create_dir_all(&state_dir)?;
+
+    let (change_tx, mut change_rx) = mpsc::channel::<(usize, FileChange)>(1024);
+    let mut workers = Workers {
+        backend: Arc::new(VfsBackend(Arc::clone(&vfs))),
+        state_dir: state_dir.clone(),
+        chroma_endpoint: args.chroma_endpoint,
+        change_tx,
+        workers: BTreeMap::new(),
+        next_id: 0,
+    };
+    for (mount, fs_root) in indexable_mounts(&vfs, &scope) {
+        workers.start(&mount, fs_root).await?;
+    }
+
+    if workers.workers.is_empty() {
+        return Err(format!("No indexable mounts found under '{}'", scope).into());
+    }
+    let mut remote_changes = vfs.subscribe_remote_changes();
+
+    let status_path = state_dir.join("status.json");
+    let started_at = chrono::Utc::now();
+    let mut status_interval =
+        tokio::time::interval(Duration::from_secs(args.status_interval_secs.max(1)));
+    let mut hangup = Hangup::new()?;
+
+    println!(
+        "openfs indexd running (pid {}). Press Ctrl+C to stop, send SIGHUP to reload {}.",
+        std::process::id(),
+        args.config_path.display()
+    );
+
+    loop {
+        tokio::select! {
+            change = change_rx.recv() => {
+                let Some((id, change)) = change else { break };
+                // Events from a worker stopped by a reload are dropped.
+                let Some(worker) = workers.workers.get(&id) else {
+                    continue;
+                };
+                let Some(fs_root) = &worker.fs_root else {
+                    continue;
+                };
+                let Some(vfs_path) = to_vfs_path(&worker.mount.path, fs_root, &change.path) else {
+                    continue;
+                };
+                let deleted = match change.kind {
+                    ChangeKind::Deleted => true,
+                    ChangeKind::Renamed => !change.path.exists(),
+                    ChangeKind::Created | ChangeKind::Modified => false,
+                };
+                if change.path.is_dir() {
+                    continue;
+                }
+                let event = if deleted {
+                    PersistentEvent::FileDeleted { path: vfs_path }
+                } else {
+                    PersistentEvent::FileChanged { path: vfs_path }
+                };
+                if let Err(e) = worker.worker.send(event).await {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+            change = remote_changes.recv() => {
+                let change = match change {
+                    Ok(change) => change,
+                    // Missed events: catch up by rescanning the remote mounts.
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        eprintln!("Warning: missed {} remote change(s), rescanning", missed);
+                        for worker in workers.workers.values().filter(|w| w.fs_root.is_none()) {
+                            let rescan = PersistentEvent::Rescan {
+                                path: worker.mount.path.clone(),
+                            };
+                            if let Err(e) = worker.worker.send(rescan).await {
+                                eprintln!("Warning: {}", e);
+                            }
+                        }
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => continue,
+                };
+                let Some(worker) = workers.workers.values().find(|worker| {
+                    worker.fs_root.is_none() && path_within(&change.path, &worker.mount.path)
+                }) else {
+                    continue;
+                };
+                let event = if change.deleted {
+                    PersistentEvent::FileDeleted { path: change.path }
+                } else {
+                    PersistentEvent::FileChanged { path: change.path }
+                };
+                if let Err(e) = worker.worker.send(event).await {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+            _ = status_interval.tick() => {
+                write_status(&status_path, workers.workers.values(), started_at, "running");
+            }
+            _ = hangup.recv() => {
+                // A bad config keeps the daemon running on the old one.
+                let config = match VfsConfig::from_file_with_profile(
+                    &args.config_path,
+                    args.profile.as_deref(),
+                ) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        eprintln!("Reload failed: {}", e);
+                        continue;
+                    }
+                };
+                match vfs.reload(config).await {
+                    Ok(summary) => {
+                        println!(
+                            "Reloaded {} (added: {:?}, removed: {:?}, updated: {:?})",
+                            args.config_path.display(),
+                            summary.added,
+                            summary.removed,
+                            summary.updated
+                        );
+                        workers.reconcile(&vfs, &scope).await?;
+                    }
+                    Err(e) => eprintln!("Reload failed: {}", e),
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                println!("\nShutting down, draining work queues...");
+                break;
+            }
+        }
+    }
+
+    for worker in workers.workers.values() {
+        if let Err(e) = worker.worker.shutdown().await {
+            eprintln!(
+                "Warning: failed to stop worker for {}: {}",
+                worker.mount.path, e
+            );
+        }
+    }
+    for worker in workers.workers.values_mut() {
+        (&mut worker.handle).await?;
+    }
+    write_status(
+        &status_path,
+        workers.workers.values(),
+        started_at,
+        "stopped",
+    );
+    println!("openfs indexd stopped");
+
+    Ok(())
+}
+
+async fn spawn_mount_worker(
+    backend: Arc<VfsBackend>,
+    mount: &MountConfig,
+    fs_root: Option<PathBuf>,
+    state_dir: &Path,
+    chroma_endpoint: Option<&str>,
+) -> Result<(MountWorker, Option<mpsc::Receiver<FileChange>>), Box<dyn std::error::Error>> {
+    let config = match &mount.index {
+        Some(index) => PipelineConfig::from_index_config(&mount.path, index)?,
+        None => PipelineConfig::default(),
+    };
+    let slug = mount_slug(&mount.path);
+    let state_path = state_dir.join(format!("{}.state.json", slug));
+    let queue_path = state_dir.join(format!("{}.queue.db", slug));
+    let debounce_ms = mount
+        .watch
+        .as_ref()
+        .map(|watch| watch.debounce.as_duration().as_millis() as u64)
+        .unwrap_or(500);
+    let queue_config = WorkQueueConfig {
+        debounce_secs: std::cmp::max(1, debounce_ms / 1000),
+        ..Default::default()
+    };
+
+    let mut pipeline = IndexingPipeline::new(config.clone())?;
+    let mut migration = None;
+    if let Some(endpoint) = chroma_endpoint {
+        let collection = mount
+            .index
+            .as_ref()
+            .and_then(|index| index.collection.clone())
+            .unwrap_or_else(|| "openfs_index".to_string());
+        let logical = ChromaHttpBackend::new(endpoint, &collection, None, None, None)
+            .await
+            .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
+        let active = logical
+            .active_collection()
+            .await
+            .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
+        let logical = Arc::new(logical) as Arc<dyn ChromaStore>;
+        let active = match active {
+            Some(active) => Arc::new(active) as Arc<dyn ChromaStore>,
+            None => Arc::clone(&logical),
+        };
+        pipeline = pipeline.with_chroma(Arc::clone(&active));
+
+        let schema = pipeline.schema_version();
+        let reembed_rate = mount.index.as_ref().and_then(|index| index.reembed_rate);
+        match (pipeline.stored_schema_version().await?, reembed_rate) {
+            (Some(stored), Some(rate)) if stored != schema => {
+                let target = ChromaHttpBackend::new(
+                    endpoint,
+                    &format!("{}-{}", collection, schema),
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
+                println!(
+                    "Index schema of {} changed from {} to {}; re-embedding into collection {} at {} files/s",
+                    mount.path,
+                    stored,
+                    schema,
+                    target.collection_name(),
+                    rate
+                );
+                let target = IndexingPipeline::new(config)?
+                    .with_chroma(Arc::new(target) as Arc<dyn ChromaStore>);
+                let target_state_path = state_dir.join(format!("{}.reembed.state.json", slug));
+                migration = Some(
+                    ReembedMigration::new(
+                        IncrementalIndexer::with_pipeline(target, &target_state_path),
+                        active,
+                        logical,
+                        &mount.path,
+                        &state_path,
+                    )
+                    .with_rate(rate),
+                );
+            }
+            (None, _) if !state_path.exists() => {
+                // Nothing indexed yet: every chunk will use this schema.
+                pipeline.record_schema_version().await?;
+            }
+            _ => {}
+        }
+    }
+
+    let (worker, handle) = match migration {
+        Some(migration) => PersistentIndexWorker::spawn_with_reembed(
+            backend,
+            migration,
+            queue_path.clone(),
+            queue_config,
+            1024,
+        )?,
+        None => PersistentIndexWorker::spawn_with_indexer(
+            backend,
+            IncrementalIndexer::with_pipeline(pipeline, &state_path),
+            queue_path.clone(),
+            queue_config,
+            1024,
+        )?,
+    };
+
+    let (engine, rx) = match &fs_root {
+        Some(fs_root) => {
+            let mut engine = WatchEngine::new()?;
+            engine.watch_path(fs_root)?;
+            let rx = engine
+                .take_receiver()
+                .ok_or("Failed to get watch receiver")?;
+            (Some(engine), Some(rx))
+        }
+        None => (None, None),
+    };
+
+    let worker = MountWorker {
+        mount: mount.clone(),
+        fs_root,
+        queue_path,
+        worker,
+        handle,
+        _engine: engine,
+    };
+    Ok((worker, rx))
+}
+
+/// Write the daemon's status file. Failures are reported but not fatal.
+fn write_status<'a>(
+    status_path: &Path,
+    workers: impl Iterator<Item = &'a MountWorker>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    state: &str,
+) {
+    let mounts: Vec<serde_json::Value> = workers
+        .map(|worker| {
+            let (pending, dead_letters) =
+                match WorkQueue::open(&worker.queue_path, WorkQueueConfig::default()) {
+                    Ok(queue) => (
+                        queue.pending_count().unwrap_or(0),
+                        queue.dead_letter_count().unwrap_or(0),
+                    ),
+                    Err(_) => (0, 0),
+                };
+            serde_json::json!({
+                "path": worker.mount.path,
+                "pending": pending,
+                "dead_letters": dead_letters,
+            })
+        })
+        .collect();
+    let status = serde_json::json!({
+        "pid": std::process::id(),
+        "state": state,
+        "started_at": started_at.to_rfc3339(),
+        "updated_at": chrono::Utc::now().to_rfc3339(),
+        "mounts": mounts,
+    });
+    let content = serde_json::to_string_pretty(&status).unwrap_or_default();
+    if let Err(e) = std::fs::write(status_path, content) {
+        eprintln!(
+            "Warning: failed to write status file {}: {}",
+            status_path.display(),
+            e
+        );
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    format!("/{}", trimmed)
+}
+
+/// Whether `path` is `base` or lies under it.
+fn path_within(path: &str, base: &str) -> bool {
+    let base = base.trim_end_matches('/');
+    base.is_empty() || path == base || path.starts_with(&format!("{}/", base))
+}
+
+/// File-name-safe identifier for a mount path.
+fn mount_slug(mount_path: &str) -> String {
+    let trimmed = mount_path.trim_matches('/');
+    if trimmed.is_empty() {
+        "root".to_string()
+    } else {
+        trimmed.replace('/', "_")
+    }
+}
+
+/// Map a filesystem path reported by the watcher back to its VFS path.
+fn to_vfs_path(mount_path: &str, fs_root: &Path, fs_path: &Path) -> Option<String> {
+    let relative = fs_path.strip_prefix(fs_root).ok()?;
+    let relative = relative.to_string_lossy();
+    let mount = mount_path.trim_end_matches('/');
+    Some(format!("{}/{}", mount, relative.trim_start_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_slug() {
+        assert_eq!(mount_slug("/"), "root");
+        assert_eq!(mount_slug("/workspace"), "workspace");
+        assert_eq!(mount_slug("/a/b/"), "a_b");
+    }
+
+    #[test]
+    fn test_path_within() {
+        assert!(path_within("/workspace/src", "/workspace"));
+        assert!(path_within("/workspace", "/workspace"));
+        assert!(path_within("/workspace", "/"));
+        assert!(!path_within("/workspace2", "/workspace"));
+    }
+
+    #[test]
+    fn test_to_vfs_path() {
+        let root = Path::new("/tmp/data");
+        assert_eq!(
+            to_vfs_path("/workspace", root, Path::new("/tmp/data/src/main.rs")),
+            Some("/workspace/src/main.rs".to_string())
+        );
+        assert_eq!(
+            to_vfs_path("/", root, Path::new("/tmp/data/a.md")),
+            Some("/a.md".to_string())
+        );
+        assert_eq!(
+            to_vfs_path("/workspace", root, Path::new("/etc/passwd")),
+            None
+        );
+    }
+}
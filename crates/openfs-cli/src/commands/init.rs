@@ -0,0 +1,382 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use openfs_config::{
+    BackendConfig, ConfigFormat, FsBackendConfig, IndexConfig, MemoryBackendConfig, MountConfig,
+    PostgresBackendConfig, S3BackendConfig, Secret, VfsConfig,
+};
+
+/// Starter backend offered by `openfs init`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    Fs,
+    Memory,
+    S3,
+    /// Google Cloud Storage through its S3-compatible XML API (HMAC keys).
+    Gcs,
+    Postgres,
+}
+
+impl Template {
+    const NAMES: &'static str = "fs, memory, s3, gcs, postgres";
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "fs" | "local" => Ok(Template::Fs),
+            "memory" | "mem" => Ok(Template::Memory),
+            "s3" => Ok(Template::S3),
+            "gcs" => Ok(Template::Gcs),
+            "postgres" | "pg" => Ok(Template::Postgres),
+            other => Err(format!(
+                "unknown backend template '{}' ({})",
+                other,
+                Self::NAMES
+            )),
+        }
+    }
+}
+
+/// Arguments for the init command.
+pub struct InitArgs {
+    /// File to write. Its extension picks the format.
+    pub output: PathBuf,
+    pub name: Option<String>,
+    pub backend: Option<Template>,
+    /// Directory for `fs`, bucket for `s3`/`gcs`.
+    pub root: Option<String>,
+    pub mount: Option<String>,
+    pub index: bool,
+    /// Accept defaults instead of prompting.
+    pub yes: bool,
+    pub force: bool,
+}
+
+/// Answers gathered from flags and prompts.
+#[derive(Debug, Clone, PartialEq)]
+struct Answers {
+    name: String,
+    template: Template,
+    /// Directory, bucket or connection URL variable, depending on `template`.
+    location: String,
+    region: Option<String>,
+    mount: String,
+    index: bool,
+}
+
+pub fn run(args: InitArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.output.exists() && !args.force {
+        return Err(format!(
+            "{} already exists (use --force to overwrite)",
+            args.output.display()
+        )
+        .into());
+    }
+
+    let interactive = !args.yes && std::io::stdin().is_terminal();
+    let mut prompt = Prompt {
+        interactive,
+        input: std::io::stdin().lock(),
+    };
+    let answers = gather(&args, &mut prompt, &probe_dirs(Path::new(".")))?;
+
+    let config = build_config(&answers);
+    let errors = config.validate();
+    if !errors.is_empty() {
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        return Err(format!("Generated config is invalid: {}", messages.join("; ")).into());
+    }
+    let content = config.to_string_as(ConfigFormat::from_path(&args.output))?;
+    std::fs::write(&args.output, content)?;
+
+    // Re-read the file the way every other command will.
+    if let Err(e) = VfsConfig::from_file_with_secrets(&args.output, None, None) {
+        let _ = std::fs::remove_file(&args.output);
+        return Err(e.into());
+    }
+
+    println!("Wrote {}", args.output.display());
+    for step in next_steps(&answers) {
+        println!("  {}", step);
+    }
+    Ok(())
+}
+
+fn gather(
+    args: &InitArgs,
+    prompt: &mut Prompt<impl BufRead>,
+    probed: &[String],
+) -> Result<Answers, Box<dyn std::error::Error>> {
+    let default_name = std::env::current_dir()
+        .ok()
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "workspace".to_string());
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => prompt.ask("Workspace name", &default_name)?,
+    };
+
+    let template = match args.backend {
+        Some(template) => template,
+        None => Template::parse(&prompt.ask(&format!("Backend ({})", Template::NAMES), "fs")?)?,
+    };
+
+    let (location, region) = match template {
+        Template::Fs => {
+            if prompt.interactive && !probed.is_empty() && args.root.is_none() {
+                println!("Directories here: {}", probed.join(", "));
+            }
+            let root = match &args.root {
+                Some(root) => root.clone(),
+                None => prompt.ask("Directory to mount", ".")?,
+            };
+            (root, None)
+        }
+        Template::Memory => (String::new(), None),
+        Template::S3 | Template::Gcs => {
+            let bucket = match &args.root {
+                Some(bucket) => bucket.clone(),
+                None => prompt.ask("Bucket", "")?,
+            };
+            if bucket.is_empty() {
+                return Err("A bucket is required (pass --root <bucket>)".into());
+            }
+            let region = match template {
+                Template::S3 => Some(prompt.ask("Region", "us-east-1")?),
+                _ => None,
+            };
+            (bucket, region)
+        }
+        Template::Postgres => {
+            let var = match &args.root {
+                Some(var) => var.clone(),
+                None => prompt.ask(
+                    "Environment variable holding the connection URL",
+                    "DATABASE_URL",
+                )?,
+            };
+            (var, None)
+        }
+    };
+
+    let mount = match &args.mount {
+        Some(mount) => mount.clone(),
+        None => prompt.ask("Mount path", "/workspace")?,
+    };
+    let index = args.index || prompt.confirm("Enable search indexing?", false)?;
+
+    Ok(Answers {
+        name,
+        template,
+        location,
+        region,
+        mount: format!("/{}", mount.trim_matches('/')),
+        index,
+    })
+}
+
+fn build_config(answers: &Answers) -> VfsConfig {
+    let backend_name = match answers.template {
+        Template::Fs => "local",
+        Template::Memory => "memory",
+        Template::S3 => "s3",
+        Template::Gcs => "gcs",
+        Template::Postgres => "postgres",
+    };
+    let backend = match answers.template {
+        Template::Fs => BackendConfig::Fs(FsBackendConfig {
+            root: answers.location.clone(),
+        }),
+        Template::Memory => BackendConfig::Memory(MemoryBackendConfig {}),
+        // Credentials come from the default AWS chain (env, profile, role).
+        Template::S3 => BackendConfig::S3(S3BackendConfig {
+            bucket: answers.location.clone(),
+            prefix: None,
+            region: answers.region.clone(),
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+        }),
+        Template::Gcs => BackendConfig::S3(S3BackendConfig {
+            bucket: answers.location.clone(),
+            prefix: None,
+            region: Some("auto".to_string()),
+            endpoint: Some("https://storage.googleapis.com".to_string()),
+            access_key_id: Some(Secret::new("${env:GCS_HMAC_ACCESS_KEY}")),
+            secret_access_key: Some(Secret::new("${env:GCS_HMAC_SECRET}")),
+        }),
+        Template::Postgres => BackendConfig::Postgres(PostgresBackendConfig {
+            connection_url: Secret::new(format!("${{env:{}}}", answers.location)),
+            table_name: None,
+            max_connections: None,
+        }),
+    };
+
+    let mut config = VfsConfig {
+        name: Some(answers.name.clone()),
+        version: Some(openfs_config::migration::CURRENT_VERSION.to_string()),
+        mounts: vec![MountConfig {
+            path: answers.mount.clone(),
+            backend: Some(backend_name.to_string()),
+            collection: None,
+            mode: None,
+            read_only: false,
+            index: answers.index.then(|| IndexConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            sync: None,
+            watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
+        }],
+        ..Default::default()
+    };
+    config.backends.insert(backend_name.to_string(), backend);
+    config
+}
+
+fn next_steps(answers: &Answers) -> Vec<String> {
+    let mut steps = Vec::new();
+    match answers.template {
+        Template::Gcs => steps.push(
+            "export GCS_HMAC_ACCESS_KEY=... GCS_HMAC_SECRET=...  # HMAC keys for the bucket"
+                .to_string(),
+        ),
+        Template::Postgres => steps.push(format!("export {}=postgres://...", answers.location)),
+        Template::Fs | Template::Memory | Template::S3 => {}
+    }
+    match answers.template {
+        Template::S3 | Template::Gcs => steps
+            .push("cargo install --path crates/openfs-cli --features openfs-remote/s3".to_string()),
+        Template::Postgres => steps.push(
+            "cargo install --path crates/openfs-cli --features openfs-remote/postgres".to_string(),
+        ),
+        Template::Fs | Template::Memory => {}
+    }
+    steps.push("openfs validate".to_string());
+    steps.push(format!("openfs ls {}", answers.mount));
+    if answers.index {
+        steps.push(format!("openfs index {}", answers.mount));
+    }
+    steps
+}
+
+/// Visible subdirectories of `dir` worth offering as mounts.
+fn probe_dirs(dir: &Path) -> Vec<String> {
+    const SKIP: &[&str] = &["target", "node_modules", "dist", "build", "venv"];
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.') && !SKIP.contains(&name.as_str()))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Line-based prompts. When not interactive every question takes its default.
+struct Prompt<R> {
+    interactive: bool,
+    input: R,
+}
+
+impl<R: BufRead> Prompt<R> {
+    fn ask(&mut self, question: &str, default: &str) -> std::io::Result<String> {
+        if !self.interactive {
+            return Ok(default.to_string());
+        }
+        if default.is_empty() {
+            print!("{}: ", question);
+        } else {
+            print!("{} [{}]: ", question, default);
+        }
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        self.input.read_line(&mut line)?;
+        let answer = line.trim();
+        Ok(if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer.to_string()
+        })
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> std::io::Result<bool> {
+        let answer = self.ask(question, if default { "Y/n" } else { "y/N" })?;
+        Ok(match answer.to_ascii_lowercase().as_str() {
+            "y" | "yes" => true,
+            "n" | "no" => false,
+            _ => default,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> InitArgs {
+        InitArgs {
+            output: PathBuf::from("openfs.yaml"),
+            name: Some("demo".to_string()),
+            backend: None,
+            root: None,
+            mount: None,
+            index: false,
+            yes: false,
+            force: false,
+        }
+    }
+
+    #[test]
+    fn test_gather_from_prompts() {
+        let mut prompt = Prompt {
+            interactive: true,
+            input: "s3\nagent-data\neu-west-1\n/data\ny\n".as_bytes(),
+        };
+        let answers = gather(&args(), &mut prompt, &[]).unwrap();
+        assert_eq!(answers.template, Template::S3);
+        assert_eq!(answers.location, "agent-data");
+        assert_eq!(answers.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(answers.mount, "/data");
+        assert!(answers.index);
+    }
+
+    #[test]
+    fn test_defaults_build_a_valid_config() {
+        let mut prompt = Prompt {
+            interactive: false,
+            input: "".as_bytes(),
+        };
+        let answers = gather(&args(), &mut prompt, &[]).unwrap();
+        assert_eq!(answers.template, Template::Fs);
+        assert_eq!(answers.mount, "/workspace");
+
+        for template in [
+            Template::Fs,
+            Template::Memory,
+            Template::Gcs,
+            Template::Postgres,
+        ] {
+            let answers = Answers {
+                template,
+                location: match template {
+                    Template::Gcs => "agent-data".to_string(),
+                    Template::Postgres => "DATABASE_URL".to_string(),
+                    _ => ".".to_string(),
+                },
+                ..answers.clone()
+            };
+            let config = build_config(&answers);
+            assert!(config.validate().is_empty(), "{:?}", template);
+            let yaml = config.to_string_as(ConfigFormat::Yaml).unwrap();
+            assert!(yaml.contains("path: /workspace"), "{}", yaml);
+        }
+    }
+}
@@ -1,10 +1,31 @@
 use openfs_remote::Vfs;
 
-pub async fn run(vfs: &Vfs, path: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+use crate::output::{entry_json, print_json, OutputFormat};
+
+pub async fn run(
+    vfs: &Vfs,
+    path: Option<String>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
     let path = path.as_deref().unwrap_or("/");
 
     let entries = vfs.list(path).await?;
 
+    if output.is_json() {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let full_path = if path == "/" {
+                    format!("/{}", entry.name)
+                } else {
+                    format!("{}/{}", path.trim_end_matches('/'), entry.name)
+                };
+                entry_json(&full_path, entry)
+            })
+            .collect();
+        return print_json(&serde_json::Value::Array(entries));
+    }
+
     if entries.is_empty() {
         println!("(empty)");
         return Ok(());
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use clap_mangen::Man;
+
+/// Render roff man pages.
+///
+/// Without `out_dir` the top-level page is printed to stdout. With it,
+/// `openfs.1` and one `openfs-<command>.1` per subcommand are written there.
+pub fn run(
+    mut cmd: clap::Command,
+    out_dir: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    cmd.build();
+    let Some(out_dir) = out_dir else {
+        Man::new(cmd).render(&mut std::io::stdout())?;
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(out_dir)?;
+    let mut pages = 0usize;
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        // Built subcommands display as "openfs-<command>".
+        let name = sub
+            .get_display_name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("openfs-{}", sub.get_name()));
+        let mut file = std::fs::File::create(out_dir.join(format!("{}.1", name)))?;
+        Man::new(sub.clone()).render(&mut file)?;
+        pages += 1;
+    }
+    let mut file = std::fs::File::create(out_dir.join("openfs.1"))?;
+    Man::new(cmd).render(&mut file)?;
+    pages += 1;
+
+    println!("Wrote {} man pages to {}", pages, out_dir.display());
+    Ok(())
+}
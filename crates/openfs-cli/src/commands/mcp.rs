@@ -1,14 +1,87 @@
-use std::path::Path;
 use std::sync::Arc;
 
-use openfs_config::VfsConfig;
-use openfs_mcp::{McpHandler, McpServer};
-use openfs_remote::Vfs;
+use openfs_config::{BackendConfig, McpProfile, VfsConfig};
+use openfs_core::{ChromaStore, PathSanitizer};
+use openfs_local::SearchEngine;
+use openfs_mcp::{McpHandler, McpPolicy, McpServer};
+use openfs_remote::{ChromaHttpBackend, Vfs};
+
+/// Arguments for the mcp command.
+pub struct McpArgs {
+    /// Only expose tools that never modify files.
+    pub read_only: bool,
+    /// Tool allowlist (overrides `mcp.tools`).
+    pub tools: Vec<String>,
+    /// Session root (overrides `mcp.root`).
+    pub root: Option<String>,
+}
+
+pub async fn run(config: VfsConfig, args: McpArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut mcp_config = config.mcp.clone().unwrap_or_default();
+    if args.read_only {
+        mcp_config.profile = McpProfile::ReadOnly;
+    }
+    if !args.tools.is_empty() {
+        mcp_config.tools = args.tools;
+    }
+    if args.root.is_some() {
+        mcp_config.root = args.root;
+    }
+    let policy = McpPolicy::from_config(&mcp_config)?
+        .with_sanitizer(PathSanitizer::from_config(&config))
+        .with_tools_config(config.tools.clone().unwrap_or_default());
 
-pub async fn run(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let config = VfsConfig::from_file(config_path)?;
     let vfs = Arc::new(Vfs::from_config(config).await?);
-    let handler = McpHandler::new(vfs);
+    register_searchers(&vfs).await;
+    let handler = McpHandler::new(vfs).with_policy(policy)?;
     let server = McpServer::new(handler);
     server.run().await
 }
+
+/// Register a searcher for every indexed mount, using the first Chroma
+/// backend in the config. Mounts that fail to connect are left without search.
+pub async fn register_searchers(vfs: &Vfs) {
+    let config = vfs.effective_config();
+    let Some(chroma) = config.backends.values().find_map(|b| match b {
+        BackendConfig::Chroma(chroma) => Some(chroma),
+        _ => None,
+    }) else {
+        return;
+    };
+
+    for mount in &config.mounts {
+        let Some(index) = mount.index.as_ref().filter(|i| i.enabled) else {
+            continue;
+        };
+        let collection = index
+            .collection
+            .as_deref()
+            .or(mount.collection.as_deref())
+            .unwrap_or("openfs_index");
+
+        let store = match ChromaHttpBackend::new(
+            &chroma.url,
+            collection,
+            chroma.api_key.as_ref().map(|s| s.expose()),
+            chroma.tenant.as_deref(),
+            chroma.database.as_deref(),
+        )
+        .await
+        {
+            Ok(store) => store.follow_active().await,
+            Err(e) => Err(e),
+        };
+        let store = match store {
+            Ok(store) => store,
+            Err(e) => {
+                eprintln!("Search disabled for {}: {}", mount.path, e);
+                continue;
+            }
+        };
+
+        match SearchEngine::for_mount(mount, Arc::new(store) as Arc<dyn ChromaStore>) {
+            Ok(engine) => vfs.set_searcher(&mount.path, Arc::new(engine)),
+            Err(e) => eprintln!("Search disabled for {}: {}", mount.path, e),
+        }
+    }
+}
@@ -1,9 +1,18 @@
 use std::path::Path;
 
-use openfs_config::VfsConfig;
+use openfs_config::{ConfigFormat, VfsConfig};
 
 pub async fn run(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let config = VfsConfig::from_file(config_path)?;
+    // Migration rewrites the file, which would flatten includes and profiles.
+    if openfs_config::has_layers(config_path)? {
+        return Err(format!(
+            "{} uses include or profiles; migrate each file separately",
+            config_path.display()
+        )
+        .into());
+    }
+    // Keep secret references as written rather than saving resolved secrets.
+    let config = VfsConfig::from_file_with_secrets(config_path, None, None)?;
 
     let version = openfs_config::migration::detect_version(&config);
     println!("Detected config version: {}", version);
@@ -12,9 +21,9 @@ pub async fn run(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let new_version = migrated.version.as_deref().unwrap_or("unknown");
     println!("Migrated to version: {}", new_version);
 
-    // Write back the migrated config
-    let yaml = serde_yaml::to_string(&migrated)?;
-    std::fs::write(config_path, yaml)?;
+    // Write back the migrated config in the file's own format
+    let content = migrated.to_string_as(ConfigFormat::from_path(config_path))?;
+    std::fs::write(config_path, content)?;
     println!("Configuration updated: {}", config_path.display());
 
     Ok(())
@@ -1,13 +1,26 @@
 pub mod append;
+pub mod bench;
+pub mod cache;
 pub mod cat;
+pub mod completions;
 pub mod config;
 pub mod cp;
+pub mod dedupe;
+pub mod diff;
+pub mod du;
 pub mod exists;
+pub mod export;
 pub mod find;
 pub mod grep;
+pub mod head;
+pub mod import;
 pub mod index;
+pub mod index_dlq;
+pub mod indexd;
 pub mod index_status;
+pub mod init;
 pub mod ls;
+pub mod man;
 pub mod mcp;
 pub mod migrate;
 #[cfg(feature = "fuse")]
@@ -33,11 +46,57 @@ pub mod mount {
     }
 }
 pub mod mv;
+pub mod patch;
 pub mod rm;
 pub mod search;
+pub mod search_tui;
+#[cfg(feature = "grpc")]
+pub mod serve_grpc;
+#[cfg(not(feature = "grpc"))]
+pub mod serve_grpc {
+    use openfs_config::VfsConfig;
+
+    /// gRPC server arguments.
+    #[allow(dead_code)]
+    pub struct ServeGrpcArgs {
+        /// Address to listen on.
+        pub addr: std::net::SocketAddr,
+    }
+
+    /// Run the gRPC server when gRPC support is disabled.
+    pub async fn run(
+        _config: VfsConfig,
+        _args: ServeGrpcArgs,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Err("gRPC support is disabled in this build. Rebuild openfs-cli with --features grpc.".into())
+    }
+}
+#[cfg(feature = "nfs")]
+pub mod serve_nfs;
+#[cfg(not(feature = "nfs"))]
+pub mod serve_nfs {
+    use openfs_config::VfsConfig;
+
+    /// NFS server arguments.
+    #[allow(dead_code)]
+    pub struct ServeNfsArgs {
+        /// TCP port on localhost.
+        pub port: u16,
+    }
+
+    /// Run the NFS server when NFS support is disabled.
+    pub fn run(_config: VfsConfig, _args: ServeNfsArgs) -> Result<(), Box<dyn std::error::Error>> {
+        Err("NFS support is disabled in this build. Rebuild openfs-cli with --features nfs.".into())
+    }
+}
+pub mod shell;
+pub mod snapshot;
 pub mod stat;
 pub mod status;
 pub mod sync;
+pub mod sync_copy;
+pub mod tag;
+pub mod tail;
 pub mod tools;
 pub mod tree;
 pub mod unmount;
@@ -3,9 +3,14 @@
 use std::path::PathBuf;
 
 use openfs_config::VfsConfig;
-use openfs_fuse::OpenFsFuse;
+#[cfg(unix)]
+use openfs_fuse::{block_on, OpenFsFuse};
+
+#[cfg(unix)]
+use super::mcp::register_searchers;
 
 /// Mount arguments.
+#[cfg_attr(not(unix), allow(dead_code))]
 pub struct MountArgs {
     /// Mount point path.
     pub mountpoint: PathBuf,
@@ -17,6 +22,7 @@ pub struct MountArgs {
 ///
 /// Note: This function does not take a Vfs reference because it needs
 /// to create and own the FUSE filesystem. The config is loaded separately.
+#[cfg(unix)]
 pub fn run(config: VfsConfig, args: MountArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Ensure mount point exists
     if !args.mountpoint.exists() {
@@ -25,6 +31,8 @@ pub fn run(config: VfsConfig, args: MountArgs) -> Result<(), Box<dyn std::error:
 
     // Create FUSE filesystem
     let ax = OpenFsFuse::from_config(config)?;
+    // Lets `.search/<query>?mode=...` use the configured indexes.
+    block_on(register_searchers(&ax.vfs))?;
 
     // Mount (this blocks until unmount)
     if args.foreground {
@@ -35,3 +43,12 @@ pub fn run(config: VfsConfig, args: MountArgs) -> Result<(), Box<dyn std::error:
 
     Ok(())
 }
+
+/// Mounting needs a FUSE driver, which is only wired up on macOS and Linux.
+/// There is no WinFsp adapter yet; see `plans/windows-mount.md`.
+#[cfg(not(unix))]
+pub fn run(_config: VfsConfig, _args: MountArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err("openfs mount only works on macOS and Linux; \
+         mounting on Windows (WinFsp) is not implemented"
+        .into())
+}
@@ -0,0 +1,28 @@
+use openfs_remote::Vfs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+pub async fn run(
+    vfs: &Vfs,
+    path: &str,
+    patch_file: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let diff = match patch_file {
+        Some(file) => std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read patch {}: {}", file.display(), e))?,
+        None => {
+            // Read from stdin
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        }
+    };
+
+    let summary = vfs.apply_patch(path, &diff).await?;
+    println!(
+        "Patched {}: {} hunk(s), +{} -{}",
+        path, summary.hunks, summary.added, summary.removed
+    );
+
+    Ok(())
+}
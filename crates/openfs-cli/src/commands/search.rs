@@ -1,18 +1,64 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
 
 use openfs_core::ChromaStore;
-use openfs_local::{IndexingPipeline, PipelineConfig, SearchConfig, SearchEngine, SearchMode};
+use openfs_local::{
+    FusionMethod, IndexState, IndexingPipeline, PipelineConfig, ScoreNormalization, SearchConfig,
+    SearchEngine, SearchMode, SearchResult,
+};
 use openfs_remote::{ChromaHttpBackend, Vfs};
+use tokio::io::{AsyncBufReadExt, BufReader};
 
-pub async fn run(
-    _vfs: &Vfs,
-    query: &str,
-    chroma_endpoint: Option<String>,
-    collection: Option<String>,
-    limit: Option<usize>,
-    mode: Option<String>,
-    context_lines: Option<usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
+use crate::output::{print_json, OutputFormat};
+
+/// Arguments for the search command.
+pub struct SearchArgs {
+    /// Search query (optional in interactive mode).
+    pub query: Option<String>,
+    /// Chroma endpoint URL.
+    pub chroma_endpoint: Option<String>,
+    /// Collection name to search.
+    pub collection: Option<String>,
+    /// Maximum number of results.
+    pub limit: Option<usize>,
+    /// Search mode (dense, sparse, hybrid).
+    pub mode: Option<String>,
+    /// Hybrid weight of dense scores; sparse scores get `1 - alpha`.
+    pub alpha: Option<f32>,
+    /// Hybrid fusion method (weighted, rrf).
+    pub fusion: Option<String>,
+    /// Score normalization before weighted fusion (none, minmax).
+    pub normalize: Option<String>,
+    /// Chunk metadata filters as `key:value` (e.g. `lang:rust`).
+    pub filters: Vec<String>,
+    /// Number of context lines to show.
+    pub context_lines: Option<usize>,
+    /// Return file-level summaries instead of raw chunks.
+    pub summaries: bool,
+    /// Prompt for queries and browse results interactively.
+    pub interactive: bool,
+    /// Output format for results.
+    pub output: OutputFormat,
+}
+
+pub async fn run(vfs: &Vfs, args: SearchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let SearchArgs {
+        query,
+        chroma_endpoint,
+        collection,
+        limit,
+        mode,
+        alpha,
+        fusion,
+        normalize,
+        filters,
+        context_lines,
+        summaries,
+        interactive,
+        output,
+    } = args;
     // Search requires a Chroma backend for dense search
     let chroma_endpoint =
         chroma_endpoint.ok_or("Search requires --chroma-endpoint to be specified")?;
@@ -21,36 +67,97 @@ pub async fn run(
 
     // Connect to Chroma
     let chroma = ChromaHttpBackend::new(&chroma_endpoint, &collection_name, None, None, None)
+        .await
+        .map_err(|e| format!("Failed to connect to Chroma: {}", e))?
+        .follow_active()
         .await
         .map_err(|e| format!("Failed to connect to Chroma: {}", e))?;
 
     // Create pipeline and search engine
+    let chroma = Arc::new(chroma) as Arc<dyn ChromaStore>;
     let config = PipelineConfig::default();
-    let pipeline = Arc::new(IndexingPipeline::new(config)?);
-    let engine = SearchEngine::new(pipeline).with_chroma(Arc::new(chroma) as Arc<dyn ChromaStore>);
-
-    // Parse search mode
-    let search_mode = match mode.as_deref() {
-        Some("dense") => SearchMode::Dense,
-        Some("sparse") => SearchMode::Sparse,
-        Some("hybrid") => SearchMode::Hybrid,
-        None => SearchMode::Dense, // Default to dense for Chroma-based search
-        Some(m) => {
-            return Err(format!(
-                "Unknown search mode: {}. Use 'dense', 'sparse', or 'hybrid'",
-                m
-            )
-            .into())
-        }
-    };
+    let pipeline = Arc::new(IndexingPipeline::new(config)?.with_chroma(chroma.clone()));
+    warn_stale_index(&pipeline).await;
+    let engine = SearchEngine::new(pipeline).with_chroma(chroma);
 
-    // Configure search
-    let search_config = SearchConfig {
-        mode: search_mode,
+    // Configure search. Default to dense for Chroma-based search, unless
+    // hybrid fusion options were given.
+    let default_mode = if alpha.is_some() || fusion.is_some() {
+        "hybrid"
+    } else {
+        "dense"
+    };
+    let mut search_config = SearchConfig {
+        mode: parse_mode(mode.as_deref().unwrap_or(default_mode))?,
         limit: limit.unwrap_or(10),
         min_score: 0.0,
+        fusion: parse_fusion(fusion.as_deref().unwrap_or("weighted"))?,
+        normalization: parse_normalization(normalize.as_deref().unwrap_or("none"))?,
         ..Default::default()
     };
+    if let Some(alpha) = alpha {
+        search_config = search_config.with_alpha(alpha)?;
+    }
+    for filter in &filters {
+        let (key, value) = parse_filter(filter)?;
+        search_config = search_config.with_filter(key, value);
+    }
+    let context = context_lines.unwrap_or(2);
+
+    if interactive {
+        if output.is_json() {
+            return Err("Interactive search does not support --output json".into());
+        }
+        // Without a terminal (e.g. piped input), fall back to a line prompt.
+        if !super::search_tui::available() {
+            return browse(vfs, &engine, search_config, context, query).await;
+        }
+        if let Some(path) = super::search_tui::run(vfs, &engine, search_config, query).await? {
+            println!("{}", path);
+        }
+        return Ok(());
+    }
+    let query = query.ok_or("Search requires a query")?;
+    let query = query.as_str();
+
+    if output.is_json() {
+        let value = if summaries {
+            let files = engine.search_summaries(query, &search_config).await?;
+            let files: Vec<_> = files
+                .iter()
+                .map(|file| {
+                    serde_json::json!({
+                        "path": file.source_path,
+                        "score": file.score,
+                        "matched_chunks": file.matched_chunks,
+                        "summary": file.summary,
+                    })
+                })
+                .collect();
+            serde_json::json!({ "query": query, "files": files })
+        } else {
+            let results = engine.search(query, &search_config).await?;
+            let results: Vec<_> = results
+                .iter()
+                .map(|result| {
+                    serde_json::json!({
+                        "path": result.chunk.source_path,
+                        "language": result.chunk.metadata.get("language"),
+                        "score": result.score,
+                        "dense_score": result.dense_score,
+                        "sparse_score": result.sparse_score,
+                        "start_line": result.chunk.start_line,
+                        "end_line": result.chunk.end_line,
+                        "chunk_index": result.chunk.chunk_index,
+                        "total_chunks": result.chunk.total_chunks,
+                        "content": result.chunk.content,
+                    })
+                })
+                .collect();
+            serde_json::json!({ "query": query, "results": results })
+        };
+        return print_json(&value);
+    }
 
     println!("Searching for: \"{}\"", query);
     println!(
@@ -58,6 +165,30 @@ pub async fn run(
         search_config.mode, search_config.limit
     );
 
+    if summaries {
+        let files = engine.search_summaries(query, &search_config).await?;
+        if files.is_empty() {
+            println!("No results found.");
+            return Ok(());
+        }
+
+        println!("Found {} files:\n", files.len());
+        for (i, file) in files.iter().enumerate() {
+            println!(
+                "{}. {} (score: {:.4}, {} matching chunks)",
+                i + 1,
+                file.source_path,
+                file.score,
+                file.matched_chunks
+            );
+            match &file.summary {
+                Some(summary) => println!("   {}\n", summary),
+                None => println!("   (no summary; re-index with --summarizer)\n"),
+            }
+        }
+        return Ok(());
+    }
+
     // Perform search
     let results = engine.search(query, &search_config).await?;
 
@@ -67,9 +198,71 @@ pub async fn run(
     }
 
     println!("Found {} results:\n", results.len());
+    print_results(&results, search_config.mode, context);
 
-    let context = context_lines.unwrap_or(2);
+    Ok(())
+}
+
+/// Warn when the collection mixes chunks from different index schemas.
+async fn warn_stale_index(pipeline: &IndexingPipeline) {
+    let state_path = IndexState::default_path(Path::new("."));
+    let state = state_path
+        .exists()
+        .then(|| IndexState::load(&state_path).ok())
+        .flatten();
+    let stored = pipeline.stored_schema_version().await.ok().flatten();
+    // The state records the schema of the latest indexing run.
+    let current = state
+        .as_ref()
+        .and_then(|state| state.schema_version.clone())
+        .or_else(|| stored.clone());
+    if let Some(current) = current {
+        let stale_files = state.map_or(0, |state| state.stale_files(&current).len());
+        super::index::warn_stale_schema(stored.as_deref(), stale_files, &current);
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<SearchMode, Box<dyn std::error::Error>> {
+    match mode {
+        "dense" => Ok(SearchMode::Dense),
+        "sparse" => Ok(SearchMode::Sparse),
+        "hybrid" => Ok(SearchMode::Hybrid),
+        m => Err(format!(
+            "Unknown search mode: {}. Use 'dense', 'sparse', or 'hybrid'",
+            m
+        )
+        .into()),
+    }
+}
+
+fn parse_fusion(fusion: &str) -> Result<FusionMethod, Box<dyn std::error::Error>> {
+    match fusion {
+        "weighted" => Ok(FusionMethod::WeightedSum),
+        "rrf" => Ok(FusionMethod::ReciprocalRank { k: 60.0 }),
+        f => Err(format!("Unknown fusion method: {}. Use 'weighted' or 'rrf'", f).into()),
+    }
+}
+
+fn parse_normalization(normalize: &str) -> Result<ScoreNormalization, Box<dyn std::error::Error>> {
+    match normalize {
+        "none" => Ok(ScoreNormalization::None),
+        "minmax" => Ok(ScoreNormalization::MinMax),
+        n => Err(format!("Unknown normalization: {}. Use 'none' or 'minmax'", n).into()),
+    }
+}
+
+/// Parse a `key:value` metadata filter; `lang` is short for `language`.
+fn parse_filter(filter: &str) -> Result<(&str, &str), Box<dyn std::error::Error>> {
+    match filter.split_once(':') {
+        Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+            let key = if key == "lang" { "language" } else { key };
+            Ok((key, value))
+        }
+        _ => Err(format!("Invalid filter: {}. Use key:value, e.g. lang:rust", filter).into()),
+    }
+}
 
+fn print_results(results: &[SearchResult], mode: SearchMode, context: usize) {
     for (i, result) in results.iter().enumerate() {
         println!(
             "{}. {} (score: {:.4})",
@@ -78,8 +271,14 @@ pub async fn run(
             result.score
         );
 
-        if let (Some(dense), Some(sparse)) = (result.dense_score, result.sparse_score) {
-            println!("   [dense: {:.4}, sparse: {:.4}]", dense, sparse);
+        if mode == SearchMode::Hybrid {
+            let source =
+                |score: Option<f32>| score.map_or("-".to_string(), |s| format!("{:.4}", s));
+            println!(
+                "   [dense: {}, sparse: {}]",
+                source(result.dense_score),
+                source(result.sparse_score)
+            );
         }
 
         println!(
@@ -90,36 +289,151 @@ pub async fn run(
             result.chunk.total_chunks
         );
 
-        // Show snippet
-        let content = &result.chunk.content;
-        let lines: Vec<&str> = content.lines().collect();
-        let preview_lines = if lines.len() > context * 2 + 1 {
-            let start = &lines[..context];
-            let end = &lines[lines.len() - context..];
-            format!(
-                "{}\n   ...\n{}",
-                start
-                    .join("\n")
-                    .lines()
-                    .map(|l| format!("   {}", l))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-                end.join("\n")
-                    .lines()
-                    .map(|l| format!("   {}", l))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            )
-        } else {
-            lines
-                .iter()
-                .map(|l| format!("   {}", l))
-                .collect::<Vec<_>>()
-                .join("\n")
-        };
+        println!("{}\n", preview(&result.chunk.content, context));
+    }
+}
 
-        println!("{}\n", preview_lines);
+/// Indented snippet showing the first and last `context` lines of a chunk.
+fn preview(content: &str, context: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let indent = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|l| format!("   {}", l))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if lines.len() > context * 2 + 1 {
+        format!(
+            "{}\n   ...\n{}",
+            indent(&lines[..context]),
+            indent(&lines[lines.len() - context..])
+        )
+    } else {
+        indent(&lines)
     }
+}
+
+/// Whether `path` is `mount` or under it.
+pub(super) fn in_mount(path: &str, mount: &str) -> bool {
+    path.strip_prefix(mount)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The extension of `path`'s file name, without the dot.
+pub(super) fn extension(path: &str) -> Option<&str> {
+    let name = path.rsplit('/').next()?;
+    name.rsplit_once('.').map(|(_, ext)| ext)
+}
+
+const BROWSE_HELP: &str = "\
+Type a query to search. Commands:
+  :open N          Print the file of result N
+  :mount [path]    Only show results under path (no path clears)
+  :type [ext]      Toggle a file extension filter (no ext clears)
+  :mode MODE       Switch to dense, sparse or hybrid
+  :help            Show this help
+  :q               Quit";
+
+/// Prompt for queries, showing ranked results with previews.
+async fn browse(
+    vfs: &Vfs,
+    engine: &SearchEngine,
+    mut config: SearchConfig,
+    context: usize,
+    initial_query: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = config.limit;
+    let mut mount: Option<String> = None;
+    let mut types: BTreeSet<String> = BTreeSet::new();
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut pending = initial_query;
+
+    println!("{}\n", BROWSE_HELP);
+    loop {
+        let line = match pending.take() {
+            Some(query) => query,
+            None => {
+                print!("search> ");
+                std::io::stdout().flush()?;
+                match lines.next_line().await? {
+                    Some(line) => line.trim().to_string(),
+                    None => break,
+                }
+            }
+        };
+        if line.is_empty() {
+            continue;
+        }
 
+        if let Some(command) = line.strip_prefix(':') {
+            let (name, arg) = command
+                .split_once(' ')
+                .map_or((command, None), |(n, a)| (n, Some(a.trim())));
+            match (name, arg) {
+                ("q" | "quit", _) => break,
+                ("help", _) => println!("{}", BROWSE_HELP),
+                ("open", Some(n)) => {
+                    match n
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| results.get(n.wrapping_sub(1)))
+                    {
+                        Some(result) => match vfs.read(&result.chunk.source_path).await {
+                            Ok(content) => println!("{}", String::from_utf8_lossy(&content)),
+                            Err(e) => eprintln!("error: {}", e),
+                        },
+                        None => eprintln!("error: no result {}", n),
+                    }
+                }
+                ("mount", path) => {
+                    mount = path.map(|p| p.trim_end_matches('/').to_string());
+                    println!("mount filter: {}", mount.as_deref().unwrap_or("(none)"));
+                }
+                ("type", Some(ext)) => {
+                    let ext = ext.trim_start_matches('.').to_string();
+                    if !types.remove(&ext) {
+                        types.insert(ext);
+                    }
+                    println!("type filter: {:?}", types);
+                }
+                ("type", None) => {
+                    types.clear();
+                    println!("type filter: (none)");
+                }
+                ("mode", Some(mode)) => match parse_mode(mode) {
+                    Ok(mode) => config.mode = mode,
+                    Err(e) => eprintln!("error: {}", e),
+                },
+                _ => eprintln!("error: unknown command :{} (type :help)", command),
+            }
+            continue;
+        }
+
+        // Over-fetch so filtering still fills the page.
+        let filtered = mount.is_some() || !types.is_empty();
+        config.limit = if filtered { limit * 3 } else { limit };
+        results = match engine.search(&line, &config).await {
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                continue;
+            }
+        };
+        results.retain(|r| {
+            let path = &r.chunk.source_path;
+            let ext = extension(path);
+            mount.as_deref().is_none_or(|m| in_mount(path, m))
+                && (types.is_empty() || ext.is_some_and(|ext| types.contains(ext)))
+        });
+        results.truncate(limit);
+
+        if results.is_empty() {
+            println!("No results found.");
+        } else {
+            print_results(&results, config.mode, context);
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,360 @@
+//! Full-screen search browser for `openfs search --tui`.
+//!
+//! Queries run as you type, once typing pauses for [`DEBOUNCE`]. The
+//! browser draws on stderr so the path picked with Enter can be printed to
+//! stdout, e.g. `openfs cat "$(openfs search --tui)"`.
+
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+
+use base64::Engine as _;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use openfs_local::{SearchConfig, SearchEngine, SearchMode, SearchResult};
+use openfs_remote::Vfs;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use tokio::time::{sleep_until, Instant};
+
+use super::search::{extension, in_mount};
+
+/// How long typing must pause before the query runs.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+const KEYS: &str = "Enter pick  ^Y copy path  F2 mount  F3 type  F4 mode  Esc quit";
+
+/// Whether stdin and stderr are terminals the browser can run in.
+pub fn available() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Run the browser until the user quits. Returns the picked path, if any.
+pub async fn run(
+    vfs: &Vfs,
+    engine: &SearchEngine,
+    config: SearchConfig,
+    initial_query: Option<String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mounts = vfs
+        .effective_config()
+        .mounts
+        .iter()
+        .map(|mount| mount.path.clone())
+        .collect();
+    let browser = Browser {
+        query: initial_query.unwrap_or_default(),
+        mode: config.mode,
+        mounts,
+        mount: None,
+        file_type: None,
+        limit: config.limit,
+        results: Vec::new(),
+        selected: ListState::default(),
+        status: String::new(),
+    };
+
+    enable_raw_mode()?;
+    execute!(std::io::stderr(), EnterAlternateScreen)?;
+    let outcome = match Terminal::new(CrosstermBackend::new(std::io::stderr())) {
+        Ok(mut terminal) => browse(&mut terminal, browser, engine, config).await,
+        Err(e) => Err(e.into()),
+    };
+    // Restore the terminal before reporting how the browser ended.
+    disable_raw_mode()?;
+    execute!(std::io::stderr(), LeaveAlternateScreen)?;
+    outcome
+}
+
+/// What a key press asks the event loop to do.
+enum Action {
+    None,
+    Search,
+    Pick(String),
+    Quit,
+}
+
+/// Browser state: the query, its results and the result filters.
+struct Browser {
+    query: String,
+    mode: SearchMode,
+    mounts: Vec<String>,
+    /// Index into `mounts` of the mount results are limited to.
+    mount: Option<usize>,
+    file_type: Option<String>,
+    limit: usize,
+    results: Vec<SearchResult>,
+    selected: ListState,
+    status: String,
+}
+
+async fn browse(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stderr>>,
+    mut browser: Browser,
+    engine: &SearchEngine,
+    mut config: SearchConfig,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut events = EventStream::new();
+    let mut due = (!browser.query.is_empty()).then(Instant::now);
+    let mut search: Option<BoxFuture<'_, Result<Vec<SearchResult>, String>>> = None;
+
+    loop {
+        terminal.draw(|frame| browser.draw(frame))?;
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event.transpose()? else {
+                    return Ok(None);
+                };
+                let Event::Key(key) = event else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match browser.handle_key(key) {
+                    Action::None => {}
+                    Action::Search => due = Some(Instant::now() + DEBOUNCE),
+                    Action::Pick(path) => return Ok(Some(path)),
+                    Action::Quit => return Ok(None),
+                }
+            }
+            _ = sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                due = None;
+                if browser.query.trim().is_empty() {
+                    // Dropping an in-flight search cancels it.
+                    search = None;
+                    browser.set_results(Ok(Vec::new()));
+                    continue;
+                }
+                // Over-fetch so the mount and type filters still fill the page.
+                config.mode = browser.mode;
+                config.limit = browser.limit * 3;
+                let query = browser.query.clone();
+                let config = config.clone();
+                browser.status = "Searching...".to_string();
+                search = Some(Box::pin(async move {
+                    engine.search(&query, &config).await.map_err(|e| e.to_string())
+                }));
+            }
+            results = async { search.as_mut().expect("guarded by is_some").await }, if search.is_some() => {
+                search = None;
+                browser.set_results(results);
+            }
+        }
+    }
+}
+
+impl Browser {
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => Action::Quit,
+            KeyCode::Char('c') if ctrl => Action::Quit,
+            KeyCode::Enter => match self.selected_result() {
+                Some(result) => Action::Pick(result.chunk.source_path.clone()),
+                None => Action::None,
+            },
+            KeyCode::Char('y') if ctrl => {
+                if let Some(path) = self.selected_result().map(|r| r.chunk.source_path.clone()) {
+                    self.status = match copy_to_clipboard(&path) {
+                        Ok(()) => format!("Copied {}", path),
+                        Err(e) => format!("Copy failed: {}", e),
+                    };
+                }
+                Action::None
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                Action::Search
+            }
+            KeyCode::Up => {
+                self.selected.select_previous();
+                Action::None
+            }
+            KeyCode::Down => {
+                self.selected.select_next();
+                Action::None
+            }
+            KeyCode::F(2) => {
+                self.mount = match self.mount {
+                    None if !self.mounts.is_empty() => Some(0),
+                    Some(i) if i + 1 < self.mounts.len() => Some(i + 1),
+                    _ => None,
+                };
+                self.selected.select_first();
+                Action::None
+            }
+            KeyCode::F(3) => {
+                // Cycle through the extensions in the current results.
+                let mut types: Vec<&str> = self
+                    .results
+                    .iter()
+                    .filter_map(|r| extension(&r.chunk.source_path))
+                    .collect();
+                types.sort();
+                types.dedup();
+                let next = match &self.file_type {
+                    None => types.first(),
+                    Some(current) => types.iter().skip_while(|t| *t != current).nth(1),
+                };
+                self.file_type = next.map(|t| t.to_string());
+                self.selected.select_first();
+                Action::None
+            }
+            KeyCode::F(4) => {
+                self.mode = match self.mode {
+                    SearchMode::Dense => SearchMode::Sparse,
+                    SearchMode::Sparse => SearchMode::Hybrid,
+                    SearchMode::Hybrid => SearchMode::Dense,
+                };
+                Action::Search
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                Action::Search
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                Action::Search
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn set_results(&mut self, results: Result<Vec<SearchResult>, String>) {
+        match results {
+            Ok(results) => {
+                self.results = results;
+                self.status.clear();
+            }
+            Err(e) => {
+                self.results.clear();
+                self.status = format!("error: {}", e);
+            }
+        }
+        self.selected.select_first();
+    }
+
+    /// Results passing the mount and type filters, best first.
+    fn visible(&self) -> Vec<&SearchResult> {
+        let mount = self.mount.map(|i| self.mounts[i].as_str());
+        self.results
+            .iter()
+            .filter(|r| {
+                let path = &r.chunk.source_path;
+                mount.is_none_or(|mount| in_mount(path, mount))
+                    && self
+                        .file_type
+                        .as_deref()
+                        .is_none_or(|t| extension(path) == Some(t))
+            })
+            .take(self.limit)
+            .collect()
+    }
+
+    fn selected_result(&self) -> Option<&SearchResult> {
+        let visible = self.visible();
+        let index = self.selected.selected()?.min(visible.len().checked_sub(1)?);
+        Some(visible[index])
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input_area, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(body);
+
+        let mode = match self.mode {
+            SearchMode::Dense => "dense",
+            SearchMode::Sparse => "sparse",
+            SearchMode::Hybrid => "hybrid",
+        };
+        let title = format!(
+            " Search ({}, mount: {}, type: {}) ",
+            mode,
+            self.mount.map_or("all", |i| self.mounts[i].as_str()),
+            self.file_type.as_deref().unwrap_or("all"),
+        );
+        let input = Paragraph::new(self.query.as_str())
+            .block(Block::default().borders(Borders::ALL).title(title));
+        frame.render_widget(input, input_area);
+        frame.set_cursor_position((
+            input_area.x + 1 + self.query.chars().count() as u16,
+            input_area.y + 1,
+        ));
+
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|r| {
+                ListItem::new(format!(
+                    "{:.3}  {}:{}-{}",
+                    r.score, r.chunk.source_path, r.chunk.start_line, r.chunk.end_line
+                ))
+            })
+            .collect();
+        let preview = self.selected_result().map(|result| {
+            let lines: Vec<Line> = result
+                .chunk
+                .content
+                .lines()
+                .enumerate()
+                .map(|(i, line)| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:>5} ", result.chunk.start_line + i),
+                            Style::default().add_modifier(Modifier::DIM),
+                        ),
+                        Span::raw(line.to_string()),
+                    ])
+                })
+                .collect();
+            (result.chunk.source_path.clone(), lines)
+        });
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" Results ({}) ", visible.len())),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.selected);
+
+        let (preview_title, preview_lines) = preview.unwrap_or_default();
+        let preview = Paragraph::new(preview_lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", preview_title)),
+        );
+        frame.render_widget(preview, preview_area);
+
+        let footer_line = Line::from(vec![
+            Span::raw(format!("{}  ", self.status)),
+            Span::styled(KEYS, Style::default().add_modifier(Modifier::DIM)),
+        ]);
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+/// Copy `text` to the system clipboard with the OSC 52 escape sequence,
+/// which terminals (including over SSH) pass to the local clipboard.
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stderr = std::io::stderr();
+    write!(stderr, "\x1b]52;c;{}\x07", encoded)?;
+    stderr.flush()
+}
@@ -0,0 +1,30 @@
+//! Serve the VFS over gRPC for services that want generated clients.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use openfs_config::VfsConfig;
+use openfs_grpc::GrpcServer;
+use openfs_remote::Vfs;
+
+use super::mcp::register_searchers;
+
+/// gRPC server arguments.
+pub struct ServeGrpcArgs {
+    /// Address to listen on.
+    pub addr: SocketAddr,
+}
+
+/// Run the gRPC server until the process is stopped.
+///
+/// Requests are not authenticated, so keep `addr` on the loopback interface
+/// unless something in front of the server checks callers.
+pub async fn run(config: VfsConfig, args: ServeGrpcArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let vfs = Arc::new(Vfs::from_config(config).await?);
+    // Lets `Search` use the configured indexes.
+    register_searchers(&vfs).await;
+
+    println!("Serving openfs.v1.Vfs over gRPC on {}", args.addr);
+    GrpcServer::new(vfs).serve(args.addr).await?;
+    Ok(())
+}
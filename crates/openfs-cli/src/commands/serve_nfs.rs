@@ -0,0 +1,43 @@
+//! Serve the VFS over NFSv3 for hosts that can't load FUSE.
+
+use std::net::{Ipv4Addr, TcpListener};
+
+use openfs_config::VfsConfig;
+use openfs_fuse::{block_on, NfsServer, OpenFsCore};
+
+use super::mcp::register_searchers;
+
+/// NFS server arguments.
+pub struct ServeNfsArgs {
+    /// TCP port on localhost.
+    pub port: u16,
+}
+
+/// Run the NFS server until the process is stopped.
+///
+/// The server only listens on the loopback interface: requests are not
+/// authenticated.
+pub fn run(config: VfsConfig, args: ServeNfsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let core = OpenFsCore::from_config(config)?;
+    // Lets `.search/<query>?mode=...` use the configured indexes.
+    block_on(register_searchers(&core.vfs))?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, args.port))?;
+    let port = listener.local_addr()?.port();
+
+    println!("Serving OpenFS over NFSv3 on 127.0.0.1:{}", port);
+    println!("Mount with:");
+    if cfg!(target_os = "macos") {
+        println!(
+            "  mount -t nfs -o vers=3,tcp,port={0},mountport={0},nolocks,locallocks localhost:/ <mountpoint>",
+            port
+        );
+    } else {
+        println!(
+            "  mount -t nfs -o vers=3,tcp,port={0},mountport={0},nolock localhost:/ <mountpoint>",
+            port
+        );
+    }
+
+    NfsServer::new(core).serve(listener)?;
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use std::path::PathBuf;
+
+use openfs_core::SearchOptions;
+use openfs_remote::Vfs;
+use rustyline::completion::Completer;
+use rustyline::config::CompletionType;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Context, Editor, Helper};
+use tokio::runtime::Handle;
+
+use super::{cat, completions, grep, head, ls, stat, tail, tree};
+use crate::output::OutputFormat;
+
+/// Lines of history kept in the history file.
+const HISTORY_LIMIT: usize = 1000;
+
+/// Command names completed at the start of a line.
+const COMMANDS: &[&str] = &[
+    "cat", "cd", "exit", "grep", "head", "help", "history", "ls", "pwd", "search", "stat", "tail",
+    "tree",
+];
+
+const HELP: &str = "\
+Commands:
+  cd [path]             Change directory (defaults to /)
+  pwd                   Print the current directory
+  ls [path]             List directory contents
+  tree [path]           Show the directory tree
+  cat <path>            Print a file
+  head <path> [n]       Print the first n lines (default 10)
+  tail <path> [n]       Print the last n lines (default 10)
+  stat <path>           Show file metadata
+  grep <pattern> [path] Search file contents recursively
+  search <query>        Semantic search under the current directory
+  history               Show command history
+  help                  Show this help
+  exit                  Leave the shell";
+
+pub async fn run(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
+    super::mcp::register_searchers(vfs).await;
+
+    let history_path = history_path();
+    let mut history = history_path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| text.lines().map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .max_history_size(HISTORY_LIMIT)?
+        .build();
+    let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::with_config(config)?;
+    for entry in &history {
+        editor.add_history_entry(entry.as_str())?;
+    }
+
+    let mut cwd = "/".to_string();
+    println!("OpenFS shell. Type 'help' for commands.");
+
+    loop {
+        editor.set_helper(Some(ShellHelper {
+            vfs,
+            cwd: cwd.clone(),
+        }));
+        // Completion lists directories from inside readline, so it blocks
+        // on VFS calls; readline runs off the async worker for that.
+        let prompt = format!("openfs:{}> ", cwd);
+        let line = match tokio::task::block_in_place(|| editor.readline(&prompt)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => {
+                println!();
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if history.last().map(String::as_str) != Some(line) {
+            history.push(line.to_string());
+            editor.add_history_entry(line)?;
+        }
+
+        let args = split_args(line);
+        let arg = |i: usize| args.get(i).map(String::as_str);
+        let path_arg = |i: usize| resolve_path(&cwd, arg(i).unwrap_or("."));
+        let result: Result<(), Box<dyn std::error::Error>> = match args[0].as_str() {
+            "exit" | "quit" => break,
+            "help" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "pwd" => {
+                println!("{}", cwd);
+                Ok(())
+            }
+            "cd" => {
+                let target = resolve_path(&cwd, arg(1).unwrap_or("/"));
+                match vfs.stat(&target).await {
+                    Ok(entry) if entry.is_dir => {
+                        cwd = target;
+                        Ok(())
+                    }
+                    Ok(_) => Err(format!("Not a directory: {}", target).into()),
+                    // Mount points above the mounts (e.g. "/") may not stat.
+                    Err(_) if vfs.list(&target).await.is_ok() => {
+                        cwd = target;
+                        Ok(())
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+            "ls" => ls::run(vfs, Some(path_arg(1)), OutputFormat::Text).await,
+            "tree" => tree::run(vfs, Some(path_arg(1)), None, OutputFormat::Text).await,
+            "cat" | "stat" | "head" | "tail" if arg(1).is_none() => {
+                Err(format!("Usage: {} <path>", args[0]).into())
+            }
+            "cat" => cat::run(vfs, &path_arg(1)).await,
+            "stat" => stat::run(vfs, &path_arg(1), OutputFormat::Text).await,
+            "head" | "tail" => match arg(2).map(str::parse::<usize>).transpose() {
+                Ok(n) if args[0] == "head" => {
+                    head::run(vfs, &path_arg(1), n.unwrap_or(10), None).await
+                }
+                Ok(n) => tail::run(vfs, &path_arg(1), n.unwrap_or(10), None, false).await,
+                Err(_) => Err(format!("Invalid line count: {}", args[2]).into()),
+            },
+            "grep" => match arg(1) {
+                Some(pattern) => {
+                    let path = Some(path_arg(2));
+                    grep::run(vfs, pattern, path, true, false, OutputFormat::Text).await
+                }
+                None => Err("Usage: grep <pattern> [path]".into()),
+            },
+            "search" if args.len() < 2 => Err("Usage: search <query>".into()),
+            "search" => search(vfs, &cwd, &args[1..].join(" ")).await,
+            "history" => {
+                for (i, entry) in history.iter().enumerate() {
+                    println!("{:>5}  {}", i + 1, entry);
+                }
+                Ok(())
+            }
+            other => Err(format!("Unknown command: {} (type 'help')", other).into()),
+        };
+
+        if let Err(e) = result {
+            crate::errors::print_error(e.as_ref());
+        }
+    }
+
+    if let Some(path) = history_path {
+        let start = history.len().saturating_sub(HISTORY_LIMIT);
+        let mut text = history[start..].join("\n");
+        text.push('\n');
+        if let Err(e) = std::fs::write(&path, text) {
+            eprintln!("Failed to save history to {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+async fn search(vfs: &Vfs, cwd: &str, query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let options = SearchOptions {
+        path_prefix: (cwd != "/").then(|| cwd.to_string()),
+        ..Default::default()
+    };
+    let hits = vfs.search(query, &options).await?;
+    if hits.is_empty() {
+        println!("No results");
+    }
+    for hit in hits {
+        println!(
+            "{:.3}  {}:{}-{}",
+            hit.score, hit.path, hit.start_line, hit.end_line
+        );
+    }
+    Ok(())
+}
+
+/// Completes command names and VFS paths in the shell.
+struct ShellHelper<'a> {
+    vfs: &'a Vfs,
+    cwd: String,
+}
+
+impl Completer for ShellHelper<'_> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        if line[..start].trim().is_empty() {
+            let commands = COMMANDS.iter().filter(|c| c.starts_with(word));
+            return Ok((start, commands.map(|c| format!("{} ", c)).collect()));
+        }
+
+        // Complete relative words against the current directory, keeping
+        // them relative.
+        let typed_dir = &word[..word.rfind('/').map_or(0, |i| i + 1)];
+        let dir = resolve_path(&self.cwd, typed_dir);
+        let dir = if dir == "/" { dir } else { format!("{}/", dir) };
+        let prefix = format!("{}{}", dir, &word[typed_dir.len()..]);
+        let paths = Handle::current().block_on(completions::path_completions(self.vfs, &prefix));
+        let candidates = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&dir))
+            .map(|name| format!("{}{}", typed_dir, name))
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper<'_> {}
+
+impl Validator for ShellHelper<'_> {}
+
+impl Helper for ShellHelper<'_> {}
+
+fn history_path() -> Option<PathBuf> {
+    dirs_next::home_dir().map(|home| home.join(".openfs_history"))
+}
+
+/// Resolve `path` against `cwd`, collapsing `.` and `..` segments.
+fn resolve_path(cwd: &str, path: &str) -> String {
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", cwd, path)
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Split a command line on whitespace, honoring single and double quotes.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
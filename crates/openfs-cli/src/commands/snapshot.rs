@@ -0,0 +1,115 @@
+use openfs_remote::{SnapshotDiff, SnapshotInfo, Vfs};
+
+use crate::output::{print_json, OutputFormat};
+
+pub async fn run_create(
+    vfs: &Vfs,
+    mount: &str,
+    label: Option<&str>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let info = vfs.snapshot(mount, label).await?;
+    if output.is_json() {
+        return print_json(&snapshot_json(&info));
+    }
+    println!(
+        "Created snapshot {} of {} ({} files, {} bytes)",
+        info.id, info.mount, info.files, info.bytes
+    );
+    Ok(())
+}
+
+pub fn run_list(
+    vfs: &Vfs,
+    mount: &str,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshots = vfs.snapshots(mount)?;
+    if output.is_json() {
+        return print_json(&serde_json::Value::Array(
+            snapshots.iter().map(snapshot_json).collect(),
+        ));
+    }
+    if snapshots.is_empty() {
+        println!("No snapshots of {}.", mount);
+        return Ok(());
+    }
+    for info in snapshots {
+        println!(
+            "{}  {}  {:>6} files  {:>10} bytes  {}",
+            info.id,
+            info.created_at.format("%Y-%m-%d %H:%M:%S"),
+            info.files,
+            info.bytes,
+            info.label.as_deref().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+pub async fn run_restore(
+    vfs: &Vfs,
+    mount: &str,
+    snapshot: &str,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = vfs.restore_snapshot(mount, snapshot).await?;
+    if output.is_json() {
+        return print_json(&serde_json::json!({
+            "written": summary.written,
+            "deleted": summary.deleted,
+            "unchanged": summary.unchanged,
+        }));
+    }
+    println!(
+        "Restored {} to {}: {} written, {} deleted, {} unchanged",
+        mount, snapshot, summary.written, summary.deleted, summary.unchanged
+    );
+    Ok(())
+}
+
+pub async fn run_diff(
+    vfs: &Vfs,
+    mount: &str,
+    from: &str,
+    to: Option<&str>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let diff = vfs.diff_snapshot(mount, from, to).await?;
+    if output.is_json() {
+        return print_json(&serde_json::json!({
+            "added": diff.added,
+            "modified": diff.modified,
+            "deleted": diff.deleted,
+        }));
+    }
+    if diff.is_empty() {
+        println!("No changes.");
+        return Ok(());
+    }
+    print_diff(&diff);
+    Ok(())
+}
+
+fn print_diff(diff: &SnapshotDiff) {
+    for (marker, paths) in [
+        ('A', &diff.added),
+        ('M', &diff.modified),
+        ('D', &diff.deleted),
+    ] {
+        for path in paths {
+            println!("{} {}", marker, path);
+        }
+    }
+}
+
+fn snapshot_json(info: &SnapshotInfo) -> serde_json::Value {
+    serde_json::json!({
+        "id": info.id,
+        "mount": info.mount,
+        "label": info.label,
+        "created_at": info.created_at.to_rfc3339(),
+        "files": info.files,
+        "bytes": info.bytes,
+    })
+}
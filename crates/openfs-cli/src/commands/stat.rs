@@ -1,8 +1,18 @@
 use openfs_remote::Vfs;
 
-pub async fn run(vfs: &Vfs, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+use crate::output::{entry_json, print_json, OutputFormat};
+
+pub async fn run(
+    vfs: &Vfs,
+    path: &str,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
     let entry = vfs.stat(path).await?;
 
+    if output.is_json() {
+        return print_json(&entry_json(path, &entry));
+    }
+
     println!("Path:     {}", path);
     println!("Name:     {}", entry.name);
     println!(
@@ -14,6 +24,10 @@ pub async fn run(vfs: &Vfs, path: &str) -> Result<(), Box<dyn std::error::Error>
         println!("Size:     {} bytes", size);
     }
 
+    if let Some(content_type) = &entry.content_type {
+        println!("Content:  {}", content_type);
+    }
+
     if let Some(modified) = entry.modified {
         println!("Modified: {}", modified.format("%Y-%m-%d %H:%M:%S UTC"));
     }
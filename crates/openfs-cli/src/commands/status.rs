@@ -1,17 +1,102 @@
 use openfs_remote::Vfs;
 
-fn sync_mode_label(mode: openfs_remote::SyncMode) -> &'static str {
+use crate::output::{print_json, sync_mode_label, sync_status_json, OutputFormat};
+
+fn backend_type(backend: &openfs_config::BackendConfig) -> &'static str {
+    match backend {
+        openfs_config::BackendConfig::Fs(_) => "fs",
+        openfs_config::BackendConfig::Memory(_) => "memory",
+        openfs_config::BackendConfig::Chroma(_) => "chroma",
+        openfs_config::BackendConfig::S3(_) => "s3",
+        openfs_config::BackendConfig::Postgres(_) => "postgres",
+        _ => "unknown",
+    }
+}
+
+fn mount_mode_label(mode: &openfs_config::MountMode) -> &'static str {
     match mode {
-        openfs_remote::SyncMode::None => "none",
-        openfs_remote::SyncMode::WriteThrough => "write-through",
-        openfs_remote::SyncMode::WriteBack => "write-back",
-        openfs_remote::SyncMode::PullMirror => "pull-mirror",
+        openfs_config::MountMode::Local => "local",
+        openfs_config::MountMode::LocalIndexed => "local-indexed",
+        openfs_config::MountMode::WriteThrough => "write-through",
+        openfs_config::MountMode::WriteBack => "write-back",
+        openfs_config::MountMode::Remote => "remote",
+        openfs_config::MountMode::RemoteCached => "remote-cached",
+        openfs_config::MountMode::PullMirror => "pull-mirror",
+        openfs_config::MountMode::TwoWay => "two-way",
+        _ => "unknown",
     }
 }
 
-pub async fn run(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(vfs: &Vfs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     let config = vfs.effective_config();
     let sync_statuses = vfs.sync_statuses().await?;
+    let disk_caches = vfs.disk_cache_stats();
+    let breakers = vfs.circuit_breakers();
+    let replication = vfs.replication_stats().await;
+
+    if output.is_json() {
+        let backends: Vec<_> = config
+            .backends
+            .iter()
+            .map(|(name, backend)| serde_json::json!({ "name": name, "type": backend_type(backend) }))
+            .collect();
+        let mounts: Vec<_> = config
+            .mounts
+            .iter()
+            .map(|mount| {
+                serde_json::json!({
+                    "path": mount.path,
+                    "backend": mount.backend,
+                    "mode": mount.mode.as_ref().map(mount_mode_label),
+                    "read_only": mount.read_only,
+                })
+            })
+            .collect();
+        let status = serde_json::json!({
+            "name": config.name,
+            "version": config.version,
+            "backends": backends,
+            "mounts": mounts,
+            "sync": sync_statuses.iter().map(sync_status_json).collect::<Vec<_>>(),
+            "disk_cache": disk_caches
+                .iter()
+                .map(|(mount, stats)| {
+                    serde_json::json!({
+                        "mount": mount,
+                        "dir": stats.dir,
+                        "entries": stats.entries,
+                        "bytes": stats.bytes,
+                        "max_bytes": stats.max_bytes,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "circuit_breakers": breakers
+                .iter()
+                .map(|(mount, status)| {
+                    serde_json::json!({
+                        "mount": mount,
+                        "state": status.state.as_str(),
+                        "consecutive_failures": status.consecutive_failures,
+                        "times_opened": status.times_opened,
+                        "retry_in_ms": status.retry_in.map(|d| d.as_millis() as u64),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "replication": replication
+                .iter()
+                .map(|(mount, replica, stats)| {
+                    serde_json::json!({
+                        "mount": mount,
+                        "replica": replica,
+                        "pending": stats.pending,
+                        "synced": stats.synced,
+                        "failed": stats.failed,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        });
+        return print_json(&status);
+    }
 
     println!("OpenFS Status");
     println!("=========");
@@ -27,31 +112,14 @@ pub async fn run(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
     // Backends
     println!("Backends:");
     for (name, backend) in &config.backends {
-        let backend_type = match backend {
-            openfs_config::BackendConfig::Fs(_) => "fs",
-            openfs_config::BackendConfig::Memory(_) => "memory",
-            openfs_config::BackendConfig::Chroma(_) => "chroma",
-            openfs_config::BackendConfig::S3(_) => "s3",
-            openfs_config::BackendConfig::Postgres(_) => "postgres",
-            _ => "unknown",
-        };
-        println!("  {} ({})", name, backend_type);
+        println!("  {} ({})", name, backend_type(backend));
     }
     println!();
 
     // Mounts
     println!("Mounts:");
     for mount in &config.mounts {
-        let mode = mount.mode.as_ref().map_or("default", |m| match m {
-            openfs_config::MountMode::Local => "local",
-            openfs_config::MountMode::LocalIndexed => "local-indexed",
-            openfs_config::MountMode::WriteThrough => "write-through",
-            openfs_config::MountMode::WriteBack => "write-back",
-            openfs_config::MountMode::Remote => "remote",
-            openfs_config::MountMode::RemoteCached => "remote-cached",
-            openfs_config::MountMode::PullMirror => "pull-mirror",
-            _ => "unknown",
-        });
+        let mode = mount.mode.as_ref().map_or("default", mount_mode_label);
 
         let read_only = if mount.read_only { " [read-only]" } else { "" };
 
@@ -91,7 +159,64 @@ pub async fn run(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
     }
     println!();
 
+    if !disk_caches.is_empty() {
+        println!("Disk cache:");
+        for (mount, stats) in &disk_caches {
+            println!(
+                "  {} -> {} ({} files, {} of {})",
+                mount,
+                stats.dir.display(),
+                stats.entries,
+                format_size(stats.bytes),
+                format_size(stats.max_bytes)
+            );
+        }
+        println!();
+    }
+
+    if !breakers.is_empty() {
+        println!("Circuit breakers:");
+        for (mount, status) in &breakers {
+            let retry_in = status
+                .retry_in
+                .map(|d| format!(", trial in {:.1}s", d.as_secs_f64()))
+                .unwrap_or_default();
+            println!(
+                "  {} -> {} ({} failure(s) in a row, opened {} time(s){})",
+                mount,
+                status.state.as_str(),
+                status.consecutive_failures,
+                status.times_opened,
+                retry_in
+            );
+        }
+        println!();
+    }
+
+    if !replication.is_empty() {
+        println!("Replication:");
+        for (mount, replica, stats) in &replication {
+            println!(
+                "  {} -> {} ({} pending, {} copied, {} failed)",
+                mount, replica, stats.pending, stats.synced, stats.failed
+            );
+        }
+        println!();
+    }
+
     println!("Status: OK");
 
     Ok(())
 }
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1}G", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}K", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
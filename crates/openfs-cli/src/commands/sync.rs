@@ -1,22 +1,44 @@
-use openfs_remote::{SyncMode, Vfs};
+use openfs_remote::{MountSyncStatus, PathSyncState, PathSyncStatus, Vfs};
 
-fn sync_mode_label(mode: SyncMode) -> &'static str {
-    match mode {
-        SyncMode::None => "none",
-        SyncMode::WriteThrough => "write-through",
-        SyncMode::WriteBack => "write-back",
-        SyncMode::PullMirror => "pull-mirror",
-    }
-}
+use crate::output::{
+    path_sync_status_json, print_json, sync_mode_label, sync_status_json, OutputFormat,
+};
 
-pub async fn run_status(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_status(
+    vfs: &Vfs,
+    verbose: bool,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
     let statuses = vfs.sync_statuses().await?;
+    let paths = if verbose {
+        vfs.sync_status().await?
+    } else {
+        Vec::new()
+    };
+
+    if output.is_json() {
+        return print_json(&serde_json::Value::Array(
+            statuses
+                .iter()
+                .map(|status| {
+                    let mut json = sync_status_json(status);
+                    if verbose {
+                        json["paths"] = paths_on(&status.mount_path, &paths, &statuses)
+                            .into_iter()
+                            .map(path_sync_status_json)
+                            .collect();
+                    }
+                    json
+                })
+                .collect(),
+        ));
+    }
 
     println!("OpenFS Sync Status");
     println!("==============");
     println!();
 
-    for status in statuses {
+    for status in &statuses {
         println!(
             "{} -> {} (mode: {}, read_only: {})",
             status.mount_path,
@@ -25,9 +47,21 @@ pub async fn run_status(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
             status.read_only
         );
         println!(
-            "  pending: {}, synced: {}, failed: {}, retries: {}",
-            status.pending, status.synced, status.failed, status.retries
+            "  pending: {}, synced: {}, failed: {}, retries: {}, dead-lettered: {}",
+            status.pending, status.synced, status.failed, status.retries, status.dead_lettered
         );
+        if status.coalesced > 0 {
+            println!(
+                "  coalesced: {} (uploads saved by merging repeated writes)",
+                status.coalesced
+            );
+        }
+        if status.conflicts > 0 {
+            println!(
+                "  conflicts: {} (see `openfs sync conflicts`)",
+                status.conflicts
+            );
+        }
         if let (Some(pending), Some(processing), Some(failed), Some(unapplied)) = (
             status.outbox_pending,
             status.outbox_processing,
@@ -39,12 +73,139 @@ pub async fn run_status(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
                 pending, processing, failed, unapplied
             );
         }
+        if verbose {
+            for path in paths_on(&status.mount_path, &paths, &statuses) {
+                println!("  {}", path_status_line(path));
+            }
+        }
         println!();
     }
 
     Ok(())
 }
 
+/// The paths on `mount`, leaving those of mounts nested under it out.
+fn paths_on<'a>(
+    mount: &str,
+    paths: &'a [PathSyncStatus],
+    mounts: &[MountSyncStatus],
+) -> Vec<&'a PathSyncStatus> {
+    let contains = |mount: &str, path: &str| {
+        path.strip_prefix(mount.trim_end_matches('/'))
+            .is_some_and(|rest| rest.starts_with('/'))
+    };
+    paths
+        .iter()
+        .filter(|path| {
+            let owner = mounts
+                .iter()
+                .map(|status| status.mount_path.as_str())
+                .filter(|other| contains(other, &path.path))
+                .max_by_key(|other| other.len());
+            owner == Some(mount)
+        })
+        .collect()
+}
+
+fn path_status_line(status: &PathSyncStatus) -> String {
+    let mut line = format!("{:<9} {}", status.state.as_str(), status.path);
+    let mut details = Vec::new();
+    if status.attempts > 0 {
+        details.push(format!("{} failed attempt(s)", status.attempts));
+    }
+    if let PathSyncState::Failed { retrying: true, .. } = status.state {
+        details.push("retrying".to_string());
+    }
+    if let Some(synced) = status.last_synced {
+        details.push(format!(
+            "last synced {}",
+            synced.format("%Y-%m-%d %H:%M:%S")
+        ));
+    }
+    if !details.is_empty() {
+        line.push_str(&format!(" ({})", details.join(", ")));
+    }
+    if let PathSyncState::Failed { reason, .. } = &status.state {
+        line.push_str(&format!(": {}", reason));
+    }
+    line
+}
+
+pub async fn run_retry_failed(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
+    let requeued = vfs.retry_failed_sync().await?;
+    println!("Requeued {} failed sync operation(s).", requeued);
+    let still_failed: usize = vfs
+        .sync_statuses()
+        .await?
+        .iter()
+        .filter_map(|s| s.outbox_failed)
+        .sum();
+    if still_failed > 0 {
+        println!(
+            "{} operation(s) failed again; see `openfs sync status`.",
+            still_failed
+        );
+    }
+    Ok(())
+}
+
+pub fn run_conflicts(
+    vfs: &Vfs,
+    clear: bool,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if clear {
+        let cleared = vfs.clear_sync_conflicts()?;
+        println!("Cleared {} conflict record(s).", cleared);
+        return Ok(());
+    }
+
+    let conflicts = vfs.sync_conflicts()?;
+    if output.is_json() {
+        return print_json(&serde_json::Value::Array(
+            conflicts
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "path": c.path,
+                        "base": c.base,
+                        "remote": c.remote,
+                        "resolution": c.resolution.as_str(),
+                        "copy": c.resolution.copy(),
+                        "detected_at": c.detected_at,
+                    })
+                })
+                .collect(),
+        ));
+    }
+
+    if conflicts.is_empty() {
+        println!("No write-back conflicts.");
+        return Ok(());
+    }
+    for conflict in conflicts {
+        let when = chrono::DateTime::from_timestamp(conflict.detected_at, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        match conflict.resolution.copy() {
+            Some(copy) => println!(
+                "{}  {}  {} -> {}",
+                when,
+                conflict.resolution.as_str(),
+                conflict.path,
+                copy
+            ),
+            None => println!(
+                "{}  {}  {}",
+                when,
+                conflict.resolution.as_str(),
+                conflict.path
+            ),
+        }
+    }
+    Ok(())
+}
+
 pub async fn run_flush(vfs: &Vfs) -> Result<(), Box<dyn std::error::Error>> {
     let mounts = vfs.flush_write_back().await?;
     println!("Flushed write-back sync state for {} mount(s).", mounts);
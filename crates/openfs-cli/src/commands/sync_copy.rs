@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use openfs_core::{Entry, VfsError};
+use openfs_remote::Vfs;
+
+use super::diff::tree_files;
+use crate::transfer::{Transfer, TransferArgs, TransferOp};
+
+/// Options for `openfs sync-copy`.
+pub struct SyncCopyArgs {
+    /// Delete destination files that are not in the source.
+    pub delete: bool,
+    /// Print the plan without changing anything.
+    pub dry_run: bool,
+    /// Compare checksums instead of modification times.
+    pub checksum: bool,
+    /// Parallelism, retries, bandwidth and progress.
+    pub transfer: TransferArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Copy,
+    Update,
+    Delete,
+}
+
+impl Action {
+    fn symbol(self) -> char {
+        match self {
+            Action::Copy => '+',
+            Action::Update => '~',
+            Action::Delete => '-',
+        }
+    }
+}
+
+pub async fn run(
+    vfs: &Vfs,
+    src: &str,
+    dst: &str,
+    args: SyncCopyArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let src = src.trim_end_matches('/');
+    let dst = dst.trim_end_matches('/');
+    let src_files = tree_files(vfs, src).await?;
+    // A missing destination is created by the copy.
+    let dst_files = match tree_files(vfs, dst).await {
+        Ok(files) => files,
+        Err(VfsError::NotFound(_)) => BTreeMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut plan = Vec::new();
+    let mut unchanged = 0usize;
+    for (relative, src_entry) in &src_files {
+        match dst_files.get(relative) {
+            None => plan.push((Action::Copy, relative.clone())),
+            Some(dst_entry) => {
+                if needs_update(vfs, src_entry, dst_entry, args.checksum).await? {
+                    plan.push((Action::Update, relative.clone()));
+                } else {
+                    unchanged += 1;
+                }
+            }
+        }
+    }
+    if args.delete {
+        for relative in dst_files.keys() {
+            if !src_files.contains_key(relative) {
+                plan.push((Action::Delete, relative.clone()));
+            }
+        }
+    }
+
+    let mut bytes = 0u64;
+    let mut counts = [0usize; 3];
+    let mut failed = 0usize;
+    if args.dry_run {
+        for (action, relative) in &plan {
+            println!("{} {}", action.symbol(), relative);
+            counts[*action as usize] += 1;
+        }
+    } else {
+        let ops: Vec<TransferOp> = plan
+            .iter()
+            .map(|(action, relative)| {
+                let dst = format!("{}/{}", dst, relative);
+                match action {
+                    Action::Copy | Action::Update => TransferOp::Copy {
+                        src: format!("{}/{}", src, relative),
+                        dst,
+                        size: src_files[relative].size.unwrap_or(0),
+                    },
+                    Action::Delete => TransferOp::Delete { path: dst },
+                }
+            })
+            .collect();
+        let total = ops
+            .iter()
+            .map(|op| match op {
+                TransferOp::Copy { size, .. } => *size,
+                _ => 0,
+            })
+            .sum();
+        let transfer = Transfer::new(&args.transfer, Some(total));
+        transfer
+            .run(vfs, &ops, |i, result| {
+                let (action, relative) = &plan[i];
+                match result {
+                    Ok(size) => {
+                        println!("{} {}", action.symbol(), relative);
+                        bytes += size;
+                        counts[*action as usize] += 1;
+                    }
+                    Err(e) => {
+                        eprintln!("error: {} {}: {}", action.symbol(), relative, e);
+                        failed += 1;
+                    }
+                }
+            })
+            .await;
+        transfer.finish();
+    }
+
+    println!(
+        "{}{} copied, {} updated, {} deleted, {} unchanged ({} bytes)",
+        if args.dry_run { "[dry run] " } else { "" },
+        counts[Action::Copy as usize],
+        counts[Action::Update as usize],
+        counts[Action::Delete as usize],
+        unchanged,
+        bytes
+    );
+
+    if failed > 0 {
+        return Err(format!("{} file(s) failed to sync", failed).into());
+    }
+    Ok(())
+}
+
+/// Whether `dst` is out of date with `src`.
+///
+/// Sizes are compared first. Then either checksums, or modification times:
+/// a destination written after the source changed is up to date.
+async fn needs_update(
+    vfs: &Vfs,
+    src: &Entry,
+    dst: &Entry,
+    checksum: bool,
+) -> Result<bool, VfsError> {
+    if src.size != dst.size {
+        return Ok(true);
+    }
+    if !checksum {
+        if let (Some(src_modified), Some(dst_modified)) = (src.modified, dst.modified) {
+            return Ok(dst_modified < src_modified);
+        }
+    }
+    Ok(vfs.checksum(&src.path).await? != vfs.checksum(&dst.path).await?)
+}
@@ -0,0 +1,81 @@
+use openfs_remote::Vfs;
+
+use crate::output::{print_json, OutputFormat};
+
+pub async fn run_add(
+    vfs: &Vfs,
+    path: &str,
+    tags: &[String],
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for tag in tags {
+        vfs.add_tag(path, tag).await?;
+    }
+    let tags = vfs.tags(path).await?;
+    if output.is_json() {
+        return print_json(&serde_json::json!({ "path": path, "tags": tags }));
+    }
+    println!("{}: {}", path, tags.join(", "));
+    Ok(())
+}
+
+pub async fn run_rm(
+    vfs: &Vfs,
+    path: &str,
+    tag: &str,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let removed = vfs.remove_tag(path, tag).await?;
+    if output.is_json() {
+        return print_json(&serde_json::json!({ "path": path, "tag": tag, "removed": removed }));
+    }
+    if removed {
+        println!("Removed tag {} from {}", tag, path);
+    } else {
+        println!("{} is not tagged {}", path, tag);
+    }
+    Ok(())
+}
+
+/// List the tags of `path`, the files tagged `tag`, or every tag in use.
+pub async fn run_ls(
+    vfs: &Vfs,
+    path: Option<&str>,
+    tag: Option<&str>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = path {
+        let tags = vfs.tags(path).await?;
+        if output.is_json() {
+            return print_json(&serde_json::json!({ "path": path, "tags": tags }));
+        }
+        for tag in tags {
+            println!("{}", tag);
+        }
+    } else if let Some(tag) = tag {
+        let files = vfs.tagged(tag).await?;
+        if output.is_json() {
+            return print_json(&serde_json::json!({ "tag": tag, "files": files }));
+        }
+        for file in files {
+            println!("{}", file);
+        }
+    } else {
+        let counts = vfs.tag_counts().await?;
+        if output.is_json() {
+            return print_json(&serde_json::Value::Object(
+                counts
+                    .into_iter()
+                    .map(|(tag, files)| (tag, serde_json::json!(files)))
+                    .collect(),
+            ));
+        }
+        if counts.is_empty() {
+            println!("No tags.");
+        }
+        for (tag, files) in counts {
+            println!("{:>6} files  {}", files, tag);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use openfs_local::WatchEngine;
+use openfs_remote::{LineRange, Vfs};
+
+/// How often to re-check the file when no watch event arrives.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub async fn run(
+    vfs: &Vfs,
+    path: &str,
+    lines: usize,
+    bytes: Option<u64>,
+    follow: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match bytes {
+        Some(bytes) => {
+            let size = vfs.stat(path).await?.size.unwrap_or(0);
+            vfs.read_range(path, size.saturating_sub(bytes), None)
+                .await?
+        }
+        None => vfs.read_lines(path, LineRange::Tail(lines)).await?,
+    };
+    io::stdout().write_all(&content)?;
+    io::stdout().flush()?;
+
+    if follow {
+        follow_file(vfs, path).await?;
+    }
+    Ok(())
+}
+
+/// Print data appended to `path` until interrupted.
+///
+/// Local `fs` mounts are woken by file watch events; other backends are polled.
+async fn follow_file(vfs: &Vfs, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut offset = vfs.stat(path).await?.size.unwrap_or(0);
+
+    let mut engine = match vfs.resolve_fs_path(path) {
+        Some(fs_path) => {
+            let mut engine = WatchEngine::new()?;
+            engine.watch_path(&fs_path)?;
+            Some(engine)
+        }
+        None => None,
+    };
+    let mut events = engine.as_mut().and_then(WatchEngine::take_receiver);
+
+    loop {
+        match events.as_mut() {
+            Some(rx) => {
+                // A closed channel means the watcher stopped; fall back to polling.
+                if let Ok(None) = tokio::time::timeout(FOLLOW_POLL_INTERVAL, rx.recv()).await {
+                    events = None;
+                }
+            }
+            None => tokio::time::sleep(FOLLOW_POLL_INTERVAL).await,
+        }
+
+        let size = match vfs.stat(path).await {
+            Ok(entry) => entry.size.unwrap_or(0),
+            Err(_) => continue,
+        };
+        if size < offset {
+            eprintln!("tail: {}: file truncated", path);
+            offset = 0;
+        }
+        if size > offset {
+            let data = vfs.read_range(path, offset, Some(size - offset)).await?;
+            offset += data.len() as u64;
+            io::stdout().write_all(&data)?;
+            io::stdout().flush()?;
+        }
+    }
+}
@@ -4,6 +4,7 @@ use openfs_remote::Vfs;
 pub async fn run(
     vfs: &Vfs,
     format: Option<String>,
+    strict: bool,
     pretty: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let config = vfs.effective_config();
@@ -14,9 +15,14 @@ pub async fn run(
         .unwrap_or("json")
         .parse()
         .map_err(|e: String| e)?;
+    let tool_format = match (tool_format, strict) {
+        (ToolFormat::OpenAi, true) => ToolFormat::OpenAiStrict,
+        (_, true) => return Err("--strict only applies to --format openai".into()),
+        (format, false) => format,
+    };
 
     // Generate tools
-    let tools = generate_tools(config);
+    let tools = generate_tools(&config);
 
     // Format output
     let output = format_tools(&tools, tool_format);
@@ -30,3 +36,18 @@ pub async fn run(
 
     Ok(())
 }
+
+pub async fn run_call(
+    vfs: &Vfs,
+    tool: &str,
+    args: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args: serde_json::Value = match args {
+        Some(args) => serde_json::from_str(args)
+            .map_err(|e| format!("Tool arguments must be a JSON object: {}", e))?,
+        None => serde_json::Value::Null,
+    };
+    let result = vfs.execute_tool(tool, &args).await?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
@@ -1,13 +1,25 @@
 use openfs_remote::Vfs;
+use serde_json::Value;
+
+use crate::output::{entry_json, print_json, OutputFormat};
 
 pub async fn run(
     vfs: &Vfs,
     path: Option<String>,
     max_depth: Option<usize>,
+    output: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let path = path.as_deref().unwrap_or("/");
     let max_depth = max_depth.unwrap_or(usize::MAX);
 
+    if output.is_json() {
+        let tree = serde_json::json!({
+            "path": path,
+            "children": tree_json(vfs, path, 0, max_depth).await,
+        });
+        return print_json(&tree);
+    }
+
     println!("{}", path);
     print_tree(vfs, path, "", true, 0, max_depth).await?;
 
@@ -64,3 +76,30 @@ async fn print_tree(
 
     Ok(())
 }
+
+/// Entries below `path` as JSON, with `children` on each directory.
+#[async_recursion::async_recursion]
+async fn tree_json(vfs: &Vfs, path: &str, depth: usize, max_depth: usize) -> Vec<Value> {
+    if depth >= max_depth {
+        return Vec::new();
+    }
+    let Ok(entries) = vfs.list(path).await else {
+        return Vec::new();
+    };
+
+    let mut children = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let child_path = if path == "/" {
+            format!("/{}", entry.name)
+        } else {
+            format!("{}/{}", path, entry.name)
+        };
+        let mut value = entry_json(&child_path, &entry);
+        if entry.is_dir {
+            value["children"] =
+                Value::Array(tree_json(vfs, &child_path, depth + 1, max_depth).await);
+        }
+        children.push(value);
+    }
+    children
+}
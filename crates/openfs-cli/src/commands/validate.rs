@@ -1,9 +1,6 @@
-use std::path::Path;
-
 use openfs_config::VfsConfig;
 
-pub async fn run(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let config = VfsConfig::from_file(config_path)?;
+pub async fn run(config: VfsConfig) -> Result<(), Box<dyn std::error::Error>> {
     let errors = config.validate();
 
     if errors.is_empty() {
@@ -1,6 +1,8 @@
 use openfs_remote::{WalConfig, WriteAheadLog};
 use std::path::PathBuf;
 
+use crate::output::{print_json, OutputFormat};
+
 /// Run the WAL checkpoint command.
 pub async fn run_checkpoint(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
     let wal_paths = resolve_wal_paths(config_dir)?;
@@ -12,9 +14,16 @@ pub async fn run_checkpoint(config_dir: Option<PathBuf>) -> Result<(), Box<dyn s
     let mut total_pruned = 0usize;
     for wal_path in wal_paths {
         let wal = WriteAheadLog::new(&wal_path, WalConfig::default())?;
-        let pruned = wal.checkpoint()?;
-        total_pruned += pruned;
-        eprintln!("{}: {} entries pruned", wal_path.display(), pruned);
+        let report = wal.checkpoint()?;
+        total_pruned += report.pruned;
+        eprintln!(
+            "{}: {} entries pruned, {} -> {} in {:?}",
+            wal_path.display(),
+            report.pruned,
+            format_size(report.bytes_before),
+            format_size(report.bytes_after),
+            report.duration
+        );
     }
     eprintln!(
         "WAL checkpoint complete: {} total entries pruned",
@@ -24,9 +33,12 @@ pub async fn run_checkpoint(config_dir: Option<PathBuf>) -> Result<(), Box<dyn s
 }
 
 /// Run the WAL status command.
-pub async fn run_status(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_status(
+    config_dir: Option<PathBuf>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
     let wal_paths = resolve_wal_paths(config_dir)?;
-    if wal_paths.is_empty() {
+    if wal_paths.is_empty() && !output.is_json() {
         println!("WAL Status:\n  No WAL databases found.");
         return Ok(());
     }
@@ -35,8 +47,11 @@ pub async fn run_status(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::
     let mut total_pending = 0usize;
     let mut total_processing = 0usize;
     let mut total_failed = 0usize;
+    let mut databases = Vec::new();
 
-    println!("WAL Status:");
+    if !output.is_json() {
+        println!("WAL Status:");
+    }
     for wal_path in wal_paths {
         let wal = WriteAheadLog::new(
             &wal_path,
@@ -49,12 +64,52 @@ pub async fn run_status(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::
         let stats = wal.outbox_stats()?;
         let unapplied = wal.get_unapplied()?;
         let failed = wal.get_failed()?;
+        let checkpoints = wal.checkpoint_stats()?;
+        let size = wal.size_bytes()?;
 
         total_unapplied += unapplied.len();
         total_pending += stats.pending;
         total_processing += stats.processing;
         total_failed += stats.failed;
 
+        if output.is_json() {
+            let failed: Vec<_> = failed
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "id": entry.id,
+                        "op": entry.op_type.as_str(),
+                        "path": entry.path,
+                        "attempts": entry.attempts,
+                        "error": entry.error,
+                    })
+                })
+                .collect();
+            databases.push(serde_json::json!({
+                "path": wal_path.display().to_string(),
+                "unapplied": unapplied.len(),
+                "pending": stats.pending,
+                "processing": stats.processing,
+                "failed": stats.failed,
+                "failed_entries": failed,
+                "size_bytes": size,
+                "checkpoints": {
+                    "count": checkpoints.count,
+                    "mean_duration_ms": checkpoints.mean_duration().as_secs_f64() * 1000.0,
+                    "max_duration_ms": checkpoints.max_duration.as_secs_f64() * 1000.0,
+                    "last": checkpoints.last.as_ref().map(|last| serde_json::json!({
+                        "trigger": last.trigger.as_str(),
+                        "started_at": last.started_at,
+                        "duration_ms": last.duration.as_secs_f64() * 1000.0,
+                        "pruned": last.pruned,
+                        "bytes_before": last.bytes_before,
+                        "bytes_after": last.bytes_after,
+                    })),
+                },
+            }));
+            continue;
+        }
+
         println!(
             "  {}: unapplied {}, pending {}, processing {}, failed {}",
             wal_path.display(),
@@ -63,6 +118,18 @@ pub async fn run_status(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::
             stats.processing,
             stats.failed
         );
+        match &checkpoints.last {
+            Some(last) => println!(
+                "    size {}, last checkpoint ({}) took {:?}, {} checkpoints averaging {:?} (max {:?})",
+                format_size(size),
+                last.trigger.as_str(),
+                last.duration,
+                checkpoints.count,
+                checkpoints.mean_duration(),
+                checkpoints.max_duration
+            ),
+            None => println!("    size {}, never checkpointed", format_size(size)),
+        }
 
         if !failed.is_empty() {
             println!("    failed entries:");
@@ -79,6 +146,18 @@ pub async fn run_status(config_dir: Option<PathBuf>) -> Result<(), Box<dyn std::
         }
     }
 
+    if output.is_json() {
+        return print_json(&serde_json::json!({
+            "databases": databases,
+            "totals": {
+                "unapplied": total_unapplied,
+                "pending": total_pending,
+                "processing": total_processing,
+                "failed": total_failed,
+            },
+        }));
+    }
+
     println!();
     println!("Totals:");
     println!("  Unapplied entries: {}", total_unapplied);
@@ -144,3 +223,15 @@ fn resolve_wal_paths(
     paths.sort();
     Ok(paths)
 }
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1}G", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}K", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
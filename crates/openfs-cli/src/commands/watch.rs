@@ -5,7 +5,7 @@ use openfs_config::{VfsConfig, WatchConfig};
 use openfs_local::{
     IndexingPipeline, PipelineConfig, QueueEventType, WatchEngine, WorkQueue, WorkQueueConfig,
 };
-use openfs_remote::Vfs;
+use openfs_remote::{TrigramIndex, Vfs};
 use regex::Regex;
 
 #[derive(Clone)]
@@ -119,7 +119,7 @@ fn resolve_watch_settings(
     debounce_ms: Option<u64>,
 ) -> Result<ResolvedWatchSettings, Box<dyn std::error::Error>> {
     let effective = vfs.effective_config();
-    let watch_cfg = watch_config_for_path(effective, path);
+    let watch_cfg = watch_config_for_path(&effective, path);
 
     let interval_from_config = watch_cfg
         .and_then(|watch| watch.poll_interval.as_ref())
@@ -174,7 +174,23 @@ pub async fn run(
 
     // Set up work queue and pipeline if auto_index is enabled
     let mut indexer = if settings.auto_index {
-        Some(WatchIndexer::new(settings.debounce_ms)?)
+        let pipeline_config = match vfs
+            .effective_config()
+            .mount_for_path(&path)
+            .and_then(|m| Some((m.path.as_str(), m.index.as_ref()?)))
+        {
+            Some((mount_path, index)) => PipelineConfig::from_index_config(mount_path, index)?,
+            None => PipelineConfig::default(),
+        };
+        Some(WatchIndexer::new(settings.debounce_ms, pipeline_config)?)
+    } else {
+        None
+    };
+
+    // Changed files drop out of the trigram index `grep --index` builds.
+    let trigram_path = TrigramIndex::default_path()?;
+    let trigrams = if trigram_path.exists() {
+        Some(TrigramIndex::open(&trigram_path)?)
     } else {
         None
     };
@@ -197,6 +213,7 @@ pub async fn run(
             &path,
             fs_root,
             &mut indexer,
+            trigrams.as_ref(),
             settings.webhook.clone(),
             settings.filters.clone(),
         )
@@ -219,6 +236,7 @@ pub async fn run(
             &path,
             settings.interval_secs,
             &mut indexer,
+            trigrams.as_ref(),
             settings.webhook,
             settings.filters,
         )
@@ -236,7 +254,10 @@ struct WatchIndexer {
 }
 
 impl WatchIndexer {
-    fn new(debounce_ms: u64) -> Result<Self, Box<dyn std::error::Error>> {
+    fn new(
+        debounce_ms: u64,
+        pipeline_config: PipelineConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let queue_path = std::path::Path::new(".").join(".openfs_watch_queue.db");
         let debounce_secs = std::cmp::max(1, debounce_ms / 1000);
         let queue = WorkQueue::open(
@@ -256,13 +277,17 @@ impl WatchIndexer {
             Err(e) => eprintln!("Warning: failed to recover stuck items: {}", e),
         }
 
-        let pipeline = IndexingPipeline::new(PipelineConfig::default())?;
+        let pipeline = IndexingPipeline::new(pipeline_config)?;
 
         Ok(WatchIndexer { pipeline, queue })
     }
 
     /// Enqueue a file change event (non-blocking, just writes to SQLite).
     fn enqueue(&self, path: &str, change_kind: &str) {
+        // Size is checked again at index time; here only the globs apply.
+        if !self.pipeline.accepts(path, None) {
+            return;
+        }
         let event_type = match change_kind {
             "deleted" => QueueEventType::Deleted,
             _ => QueueEventType::Changed,
@@ -285,6 +310,16 @@ impl WatchIndexer {
         for item in items {
             match item.event_type {
                 QueueEventType::Changed => match vfs.read(&item.path).await {
+                    Ok(content)
+                        if !self
+                            .pipeline
+                            .accepts(&item.path, Some(content.len() as u64)) =>
+                    {
+                        eprintln!("  skipped: {} (exceeds index.max_file_size)", item.path);
+                        if let Err(e) = self.queue.complete(item.id) {
+                            eprintln!("  warning: failed to complete queue item: {}", e);
+                        }
+                    }
                     Ok(content) => match self.pipeline.index_file(&item.path, &content).await {
                         Ok(result) => {
                             eprintln!(
@@ -333,6 +368,7 @@ async fn run_native(
     vfs_path: &str,
     fs_root: &std::path::Path,
     indexer: &mut Option<WatchIndexer>,
+    trigrams: Option<&TrigramIndex>,
     webhook: Option<String>,
     filters: PathFilters,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -367,7 +403,8 @@ async fn run_native(
                 let time_str = chrono::Local::now().format("%H:%M:%S");
                 println!("[{}] {}: {}", time_str, change.kind, change_vfs_path);
 
-                handle_change(&change_vfs_path, &change.kind.to_string(), indexer, &webhook).await;
+                let kind = change.kind.to_string();
+                handle_change(&change_vfs_path, &kind, indexer, trigrams, &webhook).await;
             }
             // Process work queue every 500ms
             _ = tokio::time::sleep(Duration::from_millis(500)) => {
@@ -386,6 +423,7 @@ async fn run_polling(
     path: &str,
     interval_secs: u64,
     indexer: &mut Option<WatchIndexer>,
+    trigrams: Option<&TrigramIndex>,
     webhook: Option<String>,
     filters: PathFilters,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -415,11 +453,11 @@ async fn run_polling(
             if let Some((old_size, old_modified)) = file_states.get(file_path) {
                 if size != old_size || modified != old_modified {
                     println!("[{}] modified: {}", now, file_path);
-                    handle_change(file_path, "modified", indexer, &webhook).await;
+                    handle_change(file_path, "modified", indexer, trigrams, &webhook).await;
                 }
             } else {
                 println!("[{}] created: {}", now, file_path);
-                handle_change(file_path, "created", indexer, &webhook).await;
+                handle_change(file_path, "created", indexer, trigrams, &webhook).await;
             }
         }
 
@@ -427,7 +465,7 @@ async fn run_polling(
         for file_path in file_states.keys() {
             if !new_states.contains_key(file_path) {
                 println!("[{}] deleted: {}", now, file_path);
-                handle_change(file_path, "deleted", indexer, &webhook).await;
+                handle_change(file_path, "deleted", indexer, trigrams, &webhook).await;
             }
         }
 
@@ -444,6 +482,7 @@ async fn handle_change(
     path: &str,
     change_kind: &str,
     indexer: &mut Option<WatchIndexer>,
+    trigrams: Option<&TrigramIndex>,
     webhook: &Option<String>,
 ) {
     // Enqueue for indexing via work queue (non-blocking)
@@ -451,6 +490,12 @@ async fn handle_change(
         idx.enqueue(path, change_kind);
     }
 
+    if let Some(trigrams) = trigrams {
+        if let Err(e) = trigrams.invalidate(path) {
+            eprintln!("  trigram index: {}", e);
+        }
+    }
+
     // Webhook POST
     if let Some(ref url) = webhook {
         let url = url.clone();
@@ -1,13 +1,18 @@
 use std::error::Error;
 
+use openfs_core::{ErrorCode, VfsError};
+
 /// Print a user-friendly error message with optional hint.
-pub fn print_error(err: &dyn Error) {
+pub fn print_error(err: &(dyn Error + 'static)) {
     let msg = err.to_string();
 
     // Try to extract hint from the error message
     let (main_msg, hint) = extract_hint(&msg);
 
-    eprintln!("error: {}", main_msg);
+    match error_code(err) {
+        Some(code) => eprintln!("error[{}]: {}", code, main_msg),
+        None => eprintln!("error: {}", main_msg),
+    }
 
     if let Some(hint_msg) = hint {
         eprintln!("  hint: {}", hint_msg);
@@ -21,6 +26,18 @@ pub fn print_error(err: &dyn Error) {
     }
 }
 
+/// The stable code of the first [`VfsError`] in the error chain, if any.
+fn error_code(err: &(dyn Error + 'static)) -> Option<ErrorCode> {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if let Some(vfs_error) = e.downcast_ref::<VfsError>() {
+            return Some(vfs_error.code());
+        }
+        current = e.source();
+    }
+    None
+}
+
 /// Extract a hint from known error message patterns.
 fn extract_hint(msg: &str) -> (&str, Option<&str>) {
     // Check for actionable patterns in the message
@@ -1,12 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 use openfs_config::VfsConfig;
 use openfs_remote::Vfs;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 
 mod commands;
 mod errors;
+mod output;
+mod transfer;
 
 #[derive(Parser)]
 #[command(name = "openfs", version, about = "OpenFS - Virtual Filesystem")]
@@ -15,6 +17,14 @@ struct Cli {
     #[arg(short, long)]
     config: Option<PathBuf>,
 
+    /// Config profile to apply (overrides OPENFS_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Output format: text or json (ls, stat, tree, find, grep, search, dedupe, status, sync/wal status)
+    #[arg(long, global = true, default_value = "text", value_parser = output::OutputFormat::parse)]
+    output: output::OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -38,6 +48,38 @@ enum Commands {
         /// Content to write (reads from stdin if not provided)
         content: Option<String>,
     },
+    /// Print the first lines (or bytes) of a file
+    Head {
+        /// Path to the file
+        path: String,
+        /// Number of lines to print
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        /// Print the first N bytes instead of lines
+        #[arg(short = 'c', long)]
+        bytes: Option<u64>,
+    },
+    /// Print the last lines (or bytes) of a file
+    Tail {
+        /// Path to the file
+        path: String,
+        /// Number of lines to print
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        /// Print the last N bytes instead of lines
+        #[arg(short = 'c', long)]
+        bytes: Option<u64>,
+        /// Keep printing data as it is appended
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Apply a unified diff to a file
+    Patch {
+        /// Path to the file
+        path: String,
+        /// Patch file (reads from stdin if not provided)
+        patch: Option<PathBuf>,
+    },
     /// Append content to a file
     Append {
         /// Path to the file
@@ -60,12 +102,55 @@ enum Commands {
         /// Path to check
         path: String,
     },
-    /// Copy a file
+    /// Copy a file, or a directory tree with -r
     Cp {
         /// Source path
         src: String,
         /// Destination path
         dst: String,
+        /// Copy directories recursively
+        #[arg(short, long)]
+        recursive: bool,
+        #[command(flatten)]
+        transfer: transfer::TransferArgs,
+    },
+    /// Mirror a directory tree to another path or mount
+    SyncCopy {
+        /// Source directory
+        src: String,
+        /// Destination directory
+        dst: String,
+        /// Delete destination files that are not in the source
+        #[arg(long)]
+        delete: bool,
+        /// Show what would change without copying or deleting
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        /// Compare checksums instead of modification times
+        #[arg(short, long)]
+        checksum: bool,
+        #[command(flatten)]
+        transfer: transfer::TransferArgs,
+    },
+    /// Export a directory tree to a tar archive (gzip for .gz/.tgz)
+    Export {
+        /// Directory to export
+        path: String,
+        /// Archive file to write
+        #[arg(long)]
+        to: PathBuf,
+        #[command(flatten)]
+        transfer: transfer::TransferArgs,
+    },
+    /// Import a tar or tar.gz archive into a directory
+    Import {
+        /// Archive file to read
+        archive: PathBuf,
+        /// Destination directory
+        #[arg(long)]
+        to: String,
+        #[command(flatten)]
+        transfer: transfer::TransferArgs,
     },
     /// Move (rename) a file
     Mv {
@@ -82,8 +167,118 @@ enum Commands {
         #[arg(short, long)]
         depth: Option<usize>,
     },
+    /// Compare two files or directory trees
+    Diff {
+        /// First path
+        a: String,
+        /// Second path
+        b: String,
+        /// Lines of context in text diffs
+        #[arg(short = 'U', long, default_value_t = 3)]
+        unified: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show disk usage per directory
+    Du {
+        /// Path to summarize (defaults to /)
+        path: Option<String>,
+        /// Only print directories up to this depth below the path
+        #[arg(short = 'd', long)]
+        max_depth: Option<usize>,
+        /// Order subdirectories by 'name', 'size' or 'files'
+        #[arg(short, long)]
+        sort: Option<String>,
+    },
+    /// Report files with identical content
+    Dedupe {
+        /// Path to scan (defaults to /)
+        path: Option<String>,
+        /// Delete all but the first file (by path) of each duplicate set
+        #[arg(long)]
+        delete_keeping_first: bool,
+    },
+    /// Benchmark read/write/list/grep workloads against mounts
+    Bench {
+        /// Directories to benchmark, one per mount under test
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Comma-separated workloads: read, write, list, grep
+        #[arg(short, long, value_delimiter = ',', default_value = "write,read,list,grep", value_parser = commands::bench::Workload::parse)]
+        workloads: Vec<commands::bench::Workload>,
+        /// How long each workload runs (e.g. 10s, 500ms)
+        #[arg(short, long, default_value = "10s")]
+        duration: openfs_config::HumanDuration,
+        /// Concurrent workers per workload
+        #[arg(short, long, default_value_t = 8)]
+        concurrency: usize,
+        /// Object sizes with optional weights (e.g. 4kb:8,1mb:2)
+        #[arg(short, long, value_delimiter = ',', default_value = "4kb", value_parser = commands::bench::parse_size)]
+        sizes: Vec<(u64, u32)>,
+        /// Number of files in the working set
+        #[arg(short, long, default_value_t = 100)]
+        files: usize,
+        /// Keep the benchmark files afterwards
+        #[arg(long)]
+        keep: bool,
+    },
+    /// Start an interactive shell
+    Shell,
+    /// Print a shell completion script (bash, zsh, fish, elvish, powershell)
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// Print VFS paths completing a prefix (used by completion scripts)
+    #[command(hide = true)]
+    CompletePaths {
+        /// Partial path
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+    /// Generate roff man pages
+    Man {
+        /// Write one page per command to this directory instead of stdout
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Write a starter config file, prompting for the backend and first mount
+    Init {
+        /// Config file to create (.yaml, .toml or .json)
+        #[arg(default_value = "openfs.yaml")]
+        file: PathBuf,
+        /// Workspace name (defaults to the current directory's name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Backend template (fs, memory, s3, gcs, postgres)
+        #[arg(long, value_parser = commands::init::Template::parse)]
+        backend: Option<commands::init::Template>,
+        /// Directory (fs), bucket (s3, gcs) or connection URL variable (postgres)
+        #[arg(long)]
+        root: Option<String>,
+        /// VFS path of the first mount
+        #[arg(long)]
+        mount: Option<String>,
+        /// Enable search indexing on the mount
+        #[arg(long)]
+        index: bool,
+        /// Accept defaults instead of prompting
+        #[arg(short, long)]
+        yes: bool,
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
     /// Show effective configuration
-    Config,
+    #[command(args_conflicts_with_subcommands = true)]
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+        /// Output format (yaml, toml, json)
+        #[arg(long, default_value = "yaml", value_parser = openfs_config::ConfigFormat::parse)]
+        format: openfs_config::ConfigFormat,
+    },
     /// Find files by name pattern (regex)
     Find {
         /// Regex pattern to match file names
@@ -104,11 +299,22 @@ enum Commands {
         /// Search recursively in directories
         #[arg(short, long)]
         recursive: bool,
+        /// Skip files that cannot match using the trigram index in
+        /// .openfs/trigrams.db, indexing the files read
+        #[arg(long)]
+        index: bool,
     },
     /// Index files for semantic search
+    #[command(args_conflicts_with_subcommands = true)]
     Index {
+        #[command(subcommand)]
+        action: Option<IndexAction>,
         /// Path to index (file or directory)
         path: Option<String>,
+        /// Index every mount with index.enabled: true, each with its own
+        /// chunker, embedder and collection
+        #[arg(long, conflicts_with_all = ["path", "collection", "chunker", "chunk_size"])]
+        all: bool,
         /// Chroma endpoint URL (e.g., http://localhost:8000)
         #[arg(long)]
         chroma_endpoint: Option<String>,
@@ -130,11 +336,38 @@ enum Commands {
         /// Force full re-index, ignoring incremental state
         #[arg(long)]
         force: bool,
+        /// Store a per-file summary with the chunks (extractive, llm)
+        #[arg(long)]
+        summarizer: Option<String>,
+        /// Embedding requests sent at once (overrides index.embedding.concurrency)
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// Embedding requests allowed per minute
+        #[arg(long)]
+        requests_per_minute: Option<usize>,
+        /// Estimated embedding tokens allowed per minute
+        #[arg(long)]
+        tokens_per_minute: Option<usize>,
+    },
+    /// Run the background indexing daemon (watch mounts, index changes)
+    Indexd {
+        /// Only index mounts at or under this path (defaults to /)
+        path: Option<String>,
+        /// Chroma endpoint URL (e.g., http://localhost:8000)
+        #[arg(long)]
+        chroma_endpoint: Option<String>,
+        /// Directory for work queues, index state and status.json (defaults to .openfs/indexd)
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+        /// Seconds between status.json updates
+        #[arg(long, default_value = "5")]
+        status_interval: u64,
     },
     /// Semantic search in indexed files
     Search {
-        /// Search query
-        query: String,
+        /// Search query (optional with --interactive)
+        #[arg(required_unless_present = "interactive")]
+        query: Option<String>,
         /// Chroma endpoint URL (e.g., http://localhost:8000)
         #[arg(long)]
         chroma_endpoint: Option<String>,
@@ -147,9 +380,31 @@ enum Commands {
         /// Search mode (dense, sparse, hybrid)
         #[arg(short, long)]
         mode: Option<String>,
+        /// Hybrid weight of dense scores, 0.0-1.0; sparse scores get the rest
+        /// (implies --mode hybrid)
+        #[arg(long)]
+        alpha: Option<f32>,
+        /// Hybrid fusion method: weighted (score sum) or rrf (reciprocal rank)
+        /// (implies --mode hybrid)
+        #[arg(long)]
+        fusion: Option<String>,
+        /// Rescale scores before a weighted fusion: none or minmax
+        #[arg(long)]
+        normalize: Option<String>,
+        /// Only show chunks with this metadata, as key:value (e.g. lang:rust);
+        /// repeatable
+        #[arg(long = "filter", value_name = "KEY:VALUE")]
+        filters: Vec<String>,
         /// Number of context lines to show
         #[arg(short, long, default_value = "2")]
         context: Option<usize>,
+        /// Return file-level summaries instead of raw chunks
+        #[arg(long)]
+        summaries: bool,
+        /// Browse results as you type, with a preview pane and mount and
+        /// file type filters (a line prompt when not on a terminal)
+        #[arg(short, long, visible_alias = "tui", conflicts_with = "summaries")]
+        interactive: bool,
     },
     /// Show VFS status (mounts, backends, cache stats)
     Status,
@@ -173,20 +428,45 @@ enum Commands {
         #[arg(long)]
         debounce: Option<u64>,
     },
+    /// Manage read caches
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Manage sync behavior for write-back mounts
     Sync {
         #[command(subcommand)]
         action: SyncAction,
     },
+    /// Snapshot a mount and roll it back
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Tag files; tagged files are listed under /tags/<tag>/
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
     /// Generate tool definitions for AI agents
     Tools {
-        /// Output format (json, mcp, openai)
+        /// Output format (json, mcp, openai, anthropic, gemini)
         #[arg(short, long, default_value = "json")]
         format: Option<String>,
+        /// Emit OpenAI strict-mode schemas (with --format openai)
+        #[arg(long)]
+        strict: bool,
         /// Pretty-print output
         #[arg(short, long)]
         pretty: bool,
     },
+    /// Run a tool call from `openfs tools` and print its JSON result
+    Call {
+        /// Tool name, e.g. vfs_read
+        tool: String,
+        /// Arguments as a JSON object
+        args: Option<String>,
+    },
     /// Mount OpenFS as a FUSE filesystem
     #[cfg_attr(not(feature = "fuse"), command(hide = true))]
     Mount {
@@ -196,6 +476,20 @@ enum Commands {
         #[arg(short, long)]
         foreground: bool,
     },
+    /// Serve OpenFS over NFSv3 on localhost (no FUSE needed)
+    #[cfg_attr(not(feature = "nfs"), command(hide = true))]
+    ServeNfs {
+        /// TCP port for both the NFS and MOUNT protocols
+        #[arg(long, default_value_t = 11111)]
+        port: u16,
+    },
+    /// Serve the OpenFS gRPC API (openfs.v1.Vfs)
+    #[cfg_attr(not(feature = "grpc"), command(hide = true))]
+    ServeGrpc {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: std::net::SocketAddr,
+    },
     /// Unmount OpenFS FUSE filesystem
     Unmount {
         /// Mount point to unmount
@@ -215,7 +509,17 @@ enum Commands {
     /// Migrate configuration to current version
     Migrate,
     /// Run as an MCP (Model Context Protocol) server over stdio
-    Mcp,
+    Mcp {
+        /// Only expose tools that never modify files
+        #[arg(long)]
+        read_only: bool,
+        /// Comma-separated tool allowlist (e.g. read,ls,grep); overrides `mcp.tools`
+        #[arg(long, value_delimiter = ',')]
+        tools: Vec<String>,
+        /// Confine the session to this VFS path; overrides `mcp.root`
+        #[arg(long)]
+        root: Option<String>,
+    },
     /// Manage the Write-Ahead Log (WAL)
     Wal {
         #[command(subcommand)]
@@ -225,7 +529,7 @@ enum Commands {
 
 #[derive(Subcommand)]
 enum WalAction {
-    /// Checkpoint: prune old applied WAL entries
+    /// Checkpoint: prune old applied WAL entries and compact the database
     Checkpoint {
         /// Path to the directory containing the WAL database
         #[arg(long)]
@@ -239,12 +543,143 @@ enum WalAction {
     },
 }
 
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Inspect and reprocess files that repeatedly failed to index
+    Dlq {
+        #[command(subcommand)]
+        action: DlqAction,
+    },
+    /// Re-index files indexed with older chunker, embedder or summarizer settings
+    Migrate {
+        /// Only migrate files at or under this path (defaults to /)
+        path: Option<String>,
+        /// Chroma endpoint URL (e.g., http://localhost:8000)
+        #[arg(long)]
+        chroma_endpoint: Option<String>,
+        /// Collection name for storing vectors
+        #[arg(long)]
+        collection: Option<String>,
+        /// List the files that would be re-indexed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DlqAction {
+    /// List dead-lettered files with their last error
+    List {
+        /// Work queue database (defaults to the watch and indexd queues)
+        #[arg(long)]
+        queue: Option<PathBuf>,
+    },
+    /// Requeue dead-lettered files at user priority
+    Retry {
+        /// Path to retry
+        path: Option<String>,
+        /// Retry every dead-lettered file
+        #[arg(long)]
+        all: bool,
+        /// Work queue database (defaults to the watch and indexd queues)
+        #[arg(long)]
+        queue: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the JSON Schema for config files
+    Schema,
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Read files matching globs into the cache (e.g. '/workspace/src/**/*.rs')
+    Warm {
+        #[arg(required = true)]
+        patterns: Vec<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum SyncAction {
     /// Show sync and durable outbox status per mount
-    Status,
+    Status {
+        /// Also list the sync state of each written path
+        #[arg(short, long)]
+        verbose: bool,
+    },
     /// Flush write-back queues and replay durable outbox entries
     Flush,
+    /// Requeue writes that exhausted their retries and replay them
+    RetryFailed,
+    /// List write-back conflicts and how they were resolved
+    Conflicts {
+        /// Forget the recorded conflicts
+        #[arg(long)]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Record the current content of a mount
+    Create {
+        /// Mount path (e.g. /workspace)
+        mount: String,
+        /// Name to refer to the snapshot by
+        #[arg(short, long)]
+        label: Option<String>,
+    },
+    /// List the snapshots of a mount
+    List {
+        /// Mount path
+        mount: String,
+    },
+    /// Put a mount back as it was in a snapshot
+    Restore {
+        /// Mount path
+        mount: String,
+        /// Snapshot id or label
+        snapshot: String,
+    },
+    /// Show files changed since a snapshot, or between two snapshots
+    Diff {
+        /// Mount path
+        mount: String,
+        /// Snapshot id or label
+        from: String,
+        /// Snapshot to compare with instead of the current content
+        to: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    /// Add tags to a file
+    Add {
+        /// File path
+        path: String,
+        /// Tags to add
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove a tag from a file
+    Rm {
+        /// File path
+        path: String,
+        /// Tag to remove
+        tag: String,
+    },
+    /// List a file's tags, the files with a tag, or every tag in use
+    Ls {
+        /// File path
+        path: Option<String>,
+        /// List the files with this tag instead
+        #[arg(short, long, conflicts_with = "path")]
+        tag: Option<String>,
+    },
 }
 
 fn find_config() -> Option<PathBuf> {
@@ -256,24 +691,84 @@ fn find_config() -> Option<PathBuf> {
         }
     }
 
-    // 2. openfs.yaml in current directory
-    let cwd_config = PathBuf::from("openfs.yaml");
-    if cwd_config.exists() {
+    // 2. openfs.{yaml,toml,json} in current directory
+    if let Some(cwd_config) = first_existing(PathBuf::from("openfs")) {
         return Some(cwd_config);
     }
 
-    // 3. ~/.config/ax/config.yaml
-    if let Some(home) = dirs_next::home_dir() {
-        let home_config = home.join(".config/openfs/config.yaml");
-        if home_config.exists() {
-            return Some(home_config);
-        }
-    }
+    // 3. ~/.config/openfs/config.{yaml,toml,json}
+    dirs_next::home_dir().and_then(|home| first_existing(home.join(".config/openfs/config")))
+}
 
-    None
+/// The first of `<stem>.yaml`, `<stem>.toml` and `<stem>.json` that exists.
+fn first_existing(stem: PathBuf) -> Option<PathBuf> {
+    ["yaml", "toml", "json"]
+        .iter()
+        .map(|ext| stem.with_extension(ext))
+        .find(|path| path.exists())
 }
 
 async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let profile = cli
+        .profile
+        .clone()
+        .or_else(|| std::env::var("OPENFS_PROFILE").ok());
+    let profile = profile.as_deref();
+    let load_config = |path: &Path| VfsConfig::from_file_with_profile(path, profile);
+
+    // Commands that don't need a configuration
+    match &cli.command {
+        Commands::Completions { shell } => {
+            return commands::completions::run(*shell, Cli::command());
+        }
+        Commands::Man { out_dir } => {
+            return commands::man::run(Cli::command(), out_dir.as_deref());
+        }
+        Commands::Init {
+            file,
+            name,
+            backend,
+            root,
+            mount,
+            index,
+            yes,
+            force,
+        } => {
+            return commands::init::run(commands::init::InitArgs {
+                output: file.clone(),
+                name: name.clone(),
+                backend: *backend,
+                root: root.clone(),
+                mount: mount.clone(),
+                index: *index,
+                yes: *yes,
+                force: *force,
+            });
+        }
+        Commands::Config {
+            action: Some(ConfigAction::Schema),
+            ..
+        } => {
+            return commands::config::run_schema();
+        }
+        Commands::CompletePaths { prefix } => {
+            // Completion stays quiet when there is nothing to complete from.
+            let Some(config) = cli
+                .config
+                .clone()
+                .or_else(find_config)
+                .and_then(|path| load_config(&path).ok())
+            else {
+                return Ok(());
+            };
+            if let Ok(vfs) = Vfs::from_config(config).await {
+                let _ = commands::completions::complete_paths(&vfs, prefix).await;
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Find config file
     let config_path = cli
         .config
@@ -283,7 +778,7 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     // Commands that don't need a VFS (or create their own)
     match &cli.command {
         Commands::Validate => {
-            return commands::validate::run(&config_path).await;
+            return commands::validate::run(load_config(&config_path)?).await;
         }
         Commands::Migrate => {
             return commands::migrate::run(&config_path).await;
@@ -291,24 +786,72 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Commands::IndexStatus { state_file } => {
             return commands::index_status::run(state_file.clone()).await;
         }
-        Commands::Mcp => {
-            return commands::mcp::run(&config_path).await;
+        Commands::Mcp {
+            read_only,
+            tools,
+            root,
+        } => {
+            let args = commands::mcp::McpArgs {
+                read_only: *read_only,
+                tools: tools.clone(),
+                root: root.clone(),
+            };
+            return commands::mcp::run(load_config(&config_path)?, args).await;
         }
         Commands::Wal {
             action: WalAction::Checkpoint { dir },
         } => {
             return commands::wal::run_checkpoint(dir.clone()).await;
         }
+        Commands::Index {
+            action: Some(IndexAction::Dlq { action }),
+            ..
+        } => {
+            return match action {
+                DlqAction::List { queue } => commands::index_dlq::run_list(queue.clone()).await,
+                DlqAction::Retry { path, all, queue } => {
+                    commands::index_dlq::run_retry(path.clone(), *all, queue.clone()).await
+                }
+            };
+        }
+        Commands::Indexd {
+            path,
+            chroma_endpoint,
+            state_dir,
+            status_interval,
+        } => {
+            // The daemon owns its VFS for as long as it runs.
+            let config = load_config(&config_path)?;
+            let args = commands::indexd::IndexdArgs {
+                config_path: config_path.clone(),
+                profile: profile.map(str::to_string),
+                path: path.clone(),
+                chroma_endpoint: chroma_endpoint.clone(),
+                state_dir: state_dir.clone(),
+                status_interval_secs: *status_interval,
+            };
+            return commands::indexd::run(config, args).await;
+        }
         Commands::Wal {
             action: WalAction::Status { dir },
         } => {
-            return commands::wal::run_status(dir.clone()).await;
+            return commands::wal::run_status(dir.clone(), cli.output).await;
+        }
+        Commands::ServeNfs { port } => {
+            // The server drives its own runtime from blocking threads.
+            let config = load_config(&config_path)?;
+            let args = commands::serve_nfs::ServeNfsArgs { port: *port };
+            return tokio::task::block_in_place(|| commands::serve_nfs::run(config, args));
+        }
+        Commands::ServeGrpc { addr } => {
+            let args = commands::serve_grpc::ServeGrpcArgs { addr: *addr };
+            return commands::serve_grpc::run(load_config(&config_path)?, args).await;
         }
         _ => {}
     }
 
     // Load and parse config
-    let config = VfsConfig::from_file(&config_path)?;
+    let config = load_config(&config_path)?;
 
     // Create VFS
     let vfs = Vfs::from_config(config).await?;
@@ -317,15 +860,30 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let mut should_flush = false;
     match cli.command {
         Commands::Ls { path } => {
-            commands::ls::run(&vfs, path).await?;
+            commands::ls::run(&vfs, path, cli.output).await?;
         }
         Commands::Cat { path } => {
             commands::cat::run(&vfs, &path).await?;
         }
+        Commands::Head { path, lines, bytes } => {
+            commands::head::run(&vfs, &path, lines, bytes).await?;
+        }
+        Commands::Tail {
+            path,
+            lines,
+            bytes,
+            follow,
+        } => {
+            commands::tail::run(&vfs, &path, lines, bytes, follow).await?;
+        }
         Commands::Write { path, content } => {
             commands::write::run(&vfs, &path, content).await?;
             should_flush = true;
         }
+        Commands::Patch { path, patch } => {
+            commands::patch::run(&vfs, &path, patch).await?;
+            should_flush = true;
+        }
         Commands::Append { path, content } => {
             commands::append::run(&vfs, &path, content).await?;
             should_flush = true;
@@ -335,13 +893,46 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             should_flush = true;
         }
         Commands::Stat { path } => {
-            commands::stat::run(&vfs, &path).await?;
+            commands::stat::run(&vfs, &path, cli.output).await?;
         }
         Commands::Exists { path } => {
             commands::exists::run(&vfs, &path).await?;
         }
-        Commands::Cp { src, dst } => {
-            commands::cp::run(&vfs, &src, &dst).await?;
+        Commands::Cp {
+            src,
+            dst,
+            recursive,
+            transfer,
+        } => {
+            commands::cp::run(&vfs, &src, &dst, recursive, &transfer).await?;
+            should_flush = true;
+        }
+        Commands::SyncCopy {
+            src,
+            dst,
+            delete,
+            dry_run,
+            checksum,
+            transfer,
+        } => {
+            let args = commands::sync_copy::SyncCopyArgs {
+                delete,
+                dry_run,
+                checksum,
+                transfer,
+            };
+            commands::sync_copy::run(&vfs, &src, &dst, args).await?;
+            should_flush = !dry_run;
+        }
+        Commands::Export { path, to, transfer } => {
+            commands::export::run(&vfs, &path, &to, &transfer).await?;
+        }
+        Commands::Import {
+            archive,
+            to,
+            transfer,
+        } => {
+            commands::import::run(&vfs, &archive, &to, &transfer).await?;
             should_flush = true;
         }
         Commands::Mv { src, dst } => {
@@ -349,27 +940,77 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             should_flush = true;
         }
         Commands::Tree { path, depth } => {
-            commands::tree::run(&vfs, path, depth).await?;
+            commands::tree::run(&vfs, path, depth, cli.output).await?;
+        }
+        Commands::Diff {
+            a,
+            b,
+            unified,
+            json,
+        } => {
+            commands::diff::run(&vfs, &a, &b, unified, json).await?;
+        }
+        Commands::Du {
+            path,
+            max_depth,
+            sort,
+        } => {
+            commands::du::run(&vfs, path, max_depth, sort).await?;
+        }
+        Commands::Dedupe {
+            path,
+            delete_keeping_first,
+        } => {
+            commands::dedupe::run(&vfs, path, delete_keeping_first, cli.output).await?;
+            should_flush = delete_keeping_first;
+        }
+        Commands::Bench {
+            paths,
+            workloads,
+            duration,
+            concurrency,
+            sizes,
+            files,
+            keep,
+        } => {
+            let args = commands::bench::BenchArgs {
+                workloads,
+                duration: duration.as_duration(),
+                concurrency,
+                sizes,
+                files,
+                keep,
+                output: cli.output,
+            };
+            commands::bench::run(&vfs, &paths, args).await?;
+            should_flush = keep;
+        }
+        Commands::Shell => {
+            commands::shell::run(&vfs).await?;
+            should_flush = true;
         }
-        Commands::Config => {
-            commands::config::run(&vfs).await?;
+        Commands::Config { format, .. } => {
+            commands::config::run(&vfs, format).await?;
         }
         Commands::Find {
             pattern,
             path,
             file_type,
         } => {
-            commands::find::run(&vfs, path, &pattern, file_type).await?;
+            commands::find::run(&vfs, path, &pattern, file_type, cli.output).await?;
         }
         Commands::Grep {
             pattern,
             path,
             recursive,
+            index,
         } => {
-            commands::grep::run(&vfs, &pattern, path, recursive).await?;
+            commands::grep::run(&vfs, &pattern, path, recursive, index, cli.output).await?;
         }
         Commands::Index {
+            action: None,
             path,
+            all,
             chroma_endpoint,
             collection,
             recursive,
@@ -377,9 +1018,12 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             chunk_size,
             incremental,
             force,
+            summarizer,
+            concurrency,
+            requests_per_minute,
+            tokens_per_minute,
         } => {
-            commands::index::run(
-                &vfs,
+            let args = commands::index::IndexArgs {
                 path,
                 chroma_endpoint,
                 collection,
@@ -388,8 +1032,31 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 chunk_size,
                 incremental,
                 force,
-            )
-            .await?;
+                summarizer,
+                concurrency,
+                requests_per_minute,
+                tokens_per_minute,
+                all,
+            };
+            commands::index::run(&vfs, args).await?;
+        }
+        Commands::Index {
+            action:
+                Some(IndexAction::Migrate {
+                    path,
+                    chroma_endpoint,
+                    collection,
+                    dry_run,
+                }),
+            ..
+        } => {
+            let args = commands::index::MigrateArgs {
+                path,
+                chroma_endpoint,
+                collection,
+                dry_run,
+            };
+            commands::index::run_migrate(&vfs, args).await?;
         }
         Commands::Search {
             query,
@@ -397,21 +1064,33 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             collection,
             limit,
             mode,
+            alpha,
+            fusion,
+            normalize,
+            filters,
             context,
+            summaries,
+            interactive,
         } => {
-            commands::search::run(
-                &vfs,
-                &query,
+            let args = commands::search::SearchArgs {
+                query,
                 chroma_endpoint,
                 collection,
                 limit,
                 mode,
-                context,
-            )
-            .await?;
+                alpha,
+                fusion,
+                normalize,
+                filters,
+                context_lines: context,
+                summaries,
+                interactive,
+                output: cli.output,
+            };
+            commands::search::run(&vfs, args).await?;
         }
         Commands::Status => {
-            commands::status::run(&vfs).await?;
+            commands::status::run(&vfs, cli.output).await?;
         }
         Commands::Watch {
             path,
@@ -423,23 +1102,69 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         } => {
             commands::watch::run(&vfs, path, interval, poll, auto_index, webhook, debounce).await?;
         }
+        Commands::Cache { action } => match action {
+            CacheAction::Warm { patterns } => {
+                commands::cache::run_warm(&vfs, &patterns, cli.output).await?;
+            }
+        },
         Commands::Sync { action } => match action {
-            SyncAction::Status => {
-                commands::sync::run_status(&vfs).await?;
+            SyncAction::Status { verbose } => {
+                commands::sync::run_status(&vfs, verbose, cli.output).await?;
             }
             SyncAction::Flush => {
                 commands::sync::run_flush(&vfs).await?;
             }
+            SyncAction::RetryFailed => {
+                commands::sync::run_retry_failed(&vfs).await?;
+            }
+            SyncAction::Conflicts { clear } => {
+                commands::sync::run_conflicts(&vfs, clear, cli.output)?;
+            }
         },
-        Commands::Tools { format, pretty } => {
-            commands::tools::run(&vfs, format, pretty).await?;
+        Commands::Snapshot { action } => match action {
+            SnapshotAction::Create { mount, label } => {
+                commands::snapshot::run_create(&vfs, &mount, label.as_deref(), cli.output).await?;
+            }
+            SnapshotAction::List { mount } => {
+                commands::snapshot::run_list(&vfs, &mount, cli.output)?;
+            }
+            SnapshotAction::Restore { mount, snapshot } => {
+                commands::snapshot::run_restore(&vfs, &mount, &snapshot, cli.output).await?;
+                should_flush = true;
+            }
+            SnapshotAction::Diff { mount, from, to } => {
+                commands::snapshot::run_diff(&vfs, &mount, &from, to.as_deref(), cli.output)
+                    .await?;
+            }
+        },
+        Commands::Tag { action } => match action {
+            TagAction::Add { path, tags } => {
+                commands::tag::run_add(&vfs, &path, &tags, cli.output).await?;
+            }
+            TagAction::Rm { path, tag } => {
+                commands::tag::run_rm(&vfs, &path, &tag, cli.output).await?;
+            }
+            TagAction::Ls { path, tag } => {
+                commands::tag::run_ls(&vfs, path.as_deref(), tag.as_deref(), cli.output).await?;
+            }
+        },
+        Commands::Tools {
+            format,
+            strict,
+            pretty,
+        } => {
+            commands::tools::run(&vfs, format, strict, pretty).await?;
+        }
+        Commands::Call { tool, args } => {
+            commands::tools::run_call(&vfs, &tool, args.as_deref()).await?;
+            should_flush = true;
         }
         Commands::Mount {
             mountpoint,
             foreground,
         } => {
             // Mount doesn't use the already-created VFS instance; it creates its own.
-            let config = VfsConfig::from_file(&config_path)?;
+            let config = load_config(&config_path)?;
             let args = commands::mount::MountArgs {
                 mountpoint,
                 foreground,
@@ -453,13 +1178,24 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Commands::IndexStatus { state_file } => {
             commands::index_status::run(state_file).await?;
         }
-        Commands::Mcp => {
-            commands::mcp::run(&config_path).await?;
-        }
         // These are handled above before VFS creation; this path is logically
         // unreachable due to the early return, but we return an error instead of
         // panicking if it's ever reached due to a code change.
-        Commands::Validate | Commands::Migrate | Commands::Wal { .. } => {
+        Commands::Validate
+        | Commands::Migrate
+        | Commands::Completions { .. }
+        | Commands::CompletePaths { .. }
+        | Commands::Man { .. }
+        | Commands::Init { .. }
+        | Commands::Mcp { .. }
+        | Commands::ServeNfs { .. }
+        | Commands::ServeGrpc { .. }
+        | Commands::Wal { .. }
+        | Commands::Index {
+            action: Some(IndexAction::Dlq { .. }),
+            ..
+        }
+        | Commands::Indexd { .. } => {
             return Err("Internal error: command should have been handled earlier".into());
         }
     }
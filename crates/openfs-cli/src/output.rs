@@ -0,0 +1,108 @@
+//! Machine-readable output selected with the global `--output` flag.
+
+use openfs_core::Entry;
+use openfs_remote::{MountSyncStatus, PathSyncState, PathSyncStatus, SyncMode};
+use serde_json::{json, Value};
+
+/// Output format for commands that support `--output json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// Pretty-printed JSON.
+    Json,
+}
+
+impl OutputFormat {
+    /// Parse an `--output` value.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("unknown output format '{}' (text, json)", other)),
+        }
+    }
+
+    /// Whether JSON output was requested.
+    pub fn is_json(self) -> bool {
+        self == OutputFormat::Json
+    }
+}
+
+/// Print a JSON value to stdout.
+pub fn print_json(value: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// JSON for a directory entry at `path`.
+pub fn entry_json(path: &str, entry: &Entry) -> Value {
+    json!({
+        "path": path,
+        "name": entry.name,
+        "is_dir": entry.is_dir,
+        "size": entry.size,
+        "modified": entry.modified.map(|m| m.to_rfc3339()),
+        "content_type": entry.content_type,
+    })
+}
+
+/// JSON for a mount's sync status.
+pub fn sync_status_json(status: &MountSyncStatus) -> Value {
+    let outbox = match (
+        status.outbox_pending,
+        status.outbox_processing,
+        status.outbox_failed,
+        status.outbox_wal_unapplied,
+    ) {
+        (Some(pending), Some(processing), Some(failed), Some(wal_unapplied)) => json!({
+            "pending": pending,
+            "processing": processing,
+            "failed": failed,
+            "wal_unapplied": wal_unapplied,
+        }),
+        _ => Value::Null,
+    };
+    json!({
+        "mount_path": status.mount_path,
+        "backend": status.backend_name,
+        "sync_mode": sync_mode_label(status.sync_mode),
+        "read_only": status.read_only,
+        "pending": status.pending,
+        "synced": status.synced,
+        "failed": status.failed,
+        "retries": status.retries,
+        "coalesced": status.coalesced,
+        "dead_lettered": status.dead_lettered,
+        "conflicts": status.conflicts,
+        "outbox": outbox,
+    })
+}
+
+/// JSON for one path's sync status.
+pub fn path_sync_status_json(status: &PathSyncStatus) -> Value {
+    let (reason, retrying) = match &status.state {
+        PathSyncState::Failed { reason, retrying } => (Some(reason.as_str()), *retrying),
+        _ => (None, false),
+    };
+    json!({
+        "path": status.path,
+        "state": status.state.as_str(),
+        "reason": reason,
+        "retrying": retrying,
+        "attempts": status.attempts,
+        "last_synced": status.last_synced.map(|t| t.to_rfc3339()),
+    })
+}
+
+/// Stable name for a sync mode.
+pub fn sync_mode_label(mode: SyncMode) -> &'static str {
+    match mode {
+        SyncMode::None => "none",
+        SyncMode::WriteThrough => "write-through",
+        SyncMode::WriteBack => "write-back",
+        SyncMode::PullMirror => "pull-mirror",
+        SyncMode::TwoWay => "two-way",
+    }
+}
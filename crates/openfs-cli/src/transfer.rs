@@ -0,0 +1,207 @@
+//! Bounded-parallel transfers with retries, a bandwidth limit and progress
+//! bars, shared by `cp -r`, `sync-copy`, `export` and `import`.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use openfs_config::{BackoffStrategy, HumanBytes};
+use openfs_core::VfsError;
+use openfs_remote::sync::compute_backoff;
+use openfs_remote::Vfs;
+
+/// Base delay before retrying a failed item.
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Transfer flags shared by commands that move many files.
+#[derive(Debug, Clone, clap::Args)]
+pub struct TransferArgs {
+    /// Maximum concurrent transfers
+    #[arg(short, long, default_value_t = 8)]
+    pub jobs: usize,
+    /// Retries per file after a transient error
+    #[arg(long, default_value_t = 3)]
+    pub retries: u32,
+    /// Bandwidth limit in bytes per second (e.g. 10mb)
+    #[arg(long)]
+    pub bwlimit: Option<HumanBytes>,
+    /// Hide progress bars
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+/// One unit of work for [`Transfer::run`].
+#[derive(Debug, Clone)]
+pub enum TransferOp {
+    /// Copy a file of `size` bytes between two VFS paths.
+    Copy { src: String, dst: String, size: u64 },
+    /// Write content to a VFS path.
+    Write { dst: String, content: Vec<u8> },
+    /// Delete a VFS path.
+    Delete { path: String },
+}
+
+/// Runs transfers and reports progress on stderr.
+pub struct Transfer {
+    jobs: usize,
+    retries: u32,
+    limiter: Option<RateLimiter>,
+    bar: ProgressBar,
+    files: AtomicUsize,
+}
+
+impl Transfer {
+    /// Create a transfer. With `total_bytes` the bar shows completion,
+    /// otherwise a running byte count.
+    pub fn new(args: &TransferArgs, total_bytes: Option<u64>) -> Self {
+        let bar = if args.no_progress {
+            ProgressBar::hidden()
+        } else {
+            match total_bytes {
+                Some(total) => ProgressBar::new(total).with_style(
+                    ProgressStyle::with_template(
+                        "{spinner} [{wide_bar}] {bytes}/{total_bytes} {bytes_per_sec} {msg}",
+                    )
+                    .expect("valid progress template")
+                    .progress_chars("=> "),
+                ),
+                None => ProgressBar::new_spinner().with_style(
+                    ProgressStyle::with_template("{spinner} {bytes} {bytes_per_sec} {msg}")
+                        .expect("valid progress template"),
+                ),
+            }
+        };
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Transfer {
+            jobs: args.jobs.max(1),
+            retries: args.retries,
+            limiter: args
+                .bwlimit
+                .as_ref()
+                .filter(|limit| limit.as_bytes() > 0)
+                .map(|limit| RateLimiter::new(limit.as_bytes())),
+            bar,
+            files: AtomicUsize::new(0),
+        }
+    }
+
+    /// Run `ops` with bounded parallelism.
+    ///
+    /// `on_done` receives each op's index and the bytes moved (or the error
+    /// after retries) as it finishes, with the progress bar suspended so it
+    /// can print.
+    pub async fn run(
+        &self,
+        vfs: &Vfs,
+        ops: &[TransferOp],
+        mut on_done: impl FnMut(usize, Result<u64, VfsError>),
+    ) {
+        let mut outcomes = stream::iter(ops.iter().enumerate().map(|(i, op)| async move {
+            let result = self.retry(|| self.execute(vfs, op)).await;
+            (i, result)
+        }))
+        .buffer_unordered(self.jobs);
+
+        while let Some((i, result)) = outcomes.next().await {
+            if let Ok(bytes) = &result {
+                self.advance(*bytes);
+            }
+            self.bar.suspend(|| on_done(i, result));
+        }
+    }
+
+    /// Read a file with retries, counting it against the bandwidth limit.
+    pub async fn read(&self, vfs: &Vfs, path: &str) -> Result<Vec<u8>, VfsError> {
+        let content = self.retry(|| vfs.read(path)).await?;
+        self.throttle(content.len() as u64).await;
+        Ok(content)
+    }
+
+    /// Record a finished file of `bytes` on the progress bar.
+    pub fn advance(&self, bytes: u64) {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bar.inc(bytes);
+        self.bar.set_message(format!("{} files", files));
+    }
+
+    /// Remove the progress bar.
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+
+    async fn execute(&self, vfs: &Vfs, op: &TransferOp) -> Result<u64, VfsError> {
+        match op {
+            TransferOp::Copy { src, dst, .. } => {
+                let content = vfs.read(src).await?;
+                self.throttle(content.len() as u64).await;
+                vfs.write(dst, &content).await?;
+                Ok(content.len() as u64)
+            }
+            TransferOp::Write { dst, content } => {
+                self.throttle(content.len() as u64).await;
+                vfs.write(dst, content).await?;
+                Ok(content.len() as u64)
+            }
+            TransferOp::Delete { path } => vfs.delete(path).await.map(|_| 0),
+        }
+    }
+
+    /// Retry `attempt` with exponential backoff while it fails transiently.
+    async fn retry<T, F, Fut>(&self, mut attempt: F) -> Result<T, VfsError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, VfsError>>,
+    {
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Err(e) if e.is_transient() && retries < self.retries => {
+                    tokio::time::sleep(compute_backoff(
+                        RETRY_BACKOFF,
+                        retries,
+                        BackoffStrategy::Exponential,
+                    ))
+                    .await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn throttle(&self, bytes: u64) {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(bytes).await;
+        }
+    }
+}
+
+/// Paces transfers to an average of `bytes_per_sec` since creation.
+struct RateLimiter {
+    bytes_per_sec: u64,
+    start: Instant,
+    sent: Mutex<u64>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec,
+            start: Instant::now(),
+            sent: Mutex::new(0),
+        }
+    }
+
+    /// Wait until sending `bytes` more keeps within the limit.
+    async fn acquire(&self, bytes: u64) {
+        let due = {
+            let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+            *sent += bytes;
+            Duration::from_secs_f64(*sent as f64 / self.bytes_per_sec as f64)
+        };
+        tokio::time::sleep_until((self.start + due).into()).await;
+    }
+}
@@ -99,6 +99,7 @@ fn infer_indexing(path: &str) -> IndexConfig {
                 ..Default::default()
             }),
             embedding: Some(EmbeddingConfig::default()),
+            ..Default::default()
         };
     }
 
@@ -114,6 +115,7 @@ fn infer_indexing(path: &str) -> IndexConfig {
                 ..Default::default()
             }),
             embedding: Some(EmbeddingConfig::default()),
+            ..Default::default()
         };
     }
 
@@ -124,6 +126,7 @@ fn infer_indexing(path: &str) -> IndexConfig {
             search_modes: vec![],
             chunk: None,
             embedding: None,
+            ..Default::default()
         };
     }
 
@@ -133,6 +136,7 @@ fn infer_indexing(path: &str) -> IndexConfig {
         search_modes: vec![SearchMode::Dense],
         chunk: Some(ChunkConfig::default()),
         embedding: Some(EmbeddingConfig::default()),
+        ..Default::default()
     }
 }
 
@@ -165,6 +169,11 @@ mod tests {
                 index: None,
                 sync: None,
                 watch: None,
+                cache: None,
+                log: None,
+                path_policy: None,
+                retry: None,
+                replicas: None,
             }],
             ..Default::default()
         };
@@ -0,0 +1,158 @@
+//! Compiled per-mount index policies.
+//!
+//! [`IndexFilter`] turns the include/exclude globs and size limit from a
+//! mount's `index:` section into a matcher that indexers can apply to VFS
+//! paths.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::types::{IndexConfig, MountConfig};
+use crate::ConfigError;
+
+/// Decides which files under a mount are indexed.
+#[derive(Debug, Clone)]
+pub struct IndexFilter {
+    mount_path: String,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    max_file_size: Option<u64>,
+}
+
+impl IndexFilter {
+    /// Compile the policy for files under `mount_path`.
+    pub fn new(mount_path: &str, index: &IndexConfig) -> Result<Self, ConfigError> {
+        let include = if index.include.is_empty() {
+            None
+        } else {
+            Some(build_globset(mount_path, "include", &index.include)?)
+        };
+        let exclude = build_globset(mount_path, "exclude", &index.exclude)?;
+
+        Ok(IndexFilter {
+            mount_path: format!("/{}", mount_path.trim_matches('/'))
+                .trim_end_matches('/')
+                .to_string(),
+            include,
+            exclude,
+            max_file_size: index.max_file_size.as_ref().map(|b| b.as_bytes()),
+        })
+    }
+
+    /// Compile the policy for a mount, if it has an `index:` section.
+    pub fn for_mount(mount: &MountConfig) -> Result<Option<Self>, ConfigError> {
+        mount
+            .index
+            .as_ref()
+            .map(|index| IndexFilter::new(&mount.path, index))
+            .transpose()
+    }
+
+    /// The mount this filter applies to.
+    pub fn mount_path(&self) -> &str {
+        &self.mount_path
+    }
+
+    /// Whether `path` (a full VFS path) should be indexed.
+    ///
+    /// Paths outside the mount never match. `size` is checked against
+    /// `max_file_size` when known.
+    pub fn matches(&self, path: &str, size: Option<u64>) -> bool {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        let path = path.as_str();
+        let relative = if self.mount_path.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&self.mount_path) {
+                Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+                _ => return false,
+            }
+        };
+        let relative = relative.trim_start_matches('/');
+
+        if let (Some(limit), Some(size)) = (self.max_file_size, size) {
+            if size > limit {
+                return false;
+            }
+        }
+        if let Some(include) = &self.include {
+            if !include.is_match(relative) {
+                return false;
+            }
+        }
+        !self.exclude.is_match(relative)
+    }
+}
+
+fn build_globset(context: &str, field: &str, patterns: &[String]) -> Result<GlobSet, ConfigError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            ConfigError::InvalidConfig(format!(
+                "{}.index.{}: invalid glob '{}': {}",
+                context, field, pattern, e
+            ))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| ConfigError::InvalidConfig(format!("{}.index.{}: {}", context, field, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::HumanBytes;
+
+    fn policy(include: &[&str], exclude: &[&str]) -> IndexConfig {
+        IndexConfig {
+            enabled: true,
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_policy_matches_everything_under_mount() {
+        let filter = IndexFilter::new("/workspace", &policy(&[], &[])).unwrap();
+        assert!(filter.matches("/workspace/src/main.rs", None));
+        assert!(filter.matches("/workspace/README.md", Some(10)));
+        assert!(!filter.matches("/workspace2/main.rs", None));
+        assert!(!filter.matches("/other/main.rs", None));
+        assert!(filter.matches("workspace/src/main.rs", None));
+    }
+
+    #[test]
+    fn test_root_mount() {
+        let filter = IndexFilter::new("/", &policy(&["*.md"], &[])).unwrap();
+        assert!(filter.matches("/README.md", None));
+        assert!(!filter.matches("/main.rs", None));
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let filter =
+            IndexFilter::new("/workspace", &policy(&["**/*.rs", "*.md"], &["target/**"])).unwrap();
+        assert!(filter.matches("/workspace/src/lib.rs", None));
+        assert!(filter.matches("/workspace/README.md", None));
+        assert!(!filter.matches("/workspace/target/debug/build.rs", None));
+        assert!(!filter.matches("/workspace/src/data.json", None));
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        let mut index = policy(&[], &[]);
+        index.max_file_size = Some(HumanBytes(1024));
+        let filter = IndexFilter::new("/docs", &index).unwrap();
+        assert!(filter.matches("/docs/a.txt", Some(1024)));
+        assert!(!filter.matches("/docs/b.txt", Some(1025)));
+        assert!(filter.matches("/docs/c.txt", None));
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        let err = IndexFilter::new("/workspace", &policy(&["src/[*.rs"], &[])).unwrap_err();
+        assert!(err.to_string().contains("/workspace.index.include"));
+    }
+}
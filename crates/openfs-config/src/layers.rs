@@ -0,0 +1,343 @@
+//! Config composition: `include:` files and `profiles:` overlays.
+//!
+//! Documents are merged in a fixed order: each included file (in the order
+//! listed, with its own includes resolved first), then the including file,
+//! then the selected profile. Mappings merge key by key, later values win,
+//! and `mounts` entries merge by `path`. Any other value, including a list,
+//! replaces the earlier one.
+
+use std::path::{Path, PathBuf};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{env, ConfigError, ConfigFormat, VfsConfig};
+
+/// Parse a config document, resolving includes relative to `base_dir` and
+/// applying `profile` if one is given.
+pub(crate) fn parse(
+    input: &str,
+    format: ConfigFormat,
+    base_dir: &Path,
+    profile: Option<&str>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<VfsConfig, ConfigError> {
+    let interpolated = env::interpolate_env(input)?;
+    let document = parse_value(&interpolated, format)?;
+    if profile.is_none() && !is_layered(&document) {
+        // Parse the text itself so errors keep their line and column.
+        return parse_typed(&interpolated, format);
+    }
+
+    let document = resolve_includes(document, base_dir, stack)?;
+    let document = apply_profile(document, profile)?;
+    // The merged document has no line numbers, so name the key path instead.
+    serde_path_to_error::deserialize(document).map_err(|e| match e.path().to_string() {
+        path if path == "." => ConfigError::InvalidConfig(e.inner().to_string()),
+        path => ConfigError::InvalidConfig(format!("{}: {}", path, e.inner())),
+    })
+}
+
+/// Whether the config file at `path` uses `include:` or `profiles:`.
+pub fn has_layers(path: &Path) -> Result<bool, ConfigError> {
+    let content = std::fs::read_to_string(path)?;
+    let document = parse_value(&content, ConfigFormat::from_path(path))?;
+    Ok(is_layered(&document))
+}
+
+fn parse_value(input: &str, format: ConfigFormat) -> Result<Value, ConfigError> {
+    Ok(match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(input)?,
+        ConfigFormat::Toml => toml::from_str(input)?,
+        ConfigFormat::Json => serde_json::from_str(input)?,
+    })
+}
+
+fn parse_typed(input: &str, format: ConfigFormat) -> Result<VfsConfig, ConfigError> {
+    Ok(match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(input)?,
+        ConfigFormat::Toml => toml::from_str(input)?,
+        ConfigFormat::Json => serde_json::from_str(input)?,
+    })
+}
+
+fn is_layered(document: &Value) -> bool {
+    document.get("include").is_some() || document.get("profiles").is_some()
+}
+
+/// Replace `include:` with the merged contents of the listed files.
+fn resolve_includes(
+    document: Value,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, ConfigError> {
+    let Value::Mapping(mut mapping) = document else {
+        return Ok(document);
+    };
+    let includes = match mapping.remove("include") {
+        None => Vec::new(),
+        Some(Value::String(path)) => vec![path],
+        Some(Value::Sequence(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => Ok(path),
+                _ => Err(ConfigError::InvalidConfig(
+                    "include: entries must be file paths".to_string(),
+                )),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(ConfigError::InvalidConfig(
+                "include: must be a path or a list of paths".to_string(),
+            ))
+        }
+    };
+
+    let mut merged = Value::Mapping(Mapping::new());
+    for include in includes {
+        let path = base_dir.join(&include);
+        let path = path.canonicalize().map_err(|e| {
+            ConfigError::InvalidConfig(format!("include '{}': {}", path.display(), e))
+        })?;
+        if stack.contains(&path) {
+            return Err(ConfigError::InvalidConfig(format!(
+                "include cycle through '{}'",
+                path.display()
+            )));
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let interpolated = env::interpolate_env(&content)?;
+        let included = parse_value(&interpolated, ConfigFormat::from_path(&path))?;
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        stack.push(path);
+        let included = resolve_includes(included, &dir, stack)?;
+        stack.pop();
+        merge_document(&mut merged, included);
+    }
+    merge_document(&mut merged, Value::Mapping(mapping));
+    Ok(merged)
+}
+
+/// Drop `profiles:` and merge the selected profile over the document.
+fn apply_profile(document: Value, profile: Option<&str>) -> Result<Value, ConfigError> {
+    let Value::Mapping(mut mapping) = document else {
+        return Ok(document);
+    };
+    let profiles = mapping.remove("profiles");
+    let mut document = Value::Mapping(mapping);
+
+    let Some(name) = profile else {
+        return Ok(document);
+    };
+    let overlay = profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(name))
+        .cloned()
+        .ok_or_else(|| {
+            let available: Vec<&str> = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.as_mapping())
+                .map(|profiles| profiles.keys().filter_map(|k| k.as_str()).collect())
+                .unwrap_or_default();
+            let available = if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            };
+            ConfigError::UnknownProfile(name.to_string(), available)
+        })?;
+    if is_layered(&overlay) {
+        return Err(ConfigError::InvalidConfig(format!(
+            "profiles.{}: a profile cannot contain include or profiles",
+            name
+        )));
+    }
+
+    merge_document(&mut document, overlay);
+    Ok(document)
+}
+
+/// Merge a whole config document: like [`merge`], but `mounts` merge by path.
+fn merge_document(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                let is_mounts = key.as_str() == Some("mounts");
+                match (base.get_mut(&key), value) {
+                    (Some(Value::Sequence(mounts)), Value::Sequence(overlay)) if is_mounts => {
+                        merge_mounts(mounts, overlay)
+                    }
+                    (Some(existing), value) => merge(existing, value),
+                    (None, value) => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => merge(base, overlay),
+    }
+}
+
+/// A mount whose `path` is already present merges into it; others are appended.
+fn merge_mounts(mounts: &mut Vec<Value>, overlay: Vec<Value>) {
+    for mount in overlay {
+        let existing = mount.get("path").and_then(|path| {
+            mounts
+                .iter_mut()
+                .find(|m| m.get("path").is_some_and(|p| p == path))
+        });
+        match existing {
+            Some(existing) => merge(existing, mount),
+            None => mounts.push(mount),
+        }
+    }
+}
+
+/// Deep-merge mappings; anything else is replaced by `overlay`.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BackendConfig;
+    use tempfile::TempDir;
+
+    fn load(dir: &TempDir, file: &str, profile: Option<&str>) -> Result<VfsConfig, ConfigError> {
+        VfsConfig::from_file_with_profile(&dir.path().join(file), profile)
+    }
+
+    #[test]
+    fn test_include_shares_backends() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("shared")).unwrap();
+        std::fs::write(
+            dir.path().join("shared/backends.toml"),
+            r#"
+[backends.docs]
+type = "fs"
+root = "/srv/docs"
+
+[backends.local]
+type = "fs"
+root = "/srv/default"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("openfs.yaml"),
+            r#"
+include: [shared/backends.toml]
+backends:
+  local:
+    root: ./data
+mounts:
+  - path: /docs
+    backend: docs
+"#,
+        )
+        .unwrap();
+
+        let config = load(&dir, "openfs.yaml", None).unwrap();
+        let roots: Vec<(&str, &str)> = config
+            .backends
+            .iter()
+            .map(|(name, backend)| match backend {
+                BackendConfig::Fs(fs) => (name.as_str(), fs.root.as_str()),
+                _ => panic!("expected fs backend"),
+            })
+            .collect();
+        assert_eq!(roots, vec![("docs", "/srv/docs"), ("local", "./data")]);
+        assert_eq!(config.mounts.len(), 1);
+    }
+
+    #[test]
+    fn test_profile_overrides() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("openfs.yaml"),
+            r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+profiles:
+  prod:
+    backends:
+      local:
+        root: /srv/data
+    mounts:
+      - path: /workspace
+        read_only: true
+      - path: /archive
+        backend: local
+"#,
+        )
+        .unwrap();
+
+        let dev = load(&dir, "openfs.yaml", None).unwrap();
+        assert_eq!(dev.mounts.len(), 1);
+        assert!(!dev.mounts[0].read_only);
+
+        let prod = load(&dir, "openfs.yaml", Some("prod")).unwrap();
+        match &prod.backends["local"] {
+            BackendConfig::Fs(fs) => assert_eq!(fs.root, "/srv/data"),
+            _ => panic!("expected fs backend"),
+        }
+        assert_eq!(prod.mounts.len(), 2);
+        assert!(prod.mounts[0].read_only);
+        assert_eq!(prod.mounts[0].backend.as_deref(), Some("local"));
+        assert_eq!(prod.mounts[1].path, "/archive");
+
+        let err = load(&dir, "openfs.yaml", Some("staging")).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::UnknownProfile(ref name, ref available)
+            if name == "staging" && available == "prod")
+        );
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.yaml"), "include: b.yaml\n").unwrap();
+        std::fs::write(dir.path().join("b.yaml"), "include: a.yaml\n").unwrap();
+
+        let err = load(&dir, "a.yaml", None).unwrap_err();
+        assert!(err.to_string().contains("include cycle"), "{}", err);
+    }
+
+    #[test]
+    fn test_merged_result_rejects_unknown_keys() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("shared.yaml"),
+            "mounts:\n  - path: /a\n    read_onyl: true\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("openfs.yaml"), "include: shared.yaml\n").unwrap();
+
+        let err = load(&dir, "openfs.yaml", None).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("mounts[0].read_onyl: unknown field"),
+            "{}",
+            err
+        );
+    }
+}
@@ -1,12 +1,20 @@
 mod defaults;
 mod env;
+pub mod index_policy;
+mod layers;
 pub mod migration;
+pub mod secrets;
 pub mod types;
 mod validation;
+pub mod view_policy;
 
 use std::path::Path;
 
+pub use index_policy::IndexFilter;
+pub use layers::has_layers;
+pub use secrets::{SecretProvider, SecretProviders};
 pub use types::*;
+pub use view_policy::ViewFilter;
 
 /// Configuration errors.
 #[derive(Debug, thiserror::Error)]
@@ -17,6 +25,21 @@ pub enum ConfigError {
     #[error("Failed to parse YAML: {0}")]
     YamlError(#[from] serde_yaml::Error),
 
+    #[error("Failed to parse TOML: {0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("Failed to parse JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Failed to serialize config as {0}: {1}")]
+    SerializeError(ConfigFormat, String),
+
+    #[error("Unknown profile '{0}' (available: {1})")]
+    UnknownProfile(String, String),
+
+    #[error("Secret '{0}': {1}")]
+    SecretError(String, String),
+
     #[error("Missing environment variables: {0:?}")]
     MissingEnvVars(Vec<String>),
 
@@ -36,23 +59,172 @@ pub enum ConfigError {
     InvalidConfig(String),
 }
 
+/// Serialization format of a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Parse a format name (`yaml`, `toml` or `json`).
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            other => Err(format!(
+                "unknown config format '{}' (yaml, toml, json)",
+                other
+            )),
+        }
+    }
+
+    /// The format implied by a file's extension. Anything else is YAML.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| Self::parse(&ext.to_ascii_lowercase()).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigFormat::Yaml => write!(f, "yaml"),
+            ConfigFormat::Toml => write!(f, "toml"),
+            ConfigFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
 impl VfsConfig {
     /// Parse a VFS configuration from a YAML string.
     /// Environment variables in the format `${VAR_NAME}` will be interpolated.
     pub fn from_yaml(yaml: &str) -> Result<Self, ConfigError> {
-        // First, interpolate environment variables
-        let interpolated = env::interpolate_env(yaml)?;
+        Self::parse(yaml, ConfigFormat::Yaml)
+    }
+
+    /// Parse a VFS configuration from a TOML string, interpolating `${VAR_NAME}`.
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        Self::parse(toml, ConfigFormat::Toml)
+    }
 
-        // Then parse the YAML
-        let config: VfsConfig = serde_yaml::from_str(&interpolated)?;
+    /// Parse a VFS configuration from a JSON string, interpolating `${VAR_NAME}`.
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        Self::parse(json, ConfigFormat::Json)
+    }
 
+    /// Parse a VFS configuration in the given format.
+    ///
+    /// `include:` paths are resolved against the current directory, and
+    /// `profiles:` are dropped. Use [`VfsConfig::from_file_with_profile`] to
+    /// select a profile.
+    pub fn parse(input: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        let mut config = layers::parse(input, format, Path::new("."), None, &mut Vec::new())?;
+        SecretProviders::default().resolve_config(&mut config)?;
         Ok(config)
     }
 
-    /// Load a VFS configuration from a file.
+    /// Load a VFS configuration from a file. `.toml` and `.json` files are
+    /// parsed as such; anything else is read as YAML.
     pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_file_with_profile(path, None)
+    }
+
+    /// Load a VFS configuration from a file, merge its `include:` files
+    /// (relative to the file) and apply the named profile from `profiles:`.
+    pub fn from_file_with_profile(path: &Path, profile: Option<&str>) -> Result<Self, ConfigError> {
+        Self::from_file_with_secrets(path, profile, Some(&SecretProviders::default()))
+    }
+
+    /// Load a VFS configuration from a file like
+    /// [`VfsConfig::from_file_with_profile`], resolving `${scheme:...}` secret
+    /// references with `secrets`. `None` leaves references in place, for
+    /// tools that write the config back.
+    pub fn from_file_with_secrets(
+        path: &Path,
+        profile: Option<&str>,
+        secrets: Option<&SecretProviders>,
+    ) -> Result<Self, ConfigError> {
         let content = std::fs::read_to_string(path)?;
-        Self::from_yaml(&content)
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let mut stack: Vec<_> = path.canonicalize().into_iter().collect();
+        let mut config = layers::parse(
+            &content,
+            ConfigFormat::from_path(path),
+            base_dir,
+            profile,
+            &mut stack,
+        )?;
+        if let Some(secrets) = secrets {
+            secrets.resolve_config(&mut config)?;
+        }
+        Ok(config)
+    }
+
+    /// Serialize the configuration in the given format.
+    pub fn to_string_as(&self, format: ConfigFormat) -> Result<String, ConfigError> {
+        let serialize_error =
+            |e: &dyn std::fmt::Display| ConfigError::SerializeError(format, e.to_string());
+        match format {
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| serialize_error(&e)),
+            ConfigFormat::Toml => toml::to_string_pretty(self).map_err(|e| serialize_error(&e)),
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| serialize_error(&e))
+            }
+        }
+    }
+
+    /// JSON Schema for config files, for editor completion and CI checks.
+    ///
+    /// Like the parser, the schema rejects unknown keys. It also allows the
+    /// `include:` and `profiles:` keys that are merged away at load time.
+    pub fn schema() -> serde_json::Value {
+        let mut schema = schemars::schema_for!(VfsConfig);
+        if let Some(properties) = schema
+            .get_mut("properties")
+            .and_then(|properties| properties.as_object_mut())
+        {
+            properties.insert(
+                "include".to_string(),
+                serde_json::json!({
+                    "description": "Config files to merge in before this one, relative to this file.",
+                    "anyOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ]
+                }),
+            );
+            properties.insert(
+                "profiles".to_string(),
+                serde_json::json!({
+                    "description": "Named overlays, selected with --profile or OPENFS_PROFILE.",
+                    "type": "object",
+                    "additionalProperties": { "type": "object" }
+                }),
+            );
+        }
+        schema.to_value()
+    }
+
+    /// Find the mount that owns `path` (longest matching mount prefix).
+    pub fn mount_for_path(&self, path: &str) -> Option<&MountConfig> {
+        let path = format!("/{}", path.trim_matches('/'));
+        self.mounts
+            .iter()
+            .filter(|m| {
+                let mount = m.path.trim_end_matches('/');
+                mount.is_empty()
+                    || path == mount
+                    || path
+                        .strip_prefix(mount)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|m| m.path.trim_end_matches('/').len())
     }
 }
 
@@ -146,4 +318,170 @@ mounts:
         let errors = config.validate();
         assert!(errors.is_empty());
     }
+
+    #[test]
+    fn test_mount_for_path() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+  - path: /workspace/docs
+    backend: local
+"#;
+
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let path_of = |p: &str| config.mount_for_path(p).map(|m| m.path.as_str());
+        assert_eq!(path_of("/workspace/src/main.rs"), Some("/workspace"));
+        assert_eq!(path_of("/workspace/docs/a.md"), Some("/workspace/docs"));
+        assert_eq!(path_of("/workspace"), Some("/workspace"));
+        assert_eq!(path_of("/workspace2/a.md"), None);
+    }
+
+    #[test]
+    fn test_parse_toml_and_json() {
+        let toml = r#"
+name = "my-workspace"
+
+[backends.local]
+type = "fs"
+root = "./data"
+
+[[mounts]]
+path = "/workspace"
+backend = "local"
+read_only = true
+"#;
+        let json = r#"{
+  "name": "my-workspace",
+  "backends": { "local": { "type": "fs", "root": "./data" } },
+  "mounts": [{ "path": "/workspace", "backend": "local", "read_only": true }]
+}"#;
+
+        for config in [
+            VfsConfig::from_toml(toml).unwrap(),
+            VfsConfig::from_json(json).unwrap(),
+        ] {
+            assert_eq!(config.name, Some("my-workspace".to_string()));
+            assert_eq!(config.mounts[0].path, "/workspace");
+            assert!(config.mounts[0].read_only);
+            assert!(matches!(config.backends["local"], BackendConfig::Fs(_)));
+        }
+        assert!(matches!(
+            VfsConfig::from_toml("mounts = 3"),
+            Err(ConfigError::TomlError(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("openfs.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a/openfs.JSON")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("openfs.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("openfs")),
+            ConfigFormat::Yaml
+        );
+        assert!(ConfigFormat::parse("ini").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_formats() {
+        let yaml = r#"
+name: agent
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+    collection: workspace
+    index:
+      include: ["**/*.rs"]
+      max_file_size: 1mb
+      schedule: on_change
+    watch:
+      debounce: 500ms
+  - path: /scratch
+    backend: local
+    read_only: true
+    sync:
+      interval: 30s
+      write_mode: async
+mcp:
+  profile: read_only
+"#;
+        let effective = VfsConfig::from_yaml(yaml).unwrap().effective();
+        let expected = serde_json::to_value(&effective).unwrap();
+
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = effective.to_string_as(format).unwrap();
+            let parsed = VfsConfig::parse(&text, format).unwrap();
+            assert_eq!(
+                serde_json::to_value(&parsed).unwrap(),
+                expected,
+                "{} round trip",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys_report_location() {
+        let yaml = "mounts:\n  - path: /workspace\n    read_onyl: true\n";
+        let err = VfsConfig::from_yaml(yaml).unwrap_err().to_string();
+        assert!(err.contains("read_onyl"), "{}", err);
+        assert!(err.contains("line 3 column 5"), "{}", err);
+
+        let toml = "[[mounts]]\npath = \"/workspace\"\nread_onyl = true\n";
+        let err = VfsConfig::from_toml(toml).unwrap_err().to_string();
+        assert!(
+            err.contains("read_onyl") && err.contains("line 3"),
+            "{}",
+            err
+        );
+
+        let json = r#"{"mounts": [{"path": "/workspace", "read_onyl": true}]}"#;
+        let err = VfsConfig::from_json(json).unwrap_err().to_string();
+        assert!(
+            err.contains("read_onyl") && err.contains("column"),
+            "{}",
+            err
+        );
+
+        // Backend settings are checked against their own type.
+        let yaml = "backends:\n  local:\n    type: fs\n    rot: ./data\n";
+        let err = VfsConfig::from_yaml(yaml).unwrap_err().to_string();
+        assert!(err.contains("rot"), "{}", err);
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = VfsConfig::schema();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in [
+            "backends", "mounts", "defaults", "mcp", "include", "profiles",
+        ] {
+            assert!(properties.contains_key(key), "missing {}", key);
+        }
+        assert_eq!(schema["additionalProperties"], false);
+
+        let mount = &schema["$defs"]["MountConfig"];
+        assert_eq!(mount["additionalProperties"], false);
+        assert_eq!(mount["required"], serde_json::json!(["path"]));
+        assert_eq!(schema["$defs"]["HumanDuration"]["type"], "string");
+    }
 }
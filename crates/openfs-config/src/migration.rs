@@ -60,6 +60,11 @@ mod tests {
                 index: None,
                 sync: None,
                 watch: None,
+                cache: None,
+                log: None,
+                path_policy: None,
+                retry: None,
+                replicas: None,
             }],
             ..Default::default()
         }
@@ -0,0 +1,402 @@
+//! Secret provider references.
+//!
+//! A secret field (an API key, password or connection URL) can name where its
+//! value lives instead of holding it: `${vault:secret/openfs#s3_key}`,
+//! `${aws-sm:openfs/prod}` or `${file:/run/secrets/s3_key}`. References are
+//! resolved when the config is loaded, through the [`SecretProvider`]
+//! registered for their scheme, and the result stays wrapped in [`Secret`].
+//!
+//! Only whole-value references in secret fields are resolved. A reference
+//! anywhere else is an error, so a secret can't end up in a field that is
+//! logged or printed.
+
+use std::process::Command;
+
+use regex::Regex;
+
+use crate::types::{BackendConfig, Secret, VfsConfig};
+use crate::ConfigError;
+
+/// Fetches secrets for one `${scheme:reference}` scheme.
+pub trait SecretProvider: Send + Sync {
+    /// The scheme handled, e.g. `file` for `${file:/run/secrets/x}`.
+    fn scheme(&self) -> &str;
+
+    /// Fetch the secret named by `reference` (the text after `scheme:`).
+    fn resolve(&self, reference: &str) -> Result<Secret, String>;
+}
+
+/// The providers consulted when a config is loaded.
+///
+/// The default set handles `env`, `file`, `vault` and `aws-sm`. `vault` and
+/// `aws-sm` run the `vault` and `aws` CLIs, so they use whatever credentials
+/// those are configured with.
+pub struct SecretProviders {
+    providers: Vec<Box<dyn SecretProvider>>,
+}
+
+impl Default for SecretProviders {
+    fn default() -> Self {
+        SecretProviders::new()
+            .with_provider(EnvProvider)
+            .with_provider(FileProvider)
+            .with_provider(VaultProvider)
+            .with_provider(AwsSecretsManagerProvider)
+    }
+}
+
+impl SecretProviders {
+    /// An empty registry. Any reference in the config is an error.
+    pub fn new() -> Self {
+        SecretProviders {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Add a provider, replacing any existing one for the same scheme.
+    pub fn with_provider(mut self, provider: impl SecretProvider + 'static) -> Self {
+        self.providers.retain(|p| p.scheme() != provider.scheme());
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Resolve every secret reference in `config`.
+    pub fn resolve_config(&self, config: &mut VfsConfig) -> Result<(), ConfigError> {
+        for (location, secret) in secret_fields(config) {
+            let Some((scheme, reference)) = parse_reference(secret.expose()) else {
+                continue;
+            };
+            let provider = self
+                .providers
+                .iter()
+                .find(|p| p.scheme() == scheme)
+                .ok_or_else(|| {
+                    ConfigError::SecretError(
+                        location.clone(),
+                        format!("no secret provider for '{}'", scheme),
+                    )
+                })?;
+            *secret = provider
+                .resolve(&reference)
+                .map_err(|e| ConfigError::SecretError(location, e))?;
+        }
+
+        // Secret fields are resolved, so a reference left over sits in a
+        // field that would expose it.
+        let value = serde_json::to_value(&*config)?;
+        let mut stray = Vec::new();
+        self.find_references(&value, String::new(), &mut stray);
+        match stray.into_iter().next() {
+            Some(location) => Err(ConfigError::SecretError(
+                location,
+                "secret references are only allowed in secret fields".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn find_references(&self, value: &serde_json::Value, path: String, out: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some((scheme, _)) = parse_reference(s) {
+                    if self.providers.iter().any(|p| p.scheme() == scheme) {
+                        out.push(path);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.find_references(item, format!("{}[{}]", path, i), out);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (key, item) in map {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    self.find_references(item, path, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Split `${scheme:reference}` into its parts.
+fn parse_reference(value: &str) -> Option<(String, String)> {
+    let re = Regex::new(r"^\$\{([a-z][a-z0-9-]*):([^}]+)\}$").unwrap();
+    let caps = re.captures(value.trim())?;
+    Some((caps[1].to_string(), caps[2].to_string()))
+}
+
+/// Every secret field in the config, with its location for error messages.
+fn secret_fields(config: &mut VfsConfig) -> Vec<(String, &mut Secret)> {
+    let mut fields = Vec::new();
+    for (name, backend) in config.backends.iter_mut() {
+        let location = |field: &str| format!("backends.{}.{}", name, field);
+        match backend {
+            BackendConfig::S3(s3) => {
+                if let Some(secret) = s3.access_key_id.as_mut() {
+                    fields.push((location("access_key_id"), secret));
+                }
+                if let Some(secret) = s3.secret_access_key.as_mut() {
+                    fields.push((location("secret_access_key"), secret));
+                }
+            }
+            BackendConfig::Postgres(pg) => {
+                fields.push((location("connection_url"), &mut pg.connection_url));
+            }
+            BackendConfig::Chroma(chroma) => {
+                if let Some(secret) = chroma.api_key.as_mut() {
+                    fields.push((location("api_key"), secret));
+                }
+            }
+            BackendConfig::Fs(_) | BackendConfig::Memory(_) => {}
+        }
+    }
+    fields
+}
+
+/// `${env:VAR}`: an environment variable.
+pub struct EnvProvider;
+
+impl SecretProvider for EnvProvider {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<Secret, String> {
+        std::env::var(reference)
+            .map(Secret::new)
+            .map_err(|_| format!("environment variable {} is not set", reference))
+    }
+}
+
+/// `${file:/run/secrets/x}`: a file's contents, without the trailing newline.
+pub struct FileProvider;
+
+impl SecretProvider for FileProvider {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<Secret, String> {
+        let content = std::fs::read_to_string(reference)
+            .map_err(|e| format!("failed to read {}: {}", reference, e))?;
+        Ok(Secret::new(content.trim_end_matches(['\n', '\r'])))
+    }
+}
+
+/// `${vault:path#field}`: a field of a Vault KV secret, read with
+/// `vault kv get`. The field defaults to `value`.
+pub struct VaultProvider;
+
+impl SecretProvider for VaultProvider {
+    fn scheme(&self) -> &str {
+        "vault"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<Secret, String> {
+        run_cli("vault", &vault_args(reference)).map(Secret::new)
+    }
+}
+
+fn vault_args(reference: &str) -> Vec<String> {
+    let (path, field) = reference.split_once('#').unwrap_or((reference, "value"));
+    vec![
+        "kv".to_string(),
+        "get".to_string(),
+        format!("-field={}", field),
+        path.to_string(),
+    ]
+}
+
+/// `${aws-sm:name}` or `${aws-sm:name#key}`: an AWS Secrets Manager secret,
+/// read with `aws secretsmanager`. With `#key`, the secret is parsed as a JSON
+/// object and that key is used.
+pub struct AwsSecretsManagerProvider;
+
+impl SecretProvider for AwsSecretsManagerProvider {
+    fn scheme(&self) -> &str {
+        "aws-sm"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<Secret, String> {
+        let (name, key) = match reference.split_once('#') {
+            Some((name, key)) => (name, Some(key)),
+            None => (reference, None),
+        };
+        let args = [
+            "secretsmanager",
+            "get-secret-value",
+            "--secret-id",
+            name,
+            "--query",
+            "SecretString",
+            "--output",
+            "text",
+        ]
+        .map(String::from);
+        let value = run_cli("aws", &args)?;
+        match key {
+            None => Ok(Secret::new(value)),
+            Some(key) => json_key(&value, key).map(Secret::new),
+        }
+    }
+}
+
+fn json_key(value: &str, key: &str) -> Result<String, String> {
+    let object: serde_json::Value =
+        serde_json::from_str(value).map_err(|_| "secret is not a JSON object".to_string())?;
+    match object.get(key) {
+        Some(serde_json::Value::String(s)) => Ok(s.clone()),
+        Some(other) => Ok(other.to_string()),
+        None => Err(format!("secret has no key '{}'", key)),
+    }
+}
+
+/// Run a provider CLI and return its trimmed stdout. Stderr is reported on
+/// failure; stdout never is, since it may hold the secret.
+fn run_cli(program: &str, args: &[String]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\n', '\r'])
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider;
+
+    impl SecretProvider for StaticProvider {
+        fn scheme(&self) -> &str {
+            "vault"
+        }
+
+        fn resolve(&self, reference: &str) -> Result<Secret, String> {
+            match reference {
+                "secret/openfs#s3_key" => Ok(Secret::new("AKIA123")),
+                other => Err(format!("no secret at {}", other)),
+            }
+        }
+    }
+
+    const S3_CONFIG: &str = r#"
+backends:
+  s3:
+    type: s3
+    bucket: data
+    access_key_id: ${vault:secret/openfs#s3_key}
+    secret_access_key: ${file:SECRET_FILE}
+mounts:
+  - path: /data
+    backend: s3
+"#;
+
+    /// Load the S3 config with its references left in place.
+    fn s3_config(dir: &tempfile::TempDir, yaml: &str) -> VfsConfig {
+        let secret_file = dir.path().join("s3_secret");
+        std::fs::write(&secret_file, "shh\n").unwrap();
+        let yaml = yaml.replace("SECRET_FILE", &secret_file.display().to_string());
+        let path = dir.path().join("openfs.yaml");
+        std::fs::write(&path, yaml).unwrap();
+        VfsConfig::from_file_with_secrets(&path, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_with_registered_providers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = s3_config(&dir, S3_CONFIG);
+
+        let providers = SecretProviders::default().with_provider(StaticProvider);
+        providers.resolve_config(&mut config).unwrap();
+        match &config.backends["s3"] {
+            BackendConfig::S3(s3) => {
+                assert_eq!(s3.access_key_id.as_ref().unwrap().expose(), "AKIA123");
+                assert_eq!(s3.secret_access_key.as_ref().unwrap().expose(), "shh");
+            }
+            _ => panic!("expected s3 backend"),
+        }
+    }
+
+    #[test]
+    fn test_unresolvable_references() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        // No provider for the scheme.
+        let mut config = s3_config(&dir, S3_CONFIG);
+        let err = SecretProviders::new()
+            .with_provider(FileProvider)
+            .resolve_config(&mut config)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Secret 'backends.s3.access_key_id': no secret provider for 'vault'"
+        );
+
+        // The provider fails.
+        let yaml = S3_CONFIG.replace("secret/openfs#s3_key", "secret/other");
+        let mut config = s3_config(&dir, &yaml);
+        let err = SecretProviders::default()
+            .with_provider(StaticProvider)
+            .resolve_config(&mut config)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("no secret at secret/other"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_reference_outside_secret_field() {
+        let yaml = "backends:\n  local:\n    type: fs\n    root: ${file:/etc/hostname}\n";
+        let err = VfsConfig::from_yaml(yaml).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Secret 'backends.local.root': secret references are only allowed in secret fields"
+        );
+    }
+
+    #[test]
+    fn test_env_reference() {
+        std::env::set_var("OPENFS_TEST_CHROMA_KEY", "ck-123");
+        let yaml = "backends:\n  chroma:\n    type: chroma\n    url: http://localhost:8000\n    api_key: ${env:OPENFS_TEST_CHROMA_KEY}\n";
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        match &config.backends["chroma"] {
+            BackendConfig::Chroma(chroma) => {
+                assert_eq!(chroma.api_key.as_ref().unwrap().expose(), "ck-123")
+            }
+            _ => panic!("expected chroma backend"),
+        }
+    }
+
+    #[test]
+    fn test_provider_arguments() {
+        assert_eq!(
+            vault_args("secret/openfs#s3_key"),
+            ["kv", "get", "-field=s3_key", "secret/openfs"]
+        );
+        assert_eq!(
+            vault_args("secret/openfs"),
+            ["kv", "get", "-field=value", "secret/openfs"]
+        );
+        assert_eq!(json_key(r#"{"user":"a","pass":"b"}"#, "pass").unwrap(), "b");
+        assert!(json_key("plain", "pass").is_err());
+    }
+}
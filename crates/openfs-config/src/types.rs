@@ -1,4 +1,5 @@
 use indexmap::IndexMap;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 use std::str::FromStr;
@@ -6,7 +7,7 @@ use std::str::FromStr;
 /// A wrapper type for sensitive values (API keys, passwords, connection strings)
 /// that redacts the value in `Debug` and `Display` output to prevent accidental
 /// logging of credentials.
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct Secret(String);
 
@@ -52,7 +53,7 @@ impl From<&str> for Secret {
 }
 
 /// Mount mode determines how data flows between local and remote.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum MountMode {
@@ -71,10 +72,12 @@ pub enum MountMode {
     RemoteCached,
     /// One-way sync from remote
     PullMirror,
+    /// Buffer writes locally and pull remote changes periodically
+    TwoWay,
 }
 
 /// Search mode for queries.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum SearchMode {
@@ -89,8 +92,30 @@ pub enum SearchMode {
     Metadata,
 }
 
+/// How hybrid search combines dense and sparse results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionMethod {
+    /// Weighted sum of the dense and sparse scores
+    #[default]
+    Weighted,
+    /// Reciprocal rank fusion, which only counts the order of each list
+    Rrf,
+}
+
+/// How dense and sparse scores are rescaled before a weighted sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreNormalization {
+    /// Scores as returned by the store
+    #[default]
+    None,
+    /// Each list rescaled to 0-1 by its lowest and highest score
+    MinMax,
+}
+
 /// Write synchronization mode.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum WriteMode {
@@ -102,7 +127,7 @@ pub enum WriteMode {
 }
 
 /// Retry backoff strategy.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum BackoffStrategy {
@@ -116,7 +141,7 @@ pub enum BackoffStrategy {
 }
 
 /// Chunking strategy for text splitting.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum ChunkStrategy {
@@ -134,7 +159,7 @@ pub enum ChunkStrategy {
 }
 
 /// Chunking granularity for code.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum ChunkGranularity {
@@ -150,7 +175,7 @@ pub enum ChunkGranularity {
 }
 
 /// Embedding provider.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum EmbeddingProvider {
@@ -163,6 +188,8 @@ pub enum EmbeddingProvider {
     SentenceTransformers,
     /// Voyage AI
     VoyageAi,
+    /// Google Gemini (AI Studio or Vertex AI)
+    Gemini,
 }
 
 /// Human-readable duration (e.g., "200ms", "5m", "1h").
@@ -257,6 +284,20 @@ impl<'de> Deserialize<'de> for HumanDuration {
     }
 }
 
+impl JsonSchema for HumanDuration {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "HumanDuration".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^\\s*[0-9]+\\s*([mM][sS]|[sSmMhHdD])\\s*$",
+            "description": "A duration such as \"200ms\", \"5m\" or \"1h\"."
+        })
+    }
+}
+
 /// Human-readable bytes (e.g., "512mb", "2gb").
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct HumanBytes(pub u64);
@@ -334,20 +375,34 @@ impl<'de> Deserialize<'de> for HumanBytes {
     }
 }
 
+impl JsonSchema for HumanBytes {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "HumanBytes".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^\\s*[0-9]+\\s*([kKmMgGtT]?[bB])?\\s*$",
+            "description": "A size such as \"512mb\" or \"2gb\"."
+        })
+    }
+}
+
 /// Local filesystem backend configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct FsBackendConfig {
     pub root: String,
 }
 
 /// In-memory backend configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(deny_unknown_fields)]
 pub struct MemoryBackendConfig {}
 
 /// S3 backend configuration (stub).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct S3BackendConfig {
     pub bucket: String,
@@ -364,7 +419,7 @@ pub struct S3BackendConfig {
 }
 
 /// Postgres backend configuration (stub).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct PostgresBackendConfig {
     #[serde(alias = "connection_string")]
@@ -376,7 +431,7 @@ pub struct PostgresBackendConfig {
 }
 
 /// Chroma backend configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ChromaBackendConfig {
     pub url: String,
@@ -394,7 +449,7 @@ pub struct ChromaBackendConfig {
 }
 
 /// Tagged enum for backend configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum BackendConfig {
@@ -407,7 +462,7 @@ pub enum BackendConfig {
 }
 
 /// Chunking configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ChunkConfig {
     #[serde(default)]
@@ -418,6 +473,10 @@ pub struct ChunkConfig {
     pub overlap: usize,
     #[serde(default)]
     pub granularity: ChunkGranularity,
+    /// Strategy per detected file language (e.g. `rust: ast`), overriding
+    /// `strategy` for files in that language.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub languages: IndexMap<String, ChunkStrategy>,
 }
 
 fn default_chunk_size() -> usize {
@@ -435,12 +494,13 @@ impl Default for ChunkConfig {
             size: default_chunk_size(),
             overlap: default_chunk_overlap(),
             granularity: ChunkGranularity::default(),
+            languages: IndexMap::new(),
         }
     }
 }
 
 /// Embedding configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct EmbeddingConfig {
     #[serde(default)]
@@ -449,6 +509,15 @@ pub struct EmbeddingConfig {
     pub model: Option<String>,
     #[serde(default = "default_embedding_dimensions")]
     pub dimensions: usize,
+    /// Embedding requests sent to the provider at once. Defaults to 4.
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Request budget per minute. Unset is unlimited.
+    #[serde(default)]
+    pub requests_per_minute: Option<usize>,
+    /// Estimated token budget per minute. Unset is unlimited.
+    #[serde(default)]
+    pub tokens_per_minute: Option<usize>,
 }
 
 fn default_embedding_dimensions() -> usize {
@@ -461,12 +530,91 @@ impl Default for EmbeddingConfig {
             provider: EmbeddingProvider::default(),
             model: None,
             dimensions: default_embedding_dimensions(),
+            concurrency: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+        }
+    }
+}
+
+/// When a mount's index is refreshed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IndexSchedule {
+    /// Only when `openfs index` is run.
+    #[default]
+    Manual,
+    /// Whenever the watcher reports a change.
+    OnChange,
+    /// Periodically, at the given interval.
+    Interval(HumanDuration),
+}
+
+impl FromStr for IndexSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "manual" => Ok(IndexSchedule::Manual),
+            "on_change" | "on-change" => Ok(IndexSchedule::OnChange),
+            other => HumanDuration::from_str(other)
+                .map(IndexSchedule::Interval)
+                .map_err(|_| {
+                    format!(
+                        "Invalid index schedule '{}': expected manual, on_change, or a duration",
+                        s
+                    )
+                }),
         }
     }
 }
 
+impl fmt::Display for IndexSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexSchedule::Manual => write!(f, "manual"),
+            IndexSchedule::OnChange => write!(f, "on_change"),
+            IndexSchedule::Interval(interval) => write!(f, "{}", interval),
+        }
+    }
+}
+
+impl Serialize for IndexSchedule {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for IndexSchedule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        IndexSchedule::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for IndexSchedule {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "IndexSchedule".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "\"manual\", \"on_change\", or an interval such as \"10m\".",
+            "anyOf": [
+                { "enum": ["manual", "on_change", "on-change"] },
+                generator.subschema_for::<HumanDuration>()
+            ]
+        })
+    }
+}
+
 /// Indexing configuration for a mount.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(deny_unknown_fields)]
 pub struct IndexConfig {
     #[serde(default)]
@@ -477,20 +625,218 @@ pub struct IndexConfig {
     pub chunk: Option<ChunkConfig>,
     #[serde(default)]
     pub embedding: Option<EmbeddingConfig>,
+    /// Glob patterns (relative to the mount) of files to index. Empty = all files.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Glob patterns (relative to the mount) of files to skip.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Vector store collection (overrides the mount's `collection`).
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// When the index is refreshed.
+    #[serde(default)]
+    pub schedule: IndexSchedule,
+    /// Files larger than this are not indexed.
+    #[serde(default)]
+    pub max_file_size: Option<HumanBytes>,
+    /// Files re-embedded per second when `openfs indexd` finds the index
+    /// schema changed, e.g. after switching embedding models. The mount is
+    /// re-embedded into a new collection while searches keep using the old
+    /// one until it completes. Unset re-indexes changed files in place.
+    #[serde(default)]
+    pub reembed_rate: Option<f64>,
+    /// How hybrid searches of the mount fuse dense and sparse results.
+    #[serde(default)]
+    pub hybrid: Option<HybridConfig>,
+    /// Related terms added to the sparse half of searches of the mount.
+    #[serde(default)]
+    pub expansion: Option<ExpansionConfig>,
+}
+
+/// Hybrid search settings for a mount's index.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HybridConfig {
+    /// Weight of dense scores, between 0 and 1; sparse scores get the rest.
+    /// Defaults to 0.7.
+    #[serde(default)]
+    pub alpha: Option<f32>,
+    #[serde(default)]
+    pub fusion: FusionMethod,
+    #[serde(default)]
+    pub normalize: ScoreNormalization,
+}
+
+/// Query expansion settings for a mount's index.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ExpansionConfig {
+    /// Terms added when the query contains the key, e.g. `auth` →
+    /// `[authentication, login]`.
+    #[serde(default)]
+    pub synonyms: IndexMap<String, Vec<String>>,
+    /// OpenAI-compatible chat completions base URL asked for extra terms,
+    /// with the key in `OPENAI_API_KEY`. Unset uses the synonyms only.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Model asked for extra terms. Defaults to gpt-4o-mini.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Most terms the endpoint may add. Defaults to 8.
+    #[serde(default)]
+    pub max_terms: Option<usize>,
 }
 
 /// Sync configuration for a mount.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(deny_unknown_fields)]
 pub struct SyncConfig {
     #[serde(default)]
     pub interval: Option<HumanDuration>,
     #[serde(default)]
     pub write_mode: WriteMode,
+    /// How failed writes to the backend are retried.
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    /// What write-back does when the remote file changed since it was read.
+    #[serde(default)]
+    pub conflict: ConflictStrategy,
+    /// How often a `pull_mirror` or `two_way` mount pulls remote changes.
+    /// Defaults to 30s.
+    #[serde(default)]
+    pub pull_interval: Option<HumanDuration>,
+    /// Write-back uploads sent at once during a flush. Defaults to 8.
+    #[serde(default)]
+    pub flush_concurrency: Option<usize>,
+    /// Write-back uploads of files this large or larger go in parts, and
+    /// resume after a crash. Defaults to 64mb.
+    #[serde(default)]
+    pub multipart_threshold: Option<HumanBytes>,
+    /// Size of each part of a multipart upload. S3 needs at least 5mb.
+    /// Defaults to 8mb.
+    #[serde(default)]
+    pub multipart_part_size: Option<HumanBytes>,
+}
+
+/// How a write-back flush resolves a file that changed remotely since the
+/// local copy was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Replace the remote version with the local one.
+    #[default]
+    Overwrite,
+    /// Keep the remote version and write the local one next to it.
+    KeepBoth,
+    /// Leave the remote version and dead-letter the local write.
+    Fail,
+    /// Merge line-based text changes, falling back to `keep_both`.
+    ThreeWayMerge,
+}
+
+/// Retry policy for syncing writes to a backend. Once `max_attempts` is
+/// reached, or on an error that retrying cannot fix, the write is
+/// dead-lettered until `openfs sync retry-failed`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Attempts per write, including the first. Defaults to 5.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry. Defaults to 1s.
+    #[serde(default = "default_retry_backoff")]
+    pub backoff: HumanDuration,
+    /// Longest delay between retries. Defaults to 5m.
+    #[serde(default = "default_retry_max_backoff")]
+    pub max_backoff: HumanDuration,
+    #[serde(default)]
+    pub strategy: BackoffStrategy,
+    /// Randomize each delay so clients do not retry in lockstep. Defaults to true.
+    #[serde(default = "default_true")]
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: default_retry_max_attempts(),
+            backoff: default_retry_backoff(),
+            max_backoff: default_retry_max_backoff(),
+            strategy: BackoffStrategy::default(),
+            jitter: true,
+        }
+    }
+}
+
+fn default_retry_max_attempts() -> u32 {
+    5
+}
+
+fn default_retry_backoff() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(1))
+}
+
+fn default_retry_max_backoff() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(5 * 60))
+}
+
+/// Cache settings for a mount. They only apply to modes that cache
+/// (`write_through`, `write_back`, `remote_cached`, `pull_mirror`, `two_way`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MountCacheConfig {
+    /// Bytes of file content kept in memory per mount. Defaults to 100MB.
+    #[serde(default)]
+    pub max_size: Option<HumanBytes>,
+    /// How the memory cache picks entries to evict. Defaults to `tiny_lfu`.
+    #[serde(default)]
+    pub eviction: Option<CacheEviction>,
+    /// Keep file contents on disk as well, so they survive restarts.
+    #[serde(default)]
+    pub disk: Option<DiskCacheConfig>,
+    /// Globs, relative to the mount, of files to read into the cache in the
+    /// background when the mount starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warm: Vec<String>,
+}
+
+/// Eviction policy of the memory cache. Entries are weighed by size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheEviction {
+    /// Admit by access frequency, so one large file read once cannot push out
+    /// small files that are read often.
+    TinyLfu,
+    /// Evict the least recently used files.
+    Lru,
+}
+
+/// On-disk cache of file contents, consulted after the memory cache.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DiskCacheConfig {
+    /// Directory for cached files. Each mount uses its own subdirectory.
+    pub dir: String,
+    /// Bytes kept per mount before the least recently used files are evicted.
+    /// Defaults to 1GB.
+    #[serde(default = "default_disk_cache_max_size")]
+    pub max_size: HumanBytes,
+    /// How long a cached file is served before it is read again. Defaults to 24h.
+    #[serde(default = "default_disk_cache_ttl")]
+    pub ttl: HumanDuration,
+}
+
+fn default_disk_cache_max_size() -> HumanBytes {
+    HumanBytes(1024 * 1024 * 1024)
+}
+
+fn default_disk_cache_ttl() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(24 * 60 * 60))
 }
 
 /// Watch configuration for file change notifications.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct WatchConfig {
     /// Use native OS file watching (inotify/FSEvents). Defaults to true.
@@ -539,7 +885,7 @@ impl Default for WatchConfig {
 }
 
 /// Mount configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct MountConfig {
     pub path: String,
@@ -557,10 +903,214 @@ pub struct MountConfig {
     pub sync: Option<SyncConfig>,
     #[serde(default)]
     pub watch: Option<WatchConfig>,
+    #[serde(default)]
+    pub cache: Option<MountCacheConfig>,
+    #[serde(default)]
+    pub log: Option<LogConfig>,
+    #[serde(default)]
+    pub path_policy: Option<PathPolicyConfig>,
+    /// Retry failed backend requests, and stop sending requests to a
+    /// backend that keeps failing.
+    #[serde(default)]
+    pub retry: Option<RequestRetryConfig>,
+    /// Backends holding copies of `backend`, read from when it fails.
+    #[serde(default)]
+    pub replicas: Option<ReplicasConfig>,
+}
+
+/// Read replicas of a mount's backend, e.g. the same bucket in a second
+/// region. Reads fail over to the replicas when the primary fails, and
+/// writes go to the primary and are copied to the replicas in the
+/// background, retried with the mount's `sync.retry` policy.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicasConfig {
+    /// Names of the replica backends, in failover order.
+    pub backends: Vec<String>,
+    /// Also send a read to the first replica when the primary has not
+    /// answered within this long, and take the first answer.
+    #[serde(default)]
+    pub hedge_after: Option<HumanDuration>,
+}
+
+/// How paths on a mount are normalized before they reach its backend, so
+/// that the spellings agents produce name the same stored file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PathPolicyConfig {
+    /// Lowercase paths below the mount, making a case-sensitive store
+    /// behave case-insensitively. Files stored with capitals before this was
+    /// enabled are no longer reachable.
+    #[serde(default)]
+    pub case_fold: bool,
+    /// Normalize paths to Unicode NFC, so composed and decomposed spellings
+    /// of the same name are one file.
+    #[serde(default)]
+    pub unicode_nfc: bool,
+    /// What a trailing slash on a file path does.
+    #[serde(default)]
+    pub trailing_slash: TrailingSlash,
+    /// Longest path below the mount, in bytes, after normalization.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+/// Handling of a trailing slash, see [`PathPolicyConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TrailingSlash {
+    /// Drop it: `a/` is `a`.
+    #[default]
+    Strip,
+    /// Refuse the path, except for the mount itself.
+    Reject,
+}
+
+/// Retry policy for a mount's backend requests, so that transient failures
+/// such as S3 500s or network blips do not reach the caller. Unlike
+/// `sync.retry`, which retries queued write-back uploads, this applies to
+/// every request as it is made. Requests that could take effect twice are
+/// tried once: appends, deletes, renames and conditional writes.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RequestRetryConfig {
+    /// Attempts per request, including the first. Defaults to 3.
+    #[serde(default = "default_request_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry. Defaults to 100ms.
+    #[serde(default = "default_request_backoff")]
+    pub backoff: HumanDuration,
+    /// Longest delay between retries. Defaults to 5s.
+    #[serde(default = "default_request_max_backoff")]
+    pub max_backoff: HumanDuration,
+    #[serde(default)]
+    pub strategy: BackoffStrategy,
+    /// Randomize each delay. Defaults to true.
+    #[serde(default = "default_true")]
+    pub jitter: bool,
+    /// The failures that are retried. Defaults to all of them.
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+    /// Fail requests at once while the backend keeps failing.
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+}
+
+impl Default for RequestRetryConfig {
+    fn default() -> Self {
+        RequestRetryConfig {
+            max_attempts: default_request_max_attempts(),
+            backoff: default_request_backoff(),
+            max_backoff: default_request_max_backoff(),
+            strategy: BackoffStrategy::default(),
+            jitter: true,
+            retry_on: default_retry_on(),
+            circuit_breaker: None,
+        }
+    }
+}
+
+fn default_request_max_attempts() -> u32 {
+    3
+}
+
+fn default_request_backoff() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_millis(100))
+}
+
+fn default_request_max_backoff() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(5))
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![
+        RetryOn::Connection,
+        RetryOn::Timeout,
+        RetryOn::Throttled,
+        RetryOn::ServerError,
+    ]
+}
+
+/// A kind of transient backend failure, see [`RequestRetryConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RetryOn {
+    /// The connection could not be made or was dropped.
+    Connection,
+    /// The request timed out.
+    Timeout,
+    /// The backend asked the client to slow down.
+    Throttled,
+    /// The backend failed on its side, such as an HTTP 500.
+    ServerError,
+}
+
+/// Circuit breaker for a mount's backend. After `failure_threshold`
+/// retryable failures in a row the breaker opens and requests fail at once
+/// for `open_for`. Then one trial request is let through: the breaker closes
+/// if it succeeds and opens again if it fails.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker. Defaults to 5.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long the breaker stays open. Defaults to 30s.
+    #[serde(default = "default_open_for")]
+    pub open_for: HumanDuration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: default_failure_threshold(),
+            open_for: default_open_for(),
+        }
+    }
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_open_for() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(30))
+}
+
+/// Files on a mount kept as append-only logs. Each append is stored as its
+/// own segment of whole lines, so concurrent appenders never rewrite the
+/// file or split each other's lines; reads see the segments concatenated.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LogConfig {
+    /// Globs, relative to the mount, of the log files, e.g. `logs/**/*.jsonl`.
+    pub paths: Vec<String>,
+    /// Segments a log collects before they are merged into one. Defaults to 64.
+    #[serde(default)]
+    pub compact_after: Option<usize>,
+}
+
+/// A read-only virtual directory over the files a saved glob or grep
+/// selects from `source`. Matches keep their path relative to `source`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ViewConfig {
+    /// VFS path the view appears at, e.g. `/views/rust-src`.
+    pub path: String,
+    /// Directory the view selects from. It must be inside a single mount.
+    pub source: String,
+    /// Glob over paths relative to `source`, e.g. `src/**/*.rs`.
+    #[serde(default)]
+    pub glob: Option<String>,
+    /// Regex a file's content must match.
+    #[serde(default)]
+    pub grep: Option<String>,
 }
 
 /// Top-level VFS configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct VfsConfig {
     #[serde(default)]
@@ -572,11 +1122,191 @@ pub struct VfsConfig {
     #[serde(default)]
     pub mounts: Vec<MountConfig>,
     #[serde(default)]
+    pub views: Vec<ViewConfig>,
+    #[serde(default)]
     pub defaults: Option<DefaultsConfig>,
+    #[serde(default)]
+    pub mcp: Option<McpConfig>,
+    #[serde(default)]
+    pub fuse: Option<FuseConfig>,
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
+    #[serde(default)]
+    pub tools: Option<ToolsConfig>,
+}
+
+/// Hardening for deployments that serve semi-trusted agents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityConfig {
+    /// Refuse and log paths that are valid but look like probing: any `..`
+    /// segment, even one that stays inside the root, and control characters.
+    /// Paths escaping the root, NUL bytes and overlong names are always
+    /// refused.
+    #[serde(default)]
+    pub deny_suspicious_paths: bool,
+}
+
+/// Customizes the tool definitions given to agents by `openfs tools` and
+/// the MCP server.
+///
+/// Tools are named with or without their `vfs_` or `openfs_` prefix, so
+/// `delete` covers both `vfs_delete` and `openfs_delete`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ToolsConfig {
+    /// Tools to leave out, e.g. `[delete, rename]`.
+    #[serde(default)]
+    pub hide: Vec<String>,
+    /// Mounts that path parameters may point into. Empty allows every mount.
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    /// New names and descriptions, by tool.
+    #[serde(default)]
+    pub overrides: IndexMap<String, ToolOverride>,
+}
+
+/// What an agent sees of one tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ToolOverride {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl ToolsConfig {
+    /// Whether `tool` is hidden.
+    pub fn hides(&self, tool: &str) -> bool {
+        let tool = base_tool_name(tool);
+        self.hide
+            .iter()
+            .any(|hidden| base_tool_name(hidden) == tool)
+    }
+
+    /// The override configured for `tool`.
+    pub fn override_for(&self, tool: &str) -> Option<&ToolOverride> {
+        let tool = base_tool_name(tool);
+        self.overrides
+            .iter()
+            .find(|(name, _)| base_tool_name(name) == tool)
+            .map(|(_, tool_override)| tool_override)
+    }
+
+    /// The name agents see for `tool`.
+    pub fn exposed_name<'a>(&'a self, tool: &'a str) -> &'a str {
+        self.override_for(tool)
+            .and_then(|o| o.name.as_deref())
+            .unwrap_or(tool)
+    }
+
+    /// Whether `path`, normalized, lies in one of the allowed mounts.
+    pub fn allows_path(&self, path: &str) -> bool {
+        self.mounts.is_empty()
+            || self.mounts.iter().any(|mount| {
+                let mount = mount.trim_end_matches('/');
+                path == mount
+                    || path
+                        .strip_prefix(mount)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+fn base_tool_name(tool: &str) -> &str {
+    tool.strip_prefix("openfs_")
+        .or_else(|| tool.strip_prefix("vfs_"))
+        .unwrap_or(tool)
+}
+
+/// Tool set exposed by the MCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum McpProfile {
+    /// Every tool.
+    #[default]
+    Full,
+    /// Only tools that never modify files (read, list, grep, search, ...).
+    ReadOnly,
+}
+
+/// MCP server configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct McpConfig {
+    #[serde(default)]
+    pub profile: McpProfile,
+    /// Explicit tool allowlist (e.g. `openfs_read` or `read`). Narrows the profile.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Confine the session to paths under this VFS prefix.
+    #[serde(default)]
+    pub root: Option<String>,
+}
+
+/// FUSE mount configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FuseConfig {
+    #[serde(default)]
+    pub write_buffer: WriteBufferConfig,
+    /// How long `.search` query results are reused before the query runs
+    /// again. Defaults to 60s.
+    #[serde(default = "default_search_cache_ttl")]
+    pub search_cache_ttl: HumanDuration,
+}
+
+fn default_search_cache_ttl() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(60))
+}
+
+impl Default for FuseConfig {
+    fn default() -> Self {
+        FuseConfig {
+            write_buffer: WriteBufferConfig::default(),
+            search_cache_ttl: default_search_cache_ttl(),
+        }
+    }
+}
+
+/// Per-handle write coalescing for FUSE mounts.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WriteBufferConfig {
+    /// Buffer writes per open file until fsync/close. Defaults to true.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Flush once a handle has this many unflushed bytes. Defaults to 8mb.
+    #[serde(default = "default_max_dirty")]
+    pub max_dirty: HumanBytes,
+    /// Flush on the next write once the oldest unflushed write is this old.
+    /// Defaults to 5s.
+    #[serde(default = "default_max_dirty_age")]
+    pub max_age: HumanDuration,
+}
+
+fn default_max_dirty() -> HumanBytes {
+    HumanBytes(8 * 1024 * 1024)
+}
+
+fn default_max_dirty_age() -> HumanDuration {
+    HumanDuration(std::time::Duration::from_secs(5))
+}
+
+impl Default for WriteBufferConfig {
+    fn default() -> Self {
+        WriteBufferConfig {
+            enabled: true,
+            max_dirty: default_max_dirty(),
+            max_age: default_max_dirty_age(),
+        }
+    }
 }
 
 /// Global defaults configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(deny_unknown_fields)]
 pub struct DefaultsConfig {
     #[serde(default)]
@@ -587,6 +1317,8 @@ pub struct DefaultsConfig {
     pub sync: Option<SyncConfig>,
     #[serde(default)]
     pub watch: Option<WatchConfig>,
+    #[serde(default)]
+    pub cache: Option<MountCacheConfig>,
 }
 
 impl Default for VfsConfig {
@@ -596,7 +1328,12 @@ impl Default for VfsConfig {
             version: None,
             backends: IndexMap::new(),
             mounts: Vec::new(),
+            views: Vec::new(),
             defaults: None,
+            mcp: None,
+            fuse: None,
+            security: None,
+            tools: None,
         }
     }
 }
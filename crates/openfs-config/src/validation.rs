@@ -1,6 +1,11 @@
 use std::collections::HashSet;
 
-use crate::types::{BackendConfig, ChunkConfig, EmbeddingConfig, VfsConfig, WatchConfig};
+use crate::index_policy::IndexFilter;
+use crate::types::{
+    BackendConfig, ChunkConfig, EmbeddingConfig, IndexConfig, IndexSchedule, LogConfig,
+    MountCacheConfig, RequestRetryConfig, RetryConfig, SyncConfig, VfsConfig, WatchConfig,
+};
+use crate::view_policy::ViewFilter;
 use crate::ConfigError;
 
 impl VfsConfig {
@@ -36,6 +41,14 @@ impl VfsConfig {
                     ));
                 }
             }
+            for replica in mount.replicas.iter().flat_map(|r| &r.backends) {
+                if !self.backends.contains_key(replica) {
+                    errors.push(ConfigError::UndefinedBackend(
+                        replica.clone(),
+                        mount.path.clone(),
+                    ));
+                }
+            }
         }
 
         // Check that no mount path is a prefix of another
@@ -79,6 +92,7 @@ impl VfsConfig {
         // Validate mount-level index sub-configs
         for mount in &self.mounts {
             if let Some(ref index) = mount.index {
+                validate_index_config(&mount.path, index, &mut errors);
                 if let Some(ref chunk) = index.chunk {
                     validate_chunk_config(&mount.path, chunk, &mut errors);
                 }
@@ -89,6 +103,44 @@ impl VfsConfig {
             if let Some(ref watch) = mount.watch {
                 validate_watch_config(&mount.path, watch, &mut errors);
             }
+            if let Some(ref cache) = mount.cache {
+                validate_cache_config(&mount.path, cache, &mut errors);
+            }
+            if let Some(ref sync) = mount.sync {
+                validate_sync_config(&mount.path, sync, &mut errors);
+            }
+            if let Some(ref log) = mount.log {
+                validate_log_config(&mount.path, log, &mut errors);
+            }
+            if let Some(ref policy) = mount.path_policy {
+                if policy.max_length == Some(0) {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "{}.path_policy.max_length: must be greater than 0",
+                        mount.path
+                    )));
+                }
+            }
+            if let Some(ref retry) = mount.retry {
+                validate_request_retry_config(&mount.path, retry, &mut errors);
+            }
+            if let Some(ref replicas) = mount.replicas {
+                if replicas.backends.is_empty() {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "{}.replicas.backends: must not be empty",
+                        mount.path
+                    )));
+                }
+                if replicas
+                    .backends
+                    .iter()
+                    .any(|b| Some(b) == mount.backend.as_ref())
+                {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "{}.replicas.backends: must not include the mount's own backend",
+                        mount.path
+                    )));
+                }
+            }
         }
 
         // Validate default-level configs
@@ -102,11 +154,115 @@ impl VfsConfig {
             if let Some(ref watch) = defaults.watch {
                 validate_watch_config("defaults", watch, &mut errors);
             }
+            if let Some(ref cache) = defaults.cache {
+                validate_cache_config("defaults", cache, &mut errors);
+            }
+            if let Some(ref sync) = defaults.sync {
+                validate_sync_config("defaults", sync, &mut errors);
+            }
+        }
+
+        self.validate_views(&mut errors);
+
+        if let Some(root) = self.mcp.as_ref().and_then(|mcp| mcp.root.as_ref()) {
+            if !root.starts_with('/') {
+                errors.push(ConfigError::InvalidConfig(format!(
+                    "mcp.root: '{}' must start with '/'",
+                    root
+                )));
+            }
+        }
+
+        if let Some(ref tools) = self.tools {
+            for mount in &tools.mounts {
+                if !self.mounts.iter().any(|m| m.path == *mount) {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "tools.mounts: '{}' is not a mount path",
+                        mount
+                    )));
+                }
+            }
+            let mut names = std::collections::HashSet::new();
+            for (tool, tool_override) in &tools.overrides {
+                let Some(ref name) = tool_override.name else {
+                    continue;
+                };
+                let valid = !name.is_empty()
+                    && name.len() <= 64
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if !valid {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "tools.overrides.{}.name: '{}' must be 1-64 letters, digits, '_' or '-'",
+                        tool, name
+                    )));
+                }
+                if !names.insert(name) {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "tools.overrides.{}.name: '{}' is used twice",
+                        tool, name
+                    )));
+                }
+            }
+        }
+
+        if let Some(buffer) = self.fuse.as_ref().map(|fuse| &fuse.write_buffer) {
+            if buffer.enabled && buffer.max_dirty.as_bytes() == 0 {
+                errors.push(ConfigError::InvalidConfig(
+                    "fuse.write_buffer.max_dirty: must be greater than 0".to_string(),
+                ));
+            }
         }
 
         errors
     }
 
+    fn validate_views(&self, errors: &mut Vec<ConfigError>) {
+        let mut seen_paths = HashSet::new();
+        for view in &self.views {
+            let path = normalize_path(&view.path);
+            if !view.path.starts_with('/') || path.is_empty() {
+                errors.push(ConfigError::InvalidConfig(format!(
+                    "views.{}: path must start with '/' and not be the root",
+                    view.path
+                )));
+            }
+            if !seen_paths.insert(path) {
+                errors.push(ConfigError::InvalidConfig(format!(
+                    "views.{}: duplicate view path",
+                    view.path
+                )));
+            }
+            // A view is routed like a mount, so it must not hide one.
+            for mount in &self.mounts {
+                let mount_path = normalize_path(&mount.path);
+                if mount_path == path || mount_path.starts_with(&format!("{}/", path)) {
+                    errors.push(ConfigError::InvalidConfig(format!(
+                        "views.{}: overlaps mount '{}'",
+                        view.path, mount.path
+                    )));
+                }
+            }
+            let source = normalize_path(&view.source);
+            let in_mount = self.mounts.iter().any(|mount| {
+                let mount_path = normalize_path(&mount.path);
+                mount_path.is_empty()
+                    || source == mount_path
+                    || source.starts_with(&format!("{}/", mount_path))
+            });
+            if !view.source.starts_with('/') || !in_mount {
+                errors.push(ConfigError::InvalidConfig(format!(
+                    "views.{}.source: '{}' is not inside a mount",
+                    view.path, view.source
+                )));
+            }
+            if let Err(err) = ViewFilter::new(view) {
+                errors.push(err);
+            }
+        }
+    }
+
     /// Validate and return Ok(()) if valid, or Err with all errors combined.
     pub fn validate_or_err(&self) -> Result<(), ConfigError> {
         let errors = self.validate();
@@ -186,6 +342,52 @@ fn validate_chroma_config(
     }
 }
 
+fn validate_index_config(context: &str, index: &IndexConfig, errors: &mut Vec<ConfigError>) {
+    if let Err(err) = IndexFilter::new(context, index) {
+        errors.push(err);
+    }
+    if let Some(ref max_file_size) = index.max_file_size {
+        if max_file_size.as_bytes() == 0 {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.index.max_file_size: must be greater than 0",
+                context
+            )));
+        }
+    }
+    if let Some(rate) = index.reembed_rate {
+        if !(rate > 0.0 && rate.is_finite()) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.index.reembed_rate: must be a positive number",
+                context
+            )));
+        }
+    }
+    if let Some(alpha) = index.hybrid.as_ref().and_then(|hybrid| hybrid.alpha) {
+        if !(0.0..=1.0).contains(&alpha) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.index.hybrid.alpha: must be between 0 and 1",
+                context
+            )));
+        }
+    }
+    if let IndexSchedule::Interval(ref interval) = index.schedule {
+        if interval.as_duration().is_zero() {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.index.schedule: interval must be greater than 0",
+                context
+            )));
+        }
+    }
+    if let Some(ref collection) = index.collection {
+        if collection.trim().is_empty() {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.index.collection: must not be empty",
+                context
+            )));
+        }
+    }
+}
+
 fn validate_chunk_config(context: &str, chunk: &ChunkConfig, errors: &mut Vec<ConfigError>) {
     if chunk.size == 0 {
         errors.push(ConfigError::InvalidConfig(format!(
@@ -224,6 +426,19 @@ fn validate_embedding_config(
             context, embedding.dimensions
         )));
     }
+    let limits = [
+        ("concurrency", embedding.concurrency),
+        ("requests_per_minute", embedding.requests_per_minute),
+        ("tokens_per_minute", embedding.tokens_per_minute),
+    ];
+    for (field, value) in limits {
+        if value == Some(0) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.embedding.{}: must be greater than 0",
+                context, field
+            )));
+        }
+    }
 }
 
 fn validate_watch_config(context: &str, watch: &WatchConfig, errors: &mut Vec<ConfigError>) {
@@ -261,6 +476,146 @@ fn validate_watch_config(context: &str, watch: &WatchConfig, errors: &mut Vec<Co
     }
 }
 
+fn validate_cache_config(context: &str, cache: &MountCacheConfig, errors: &mut Vec<ConfigError>) {
+    for pattern in &cache.warm {
+        if let Err(e) = globset::Glob::new(pattern) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.cache.warm: invalid glob '{}': {}",
+                context, pattern, e
+            )));
+        }
+    }
+    if cache
+        .max_size
+        .as_ref()
+        .is_some_and(|size| size.as_bytes() == 0)
+    {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.cache.max_size: must be greater than 0",
+            context
+        )));
+    }
+    let Some(ref disk) = cache.disk else {
+        return;
+    };
+    if disk.dir.trim().is_empty() {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.cache.disk.dir: must not be empty",
+            context
+        )));
+    }
+    if disk.max_size.as_bytes() == 0 {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.cache.disk.max_size: must be greater than 0",
+            context
+        )));
+    }
+}
+
+fn validate_log_config(context: &str, log: &LogConfig, errors: &mut Vec<ConfigError>) {
+    if log.paths.is_empty() {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.log.paths: must not be empty",
+            context
+        )));
+    }
+    for pattern in &log.paths {
+        if let Err(e) = globset::Glob::new(pattern) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.log.paths: invalid glob '{}': {}",
+                context, pattern, e
+            )));
+        }
+    }
+    if log.compact_after == Some(0) {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.log.compact_after: must be greater than 0",
+            context
+        )));
+    }
+}
+
+fn validate_sync_config(context: &str, sync: &SyncConfig, errors: &mut Vec<ConfigError>) {
+    if let Some(ref retry) = sync.retry {
+        validate_retry_config(context, retry, errors);
+    }
+    if sync
+        .pull_interval
+        .as_ref()
+        .is_some_and(|interval| interval.as_duration().is_zero())
+    {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.sync.pull_interval: must be greater than 0",
+            context
+        )));
+    }
+    if sync.flush_concurrency == Some(0) {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.sync.flush_concurrency: must be greater than 0",
+            context
+        )));
+    }
+    for (field, size) in [
+        ("multipart_threshold", &sync.multipart_threshold),
+        ("multipart_part_size", &sync.multipart_part_size),
+    ] {
+        if size.as_ref().is_some_and(|size| size.as_bytes() == 0) {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.sync.{}: must be greater than 0",
+                context, field
+            )));
+        }
+    }
+}
+
+fn validate_retry_config(context: &str, retry: &RetryConfig, errors: &mut Vec<ConfigError>) {
+    if retry.max_attempts == 0 {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.sync.retry.max_attempts: must be at least 1",
+            context
+        )));
+    }
+    if retry.backoff.as_duration() > retry.max_backoff.as_duration() {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.sync.retry.backoff: must not exceed max_backoff",
+            context
+        )));
+    }
+}
+
+fn validate_request_retry_config(
+    context: &str,
+    retry: &RequestRetryConfig,
+    errors: &mut Vec<ConfigError>,
+) {
+    if retry.max_attempts == 0 {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.retry.max_attempts: must be at least 1",
+            context
+        )));
+    }
+    if retry.backoff.as_duration() > retry.max_backoff.as_duration() {
+        errors.push(ConfigError::InvalidConfig(format!(
+            "{}.retry.backoff: must not exceed max_backoff",
+            context
+        )));
+    }
+    if let Some(ref breaker) = retry.circuit_breaker {
+        if breaker.failure_threshold == 0 {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.retry.circuit_breaker.failure_threshold: must be at least 1",
+                context
+            )));
+        }
+        if breaker.open_for.as_duration().is_zero() {
+            errors.push(ConfigError::InvalidConfig(format!(
+                "{}.retry.circuit_breaker.open_for: must be greater than 0",
+                context
+            )));
+        }
+    }
+}
+
 /// Normalize a path by removing trailing slashes.
 fn normalize_path(path: &str) -> &str {
     path.trim_end_matches('/')
@@ -271,8 +626,8 @@ mod tests {
     use super::*;
     use crate::types::{
         BackendConfig, ChromaBackendConfig, ChunkConfig, EmbeddingConfig, FsBackendConfig,
-        HumanDuration, IndexConfig, MountConfig, PostgresBackendConfig, S3BackendConfig, Secret,
-        SyncConfig as MountSyncConfig, WatchConfig,
+        HumanDuration, IndexConfig, MountConfig, PostgresBackendConfig, RetryOn, S3BackendConfig,
+        Secret, SyncConfig as MountSyncConfig, WatchConfig,
     };
 
     #[test]
@@ -655,6 +1010,36 @@ mod tests {
             .contains("embedding.dimensions: must be at most 4096")));
     }
 
+    #[test]
+    fn test_validate_embedding_zero_concurrency() {
+        let config = VfsConfig {
+            backends: indexmap::indexmap! {
+                "local".to_string() => BackendConfig::Fs(FsBackendConfig {
+                    root: "./data".to_string(),
+                }),
+            },
+            mounts: vec![MountConfig {
+                path: "/workspace".to_string(),
+                backend: Some("local".to_string()),
+                index: Some(IndexConfig {
+                    enabled: true,
+                    embedding: Some(EmbeddingConfig {
+                        concurrency: Some(0),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..default_mount()
+            }],
+            ..Default::default()
+        };
+
+        let errors = config.validate();
+        assert!(errors.iter().any(|e| e
+            .to_string()
+            .contains("embedding.concurrency: must be greater than 0")));
+    }
+
     #[test]
     fn test_validate_or_err_shows_all_errors() {
         let config = VfsConfig {
@@ -786,6 +1171,421 @@ mod tests {
             .any(|e| e.to_string().contains("watch.exclude: invalid regex")));
     }
 
+    #[test]
+    fn test_validate_index_policy() {
+        let config = VfsConfig {
+            backends: indexmap::indexmap! {
+                "local".to_string() => BackendConfig::Fs(FsBackendConfig {
+                    root: "./data".to_string(),
+                }),
+            },
+            mounts: vec![MountConfig {
+                path: "/workspace".to_string(),
+                backend: Some("local".to_string()),
+                index: Some(IndexConfig {
+                    enabled: true,
+                    exclude: vec!["target/[".to_string()],
+                    max_file_size: Some(crate::types::HumanBytes(0)),
+                    schedule: IndexSchedule::Interval(crate::types::HumanDuration::default()),
+                    reembed_rate: Some(0.0),
+                    hybrid: Some(crate::types::HybridConfig {
+                        alpha: Some(1.5),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..default_mount()
+            }],
+            ..Default::default()
+        };
+
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("/workspace.index.exclude: invalid glob")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("index.max_file_size: must be greater than 0")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("index.schedule: interval must be greater than 0")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("index.reembed_rate: must be a positive number")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("index.hybrid.alpha: must be between 0 and 1")));
+    }
+
+    #[test]
+    fn test_parse_index_policy_yaml() {
+        let yaml = r#"
+name: test
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+    index:
+      enabled: true
+      include: ["**/*.rs"]
+      exclude: ["target/**"]
+      collection: code
+      schedule: 15m
+      max_file_size: 1mb
+      chunk:
+        strategy: ast
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        assert!(config.validate().is_empty());
+        let index = config.mounts[0].index.as_ref().unwrap();
+        assert_eq!(index.collection.as_deref(), Some("code"));
+        assert_eq!(
+            index.schedule,
+            IndexSchedule::Interval(crate::types::HumanDuration(std::time::Duration::from_secs(
+                900
+            )))
+        );
+        assert_eq!(
+            index.max_file_size.as_ref().unwrap().as_bytes(),
+            1024 * 1024
+        );
+    }
+
+    #[test]
+    fn test_validate_mcp_config() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+mcp:
+  profile: read_only
+  tools: [read, grep]
+  root: workspace/project
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let mcp = config.mcp.as_ref().unwrap();
+        assert_eq!(mcp.profile, crate::types::McpProfile::ReadOnly);
+        assert_eq!(mcp.tools, vec!["read", "grep"]);
+        assert!(config
+            .validate()
+            .iter()
+            .any(|e| e.to_string().contains("mcp.root")));
+    }
+
+    #[test]
+    fn test_validate_tools_config() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+tools:
+  hide: [delete, openfs_rename]
+  mounts: [/workspace, /missing]
+  overrides:
+    read:
+      name: read_file
+      description: Read a file. Prefer ranges for large files.
+    write:
+      name: "write file"
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let tools = config.tools.as_ref().unwrap();
+        assert!(tools.hides("vfs_delete"));
+        assert!(tools.hides("rename"));
+        assert!(!tools.hides("openfs_read"));
+        assert_eq!(tools.exposed_name("openfs_read"), "read_file");
+        assert_eq!(tools.exposed_name("vfs_ls"), "vfs_ls");
+        assert!(tools.allows_path("/workspace/a.txt"));
+        assert!(!tools.allows_path("/workspace2/a.txt"));
+
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].contains("tools.mounts: '/missing'"));
+        assert!(errors[1].contains("tools.overrides.write.name"));
+    }
+
+    #[test]
+    fn test_validate_fuse_config() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+fuse:
+  write_buffer:
+    max_dirty: 0b
+    max_age: 2s
+  search_cache_ttl: 30s
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let fuse = config.fuse.as_ref().unwrap();
+        let buffer = &fuse.write_buffer;
+        assert!(buffer.enabled);
+        assert_eq!(buffer.max_age.as_duration().as_secs(), 2);
+        assert_eq!(fuse.search_cache_ttl.as_duration().as_secs(), 30);
+        assert!(config
+            .validate()
+            .iter()
+            .any(|e| e.to_string().contains("fuse.write_buffer.max_dirty")));
+    }
+
+    #[test]
+    fn test_validate_disk_cache() {
+        let yaml = r#"
+backends:
+  s3:
+    type: s3
+    bucket: agent-data
+mounts:
+  - path: /data
+    backend: s3
+    cache:
+      disk:
+        dir: .openfs/cache
+        max_size: 0b
+      warm:
+        - "src/**/*.rs"
+        - "docs/[*.md"
+defaults:
+  cache:
+    max_size: 0b
+    eviction: lru
+    disk:
+      dir: ""
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let disk = config.mounts[0]
+            .cache
+            .as_ref()
+            .unwrap()
+            .disk
+            .as_ref()
+            .unwrap();
+        assert_eq!(disk.ttl.as_duration().as_secs(), 24 * 60 * 60);
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].contains("/data.cache.warm: invalid glob 'docs/[*.md'"));
+        assert!(errors[1].contains("/data.cache.disk.max_size"));
+        assert!(errors[2].contains("defaults.cache.max_size"));
+        assert!(errors[3].contains("defaults.cache.disk.dir"));
+    }
+
+    #[test]
+    fn test_validate_sync_retry() {
+        let yaml = r#"
+backends:
+  s3:
+    type: s3
+    bucket: agent-data
+mounts:
+  - path: /data
+    backend: s3
+    mode: write_back
+    sync:
+      retry:
+        max_attempts: 0
+      pull_interval: 0s
+      flush_concurrency: 0
+      multipart_part_size: 0b
+defaults:
+  sync:
+    retry:
+      backoff: 10m
+      strategy: linear
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let retry = config.mounts[0]
+            .sync
+            .as_ref()
+            .unwrap()
+            .retry
+            .clone()
+            .unwrap();
+        assert_eq!(retry.max_backoff.as_duration().as_secs(), 5 * 60);
+        assert!(retry.jitter);
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors[0].contains("/data.sync.retry.max_attempts"));
+        assert!(errors[1].contains("/data.sync.pull_interval"));
+        assert!(errors[2].contains("/data.sync.flush_concurrency"));
+        assert!(errors[3].contains("/data.sync.multipart_part_size"));
+        assert!(errors[4].contains("defaults.sync.retry.backoff"));
+    }
+
+    #[test]
+    fn test_validate_views() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+views:
+  - path: /views/rust-src
+    source: /workspace
+    glob: "src/**/*.rs"
+  - path: /views/recent-errors
+    source: /logs
+    grep: "ERROR"
+  - path: /workspace
+    source: /workspace
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("views./views/recent-errors.source"));
+        assert!(errors[1].contains("overlaps mount '/workspace'"));
+        assert!(errors[2].contains("needs a glob or a grep"));
+    }
+
+    #[test]
+    fn test_validate_log_config() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+    log:
+      paths:
+        - "logs/**/*.jsonl"
+        - "runs/[*.log"
+      compact_after: 0
+  - path: /other
+    backend: local
+    log:
+      paths: []
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("/workspace.log.paths: invalid glob 'runs/[*.log'"));
+        assert!(errors[1].contains("/workspace.log.compact_after"));
+        assert!(errors[2].contains("/other.log.paths: must not be empty"));
+    }
+
+    #[test]
+    fn test_validate_path_policy() {
+        let yaml = r#"
+backends:
+  local:
+    type: fs
+    root: ./data
+mounts:
+  - path: /workspace
+    backend: local
+    path_policy:
+      case_fold: true
+      trailing_slash: reject
+      max_length: 0
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].contains("/workspace.path_policy.max_length"));
+    }
+
+    #[test]
+    fn test_validate_request_retry() {
+        let yaml = r#"
+backends:
+  bucket:
+    type: memory
+mounts:
+  - path: /s3
+    backend: bucket
+    retry:
+      retry_on: [server_error, throttled]
+      circuit_breaker: {}
+  - path: /other
+    backend: bucket
+    retry:
+      max_attempts: 0
+      backoff: 10s
+      circuit_breaker:
+        failure_threshold: 0
+        open_for: 0s
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let retry = config.mounts[0].retry.as_ref().unwrap();
+        assert_eq!(retry.max_attempts, 3);
+        assert_eq!(
+            retry.retry_on,
+            vec![RetryOn::ServerError, RetryOn::Throttled]
+        );
+        let breaker = retry.circuit_breaker.as_ref().unwrap();
+        assert_eq!(breaker.failure_threshold, 5);
+        assert_eq!(breaker.open_for.as_duration().as_secs(), 30);
+
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].contains("/other.retry.max_attempts"));
+        assert!(errors[1].contains("/other.retry.backoff"));
+        assert!(errors[2].contains("/other.retry.circuit_breaker.failure_threshold"));
+        assert!(errors[3].contains("/other.retry.circuit_breaker.open_for"));
+    }
+
+    #[test]
+    fn test_validate_replicas() {
+        let yaml = r#"
+backends:
+  east:
+    type: memory
+  west:
+    type: memory
+mounts:
+  - path: /data
+    backend: east
+    replicas:
+      backends: [west]
+      hedge_after: 200ms
+  - path: /other
+    backend: east
+    replicas:
+      backends: [east, south]
+  - path: /empty
+    backend: east
+    replicas:
+      backends: []
+"#;
+        let config = VfsConfig::from_yaml(yaml).unwrap();
+        let replicas = config.mounts[0].replicas.as_ref().unwrap();
+        assert_eq!(replicas.backends, vec!["west"]);
+        assert_eq!(
+            replicas
+                .hedge_after
+                .as_ref()
+                .unwrap()
+                .as_duration()
+                .as_millis(),
+            200
+        );
+
+        let errors: Vec<String> = config.validate().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("south"));
+        assert!(errors[1].contains("/other.replicas.backends: must not include"));
+        assert!(errors[2].contains("/empty.replicas.backends: must not be empty"));
+    }
+
     fn default_mount() -> MountConfig {
         MountConfig {
             path: String::new(),
@@ -796,6 +1596,11 @@ mod tests {
             index: None,
             sync: None,
             watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
         }
     }
 }
@@ -0,0 +1,114 @@
+//! Compiled view selectors.
+//!
+//! [`ViewFilter`] turns a view's `glob:` and `grep:` into a matcher for
+//! paths relative to the view's source and for file content.
+
+use globset::{Glob, GlobMatcher};
+use regex::bytes::Regex;
+
+use crate::types::ViewConfig;
+use crate::ConfigError;
+
+/// Decides which files under a view's source appear in the view.
+#[derive(Debug, Clone)]
+pub struct ViewFilter {
+    glob: Option<GlobMatcher>,
+    grep: Option<Regex>,
+}
+
+impl ViewFilter {
+    /// Compile the selectors of `view`.
+    pub fn new(view: &ViewConfig) -> Result<Self, ConfigError> {
+        if view.glob.is_none() && view.grep.is_none() {
+            return Err(ConfigError::InvalidConfig(format!(
+                "views.{}: needs a glob or a grep",
+                view.path
+            )));
+        }
+        let glob = view
+            .glob
+            .as_ref()
+            .map(|pattern| {
+                Glob::new(pattern)
+                    .map(|glob| glob.compile_matcher())
+                    .map_err(|e| {
+                        ConfigError::InvalidConfig(format!(
+                            "views.{}.glob: invalid glob '{}': {}",
+                            view.path, pattern, e
+                        ))
+                    })
+            })
+            .transpose()?;
+        let grep = view
+            .grep
+            .as_ref()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    ConfigError::InvalidConfig(format!(
+                        "views.{}.grep: invalid regex '{}': {}",
+                        view.path, pattern, e
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(ViewFilter { glob, grep })
+    }
+
+    /// Whether a file at `relative` (to the view's source) passes the glob.
+    pub fn matches_path(&self, relative: &str) -> bool {
+        self.glob
+            .as_ref()
+            .is_none_or(|glob| glob.is_match(relative.trim_start_matches('/')))
+    }
+
+    /// Whether the content must be read to decide, see [`Self::matches_content`].
+    pub fn needs_content(&self) -> bool {
+        self.grep.is_some()
+    }
+
+    /// Whether a file's content passes the grep.
+    pub fn matches_content(&self, content: &[u8]) -> bool {
+        self.grep.as_ref().is_none_or(|grep| grep.is_match(content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(glob: Option<&str>, grep: Option<&str>) -> ViewConfig {
+        ViewConfig {
+            path: "/views/test".to_string(),
+            source: "/workspace".to_string(),
+            glob: glob.map(str::to_string),
+            grep: grep.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_glob_and_grep() {
+        let filter = ViewFilter::new(&view(Some("src/**/*.rs"), Some("(?i)error"))).unwrap();
+        assert!(filter.matches_path("src/lib.rs"));
+        assert!(filter.matches_path("/src/a/b.rs"));
+        assert!(!filter.matches_path("README.md"));
+        assert!(filter.needs_content());
+        assert!(filter.matches_content(b"line\nERROR: boom\n"));
+        assert!(!filter.matches_content(b"all good\n"));
+    }
+
+    #[test]
+    fn test_requires_a_selector() {
+        let err = ViewFilter::new(&view(None, None)).unwrap_err();
+        assert!(
+            err.to_string().contains("needs a glob or a grep"),
+            "{}",
+            err
+        );
+        let err = ViewFilter::new(&view(None, Some("("))).unwrap_err();
+        assert!(
+            err.to_string().contains("views./views/test.grep"),
+            "{}",
+            err
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -16,19 +17,59 @@ pub struct CacheConfig {
     pub max_entries: usize,
     /// Maximum total size in bytes (weighted by entry size).
     pub max_size: usize,
+    /// How entries are chosen for eviction once `max_size` is reached.
+    pub eviction: EvictionPolicy,
     /// Time-to-live for cache entries.
     pub ttl: Duration,
     /// Whether to enable the cache.
     pub enabled: bool,
+    /// Second-level cache on disk, consulted after this one.
+    pub disk: Option<DiskCacheConfig>,
+}
+
+/// How the cache picks entries to evict when it is over its size limit.
+///
+/// Both policies weigh each entry by its content length, so the limit is in
+/// bytes rather than entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Admit new entries by access frequency (W-TinyLFU). A large file read
+    /// once cannot push out small files that are read often.
+    #[default]
+    TinyLfu,
+    /// Evict the least recently used entries, admitting everything.
+    Lru,
+}
+
+impl EvictionPolicy {
+    fn to_moka(self) -> moka::policy::EvictionPolicy {
+        match self {
+            EvictionPolicy::TinyLfu => moka::policy::EvictionPolicy::tiny_lfu(),
+            EvictionPolicy::Lru => moka::policy::EvictionPolicy::lru(),
+        }
+    }
+}
+
+/// Configuration for an on-disk cache of file contents.
+#[derive(Debug, Clone)]
+pub struct DiskCacheConfig {
+    /// Directory holding the cached files.
+    pub dir: PathBuf,
+    /// Maximum total size in bytes before least recently used files are evicted.
+    pub max_size: u64,
+    /// Time-to-live for cached files, measured from when they were stored.
+    pub ttl: Duration,
 }
 
 impl Default for CacheConfig {
     fn default() -> Self {
         CacheConfig {
             max_entries: 1000,
-            max_size: 100 * 1024 * 1024,   // 100 MB
+            max_size: 100 * 1024 * 1024, // 100 MB
+            eviction: EvictionPolicy::default(),
             ttl: Duration::from_secs(300), // 5 minutes
             enabled: true,
+            disk: None,
         }
     }
 }
@@ -145,6 +186,7 @@ impl LruCache {
 
         // Build the moka cache with:
         // - max_capacity for total size (weighted by entry size)
+        // - the configured eviction policy
         // - time_to_live for TTL
         // - eviction_listener to track stats
         //
@@ -152,7 +194,10 @@ impl LruCache {
         // for existing code that expects a max_entries limit.
         let cache = Cache::builder()
             .max_capacity(config.max_size as u64)
-            .weigher(|_key: &String, value: &Vec<u8>| value.len() as u32)
+            .weigher(|_key: &String, value: &Vec<u8>| {
+                u32::try_from(value.len()).unwrap_or(u32::MAX)
+            })
+            .eviction_policy(config.eviction.to_moka())
             .time_to_live(config.ttl)
             .eviction_listener(move |_key: Arc<String>, value: Vec<u8>, cause| {
                 if let Ok(mut entries) = entries_clone.write() {
@@ -391,8 +436,10 @@ mod tests {
         let config = CacheConfig {
             max_entries: 3,
             max_size: 1024 * 1024,
+            eviction: EvictionPolicy::default(),
             ttl: Duration::from_secs(300),
             enabled: true,
+            disk: None,
         };
         let cache = LruCache::new(config);
 
@@ -425,8 +472,10 @@ mod tests {
         let config = CacheConfig {
             max_entries: 100,
             max_size: 1024 * 1024,
+            eviction: EvictionPolicy::default(),
             ttl: Duration::from_millis(50),
             enabled: true,
+            disk: None,
         };
         let cache = LruCache::new(config);
 
@@ -479,4 +528,42 @@ mod tests {
         cache.put("/test.txt", b"hello".to_vec()).await;
         assert!(cache.get("/test.txt").await.is_none());
     }
+
+    /// Ten hot 1 KB files, then one cold 8 KB file in a 16 KB cache.
+    async fn fill_hot_then_large(eviction: EvictionPolicy) -> LruCache {
+        let cache = LruCache::new(CacheConfig {
+            max_size: 16 * 1024,
+            eviction,
+            ..Default::default()
+        });
+        for i in 0..10 {
+            let key = format!("/hot/{}.txt", i);
+            cache.put(&key, vec![b'h'; 1024]).await;
+            for _ in 0..5 {
+                cache.get(&key).await;
+            }
+        }
+        cache.put("/large.bin", vec![b'l'; 8 * 1024]).await;
+        cache.cache.run_pending_tasks().await;
+        cache
+    }
+
+    #[tokio::test]
+    async fn test_tiny_lfu_keeps_hot_small_entries() {
+        let cache = fill_hot_then_large(EvictionPolicy::TinyLfu).await;
+        for i in 0..10 {
+            assert!(cache.contains(&format!("/hot/{}.txt", i)).await);
+        }
+        assert!(!cache.contains("/large.bin").await);
+        assert!(cache.stats().await.size <= 16 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_lru_admits_large_entry() {
+        let cache = fill_hot_then_large(EvictionPolicy::Lru).await;
+        assert!(cache.contains("/large.bin").await);
+        let stats = cache.stats().await;
+        assert!(stats.evictions >= 2, "{:?}", stats);
+        assert!(stats.size <= 16 * 1024, "{:?}", stats);
+    }
 }
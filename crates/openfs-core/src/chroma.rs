@@ -5,6 +5,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::BackendError;
 
+/// Collection metadata key naming the collection that currently holds a
+/// logical collection's chunks. Set when a re-embedding migration swaps in a
+/// new collection; clients follow it before reading or writing.
+pub const ACTIVE_COLLECTION_KEY: &str = "active_collection";
+
 /// Sparse vector representation for BM25/keyword search.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SparseEmbedding {
@@ -22,6 +27,16 @@ pub struct QueryResult {
     pub metadata: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// A document as stored, with its embeddings.
+#[derive(Debug, Clone)]
+pub struct StoredDocument {
+    pub id: String,
+    pub document: Option<String>,
+    pub embedding: Option<Vec<f32>>,
+    pub sparse_embedding: Option<SparseEmbedding>,
+    pub metadata: Option<HashMap<String, serde_json::Value>>,
+}
+
 /// Lightweight embedding trait for use in write paths.
 ///
 /// This is intentionally minimal (single-text only) to keep it in openfs-core
@@ -65,6 +80,12 @@ pub trait ChromaStore: Send + Sync + 'static {
         n_results: usize,
     ) -> Result<Vec<QueryResult>, BackendError>;
 
+    /// Get all documents matching a metadata filter, with their embeddings.
+    async fn get_by_metadata(
+        &self,
+        filter: serde_json::Value,
+    ) -> Result<Vec<StoredDocument>, BackendError>;
+
     /// Delete all documents matching a metadata filter.
     async fn delete_by_metadata(&self, filter: serde_json::Value) -> Result<usize, BackendError>;
 
@@ -82,3 +103,80 @@ pub trait ChromaStore: Send + Sync + 'static {
     /// Get the collection name.
     fn collection_name(&self) -> &str;
 }
+
+/// Whether `metadata` satisfies a Chroma `where` filter, for stores that
+/// evaluate filters in process.
+///
+/// Supports field equality, `$eq`, `$ne`, `$in`, `$nin`, `$contains` on
+/// list fields, and `$and`/`$or`.
+pub fn metadata_matches(
+    metadata: Option<&HashMap<String, serde_json::Value>>,
+    filter: &serde_json::Value,
+) -> bool {
+    let Some(filter) = filter.as_object() else {
+        return true;
+    };
+    filter.iter().all(|(key, condition)| match key.as_str() {
+        "$and" => condition
+            .as_array()
+            .is_some_and(|all| all.iter().all(|f| metadata_matches(metadata, f))),
+        "$or" => condition
+            .as_array()
+            .is_some_and(|any| any.iter().any(|f| metadata_matches(metadata, f))),
+        _ => {
+            let value = metadata.and_then(|m| m.get(key));
+            match condition.as_object() {
+                Some(ops) => ops.iter().all(|(op, operand)| match op.as_str() {
+                    "$eq" => value == Some(operand),
+                    "$ne" => value != Some(operand),
+                    "$in" => {
+                        value.is_some_and(|v| operand.as_array().is_some_and(|set| set.contains(v)))
+                    }
+                    "$nin" => !value
+                        .is_some_and(|v| operand.as_array().is_some_and(|set| set.contains(v))),
+                    "$contains" => value
+                        .and_then(|v| v.as_array())
+                        .is_some_and(|list| list.contains(operand)),
+                    _ => false,
+                }),
+                None => value == Some(condition),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_metadata_matches() {
+        let metadata = HashMap::from([
+            ("source_path".to_string(), json!("/a.txt")),
+            ("paths".to_string(), json!(["/a.txt", "/b.txt"])),
+        ]);
+        let metadata = Some(&metadata);
+        assert!(metadata_matches(
+            metadata,
+            &json!({"source_path": "/a.txt"})
+        ));
+        assert!(!metadata_matches(
+            metadata,
+            &json!({"source_path": "/b.txt"})
+        ));
+        assert!(metadata_matches(
+            metadata,
+            &json!({"paths": {"$contains": "/b.txt"}})
+        ));
+        assert!(metadata_matches(
+            metadata,
+            &json!({"source_path": {"$in": ["/a.txt", "/c.txt"]}})
+        ));
+        assert!(metadata_matches(
+            metadata,
+            &json!({"$and": [{"source_path": "/a.txt"}, {"paths": {"$contains": "/a.txt"}}]})
+        ));
+        assert!(!metadata_matches(None, &json!({"source_path": "/a.txt"})));
+    }
+}
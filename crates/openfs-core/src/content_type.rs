@@ -0,0 +1,200 @@
+//! Content-type detection from magic bytes and file extensions.
+//!
+//! [`detect_content_type`] trusts the leading bytes of a file over its name,
+//! since formats like PNG or gzip announce themselves, then falls back to the
+//! extension and finally to whether the content looks like text.
+//! [`is_binary_content`] is the check grep and indexing use to skip files.
+
+/// MIME type of content that is neither recognized nor text.
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// MIME type of unrecognized text.
+pub const TEXT_PLAIN: &str = "text/plain";
+
+/// How many leading bytes are inspected.
+pub const SNIFF_LEN: usize = 8192;
+
+/// Leading byte signatures, checked in order. `None` bytes match anything.
+const MAGIC: &[(&[Option<u8>], &str)] = &[
+    (&bytes(b"\x89PNG\r\n\x1a\n"), "image/png"),
+    (&bytes(b"\xff\xd8\xff"), "image/jpeg"),
+    (&bytes(b"GIF87a"), "image/gif"),
+    (&bytes(b"GIF89a"), "image/gif"),
+    (
+        &[
+            Some(b'R'),
+            Some(b'I'),
+            Some(b'F'),
+            Some(b'F'),
+            None,
+            None,
+            None,
+            None,
+            Some(b'W'),
+            Some(b'E'),
+            Some(b'B'),
+            Some(b'P'),
+        ],
+        "image/webp",
+    ),
+    (&bytes(b"%PDF-"), "application/pdf"),
+    (&bytes(b"PK\x03\x04"), "application/zip"),
+    (&bytes(b"\x1f\x8b"), "application/gzip"),
+    (&bytes(b"BZh"), "application/x-bzip2"),
+    (&bytes(b"7z\xbc\xaf\x27\x1c"), "application/x-7z-compressed"),
+    (&bytes(b"\x28\xb5\x2f\xfd"), "application/zstd"),
+    (&bytes(b"\0asm"), "application/wasm"),
+    (&bytes(b"\x7fELF"), "application/x-elf"),
+    (&bytes(b"SQLite format 3\0"), "application/vnd.sqlite3"),
+    (&bytes(b"ID3"), "audio/mpeg"),
+    (&bytes(b"OggS"), "audio/ogg"),
+    (
+        &[
+            None,
+            None,
+            None,
+            None,
+            Some(b'f'),
+            Some(b't'),
+            Some(b'y'),
+            Some(b'p'),
+        ],
+        "video/mp4",
+    ),
+];
+
+const fn bytes<const N: usize>(signature: &[u8; N]) -> [Option<u8>; N] {
+    let mut out = [None; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = Some(signature[i]);
+        i += 1;
+    }
+    out
+}
+
+/// The content type announced by the leading bytes of `content`, if any.
+pub fn content_type_from_magic(content: &[u8]) -> Option<&'static str> {
+    MAGIC.iter().find_map(|(signature, content_type)| {
+        let matches = content.len() >= signature.len()
+            && signature
+                .iter()
+                .zip(content)
+                .all(|(expected, byte)| expected.is_none_or(|b| b == *byte));
+        matches.then_some(*content_type)
+    })
+}
+
+/// The content type usually stored under `path`'s extension, if known.
+pub fn content_type_for_path(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (_, ext) = name.rsplit_once('.')?;
+    let content_type = match ext.to_ascii_lowercase().as_str() {
+        "md" | "markdown" => "text/markdown",
+        "txt" | "log" | "rst" | "adoc" | "org" => TEXT_PLAIN,
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        "jsonl" | "ndjson" => "application/x-ndjson",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "js" | "mjs" | "cjs" => "text/javascript",
+        "ts" | "tsx" => "text/x-typescript",
+        "rs" => "text/x-rust",
+        "py" => "text/x-python",
+        "go" => "text/x-go",
+        "java" => "text/x-java",
+        "c" | "h" => "text/x-c",
+        "cpp" | "cc" | "hpp" => "text/x-c++",
+        "sh" | "bash" | "zsh" => "text/x-shellscript",
+        "sql" => "application/sql",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "wasm" => "application/wasm",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "db" | "sqlite" => "application/vnd.sqlite3",
+        _ => return None,
+    };
+    Some(content_type)
+}
+
+/// Whether `content_type` is a text format.
+pub fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || matches!(
+            content_type,
+            "application/json"
+                | "application/x-ndjson"
+                | "application/xml"
+                | "application/yaml"
+                | "application/toml"
+                | "application/sql"
+                | "image/svg+xml"
+        )
+}
+
+/// Whether `content` is binary: a known binary format, or NUL bytes among
+/// its first [`SNIFF_LEN`] bytes.
+pub fn is_binary_content(content: &[u8]) -> bool {
+    if content_type_from_magic(content).is_some_and(|t| !is_text_content_type(t)) {
+        return true;
+    }
+    content[..content.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// The content type of `content` stored at `path`.
+pub fn detect_content_type(path: &str, content: &[u8]) -> &'static str {
+    if let Some(content_type) = content_type_from_magic(content) {
+        return content_type;
+    }
+    if is_binary_content(content) {
+        return OCTET_STREAM;
+    }
+    content_type_for_path(path).unwrap_or(TEXT_PLAIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_bytes_win_over_extension() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(detect_content_type("/notes.txt", png), "image/png");
+        assert_eq!(
+            detect_content_type("/a.bin", b"RIFF\x10\0\0\0WEBPVP8 "),
+            "image/webp"
+        );
+        assert_eq!(
+            detect_content_type("/clip", b"\0\0\0\x18ftypmp42"),
+            "video/mp4"
+        );
+        assert!(is_binary_content(png));
+        assert!(is_binary_content(b"\x1f\x8bcompressed"));
+    }
+
+    #[test]
+    fn test_extension_and_text_fallback() {
+        assert_eq!(
+            detect_content_type("/a/README.MD", b"# hi"),
+            "text/markdown"
+        );
+        assert_eq!(detect_content_type("/data.json", b"{}"), "application/json");
+        assert_eq!(detect_content_type("/Makefile", b"all:\n"), TEXT_PLAIN);
+        // Valid UTF-8, but NUL bytes give a binary file away.
+        assert_eq!(detect_content_type("/data.json", b"{\0\0}"), OCTET_STREAM);
+        assert!(!is_binary_content("caf\u{e9}".as_bytes()));
+        assert!(!is_binary_content(b""));
+        assert_eq!(content_type_for_path("/dir.d/noext"), None);
+    }
+}
@@ -0,0 +1,459 @@
+//! Line-based unified diffs (the inverse of [`crate::apply_patch`]) and
+//! three-way merges.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Produce a unified diff turning `old` into `new`.
+///
+/// Returns an empty string when the inputs are identical. The output applies
+/// cleanly with [`crate::apply_patch`].
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+) -> String {
+    if old == new {
+        return String::new();
+    }
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_lines(&a, &b);
+
+    // Line positions in `a` and `b` before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Equal).collect();
+    let mut c = 0;
+    while c < changes.len() {
+        let start = changes[c].saturating_sub(context);
+        let mut last = changes[c];
+        // Merge changes whose context would overlap.
+        while c + 1 < changes.len() && changes[c + 1] <= last + 2 * context + 1 {
+            c += 1;
+            last = changes[c];
+        }
+        let end = (last + 1 + context).min(ops.len());
+        c += 1;
+
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for k in start..end {
+            let (prefix, line) = match ops[k] {
+                Op::Equal => (' ', a[positions[k].0]),
+                Op::Delete => ('-', a[positions[k].0]),
+                Op::Insert => ('+', b[positions[k].1]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Format `start,len` for a hunk header (1-indexed; an empty range names the
+/// line before it).
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// Shortest edit script between two line sequences (Myers' algorithm).
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<Op> {
+    // Common prefix and suffix need no search.
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops = vec![Op::Equal; prefix];
+    ops.extend(myers(a_mid, b_mid));
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+fn myers(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    if max == 0 {
+        return Vec::new();
+    }
+    let offset = max as isize;
+    let index = |k: isize| (k + offset) as usize;
+    // Take the diagonal reached from k+1 (an insertion) rather than k-1.
+    let down =
+        |v: &[isize], k: isize, d: isize| k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]);
+
+    let mut v = vec![0isize; 2 * max + 2];
+    let mut trace = Vec::new();
+    'search: for d in 0..=offset {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if down(&v, k, d) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if down(v, k, d) { k + 1 } else { k - 1 };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(Op::Insert);
+            } else {
+                ops.push(Op::Delete);
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Three-way merge of text: apply the changes `ours` and `theirs` each made
+/// to `base`.
+///
+/// Returns `None` when both sides changed the same or adjacent lines in
+/// different ways.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    if ours == theirs || base == theirs {
+        return Some(ours.to_string());
+    }
+    if base == ours {
+        return Some(theirs.to_string());
+    }
+    let merge = merge3_with_markers(base, ours, theirs, "ours", "theirs");
+    merge.conflicts.is_empty().then_some(merge.text)
+}
+
+/// The result of [`merge3_with_markers`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MergeResult {
+    /// The merged text, with conflict markers around each conflict.
+    pub text: String,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Lines that `ours` and `theirs` changed in different ways.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MergeConflict {
+    /// Line of the `<<<<<<<` marker in the merged text (1-indexed).
+    pub line: usize,
+    /// First line of the base the conflict replaces (1-indexed), or the line
+    /// after the insertion point when both sides only inserted.
+    pub base_start: usize,
+    /// Number of base lines the conflict replaces.
+    pub base_len: usize,
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+/// Three-way merge that, where [`merge3`] gives up, writes both versions
+/// between diff3-style conflict markers and reports them:
+///
+/// ```text
+/// <<<<<<< ours
+/// our lines
+/// ||||||| base
+/// base lines
+/// =======
+/// their lines
+/// >>>>>>> theirs
+/// ```
+pub fn merge3_with_markers(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    ours_label: &str,
+    theirs_label: &str,
+) -> MergeResult {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let ours_hunks = hunks(&base_lines, &ours_lines);
+    let theirs_hunks = hunks(&base_lines, &theirs_lines);
+
+    let mut merged = String::with_capacity(ours.len().max(theirs.len()));
+    let mut conflicts = Vec::new();
+    let (mut i, mut j, mut pos) = (0, 0, 0);
+    loop {
+        // The next region of base touched by either side: the first hunk,
+        // grown by every hunk from either side that overlaps or adjoins it.
+        let (start, mut end) = match (ours_hunks.get(i), theirs_hunks.get(j)) {
+            (None, None) => break,
+            (Some(a), Some(b)) if b.start < a.start => (b.start, b.end),
+            (Some(a), _) => (a.start, a.end),
+            (None, Some(b)) => (b.start, b.end),
+        };
+        let (first_ours, first_theirs) = (i, j);
+        loop {
+            if ours_hunks.get(i).is_some_and(|h| h.start <= end) {
+                end = end.max(ours_hunks[i].end);
+                i += 1;
+            } else if theirs_hunks.get(j).is_some_and(|h| h.start <= end) {
+                end = end.max(theirs_hunks[j].end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        merged.extend(base_lines[pos..start].iter().copied());
+        pos = end;
+        let region = |hunks: &[Hunk]| apply_hunks(&base_lines, start, end, hunks);
+        let ours_text = region(&ours_hunks[first_ours..i]);
+        let theirs_text = region(&theirs_hunks[first_theirs..j]);
+        if first_theirs == j || ours_text == theirs_text {
+            merged.push_str(&ours_text);
+            continue;
+        }
+        if first_ours == i {
+            merged.push_str(&theirs_text);
+            continue;
+        }
+
+        let base_text: String = base_lines[start..end].concat();
+        conflicts.push(MergeConflict {
+            line: merged.matches('\n').count() + 1 + usize::from(!ends_line(&merged)),
+            base_start: start + 1,
+            base_len: end - start,
+            base: base_text.clone(),
+            ours: ours_text.clone(),
+            theirs: theirs_text.clone(),
+        });
+        if !ends_line(&merged) {
+            merged.push('\n');
+        }
+        for (marker, text) in [
+            (format!("<<<<<<< {}\n", ours_label), ours_text),
+            ("||||||| base\n".to_string(), base_text),
+            ("=======\n".to_string(), theirs_text),
+        ] {
+            merged.push_str(&marker);
+            merged.push_str(&text);
+            if !ends_line(&text) {
+                merged.push('\n');
+            }
+        }
+        merged.push_str(&format!(">>>>>>> {}\n", theirs_label));
+    }
+    merged.extend(base_lines[pos..].iter().copied());
+    MergeResult {
+        text: merged,
+        conflicts,
+    }
+}
+
+/// Whether `text` is empty or ends with a newline.
+fn ends_line(text: &str) -> bool {
+    text.is_empty() || text.ends_with('\n')
+}
+
+/// Lines `start..end` of `base` with `hunks` (all within them) applied.
+fn apply_hunks(base: &[&str], start: usize, end: usize, hunks: &[Hunk]) -> String {
+    let mut text = String::new();
+    let mut pos = start;
+    for hunk in hunks {
+        text.extend(base[pos..hunk.start].iter().copied());
+        text.extend(hunk.lines.iter().copied());
+        pos = hunk.end;
+    }
+    text.extend(base[pos..end].iter().copied());
+    text
+}
+
+/// Lines `start..end` of the base replaced by `lines`.
+#[derive(Debug, PartialEq, Eq)]
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// The changes turning `base` into `new`, in order.
+fn hunks<'a>(base: &[&str], new: &[&'a str]) -> Vec<Hunk<'a>> {
+    let mut hunks = Vec::new();
+    let mut current: Option<Hunk<'a>> = None;
+    let (mut i, mut j) = (0, 0);
+    for op in diff_lines(base, new) {
+        match op {
+            Op::Equal => {
+                hunks.extend(current.take());
+                i += 1;
+                j += 1;
+            }
+            Op::Delete => {
+                current
+                    .get_or_insert(Hunk {
+                        start: i,
+                        end: i,
+                        lines: Vec::new(),
+                    })
+                    .end = i + 1;
+                i += 1;
+            }
+            Op::Insert => {
+                current
+                    .get_or_insert(Hunk {
+                        start: i,
+                        end: i,
+                        lines: Vec::new(),
+                    })
+                    .lines
+                    .push(new[j]);
+                j += 1;
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_patch;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+        let diff = unified_diff(old, new, "a/x", "b/x", 1);
+        assert_eq!(
+            diff,
+            "--- a/x\n+++ b/x\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -8,1 +8,2 @@\n h\n+i\n"
+        );
+        assert_eq!(unified_diff(old, old, "a", "b", 3), "");
+    }
+
+    #[test]
+    fn test_unified_diff_round_trips() {
+        let cases = [
+            ("", "new file\n"),
+            ("one\ntwo\n", ""),
+            ("x\ny", "x\ny\n"),
+            (
+                "1\n2\n3\n4\n5\n6\n7\n8\n9\n",
+                "0\n1\n2\n4\n5\n6\nseven\n8\n9\n10",
+            ),
+            ("same\n", "other\n"),
+        ];
+        for (old, new) in cases {
+            let diff = unified_diff(old, new, "a", "b", 3);
+            let (patched, _) = apply_patch(old, &diff).unwrap();
+            assert_eq!(patched, new, "diff:\n{}", diff);
+        }
+    }
+
+    #[test]
+    fn test_merge3() {
+        let base = "a\nb\nc\nd\ne\n";
+        // Changes to different lines combine.
+        assert_eq!(
+            merge3(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\nf\n").unwrap(),
+            "A\nb\nc\nd\nE\nf\n"
+        );
+        // The same change on both sides is taken once.
+        assert_eq!(
+            merge3(base, "a\nB\nc\nd\nx\n", "a\nB\nc\nd\ne\n").unwrap(),
+            "a\nB\nc\nd\nx\n"
+        );
+        // Different changes to the same line conflict.
+        assert!(merge3(base, "a\nB\nc\nd\ne\n", "a\nb2\nc\nd\ne\n").is_none());
+        assert_eq!(merge3(base, base, "new\n").unwrap(), "new\n");
+    }
+
+    #[test]
+    fn test_merge3_with_markers() {
+        let base = "a\nb\nc\nd\ne\n";
+        let merge =
+            merge3_with_markers(base, "a\nB\nc\nd\nE\n", "a\nb2\nc\nd\ne\n", "mine", "yours");
+        assert_eq!(
+            merge.text,
+            "a\n<<<<<<< mine\nB\n||||||| base\nb\n=======\nb2\n>>>>>>> yours\nc\nd\nE\n"
+        );
+        assert_eq!(
+            merge.conflicts,
+            [MergeConflict {
+                line: 2,
+                base_start: 2,
+                base_len: 1,
+                base: "b\n".to_string(),
+                ours: "B\n".to_string(),
+                theirs: "b2\n".to_string(),
+            }]
+        );
+
+        // Without conflicts the text matches merge3.
+        let clean = merge3_with_markers(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\n", "o", "t");
+        assert!(clean.conflicts.is_empty());
+        assert_eq!(clean.text, "A\nb\nc\nd\nE\n");
+
+        // Conflicting lines without a final newline still get their own
+        // marker lines.
+        let merge = merge3_with_markers("x", "y", "z", "o", "t");
+        assert_eq!(
+            merge.text,
+            "<<<<<<< o\ny\n||||||| base\nx\n=======\nz\n>>>>>>> t\n"
+        );
+    }
+}
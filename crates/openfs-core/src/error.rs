@@ -1,3 +1,91 @@
+/// Stable, machine-readable error codes, shared by every surface (the CLI,
+/// the MCP server and the NFS server) so clients can match on a code rather
+/// than parse messages. Codes are never renamed; new ones may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The path does not exist.
+    NotFound,
+    /// A directory was expected.
+    NotADirectory,
+    /// The path is malformed or refused by a path policy.
+    InvalidPath,
+    /// The backend or a policy denied access.
+    PermissionDenied,
+    /// The mount is read-only.
+    ReadOnly,
+    /// No mount covers the path.
+    NoMount,
+    /// A conditional write found a different version than expected.
+    PreconditionFailed,
+    /// The backend could not be reached or is failing; retrying may help.
+    BackendUnavailable,
+    /// The backend did not answer in time.
+    Timeout,
+    /// The backend is rate limiting requests.
+    Throttled,
+    /// The configuration is invalid.
+    InvalidConfig,
+    /// A patch or string replacement did not apply.
+    EditFailed,
+    /// A local IO error.
+    IoError,
+    /// Watching for changes failed.
+    WatchFailed,
+    /// Indexing failed.
+    IndexingFailed,
+    /// A tool call named an unknown tool or had bad arguments.
+    InvalidArgument,
+    /// Any other failure.
+    Internal,
+}
+
+impl ErrorCode {
+    /// The code as it appears on the wire, e.g. `NOT_FOUND`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::NotADirectory => "NOT_A_DIRECTORY",
+            ErrorCode::InvalidPath => "INVALID_PATH",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::ReadOnly => "READ_ONLY",
+            ErrorCode::NoMount => "NO_MOUNT",
+            ErrorCode::PreconditionFailed => "PRECONDITION_FAILED",
+            ErrorCode::BackendUnavailable => "BACKEND_UNAVAILABLE",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Throttled => "THROTTLED",
+            ErrorCode::InvalidConfig => "INVALID_CONFIG",
+            ErrorCode::EditFailed => "EDIT_FAILED",
+            ErrorCode::IoError => "IO_ERROR",
+            ErrorCode::WatchFailed => "WATCH_FAILED",
+            ErrorCode::IndexingFailed => "INDEXING_FAILED",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    fn from_io(e: &std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            std::io::ErrorKind::NotADirectory => ErrorCode::NotADirectory,
+            std::io::ErrorKind::ReadOnlyFilesystem => ErrorCode::ReadOnly,
+            std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+            std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted => ErrorCode::BackendUnavailable,
+            _ => ErrorCode::IoError,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Errors that can occur in backend operations.
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -38,6 +126,26 @@ pub enum BackendError {
     #[error("Operation '{operation}' timed out for path: {path}")]
     Timeout { operation: String, path: String },
 
+    /// The backend asked the client to slow down, optionally saying when to retry.
+    #[error("Backend '{backend}' is throttling requests")]
+    Throttled {
+        backend: String,
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// The backend failed on its side, such as an HTTP 500; the request may
+    /// succeed later.
+    #[error("Backend '{backend}' is unavailable: {message}")]
+    Unavailable { backend: String, message: String },
+
+    /// The backend's circuit breaker is open after repeated failures, so the
+    /// request was not sent.
+    #[error("Backend '{backend}' is failing; requests are paused for {retry_after:?}")]
+    CircuitOpen {
+        backend: String,
+        retry_after: std::time::Duration,
+    },
+
     /// IO error.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -53,6 +161,9 @@ impl BackendError {
         match self {
             BackendError::ConnectionFailed { .. } => true,
             BackendError::Timeout { .. } => true,
+            BackendError::Throttled { .. } => true,
+            BackendError::Unavailable { .. } => true,
+            BackendError::CircuitOpen { .. } => true,
             BackendError::Io(e) => matches!(
                 e.kind(),
                 std::io::ErrorKind::ConnectionRefused
@@ -64,6 +175,33 @@ impl BackendError {
             _ => false,
         }
     }
+
+    /// The stable code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            BackendError::NotFound(_) => ErrorCode::NotFound,
+            BackendError::NotADirectory(_) => ErrorCode::NotADirectory,
+            BackendError::PathTraversal(_) => ErrorCode::InvalidPath,
+            BackendError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            BackendError::PreconditionFailed { .. } => ErrorCode::PreconditionFailed,
+            BackendError::ConnectionFailed { .. }
+            | BackendError::Unavailable { .. }
+            | BackendError::CircuitOpen { .. } => ErrorCode::BackendUnavailable,
+            BackendError::Timeout { .. } => ErrorCode::Timeout,
+            BackendError::Throttled { .. } => ErrorCode::Throttled,
+            BackendError::Io(e) => ErrorCode::from_io(e),
+            BackendError::Other(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// How long the backend asked to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            BackendError::Throttled { retry_after, .. } => *retry_after,
+            BackendError::CircuitOpen { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
 }
 
 /// Errors that can occur during VFS operations.
@@ -84,6 +222,10 @@ pub enum VfsError {
     #[error("Path not found: {0}")]
     NotFound(String),
 
+    /// Path is refused by its mount's path policy.
+    #[error("Invalid path '{path}': {reason}")]
+    InvalidPath { path: String, reason: String },
+
     /// Backend-specific error.
     #[error("Backend error: {0}")]
     Backend(Box<dyn std::error::Error + Send + Sync>),
@@ -103,6 +245,86 @@ pub enum VfsError {
     /// Indexing-related error.
     #[error("Indexing error: {0}")]
     Indexing(String),
+
+    /// A patch or string replacement could not be applied to a file.
+    #[error("Edit failed for '{path}': {source}")]
+    Patch {
+        path: String,
+        #[source]
+        source: crate::patch::PatchError,
+    },
+
+    /// A tool call named an unknown tool or its arguments do not match the
+    /// tool's schema.
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+}
+
+impl VfsError {
+    /// Returns true if this error wraps a transient backend or IO failure.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            VfsError::Backend(boxed) => boxed
+                .downcast_ref::<BackendError>()
+                .is_some_and(BackendError::is_transient),
+            VfsError::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+            ),
+            _ => false,
+        }
+    }
+
+    /// The stable code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            VfsError::NoMount(_) => ErrorCode::NoMount,
+            VfsError::ReadOnly(_) => ErrorCode::ReadOnly,
+            VfsError::NotFound(_) => ErrorCode::NotFound,
+            VfsError::InvalidPath { .. } => ErrorCode::InvalidPath,
+            VfsError::Backend(boxed) => boxed
+                .downcast_ref::<BackendError>()
+                .map_or(ErrorCode::Internal, BackendError::code),
+            VfsError::Io(e) => ErrorCode::from_io(e),
+            VfsError::Config(_) => ErrorCode::InvalidConfig,
+            VfsError::Watch(_) => ErrorCode::WatchFailed,
+            VfsError::Indexing(_) => ErrorCode::IndexingFailed,
+            VfsError::Patch { .. } => ErrorCode::EditFailed,
+            VfsError::InvalidArgument(_) => ErrorCode::InvalidArgument,
+        }
+    }
+
+    /// The error as a JSON object with its `code`, `message`, whether
+    /// retrying may help and the current request ID, for clients that handle
+    /// errors programmatically.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "retryable": self.is_transient(),
+        });
+        if let Some(retry_after) = self.retry_after() {
+            body["retry_after_ms"] = (retry_after.as_millis() as u64).into();
+        }
+        if let Some(request_id) = crate::current_request_id() {
+            body["request_id"] = request_id.into();
+        }
+        body
+    }
+
+    /// How long the backend asked to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            VfsError::Backend(boxed) => boxed
+                .downcast_ref::<BackendError>()
+                .and_then(BackendError::retry_after),
+            _ => None,
+        }
+    }
 }
 
 impl From<BackendError> for VfsError {
@@ -149,6 +371,49 @@ mod tests {
         assert!(!err.is_transient());
     }
 
+    #[test]
+    fn test_vfs_is_transient() {
+        let timeout: VfsError = BackendError::Timeout {
+            operation: "read".to_string(),
+            path: "/foo".to_string(),
+        }
+        .into();
+        assert!(timeout.is_transient());
+
+        let reset = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        assert!(VfsError::Io(reset).is_transient());
+        assert!(!VfsError::NotFound("/missing".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_throttled_retry_after() {
+        let err: VfsError = BackendError::Throttled {
+            backend: "s3".to_string(),
+            retry_after: Some(std::time::Duration::from_secs(3)),
+        }
+        .into();
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(3)));
+        assert_eq!(VfsError::NotFound("/a".to_string()).retry_after(), None);
+    }
+
+    #[test]
+    fn test_unavailable_and_circuit_open_are_transient() {
+        let err = BackendError::Unavailable {
+            backend: "s3".to_string(),
+            message: "500 Internal Server Error".to_string(),
+        };
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), None);
+
+        let err = BackendError::CircuitOpen {
+            backend: "s3".to_string(),
+            retry_after: std::time::Duration::from_secs(10),
+        };
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(10)));
+    }
+
     #[test]
     fn test_vfs_from_backend_not_found() {
         let backend_err = BackendError::NotFound("/missing".to_string());
@@ -178,6 +443,37 @@ mod tests {
         assert!(matches!(vfs_err, VfsError::Config(_)));
     }
 
+    #[test]
+    fn test_error_codes() {
+        let precondition: VfsError = BackendError::PreconditionFailed {
+            path: "/a".to_string(),
+            expected: "1".to_string(),
+            actual: "2".to_string(),
+        }
+        .into();
+        assert_eq!(precondition.code(), ErrorCode::PreconditionFailed);
+        assert_eq!(
+            VfsError::ReadOnly("/docs".to_string()).code(),
+            ErrorCode::ReadOnly
+        );
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(VfsError::Io(denied).code(), ErrorCode::PermissionDenied);
+        let unavailable: VfsError = BackendError::CircuitOpen {
+            backend: "s3".to_string(),
+            retry_after: std::time::Duration::from_millis(1500),
+        }
+        .into();
+        assert_eq!(unavailable.code(), ErrorCode::BackendUnavailable);
+
+        let body = unavailable.to_json();
+        assert_eq!(body["code"], "BACKEND_UNAVAILABLE");
+        assert_eq!(body["retryable"], true);
+        assert_eq!(body["retry_after_ms"], 1500);
+        let body = VfsError::NotFound("/a".to_string()).to_json();
+        assert_eq!(body["code"], ErrorCode::NotFound.as_str());
+        assert!(body.get("retry_after_ms").is_none());
+    }
+
     #[test]
     fn test_display_no_mount() {
         let err = VfsError::NoMount("/foo".to_string());
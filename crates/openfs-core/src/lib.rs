@@ -1,15 +1,38 @@
 mod cache;
 mod chroma;
+mod content_type;
+mod diff;
 mod error;
 mod metrics;
+mod patch;
 mod path_trie;
+mod request_id;
+mod sanitize;
+mod search;
 mod tools;
 mod traits;
 
-pub use cache::{create_cache, CacheConfig, CacheStats, LruCache, SharedCache};
+pub use cache::{
+    create_cache, CacheConfig, CacheStats, DiskCacheConfig, EvictionPolicy, LruCache, SharedCache,
+};
 pub use path_trie::PathTrie;
-pub use chroma::{ChromaStore, QueryResult, SparseEmbedding, TextEmbedder};
-pub use error::{BackendError, VfsError};
+pub use chroma::{
+    metadata_matches, ChromaStore, QueryResult, SparseEmbedding, StoredDocument, TextEmbedder,
+    ACTIVE_COLLECTION_KEY,
+};
+pub use content_type::{
+    content_type_for_path, content_type_from_magic, detect_content_type, is_binary_content,
+    is_text_content_type, OCTET_STREAM, SNIFF_LEN, TEXT_PLAIN,
+};
+pub use diff::{merge3, merge3_with_markers, unified_diff, MergeConflict, MergeResult};
+pub use error::{BackendError, ErrorCode, VfsError};
 pub use metrics::{create_metrics, MetricsSnapshot, SharedMetrics, VfsMetrics};
-pub use tools::{format_tools, generate_tools, ToolDefinition, ToolFormat, ToolParameter};
-pub use traits::{Backend, Entry};
+pub use patch::{apply_patch, patch_creates_file, str_replace, PatchError, PatchSummary};
+pub use request_id::{current_request_id, new_request_id, with_request_id};
+pub use sanitize::{sanitize_path, PathSanitizer, PathViolation, MAX_COMPONENT_LEN};
+pub use search::{SearchHit, SearchOptions, Searcher};
+pub use tools::{
+    allowed_mounts_note, find_tool, format_tools, generate_tools, ToolDefinition, ToolFormat,
+    ToolParameter,
+};
+pub use traits::{slice_range, Backend, Entry, FileMeta, UploadedPart};
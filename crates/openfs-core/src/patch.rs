@@ -0,0 +1,509 @@
+//! Targeted text edits: unified diffs and string replacement.
+
+/// Errors from parsing or applying a unified diff or string replacement.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    /// The diff is malformed.
+    #[error("Invalid patch at line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    /// The diff changes more than one file.
+    #[error("Patch touches more than one file")]
+    MultipleFiles,
+
+    /// `str_replace` found no occurrence of the target string.
+    #[error("String to replace was not found")]
+    NoMatch,
+
+    /// `str_replace` found several occurrences and none was selected.
+    #[error("String to replace occurs {count} times; pass an occurrence or add context")]
+    AmbiguousMatch { count: usize },
+
+    /// `str_replace` was asked for an occurrence that does not exist.
+    #[error("Occurrence {occurrence} requested but the string occurs {count} times")]
+    OccurrenceOutOfRange { occurrence: usize, count: usize },
+
+    /// `str_replace` was given an empty target string.
+    #[error("String to replace must not be empty")]
+    EmptyMatch,
+
+    /// The target file is not UTF-8 text.
+    #[error("Cannot patch a binary file")]
+    BinaryFile,
+
+    /// A hunk's context or removed lines do not match the file.
+    #[error("Hunk #{hunk} failed at line {line}: expected {expected:?}, found {actual:?}")]
+    Conflict {
+        /// 1-indexed hunk number.
+        hunk: usize,
+        /// 1-indexed line in the original file.
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Summary of an applied patch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchSummary {
+    /// Number of hunks applied.
+    pub hunks: usize,
+    /// Lines added.
+    pub added: usize,
+    /// Lines removed.
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    lines: Vec<HunkLine>,
+    /// The old side's last line has no trailing newline.
+    old_no_newline: bool,
+    /// The new side's last line has no trailing newline.
+    new_no_newline: bool,
+}
+
+impl Hunk {
+    fn old_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+            HunkLine::Add(_) => None,
+        })
+    }
+
+    /// Apply a `\ No newline at end of file` marker following a line of `kind`.
+    fn mark_no_newline(&mut self, kind: Option<char>) {
+        match kind {
+            Some('-') => self.old_no_newline = true,
+            Some('+') => self.new_no_newline = true,
+            _ => {
+                self.old_no_newline = true;
+                self.new_no_newline = true;
+            }
+        }
+    }
+
+    fn new_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+            HunkLine::Remove(_) => None,
+        })
+    }
+}
+
+/// Whether a unified diff creates a new file (`--- /dev/null` or a single
+/// `@@ -0,0` hunk).
+pub fn patch_creates_file(diff: &str) -> bool {
+    match parse(diff) {
+        Ok(hunks) => {
+            hunks.len() == 1 && hunks[0].old_start == 0 && hunks[0].old_lines().count() == 0
+        }
+        Err(_) => false,
+    }
+}
+
+/// Apply a single-file unified diff to `original`.
+///
+/// Hunks whose line numbers are stale are located by searching for their
+/// context, so patches still apply after unrelated edits elsewhere in the file.
+pub fn apply_patch(original: &str, diff: &str) -> Result<(String, PatchSummary), PatchError> {
+    let hunks = parse(diff)?;
+
+    let ends_with_newline = original.is_empty() || original.ends_with('\n');
+    // Split on '\n' only, so carriage returns in CRLF files are kept and compared.
+    let mut lines: Vec<&str> = original.split('\n').collect();
+    if ends_with_newline {
+        lines.pop();
+    }
+
+    let mut output: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut summary = PatchSummary::default();
+    let mut cursor = 0usize;
+    let mut new_no_newline = None;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old: Vec<&str> = hunk.old_lines().collect();
+        // A hunk without old lines inserts after line `old_start`.
+        let expected_at = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        }
+        .max(cursor);
+        let at = find_hunk(&lines, &old, expected_at, cursor)
+            .ok_or_else(|| conflict(index + 1, &lines, &old, expected_at.min(lines.len())))?;
+
+        output.extend_from_slice(&lines[cursor..at]);
+        output.extend(hunk.new_lines());
+        cursor = at + old.len();
+
+        summary.hunks += 1;
+        for line in &hunk.lines {
+            match line {
+                HunkLine::Add(_) => summary.added += 1,
+                HunkLine::Remove(_) => summary.removed += 1,
+                HunkLine::Context(_) => {}
+            }
+        }
+
+        if cursor == lines.len() {
+            if hunk.new_no_newline {
+                new_no_newline = Some(true);
+            } else if hunk.old_no_newline {
+                new_no_newline = Some(false);
+            }
+        }
+    }
+    output.extend_from_slice(&lines[cursor..]);
+
+    let trailing_newline = match new_no_newline {
+        Some(no_newline) => !no_newline,
+        None => ends_with_newline,
+    };
+    let mut content = output.join("\n");
+    if trailing_newline && !output.is_empty() {
+        content.push('\n');
+    }
+    Ok((content, summary))
+}
+
+/// Replace one occurrence of `old` with `new` in `content`.
+///
+/// Without `occurrence`, `old` must occur exactly once. With it, the
+/// `occurrence`-th match (1-indexed) is replaced. Returns the new content and
+/// the 1-indexed line where the replacement starts.
+pub fn str_replace(
+    content: &str,
+    old: &str,
+    new: &str,
+    occurrence: Option<usize>,
+) -> Result<(String, usize), PatchError> {
+    if old.is_empty() {
+        return Err(PatchError::EmptyMatch);
+    }
+
+    let matches: Vec<usize> = content.match_indices(old).map(|(i, _)| i).collect();
+    let offset = match (occurrence, matches.len()) {
+        (_, 0) => return Err(PatchError::NoMatch),
+        (None, 1) => matches[0],
+        (None, count) => return Err(PatchError::AmbiguousMatch { count }),
+        (Some(n), count) if n == 0 || n > count => {
+            return Err(PatchError::OccurrenceOutOfRange {
+                occurrence: n,
+                count,
+            })
+        }
+        (Some(n), _) => matches[n - 1],
+    };
+
+    let line = content[..offset].matches('\n').count() + 1;
+    let mut replaced = String::with_capacity(content.len() - old.len() + new.len());
+    replaced.push_str(&content[..offset]);
+    replaced.push_str(new);
+    replaced.push_str(&content[offset + old.len()..]);
+    Ok((replaced, line))
+}
+
+/// Find where `old` matches `lines`, preferring `expected_at` and never
+/// starting before `min_at`.
+fn find_hunk(lines: &[&str], old: &[&str], expected_at: usize, min_at: usize) -> Option<usize> {
+    let matches_at = |at: usize| at + old.len() <= lines.len() && lines[at..at + old.len()] == *old;
+    if matches_at(expected_at) {
+        return Some(expected_at);
+    }
+    // Search outward from the expected position.
+    for distance in 1..=lines.len() {
+        let after = expected_at + distance;
+        if matches_at(after) {
+            return Some(after);
+        }
+        if let Some(before) = expected_at.checked_sub(distance) {
+            if before >= min_at && matches_at(before) {
+                return Some(before);
+            }
+        }
+    }
+    None
+}
+
+/// Describe the first mismatching line at the hunk's expected position.
+fn conflict(hunk: usize, lines: &[&str], old: &[&str], at: usize) -> PatchError {
+    for (offset, expected) in old.iter().enumerate() {
+        let actual = lines.get(at + offset).copied();
+        if actual != Some(*expected) {
+            return PatchError::Conflict {
+                hunk,
+                line: at + offset + 1,
+                expected: expected.to_string(),
+                actual: actual.unwrap_or("<end of file>").to_string(),
+            };
+        }
+    }
+    PatchError::Conflict {
+        hunk,
+        line: at + 1,
+        expected: old.first().unwrap_or(&"").to_string(),
+        actual: "<no match>".to_string(),
+    }
+}
+
+fn parse(diff: &str) -> Result<Vec<Hunk>, PatchError> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut current: Option<Hunk> = None;
+    let mut remaining = (0usize, 0usize);
+    let mut last_side = None;
+
+    // Split on '\n' only, so CRLF patches match CRLF files.
+    let diff = diff.strip_suffix('\n').unwrap_or(diff);
+    for (number, line) in diff.split('\n').enumerate() {
+        let number = number + 1;
+
+        if current.is_some() && remaining == (0, 0) {
+            hunks.extend(current.take());
+        }
+
+        if let Some(hunk) = current.as_mut() {
+            let (kind, text) = match line.chars().next() {
+                Some(' ') => (' ', &line[1..]),
+                Some('-') => ('-', &line[1..]),
+                Some('+') => ('+', &line[1..]),
+                Some('\\') => {
+                    hunk.mark_no_newline(last_side);
+                    continue;
+                }
+                // Some editors strip the single space from empty context lines.
+                None => (' ', ""),
+                Some(_) => {
+                    return Err(PatchError::Parse {
+                        line: number,
+                        message: format!("unexpected line in hunk: {:?}", line),
+                    })
+                }
+            };
+
+            let (old_left, new_left) = &mut remaining;
+            let ok = match kind {
+                ' ' => *old_left > 0 && *new_left > 0,
+                '-' => *old_left > 0,
+                _ => *new_left > 0,
+            };
+            if !ok {
+                return Err(PatchError::Parse {
+                    line: number,
+                    message: "hunk is longer than its header says".to_string(),
+                });
+            }
+            match kind {
+                ' ' => {
+                    *old_left -= 1;
+                    *new_left -= 1;
+                    hunk.lines.push(HunkLine::Context(text.to_string()));
+                }
+                '-' => {
+                    *old_left -= 1;
+                    hunk.lines.push(HunkLine::Remove(text.to_string()));
+                }
+                _ => {
+                    *new_left -= 1;
+                    hunk.lines.push(HunkLine::Add(text.to_string()));
+                }
+            }
+            last_side = Some(kind);
+            continue;
+        }
+
+        // A trailing "\ No newline" marker may follow a hunk that just ended.
+        if line.starts_with('\\') {
+            if let Some(hunk) = hunks.last_mut() {
+                hunk.mark_no_newline(last_side);
+            }
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let (hunk, counts) = parse_hunk_header(line).ok_or_else(|| PatchError::Parse {
+                line: number,
+                message: format!("invalid hunk header: {:?}", line),
+            })?;
+            current = Some(hunk);
+            remaining = counts;
+            last_side = None;
+        } else if line.starts_with("--- ") && !hunks.is_empty() {
+            return Err(PatchError::MultipleFiles);
+        }
+        // Other lines ("diff --git", "index", "+++", commentary) are ignored.
+    }
+
+    if let Some(hunk) = current.take() {
+        if remaining != (0, 0) {
+            return Err(PatchError::Parse {
+                line: diff.split('\n').count(),
+                message: "hunk is shorter than its header says".to_string(),
+            });
+        }
+        hunks.push(hunk);
+    }
+
+    if hunks.is_empty() {
+        return Err(PatchError::Parse {
+            line: 1,
+            message: "no hunks found".to_string(),
+        });
+    }
+    Ok(hunks)
+}
+
+/// Parse `@@ -a,b +c,d @@`, returning the hunk and its (old, new) line counts.
+fn parse_hunk_header(line: &str) -> Option<(Hunk, (usize, usize))> {
+    let body = line.strip_prefix("@@ ")?;
+    let end = body.find(" @@")?;
+    let mut ranges = body[..end].split_whitespace();
+    let (old_start, old_len) = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let (_, new_len) = parse_range(ranges.next()?.strip_prefix('+')?)?;
+    let hunk = Hunk {
+        old_start,
+        ..Default::default()
+    };
+    Some((hunk, (old_len, new_len)))
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "fn main() {\n    println!(\"hello\");\n}\n";
+
+    #[test]
+    fn test_apply_simple_patch() {
+        let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,4 @@\n fn main() {\n-    println!(\"hello\");\n+    println!(\"hello\");\n+    println!(\"world\");\n }\n";
+        let (content, summary) = apply_patch(ORIGINAL, diff).unwrap();
+        assert_eq!(
+            content,
+            "fn main() {\n    println!(\"hello\");\n    println!(\"world\");\n}\n"
+        );
+        assert_eq!(
+            summary,
+            PatchSummary {
+                hunks: 1,
+                added: 2,
+                removed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_with_offset() {
+        let original = format!("// header\n// more\n{}", ORIGINAL);
+        let diff = "@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"hello\");\n+    println!(\"bye\");\n }\n";
+        let (content, _) = apply_patch(&original, diff).unwrap();
+        assert_eq!(
+            content,
+            "// header\n// more\nfn main() {\n    println!(\"bye\");\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_conflict_reports_line() {
+        let diff = "@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"goodbye\");\n+    println!(\"bye\");\n }\n";
+        let err = apply_patch(ORIGINAL, diff).unwrap_err();
+        assert_eq!(
+            err,
+            PatchError::Conflict {
+                hunk: 1,
+                line: 2,
+                expected: "    println!(\"goodbye\");".to_string(),
+                actual: "    println!(\"hello\");".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_multiple_hunks_and_new_file() {
+        let original = "a\nb\nc\nd\ne\nf\n";
+        let diff = "@@ -1,2 +1,2 @@\n-a\n+A\n b\n@@ -5,2 +5,2 @@\n e\n-f\n+F\n";
+        let (content, summary) = apply_patch(original, diff).unwrap();
+        assert_eq!(content, "A\nb\nc\nd\ne\nF\n");
+        assert_eq!(summary.hunks, 2);
+
+        let diff = "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n";
+        assert!(patch_creates_file(diff));
+        let (content, _) = apply_patch("", diff).unwrap();
+        assert_eq!(content, "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_no_newline_markers() {
+        let diff = "@@ -1 +1 @@\n-old\n\\ No newline at end of file\n+new\n";
+        let (content, _) = apply_patch("old", diff).unwrap();
+        assert_eq!(content, "new\n");
+
+        let diff = "@@ -1 +1 @@\n-old\n+new\n\\ No newline at end of file\n";
+        let (content, _) = apply_patch("old\n", diff).unwrap();
+        assert_eq!(content, "new");
+    }
+
+    #[test]
+    fn test_str_replace() {
+        let content = "let a = 1;\nlet b = 1;\n";
+        assert_eq!(
+            str_replace(content, "b = 1", "b = 2", None).unwrap(),
+            ("let a = 1;\nlet b = 2;\n".to_string(), 2)
+        );
+        assert_eq!(
+            str_replace(content, "= 1", "= 3", None),
+            Err(PatchError::AmbiguousMatch { count: 2 })
+        );
+        assert_eq!(
+            str_replace(content, "= 1", "= 3", Some(2)).unwrap().0,
+            "let a = 1;\nlet b = 3;\n"
+        );
+        assert_eq!(
+            str_replace(content, "= 1", "= 3", Some(3)),
+            Err(PatchError::OccurrenceOutOfRange {
+                occurrence: 3,
+                count: 2
+            })
+        );
+        assert_eq!(
+            str_replace(content, "let c", "x", None),
+            Err(PatchError::NoMatch)
+        );
+        assert_eq!(
+            str_replace(content, "", "x", None),
+            Err(PatchError::EmptyMatch)
+        );
+    }
+
+    #[test]
+    fn test_invalid_patches() {
+        assert!(matches!(
+            apply_patch(ORIGINAL, "not a diff"),
+            Err(PatchError::Parse { .. })
+        ));
+        assert!(matches!(
+            apply_patch(ORIGINAL, "@@ -1,3 +1,3 @@\n fn main() {\n"),
+            Err(PatchError::Parse { .. })
+        ));
+        let two_files =
+            "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n--- a/y\n+++ b/y\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(
+            apply_patch("a\n", two_files),
+            Err(PatchError::MultipleFiles)
+        );
+    }
+}
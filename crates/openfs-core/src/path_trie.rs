@@ -147,6 +147,23 @@ impl PathTrie {
         node.children.keys().cloned().collect()
     }
 
+    /// The number of components of the longest path in the trie that is a
+    /// prefix of `path`, component-wise. `Some(0)` means only `/` matched.
+    pub fn longest_prefix(&self, path: &str) -> Option<usize> {
+        let mut node = &self.root;
+        let mut longest = node.is_terminal.then_some(0);
+        for (depth, component) in path.split('/').filter(|s| !s.is_empty()).enumerate() {
+            match node.children.get(component) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if node.is_terminal {
+                longest = Some(depth + 1);
+            }
+        }
+        longest
+    }
+
     /// Return all paths stored in the trie.
     pub fn all_paths(&self) -> Vec<String> {
         let mut result = Vec::new();
@@ -373,6 +390,21 @@ mod tests {
         assert_eq!(trie.len(), 1);
     }
 
+    #[test]
+    fn test_longest_prefix() {
+        let mut trie = PathTrie::new();
+        trie.insert("/a");
+        trie.insert("/a/b/c");
+        assert_eq!(trie.longest_prefix("/a/b/c/d.txt"), Some(3));
+        assert_eq!(trie.longest_prefix("/a/b/x"), Some(1));
+        assert_eq!(trie.longest_prefix("/a"), Some(1));
+        assert_eq!(trie.longest_prefix("/ab"), None);
+        assert_eq!(trie.longest_prefix("/"), None);
+
+        trie.insert("/");
+        assert_eq!(trie.longest_prefix("/ab"), Some(0));
+    }
+
     #[test]
     fn test_remove_subtree_root() {
         let mut trie = PathTrie::new();
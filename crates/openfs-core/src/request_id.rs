@@ -0,0 +1,64 @@
+//! Request IDs, which tie a client request to everything it causes.
+//!
+//! A server runs each request inside [`with_request_id`], with the ID the
+//! client sent or a fresh one from [`new_request_id`]. Code below it, in the
+//! Vfs and the backends, reads the ID with [`current_request_id`], and every
+//! tracing span and event inside is a child of a `request` span carrying it as
+//! `request_id`. Error bodies from [`VfsError::to_json`](crate::VfsError::to_json)
+//! include it, so a failure an agent reports can be found in the server logs.
+//!
+//! The ID is task-local: work handed to a spawned task, such as a write-back
+//! flush, runs without it.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tracing::Instrument;
+
+tokio::task_local! {
+    static REQUEST_ID: Arc<str>;
+}
+
+/// A new ID, unique within the process and unlikely to repeat across
+/// restarts.
+pub fn new_request_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let started = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_micros() as u64)
+        .unwrap_or_default();
+    let seq = NEXT.fetch_add(1, Ordering::Relaxed);
+    format!("{:012x}-{:04x}", started & 0xffff_ffff_ffff, seq & 0xffff)
+}
+
+/// The ID of the request being handled, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.to_string()).ok()
+}
+
+/// Run `future` as the request `id`.
+pub async fn with_request_id<F: Future>(id: impl Into<String>, future: F) -> F::Output {
+    let id: Arc<str> = id.into().into();
+    let span = tracing::info_span!("request", request_id = %id);
+    REQUEST_ID.scope(id, future.instrument(span)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_id_scope() {
+        assert_eq!(current_request_id(), None);
+        let seen = with_request_id("req-1", async {
+            tokio::task::yield_now().await;
+            current_request_id()
+        })
+        .await;
+        assert_eq!(seen.as_deref(), Some("req-1"));
+        assert_eq!(current_request_id(), None);
+
+        assert_ne!(new_request_id(), new_request_id());
+    }
+}
@@ -0,0 +1,216 @@
+//! Path sanitization shared by every entry point that takes a path from
+//! outside: the router, the MCP server and the NFS server.
+//!
+//! [`PathSanitizer::sanitize`] resolves `.` and `..` segments and always
+//! refuses paths that climb above the root, hold a NUL byte or have a
+//! component longer than [`MAX_COMPONENT_LEN`]. With
+//! [`deny_suspicious`](PathSanitizer::with_deny_suspicious) set, which the
+//! `security.deny_suspicious_paths` config switch does, it also refuses any
+//! `..` segment and control characters, and logs every path it refuses.
+
+use openfs_config::VfsConfig;
+
+/// Longest path component, in bytes, as on common filesystems.
+pub const MAX_COMPONENT_LEN: usize = 255;
+
+/// Why a path was refused.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum PathViolation {
+    /// A `..` segment climbs above the root.
+    #[error("'..' climbs above the root")]
+    Escape,
+    /// The path contains a NUL byte.
+    #[error("contains a NUL byte")]
+    NulByte,
+    /// A component is longer than [`MAX_COMPONENT_LEN`] bytes.
+    #[error("a component of {0} bytes is over the limit of {MAX_COMPONENT_LEN}")]
+    ComponentTooLong(usize),
+    /// A name is empty, `.`, `..` or contains a slash.
+    #[error("not a valid file name")]
+    InvalidName,
+    /// A `..` segment, refused when denying suspicious paths.
+    #[error("contains a '..' segment")]
+    ParentSegment,
+    /// A control character, refused when denying suspicious paths.
+    #[error("contains a control character")]
+    ControlChar,
+}
+
+/// Checks paths and names that come from clients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathSanitizer {
+    deny_suspicious: bool,
+}
+
+impl PathSanitizer {
+    /// The sanitizer `config`'s `security` section asks for.
+    pub fn from_config(config: &VfsConfig) -> Self {
+        let deny = config
+            .security
+            .as_ref()
+            .is_some_and(|security| security.deny_suspicious_paths);
+        PathSanitizer::default().with_deny_suspicious(deny)
+    }
+
+    /// Also refuse, and log, paths that are valid but look like probing.
+    pub fn with_deny_suspicious(mut self, deny: bool) -> Self {
+        self.deny_suspicious = deny;
+        self
+    }
+
+    /// Check `path` and return it absolute, with `.`, `..` and repeated or
+    /// trailing slashes resolved.
+    pub fn sanitize(&self, path: &str) -> Result<String, PathViolation> {
+        self.check_chars(path)
+            .and_then(|()| self.resolve(path))
+            .map_err(|violation| self.refused(path, violation))
+    }
+
+    /// Check a single file name, e.g. one a client wants to create.
+    pub fn check_name(&self, name: &str) -> Result<(), PathViolation> {
+        let checked = if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            Err(PathViolation::InvalidName)
+        } else {
+            self.check_chars(name)
+                .and_then(|()| check_component_len(name))
+        };
+        checked.map_err(|violation| self.refused(name, violation))
+    }
+
+    fn check_chars(&self, path: &str) -> Result<(), PathViolation> {
+        if path.contains('\0') {
+            return Err(PathViolation::NulByte);
+        }
+        if self.deny_suspicious && path.chars().any(char::is_control) {
+            return Err(PathViolation::ControlChar);
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, path: &str) -> Result<String, PathViolation> {
+        let mut parts: Vec<&str> = Vec::new();
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." if self.deny_suspicious => return Err(PathViolation::ParentSegment),
+                ".." => {
+                    parts.pop().ok_or(PathViolation::Escape)?;
+                }
+                part => {
+                    check_component_len(part)?;
+                    parts.push(part);
+                }
+            }
+        }
+        Ok(format!("/{}", parts.join("/")))
+    }
+
+    fn refused(&self, path: &str, violation: PathViolation) -> PathViolation {
+        if self.deny_suspicious {
+            tracing::warn!(path = %path.escape_debug(), reason = %violation, "refused path");
+        }
+        violation
+    }
+}
+
+fn check_component_len(part: &str) -> Result<(), PathViolation> {
+    if part.len() > MAX_COMPONENT_LEN {
+        return Err(PathViolation::ComponentTooLong(part.len()));
+    }
+    Ok(())
+}
+
+/// [`PathSanitizer::sanitize`] with the default, lenient settings.
+pub fn sanitize_path(path: &str) -> Result<String, PathViolation> {
+    PathSanitizer::default().sanitize(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_resolves_and_refuses() {
+        assert_eq!(
+            sanitize_path("workspace//a/./b/").unwrap(),
+            "/workspace/a/b"
+        );
+        assert_eq!(sanitize_path("/workspace/a/../b").unwrap(), "/workspace/b");
+        assert_eq!(sanitize_path("").unwrap(), "/");
+        assert_eq!(sanitize_path("/a/../.."), Err(PathViolation::Escape));
+        assert_eq!(sanitize_path("/a\0b"), Err(PathViolation::NulByte));
+        let long = format!("/a/{}", "x".repeat(MAX_COMPONENT_LEN + 1));
+        assert_eq!(
+            sanitize_path(&long),
+            Err(PathViolation::ComponentTooLong(MAX_COMPONENT_LEN + 1))
+        );
+        // Tabs and newlines are legal names unless suspicious paths are denied.
+        assert!(sanitize_path("/a\tb").is_ok());
+
+        let strict = PathSanitizer::default().with_deny_suspicious(true);
+        assert_eq!(
+            strict.sanitize("/a/../b"),
+            Err(PathViolation::ParentSegment)
+        );
+        assert_eq!(strict.sanitize("/a\nb"), Err(PathViolation::ControlChar));
+        assert_eq!(strict.sanitize("/a/./b").unwrap(), "/a/b");
+    }
+
+    #[test]
+    fn test_check_name() {
+        let sanitizer = PathSanitizer::default();
+        assert!(sanitizer.check_name("notes.md").is_ok());
+        for name in ["", ".", "..", "a/b"] {
+            assert_eq!(sanitizer.check_name(name), Err(PathViolation::InvalidName));
+        }
+        assert_eq!(sanitizer.check_name("a\0"), Err(PathViolation::NulByte));
+    }
+
+    /// Deterministic xorshift, so failures reproduce.
+    fn next(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn test_fuzz_sanitized_paths_stay_inside() {
+        const PIECES: &[&str] = &[
+            "/", "//", ".", "..", "../", "/..", "a", "b.md", "\0", "\n", "%2e%2e", "\\..", "é", "x",
+        ];
+        for strict in [false, true] {
+            let sanitizer = PathSanitizer::default().with_deny_suspicious(strict);
+            let mut state = 0x9e37_79b9_7f4a_7c15;
+            for _ in 0..20_000 {
+                let len = next(&mut state) % 12;
+                let mut path: String = (0..len)
+                    .map(|_| PIECES[(next(&mut state) % PIECES.len() as u64) as usize])
+                    .collect();
+                if next(&mut state).is_multiple_of(50) {
+                    path.push_str(&"y".repeat(MAX_COMPONENT_LEN + 1));
+                }
+                let Ok(clean) = sanitizer.sanitize(&path) else {
+                    continue;
+                };
+                assert!(clean.starts_with('/'), "{:?} -> {:?}", path, clean);
+                assert!(!clean.contains('\0'), "{:?} -> {:?}", path, clean);
+                assert!(
+                    clean
+                        .split('/')
+                        .all(|part| part != ".." && part != "." && part.len() <= MAX_COMPONENT_LEN),
+                    "{:?} -> {:?}",
+                    path,
+                    clean
+                );
+                assert!(clean == "/" || !clean.ends_with('/'), "{:?}", clean);
+                // Sanitizing is idempotent.
+                assert_eq!(sanitizer.sanitize(&clean).as_deref(), Ok(clean.as_str()));
+                if strict {
+                    assert!(!path.split('/').any(|part| part == ".."), "{:?}", path);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use openfs_config::SearchMode;
+use serde::{Deserialize, Serialize};
+
+use crate::error::VfsError;
+use crate::traits::FileMeta;
+
+/// Options for a semantic search through the VFS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Search mode to use. `None` uses the mount's first configured
+    /// `index.search_modes` entry, or the searcher's own default.
+    #[serde(default)]
+    pub mode: Option<SearchMode>,
+    /// Maximum number of results to return.
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Minimum score threshold (0.0 to 1.0).
+    #[serde(default)]
+    pub min_score: f32,
+    /// Only return results under this VFS path.
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Only return results from files carrying all these metadata values.
+    #[serde(default)]
+    pub meta: FileMeta,
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            mode: None,
+            limit: default_limit(),
+            min_score: 0.0,
+            path_prefix: None,
+            meta: FileMeta::new(),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Whether `path` falls under [`path_prefix`](Self::path_prefix).
+    pub fn matches_path(&self, path: &str) -> bool {
+        match self.path_prefix.as_deref().map(|p| p.trim_end_matches('/')) {
+            None | Some("") => true,
+            Some(prefix) => {
+                path == prefix
+                    || path
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+        }
+    }
+
+    /// Whether a file's metadata has every value in [`meta`](Self::meta).
+    pub fn matches_meta(&self, meta: &FileMeta) -> bool {
+        self.meta
+            .iter()
+            .all(|(key, value)| meta.get(key) == Some(value))
+    }
+
+    /// Whether results are filtered after the search, so more should be
+    /// fetched than asked for.
+    pub fn is_filtered(&self) -> bool {
+        self.path_prefix.is_some() || !self.meta.is_empty()
+    }
+}
+
+/// A single search match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// VFS path of the matching file.
+    pub path: String,
+    /// Similarity score (higher is better).
+    pub score: f32,
+    /// Dense (embedding) score before fusion, when the chunk was a dense match.
+    #[serde(default)]
+    pub dense_score: Option<f32>,
+    /// Sparse (BM25) score before fusion, when the chunk was a sparse match.
+    #[serde(default)]
+    pub sparse_score: Option<f32>,
+    /// Matching chunk text.
+    pub content: String,
+    /// First line of the chunk (1-indexed).
+    pub start_line: usize,
+    /// Last line of the chunk (1-indexed).
+    pub end_line: usize,
+    /// Chunk metadata stored at index time.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// Semantic search over indexed VFS content.
+///
+/// Like [`TextEmbedder`](crate::TextEmbedder), this is kept minimal so the VFS
+/// can route searches without depending on the indexing pipeline. The
+/// implementation lives in openfs-local.
+#[async_trait]
+pub trait Searcher: Send + Sync + 'static {
+    /// Run a query and return matches ranked by score.
+    async fn search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchHit>, VfsError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_path() {
+        let mut options = SearchOptions::default();
+        assert!(options.matches_path("/anything"));
+
+        options.path_prefix = Some("/workspace/".to_string());
+        assert!(options.matches_path("/workspace/src/lib.rs"));
+        assert!(options.matches_path("/workspace"));
+        assert!(!options.matches_path("/workspace2/lib.rs"));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use openfs_config::VfsConfig;
+use openfs_config::{ToolsConfig, VfsConfig};
 
 /// A tool parameter definition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +44,12 @@ pub enum ToolFormat {
     Mcp,
     /// OpenAI function calling format.
     OpenAi,
+    /// OpenAI function calling with strict-mode schemas.
+    OpenAiStrict,
+    /// Anthropic Messages API tool-use format.
+    Anthropic,
+    /// Gemini function declarations.
+    Gemini,
 }
 
 impl std::str::FromStr for ToolFormat {
@@ -54,7 +60,13 @@ impl std::str::FromStr for ToolFormat {
             "json" => Ok(ToolFormat::Json),
             "mcp" => Ok(ToolFormat::Mcp),
             "openai" => Ok(ToolFormat::OpenAi),
-            _ => Err(format!("Unknown format: {}. Use json, mcp, or openai", s)),
+            "openai-strict" => Ok(ToolFormat::OpenAiStrict),
+            "anthropic" => Ok(ToolFormat::Anthropic),
+            "gemini" => Ok(ToolFormat::Gemini),
+            _ => Err(format!(
+                "Unknown format: {}. Use json, mcp, openai, openai-strict, anthropic, or gemini",
+                s
+            )),
         }
     }
 }
@@ -222,7 +234,109 @@ pub fn generate_tools(config: &VfsConfig) -> Vec<ToolDefinition> {
         });
     }
 
+    match &config.tools {
+        Some(tools_config) => customize_tools(tools, tools_config),
+        None => tools,
+    }
+}
+
+/// Apply the `tools:` config section: drop hidden tools, rename and
+/// redescribe tools, and note the allowed mounts on path parameters.
+fn customize_tools(tools: Vec<ToolDefinition>, config: &ToolsConfig) -> Vec<ToolDefinition> {
     tools
+        .into_iter()
+        .filter(|tool| !config.hides(&tool.name))
+        .map(|mut tool| {
+            if let Some(tool_override) = config.override_for(&tool.name) {
+                if let Some(description) = &tool_override.description {
+                    tool.description = description.clone();
+                }
+                if let Some(name) = &tool_override.name {
+                    tool.name = name.clone();
+                }
+            }
+            if let Some(note) = allowed_mounts_note(config) {
+                for param in tool.parameters.iter_mut().filter(|p| p.name == "path") {
+                    param.description = format!("{} {}", param.description, note);
+                }
+            }
+            tool
+        })
+        .collect()
+}
+
+/// A sentence for path parameter descriptions naming the mounts paths must
+/// lie in, if the `tools:` section restricts them.
+pub fn allowed_mounts_note(config: &ToolsConfig) -> Option<String> {
+    if config.mounts.is_empty() {
+        return None;
+    }
+    Some(format!("Must be under {}.", config.mounts.join(" or ")))
+}
+
+/// Find the tool agents call `name`, as generated for `config`.
+///
+/// The definition returned has the tool's built-in name (e.g. `vfs_read`),
+/// even if the `tools:` section renames it, so callers can dispatch on it.
+/// Hidden tools are not found.
+pub fn find_tool(config: &VfsConfig, name: &str) -> Option<ToolDefinition> {
+    let builtin = VfsConfig {
+        tools: None,
+        ..config.clone()
+    };
+    generate_tools(&builtin)
+        .into_iter()
+        .find(|tool| match &config.tools {
+            Some(tools_config) => {
+                !tools_config.hides(&tool.name) && tools_config.exposed_name(&tool.name) == name
+            }
+            None => tool.name == name,
+        })
+}
+
+impl ToolDefinition {
+    /// Check tool call arguments against the parameters: `args` must be an
+    /// object with every required parameter, no unknown ones, and values of
+    /// the declared types.
+    pub fn check_arguments(&self, args: &serde_json::Value) -> Result<(), String> {
+        let empty = serde_json::Map::new();
+        let args = match args {
+            serde_json::Value::Object(map) => map,
+            serde_json::Value::Null => &empty,
+            _ => return Err(format!("arguments to {} must be an object", self.name)),
+        };
+        for param in self.parameters.iter().filter(|p| p.required) {
+            if args.get(&param.name).is_none_or(|v| v.is_null()) {
+                return Err(format!("{} requires '{}'", self.name, param.name));
+            }
+        }
+        for (name, value) in args {
+            let Some(param) = self.parameters.iter().find(|p| &p.name == name) else {
+                return Err(format!("{} has no parameter '{}'", self.name, name));
+            };
+            if value.is_null() && !param.required {
+                continue;
+            }
+            let type_matches = match param.param_type.as_str() {
+                "string" => value.is_string(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "number" => value.is_number(),
+                "boolean" => value.is_boolean(),
+                "array" => value.is_array(),
+                "object" => value.is_object(),
+                _ => true,
+            };
+            if !type_matches {
+                return Err(format!("'{}' must be of type {}", name, param.param_type));
+            }
+            if let (Some(allowed), Some(value)) = (&param.enum_values, value.as_str()) {
+                if !allowed.iter().any(|a| a == value) {
+                    return Err(format!("'{}' must be one of: {}", name, allowed.join(", ")));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Convert tools to MCP format.
@@ -324,6 +438,124 @@ pub fn to_openai_format(tools: &[ToolDefinition]) -> serde_json::Value {
     })
 }
 
+/// Convert tools to OpenAI function calling format with strict-mode schemas.
+///
+/// Strict mode requires every property to be listed as required and no
+/// others to be allowed, so optional parameters are made nullable instead.
+/// Defaults are not supported and left out.
+pub fn to_openai_strict_format(tools: &[ToolDefinition]) -> serde_json::Value {
+    let functions: Vec<serde_json::Value> = tools
+        .iter()
+        .map(|tool| {
+            let properties: HashMap<String, serde_json::Value> = tool
+                .parameters
+                .iter()
+                .map(|p| {
+                    let mut prop = serde_json::json!({
+                        "type": p.param_type,
+                        "description": p.description,
+                    });
+
+                    if let Some(enum_vals) = &p.enum_values {
+                        prop["enum"] = serde_json::json!(enum_vals);
+                    }
+
+                    if !p.required {
+                        prop["type"] = serde_json::json!([p.param_type, "null"]);
+                        if let Some(values) = prop.get_mut("enum").and_then(|v| v.as_array_mut()) {
+                            values.push(serde_json::Value::Null);
+                        }
+                    }
+
+                    (p.name.clone(), prop)
+                })
+                .collect();
+
+            let required: Vec<String> = tool.parameters.iter().map(|p| p.name.clone()).collect();
+
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "strict": true,
+                    "parameters": {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                        "additionalProperties": false,
+                    }
+                }
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "tools": functions
+    })
+}
+
+/// Convert tools to the Anthropic Messages API tool-use format.
+pub fn to_anthropic_format(tools: &[ToolDefinition]) -> serde_json::Value {
+    // The `tools` request parameter takes the same shape as the MCP output.
+    to_mcp_format(tools)
+}
+
+/// Convert tools to Gemini function declarations.
+///
+/// Gemini schemas name types in upper case, and a function without
+/// parameters must leave `parameters` out rather than send an empty object.
+pub fn to_gemini_format(tools: &[ToolDefinition]) -> serde_json::Value {
+    let declarations: Vec<serde_json::Value> = tools
+        .iter()
+        .map(|tool| {
+            let mut declaration = serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+            });
+            if tool.parameters.is_empty() {
+                return declaration;
+            }
+
+            let properties: HashMap<String, serde_json::Value> = tool
+                .parameters
+                .iter()
+                .map(|p| {
+                    let mut prop = serde_json::json!({
+                        "type": p.param_type.to_uppercase(),
+                        "description": p.description,
+                    });
+
+                    if let Some(enum_vals) = &p.enum_values {
+                        prop["format"] = serde_json::json!("enum");
+                        prop["enum"] = serde_json::json!(enum_vals);
+                    }
+
+                    (p.name.clone(), prop)
+                })
+                .collect();
+
+            let required: Vec<String> = tool
+                .parameters
+                .iter()
+                .filter(|p| p.required)
+                .map(|p| p.name.clone())
+                .collect();
+
+            declaration["parameters"] = serde_json::json!({
+                "type": "OBJECT",
+                "properties": properties,
+                "required": required,
+            });
+            declaration
+        })
+        .collect();
+
+    serde_json::json!({
+        "tools": [{ "functionDeclarations": declarations }]
+    })
+}
+
 /// Convert tools to JSON format (generic).
 pub fn to_json_format(tools: &[ToolDefinition]) -> serde_json::Value {
     serde_json::json!({
@@ -337,6 +569,9 @@ pub fn format_tools(tools: &[ToolDefinition], format: ToolFormat) -> serde_json:
         ToolFormat::Json => to_json_format(tools),
         ToolFormat::Mcp => to_mcp_format(tools),
         ToolFormat::OpenAi => to_openai_format(tools),
+        ToolFormat::OpenAiStrict => to_openai_strict_format(tools),
+        ToolFormat::Anthropic => to_anthropic_format(tools),
+        ToolFormat::Gemini => to_gemini_format(tools),
     }
 }
 
@@ -368,8 +603,18 @@ mod tests {
                 index: None,
                 sync: None,
                 watch: None,
+                cache: None,
+                log: None,
+                path_policy: None,
+                retry: None,
+                replicas: None,
             }],
+            views: Vec::new(),
             defaults: None,
+            mcp: None,
+            fuse: None,
+            security: None,
+            tools: None,
         }
     }
 
@@ -386,6 +631,33 @@ mod tests {
         assert!(tools.iter().any(|t| t.name == "vfs_mounts"));
     }
 
+    #[test]
+    fn test_generate_tools_honors_tools_config() {
+        let mut config = test_config();
+        config.tools = Some(ToolsConfig {
+            hide: vec!["delete".to_string()],
+            mounts: vec!["/workspace".to_string()],
+            overrides: [(
+                "read".to_string(),
+                openfs_config::ToolOverride {
+                    name: Some("read_file".to_string()),
+                    description: Some("Read a file.".to_string()),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+        let tools = generate_tools(&config);
+
+        assert!(!tools.iter().any(|t| t.name == "vfs_delete"));
+        assert!(!tools.iter().any(|t| t.name == "vfs_read"));
+        let read = tools.iter().find(|t| t.name == "read_file").unwrap();
+        assert_eq!(read.description, "Read a file.");
+        assert!(read.parameters[0]
+            .description
+            .ends_with("Must be under /workspace."));
+    }
+
     #[test]
     fn test_mcp_format() {
         let config = test_config();
@@ -426,6 +698,111 @@ mod tests {
         assert_eq!("json".parse::<ToolFormat>().unwrap(), ToolFormat::Json);
         assert_eq!("mcp".parse::<ToolFormat>().unwrap(), ToolFormat::Mcp);
         assert_eq!("openai".parse::<ToolFormat>().unwrap(), ToolFormat::OpenAi);
+        assert_eq!(
+            "Anthropic".parse::<ToolFormat>().unwrap(),
+            ToolFormat::Anthropic
+        );
+        assert_eq!("gemini".parse::<ToolFormat>().unwrap(), ToolFormat::Gemini);
         assert!("invalid".parse::<ToolFormat>().is_err());
     }
+
+    #[test]
+    fn test_openai_strict_format() {
+        let tools = generate_tools(&test_config());
+        let strict = to_openai_strict_format(&tools);
+        for tool in strict["tools"].as_array().unwrap() {
+            let function = &tool["function"];
+            assert_eq!(function["strict"], true);
+            let parameters = &function["parameters"];
+            assert_eq!(parameters["additionalProperties"], false);
+            let properties = parameters["properties"].as_object().unwrap();
+            assert_eq!(
+                parameters["required"].as_array().unwrap().len(),
+                properties.len()
+            );
+        }
+
+        let tool = ToolDefinition {
+            name: "pick".to_string(),
+            description: "Pick a mode".to_string(),
+            parameters: vec![ToolParameter {
+                name: "mode".to_string(),
+                description: "The mode".to_string(),
+                param_type: "string".to_string(),
+                required: false,
+                enum_values: Some(vec!["a".to_string()]),
+                default: Some(serde_json::json!("a")),
+            }],
+        };
+        let strict = to_openai_strict_format(&[tool]);
+        let mode = &strict["tools"][0]["function"]["parameters"]["properties"]["mode"];
+        assert_eq!(mode["type"], serde_json::json!(["string", "null"]));
+        assert_eq!(mode["enum"], serde_json::json!(["a", null]));
+        assert!(mode.get("default").is_none());
+    }
+
+    #[test]
+    fn test_anthropic_and_gemini_formats() {
+        let tools = generate_tools(&test_config());
+
+        let anthropic = format_tools(&tools, ToolFormat::Anthropic);
+        let first = &anthropic["tools"][0];
+        assert_eq!(first["name"], tools[0].name);
+        assert_eq!(first["input_schema"]["type"], "object");
+
+        let gemini = format_tools(&tools, ToolFormat::Gemini);
+        let declarations = gemini["tools"][0]["functionDeclarations"]
+            .as_array()
+            .unwrap();
+        assert_eq!(declarations.len(), tools.len());
+        let read = declarations
+            .iter()
+            .find(|d| d["name"] == "vfs_read")
+            .unwrap();
+        assert_eq!(read["parameters"]["type"], "OBJECT");
+        assert_eq!(read["parameters"]["properties"]["path"]["type"], "STRING");
+        let mounts = declarations
+            .iter()
+            .find(|d| d["name"] == "vfs_mounts")
+            .unwrap();
+        assert!(mounts.get("parameters").is_none());
+    }
+
+    #[test]
+    fn test_find_tool_and_check_arguments() {
+        let mut config = test_config();
+        config.tools = Some(ToolsConfig {
+            hide: vec!["delete".to_string()],
+            mounts: vec![],
+            overrides: [(
+                "read".to_string(),
+                openfs_config::ToolOverride {
+                    name: Some("read_file".to_string()),
+                    description: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+
+        let read = find_tool(&config, "read_file").unwrap();
+        assert_eq!(read.name, "vfs_read");
+        assert!(find_tool(&config, "vfs_read").is_none());
+        assert!(find_tool(&config, "vfs_delete").is_none());
+
+        assert!(read
+            .check_arguments(&serde_json::json!({"path": "/workspace/a"}))
+            .is_ok());
+        let missing = read.check_arguments(&serde_json::json!({})).unwrap_err();
+        assert!(missing.contains("requires 'path'"), "{missing}");
+        let unknown = read
+            .check_arguments(&serde_json::json!({"path": "/a", "mode": "x"}))
+            .unwrap_err();
+        assert!(unknown.contains("no parameter 'mode'"), "{unknown}");
+        let wrong_type = read
+            .check_arguments(&serde_json::json!({"path": 1}))
+            .unwrap_err();
+        assert!(wrong_type.contains("type string"), "{wrong_type}");
+        assert!(read.check_arguments(&serde_json::json!("/a")).is_err());
+    }
 }
@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
+use crate::content_type::content_type_for_path;
 use crate::error::BackendError;
 
+/// User metadata attached to a file, as key-value pairs.
+pub type FileMeta = BTreeMap<String, String>;
+
 /// Metadata about a file or directory entry.
 #[derive(Debug, Clone)]
 pub struct Entry {
@@ -16,6 +22,14 @@ pub struct Entry {
     pub size: Option<u64>,
     /// Last modification time.
     pub modified: Option<DateTime<Utc>>,
+    /// Opaque version of the content (e.g. an S3 ETag), where the backend
+    /// lists one. It changes whenever the content does.
+    pub etag: Option<String>,
+    /// User metadata, filled in by `stat` on backends that store it.
+    pub meta: FileMeta,
+    /// MIME type of a file: the one the backend stores, or a guess from the
+    /// extension. `Vfs::content_type` sniffs the content instead.
+    pub content_type: Option<String>,
 }
 
 impl Entry {
@@ -23,10 +37,13 @@ impl Entry {
     pub fn file(path: String, name: String, size: u64, modified: Option<DateTime<Utc>>) -> Self {
         Entry {
             path,
-            name,
             is_dir: false,
             size: Some(size),
             modified,
+            etag: None,
+            meta: FileMeta::new(),
+            content_type: content_type_for_path(&name).map(String::from),
+            name,
         }
     }
 
@@ -38,8 +55,38 @@ impl Entry {
             is_dir: true,
             size: None,
             modified,
+            etag: None,
+            meta: FileMeta::new(),
+            content_type: None,
         }
     }
+
+    /// Set the content version.
+    pub fn with_etag(mut self, etag: impl Into<String>) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Set the user metadata.
+    pub fn with_meta(mut self, meta: FileMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Set the MIME type.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+/// A stored part of a multipart upload, see [`Backend::upload_part`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedPart {
+    /// Part number, from 1.
+    pub number: u32,
+    /// Tag the backend returned for the part, needed to complete the upload.
+    pub etag: String,
 }
 
 /// Trait for VFS backend implementations.
@@ -60,6 +107,21 @@ pub trait Backend: Send + Sync + 'static {
         Ok((content, None))
     }
 
+    /// Read up to `len` bytes starting at `offset` (to end of file if `len` is None).
+    ///
+    /// Reading past the end returns the available bytes, possibly none. The
+    /// default reads the whole file; backends with native range reads should
+    /// override this.
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        let content = self.read(path).await?;
+        Ok(slice_range(&content, offset, len).to_vec())
+    }
+
     /// Write content to a file, creating it if it doesn't exist.
     async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError>;
 
@@ -82,6 +144,39 @@ pub trait Backend: Send + Sync + 'static {
         Ok(None)
     }
 
+    /// Create a file only if nothing exists at `path`, returning its CAS token.
+    ///
+    /// Fails with [`BackendError::PreconditionFailed`] when the path is taken,
+    /// so of several racing creators exactly one succeeds.
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let _ = content;
+        Err(BackendError::Other(format!(
+            "create_if_absent is not supported by this backend: {}",
+            path
+        )))
+    }
+
+    /// Read the user metadata of a file; empty when it has none.
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        Err(BackendError::Other(format!(
+            "file metadata is not supported by this backend: {}",
+            path
+        )))
+    }
+
+    /// Replace the user metadata of an existing file.
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        let _ = meta;
+        Err(BackendError::Other(format!(
+            "file metadata is not supported by this backend: {}",
+            path
+        )))
+    }
+
     /// Append content to a file.
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError>;
 
@@ -97,6 +192,63 @@ pub trait Backend: Send + Sync + 'static {
     /// Get metadata for a path.
     async fn stat(&self, path: &str) -> Result<Entry, BackendError>;
 
+    /// Set a file's modification time.
+    ///
+    /// Returns `false` when the backend cannot store modification times,
+    /// which is the default.
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        let _ = (path, modified);
+        Ok(false)
+    }
+
+    /// Start a multipart upload to `path` and return its id, or `None` when
+    /// the backend cannot upload in parts, which is the default.
+    ///
+    /// Nothing is visible at `path` until [`Backend::complete_multipart`].
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        let _ = path;
+        Ok(None)
+    }
+
+    /// Upload part `number` of a multipart upload. Uploading a number again
+    /// replaces the part. An unknown `upload_id` (aborted, or expired by the
+    /// backend) is `NotFound`.
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        let _ = (path, upload_id, number, content);
+        Err(BackendError::Other(
+            "multipart uploads are not supported by this backend".to_string(),
+        ))
+    }
+
+    /// Write `parts`, in order, to `path` and end the upload.
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        let _ = (path, upload_id, parts);
+        Err(BackendError::Other(
+            "multipart uploads are not supported by this backend".to_string(),
+        ))
+    }
+
+    /// Discard a multipart upload and its parts.
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        let _ = (path, upload_id);
+        Ok(())
+    }
+
     /// Rename/move a file or directory.
     ///
     /// Default implementation uses read-write-delete which is not atomic.
@@ -108,3 +260,15 @@ pub trait Backend: Send + Sync + 'static {
         Ok(())
     }
 }
+
+/// The part of `content` covered by `offset` and `len`, clamped to its bounds.
+pub fn slice_range(content: &[u8], offset: u64, len: Option<u64>) -> &[u8] {
+    let start = usize::try_from(offset)
+        .unwrap_or(usize::MAX)
+        .min(content.len());
+    let end = match len {
+        Some(len) => start.saturating_add(usize::try_from(len).unwrap_or(usize::MAX)),
+        None => content.len(),
+    };
+    &content[start..end.min(content.len())]
+}
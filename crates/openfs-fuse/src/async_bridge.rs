@@ -106,6 +106,9 @@ pub enum FuseError {
     /// Read-only filesystem.
     #[error("read-only filesystem")]
     ReadOnly,
+    /// Invalid argument, such as a path the mount refuses.
+    #[error("invalid argument: {0}")]
+    InvalidArg(String),
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(std::io::Error),
@@ -126,6 +129,7 @@ impl FuseError {
             FuseError::Exists => libc::EEXIST,
             FuseError::NotEmpty => libc::ENOTEMPTY,
             FuseError::ReadOnly => libc::EROFS,
+            FuseError::InvalidArg(_) => libc::EINVAL,
             FuseError::Io(e) => e.raw_os_error().unwrap_or(libc::EIO),
             FuseError::Other(_) => libc::EIO,
         }
@@ -149,6 +153,7 @@ impl From<openfs_core::VfsError> for FuseError {
             openfs_core::VfsError::NotFound(_) => FuseError::NotFound,
             openfs_core::VfsError::ReadOnly(_) => FuseError::ReadOnly,
             openfs_core::VfsError::NoMount(_) => FuseError::NotFound,
+            openfs_core::VfsError::InvalidPath { .. } => FuseError::InvalidArg(e.to_string()),
             openfs_core::VfsError::Io(io_err) => FuseError::Io(io_err),
             openfs_core::VfsError::Config(msg) => FuseError::Other(msg),
             openfs_core::VfsError::Backend(e) => FuseError::Other(e.to_string()),
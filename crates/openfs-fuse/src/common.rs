@@ -3,16 +3,24 @@
 //! This module contains the shared VFS-interaction code for the Unix
 //! (`fuser`) FUSE implementation.
 
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::SystemTime;
 
+use chrono::{DateTime, Utc};
+#[cfg(unix)]
+use libc::{XATTR_CREATE, XATTR_REPLACE};
 use openfs_config::VfsConfig;
+use openfs_core::{Entry, VfsError};
 use openfs_remote::Vfs;
+#[cfg(all(unix, feature = "fuse"))]
 use tracing::info;
 
 use crate::async_bridge::{block_on, init_runtime};
 use crate::inode::{InodeAttr, InodeKind, InodeTable, ROOT_INO, VIRTUAL_INO_BASE};
-use crate::search_dir::{SearchDir, QUERY_DIR_PATH, SEARCH_DIR_PATH};
+use crate::search_dir::{QuerySpec, SearchDir, SearchHitTuple, QUERY_DIR_PATH, SEARCH_DIR_PATH};
+use crate::write_buffer::WriteBuffers;
 
 /// Errors returned by filesystem operations.
 #[derive(Debug)]
@@ -31,13 +39,26 @@ pub enum FsOpError {
     NotSymlink,
     /// Is a directory (tried to read as file).
     IsDir,
+    /// Extended attribute not set.
+    NoAttr,
+    /// Extended attribute already set (`XATTR_CREATE`).
+    Exists,
 }
 
+/// Hidden directory holding extended attribute sidecars for its siblings.
+pub const XATTR_DIR: &str = ".openfs-xattr";
+
+#[cfg(not(unix))]
+const XATTR_CREATE: i32 = 1;
+#[cfg(not(unix))]
+const XATTR_REPLACE: i32 = 2;
+
 impl From<crate::async_bridge::FuseError> for FsOpError {
     fn from(e: crate::async_bridge::FuseError) -> Self {
         match e {
             crate::async_bridge::FuseError::NotFound => FsOpError::NotFound,
             crate::async_bridge::FuseError::ReadOnly => FsOpError::ReadOnly,
+            crate::async_bridge::FuseError::InvalidArg(_) => FsOpError::InvalidArg,
             crate::async_bridge::FuseError::IsDir => FsOpError::IsDir,
             crate::async_bridge::FuseError::NotEmpty => FsOpError::NotEmpty,
             crate::async_bridge::FuseError::PermissionDenied => FsOpError::ReadOnly,
@@ -54,6 +75,8 @@ pub struct OpenFsCore {
     pub inodes: Arc<InodeTable>,
     /// Virtual search directory.
     pub search_dir: Arc<SearchDir>,
+    /// Open file handles and their buffered writes.
+    write_buffers: WriteBuffers,
 }
 
 impl OpenFsCore {
@@ -63,29 +86,91 @@ impl OpenFsCore {
             return Ok(());
         }
 
+        let tuples = self.grep_hits(&query, 200)?;
+        let entries = self.search_dir.create_result_entries(&tuples);
+        self.search_dir.store_results(&query, entries);
+        Ok(())
+    }
+
+    /// Run the query of a `/.search/<query>?<params>` directory.
+    ///
+    /// Uses the configured searchers, or matching lines when there are none.
+    fn materialize_search(&self, path: &str) -> Result<(), FsOpError> {
+        let name = SearchDir::search_name(path).ok_or(FsOpError::NotFound)?;
+        if self.search_dir.has_search(name) {
+            return Ok(());
+        }
+        let spec = QuerySpec::parse(name).ok_or(FsOpError::NotFound)?;
+
         let vfs = self.vfs.clone();
-        let grep_result = block_on(async {
-            let opts = openfs_remote::GrepOptions {
-                recursive: true,
-                max_matches: 200,
-                max_depth: 20,
-            };
-            openfs_remote::grep(&vfs, &query, "/", &opts).await
-        })?;
+        let options = spec.search_options();
+        let result = block_on(async { vfs.search(&spec.query, &options).await })?;
 
-        let tuples: Vec<(String, String, f32, usize, usize)> = match grep_result {
-            Ok(matches) => matches
+        let hits = match result {
+            Ok(hits) => hits
                 .into_iter()
-                .map(|m| (m.path, m.line, 1.0, m.line_number, m.line_number))
+                .map(|hit| {
+                    (
+                        hit.path,
+                        hit.content,
+                        hit.score,
+                        hit.start_line,
+                        hit.end_line,
+                    )
+                })
                 .collect(),
-            Err(_) => Vec::new(),
+            Err(VfsError::Config(_)) => self.grep_hits(&spec.query, spec.limit)?,
+            Err(e) => return Err(FsOpError::Io(e.to_string())),
         };
 
-        let entries = self.search_dir.create_result_entries(&tuples);
-        self.search_dir.store_results(&query, entries);
+        self.search_dir.store_hits(name, &hits);
         Ok(())
     }
 
+    /// Grep every mount for `pattern`, as search result tuples.
+    fn grep_hits(
+        &self,
+        pattern: &str,
+        max_matches: usize,
+    ) -> Result<Vec<SearchHitTuple>, FsOpError> {
+        let mut roots: Vec<String> = self
+            .vfs
+            .effective_config()
+            .mounts
+            .iter()
+            .map(|mount| mount.path.clone())
+            .collect();
+        if roots.iter().any(|root| root == "/") {
+            roots = vec!["/".to_string()];
+        }
+
+        let vfs = self.vfs.clone();
+        let matches = block_on(async {
+            let mut matches = Vec::new();
+            for root in &roots {
+                let opts = openfs_remote::GrepOptions {
+                    recursive: true,
+                    max_matches: max_matches - matches.len(),
+                    max_depth: 20,
+                    trigram_index: None,
+                };
+                match openfs_remote::grep(&vfs, pattern, root, &opts).await {
+                    Ok(found) => matches.extend(found),
+                    Err(_) => break,
+                }
+                if matches.len() >= max_matches {
+                    break;
+                }
+            }
+            matches
+        })?;
+
+        Ok(matches
+            .into_iter()
+            .map(|m| (m.path, m.line, 1.0, m.line_number, m.line_number))
+            .collect())
+    }
+
     /// Create a new core from a config file.
     pub fn from_config_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         let config = VfsConfig::from_file(path)?;
@@ -96,15 +181,19 @@ impl OpenFsCore {
     pub fn from_config(config: VfsConfig) -> Result<Self, Box<dyn std::error::Error>> {
         init_runtime()?;
 
+        let fuse = config.fuse.clone().unwrap_or_default();
         let vfs = block_on(async { Vfs::from_config(config).await })??;
 
         let inodes = Arc::new(InodeTable::new());
-        let search_dir = Arc::new(SearchDir::new(inodes.clone()));
+        let search_dir = Arc::new(
+            SearchDir::new(inodes.clone()).with_cache_ttl(fuse.search_cache_ttl.as_duration()),
+        );
 
         Ok(OpenFsCore {
             vfs: Arc::new(vfs),
             inodes,
             search_dir,
+            write_buffers: WriteBuffers::new(fuse.write_buffer),
         })
     }
 
@@ -122,6 +211,30 @@ impl OpenFsCore {
         }
     }
 
+    /// Get the inode for a backend entry, carrying over its size and mtime.
+    fn inode_for_entry(&self, path: &str, entry: &Entry) -> u64 {
+        let size = entry.size.unwrap_or(0);
+        let ino = self
+            .inodes
+            .get_ino(path)
+            .unwrap_or_else(|| self.inodes.get_or_create(path, entry.is_dir, size));
+        self.inodes.refresh_attr(
+            ino,
+            size,
+            entry.is_dir,
+            entry.modified.map(SystemTime::from),
+        );
+        self.apply_buffered_size(ino);
+        ino
+    }
+
+    /// Report the size of unflushed writes rather than the backend's.
+    fn apply_buffered_size(&self, ino: u64) {
+        if let Some(size) = self.write_buffers.size(ino) {
+            self.inodes.refresh_attr(ino, size, false, None);
+        }
+    }
+
     /// Perform a lookup operation.
     pub fn do_lookup(&self, parent: u64, name: &str) -> Result<InodeAttr, FsOpError> {
         let parent_path = self.get_path(parent).ok_or(FsOpError::NotFound)?;
@@ -131,6 +244,8 @@ impl OpenFsCore {
             let _ = self.materialize_query_results(&child_path);
         } else if SearchDir::is_query_path(&parent_path) {
             let _ = self.materialize_query_results(&parent_path);
+        } else if SearchDir::search_name(&child_path).is_some() {
+            let _ = self.materialize_search(&child_path);
         }
 
         // Check for virtual .search directory
@@ -161,9 +276,7 @@ impl OpenFsCore {
 
         match result {
             Ok(entry) => {
-                let ino =
-                    self.inodes
-                        .get_or_create(&child_path, entry.is_dir, entry.size.unwrap_or(0));
+                let ino = self.inode_for_entry(&child_path, &entry);
                 self.inodes
                     .get_attr(ino)
                     .ok_or(FsOpError::Io("inode missing after creation".to_string()))
@@ -211,8 +324,13 @@ impl OpenFsCore {
 
         match result {
             Ok(entry) => {
-                self.inodes
-                    .update_attr(ino, entry.size.unwrap_or(0), entry.is_dir);
+                self.inodes.refresh_attr(
+                    ino,
+                    entry.size.unwrap_or(0),
+                    entry.is_dir,
+                    entry.modified.map(SystemTime::from),
+                );
+                self.apply_buffered_size(ino);
                 self.inodes.get_attr(ino).ok_or(FsOpError::NotFound)
             }
             Err(_) => Err(FsOpError::NotFound),
@@ -230,6 +348,8 @@ impl OpenFsCore {
                 if let Some((query_path, _)) = path.rsplit_once('/') {
                     let _ = self.materialize_query_results(query_path);
                 }
+            } else if SearchDir::search_name(&path).is_some() {
+                let _ = self.materialize_search(&path);
             }
 
             if let Some(data) = self.search_dir.read_file(&path) {
@@ -243,6 +363,10 @@ impl OpenFsCore {
             return Err(FsOpError::IsDir);
         }
 
+        if let Some(data) = self.write_buffers.read(ino, offset.max(0) as u64, size) {
+            return Ok(data);
+        }
+
         let vfs = self.vfs.clone();
         let result = block_on(async { vfs.read(&path).await })?;
 
@@ -261,13 +385,35 @@ impl OpenFsCore {
     }
 
     /// Perform a write operation.
-    pub fn do_write(&self, ino: u64, offset: i64, data: &[u8]) -> Result<u32, FsOpError> {
+    ///
+    /// Writes through a buffered handle are collected in memory and written
+    /// back by `do_flush`; other writes go straight to the backend.
+    pub fn do_write(&self, ino: u64, fh: u64, offset: i64, data: &[u8]) -> Result<u32, FsOpError> {
         let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
 
         if SearchDir::is_search_path(&path) {
             return Err(FsOpError::ReadOnly);
         }
 
+        if let Some(needs_load) = self.write_buffers.needs_load(fh) {
+            if needs_load {
+                let vfs = self.vfs.clone();
+                let content = match block_on(async { vfs.read(&path).await })? {
+                    Ok(content) => content,
+                    Err(VfsError::NotFound(_)) => Vec::new(),
+                    Err(e) => return Err(FsOpError::Io(e.to_string())),
+                };
+                self.write_buffers.load(fh, content);
+            }
+            if let Some((size, due)) = self.write_buffers.write(fh, offset.max(0) as u64, data) {
+                self.inodes.update_attr(ino, size, false);
+                if due {
+                    self.do_flush(fh)?;
+                }
+                return Ok(data.len() as u32);
+            }
+        }
+
         let vfs = self.vfs.clone();
         let result = block_on(async {
             if offset == 0 {
@@ -312,6 +458,8 @@ impl OpenFsCore {
 
         if SearchDir::is_query_path(&path) {
             let _ = self.materialize_query_results(&path);
+        } else if SearchDir::search_name(&path).is_some() {
+            let _ = self.materialize_search(&path);
         }
 
         // Handle virtual .search directory
@@ -319,6 +467,8 @@ impl OpenFsCore {
             if let Some(entries) = self.search_dir.readdir(&path) {
                 let parent_ino = if path == SEARCH_DIR_PATH {
                     ROOT_INO
+                } else if SearchDir::search_name(&path).is_some() {
+                    VIRTUAL_INO_BASE
                 } else {
                     ino
                 };
@@ -342,27 +492,24 @@ impl OpenFsCore {
         let vfs = self.vfs.clone();
         let result = block_on(async { vfs.list(&path).await })?;
 
+        let parent_path = if path == "/" {
+            "/".to_string()
+        } else {
+            path.rsplit_once('/')
+                .map(|(p, _)| if p.is_empty() { "/" } else { p })
+                .unwrap_or("/")
+                .to_string()
+        };
+        let parent_ino = self.inodes.get_ino(&parent_path).unwrap_or(ROOT_INO);
+
         match result {
             Ok(vfs_entries) => {
-                let parent_path = if path == "/" {
-                    "/".to_string()
-                } else {
-                    path.rsplit_once('/')
-                        .map(|(p, _)| if p.is_empty() { "/" } else { p })
-                        .unwrap_or("/")
-                        .to_string()
-                };
-                let parent_ino = self.inodes.get_ino(&parent_path).unwrap_or(ROOT_INO);
-
                 let entries: Vec<DirEntry> = vfs_entries
                     .into_iter()
+                    .filter(|entry| entry.name != XATTR_DIR)
                     .map(|entry| {
                         let child_path = Self::child_path(&path, &entry.name);
-                        let entry_ino = self.inodes.get_or_create(
-                            &child_path,
-                            entry.is_dir,
-                            entry.size.unwrap_or(0),
-                        );
+                        let entry_ino = self.inode_for_entry(&child_path, &entry);
                         let kind = if entry.is_dir {
                             InodeKind::Directory
                         } else {
@@ -383,12 +530,49 @@ impl OpenFsCore {
                     is_root: path == "/",
                 })
             }
+            // Above the mounts (e.g. `/` with only `/workspace` mounted).
+            Err(VfsError::NoMount(_)) => Ok(ReadDirResult {
+                ino,
+                parent_ino,
+                entries: self.mount_point_entries(&path),
+                is_root: path == "/",
+            }),
             Err(e) => Err(FsOpError::Io(e.to_string())),
         }
     }
 
-    /// Create a new file.
-    pub fn do_create(&self, parent: u64, name: &str) -> Result<InodeAttr, FsOpError> {
+    /// Directories leading to the mounts and views below `path`.
+    fn mount_point_entries(&self, path: &str) -> Vec<DirEntry> {
+        let prefix = if path == "/" {
+            "/".to_string()
+        } else {
+            format!("{}/", path)
+        };
+        let config = self.vfs.effective_config();
+        let mount_paths = config.mounts.iter().map(|mount| &mount.path);
+        let mut names: Vec<&str> = mount_paths
+            .chain(config.views.iter().map(|view| &view.path))
+            .filter_map(|path| path.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split('/').find(|name| !name.is_empty()))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let child_path = Self::child_path(path, name);
+                DirEntry {
+                    ino: self.inodes.get_or_create(&child_path, true, 0),
+                    name: name.to_string(),
+                    kind: InodeKind::Directory,
+                }
+            })
+            .collect()
+    }
+
+    /// Create a new file and open a handle on it.
+    pub fn do_create(&self, parent: u64, name: &str) -> Result<(InodeAttr, u64), FsOpError> {
         let parent_path = self.get_path(parent).ok_or(FsOpError::NotFound)?;
 
         if SearchDir::is_search_path(&parent_path) {
@@ -403,9 +587,12 @@ impl OpenFsCore {
         match result {
             Ok(()) => {
                 let ino = self.inodes.get_or_create(&child_path, false, 0);
-                self.inodes
+                let attr = self
+                    .inodes
                     .get_attr(ino)
-                    .ok_or(FsOpError::Io("inode missing after creation".to_string()))
+                    .ok_or(FsOpError::Io("inode missing after creation".to_string()))?;
+                let fh = self.write_buffers.open(ino, &child_path, Some(Vec::new()));
+                Ok((attr, fh))
             }
             Err(openfs_core::VfsError::ReadOnly(_)) => Err(FsOpError::ReadOnly),
             Err(e) => Err(FsOpError::Io(e.to_string())),
@@ -454,6 +641,8 @@ impl OpenFsCore {
         match result {
             Ok(()) => {
                 self.inodes.remove_path(&child_path);
+                self.write_buffers.unlink(&child_path);
+                self.remove_xattr_sidecar(&child_path);
                 Ok(())
             }
             Err(openfs_core::VfsError::ReadOnly(_)) => Err(FsOpError::ReadOnly),
@@ -477,8 +666,10 @@ impl OpenFsCore {
 
         match list_result {
             Ok(entries) => {
-                let non_placeholder: Vec<_> =
-                    entries.iter().filter(|e| e.name != ".axkeep").collect();
+                let non_placeholder: Vec<_> = entries
+                    .iter()
+                    .filter(|e| e.name != ".axkeep" && e.name != XATTR_DIR)
+                    .collect();
                 if !non_placeholder.is_empty() {
                     return Err(FsOpError::NotEmpty);
                 }
@@ -489,6 +680,7 @@ impl OpenFsCore {
                 }
 
                 self.inodes.remove_path(&child_path);
+                self.remove_xattr_sidecar(&child_path);
                 Ok(())
             }
             Err(openfs_core::VfsError::NotFound(_)) => Err(FsOpError::NotFound),
@@ -513,8 +705,51 @@ impl OpenFsCore {
         }
     }
 
-    /// Handle setattr (truncate).
-    pub fn do_setattr(&self, ino: u64, size: Option<u64>) -> Result<InodeAttr, FsOpError> {
+    /// Open a file handle. Returns 0 when writes are not buffered.
+    pub fn do_open(&self, ino: u64) -> Result<u64, FsOpError> {
+        if ino >= VIRTUAL_INO_BASE {
+            return Ok(0);
+        }
+        let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
+        if SearchDir::is_search_path(&path) {
+            return Ok(0);
+        }
+        Ok(self.write_buffers.open(ino, &path, None))
+    }
+
+    /// Write back a handle's buffered writes (flush and fsync).
+    pub fn do_flush(&self, fh: u64) -> Result<(), FsOpError> {
+        let Some(dirty) = self.write_buffers.take_dirty(fh) else {
+            return Ok(());
+        };
+        let vfs = self.vfs.clone();
+        match block_on(async { vfs.write(&dirty.path, &dirty.content).await })? {
+            Ok(()) => {
+                self.write_buffers.mark_clean(fh, dirty.generation);
+                Ok(())
+            }
+            Err(VfsError::ReadOnly(_)) => Err(FsOpError::ReadOnly),
+            Err(e) => Err(FsOpError::Io(e.to_string())),
+        }
+    }
+
+    /// Flush and close a handle.
+    pub fn do_release(&self, fh: u64) -> Result<(), FsOpError> {
+        let result = self.do_flush(fh);
+        self.write_buffers.release(fh);
+        result
+    }
+
+    /// Handle setattr (truncate and `utimens`).
+    ///
+    /// A new mtime is stored on the backend where it supports it and kept on
+    /// the inode either way.
+    pub fn do_setattr(
+        &self,
+        ino: u64,
+        size: Option<u64>,
+        mtime: Option<SystemTime>,
+    ) -> Result<InodeAttr, FsOpError> {
         let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
 
         if let Some(new_size) = size {
@@ -525,12 +760,190 @@ impl OpenFsCore {
                     return Err(FsOpError::Io(e.to_string()));
                 }
             }
+            self.write_buffers.truncate(ino, new_size);
             self.inodes.update_attr(ino, new_size, false);
         }
 
+        if let Some(mtime) = mtime {
+            if SearchDir::is_search_path(&path) {
+                return Err(FsOpError::ReadOnly);
+            }
+            let is_dir = self
+                .inodes
+                .get_attr(ino)
+                .is_some_and(|attr| attr.kind == InodeKind::Directory);
+            // Directories have no backend object to stamp.
+            if !is_dir {
+                let vfs = self.vfs.clone();
+                let modified = DateTime::<Utc>::from(mtime);
+                match block_on(async { vfs.set_modified(&path, modified).await })? {
+                    Ok(_) => {}
+                    Err(VfsError::ReadOnly(_)) => return Err(FsOpError::ReadOnly),
+                    Err(VfsError::NotFound(_)) => return Err(FsOpError::NotFound),
+                    Err(e) => return Err(FsOpError::Io(e.to_string())),
+                }
+            }
+            self.inodes.set_mtime(ino, mtime);
+        }
+
         self.inodes.get_attr(ino).ok_or(FsOpError::NotFound)
     }
 
+    /// Get an extended attribute.
+    pub fn do_getxattr(&self, ino: u64, name: &str) -> Result<Vec<u8>, FsOpError> {
+        let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
+        if SearchDir::is_search_path(&path) {
+            return Err(FsOpError::NoAttr);
+        }
+        self.load_xattrs(&path)?
+            .remove(name)
+            .ok_or(FsOpError::NoAttr)
+    }
+
+    /// List extended attribute names.
+    pub fn do_listxattr(&self, ino: u64) -> Result<Vec<String>, FsOpError> {
+        let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
+        if SearchDir::is_search_path(&path) {
+            return Ok(Vec::new());
+        }
+        Ok(self.load_xattrs(&path)?.into_keys().collect())
+    }
+
+    /// Set an extended attribute. `flags` takes `XATTR_CREATE` or
+    /// `XATTR_REPLACE`.
+    pub fn do_setxattr(
+        &self,
+        ino: u64,
+        name: &str,
+        value: &[u8],
+        flags: i32,
+    ) -> Result<(), FsOpError> {
+        let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
+        if SearchDir::is_search_path(&path) {
+            return Err(FsOpError::ReadOnly);
+        }
+        if name.is_empty() {
+            return Err(FsOpError::InvalidArg);
+        }
+
+        let mut xattrs = self.load_xattrs(&path)?;
+        let exists = xattrs.contains_key(name);
+        if flags & XATTR_CREATE != 0 && exists {
+            return Err(FsOpError::Exists);
+        }
+        if flags & XATTR_REPLACE != 0 && !exists {
+            return Err(FsOpError::NoAttr);
+        }
+        xattrs.insert(name.to_string(), value.to_vec());
+        self.store_xattrs(&path, &xattrs)
+    }
+
+    /// Remove an extended attribute.
+    pub fn do_removexattr(&self, ino: u64, name: &str) -> Result<(), FsOpError> {
+        let path = self.get_path(ino).ok_or(FsOpError::NotFound)?;
+        if SearchDir::is_search_path(&path) {
+            return Err(FsOpError::ReadOnly);
+        }
+
+        let mut xattrs = self.load_xattrs(&path)?;
+        if xattrs.remove(name).is_none() {
+            return Err(FsOpError::NoAttr);
+        }
+        self.store_xattrs(&path, &xattrs)
+    }
+
+    /// Sidecar holding the extended attributes of `path`:
+    /// `<parent>/.openfs-xattr/<name>.json`.
+    fn xattr_sidecar(path: &str) -> Option<String> {
+        let (parent, name) = path.rsplit_once('/')?;
+        if name.is_empty() {
+            return None;
+        }
+        Some(format!("{}/{}/{}.json", parent, XATTR_DIR, name))
+    }
+
+    fn load_xattrs(&self, path: &str) -> Result<BTreeMap<String, Vec<u8>>, FsOpError> {
+        let Some(sidecar) = Self::xattr_sidecar(path) else {
+            return Ok(BTreeMap::new());
+        };
+        let vfs = self.vfs.clone();
+        let content = match block_on(async { vfs.read(&sidecar).await })? {
+            Ok(content) => content,
+            Err(VfsError::NotFound(_)) => return Ok(BTreeMap::new()),
+            Err(e) => return Err(FsOpError::Io(e.to_string())),
+        };
+
+        // Values are hex-encoded so binary attributes survive JSON.
+        let encoded: BTreeMap<String, String> =
+            serde_json::from_slice(&content).map_err(|e| FsOpError::Io(e.to_string()))?;
+        encoded
+            .into_iter()
+            .map(|(name, value)| {
+                hex_decode(&value)
+                    .map(|value| (name, value))
+                    .ok_or_else(|| FsOpError::Io(format!("corrupt xattr sidecar {}", sidecar)))
+            })
+            .collect()
+    }
+
+    fn store_xattrs(
+        &self,
+        path: &str,
+        xattrs: &BTreeMap<String, Vec<u8>>,
+    ) -> Result<(), FsOpError> {
+        let sidecar = Self::xattr_sidecar(path).ok_or(FsOpError::ReadOnly)?;
+        let vfs = self.vfs.clone();
+        let result = if xattrs.is_empty() {
+            block_on(async {
+                match vfs.delete(&sidecar).await {
+                    Err(VfsError::NotFound(_)) => Ok(()),
+                    other => other,
+                }
+            })?
+        } else {
+            let encoded: BTreeMap<&str, String> = xattrs
+                .iter()
+                .map(|(name, value)| (name.as_str(), hex_encode(value)))
+                .collect();
+            let content = serde_json::to_vec(&encoded).map_err(|e| FsOpError::Io(e.to_string()))?;
+            block_on(async { vfs.write(&sidecar, &content).await })?
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(VfsError::ReadOnly(_)) => Err(FsOpError::ReadOnly),
+            Err(e) => Err(FsOpError::Io(e.to_string())),
+        }
+    }
+
+    /// Best-effort removal of a deleted path's xattr sidecar.
+    fn remove_xattr_sidecar(&self, path: &str) {
+        let Some(sidecar) = Self::xattr_sidecar(path) else {
+            return;
+        };
+        let vfs = self.vfs.clone();
+        if let Ok(Err(e)) = block_on(async { vfs.delete(&sidecar).await }) {
+            if !matches!(e, VfsError::NotFound(_)) {
+                tracing::warn!("Failed to delete xattr sidecar {}: {}", sidecar, e);
+            }
+        }
+    }
+
+    /// Best-effort move of a renamed path's xattr sidecar.
+    fn rename_xattr_sidecar(&self, src: &str, dst: &str) {
+        let (Some(src_sidecar), Some(dst_sidecar)) =
+            (Self::xattr_sidecar(src), Self::xattr_sidecar(dst))
+        else {
+            return;
+        };
+        let vfs = self.vfs.clone();
+        if let Ok(Err(e)) = block_on(async { vfs.rename(&src_sidecar, &dst_sidecar).await }) {
+            if !matches!(e, VfsError::NotFound(_)) {
+                tracing::warn!("Failed to move xattr sidecar {}: {}", src_sidecar, e);
+            }
+        }
+    }
+
     /// Handle rename.
     pub fn do_rename(
         &self,
@@ -549,12 +962,19 @@ impl OpenFsCore {
         let src_path = Self::child_path(&parent_path, name);
         let dst_path = Self::child_path(&newparent_path, newname);
 
+        // The backend can only move what has been written back.
+        for fh in self.write_buffers.dirty_under(&src_path) {
+            self.do_flush(fh)?;
+        }
+
         let vfs = self.vfs.clone();
         let result = block_on(async { vfs.rename(&src_path, &dst_path).await })?;
 
         match result {
             Ok(()) => {
                 self.inodes.remove_path(&src_path);
+                self.write_buffers.rename(&src_path, &dst_path);
+                self.rename_xattr_sidecar(&src_path, &dst_path);
                 Ok(())
             }
             Err(openfs_core::VfsError::ReadOnly(_)) => Err(FsOpError::ReadOnly),
@@ -564,9 +984,9 @@ impl OpenFsCore {
     }
 
     /// Mount the filesystem (platform-specific dispatch).
-    #[cfg(unix)]
+    #[cfg(all(unix, feature = "fuse"))]
     pub fn mount(self, mountpoint: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        use crate::unix_fuse::UnixFuse;
+        use crate::unix_fuse::serve;
         use fuser::MountOption;
 
         let options = vec![
@@ -577,16 +997,16 @@ impl OpenFsCore {
         ];
 
         info!("Mounting OpenFS at {:?}", mountpoint);
-        fuser::mount2(UnixFuse(self), mountpoint, &options)?;
+        serve(self, mountpoint, &options)?;
         info!("OpenFS unmounted");
 
         Ok(())
     }
 
     /// Mount the filesystem in the foreground.
-    #[cfg(unix)]
+    #[cfg(all(unix, feature = "fuse"))]
     pub fn mount_foreground(self, mountpoint: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        use crate::unix_fuse::UnixFuse;
+        use crate::unix_fuse::serve;
         use fuser::MountOption;
 
         let options = vec![
@@ -595,12 +1015,26 @@ impl OpenFsCore {
         ];
 
         info!("Mounting OpenFS at {:?} (foreground)", mountpoint);
-        fuser::mount2(UnixFuse(self), mountpoint, &options)?;
+        serve(self, mountpoint, &options)?;
 
         Ok(())
     }
 }
 
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
 /// Result from a readdir operation.
 pub struct ReadDirResult {
     /// Inode of the directory being listed.
@@ -625,7 +1059,7 @@ pub struct DirEntry {
 
 /// Convert an `InodeAttr` to a platform-specific `FileAttr` representation.
 /// This is a helper used by the FUSE drivers.
-#[cfg(unix)]
+#[cfg(all(unix, feature = "fuse"))]
 pub fn inode_attr_to_file_attr(attr: &InodeAttr) -> fuser::FileAttr {
     use fuser::{FileAttr, FileType};
 
@@ -870,6 +1304,241 @@ mounts:
         assert_eq!(attr.size, 100);
     }
 
+    #[test]
+    fn test_core_getattr_uses_backend_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        block_on(async {
+            core.vfs
+                .write("/workspace/test.txt", b"hello")
+                .await
+                .unwrap()
+        })
+        .unwrap();
+        let entry =
+            block_on(async { core.vfs.stat("/workspace/test.txt").await.unwrap() }).unwrap();
+
+        let ino = core.inodes.get_or_create("/workspace/test.txt", false, 0);
+        let first = core.do_getattr(ino).unwrap();
+        assert_eq!(first.size, 5);
+        assert_eq!(Some(DateTime::<Utc>::from(first.mtime)), entry.modified);
+
+        // Repeated stats must not bump the mtime.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = core.do_getattr(ino).unwrap();
+        assert_eq!(second.mtime, first.mtime);
+    }
+
+    #[test]
+    fn test_core_setattr_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        block_on(async {
+            core.vfs
+                .write("/workspace/test.txt", b"hello")
+                .await
+                .unwrap()
+        })
+        .unwrap();
+        let ino = core.inodes.get_or_create("/workspace/test.txt", false, 5);
+
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        let attr = core.do_setattr(ino, None, Some(mtime)).unwrap();
+        assert_eq!(attr.mtime, mtime);
+
+        // The backend keeps it, so a fresh stat agrees.
+        let attr = core.do_getattr(ino).unwrap();
+        assert_eq!(attr.mtime, mtime);
+    }
+
+    #[test]
+    fn test_core_xattr_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        block_on(async {
+            core.vfs
+                .write("/workspace/test.txt", b"hello")
+                .await
+                .unwrap()
+        })
+        .unwrap();
+        let ino = core.inodes.get_or_create("/workspace/test.txt", false, 5);
+
+        assert!(matches!(
+            core.do_getxattr(ino, "user.tag"),
+            Err(FsOpError::NoAttr)
+        ));
+        assert!(core.do_listxattr(ino).unwrap().is_empty());
+
+        core.do_setxattr(ino, "user.tag", b"blue", 0).unwrap();
+        core.do_setxattr(ino, "user.bin", &[0, 255, 10], 0).unwrap();
+        assert_eq!(core.do_getxattr(ino, "user.tag").unwrap(), b"blue");
+        assert_eq!(core.do_getxattr(ino, "user.bin").unwrap(), vec![0, 255, 10]);
+        assert_eq!(
+            core.do_listxattr(ino).unwrap(),
+            vec!["user.bin".to_string(), "user.tag".to_string()]
+        );
+
+        assert!(matches!(
+            core.do_setxattr(ino, "user.tag", b"red", libc::XATTR_CREATE),
+            Err(FsOpError::Exists)
+        ));
+        assert!(matches!(
+            core.do_setxattr(ino, "user.new", b"red", libc::XATTR_REPLACE),
+            Err(FsOpError::NoAttr)
+        ));
+
+        core.do_removexattr(ino, "user.tag").unwrap();
+        core.do_removexattr(ino, "user.bin").unwrap();
+        assert!(matches!(
+            core.do_removexattr(ino, "user.bin"),
+            Err(FsOpError::NoAttr)
+        ));
+        assert!(!temp_dir
+            .path()
+            .join(XATTR_DIR)
+            .join("test.txt.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_core_xattr_sidecar_hidden_and_follows_rename() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        block_on(async { core.vfs.write("/workspace/a.txt", b"a").await.unwrap() }).unwrap();
+        let dir_ino = core.inodes.get_or_create("/workspace", true, 0);
+        let ino = core.inodes.get_or_create("/workspace/a.txt", false, 1);
+        core.do_setxattr(ino, "user.tag", b"x", 0).unwrap();
+
+        let listing = core.do_readdir(dir_ino).unwrap();
+        let names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a.txt"]);
+
+        core.do_rename(dir_ino, "a.txt", dir_ino, "b.txt").unwrap();
+        let ino = core.inodes.get_or_create("/workspace/b.txt", false, 1);
+        assert_eq!(core.do_getxattr(ino, "user.tag").unwrap(), b"x");
+
+        core.do_unlink(dir_ino, "b.txt").unwrap();
+        assert!(!temp_dir.path().join(XATTR_DIR).join("b.txt.json").exists());
+    }
+
+    #[test]
+    fn test_core_buffered_writes_flush_on_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+        let dir_ino = core.inodes.get_or_create("/workspace", true, 0);
+
+        let (attr, fh) = core.do_create(dir_ino, "new.txt").unwrap();
+        assert_ne!(fh, 0);
+        assert_eq!(core.do_write(attr.ino, fh, 0, b"hello ").unwrap(), 6);
+        assert_eq!(core.do_write(attr.ino, fh, 6, b"world").unwrap(), 5);
+
+        // Nothing reaches the backend yet, but the mount sees the writes.
+        let on_disk = std::fs::read(temp_dir.path().join("new.txt")).unwrap();
+        assert!(on_disk.is_empty());
+        assert_eq!(core.do_read(attr.ino, 0, 100).unwrap(), b"hello world");
+        assert_eq!(core.do_getattr(attr.ino).unwrap().size, 11);
+
+        core.do_release(fh).unwrap();
+        let on_disk = std::fs::read(temp_dir.path().join("new.txt")).unwrap();
+        assert_eq!(on_disk, b"hello world");
+    }
+
+    #[test]
+    fn test_core_buffered_write_loads_existing_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        std::fs::write(temp_dir.path().join("a.txt"), b"abcdef").unwrap();
+        let ino = core.inodes.get_or_create("/workspace/a.txt", false, 6);
+        let fh = core.do_open(ino).unwrap();
+        core.do_write(ino, fh, 2, b"XY").unwrap();
+        core.do_flush(fh).unwrap();
+
+        let on_disk = std::fs::read(temp_dir.path().join("a.txt")).unwrap();
+        assert_eq!(on_disk, b"abXYef");
+        core.do_release(fh).unwrap();
+    }
+
+    #[test]
+    fn test_core_buffered_writes_flush_before_rename() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+        let dir_ino = core.inodes.get_or_create("/workspace", true, 0);
+
+        let (attr, fh) = core.do_create(dir_ino, "tmp.txt").unwrap();
+        core.do_write(attr.ino, fh, 0, b"content").unwrap();
+        core.do_rename(dir_ino, "tmp.txt", dir_ino, "final.txt")
+            .unwrap();
+        core.do_release(fh).unwrap();
+
+        let on_disk = std::fs::read(temp_dir.path().join("final.txt")).unwrap();
+        assert_eq!(on_disk, b"content");
+        assert!(!temp_dir.path().join("tmp.txt").exists());
+    }
+
+    #[test]
+    fn test_core_write_buffer_disabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = make_test_config(temp_dir.path().to_str().unwrap());
+        config.fuse = Some(openfs_config::FuseConfig {
+            write_buffer: openfs_config::WriteBufferConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let core = OpenFsCore::from_config(config).unwrap();
+        let dir_ino = core.inodes.get_or_create("/workspace", true, 0);
+
+        let (attr, fh) = core.do_create(dir_ino, "direct.txt").unwrap();
+        assert_eq!(fh, 0);
+        core.do_write(attr.ino, fh, 0, b"now").unwrap();
+        let on_disk = std::fs::read(temp_dir.path().join("direct.txt")).unwrap();
+        assert_eq!(on_disk, b"now");
+    }
+
+    #[test]
+    fn test_core_readdir_root_lists_mounts() {
+        let temp1 = TempDir::new().unwrap();
+        let temp2 = TempDir::new().unwrap();
+        let config = make_multi_mount_config(
+            temp1.path().to_str().unwrap(),
+            temp2.path().to_str().unwrap(),
+        );
+        let core = OpenFsCore::from_config(config).unwrap();
+
+        let listing = core.do_readdir(ROOT_INO).unwrap();
+        assert!(listing.is_root);
+        let mut names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["docs", "workspace"]);
+        assert!(listing
+            .entries
+            .iter()
+            .all(|e| e.kind == InodeKind::Directory));
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0u8, 1, 127, 128, 255];
+        assert_eq!(hex_encode(&bytes), "00017f80ff");
+        assert_eq!(hex_decode("00017f80ff"), Some(bytes));
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("zz"), None);
+    }
+
     #[test]
     fn test_core_effective_config() {
         let temp_dir = TempDir::new().unwrap();
@@ -901,6 +1570,38 @@ mounts:
         assert_eq!(dir_entries.len(), 1);
     }
 
+    #[test]
+    fn test_core_search_query_dir_falls_back_to_grep() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_test_config(temp_dir.path().to_str().unwrap());
+        let core = OpenFsCore::from_config(config).unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "retry once\nretry twice\n").unwrap();
+
+        let search = core.do_lookup(ROOT_INO, ".search").unwrap();
+        let dir = core.do_lookup(search.ino, "retry?k=1").unwrap();
+        assert_eq!(dir.kind, InodeKind::Directory);
+
+        let listing = core.do_readdir(dir.ino).unwrap();
+        let names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["01-a.txt", "01-a.txt.snippet"]);
+
+        let link = core.do_lookup(dir.ino, "01-a.txt").unwrap();
+        assert_eq!(link.kind, InodeKind::Symlink);
+        assert_eq!(core.do_readlink(link.ino).unwrap(), "../../workspace/a.txt");
+
+        let snippet = core.do_lookup(dir.ino, "01-a.txt.snippet").unwrap();
+        let data = core.do_read(snippet.ino, 0, 4096).unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "/workspace/a.txt:1-1 [score=1.0000]\nretry once\n"
+        );
+
+        assert!(matches!(
+            core.do_lookup(search.ino, "retry?k=0"),
+            Err(FsOpError::NotFound)
+        ));
+    }
+
     #[test]
     fn test_core_handles_special_characters() {
         let temp_dir = TempDir::new().unwrap();
@@ -14,6 +14,35 @@ pub const ROOT_INO: u64 = 1;
 /// Reserved inode prefix for virtual directories (like .search).
 pub const VIRTUAL_INO_BASE: u64 = 0x1000_0000_0000_0000;
 
+/// Owner reported for every inode: the user running the mount.
+#[cfg(unix)]
+fn owner() -> (u32, u32) {
+    unsafe { (libc::getuid(), libc::getgid()) }
+}
+
+/// Owner reported for every inode. Windows has no uid/gid.
+#[cfg(not(unix))]
+fn owner() -> (u32, u32) {
+    (0, 0)
+}
+
+/// A kernel cache entry made stale by a change outside the mount, see
+/// [`InodeTable::invalidations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalidation {
+    /// Forget an inode's cached attributes, data or directory listing.
+    Inode(u64),
+    /// Forget the lookup of `name` in directory `parent`, including a
+    /// cached "not found".
+    Entry { parent: u64, name: String },
+    /// `name` (inode `child`) was deleted from directory `parent`.
+    Delete {
+        parent: u64,
+        child: u64,
+        name: String,
+    },
+}
+
 /// Inode attributes matching FUSE requirements.
 #[derive(Debug, Clone)]
 pub struct InodeAttr {
@@ -66,8 +95,8 @@ impl InodeAttr {
             kind: InodeKind::Directory,
             perm: 0o755,
             nlink: 2,
-            uid: unsafe { libc::getuid() },
-            gid: unsafe { libc::getgid() },
+            uid: owner().0,
+            gid: owner().1,
         }
     }
 
@@ -85,8 +114,8 @@ impl InodeAttr {
             kind: InodeKind::File,
             perm: 0o644,
             nlink: 1,
-            uid: unsafe { libc::getuid() },
-            gid: unsafe { libc::getgid() },
+            uid: owner().0,
+            gid: owner().1,
         }
     }
 
@@ -104,8 +133,8 @@ impl InodeAttr {
             kind: InodeKind::Symlink,
             perm: 0o777,
             nlink: 1,
-            uid: unsafe { libc::getuid() },
-            gid: unsafe { libc::getgid() },
+            uid: owner().0,
+            gid: owner().1,
         }
     }
 
@@ -123,6 +152,12 @@ impl InodeAttr {
         self.touch();
     }
 
+    /// Set modification time, e.g. from backend metadata or `utimens`.
+    pub fn set_mtime(&mut self, mtime: SystemTime) {
+        self.mtime = mtime;
+        self.ctime = mtime;
+    }
+
     /// Default TTL for attributes.
     pub fn ttl() -> Duration {
         Duration::from_secs(1)
@@ -241,6 +276,31 @@ impl InodeTable {
         }
     }
 
+    /// Refresh attributes from backend metadata.
+    ///
+    /// Unlike `update_attr`, timestamps are left alone unless the backend
+    /// reports an mtime, so repeated stats do not look like modifications.
+    pub fn refresh_attr(&self, ino: u64, size: u64, is_dir: bool, mtime: Option<SystemTime>) {
+        let mut attrs = self.attrs.write();
+        if let Some(attr) = attrs.get_mut(&ino) {
+            if !is_dir {
+                attr.size = size;
+                attr.blocks = size.div_ceil(512);
+            }
+            if let Some(mtime) = mtime {
+                attr.set_mtime(mtime);
+            }
+        }
+    }
+
+    /// Set the modification time for an inode.
+    pub fn set_mtime(&self, ino: u64, mtime: SystemTime) {
+        let mut attrs = self.attrs.write();
+        if let Some(attr) = attrs.get_mut(&ino) {
+            attr.set_mtime(mtime);
+        }
+    }
+
     /// Remove an inode mapping.
     pub fn remove(&self, ino: u64) {
         let path = {
@@ -253,7 +313,10 @@ impl InodeTable {
             let mut ino_to_path = self.ino_to_path.write();
             let mut attrs = self.attrs.write();
 
-            path_to_ino.remove(&path);
+            // The path may already point at a newer inode.
+            if path_to_ino.get(&path) == Some(&ino) {
+                path_to_ino.remove(&path);
+            }
             ino_to_path.remove(&ino);
             attrs.remove(&ino);
         }
@@ -311,6 +374,17 @@ impl InodeTable {
         ino
     }
 
+    /// Give a virtual inode (from `alloc_virtual_ino`) a path and
+    /// attributes, so it resolves like any other inode.
+    pub fn register_virtual(&self, path: &str, attr: InodeAttr) {
+        let normalized = Self::normalize_path(path);
+        self.path_to_ino
+            .write()
+            .insert(normalized.clone(), attr.ino);
+        self.ino_to_path.write().insert(attr.ino, normalized);
+        self.attrs.write().insert(attr.ino, attr);
+    }
+
     /// Normalize a path for consistent lookup.
     fn normalize_path(path: &str) -> String {
         let mut normalized = path.to_string();
@@ -338,6 +412,62 @@ impl InodeTable {
         };
         Some(child_path)
     }
+
+    /// What the kernel must forget after `path` changed or was deleted
+    /// outside the mount. A deleted path is dropped from the table.
+    ///
+    /// Only paths the table knows can be cached by the kernel, so for a new
+    /// path the lookup of its first unknown component in the deepest known
+    /// ancestor is invalidated.
+    pub fn invalidations(&self, path: &str, deleted: bool) -> Vec<Invalidation> {
+        let normalized = Self::normalize_path(path);
+        if let Some(ino) = self.get_ino(&normalized) {
+            if !deleted || ino == ROOT_INO {
+                return vec![Invalidation::Inode(ino)];
+            }
+            let (parent_path, name) = split_parent(&normalized);
+            let parent = self.get_ino(parent_path);
+            self.remove(ino);
+            return match parent {
+                Some(parent) => vec![
+                    Invalidation::Delete {
+                        parent,
+                        child: ino,
+                        name: name.to_string(),
+                    },
+                    Invalidation::Inode(parent),
+                ],
+                None => Vec::new(),
+            };
+        }
+
+        let mut child = normalized.as_str();
+        loop {
+            let (parent_path, name) = split_parent(child);
+            if let Some(parent) = self.get_ino(parent_path) {
+                return vec![
+                    Invalidation::Entry {
+                        parent,
+                        name: name.to_string(),
+                    },
+                    Invalidation::Inode(parent),
+                ];
+            }
+            if parent_path == "/" {
+                return Vec::new();
+            }
+            child = parent_path;
+        }
+    }
+}
+
+/// Split a normalized path into its parent path and last component.
+fn split_parent(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(0) => ("/", &path[1..]),
+        Some(slash) => (&path[..slash], &path[slash + 1..]),
+        None => ("/", path),
+    }
 }
 
 impl Default for InodeTable {
@@ -691,6 +821,49 @@ mod tests {
         table.update_attr(99999, 100, false);
     }
 
+    #[test]
+    fn test_inode_table_refresh_attr_keeps_mtime() {
+        let table = InodeTable::new();
+
+        let ino = table.get_or_create("/test.txt", false, 100);
+        let original_mtime = table.get_attr(ino).unwrap().mtime;
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        // No backend mtime: size changes, timestamps don't
+        table.refresh_attr(ino, 200, false, None);
+        let attr = table.get_attr(ino).unwrap();
+        assert_eq!(attr.size, 200);
+        assert_eq!(attr.blocks, 1);
+        assert_eq!(attr.mtime, original_mtime);
+    }
+
+    #[test]
+    fn test_inode_table_refresh_attr_uses_backend_mtime() {
+        let table = InodeTable::new();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let ino = table.get_or_create("/testdir", true, 0);
+        table.refresh_attr(ino, 999, true, Some(mtime));
+
+        let attr = table.get_attr(ino).unwrap();
+        assert_eq!(attr.size, 4096);
+        assert_eq!(attr.mtime, mtime);
+        assert_eq!(attr.ctime, mtime);
+    }
+
+    #[test]
+    fn test_inode_table_set_mtime() {
+        let table = InodeTable::new();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+
+        let ino = table.get_or_create("/test.txt", false, 100);
+        table.set_mtime(ino, mtime);
+        assert_eq!(table.get_attr(ino).unwrap().mtime, mtime);
+
+        // Should not panic
+        table.set_mtime(99999, mtime);
+    }
+
     // ============== Symlink Tests ==============
 
     #[test]
@@ -745,6 +918,25 @@ mod tests {
         assert_ne!(vino1, vino2);
     }
 
+    #[test]
+    fn test_inode_table_register_virtual() {
+        let table = InodeTable::new();
+
+        let vino = table.alloc_virtual_ino();
+        table.register_virtual("/.search/hello/", InodeAttr::directory(vino));
+
+        assert_eq!(table.get_ino("/.search/hello"), Some(vino));
+        assert_eq!(table.get_path(vino), Some("/.search/hello".to_string()));
+        assert_eq!(table.get_attr(vino).unwrap().kind, InodeKind::Directory);
+
+        // Re-registering the path leaves it with the newer inode.
+        let newer = table.alloc_virtual_ino();
+        table.register_virtual("/.search/hello", InodeAttr::directory(newer));
+        table.remove(vino);
+        assert!(table.get_path(vino).is_none());
+        assert_eq!(table.get_ino("/.search/hello"), Some(newer));
+    }
+
     #[test]
     fn test_virtual_ino_base_is_large() {
         // Virtual inodes should be in a separate space from regular inodes
@@ -785,6 +977,52 @@ mod tests {
         assert_eq!(child, None);
     }
 
+    // ============== Invalidation Tests ==============
+
+    #[test]
+    fn test_invalidations() {
+        let table = InodeTable::new();
+        let dir = table.get_or_create("/workspace", true, 0);
+        let file = table.get_or_create("/workspace/a.txt", false, 10);
+
+        assert_eq!(
+            table.invalidations("/workspace/a.txt", false),
+            [Invalidation::Inode(file)]
+        );
+        assert_eq!(
+            table.invalidations("/workspace/new/b.txt", false),
+            [
+                Invalidation::Entry {
+                    parent: dir,
+                    name: "new".to_string()
+                },
+                Invalidation::Inode(dir),
+            ]
+        );
+        assert_eq!(
+            table.invalidations("/workspace/a.txt", true),
+            [
+                Invalidation::Delete {
+                    parent: dir,
+                    child: file,
+                    name: "a.txt".to_string()
+                },
+                Invalidation::Inode(dir),
+            ]
+        );
+        assert_eq!(table.get_ino("/workspace/a.txt"), None);
+        assert_eq!(
+            table.invalidations("/other/c.txt", false),
+            [
+                Invalidation::Entry {
+                    parent: ROOT_INO,
+                    name: "other".to_string()
+                },
+                Invalidation::Inode(ROOT_INO),
+            ]
+        );
+    }
+
     // ============== Get Methods Tests ==============
 
     #[test]
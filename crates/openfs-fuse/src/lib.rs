@@ -18,7 +18,9 @@
 //!
 //! The crate is split into platform-neutral and platform-specific modules:
 //! - `common` — `OpenFsCore` struct with all VFS interaction logic
-//! - `unix_fuse` — `fuser::Filesystem` impl for macOS/Linux
+//! - `unix_fuse` — `fuser::Filesystem` impl for macOS/Linux (`fuse` feature)
+//! - `write_buffer` — per-handle write coalescing, flushed on fsync/close
+//! - `nfs` — userspace NFSv3 server over the same core, for hosts without FUSE
 //!
 //! # Example
 //!
@@ -33,13 +35,16 @@
 mod async_bridge;
 pub(crate) mod common;
 mod inode;
+mod nfs;
 mod search_dir;
-#[cfg(unix)]
+#[cfg(all(unix, feature = "fuse"))]
 pub(crate) mod unix_fuse;
+mod write_buffer;
 
 pub use async_bridge::{block_on, init_runtime, spawn, FuseError, FuseResult};
 pub use common::{OpenFsCore, DirEntry, FsOpError, ReadDirResult};
-pub use inode::{InodeAttr, InodeKind, InodeTable, ROOT_INO};
+pub use inode::{InodeAttr, InodeKind, InodeTable, Invalidation, ROOT_INO};
+pub use nfs::NfsServer;
 pub use search_dir::{SearchDir, SearchResultEntry, QUERY_DIR_PATH, SEARCH_DIR_PATH};
 
 /// The main FUSE filesystem type.
@@ -0,0 +1,385 @@
+//! Userspace NFSv3 server for hosts where FUSE is not available.
+//!
+//! Serves the NFS (RFC 1813) and MOUNT programs on one TCP port, backed by
+//! the same `OpenFsCore` operations as the FUSE driver. There is no
+//! portmapper, so clients pass the port explicitly:
+//!
+//! ```text
+//! mount -t nfs -o vers=3,tcp,port=11111,mountport=11111,nolock localhost:/ /mnt/openfs
+//! ```
+//!
+//! Requests carry no authentication that is checked; the server is meant to
+//! be bound to localhost.
+
+mod mount;
+mod nfs3;
+mod xdr;
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use tracing::{debug, info};
+
+use crate::common::OpenFsCore;
+use xdr::{XdrError, XdrReader, XdrWriter};
+
+const RPC_VERSION: u32 = 2;
+const MSG_CALL: u32 = 0;
+const MSG_REPLY: u32 = 1;
+const MSG_ACCEPTED: u32 = 0;
+const MSG_DENIED: u32 = 1;
+const RPC_MISMATCH: u32 = 0;
+
+const ACCEPT_SUCCESS: u32 = 0;
+const ACCEPT_PROG_UNAVAIL: u32 = 1;
+const ACCEPT_PROG_MISMATCH: u32 = 2;
+const ACCEPT_PROC_UNAVAIL: u32 = 3;
+const ACCEPT_GARBAGE_ARGS: u32 = 4;
+
+const AUTH_NONE: u32 = 0;
+const AUTH_UNIX: u32 = 1;
+const MAX_AUTH_BYTES: usize = 400;
+
+const NFS_PROGRAM: u32 = 100003;
+const NFS_VERSION: u32 = 3;
+const MOUNT_PROGRAM: u32 = 100005;
+const MOUNT_VERSION: u32 = 3;
+
+/// Largest READ/WRITE payload offered to clients.
+const MAX_IO: u32 = 1024 * 1024;
+/// Largest RPC record accepted, leaving room for headers around `MAX_IO`.
+const MAX_RECORD: usize = MAX_IO as usize + 64 * 1024;
+
+/// Why a procedure produced no result body.
+enum ProcError {
+    /// Procedure number not implemented.
+    Unavail,
+    /// Arguments could not be decoded.
+    Garbage,
+}
+
+impl From<XdrError> for ProcError {
+    fn from(_: XdrError) -> Self {
+        ProcError::Garbage
+    }
+}
+
+type ProcResult = Result<(), ProcError>;
+
+/// NFSv3 server over an `OpenFsCore`.
+pub struct NfsServer {
+    core: OpenFsCore,
+    /// Write verifier; changes on restart so clients resend unstable writes.
+    verifier: [u8; 8],
+    /// Buffered write handle per inode, opened by UNSTABLE writes and
+    /// written back on COMMIT.
+    handles: Mutex<HashMap<u64, u64>>,
+}
+
+impl NfsServer {
+    /// Create a server for a core.
+    pub fn new(core: OpenFsCore) -> Self {
+        let boot = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        NfsServer {
+            core,
+            verifier: boot.to_be_bytes(),
+            handles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Accept connections until the listener fails, one thread each.
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        info!("Serving NFSv3 on {}", listener.local_addr()?);
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = server.clone();
+            std::thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+                if let Err(e) = server.handle_connection(stream) {
+                    debug!("NFS connection {:?} closed: {}", peer, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        while let Some(record) = read_record(&mut stream)? {
+            if let Some(reply) = self.handle_call(&record) {
+                write_record(&mut stream, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode one RPC call and build its reply. Returns `None` for messages
+    /// that get no reply (malformed headers, replies).
+    fn handle_call(&self, msg: &[u8]) -> Option<Vec<u8>> {
+        let mut args = XdrReader::new(msg);
+        let xid = args.u32().ok()?;
+        if args.u32().ok()? != MSG_CALL {
+            return None;
+        }
+        let rpcvers = args.u32().ok()?;
+        let prog = args.u32().ok()?;
+        let vers = args.u32().ok()?;
+        let proc = args.u32().ok()?;
+        // Credentials and verifier: flavor + opaque body, both ignored.
+        for _ in 0..2 {
+            args.u32().ok()?;
+            args.opaque(MAX_AUTH_BYTES).ok()?;
+        }
+
+        let mut reply = XdrWriter::new();
+        reply.u32(xid);
+        reply.u32(MSG_REPLY);
+        if rpcvers != RPC_VERSION {
+            reply.u32(MSG_DENIED);
+            reply.u32(RPC_MISMATCH);
+            reply.u32(RPC_VERSION);
+            reply.u32(RPC_VERSION);
+            return Some(reply.into_inner());
+        }
+        reply.u32(MSG_ACCEPTED);
+        reply.u32(AUTH_NONE);
+        reply.opaque(&[]);
+
+        let mut body = XdrWriter::new();
+        let result = match (prog, vers) {
+            (NFS_PROGRAM, NFS_VERSION) => self.nfs3(proc, &mut args, &mut body),
+            (MOUNT_PROGRAM, MOUNT_VERSION) => self.mount(proc, &mut args, &mut body),
+            (NFS_PROGRAM, _) | (MOUNT_PROGRAM, _) => {
+                let supported = if prog == NFS_PROGRAM {
+                    NFS_VERSION
+                } else {
+                    MOUNT_VERSION
+                };
+                reply.u32(ACCEPT_PROG_MISMATCH);
+                reply.u32(supported);
+                reply.u32(supported);
+                return Some(reply.into_inner());
+            }
+            _ => {
+                debug!("NFS: unknown program {}", prog);
+                reply.u32(ACCEPT_PROG_UNAVAIL);
+                return Some(reply.into_inner());
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                reply.u32(ACCEPT_SUCCESS);
+                reply.raw(&body.into_inner());
+            }
+            Err(ProcError::Unavail) => reply.u32(ACCEPT_PROC_UNAVAIL),
+            Err(ProcError::Garbage) => reply.u32(ACCEPT_GARBAGE_ARGS),
+        }
+        Some(reply.into_inner())
+    }
+}
+
+/// Read one record-marked RPC message. Returns `None` at end of stream.
+fn read_record(stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut record = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        match stream.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && record.is_empty() => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        }
+        let header = u32::from_be_bytes(header);
+        let last = header & 0x8000_0000 != 0;
+        let len = (header & 0x7fff_ffff) as usize;
+        if record.len() + len > MAX_RECORD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "RPC record too large",
+            ));
+        }
+        let start = record.len();
+        record.resize(start + len, 0);
+        stream.read_exact(&mut record[start..])?;
+        if last {
+            return Ok(Some(record));
+        }
+    }
+}
+
+/// Write a reply as a single last fragment.
+fn write_record(stream: &mut impl Write, reply: &[u8]) -> io::Result<()> {
+    let header = 0x8000_0000 | reply.len() as u32;
+    let mut out = Vec::with_capacity(reply.len() + 4);
+    out.extend_from_slice(&header.to_be_bytes());
+    out.extend_from_slice(reply);
+    stream.write_all(&out)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_bridge::block_on;
+    use openfs_config::VfsConfig;
+    use tempfile::TempDir;
+
+    /// A server over an `fs` backend mounted at `/workspace`.
+    pub(super) fn make_server(temp_dir: &TempDir) -> NfsServer {
+        let yaml = format!(
+            r#"
+name: test-vfs
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: local
+"#,
+            temp_dir.path().display()
+        );
+        let config = VfsConfig::from_yaml(&yaml).unwrap();
+        NfsServer::new(OpenFsCore::from_config(config).unwrap())
+    }
+
+    pub(super) fn call_message(prog: u32, vers: u32, proc: u32, args: &[u8]) -> Vec<u8> {
+        let mut w = XdrWriter::new();
+        w.u32(42);
+        w.u32(MSG_CALL);
+        w.u32(RPC_VERSION);
+        w.u32(prog);
+        w.u32(vers);
+        w.u32(proc);
+        // AUTH_UNIX credentials, as sent by Linux clients.
+        let mut cred = XdrWriter::new();
+        cred.u32(0);
+        cred.string("host");
+        cred.u32(1000);
+        cred.u32(1000);
+        cred.u32(0);
+        w.u32(AUTH_UNIX);
+        w.opaque(&cred.into_inner());
+        w.u32(AUTH_NONE);
+        w.opaque(&[]);
+        w.raw(args);
+        w.into_inner()
+    }
+
+    /// Run a call and return its accept status and result body.
+    pub(super) fn call(
+        server: &NfsServer,
+        prog: u32,
+        vers: u32,
+        proc: u32,
+        args: &[u8],
+    ) -> (u32, Vec<u8>) {
+        let reply = server
+            .handle_call(&call_message(prog, vers, proc, args))
+            .unwrap();
+        let mut r = XdrReader::new(&reply);
+        assert_eq!(r.u32(), Ok(42));
+        assert_eq!(r.u32(), Ok(MSG_REPLY));
+        assert_eq!(r.u32(), Ok(MSG_ACCEPTED));
+        assert_eq!(r.u32(), Ok(AUTH_NONE));
+        r.opaque(MAX_AUTH_BYTES).unwrap();
+        let stat = r.u32().unwrap();
+        (stat, r.remaining().to_vec())
+    }
+
+    pub(super) fn write_file(server: &NfsServer, path: &str, content: &[u8]) {
+        let vfs = server.core.vfs.clone();
+        block_on(async { vfs.write(path, content).await })
+            .unwrap()
+            .unwrap();
+    }
+
+    pub(super) fn read_file(server: &NfsServer, path: &str) -> Option<Vec<u8>> {
+        let vfs = server.core.vfs.clone();
+        block_on(async { vfs.read(path).await }).unwrap().ok()
+    }
+
+    #[test]
+    fn test_null_and_unknown_programs() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+
+        assert_eq!(
+            call(&server, NFS_PROGRAM, 3, 0, &[]),
+            (ACCEPT_SUCCESS, vec![])
+        );
+        assert_eq!(
+            call(&server, MOUNT_PROGRAM, 3, 0, &[]),
+            (ACCEPT_SUCCESS, vec![])
+        );
+        assert_eq!(call(&server, 100000, 2, 0, &[]).0, ACCEPT_PROG_UNAVAIL);
+        assert_eq!(
+            call(&server, NFS_PROGRAM, 3, 99, &[]).0,
+            ACCEPT_PROC_UNAVAIL
+        );
+        // GETATTR without a handle.
+        assert_eq!(call(&server, NFS_PROGRAM, 3, 1, &[]).0, ACCEPT_GARBAGE_ARGS);
+
+        let (stat, body) = call(&server, NFS_PROGRAM, 4, 0, &[]);
+        assert_eq!(stat, ACCEPT_PROG_MISMATCH);
+        let mut r = XdrReader::new(&body);
+        assert_eq!((r.u32(), r.u32()), (Ok(3), Ok(3)));
+    }
+
+    #[test]
+    fn test_rpc_version_mismatch_denied() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+
+        let mut msg = call_message(NFS_PROGRAM, 3, 0, &[]);
+        msg[11] = 3;
+        let reply = server.handle_call(&msg).unwrap();
+        let mut r = XdrReader::new(&reply);
+        assert_eq!(r.u32(), Ok(42));
+        assert_eq!(r.u32(), Ok(MSG_REPLY));
+        assert_eq!(r.u32(), Ok(MSG_DENIED));
+        assert_eq!(r.u32(), Ok(RPC_MISMATCH));
+    }
+
+    #[test]
+    fn test_read_record_joins_fragments() {
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&3u32.to_be_bytes());
+        stream.extend_from_slice(b"abc");
+        stream.extend_from_slice(&(0x8000_0000u32 | 2).to_be_bytes());
+        stream.extend_from_slice(b"de");
+        let mut reader = &stream[..];
+        assert_eq!(read_record(&mut reader).unwrap(), Some(b"abcde".to_vec()));
+        assert_eq!(read_record(&mut reader).unwrap(), None);
+
+        let oversized = (0x8000_0000u32 | (MAX_RECORD as u32 + 1)).to_be_bytes();
+        assert!(read_record(&mut &oversized[..]).is_err());
+    }
+
+    #[test]
+    fn test_serve_over_tcp() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || server.serve(listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        for _ in 0..2 {
+            write_record(&mut stream, &call_message(NFS_PROGRAM, 3, 0, &[])).unwrap();
+            let reply = read_record(&mut stream).unwrap().unwrap();
+            let mut r = XdrReader::new(&reply);
+            assert_eq!(r.u32(), Ok(42));
+            assert_eq!(r.u32(), Ok(MSG_REPLY));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! MOUNT protocol, version 3 (RFC 1813, appendix I).
+//!
+//! The whole VFS is exported as `/`; mounting a deeper path resolves it to
+//! that directory's handle.
+
+use super::nfs3::file_handle;
+use super::xdr::{XdrReader, XdrWriter};
+use super::{NfsServer, ProcError, ProcResult, AUTH_UNIX};
+use crate::inode::{InodeKind, ROOT_INO};
+
+const MNTPATHLEN: usize = 1024;
+
+const MNT3_OK: u32 = 0;
+const MNT3ERR_NOENT: u32 = 2;
+const MNT3ERR_NOTDIR: u32 = 20;
+
+const PROC_NULL: u32 = 0;
+const PROC_MNT: u32 = 1;
+const PROC_DUMP: u32 = 2;
+const PROC_UMNT: u32 = 3;
+const PROC_UMNTALL: u32 = 4;
+const PROC_EXPORT: u32 = 5;
+
+impl NfsServer {
+    pub(super) fn mount(&self, proc: u32, args: &mut XdrReader, out: &mut XdrWriter) -> ProcResult {
+        match proc {
+            PROC_NULL | PROC_UMNTALL => Ok(()),
+            PROC_MNT => {
+                let path = args.string(MNTPATHLEN)?;
+                match self.resolve_export(path) {
+                    Ok(ino) => {
+                        out.u32(MNT3_OK);
+                        out.opaque(&file_handle(ino));
+                        out.u32(1);
+                        out.u32(AUTH_UNIX);
+                    }
+                    Err(status) => out.u32(status),
+                }
+                Ok(())
+            }
+            PROC_DUMP => {
+                // Mounts are not tracked.
+                out.bool(false);
+                Ok(())
+            }
+            PROC_UMNT => {
+                args.string(MNTPATHLEN)?;
+                Ok(())
+            }
+            PROC_EXPORT => {
+                out.bool(true);
+                out.string("/");
+                // No group restrictions.
+                out.bool(false);
+                out.bool(false);
+                Ok(())
+            }
+            _ => Err(ProcError::Unavail),
+        }
+    }
+
+    /// Walk a mount path from the root to a directory inode.
+    fn resolve_export(&self, path: &str) -> Result<u64, u32> {
+        let mut ino = ROOT_INO;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let attr = self.core.do_lookup(ino, name).map_err(|_| MNT3ERR_NOENT)?;
+            if attr.kind != InodeKind::Directory {
+                return Err(MNT3ERR_NOTDIR);
+            }
+            ino = attr.ino;
+        }
+        Ok(ino)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{call, make_server, write_file};
+    use super::super::{ACCEPT_SUCCESS, MOUNT_PROGRAM};
+    use super::*;
+    use tempfile::TempDir;
+
+    fn mnt(server: &NfsServer, path: &str) -> (u32, Option<Vec<u8>>) {
+        let mut args = XdrWriter::new();
+        args.string(path);
+        let (stat, body) = call(server, MOUNT_PROGRAM, 3, PROC_MNT, &args.into_inner());
+        assert_eq!(stat, ACCEPT_SUCCESS);
+        let mut r = XdrReader::new(&body);
+        let status = r.u32().unwrap();
+        let handle = (status == MNT3_OK).then(|| r.opaque(64).unwrap().to_vec());
+        (status, handle)
+    }
+
+    #[test]
+    fn test_mnt_root_and_subdirectory() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        write_file(&server, "/workspace/docs/a.txt", b"a");
+
+        assert_eq!(
+            mnt(&server, "/"),
+            (MNT3_OK, Some(file_handle(ROOT_INO).to_vec()))
+        );
+
+        let (status, handle) = mnt(&server, "/workspace/docs");
+        assert_eq!(status, MNT3_OK);
+        let ino = server.core.inodes.get_ino("/workspace/docs").unwrap();
+        assert_eq!(handle, Some(file_handle(ino).to_vec()));
+
+        assert_eq!(mnt(&server, "/workspace/missing"), (MNT3ERR_NOENT, None));
+        assert_eq!(
+            mnt(&server, "/workspace/docs/a.txt"),
+            (MNT3ERR_NOTDIR, None)
+        );
+    }
+
+    #[test]
+    fn test_export_lists_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+
+        let (stat, body) = call(&server, MOUNT_PROGRAM, 3, PROC_EXPORT, &[]);
+        assert_eq!(stat, ACCEPT_SUCCESS);
+        let mut r = XdrReader::new(&body);
+        assert_eq!(r.bool(), Ok(true));
+        assert_eq!(r.string(MNTPATHLEN), Ok("/"));
+        assert_eq!(r.bool(), Ok(false));
+        assert_eq!(r.bool(), Ok(false));
+    }
+}
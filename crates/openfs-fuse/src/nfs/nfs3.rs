@@ -0,0 +1,1084 @@
+//! NFS protocol, version 3 (RFC 1813).
+//!
+//! File handles are the 8-byte inode number. Handles outlive neither a
+//! server restart nor a rename, since the inode table is rebuilt by path.
+//!
+//! UNSTABLE writes go into a buffered `OpenFsCore` handle per inode and are
+//! written back on COMMIT, which clients send on `close`/`fsync`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use openfs_core::{PathSanitizer, PathViolation, MAX_COMPONENT_LEN};
+
+use super::xdr::{XdrError, XdrReader, XdrWriter};
+use super::{NfsServer, ProcError, ProcResult, MAX_IO};
+use crate::common::{FsOpError, OpenFsCore};
+use crate::inode::{InodeAttr, InodeKind, ROOT_INO, VIRTUAL_INO_BASE};
+
+const FHSIZE: usize = 64;
+const NAME_MAX: usize = MAX_COMPONENT_LEN;
+/// Longest name accepted off the wire; longer than `NAME_MAX` so the client
+/// gets NAMETOOLONG rather than a decoding error.
+const NAME_WIRE_MAX: usize = 1024;
+const FSID: u64 = 1;
+
+const NFS3_OK: u32 = 0;
+const NFS3ERR_NOENT: u32 = 2;
+const NFS3ERR_IO: u32 = 5;
+const NFS3ERR_EXIST: u32 = 17;
+const NFS3ERR_ISDIR: u32 = 21;
+const NFS3ERR_INVAL: u32 = 22;
+const NFS3ERR_ROFS: u32 = 30;
+const NFS3ERR_NAMETOOLONG: u32 = 63;
+const NFS3ERR_NOTEMPTY: u32 = 66;
+const NFS3ERR_STALE: u32 = 70;
+const NFS3ERR_BADHANDLE: u32 = 10001;
+const NFS3ERR_BAD_COOKIE: u32 = 10003;
+const NFS3ERR_NOTSUPP: u32 = 10004;
+const NFS3ERR_TOOSMALL: u32 = 10005;
+
+const PROC_NULL: u32 = 0;
+const PROC_GETATTR: u32 = 1;
+const PROC_SETATTR: u32 = 2;
+const PROC_LOOKUP: u32 = 3;
+const PROC_ACCESS: u32 = 4;
+const PROC_READLINK: u32 = 5;
+const PROC_READ: u32 = 6;
+const PROC_WRITE: u32 = 7;
+const PROC_CREATE: u32 = 8;
+const PROC_MKDIR: u32 = 9;
+const PROC_SYMLINK: u32 = 10;
+const PROC_MKNOD: u32 = 11;
+const PROC_REMOVE: u32 = 12;
+const PROC_RMDIR: u32 = 13;
+const PROC_RENAME: u32 = 14;
+const PROC_LINK: u32 = 15;
+const PROC_READDIR: u32 = 16;
+const PROC_READDIRPLUS: u32 = 17;
+const PROC_FSSTAT: u32 = 18;
+const PROC_FSINFO: u32 = 19;
+const PROC_PATHCONF: u32 = 20;
+const PROC_COMMIT: u32 = 21;
+
+const NF3REG: u32 = 1;
+const NF3DIR: u32 = 2;
+const NF3LNK: u32 = 5;
+
+const UNSTABLE: u32 = 0;
+const FILE_SYNC: u32 = 2;
+
+const UNCHECKED: u32 = 0;
+const GUARDED: u32 = 1;
+const EXCLUSIVE: u32 = 2;
+
+const DONT_CHANGE: u32 = 0;
+const SET_TO_SERVER_TIME: u32 = 1;
+const SET_TO_CLIENT_TIME: u32 = 2;
+
+const ACCESS_ALL: u32 = 0x3f;
+const FSF3_HOMOGENEOUS: u32 = 0x08;
+const FSF3_CANSETTIME: u32 = 0x10;
+
+/// Reply bytes around the entry list of a READDIR/READDIRPLUS result.
+const READDIR_OVERHEAD: usize = 128;
+
+/// The file handle for an inode.
+pub(super) fn file_handle(ino: u64) -> [u8; 8] {
+    ino.to_be_bytes()
+}
+
+/// Map a core error to an NFS status.
+fn nfs_status(e: &FsOpError) -> u32 {
+    match e {
+        FsOpError::NotFound => NFS3ERR_NOENT,
+        FsOpError::ReadOnly => NFS3ERR_ROFS,
+        FsOpError::InvalidArg | FsOpError::NotSymlink => NFS3ERR_INVAL,
+        FsOpError::NotEmpty => NFS3ERR_NOTEMPTY,
+        FsOpError::IsDir => NFS3ERR_ISDIR,
+        FsOpError::Exists => NFS3ERR_EXIST,
+        FsOpError::Io(_) | FsOpError::NoAttr => NFS3ERR_IO,
+    }
+}
+
+/// Like `nfs_status`, for operations on the object a handle names: if it
+/// is gone, the handle is stale.
+fn handle_status(e: &FsOpError) -> u32 {
+    match e {
+        FsOpError::NotFound => NFS3ERR_STALE,
+        e => nfs_status(e),
+    }
+}
+
+fn check_name(name: &str) -> Result<(), u32> {
+    match PathSanitizer::default().check_name(name) {
+        Ok(()) => Ok(()),
+        Err(PathViolation::ComponentTooLong(_)) => Err(NFS3ERR_NAMETOOLONG),
+        Err(_) => Err(NFS3ERR_INVAL),
+    }
+}
+
+/// Attribute changes requested by SETATTR, CREATE and MKDIR. Mode and
+/// ownership are accepted but not stored.
+struct SetAttr {
+    size: Option<u64>,
+    mtime: Option<SystemTime>,
+}
+
+fn read_time(args: &mut XdrReader) -> Result<SystemTime, XdrError> {
+    let secs = args.u32()? as u64;
+    let nanos = args.u32()?;
+    Ok(UNIX_EPOCH + Duration::new(secs, nanos.min(999_999_999)))
+}
+
+fn read_set_time(args: &mut XdrReader) -> Result<Option<SystemTime>, XdrError> {
+    match args.u32()? {
+        DONT_CHANGE => Ok(None),
+        SET_TO_SERVER_TIME => Ok(Some(SystemTime::now())),
+        SET_TO_CLIENT_TIME => read_time(args).map(Some),
+        _ => Err(XdrError),
+    }
+}
+
+fn read_sattr(args: &mut XdrReader) -> Result<SetAttr, XdrError> {
+    // mode, uid, gid
+    for _ in 0..3 {
+        if args.bool()? {
+            args.u32()?;
+        }
+    }
+    let size = if args.bool()? {
+        Some(args.u64()?)
+    } else {
+        None
+    };
+    read_set_time(args)?;
+    let mtime = read_set_time(args)?;
+    Ok(SetAttr { size, mtime })
+}
+
+fn write_time(out: &mut XdrWriter, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    out.u32(since_epoch.as_secs() as u32);
+    out.u32(since_epoch.subsec_nanos());
+}
+
+fn write_fattr(out: &mut XdrWriter, attr: &InodeAttr) {
+    out.u32(match attr.kind {
+        InodeKind::File => NF3REG,
+        InodeKind::Directory => NF3DIR,
+        InodeKind::Symlink => NF3LNK,
+    });
+    out.u32(attr.perm as u32);
+    out.u32(attr.nlink);
+    out.u32(attr.uid);
+    out.u32(attr.gid);
+    out.u64(attr.size);
+    out.u64(attr.blocks * 512);
+    // rdev
+    out.u32(0);
+    out.u32(0);
+    out.u64(FSID);
+    out.u64(attr.ino);
+    write_time(out, attr.atime);
+    write_time(out, attr.mtime);
+    write_time(out, attr.ctime);
+}
+
+fn write_post_op_attr(out: &mut XdrWriter, attr: Option<&InodeAttr>) {
+    out.bool(attr.is_some());
+    if let Some(attr) = attr {
+        write_fattr(out, attr);
+    }
+}
+
+impl NfsServer {
+    pub(super) fn nfs3(&self, proc: u32, args: &mut XdrReader, out: &mut XdrWriter) -> ProcResult {
+        match proc {
+            PROC_NULL => {}
+            PROC_GETATTR => {
+                let fh = args.opaque(FHSIZE)?;
+                let result = self
+                    .resolve(fh)
+                    .and_then(|ino| self.core.do_getattr(ino).map_err(|e| handle_status(&e)));
+                match result {
+                    Ok(attr) => {
+                        out.u32(NFS3_OK);
+                        write_fattr(out, &attr);
+                    }
+                    Err(status) => out.u32(status),
+                }
+            }
+            PROC_SETATTR => {
+                let fh = args.opaque(FHSIZE)?;
+                let sattr = read_sattr(args)?;
+                // The ctime guard is not checked.
+                if args.bool()? {
+                    read_time(args)?;
+                }
+                let ino = self.resolve(fh);
+                let result = ino.and_then(|ino| self.setattr(ino, &sattr));
+                self.write_status_wcc(out, result, ino.ok());
+            }
+            PROC_LOOKUP => {
+                let fh = args.opaque(FHSIZE)?;
+                let name = args.string(NAME_WIRE_MAX)?;
+                let dir = self.resolve(fh);
+                match dir.and_then(|dir| self.lookup(dir, name)) {
+                    Ok(ino) => {
+                        out.u32(NFS3_OK);
+                        out.opaque(&file_handle(ino));
+                        self.write_post_op(out, Some(ino));
+                        self.write_post_op(out, dir.ok());
+                    }
+                    Err(status) => {
+                        out.u32(status);
+                        self.write_post_op(out, dir.ok());
+                    }
+                }
+            }
+            PROC_ACCESS => {
+                let fh = args.opaque(FHSIZE)?;
+                let access = args.u32()?;
+                match self.resolve(fh) {
+                    Ok(ino) => {
+                        out.u32(NFS3_OK);
+                        self.write_post_op(out, Some(ino));
+                        out.u32(access & ACCESS_ALL);
+                    }
+                    Err(status) => {
+                        out.u32(status);
+                        out.bool(false);
+                    }
+                }
+            }
+            PROC_READLINK => {
+                let fh = args.opaque(FHSIZE)?;
+                let ino = self.resolve(fh);
+                match ino.and_then(|ino| self.core.do_readlink(ino).map_err(|e| nfs_status(&e))) {
+                    Ok(target) => {
+                        out.u32(NFS3_OK);
+                        self.write_post_op(out, ino.ok());
+                        out.string(&target);
+                    }
+                    Err(status) => {
+                        out.u32(status);
+                        self.write_post_op(out, ino.ok());
+                    }
+                }
+            }
+            PROC_READ => {
+                let fh = args.opaque(FHSIZE)?;
+                let offset = args.u64()?;
+                let count = args.u32()?;
+                self.read(fh, offset, count, out);
+            }
+            PROC_WRITE => {
+                let fh = args.opaque(FHSIZE)?;
+                let offset = args.u64()?;
+                let _count = args.u32()?;
+                let stable = args.u32()?;
+                let data = args.opaque(MAX_IO as usize)?;
+                if stable > FILE_SYNC {
+                    return Err(ProcError::Garbage);
+                }
+                self.write(fh, offset, stable, data, out);
+            }
+            PROC_CREATE => {
+                let fh = args.opaque(FHSIZE)?;
+                let name = args.string(NAME_WIRE_MAX)?;
+                let mode = args.u32()?;
+                let sattr = match mode {
+                    UNCHECKED | GUARDED => Some(read_sattr(args)?),
+                    EXCLUSIVE => {
+                        args.fixed_opaque(8)?;
+                        None
+                    }
+                    _ => return Err(ProcError::Garbage),
+                };
+                let dir = self.resolve(fh);
+                let result = dir.and_then(|dir| self.create(dir, name, mode, sattr.as_ref()));
+                self.write_created(out, result, dir.ok());
+            }
+            PROC_MKDIR => {
+                let fh = args.opaque(FHSIZE)?;
+                let name = args.string(NAME_WIRE_MAX)?;
+                read_sattr(args)?;
+                let dir = self.resolve(fh);
+                let result = dir.and_then(|dir| self.mkdir(dir, name));
+                self.write_created(out, result, dir.ok());
+            }
+            PROC_SYMLINK | PROC_MKNOD => {
+                self.write_status_wcc(out, Err(NFS3ERR_NOTSUPP), None);
+            }
+            PROC_REMOVE | PROC_RMDIR => {
+                let fh = args.opaque(FHSIZE)?;
+                let name = args.string(NAME_WIRE_MAX)?;
+                let dir = self.resolve(fh);
+                let result = dir.and_then(|dir| self.remove(dir, name, proc == PROC_RMDIR));
+                self.write_status_wcc(out, result, dir.ok());
+            }
+            PROC_RENAME => {
+                let from_fh = args.opaque(FHSIZE)?;
+                let from_name = args.string(NAME_WIRE_MAX)?;
+                let to_fh = args.opaque(FHSIZE)?;
+                let to_name = args.string(NAME_WIRE_MAX)?;
+                let from_dir = self.resolve(from_fh);
+                let to_dir = self.resolve(to_fh);
+                let result = from_dir
+                    .and_then(|from_dir| self.rename(from_dir, from_name, to_dir?, to_name));
+                self.write_status_wcc(out, result, from_dir.ok());
+                self.write_wcc(out, to_dir.ok());
+            }
+            PROC_LINK => {
+                out.u32(NFS3ERR_NOTSUPP);
+                out.bool(false);
+                self.write_wcc(out, None);
+            }
+            PROC_READDIR | PROC_READDIRPLUS => {
+                let fh = args.opaque(FHSIZE)?;
+                let cookie = args.u64()?;
+                args.fixed_opaque(8)?;
+                if proc == PROC_READDIRPLUS {
+                    // dircount: only the overall reply size is enforced.
+                    args.u32()?;
+                }
+                let count = args.u32()?;
+                self.readdir(fh, cookie, count, proc == PROC_READDIRPLUS, out);
+            }
+            PROC_FSSTAT | PROC_FSINFO | PROC_PATHCONF => {
+                let fh = args.opaque(FHSIZE)?;
+                let ino = match self.resolve(fh) {
+                    Ok(ino) => ino,
+                    Err(status) => {
+                        out.u32(status);
+                        out.bool(false);
+                        return Ok(());
+                    }
+                };
+                out.u32(NFS3_OK);
+                self.write_post_op(out, Some(ino));
+                match proc {
+                    PROC_FSSTAT => write_fsstat(out),
+                    PROC_FSINFO => write_fsinfo(out),
+                    _ => write_pathconf(out),
+                }
+            }
+            PROC_COMMIT => {
+                let fh = args.opaque(FHSIZE)?;
+                args.u64()?;
+                args.u32()?;
+                let ino = self.resolve(fh);
+                let result = ino.and_then(|ino| self.commit(ino).map_err(|e| handle_status(&e)));
+                let ok = result.is_ok();
+                self.write_status_wcc(out, result, ino.ok());
+                if ok {
+                    out.fixed_opaque(&self.verifier);
+                }
+            }
+            _ => return Err(ProcError::Unavail),
+        }
+        Ok(())
+    }
+
+    /// The inode a file handle names, if it is still known.
+    fn resolve(&self, fh: &[u8]) -> Result<u64, u32> {
+        let bytes: [u8; 8] = fh.try_into().map_err(|_| NFS3ERR_BADHANDLE)?;
+        let ino = u64::from_be_bytes(bytes);
+        if self.core.get_path(ino).is_some() || self.core.inodes.get_attr(ino).is_some() {
+            Ok(ino)
+        } else {
+            Err(NFS3ERR_STALE)
+        }
+    }
+
+    fn write_post_op(&self, out: &mut XdrWriter, ino: Option<u64>) {
+        let attr = ino.and_then(|ino| self.core.inodes.get_attr(ino));
+        write_post_op_attr(out, attr.as_ref());
+    }
+
+    /// `wcc_data` with post-operation attributes only.
+    fn write_wcc(&self, out: &mut XdrWriter, ino: Option<u64>) {
+        out.bool(false);
+        self.write_post_op(out, ino);
+    }
+
+    fn write_status_wcc(&self, out: &mut XdrWriter, result: Result<(), u32>, ino: Option<u64>) {
+        out.u32(result.err().unwrap_or(NFS3_OK));
+        self.write_wcc(out, ino);
+    }
+
+    /// Result of CREATE and MKDIR.
+    fn write_created(&self, out: &mut XdrWriter, result: Result<u64, u32>, dir: Option<u64>) {
+        match result {
+            Ok(ino) => {
+                out.u32(NFS3_OK);
+                out.bool(true);
+                out.opaque(&file_handle(ino));
+                self.write_post_op(out, Some(ino));
+            }
+            Err(status) => out.u32(status),
+        }
+        self.write_wcc(out, dir);
+    }
+
+    fn parent_of(&self, dir: u64) -> u64 {
+        self.core
+            .get_path(dir)
+            .and_then(|path| {
+                let (parent, _) = path.rsplit_once('/')?;
+                let parent = if parent.is_empty() { "/" } else { parent };
+                self.core.inodes.get_ino(parent)
+            })
+            .unwrap_or(ROOT_INO)
+    }
+
+    fn lookup(&self, dir: u64, name: &str) -> Result<u64, u32> {
+        match name {
+            "." => Ok(dir),
+            ".." => Ok(self.parent_of(dir)),
+            _ => {
+                check_name(name)?;
+                self.core
+                    .do_lookup(dir, name)
+                    .map(|attr| attr.ino)
+                    .map_err(|e| nfs_status(&e))
+            }
+        }
+    }
+
+    fn setattr(&self, ino: u64, sattr: &SetAttr) -> Result<(), u32> {
+        if sattr.size.is_none() && sattr.mtime.is_none() {
+            return Ok(());
+        }
+        self.core
+            .do_setattr(ino, sattr.size, sattr.mtime)
+            .map(|_| ())
+            .map_err(|e| handle_status(&e))
+    }
+
+    fn read(&self, fh: &[u8], offset: u64, count: u32, out: &mut XdrWriter) {
+        let ino = match self.resolve(fh) {
+            Ok(ino) => ino,
+            Err(status) => {
+                out.u32(status);
+                out.bool(false);
+                return;
+            }
+        };
+        let count = count.min(MAX_IO);
+        let offset = offset.min(i64::MAX as u64);
+        match self.core.do_read(ino, offset as i64, count) {
+            Ok(data) => {
+                let attr = self.core.inodes.get_attr(ino);
+                let end = offset.saturating_add(data.len() as u64);
+                let eof =
+                    (data.len() as u32) < count || attr.as_ref().is_some_and(|a| end >= a.size);
+                out.u32(NFS3_OK);
+                write_post_op_attr(out, attr.as_ref());
+                out.u32(data.len() as u32);
+                out.bool(eof);
+                out.opaque(&data);
+            }
+            Err(e) => {
+                out.u32(handle_status(&e));
+                self.write_post_op(out, Some(ino));
+            }
+        }
+    }
+
+    fn write(&self, fh: &[u8], offset: u64, stable: u32, data: &[u8], out: &mut XdrWriter) {
+        let ino = match self.resolve(fh) {
+            Ok(ino) => ino,
+            Err(status) => {
+                self.write_status_wcc(out, Err(status), None);
+                return;
+            }
+        };
+        let result = (|| {
+            let handle = self.write_handle(ino)?;
+            let offset = offset.min(i64::MAX as u64) as i64;
+            let written = self.core.do_write(ino, handle, offset, data)?;
+            let committed = if handle != 0 && stable == UNSTABLE {
+                UNSTABLE
+            } else {
+                self.core.do_flush(handle)?;
+                FILE_SYNC
+            };
+            Ok::<_, FsOpError>((written, committed))
+        })();
+        match result {
+            Ok((written, committed)) => {
+                self.write_status_wcc(out, Ok(()), Some(ino));
+                out.u32(written);
+                out.u32(committed);
+                out.fixed_opaque(&self.verifier);
+            }
+            Err(e) => self.write_status_wcc(out, Err(handle_status(&e)), Some(ino)),
+        }
+    }
+
+    /// The buffered handle UNSTABLE writes to `ino` go to, opened on first
+    /// use. 0 when write buffering is disabled.
+    fn write_handle(&self, ino: u64) -> Result<u64, FsOpError> {
+        let mut handles = self.handles.lock();
+        if let Some(&fh) = handles.get(&ino) {
+            return Ok(fh);
+        }
+        let fh = self.core.do_open(ino)?;
+        if fh != 0 {
+            handles.insert(ino, fh);
+        }
+        Ok(fh)
+    }
+
+    /// Flush and close the write handle of `ino`, if any.
+    fn release_handle(&self, ino: u64) -> Result<(), FsOpError> {
+        match self.handles.lock().remove(&ino) {
+            Some(fh) => self.core.do_release(fh),
+            None => Ok(()),
+        }
+    }
+
+    /// Write back UNSTABLE writes. The handle is only closed once they are
+    /// stored, so a failed COMMIT can be retried.
+    fn commit(&self, ino: u64) -> Result<(), FsOpError> {
+        let Some(fh) = self.handles.lock().get(&ino).copied() else {
+            return Ok(());
+        };
+        self.core.do_flush(fh)?;
+        self.release_handle(ino)
+    }
+
+    /// CREATE. `EXCLUSIVE` verifiers are not stored, so an existing file is
+    /// taken to be a retransmitted create and returned as-is.
+    fn create(&self, dir: u64, name: &str, mode: u32, sattr: Option<&SetAttr>) -> Result<u64, u32> {
+        check_name(name)?;
+        let ino = match self.core.do_lookup(dir, name) {
+            Ok(attr) if attr.kind == InodeKind::Directory => return Err(NFS3ERR_ISDIR),
+            Ok(attr) => match mode {
+                GUARDED => return Err(NFS3ERR_EXIST),
+                _ => attr.ino,
+            },
+            Err(FsOpError::NotFound) => {
+                let (attr, fh) = self.core.do_create(dir, name).map_err(|e| nfs_status(&e))?;
+                if fh != 0 {
+                    if let Some(old) = self.handles.lock().insert(attr.ino, fh) {
+                        let _ = self.core.do_release(old);
+                    }
+                }
+                attr.ino
+            }
+            Err(e) => return Err(nfs_status(&e)),
+        };
+        if let Some(sattr) = sattr {
+            self.setattr(ino, sattr)?;
+        }
+        Ok(ino)
+    }
+
+    fn mkdir(&self, dir: u64, name: &str) -> Result<u64, u32> {
+        check_name(name)?;
+        match self.core.do_lookup(dir, name) {
+            Ok(_) => Err(NFS3ERR_EXIST),
+            Err(FsOpError::NotFound) => self
+                .core
+                .do_mkdir(dir, name)
+                .map(|attr| attr.ino)
+                .map_err(|e| nfs_status(&e)),
+            Err(e) => Err(nfs_status(&e)),
+        }
+    }
+
+    fn child_ino(&self, dir: u64, name: &str) -> Option<u64> {
+        let path = OpenFsCore::child_path(&self.core.get_path(dir)?, name);
+        self.core.inodes.get_ino(&path)
+    }
+
+    fn remove(&self, dir: u64, name: &str, is_dir: bool) -> Result<(), u32> {
+        check_name(name)?;
+        let ino = self.child_ino(dir, name);
+        let result = if is_dir {
+            self.core.do_rmdir(dir, name)
+        } else {
+            self.core.do_unlink(dir, name)
+        };
+        result.map_err(|e| nfs_status(&e))?;
+        if let Some(ino) = ino {
+            // Unlinked handles have nothing left to write back.
+            let _ = self.release_handle(ino);
+        }
+        Ok(())
+    }
+
+    fn rename(
+        &self,
+        from_dir: u64,
+        from_name: &str,
+        to_dir: u64,
+        to_name: &str,
+    ) -> Result<(), u32> {
+        check_name(from_name)?;
+        check_name(to_name)?;
+        // The source inode goes away with its path, so close its handle
+        // (writing back pending data) first.
+        if let Some(ino) = self.child_ino(from_dir, from_name) {
+            self.release_handle(ino).map_err(|e| nfs_status(&e))?;
+        }
+        self.core
+            .do_rename(from_dir, from_name, to_dir, to_name)
+            .map_err(|e| nfs_status(&e))
+    }
+
+    fn readdir(&self, fh: &[u8], cookie: u64, count: u32, plus: bool, out: &mut XdrWriter) {
+        let dir = match self.resolve(fh) {
+            Ok(dir) => dir,
+            Err(status) => {
+                out.u32(status);
+                out.bool(false);
+                return;
+            }
+        };
+        let listing = match self.core.do_readdir(dir) {
+            Ok(listing) => listing,
+            Err(e) => {
+                out.u32(handle_status(&e));
+                self.write_post_op(out, Some(dir));
+                return;
+            }
+        };
+
+        // Same order as the FUSE driver: ".", "..", ".search" at the root,
+        // then the backend entries. A cookie is the position after an entry.
+        let mut names: Vec<(u64, &str)> = vec![(listing.ino, "."), (listing.parent_ino, "..")];
+        if listing.is_root {
+            names.push((VIRTUAL_INO_BASE, ".search"));
+        }
+        names.extend(listing.entries.iter().map(|e| (e.ino, e.name.as_str())));
+
+        if cookie > names.len() as u64 {
+            out.u32(NFS3ERR_BAD_COOKIE);
+            self.write_post_op(out, Some(dir));
+            return;
+        }
+
+        let budget = (count as usize).saturating_sub(READDIR_OVERHEAD);
+        let mut entries = XdrWriter::new();
+        let mut eof = true;
+        for (i, (ino, name)) in names.iter().enumerate().skip(cookie as usize) {
+            let mut entry = XdrWriter::new();
+            entry.bool(true);
+            entry.u64(*ino);
+            entry.string(name);
+            entry.u64(i as u64 + 1);
+            if plus {
+                self.write_post_op(&mut entry, Some(*ino));
+                entry.bool(true);
+                entry.opaque(&file_handle(*ino));
+            }
+            if entries.len() + entry.len() > budget {
+                eof = false;
+                break;
+            }
+            entries.raw(&entry.into_inner());
+        }
+        if !eof && entries.len() == 0 {
+            out.u32(NFS3ERR_TOOSMALL);
+            self.write_post_op(out, Some(dir));
+            return;
+        }
+
+        out.u32(NFS3_OK);
+        self.write_post_op(out, Some(dir));
+        // Cookie verifier; cookies stay valid while the listing is unchanged.
+        out.fixed_opaque(&[0; 8]);
+        out.raw(&entries.into_inner());
+        out.bool(false);
+        out.bool(eof);
+    }
+}
+
+/// Same figures the FUSE driver reports from `statfs`.
+fn write_fsstat(out: &mut XdrWriter) {
+    let block = 4096u64;
+    out.u64(1_000_000 * block);
+    out.u64(500_000 * block);
+    out.u64(500_000 * block);
+    out.u64(1_000_000);
+    out.u64(500_000);
+    out.u64(500_000);
+    // invarsec
+    out.u32(0);
+}
+
+fn write_fsinfo(out: &mut XdrWriter) {
+    // rtmax, rtpref, rtmult, then the same for writes.
+    for _ in 0..2 {
+        out.u32(MAX_IO);
+        out.u32(MAX_IO);
+        out.u32(4096);
+    }
+    // dtpref
+    out.u32(64 * 1024);
+    out.u64(u64::MAX);
+    // time_delta: 1ms
+    out.u32(0);
+    out.u32(1_000_000);
+    out.u32(FSF3_HOMOGENEOUS | FSF3_CANSETTIME);
+}
+
+fn write_pathconf(out: &mut XdrWriter) {
+    // linkmax, name_max
+    out.u32(1);
+    out.u32(NAME_MAX as u32);
+    // no_trunc, chown_restricted, case_insensitive, case_preserving
+    out.bool(true);
+    out.bool(true);
+    out.bool(false);
+    out.bool(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{call, make_server, read_file, write_file};
+    use super::super::{ACCEPT_SUCCESS, NFS_PROGRAM};
+    use super::*;
+    use tempfile::TempDir;
+
+    fn nfs(server: &NfsServer, proc: u32, args: XdrWriter) -> Vec<u8> {
+        let (stat, body) = call(server, NFS_PROGRAM, 3, proc, &args.into_inner());
+        assert_eq!(stat, ACCEPT_SUCCESS);
+        body
+    }
+
+    fn diropargs(dir: &[u8], name: &str) -> XdrWriter {
+        let mut args = XdrWriter::new();
+        args.opaque(dir);
+        args.string(name);
+        args
+    }
+
+    fn skip_post_op_attr(r: &mut XdrReader) -> Option<(u32, u64)> {
+        if !r.bool().unwrap() {
+            return None;
+        }
+        let ftype = r.u32().unwrap();
+        for _ in 0..4 {
+            r.u32().unwrap();
+        }
+        let size = r.u64().unwrap();
+        // used, rdev, fsid, fileid, atime, mtime, ctime
+        r.fixed_opaque(56).unwrap();
+        Some((ftype, size))
+    }
+
+    fn skip_wcc(r: &mut XdrReader) {
+        assert_eq!(r.bool(), Ok(false));
+        skip_post_op_attr(r);
+    }
+
+    /// LOOKUP `name` in `dir`, returning the status and handle.
+    fn lookup(server: &NfsServer, dir: &[u8], name: &str) -> (u32, Vec<u8>) {
+        let body = nfs(server, PROC_LOOKUP, diropargs(dir, name));
+        let mut r = XdrReader::new(&body);
+        let status = r.u32().unwrap();
+        let fh = if status == NFS3_OK {
+            r.opaque(FHSIZE).unwrap().to_vec()
+        } else {
+            Vec::new()
+        };
+        (status, fh)
+    }
+
+    fn lookup_path(server: &NfsServer, path: &str) -> Vec<u8> {
+        let mut fh = file_handle(ROOT_INO).to_vec();
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            let (status, next) = lookup(server, &fh, name);
+            assert_eq!(status, NFS3_OK, "lookup {}", name);
+            fh = next;
+        }
+        fh
+    }
+
+    fn getattr(server: &NfsServer, fh: &[u8]) -> Result<(u32, u64, u64), u32> {
+        let mut args = XdrWriter::new();
+        args.opaque(fh);
+        let body = nfs(server, PROC_GETATTR, args);
+        let mut r = XdrReader::new(&body);
+        match r.u32().unwrap() {
+            NFS3_OK => {
+                let ftype = r.u32().unwrap();
+                for _ in 0..4 {
+                    r.u32().unwrap();
+                }
+                let size = r.u64().unwrap();
+                // used, rdev, fsid, fileid, atime
+                r.fixed_opaque(40).unwrap();
+                let mtime = r.u32().unwrap() as u64;
+                Ok((ftype, size, mtime))
+            }
+            status => Err(status),
+        }
+    }
+
+    fn read(server: &NfsServer, fh: &[u8], offset: u64, count: u32) -> (Vec<u8>, bool) {
+        let mut args = XdrWriter::new();
+        args.opaque(fh);
+        args.u64(offset);
+        args.u32(count);
+        let body = nfs(server, PROC_READ, args);
+        let mut r = XdrReader::new(&body);
+        assert_eq!(r.u32(), Ok(NFS3_OK));
+        skip_post_op_attr(&mut r);
+        let len = r.u32().unwrap();
+        let eof = r.bool().unwrap();
+        let data = r.opaque(MAX_IO as usize).unwrap().to_vec();
+        assert_eq!(len as usize, data.len());
+        (data, eof)
+    }
+
+    fn write(server: &NfsServer, fh: &[u8], offset: u64, stable: u32, data: &[u8]) -> u32 {
+        let mut args = XdrWriter::new();
+        args.opaque(fh);
+        args.u64(offset);
+        args.u32(data.len() as u32);
+        args.u32(stable);
+        args.opaque(data);
+        let body = nfs(server, PROC_WRITE, args);
+        let mut r = XdrReader::new(&body);
+        assert_eq!(r.u32(), Ok(NFS3_OK));
+        skip_wcc(&mut r);
+        assert_eq!(r.u32(), Ok(data.len() as u32));
+        let committed = r.u32().unwrap();
+        assert_eq!(r.fixed_opaque(8), Ok(&server.verifier[..]));
+        committed
+    }
+
+    fn status_only(server: &NfsServer, proc: u32, args: XdrWriter) -> u32 {
+        let body = nfs(server, proc, args);
+        XdrReader::new(&body).u32().unwrap()
+    }
+
+    fn empty_sattr(args: &mut XdrWriter) {
+        for _ in 0..4 {
+            args.bool(false);
+        }
+        args.u32(DONT_CHANGE);
+        args.u32(DONT_CHANGE);
+    }
+
+    fn readdir_names(server: &NfsServer, fh: &[u8], plus: bool, count: u32) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut cookie = 0;
+        loop {
+            let mut args = XdrWriter::new();
+            args.opaque(fh);
+            args.u64(cookie);
+            args.fixed_opaque(&[0; 8]);
+            if plus {
+                args.u32(count);
+            }
+            args.u32(count);
+            let proc = if plus { PROC_READDIRPLUS } else { PROC_READDIR };
+            let body = nfs(server, proc, args);
+            let mut r = XdrReader::new(&body);
+            assert_eq!(r.u32(), Ok(NFS3_OK));
+            skip_post_op_attr(&mut r);
+            r.fixed_opaque(8).unwrap();
+            while r.bool().unwrap() {
+                let fileid = r.u64().unwrap();
+                names.push(r.string(NAME_MAX).unwrap().to_string());
+                cookie = r.u64().unwrap();
+                if plus {
+                    skip_post_op_attr(&mut r);
+                    assert_eq!(r.bool(), Ok(true));
+                    assert_eq!(r.opaque(FHSIZE), Ok(&file_handle(fileid)[..]));
+                }
+            }
+            if r.bool().unwrap() {
+                return names;
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_getattr_read() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        write_file(&server, "/workspace/hello.txt", b"hello world");
+
+        let fh = lookup_path(&server, "/workspace/hello.txt");
+        let (ftype, size, _) = getattr(&server, &fh).unwrap();
+        assert_eq!((ftype, size), (NF3REG, 11));
+        assert_eq!(getattr(&server, &file_handle(ROOT_INO)).unwrap().0, NF3DIR);
+
+        assert_eq!(read(&server, &fh, 0, 5), (b"hello".to_vec(), false));
+        assert_eq!(read(&server, &fh, 6, 100), (b"world".to_vec(), true));
+
+        let workspace = lookup_path(&server, "/workspace");
+        assert_eq!(lookup(&server, &workspace, "missing").0, NFS3ERR_NOENT);
+        assert_eq!(
+            lookup(&server, &workspace, ".."),
+            (NFS3_OK, file_handle(ROOT_INO).to_vec())
+        );
+        assert_eq!(getattr(&server, &[1, 2, 3]), Err(NFS3ERR_BADHANDLE));
+        assert_eq!(getattr(&server, &file_handle(987_654)), Err(NFS3ERR_STALE));
+    }
+
+    #[test]
+    fn test_unstable_writes_stored_on_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        write_file(&server, "/workspace/keep.txt", b"");
+        let dir = lookup_path(&server, "/workspace");
+
+        let mut args = diropargs(&dir, "new.txt");
+        args.u32(GUARDED);
+        empty_sattr(&mut args);
+        let body = nfs(&server, PROC_CREATE, args);
+        let mut r = XdrReader::new(&body);
+        assert_eq!(r.u32(), Ok(NFS3_OK));
+        assert_eq!(r.bool(), Ok(true));
+        let fh = r.opaque(FHSIZE).unwrap().to_vec();
+
+        assert_eq!(write(&server, &fh, 0, UNSTABLE, b"hello "), UNSTABLE);
+        assert_eq!(write(&server, &fh, 6, UNSTABLE, b"nfs"), UNSTABLE);
+        assert_eq!(read(&server, &fh, 0, 100), (b"hello nfs".to_vec(), true));
+        assert_eq!(read_file(&server, "/workspace/new.txt"), Some(Vec::new()));
+
+        let mut args = XdrWriter::new();
+        args.opaque(&fh);
+        args.u64(0);
+        args.u32(0);
+        let body = nfs(&server, PROC_COMMIT, args);
+        let mut r = XdrReader::new(&body);
+        assert_eq!(r.u32(), Ok(NFS3_OK));
+        skip_wcc(&mut r);
+        assert_eq!(r.fixed_opaque(8), Ok(&server.verifier[..]));
+        assert_eq!(
+            read_file(&server, "/workspace/new.txt"),
+            Some(b"hello nfs".to_vec())
+        );
+        assert!(server.handles.lock().is_empty());
+
+        // Stable writes are stored before the reply.
+        assert_eq!(write(&server, &fh, 9, FILE_SYNC, b"!"), FILE_SYNC);
+        assert_eq!(
+            read_file(&server, "/workspace/new.txt"),
+            Some(b"hello nfs!".to_vec())
+        );
+
+        let mut args = diropargs(&dir, "new.txt");
+        args.u32(GUARDED);
+        empty_sattr(&mut args);
+        assert_eq!(status_only(&server, PROC_CREATE, args), NFS3ERR_EXIST);
+    }
+
+    #[test]
+    fn test_setattr_truncates_and_sets_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        write_file(&server, "/workspace/a.txt", b"content");
+        let fh = lookup_path(&server, "/workspace/a.txt");
+
+        let mut args = XdrWriter::new();
+        args.opaque(&fh);
+        for _ in 0..3 {
+            args.bool(false);
+        }
+        args.bool(true);
+        args.u64(0);
+        args.u32(DONT_CHANGE);
+        args.u32(SET_TO_CLIENT_TIME);
+        args.u32(1_600_000_000);
+        args.u32(0);
+        args.bool(false);
+        assert_eq!(status_only(&server, PROC_SETATTR, args), NFS3_OK);
+
+        assert_eq!(read_file(&server, "/workspace/a.txt"), Some(Vec::new()));
+        let (_, size, mtime) = getattr(&server, &fh).unwrap();
+        assert_eq!((size, mtime), (0, 1_600_000_000));
+    }
+
+    #[test]
+    fn test_readdir_pages_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        for i in 0..20 {
+            write_file(&server, &format!("/workspace/file-{:02}.txt", i), b"x");
+        }
+        let dir = lookup_path(&server, "/workspace");
+
+        let mut expected: Vec<String> = vec![".".into(), "..".into()];
+        expected.extend((0..20).map(|i| format!("file-{:02}.txt", i)));
+
+        for plus in [false, true] {
+            let mut names = readdir_names(&server, &dir, plus, 512);
+            names[2..].sort();
+            assert_eq!(names, expected);
+        }
+
+        let root = readdir_names(&server, &file_handle(ROOT_INO), false, 4096);
+        assert!(root.contains(&".search".to_string()));
+        assert!(root.contains(&"workspace".to_string()));
+    }
+
+    #[test]
+    fn test_mkdir_rename_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = make_server(&temp_dir);
+        write_file(&server, "/workspace/a.txt", b"a");
+        let dir = lookup_path(&server, "/workspace");
+
+        let mut args = diropargs(&dir, "sub");
+        empty_sattr(&mut args);
+        assert_eq!(status_only(&server, PROC_MKDIR, args), NFS3_OK);
+        let mut args = diropargs(&dir, "sub");
+        empty_sattr(&mut args);
+        assert_eq!(status_only(&server, PROC_MKDIR, args), NFS3ERR_EXIST);
+
+        let sub = lookup_path(&server, "/workspace/sub");
+        let mut args = diropargs(&dir, "a.txt");
+        args.opaque(&sub);
+        args.string("b.txt");
+        assert_eq!(status_only(&server, PROC_RENAME, args), NFS3_OK);
+        assert_eq!(
+            read_file(&server, "/workspace/sub/b.txt"),
+            Some(b"a".to_vec())
+        );
+        assert_eq!(read_file(&server, "/workspace/a.txt"), None);
+
+        assert_eq!(
+            status_only(&server, PROC_RMDIR, diropargs(&dir, "sub")),
+            NFS3ERR_NOTEMPTY
+        );
+        assert_eq!(
+            status_only(&server, PROC_REMOVE, diropargs(&sub, "b.txt")),
+            NFS3_OK
+        );
+        assert_eq!(
+            status_only(&server, PROC_RMDIR, diropargs(&dir, "sub")),
+            NFS3_OK
+        );
+
+        assert_eq!(
+            status_only(&server, PROC_REMOVE, diropargs(&dir, "a/b")),
+            NFS3ERR_INVAL
+        );
+        let long = "x".repeat(NAME_MAX + 1);
+        assert_eq!(
+            status_only(&server, PROC_REMOVE, diropargs(&dir, &long)),
+            NFS3ERR_NAMETOOLONG
+        );
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(nfs_status(&FsOpError::NotFound), NFS3ERR_NOENT);
+        assert_eq!(handle_status(&FsOpError::NotFound), NFS3ERR_STALE);
+        assert_eq!(nfs_status(&FsOpError::ReadOnly), NFS3ERR_ROFS);
+        assert_eq!(nfs_status(&FsOpError::NotEmpty), NFS3ERR_NOTEMPTY);
+        assert_eq!(nfs_status(&FsOpError::IsDir), NFS3ERR_ISDIR);
+        assert_eq!(nfs_status(&FsOpError::Exists), NFS3ERR_EXIST);
+        assert_eq!(nfs_status(&FsOpError::Io("x".into())), NFS3ERR_IO);
+    }
+}
@@ -0,0 +1,169 @@
+//! XDR (RFC 4506) encoding and decoding.
+//!
+//! Only the primitives the NFSv3 and MOUNT protocols use: 32/64-bit
+//! integers, booleans, and variable or fixed-length opaque data padded to a
+//! multiple of four bytes.
+
+/// Arguments ended early or carried an out-of-range length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XdrError;
+
+/// Reads XDR values from a buffer.
+pub struct XdrReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> XdrReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        XdrReader { buf, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], XdrError> {
+        let end = self.pos.checked_add(len).ok_or(XdrError)?;
+        let bytes = self.buf.get(self.pos..end).ok_or(XdrError)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, XdrError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, XdrError> {
+        let hi = self.u32()? as u64;
+        let lo = self.u32()? as u64;
+        Ok((hi << 32) | lo)
+    }
+
+    pub fn bool(&mut self) -> Result<bool, XdrError> {
+        match self.u32()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(XdrError),
+        }
+    }
+
+    /// Fixed-length opaque data (`opaque x[len]`).
+    pub fn fixed_opaque(&mut self, len: usize) -> Result<&'a [u8], XdrError> {
+        let bytes = self.take(len)?;
+        self.take(padding(len))?;
+        Ok(bytes)
+    }
+
+    /// Variable-length opaque data (`opaque x<max>`).
+    pub fn opaque(&mut self, max: usize) -> Result<&'a [u8], XdrError> {
+        let len = self.u32()? as usize;
+        if len > max {
+            return Err(XdrError);
+        }
+        self.fixed_opaque(len)
+    }
+
+    /// A string (`string x<max>`). Names must be UTF-8.
+    pub fn string(&mut self, max: usize) -> Result<&'a str, XdrError> {
+        std::str::from_utf8(self.opaque(max)?).map_err(|_| XdrError)
+    }
+
+    /// Bytes not yet read.
+    #[cfg(test)]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.buf[self.pos..]
+    }
+}
+
+/// Builds an XDR-encoded buffer.
+#[derive(Default)]
+pub struct XdrWriter {
+    buf: Vec<u8>,
+}
+
+impl XdrWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u32(value as u32);
+    }
+
+    pub fn fixed_opaque(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(self.buf.len() + padding(bytes.len()), 0);
+    }
+
+    pub fn opaque(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.fixed_opaque(bytes);
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.opaque(value.as_bytes());
+    }
+
+    /// Append already-encoded bytes.
+    pub fn raw(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Bytes of zero padding after `len` bytes of opaque data.
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut w = XdrWriter::new();
+        w.u32(7);
+        w.u64(0x0102_0304_0506_0708);
+        w.bool(true);
+        w.string("abcde");
+        w.fixed_opaque(&[9, 9]);
+        let buf = w.into_inner();
+        // 4 + 8 + 4 + (4 + 5 + 3) + (2 + 2)
+        assert_eq!(buf.len(), 32);
+
+        let mut r = XdrReader::new(&buf);
+        assert_eq!(r.u32(), Ok(7));
+        assert_eq!(r.u64(), Ok(0x0102_0304_0506_0708));
+        assert_eq!(r.bool(), Ok(true));
+        assert_eq!(r.string(255), Ok("abcde"));
+        assert_eq!(r.fixed_opaque(2), Ok(&[9u8, 9][..]));
+        assert!(r.remaining().is_empty());
+    }
+
+    #[test]
+    fn test_truncated_and_oversized() {
+        let mut r = XdrReader::new(&[0, 0, 1]);
+        assert_eq!(r.u32(), Err(XdrError));
+
+        let mut w = XdrWriter::new();
+        w.opaque(&[1, 2, 3, 4, 5]);
+        let buf = w.into_inner();
+        assert_eq!(XdrReader::new(&buf).opaque(4), Err(XdrError));
+        assert_eq!(XdrReader::new(&buf[..8]).opaque(8), Err(XdrError));
+        assert_eq!(XdrReader::new(&[0, 0, 0, 2]).bool(), Err(XdrError));
+    }
+}
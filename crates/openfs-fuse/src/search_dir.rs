@@ -1,11 +1,22 @@
-//! Virtual .search directory for grep-style query results.
+//! Virtual .search directory for query results.
 //!
-//! Query directories expose a single `results.txt` file containing concatenated
-//! matches. This keeps search behavior simple and file-oriented for FUSE clients.
+//! Two layouts are served:
+//! - `/.search/<query>?k=20&mode=hybrid` holds one `NN-<name>.snippet` file
+//!   per hit with the matching text, next to a `NN-<name>` symlink to the
+//!   file it came from. `k` (default 10) and `mode` (`dense`, `sparse`,
+//!   `hybrid`) are optional.
+//! - `/.search/query/<url-encoded-query>` holds a single `results.txt` with
+//!   every match concatenated.
+//!
+//! Results are cached per query directory and the query runs again once the
+//! cache TTL has passed.
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use openfs_config::SearchMode;
+use openfs_core::SearchOptions;
 use parking_lot::RwLock;
 
 use crate::inode::{InodeAttr, InodeKind, InodeTable, VIRTUAL_INO_BASE};
@@ -17,6 +28,14 @@ pub const SEARCH_DIR_PATH: &str = "/.search";
 pub const QUERY_DIR_PATH: &str = "/.search/query";
 
 const RESULTS_FILE_NAME: &str = "results.txt";
+const SNIPPET_SUFFIX: &str = ".snippet";
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 1000;
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// A hit to store: (source_path, content, score, start_line, end_line).
+pub type SearchHitTuple = (String, String, f32, usize, usize);
 
 /// A search result entry in the virtual filesystem.
 #[derive(Debug, Clone)]
@@ -30,30 +49,122 @@ pub struct SearchResultEntry {
     pub end_line: usize,
 }
 
+/// A symlink from a query directory to the file a hit came from.
+#[derive(Debug, Clone)]
+pub struct SearchLink {
+    pub name: String,
+    pub ino: u64,
+    /// Target relative to the query directory, so it resolves inside the
+    /// mount wherever that is.
+    pub target: String,
+}
+
+/// A parsed `/.search/<query>?k=..&mode=..` directory name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuerySpec {
+    pub query: String,
+    pub limit: usize,
+    pub mode: Option<SearchMode>,
+}
+
+impl QuerySpec {
+    /// Parse a query directory name.
+    ///
+    /// Text after the last `?` is read as parameters when it is empty or
+    /// contains `=`; otherwise the whole name is the query. Returns `None`
+    /// for an empty query or an unknown or invalid parameter.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (query, params) = match name.rsplit_once('?') {
+            Some((query, params)) if params.is_empty() || params.contains('=') => (query, params),
+            _ => (name, ""),
+        };
+        let query = urlencoding::decode(query).ok()?.trim().to_string();
+        if query.is_empty() {
+            return None;
+        }
+
+        let mut spec = QuerySpec {
+            query,
+            limit: DEFAULT_LIMIT,
+            mode: None,
+        };
+        for param in params.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=')?;
+            match key {
+                "k" => {
+                    spec.limit = value.parse().ok().filter(|k| (1..=MAX_LIMIT).contains(k))?;
+                }
+                "mode" => {
+                    spec.mode = Some(match value {
+                        "dense" => SearchMode::Dense,
+                        "sparse" => SearchMode::Sparse,
+                        "hybrid" => SearchMode::Hybrid,
+                        _ => return None,
+                    });
+                }
+                _ => return None,
+            }
+        }
+        Some(spec)
+    }
+
+    /// Options for `Vfs::search`.
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            mode: self.mode,
+            limit: self.limit,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct CachedQuery {
     results: Vec<SearchResultEntry>,
+    links: Vec<SearchLink>,
     dir_ino: u64,
-    cached_at: std::time::Instant,
+    /// When the query last ran; `None` until it has.
+    cached_at: Option<Instant>,
+}
+
+impl CachedQuery {
+    fn inos(&self) -> impl Iterator<Item = u64> + '_ {
+        self.results
+            .iter()
+            .map(|r| r.ino)
+            .chain(self.links.iter().map(|l| l.ino))
+    }
 }
 
 /// Manages the virtual .search directory.
 pub struct SearchDir {
     inodes: Arc<InodeTable>,
+    /// `/.search/query/<query>` results, keyed by decoded query.
     query_cache: RwLock<HashMap<String, CachedQuery>>,
-    cache_ttl_secs: u64,
+    /// `/.search/<query>?<params>` results, keyed by directory name.
+    searches: RwLock<HashMap<String, CachedQuery>>,
+    cache_ttl: Duration,
 }
 
 impl SearchDir {
     /// Create a new search directory manager.
     pub fn new(inodes: Arc<InodeTable>) -> Self {
+        inodes.register_virtual(SEARCH_DIR_PATH, InodeAttr::directory(VIRTUAL_INO_BASE));
+        inodes.register_virtual(QUERY_DIR_PATH, InodeAttr::directory(VIRTUAL_INO_BASE + 1));
         SearchDir {
             inodes,
             query_cache: RwLock::new(HashMap::new()),
-            cache_ttl_secs: 60,
+            searches: RwLock::new(HashMap::new()),
+            cache_ttl: DEFAULT_CACHE_TTL,
         }
     }
 
+    /// Set how long query results are reused.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
     pub fn is_search_path(path: &str) -> bool {
         path == SEARCH_DIR_PATH || path.starts_with(&format!("{}/", SEARCH_DIR_PATH))
     }
@@ -71,6 +182,14 @@ impl SearchDir {
         path.starts_with(&format!("{}/", QUERY_DIR_PATH)) && path.len() > QUERY_DIR_PATH.len() + 1
     }
 
+    /// The query directory name of `/.search/<name>` or a path below it,
+    /// other than the `query` directory.
+    pub fn search_name(path: &str) -> Option<&str> {
+        let rest = path.strip_prefix(SEARCH_DIR_PATH)?.strip_prefix('/')?;
+        let name = rest.split('/').next()?;
+        (!name.is_empty() && name != "query").then_some(name)
+    }
+
     /// Extract the query string from either:
     /// - `/.search/query/<query>`
     /// - `/.search/query/<query>/<file>`
@@ -86,26 +205,78 @@ impl SearchDir {
             .map(|s| s.into_owned())
     }
 
-    /// Whether query content is currently cached.
+    fn is_fresh(&self, cached: Option<&CachedQuery>) -> bool {
+        cached
+            .and_then(|cached| cached.cached_at)
+            .is_some_and(|at| at.elapsed() < self.cache_ttl)
+    }
+
+    /// Whether results for a `/.search/query/<query>` are cached and fresh.
     pub fn has_query(&self, query: &str) -> bool {
-        self.query_cache.read().contains_key(query)
+        self.is_fresh(self.query_cache.read().get(query))
     }
 
-    fn ensure_query_dir(&self, query: &str) -> u64 {
-        if let Some(cached) = self.query_cache.read().get(query) {
+    /// Whether results for a `/.search/<name>` directory are cached and fresh.
+    pub fn has_search(&self, name: &str) -> bool {
+        self.is_fresh(self.searches.read().get(name))
+    }
+
+    fn query_dir_path(query: &str) -> String {
+        format!("{}/{}", QUERY_DIR_PATH, urlencoding::encode(query))
+    }
+
+    fn search_dir_path(name: &str) -> String {
+        format!("{}/{}", SEARCH_DIR_PATH, name)
+    }
+
+    /// The inode of a query directory, registering an empty one if needed.
+    fn ensure_dir(
+        &self,
+        cache: &RwLock<HashMap<String, CachedQuery>>,
+        key: &str,
+        path: &str,
+    ) -> u64 {
+        if let Some(cached) = cache.read().get(key) {
             return cached.dir_ino;
         }
 
-        let dir_ino = self.inodes.alloc_virtual_ino();
-        self.query_cache.write().insert(
-            query.to_string(),
+        let mut cache = cache.write();
+        let cached = cache.entry(key.to_string()).or_insert_with(|| {
+            let dir_ino = self.inodes.alloc_virtual_ino();
+            self.inodes
+                .register_virtual(path, InodeAttr::directory(dir_ino));
             CachedQuery {
                 results: Vec::new(),
+                links: Vec::new(),
                 dir_ino,
-                cached_at: std::time::Instant::now(),
-            },
-        );
-        dir_ino
+                cached_at: None,
+            }
+        });
+        cached.dir_ino
+    }
+
+    fn ensure_query_dir(&self, query: &str) -> u64 {
+        self.ensure_dir(&self.query_cache, query, &Self::query_dir_path(query))
+    }
+
+    fn ensure_search_dir(&self, name: &str) -> u64 {
+        self.ensure_dir(&self.searches, name, &Self::search_dir_path(name))
+    }
+
+    /// Split `/.search/<name>/<file>` into the directory name and file name.
+    fn search_file(path: &str) -> Option<(&str, &str)> {
+        let name = Self::search_name(path)?;
+        let rest = &path[SEARCH_DIR_PATH.len() + 1 + name.len()..];
+        let file = rest.strip_prefix('/')?;
+        (!file.is_empty() && !file.contains('/')).then_some((name, file))
+    }
+
+    fn entry_attr(cached: &CachedQuery, name: &str) -> Option<InodeAttr> {
+        if let Some(result) = cached.results.iter().find(|r| r.name == name) {
+            return Some(InodeAttr::file(result.ino, result.content.len() as u64));
+        }
+        let link = cached.links.iter().find(|l| l.name == name)?;
+        Some(InodeAttr::symlink(link.ino, link.target.len() as u64))
     }
 
     /// Get attributes for a search path.
@@ -136,17 +307,35 @@ impl SearchDir {
             }
         }
 
-        None
+        if let Some((name, file)) = Self::search_file(path) {
+            let searches = self.searches.read();
+            return Self::entry_attr(searches.get(name)?, file);
+        }
+
+        let name = Self::search_name(path)?;
+        if path != Self::search_dir_path(name) {
+            return None;
+        }
+        QuerySpec::parse(name)?;
+        let ino = self.ensure_search_dir(name);
+        Some(InodeAttr::directory(ino))
     }
 
     /// List entries in a search directory.
     pub fn readdir(&self, path: &str) -> Option<Vec<(u64, String, InodeKind)>> {
         if Self::is_search_root(path) {
-            return Some(vec![(
+            let mut entries = vec![(
                 VIRTUAL_INO_BASE + 1,
                 "query".to_string(),
                 InodeKind::Directory,
-            )]);
+            )];
+            entries.extend(
+                self.searches
+                    .read()
+                    .iter()
+                    .map(|(name, cached)| (cached.dir_ino, name.clone(), InodeKind::Directory)),
+            );
+            return Some(entries);
         }
 
         if Self::is_query_dir(path) {
@@ -173,16 +362,49 @@ impl SearchDir {
             return Some(entries);
         }
 
-        None
+        let name = Self::search_name(path)?;
+        if path != Self::search_dir_path(name) {
+            return None;
+        }
+        let searches = self.searches.read();
+        let cached = searches.get(name)?;
+        let mut entries: Vec<(u64, String, InodeKind)> = cached
+            .links
+            .iter()
+            .map(|l| (l.ino, l.name.clone(), InodeKind::Symlink))
+            .chain(
+                cached
+                    .results
+                    .iter()
+                    .map(|r| (r.ino, r.name.clone(), InodeKind::File)),
+            )
+            .collect();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        Some(entries)
     }
 
-    /// This search model does not expose symlinks.
-    pub fn readlink(&self, _ino: u64) -> Option<String> {
-        None
+    /// Target of a hit symlink.
+    pub fn readlink(&self, ino: u64) -> Option<String> {
+        self.searches
+            .read()
+            .values()
+            .flat_map(|cached| cached.links.iter())
+            .find(|link| link.ino == ino)
+            .map(|link| link.target.clone())
     }
 
-    /// Read virtual file content for `/.search/query/<query>/results.txt`.
+    /// Read a virtual results or snippet file.
     pub fn read_file(&self, path: &str) -> Option<Vec<u8>> {
+        if let Some((name, file)) = Self::search_file(path) {
+            let searches = self.searches.read();
+            let result = searches
+                .get(name)?
+                .results
+                .iter()
+                .find(|r| r.name == file)?;
+            return Some(result.content.as_bytes().to_vec());
+        }
+
         if !Self::is_query_path(path) {
             return None;
         }
@@ -200,32 +422,110 @@ impl SearchDir {
         Some(result.content.as_bytes().to_vec())
     }
 
-    /// Store query results, replacing any previous results for this query.
-    pub fn store_results(&self, query: &str, results: Vec<SearchResultEntry>) {
-        let dir_ino = if let Some(existing) = self.query_cache.read().get(query) {
-            existing.dir_ino
-        } else {
-            self.inodes.alloc_virtual_ino()
-        };
-
-        self.query_cache.write().insert(
-            query.to_string(),
+    /// Replace the entries of a cached query, keeping its directory inode.
+    fn replace(
+        &self,
+        cache: &RwLock<HashMap<String, CachedQuery>>,
+        key: &str,
+        dir_ino: u64,
+        results: Vec<SearchResultEntry>,
+        links: Vec<SearchLink>,
+    ) {
+        let previous = cache.write().insert(
+            key.to_string(),
             CachedQuery {
                 results,
+                links,
                 dir_ino,
-                cached_at: std::time::Instant::now(),
+                cached_at: Some(Instant::now()),
             },
         );
+        for ino in previous.iter().flat_map(|cached| cached.inos()) {
+            self.inodes.remove(ino);
+        }
+    }
+
+    /// Store query results, replacing any previous results for this query.
+    pub fn store_results(&self, query: &str, results: Vec<SearchResultEntry>) {
+        let dir_ino = self.ensure_query_dir(query);
+        let dir_path = Self::query_dir_path(query);
+        for result in &results {
+            self.inodes.register_virtual(
+                &format!("{}/{}", dir_path, result.name),
+                InodeAttr::file(result.ino, result.content.len() as u64),
+            );
+        }
+        self.replace(&self.query_cache, query, dir_ino, results, Vec::new());
+    }
+
+    /// Store the hits of a `/.search/<name>` query as snippet files and
+    /// symlinks, ranked in the order given.
+    pub fn store_hits(&self, name: &str, hits: &[SearchHitTuple]) {
+        let dir_ino = self.ensure_search_dir(name);
+        let dir_path = Self::search_dir_path(name);
+        let width = hits.len().to_string().len().max(2);
+
+        let mut results = Vec::with_capacity(hits.len());
+        let mut links = Vec::with_capacity(hits.len());
+        for (i, (source_path, snippet, score, start_line, end_line)) in hits.iter().enumerate() {
+            let base = source_path
+                .rsplit('/')
+                .find(|part| !part.is_empty())
+                .unwrap_or("result");
+            let link_name = format!("{:0width$}-{}", i + 1, base, width = width);
+
+            let link = SearchLink {
+                name: link_name.clone(),
+                ino: self.inodes.alloc_virtual_ino(),
+                target: format!("../../{}", source_path.trim_start_matches('/')),
+            };
+            self.inodes.register_virtual(
+                &format!("{}/{}", dir_path, link.name),
+                InodeAttr::symlink(link.ino, link.target.len() as u64),
+            );
+
+            let result = SearchResultEntry {
+                name: format!("{}{}", link_name, SNIPPET_SUFFIX),
+                ino: self.inodes.alloc_virtual_ino(),
+                content: format!(
+                    "{}:{}-{} [score={:.4}]\n{}\n",
+                    source_path, start_line, end_line, score, snippet
+                ),
+                score: *score,
+                source_path: source_path.clone(),
+                start_line: *start_line,
+                end_line: *end_line,
+            };
+            self.inodes.register_virtual(
+                &format!("{}/{}", dir_path, result.name),
+                InodeAttr::file(result.ino, result.content.len() as u64),
+            );
+
+            links.push(link);
+            results.push(result);
+        }
+
+        self.replace(&self.searches, name, dir_ino, results, links);
     }
 
     /// Clear expired cache entries.
     pub fn cleanup_cache(&self) {
-        let now = std::time::Instant::now();
-        let ttl = std::time::Duration::from_secs(self.cache_ttl_secs);
-
-        self.query_cache
-            .write()
-            .retain(|_, cached| now.duration_since(cached.cached_at) < ttl);
+        for cache in [&self.query_cache, &self.searches] {
+            let mut expired = Vec::new();
+            cache.write().retain(|_, cached| {
+                let fresh = cached
+                    .cached_at
+                    .is_some_and(|at| at.elapsed() < self.cache_ttl);
+                if !fresh {
+                    expired.extend(cached.inos());
+                    expired.push(cached.dir_ino);
+                }
+                fresh
+            });
+            for ino in expired {
+                self.inodes.remove(ino);
+            }
+        }
     }
 
     /// Lookup an entry in a search directory.
@@ -235,6 +535,12 @@ impl SearchDir {
             return Some((ino, InodeAttr::directory(ino)));
         }
 
+        if Self::is_search_root(parent_path) {
+            QuerySpec::parse(name)?;
+            let ino = self.ensure_search_dir(name);
+            return Some((ino, InodeAttr::directory(ino)));
+        }
+
         if Self::is_query_dir(parent_path) {
             let query = urlencoding::decode(name).ok()?.into_owned();
             let ino = self.ensure_query_dir(&query);
@@ -250,7 +556,10 @@ impl SearchDir {
             return Some((result.ino, attr));
         }
 
-        None
+        let dir_name = Self::search_name(parent_path)?;
+        let searches = self.searches.read();
+        let attr = Self::entry_attr(searches.get(dir_name)?, name)?;
+        Some((attr.ino, attr))
     }
 
     /// Build one concatenated results file for a query.
@@ -297,6 +606,10 @@ mod tests {
         SearchDir::new(inodes)
     }
 
+    fn hit(path: &str, snippet: &str, score: f32) -> SearchHitTuple {
+        (path.to_string(), snippet.to_string(), score, 3, 5)
+    }
+
     #[test]
     fn test_query_dir_and_results_file() {
         let search_dir = create_search_dir();
@@ -321,4 +634,135 @@ mod tests {
         assert!(text.contains("/workspace/a.txt:3-3"));
         assert!(text.contains("hello world"));
     }
+
+    #[test]
+    fn test_query_spec_parse() {
+        assert_eq!(
+            QuerySpec::parse("query terms?k=20&mode=hybrid"),
+            Some(QuerySpec {
+                query: "query terms".to_string(),
+                limit: 20,
+                mode: Some(SearchMode::Hybrid),
+            })
+        );
+
+        let plain = QuerySpec::parse("error handling").unwrap();
+        assert_eq!((plain.limit, plain.mode), (DEFAULT_LIMIT, None));
+        assert_eq!(QuerySpec::parse("what is x?").unwrap().query, "what is x");
+        assert_eq!(QuerySpec::parse("a?b").unwrap().query, "a?b");
+        assert_eq!(
+            QuerySpec::parse("retry%20logic").unwrap().query,
+            "retry logic"
+        );
+
+        assert_eq!(QuerySpec::parse("?k=5"), None);
+        assert_eq!(QuerySpec::parse("x?k=0"), None);
+        assert_eq!(QuerySpec::parse("x?k=many"), None);
+        assert_eq!(QuerySpec::parse("x?mode=fuzzy"), None);
+        assert_eq!(QuerySpec::parse("x?limit=5"), None);
+    }
+
+    #[test]
+    fn test_search_name() {
+        assert_eq!(SearchDir::search_name("/.search/foo?k=2"), Some("foo?k=2"));
+        assert_eq!(SearchDir::search_name("/.search/foo/01-a.rs"), Some("foo"));
+        assert_eq!(SearchDir::search_name("/.search/query"), None);
+        assert_eq!(SearchDir::search_name("/.search/query/foo"), None);
+        assert_eq!(SearchDir::search_name("/.search"), None);
+        assert_eq!(SearchDir::search_name("/workspace/foo"), None);
+    }
+
+    #[test]
+    fn test_hits_become_snippets_and_links() {
+        let inodes = Arc::new(InodeTable::new());
+        let search_dir = SearchDir::new(inodes.clone());
+        let name = "retry logic?k=2";
+
+        let (dir_ino, attr) = search_dir.lookup(SEARCH_DIR_PATH, name).unwrap();
+        assert_eq!(attr.kind, InodeKind::Directory);
+        assert!(!search_dir.has_search(name));
+
+        search_dir.store_hits(
+            name,
+            &[
+                hit("/workspace/src/retry.rs", "fn retry()", 0.9),
+                hit("/docs/retry.md", "Retries back off", 0.5),
+            ],
+        );
+        assert!(search_dir.has_search(name));
+
+        let dir_path = "/.search/retry logic?k=2";
+        let entries = search_dir.readdir(dir_path).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| (e.1.as_str(), e.2)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("01-retry.rs", InodeKind::Symlink),
+                ("01-retry.rs.snippet", InodeKind::File),
+                ("02-retry.md", InodeKind::Symlink),
+                ("02-retry.md.snippet", InodeKind::File),
+            ]
+        );
+
+        let snippet = search_dir
+            .read_file(&format!("{}/01-retry.rs.snippet", dir_path))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(snippet).unwrap(),
+            "/workspace/src/retry.rs:3-5 [score=0.9000]\nfn retry()\n"
+        );
+
+        let (link_ino, link_attr) = search_dir.lookup(dir_path, "02-retry.md").unwrap();
+        assert_eq!(link_attr.kind, InodeKind::Symlink);
+        assert_eq!(
+            search_dir.readlink(link_ino),
+            Some("../../docs/retry.md".to_string())
+        );
+
+        // Virtual entries resolve through the inode table.
+        assert_eq!(inodes.get_path(dir_ino), Some(dir_path.to_string()));
+        assert_eq!(
+            inodes.get_path(link_ino),
+            Some(format!("{}/02-retry.md", dir_path))
+        );
+
+        let root: Vec<_> = search_dir
+            .readdir(SEARCH_DIR_PATH)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.1)
+            .collect();
+        assert!(root.contains(&"query".to_string()));
+        assert!(root.contains(&name.to_string()));
+    }
+
+    #[test]
+    fn test_refresh_replaces_hit_inodes() {
+        let inodes = Arc::new(InodeTable::new());
+        let search_dir = SearchDir::new(inodes.clone());
+
+        search_dir.store_hits("q", &[hit("/a.txt", "a", 1.0)]);
+        let (old_ino, _) = search_dir.lookup("/.search/q", "01-a.txt").unwrap();
+
+        search_dir.store_hits("q", &[hit("/b.txt", "b", 1.0)]);
+        assert!(inodes.get_path(old_ino).is_none());
+        assert!(search_dir.lookup("/.search/q", "01-a.txt").is_none());
+        assert!(search_dir.lookup("/.search/q", "01-b.txt").is_some());
+    }
+
+    #[test]
+    fn test_cache_ttl_expires_results() {
+        let inodes = Arc::new(InodeTable::new());
+        let search_dir = SearchDir::new(inodes.clone()).with_cache_ttl(Duration::ZERO);
+
+        search_dir.store_hits("q", &[hit("/a.txt", "a", 1.0)]);
+        search_dir.store_results("legacy", search_dir.create_result_entries(&[]));
+        assert!(!search_dir.has_search("q"));
+        assert!(!search_dir.has_query("legacy"));
+
+        let (ino, _) = search_dir.lookup("/.search/q", "01-a.txt").unwrap();
+        search_dir.cleanup_cache();
+        assert!(search_dir.readdir("/.search/q").is_none());
+        assert!(inodes.get_path(ino).is_none());
+    }
 }
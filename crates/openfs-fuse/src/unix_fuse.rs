@@ -1,16 +1,21 @@
 //! Unix FUSE implementation using the `fuser` crate.
 
 use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 use fuser::{
-    FileType, Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty,
-    ReplyEntry, ReplyOpen, ReplyWrite, Request, TimeOrNow,
+    FileType, Filesystem, MountOption, Notifier, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory,
+    ReplyEmpty, ReplyEntry, ReplyOpen, ReplyWrite, ReplyXattr, Request, Session, TimeOrNow,
 };
-use tracing::{debug, error};
+use openfs_remote::RemoteChangeEvent;
+use tokio::sync::broadcast;
+use tracing::{debug, error, warn};
 
+use crate::async_bridge::spawn;
 use crate::common::{inode_attr_to_file_attr, OpenFsCore, FsOpError};
-use crate::inode::{InodeAttr, InodeKind, VIRTUAL_INO_BASE};
+use crate::inode::{InodeAttr, InodeKind, InodeTable, Invalidation, VIRTUAL_INO_BASE};
 
 /// Unix FUSE filesystem wrapper around `OpenFsCore`.
 pub struct UnixFuse(pub OpenFsCore);
@@ -25,10 +30,29 @@ impl UnixFuse {
             FsOpError::Io(_) => libc::EIO,
             FsOpError::NotSymlink => libc::EINVAL,
             FsOpError::IsDir => libc::EISDIR,
+            FsOpError::NoAttr => ENOATTR,
+            FsOpError::Exists => libc::EEXIST,
+        }
+    }
+
+    /// Reply to getxattr/listxattr: a zero `size` asks for the length only.
+    fn reply_xattr(data: &[u8], size: u32, reply: ReplyXattr) {
+        if size == 0 {
+            reply.size(data.len() as u32);
+        } else if data.len() > size as usize {
+            reply.error(libc::ERANGE);
+        } else {
+            reply.data(data);
         }
     }
 }
 
+/// errno for a missing extended attribute.
+#[cfg(target_os = "macos")]
+const ENOATTR: i32 = libc::ENOATTR;
+#[cfg(not(target_os = "macos"))]
+const ENOATTR: i32 = libc::ENODATA;
+
 impl Filesystem for UnixFuse {
     fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
         let name_str = match name.to_str() {
@@ -93,7 +117,7 @@ impl Filesystem for UnixFuse {
         &mut self,
         _req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         data: &[u8],
         _write_flags: u32,
@@ -103,7 +127,7 @@ impl Filesystem for UnixFuse {
     ) {
         debug!("write: ino={}, offset={}, size={}", ino, offset, data.len());
 
-        match self.0.do_write(ino, offset, data) {
+        match self.0.do_write(ino, fh, offset, data) {
             Ok(written) => reply.written(written),
             Err(e) => {
                 error!("write failed: {:?}", Self::error_to_errno(&e));
@@ -189,9 +213,9 @@ impl Filesystem for UnixFuse {
         debug!("create: parent={}, name={}", parent, name_str);
 
         match self.0.do_create(parent, name_str) {
-            Ok(attr) => {
+            Ok((attr, fh)) => {
                 let file_attr = inode_attr_to_file_attr(&attr);
-                reply.created(&InodeAttr::ttl(), &file_attr, 0, 0, 0);
+                reply.created(&InodeAttr::ttl(), &file_attr, 0, fh, 0);
             }
             Err(e) => {
                 error!("create failed: {:?}", Self::error_to_errno(&e));
@@ -283,12 +307,57 @@ impl Filesystem for UnixFuse {
     fn open(&mut self, _req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
         debug!("open: ino={}", ino);
 
-        match self.0.do_access(ino) {
-            Ok(()) => reply.opened(0, 0),
+        match self.0.do_open(ino) {
+            Ok(fh) => reply.opened(fh, 0),
             Err(e) => reply.error(Self::error_to_errno(&e)),
         }
     }
 
+    fn flush(&mut self, _req: &Request, ino: u64, fh: u64, _lock_owner: u64, reply: ReplyEmpty) {
+        debug!("flush: ino={}, fh={}", ino, fh);
+
+        match self.0.do_flush(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => {
+                error!("flush failed: {:?}", Self::error_to_errno(&e));
+                reply.error(Self::error_to_errno(&e));
+            }
+        }
+    }
+
+    fn fsync(&mut self, _req: &Request, ino: u64, fh: u64, _datasync: bool, reply: ReplyEmpty) {
+        debug!("fsync: ino={}, fh={}", ino, fh);
+
+        match self.0.do_flush(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => {
+                error!("fsync failed: {:?}", Self::error_to_errno(&e));
+                reply.error(Self::error_to_errno(&e));
+            }
+        }
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        debug!("release: ino={}, fh={}", ino, fh);
+
+        match self.0.do_release(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => {
+                error!("release failed: {:?}", Self::error_to_errno(&e));
+                reply.error(Self::error_to_errno(&e));
+            }
+        }
+    }
+
     fn opendir(&mut self, _req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
         debug!("opendir: ino={}", ino);
 
@@ -308,7 +377,7 @@ impl Filesystem for UnixFuse {
         _gid: Option<u32>,
         size: Option<u64>,
         _atime: Option<TimeOrNow>,
-        _mtime: Option<TimeOrNow>,
+        mtime: Option<TimeOrNow>,
         _ctime: Option<SystemTime>,
         _fh: Option<u64>,
         _crtime: Option<SystemTime>,
@@ -317,9 +386,13 @@ impl Filesystem for UnixFuse {
         _flags: Option<u32>,
         reply: ReplyAttr,
     ) {
-        debug!("setattr: ino={}, size={:?}", ino, size);
+        debug!("setattr: ino={}, size={:?}, mtime={:?}", ino, size, mtime);
 
-        match self.0.do_setattr(ino, size) {
+        let mtime = mtime.map(|time| match time {
+            TimeOrNow::SpecificTime(time) => time,
+            TimeOrNow::Now => SystemTime::now(),
+        });
+        match self.0.do_setattr(ino, size, mtime) {
             Ok(attr) => {
                 let file_attr = inode_attr_to_file_attr(&attr);
                 reply.attr(&InodeAttr::ttl(), &file_attr);
@@ -379,6 +452,148 @@ impl Filesystem for UnixFuse {
             Err(e) => reply.error(Self::error_to_errno(&e)),
         }
     }
+
+    fn getxattr(&mut self, _req: &Request, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        let name_str = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(libc::EINVAL);
+                return;
+            }
+        };
+
+        debug!("getxattr: ino={}, name={}", ino, name_str);
+
+        match self.0.do_getxattr(ino, name_str) {
+            Ok(value) => Self::reply_xattr(&value, size, reply),
+            Err(e) => reply.error(Self::error_to_errno(&e)),
+        }
+    }
+
+    fn listxattr(&mut self, _req: &Request, ino: u64, size: u32, reply: ReplyXattr) {
+        debug!("listxattr: ino={}", ino);
+
+        match self.0.do_listxattr(ino) {
+            Ok(names) => {
+                // NUL-terminated names, back to back.
+                let mut data = Vec::new();
+                for name in names {
+                    data.extend_from_slice(name.as_bytes());
+                    data.push(0);
+                }
+                Self::reply_xattr(&data, size, reply);
+            }
+            Err(e) => reply.error(Self::error_to_errno(&e)),
+        }
+    }
+
+    fn setxattr(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        name: &OsStr,
+        value: &[u8],
+        flags: i32,
+        _position: u32,
+        reply: ReplyEmpty,
+    ) {
+        let name_str = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(libc::EINVAL);
+                return;
+            }
+        };
+
+        debug!(
+            "setxattr: ino={}, name={}, len={}",
+            ino,
+            name_str,
+            value.len()
+        );
+
+        match self.0.do_setxattr(ino, name_str, value, flags) {
+            Ok(()) => reply.ok(),
+            Err(e) => {
+                error!("setxattr failed: {:?}", Self::error_to_errno(&e));
+                reply.error(Self::error_to_errno(&e));
+            }
+        }
+    }
+
+    fn removexattr(&mut self, _req: &Request, ino: u64, name: &OsStr, reply: ReplyEmpty) {
+        let name_str = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(libc::EINVAL);
+                return;
+            }
+        };
+
+        debug!("removexattr: ino={}, name={}", ino, name_str);
+
+        match self.0.do_removexattr(ino, name_str) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(Self::error_to_errno(&e)),
+        }
+    }
+}
+
+/// Mount `core` at `mountpoint` and serve requests until it is unmounted.
+///
+/// Remote changes found by pulls are passed on to the kernel as they are
+/// published, so lookups, attributes and directory listings cached there
+/// are refreshed and watchers of the mount see files change.
+pub(crate) fn serve(
+    core: OpenFsCore,
+    mountpoint: &Path,
+    options: &[MountOption],
+) -> std::io::Result<()> {
+    let changes = core.vfs.subscribe_remote_changes();
+    let inodes = core.inodes.clone();
+    let mut session = Session::new(UnixFuse(core), mountpoint, options)?;
+    let notifier = session.notifier();
+    if let Err(e) = spawn(forward_remote_changes(changes, inodes, notifier)) {
+        warn!("Remote changes will not reach the kernel: {}", e);
+    }
+    session.run()
+}
+
+/// Send the kernel invalidations for each remote change until the VFS goes.
+async fn forward_remote_changes(
+    mut changes: broadcast::Receiver<RemoteChangeEvent>,
+    inodes: Arc<InodeTable>,
+    notifier: Notifier,
+) {
+    loop {
+        let event = match changes.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!(
+                    missed,
+                    "Missed remote changes; the kernel may serve stale entries until they expire"
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        for invalidation in inodes.invalidations(&event.path, event.deleted) {
+            let result = match &invalidation {
+                Invalidation::Inode(ino) => notifier.inval_inode(*ino, 0, 0),
+                Invalidation::Entry { parent, name } => {
+                    notifier.inval_entry(*parent, OsStr::new(name))
+                }
+                Invalidation::Delete {
+                    parent,
+                    child,
+                    name,
+                } => notifier.delete(*parent, *child, OsStr::new(name)),
+            };
+            if let Err(e) = result {
+                debug!(path = %event.path, ?invalidation, "invalidation failed: {}", e);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -468,5 +683,7 @@ mod tests {
             libc::EINVAL
         );
         assert_eq!(UnixFuse::error_to_errno(&FsOpError::IsDir), libc::EISDIR);
+        assert_eq!(UnixFuse::error_to_errno(&FsOpError::NoAttr), ENOATTR);
+        assert_eq!(UnixFuse::error_to_errno(&FsOpError::Exists), libc::EEXIST);
     }
 }
@@ -0,0 +1,353 @@
+//! Per-handle write buffering.
+//!
+//! FUSE splits writes into small chunks, and rewriting the backend object on
+//! every `write()` makes tools like `git checkout` crawl on remote mounts.
+//! Instead each open handle keeps an image of its file that collects writes
+//! and is written back on fsync, flush (close) and release, or earlier once a
+//! threshold from [`WriteBufferConfig`] is crossed.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use openfs_config::WriteBufferConfig;
+use parking_lot::Mutex;
+
+/// State of one open file handle.
+struct Handle {
+    ino: u64,
+    path: String,
+    /// File content, loaded on the first write.
+    content: Option<Vec<u8>>,
+    /// Bytes written since the last flush.
+    dirty_bytes: u64,
+    /// Time of the oldest unflushed write.
+    dirty_since: Option<Instant>,
+    /// Bumped on every change so a flush only marks clean what it wrote.
+    generation: u64,
+    /// The path was unlinked while open; nothing is written back.
+    unlinked: bool,
+}
+
+impl Handle {
+    fn is_dirty(&self) -> bool {
+        self.dirty_since.is_some() && !self.unlinked
+    }
+
+    fn mark_dirty(&mut self, bytes: u64) {
+        self.dirty_bytes += bytes;
+        self.dirty_since.get_or_insert_with(Instant::now);
+        self.generation += 1;
+    }
+}
+
+/// Content of a handle to write back, from [`WriteBuffers::take_dirty`].
+pub struct DirtyFile {
+    /// VFS path to write.
+    pub path: String,
+    /// Full file content.
+    pub content: Vec<u8>,
+    /// Pass back to [`WriteBuffers::mark_clean`] once written.
+    pub generation: u64,
+}
+
+/// Open handles and their unflushed writes.
+///
+/// Handle `0` means "unbuffered": it is returned when buffering is disabled
+/// and every method treats it as unknown.
+pub struct WriteBuffers {
+    config: WriteBufferConfig,
+    next_fh: AtomicU64,
+    handles: Mutex<HashMap<u64, Handle>>,
+}
+
+impl WriteBuffers {
+    /// Create an empty handle table.
+    pub fn new(config: WriteBufferConfig) -> Self {
+        WriteBuffers {
+            config,
+            next_fh: AtomicU64::new(1),
+            handles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open a handle on `path`. `content` is the file's current content when
+    /// already known (e.g. empty for a file just created).
+    pub fn open(&self, ino: u64, path: &str, content: Option<Vec<u8>>) -> u64 {
+        if !self.config.enabled {
+            return 0;
+        }
+        let fh = self.next_fh.fetch_add(1, Ordering::Relaxed);
+        self.handles.lock().insert(
+            fh,
+            Handle {
+                ino,
+                path: path.to_string(),
+                content,
+                dirty_bytes: 0,
+                dirty_since: None,
+                generation: 0,
+                unlinked: false,
+            },
+        );
+        fh
+    }
+
+    /// Whether `fh` must be loaded before writing, or `None` for an unknown
+    /// handle.
+    pub fn needs_load(&self, fh: u64) -> Option<bool> {
+        self.handles
+            .lock()
+            .get(&fh)
+            .map(|handle| handle.content.is_none())
+    }
+
+    /// Set the loaded content of `fh` unless it already has some.
+    pub fn load(&self, fh: u64, content: Vec<u8>) {
+        if let Some(handle) = self.handles.lock().get_mut(&fh) {
+            handle.content.get_or_insert(content);
+        }
+    }
+
+    /// Apply a write to a loaded handle. Returns the new file size and
+    /// whether a flush is due.
+    pub fn write(&self, fh: u64, offset: u64, data: &[u8]) -> Option<(u64, bool)> {
+        let mut handles = self.handles.lock();
+        let handle = handles.get_mut(&fh)?;
+        let content = handle.content.as_mut()?;
+
+        let start = offset as usize;
+        let end = start + data.len();
+        if end > content.len() {
+            content.resize(end, 0);
+        }
+        content[start..end].copy_from_slice(data);
+        let size = content.len() as u64;
+        handle.mark_dirty(data.len() as u64);
+
+        let due = handle.dirty_bytes >= self.config.max_dirty.as_bytes()
+            || handle
+                .dirty_since
+                .is_some_and(|since| since.elapsed() >= self.config.max_age.as_duration());
+        Some((size, due))
+    }
+
+    /// Copy out the content of `fh` if it has unflushed writes.
+    pub fn take_dirty(&self, fh: u64) -> Option<DirtyFile> {
+        let handles = self.handles.lock();
+        let handle = handles.get(&fh).filter(|handle| handle.is_dirty())?;
+        Some(DirtyFile {
+            path: handle.path.clone(),
+            content: handle.content.clone().unwrap_or_default(),
+            generation: handle.generation,
+        })
+    }
+
+    /// Mark `fh` clean after writing `generation` back. Writes made since
+    /// keep it dirty.
+    pub fn mark_clean(&self, fh: u64, generation: u64) {
+        if let Some(handle) = self.handles.lock().get_mut(&fh) {
+            if handle.generation == generation {
+                handle.dirty_bytes = 0;
+                handle.dirty_since = None;
+            }
+        }
+    }
+
+    /// Forget a handle.
+    pub fn release(&self, fh: u64) {
+        self.handles.lock().remove(&fh);
+    }
+
+    /// Read from the newest buffered image of `ino`, if any handle has one.
+    pub fn read(&self, ino: u64, offset: u64, size: u32) -> Option<Vec<u8>> {
+        let handles = self.handles.lock();
+        let content = Self::image(&handles, ino)?;
+        let start = (offset as usize).min(content.len());
+        let end = (start + size as usize).min(content.len());
+        Some(content[start..end].to_vec())
+    }
+
+    /// Size of the newest buffered image of `ino`, if any handle has one.
+    pub fn size(&self, ino: u64) -> Option<u64> {
+        let handles = self.handles.lock();
+        Self::image(&handles, ino).map(|content| content.len() as u64)
+    }
+
+    /// Resize every loaded image of `ino`.
+    pub fn truncate(&self, ino: u64, size: u64) {
+        for handle in self.handles.lock().values_mut() {
+            if handle.ino != ino {
+                continue;
+            }
+            if let Some(content) = handle.content.as_mut() {
+                content.resize(size as usize, 0);
+                handle.mark_dirty(0);
+            }
+        }
+    }
+
+    /// Dirty handles on `path` or anything below it.
+    pub fn dirty_under(&self, path: &str) -> Vec<u64> {
+        let prefix = format!("{}/", path);
+        self.handles
+            .lock()
+            .iter()
+            .filter(|(_, handle)| {
+                handle.is_dirty() && (handle.path == path || handle.path.starts_with(&prefix))
+            })
+            .map(|(&fh, _)| fh)
+            .collect()
+    }
+
+    /// Point handles on `from` (or below it) at `to`.
+    pub fn rename(&self, from: &str, to: &str) {
+        let prefix = format!("{}/", from);
+        for handle in self.handles.lock().values_mut() {
+            if handle.path == from {
+                handle.path = to.to_string();
+            } else if let Some(rest) = handle.path.strip_prefix(&prefix) {
+                handle.path = format!("{}/{}", to, rest);
+            }
+        }
+    }
+
+    /// Drop pending writes for an unlinked path.
+    pub fn unlink(&self, path: &str) {
+        for handle in self.handles.lock().values_mut() {
+            if handle.path == path {
+                handle.unlinked = true;
+            }
+        }
+    }
+
+    /// Dirty images win over clean ones, since they are ahead of the backend.
+    fn image(handles: &HashMap<u64, Handle>, ino: u64) -> Option<&Vec<u8>> {
+        handles
+            .values()
+            .filter(|handle| handle.ino == ino && handle.content.is_some())
+            .max_by_key(|handle| handle.is_dirty())
+            .and_then(|handle| handle.content.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::{HumanBytes, HumanDuration};
+    use std::time::Duration;
+
+    fn make_buffers(max_dirty: u64, max_age: Duration) -> WriteBuffers {
+        WriteBuffers::new(WriteBufferConfig {
+            enabled: true,
+            max_dirty: HumanBytes(max_dirty),
+            max_age: HumanDuration(max_age),
+        })
+    }
+
+    #[test]
+    fn test_disabled_returns_unbuffered_handle() {
+        let buffers = WriteBuffers::new(WriteBufferConfig {
+            enabled: false,
+            ..WriteBufferConfig::default()
+        });
+        let fh = buffers.open(2, "/w/a.txt", None);
+        assert_eq!(fh, 0);
+        assert_eq!(buffers.needs_load(fh), None);
+        assert!(buffers.write(fh, 0, b"x").is_none());
+    }
+
+    #[test]
+    fn test_writes_coalesce_until_flushed() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", Some(Vec::new()));
+        assert_eq!(buffers.needs_load(fh), Some(false));
+        assert!(buffers.take_dirty(fh).is_none());
+
+        assert_eq!(buffers.write(fh, 0, b"hello "), Some((6, false)));
+        assert_eq!(buffers.write(fh, 6, b"world"), Some((11, false)));
+        assert_eq!(buffers.read(2, 0, 100).unwrap(), b"hello world");
+        assert_eq!(buffers.size(2), Some(11));
+
+        let dirty = buffers.take_dirty(fh).unwrap();
+        assert_eq!(dirty.path, "/w/a.txt");
+        assert_eq!(dirty.content, b"hello world");
+        buffers.mark_clean(fh, dirty.generation);
+        assert!(buffers.take_dirty(fh).is_none());
+    }
+
+    #[test]
+    fn test_write_after_take_stays_dirty() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", Some(Vec::new()));
+        buffers.write(fh, 0, b"one");
+        let dirty = buffers.take_dirty(fh).unwrap();
+        buffers.write(fh, 3, b"two");
+        buffers.mark_clean(fh, dirty.generation);
+        assert_eq!(buffers.take_dirty(fh).unwrap().content, b"onetwo");
+    }
+
+    #[test]
+    fn test_flush_due_on_thresholds() {
+        let buffers = make_buffers(8, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", Some(Vec::new()));
+        assert_eq!(buffers.write(fh, 0, b"1234"), Some((4, false)));
+        assert_eq!(buffers.write(fh, 4, b"5678"), Some((8, true)));
+
+        let buffers = make_buffers(1024, Duration::ZERO);
+        let fh = buffers.open(2, "/w/a.txt", Some(Vec::new()));
+        assert_eq!(buffers.write(fh, 0, b"x"), Some((1, true)));
+    }
+
+    #[test]
+    fn test_load_and_sparse_write() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", None);
+        assert_eq!(buffers.needs_load(fh), Some(true));
+        assert!(buffers.write(fh, 0, b"x").is_none());
+
+        buffers.load(fh, b"abc".to_vec());
+        // A second load does not clobber buffered writes.
+        buffers.write(fh, 1, b"B");
+        buffers.load(fh, b"zzz".to_vec());
+        assert_eq!(buffers.write(fh, 5, b"!"), Some((6, false)));
+        assert_eq!(buffers.read(2, 0, 10).unwrap(), b"aBc\0\0!");
+        assert_eq!(buffers.read(2, 10, 10).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", Some(b"hello".to_vec()));
+        buffers.truncate(2, 2);
+        assert_eq!(buffers.take_dirty(fh).unwrap().content, b"he");
+    }
+
+    #[test]
+    fn test_rename_and_dirty_under() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let a = buffers.open(2, "/w/dir/a.txt", Some(Vec::new()));
+        let b = buffers.open(3, "/w/dirty.txt", Some(Vec::new()));
+        buffers.write(a, 0, b"a");
+        buffers.write(b, 0, b"b");
+
+        assert_eq!(buffers.dirty_under("/w/dir"), vec![a]);
+        buffers.rename("/w/dir", "/w/moved");
+        assert_eq!(buffers.take_dirty(a).unwrap().path, "/w/moved/a.txt");
+        assert_eq!(buffers.take_dirty(b).unwrap().path, "/w/dirty.txt");
+    }
+
+    #[test]
+    fn test_unlink_drops_pending_writes() {
+        let buffers = make_buffers(1024, Duration::from_secs(60));
+        let fh = buffers.open(2, "/w/a.txt", Some(Vec::new()));
+        buffers.write(fh, 0, b"data");
+        buffers.unlink("/w/a.txt");
+        assert!(buffers.take_dirty(fh).is_none());
+        assert!(buffers.dirty_under("/w").is_empty());
+
+        buffers.release(fh);
+        assert_eq!(buffers.needs_load(fh), None);
+        assert_eq!(buffers.size(2), None);
+    }
+}
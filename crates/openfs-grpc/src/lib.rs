@@ -0,0 +1,62 @@
+//! gRPC server for the OpenFS VFS.
+//!
+//! Serves the `openfs.v1.Vfs` service from `proto/openfs/v1/vfs.proto`, so
+//! services in other languages can use the VFS through generated clients.
+
+mod service;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use openfs_remote::Vfs;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tracing::info;
+
+pub use service::status_from_vfs_error;
+
+/// Types and stubs generated from `vfs.proto`.
+pub mod proto {
+    tonic::include_proto!("openfs.v1");
+}
+
+/// gRPC server for a VFS.
+pub struct GrpcServer {
+    vfs: Arc<Vfs>,
+}
+
+impl GrpcServer {
+    /// Create a server for a VFS.
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        GrpcServer { vfs }
+    }
+
+    /// The `openfs.v1.Vfs` service, to add to a `tonic` server of your own.
+    pub fn into_service(self) -> proto::vfs_server::VfsServer<Self> {
+        proto::vfs_server::VfsServer::new(self)
+    }
+
+    /// Serve on `addr` until the process is stopped.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        info!("Serving gRPC on {}", addr);
+        Server::builder()
+            .add_service(self.into_service())
+            .serve(addr)
+            .await
+    }
+
+    /// Serve connections accepted by `listener` until it fails.
+    pub async fn serve_with_listener(
+        self,
+        listener: TcpListener,
+    ) -> Result<(), tonic::transport::Error> {
+        if let Ok(addr) = listener.local_addr() {
+            info!("Serving gRPC on {}", addr);
+        }
+        Server::builder()
+            .add_service(self.into_service())
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+    }
+}
@@ -0,0 +1,431 @@
+//! `openfs.v1.Vfs` service implementation.
+
+use std::path::Path;
+use std::pin::Pin;
+
+use openfs_config::SearchMode;
+use openfs_core::{Entry, ErrorCode, SearchOptions, VfsError};
+use openfs_local::{ChangeKind, WatchEngine};
+use openfs_remote::{grep_each, GrepOptions};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status, Streaming};
+use tracing::debug;
+
+use crate::proto::vfs_server::Vfs as VfsService;
+use crate::proto::{self, watch_event, write_request};
+use crate::GrpcServer;
+
+/// Size of the chunks `Read` streams.
+const READ_CHUNK_SIZE: u64 = 1024 * 1024;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Map a VFS error onto the closest gRPC status.
+pub fn status_from_vfs_error(e: VfsError) -> Status {
+    let code = match e.code() {
+        ErrorCode::NotFound | ErrorCode::NoMount => Code::NotFound,
+        ErrorCode::ReadOnly | ErrorCode::PermissionDenied => Code::PermissionDenied,
+        ErrorCode::PreconditionFailed | ErrorCode::NotADirectory => Code::FailedPrecondition,
+        ErrorCode::InvalidPath | ErrorCode::InvalidConfig | ErrorCode::InvalidArgument => {
+            Code::InvalidArgument
+        }
+        ErrorCode::BackendUnavailable => Code::Unavailable,
+        ErrorCode::Timeout => Code::DeadlineExceeded,
+        ErrorCode::Throttled => Code::ResourceExhausted,
+        _ => Code::Internal,
+    };
+    Status::new(code, e.to_string())
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn entry_to_proto(entry: Entry) -> proto::Entry {
+    proto::Entry {
+        path: entry.path,
+        name: entry.name,
+        is_dir: entry.is_dir,
+        size: entry.size,
+        modified: entry.modified.map(|modified| prost_types::Timestamp {
+            seconds: modified.timestamp(),
+            nanos: modified.timestamp_subsec_nanos() as i32,
+        }),
+    }
+}
+
+fn search_mode(mode: proto::SearchMode) -> Option<SearchMode> {
+    match mode {
+        proto::SearchMode::Unspecified => None,
+        proto::SearchMode::Dense => Some(SearchMode::Dense),
+        proto::SearchMode::Sparse => Some(SearchMode::Sparse),
+        proto::SearchMode::Hybrid => Some(SearchMode::Hybrid),
+        proto::SearchMode::Metadata => Some(SearchMode::Metadata),
+    }
+}
+
+fn watch_kind(kind: &ChangeKind) -> watch_event::Kind {
+    match kind {
+        ChangeKind::Created => watch_event::Kind::Created,
+        ChangeKind::Modified => watch_event::Kind::Modified,
+        ChangeKind::Deleted => watch_event::Kind::Deleted,
+        ChangeKind::Renamed => watch_event::Kind::Renamed,
+    }
+}
+
+/// The VFS path under `vfs_root` of a changed file below `fs_root`.
+fn vfs_path_of(changed: &Path, fs_roots: &[&Path], vfs_root: &str) -> Option<String> {
+    let relative = fs_roots
+        .iter()
+        .find_map(|root| changed.strip_prefix(root).ok())?;
+    let relative = relative.to_string_lossy();
+    if relative.is_empty() {
+        return Some(vfs_root.to_string());
+    }
+    Some(join_path(vfs_root, &relative))
+}
+
+#[tonic::async_trait]
+impl VfsService for GrpcServer {
+    type ReadStream = ResponseStream<proto::Chunk>;
+    type GrepStream = ResponseStream<proto::GrepMatch>;
+    type WatchStream = ResponseStream<proto::WatchEvent>;
+
+    async fn read(
+        &self,
+        request: Request<proto::ReadRequest>,
+    ) -> Result<Response<Self::ReadStream>, Status> {
+        let req = request.into_inner();
+        let entry = self
+            .vfs
+            .stat(&req.path)
+            .await
+            .map_err(status_from_vfs_error)?;
+        if entry.is_dir {
+            return Err(Status::invalid_argument(format!(
+                "Is a directory: {}",
+                req.path
+            )));
+        }
+        let size = entry.size.unwrap_or(0);
+        let end = match req.length {
+            0 => size,
+            length => size.min(req.offset.saturating_add(length)),
+        };
+
+        let vfs = self.vfs.clone();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            let mut offset = req.offset;
+            while offset < end {
+                let len = READ_CHUNK_SIZE.min(end - offset);
+                let chunk = match vfs.read_range(&req.path, offset, Some(len)).await {
+                    Ok(data) if data.is_empty() => break,
+                    Ok(data) => data,
+                    Err(e) => {
+                        let _ = tx.send(Err(status_from_vfs_error(e))).await;
+                        break;
+                    }
+                };
+                offset += chunk.len() as u64;
+                if tx.send(Ok(proto::Chunk { data: chunk })).await.is_err() {
+                    // The client went away.
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn write(
+        &self,
+        request: Request<Streaming<proto::WriteRequest>>,
+    ) -> Result<Response<proto::WriteResponse>, Status> {
+        let mut stream = request.into_inner();
+        let header = match stream.next().await {
+            Some(Ok(proto::WriteRequest {
+                part: Some(write_request::Part::Header(header)),
+            })) => header,
+            Some(Err(status)) => return Err(status),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "the first message of a write must be its header",
+                ))
+            }
+        };
+
+        let mut content = Vec::new();
+        while let Some(message) = stream.next().await {
+            match message?.part {
+                Some(write_request::Part::Data(data)) => content.extend_from_slice(&data),
+                Some(write_request::Part::Header(_)) => {
+                    return Err(Status::invalid_argument("a write takes exactly one header"))
+                }
+                None => {}
+            }
+        }
+
+        match header.cas_token.as_deref() {
+            Some(token) => self
+                .vfs
+                .compare_and_swap(&header.path, Some(token), &content)
+                .await
+                .map(drop),
+            None => self.vfs.write(&header.path, &content).await,
+        }
+        .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::WriteResponse {
+            bytes_written: content.len() as u64,
+        }))
+    }
+
+    async fn append(
+        &self,
+        request: Request<proto::AppendRequest>,
+    ) -> Result<Response<proto::AppendResponse>, Status> {
+        let req = request.into_inner();
+        self.vfs
+            .append(&req.path, &req.data)
+            .await
+            .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::AppendResponse {}))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<proto::DeleteRequest>,
+    ) -> Result<Response<proto::DeleteResponse>, Status> {
+        let req = request.into_inner();
+        self.vfs
+            .delete(&req.path)
+            .await
+            .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::DeleteResponse {}))
+    }
+
+    async fn rename(
+        &self,
+        request: Request<proto::RenameRequest>,
+    ) -> Result<Response<proto::RenameResponse>, Status> {
+        let req = request.into_inner();
+        self.vfs
+            .rename(&req.from, &req.to)
+            .await
+            .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::RenameResponse {}))
+    }
+
+    async fn stat(
+        &self,
+        request: Request<proto::StatRequest>,
+    ) -> Result<Response<proto::Entry>, Status> {
+        let req = request.into_inner();
+        let mut entry = self
+            .vfs
+            .stat(&req.path)
+            .await
+            .map_err(status_from_vfs_error)?;
+        entry.path = req.path;
+        Ok(Response::new(entry_to_proto(entry)))
+    }
+
+    async fn list(
+        &self,
+        request: Request<proto::ListRequest>,
+    ) -> Result<Response<proto::ListResponse>, Status> {
+        let req = request.into_inner();
+        let entries = if req.recursive {
+            self.vfs.walk(&req.path).await
+        } else {
+            // Backends list relative paths; answer with VFS paths.
+            self.vfs.list(&req.path).await.map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|mut entry| {
+                        entry.path = join_path(&req.path, &entry.name);
+                        entry
+                    })
+                    .collect()
+            })
+        }
+        .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::ListResponse {
+            entries: entries.into_iter().map(entry_to_proto).collect(),
+        }))
+    }
+
+    async fn grep(
+        &self,
+        request: Request<proto::GrepRequest>,
+    ) -> Result<Response<Self::GrepStream>, Status> {
+        let req = request.into_inner();
+        let defaults = GrepOptions::default();
+        let options = GrepOptions {
+            recursive: req.recursive,
+            max_matches: match req.max_matches {
+                0 => defaults.max_matches,
+                max => max as usize,
+            },
+            max_depth: match req.max_depth {
+                0 => defaults.max_depth,
+                depth => depth as usize,
+            },
+            ..defaults
+        };
+
+        // Unbounded, since matches are handed over synchronously; the
+        // number sent is capped by `max_matches`.
+        let vfs = self.vfs.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let result = grep_each(&vfs, &req.pattern, &req.path, &options, |m| {
+                let _ = tx.send(Ok(proto::GrepMatch {
+                    path: m.path,
+                    line_number: m.line_number as u64,
+                    line: m.line,
+                }));
+            })
+            .await;
+            if let Err(e) = result {
+                let _ = tx.send(Err(status_from_vfs_error(e)));
+            }
+        });
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let req = request.into_inner();
+        let defaults = SearchOptions::default();
+        let options = SearchOptions {
+            mode: search_mode(req.mode()),
+            limit: match req.limit {
+                0 => defaults.limit,
+                limit => limit as usize,
+            },
+            min_score: req.min_score,
+            path_prefix: req.path_prefix,
+            ..defaults
+        };
+        let hits = self
+            .vfs
+            .search(&req.query, &options)
+            .await
+            .map_err(status_from_vfs_error)?;
+        Ok(Response::new(proto::SearchResponse {
+            hits: hits
+                .into_iter()
+                .map(|hit| proto::SearchHit {
+                    path: hit.path,
+                    score: hit.score,
+                    content: hit.content,
+                    start_line: hit.start_line as u64,
+                    end_line: hit.end_line as u64,
+                    metadata: hit.metadata,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn watch(
+        &self,
+        request: Request<proto::WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let req = request.into_inner();
+        let Some(fs_root) = self.vfs.resolve_fs_path(&req.path) else {
+            return Err(Status::unimplemented(format!(
+                "watching is only available on fs mounts: {}",
+                req.path
+            )));
+        };
+        let mut engine = WatchEngine::new().map_err(status_from_vfs_error)?;
+        engine.watch_path(&fs_root).map_err(status_from_vfs_error)?;
+        let mut changes = engine
+            .take_receiver()
+            .ok_or_else(|| Status::internal("watch receiver already taken"))?;
+        // Events may name the canonical path (e.g. /private/var on macOS).
+        let canonical_root = fs_root.canonicalize().unwrap_or_else(|_| fs_root.clone());
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            // Keep the watcher alive for as long as the stream is.
+            let _engine = engine;
+            loop {
+                let change = tokio::select! {
+                    change = changes.recv() => match change {
+                        Some(change) => change,
+                        None => break,
+                    },
+                    _ = tx.closed() => break,
+                };
+                let roots = [fs_root.as_path(), canonical_root.as_path()];
+                let Some(path) = vfs_path_of(&change.path, &roots, &req.path) else {
+                    debug!(path = %change.path.display(), "change outside the watched path");
+                    continue;
+                };
+                let event = proto::WatchEvent {
+                    kind: watch_kind(&change.kind).into(),
+                    path,
+                    from: None,
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_core::BackendError;
+
+    #[test]
+    fn test_status_codes() {
+        let not_found = status_from_vfs_error(VfsError::NotFound("/a".into()));
+        assert_eq!(not_found.code(), Code::NotFound);
+        let read_only = status_from_vfs_error(VfsError::ReadOnly("/a".into()));
+        assert_eq!(read_only.code(), Code::PermissionDenied);
+        let config = status_from_vfs_error(VfsError::Config("bad regex".into()));
+        assert_eq!(config.code(), Code::InvalidArgument);
+        let stale: VfsError = BackendError::PreconditionFailed {
+            path: "a".into(),
+            expected: "1".into(),
+            actual: "2".into(),
+        }
+        .into();
+        assert_eq!(
+            status_from_vfs_error(stale).code(),
+            Code::FailedPrecondition
+        );
+        let other = status_from_vfs_error(VfsError::Indexing("boom".into()));
+        assert_eq!(other.code(), Code::Internal);
+    }
+
+    #[test]
+    fn test_vfs_path_of() {
+        let root = Path::new("/data/ws");
+        assert_eq!(
+            vfs_path_of(Path::new("/data/ws/src/a.rs"), &[root], "/workspace").as_deref(),
+            Some("/workspace/src/a.rs")
+        );
+        assert_eq!(
+            vfs_path_of(Path::new("/data/ws"), &[root], "/workspace/").as_deref(),
+            Some("/workspace/")
+        );
+        assert_eq!(
+            vfs_path_of(Path::new("/elsewhere/a.rs"), &[root], "/workspace"),
+            None
+        );
+    }
+}
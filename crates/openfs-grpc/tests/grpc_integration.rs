@@ -0,0 +1,403 @@
+//! gRPC Integration Tests
+//!
+//! Round-trips each RPC through an in-process server on a fs backend.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use openfs_config::VfsConfig;
+use openfs_grpc::proto::vfs_client::VfsClient;
+use openfs_grpc::proto::{
+    write_request, AppendRequest, DeleteRequest, GrepRequest, ListRequest, ReadRequest,
+    RenameRequest, SearchRequest, StatRequest, WatchRequest, WriteHeader, WriteRequest,
+};
+use openfs_grpc::GrpcServer;
+use openfs_remote::Vfs;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tonic::transport::Channel;
+use tonic::Code;
+
+async fn make_client(tmp: &TempDir) -> VfsClient<Channel> {
+    let yaml = format!(
+        r#"
+name: grpc-test
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: local
+"#,
+        tmp.path().to_str().unwrap()
+    );
+    let config = VfsConfig::from_yaml(&yaml).unwrap();
+    let vfs = Arc::new(Vfs::from_config(config).await.unwrap());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(GrpcServer::new(vfs).serve_with_listener(listener));
+    VfsClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap()
+}
+
+fn write_stream(
+    path: &str,
+    cas_token: Option<&str>,
+    chunks: &[&[u8]],
+) -> impl tokio_stream::Stream<Item = WriteRequest> {
+    let header = WriteRequest {
+        part: Some(write_request::Part::Header(WriteHeader {
+            path: path.to_string(),
+            cas_token: cas_token.map(String::from),
+        })),
+    };
+    let data = chunks.iter().map(|chunk| WriteRequest {
+        part: Some(write_request::Part::Data(chunk.to_vec())),
+    });
+    tokio_stream::iter(std::iter::once(header).chain(data).collect::<Vec<_>>())
+}
+
+async fn read_all(
+    client: &mut VfsClient<Channel>,
+    path: &str,
+    offset: u64,
+    length: u64,
+) -> Vec<u8> {
+    let mut stream = client
+        .read(ReadRequest {
+            path: path.to_string(),
+            offset,
+            length,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut content = Vec::new();
+    while let Some(chunk) = stream.message().await.unwrap() {
+        content.extend_from_slice(&chunk.data);
+    }
+    content
+}
+
+#[tokio::test]
+async fn test_write_then_read() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let written = client
+        .write(write_stream(
+            "/workspace/notes.txt",
+            None,
+            &[b"hello ", b"world"],
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(written.bytes_written, 11);
+    assert_eq!(
+        std::fs::read(tmp.path().join("notes.txt")).unwrap(),
+        b"hello world"
+    );
+
+    assert_eq!(
+        read_all(&mut client, "/workspace/notes.txt", 0, 0).await,
+        b"hello world"
+    );
+    assert_eq!(
+        read_all(&mut client, "/workspace/notes.txt", 6, 3).await,
+        b"wor"
+    );
+}
+
+#[tokio::test]
+async fn test_read_streams_large_files_in_chunks() {
+    let tmp = TempDir::new().unwrap();
+    let content: Vec<u8> = (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect();
+    std::fs::write(tmp.path().join("big.bin"), &content).unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let mut stream = client
+        .read(ReadRequest {
+            path: "/workspace/big.bin".to_string(),
+            offset: 0,
+            length: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut chunks = 0;
+    let mut read = Vec::new();
+    while let Some(chunk) = stream.message().await.unwrap() {
+        chunks += 1;
+        read.extend_from_slice(&chunk.data);
+    }
+    assert_eq!(chunks, 4);
+    assert_eq!(read, content);
+}
+
+#[tokio::test]
+async fn test_read_missing_file_is_not_found() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let err = client
+        .read(ReadRequest {
+            path: "/workspace/missing.txt".to_string(),
+            offset: 0,
+            length: 0,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_write_requires_header_first() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let data_only = tokio_stream::iter(vec![WriteRequest {
+        part: Some(write_request::Part::Data(b"orphan".to_vec())),
+    }]);
+    let err = client.write(data_only).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_write_with_stale_cas_token_fails() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("a.txt"), "v1").unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let err = client
+        .write(write_stream("/workspace/a.txt", Some("stale"), &[b"v2"]))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+    assert_eq!(std::fs::read(tmp.path().join("a.txt")).unwrap(), b"v1");
+}
+
+#[tokio::test]
+async fn test_append_stat_rename_delete() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("log.txt"), "one\n").unwrap();
+    let mut client = make_client(&tmp).await;
+
+    client
+        .append(AppendRequest {
+            path: "/workspace/log.txt".to_string(),
+            data: b"two\n".to_vec(),
+        })
+        .await
+        .unwrap();
+    let entry = client
+        .stat(StatRequest {
+            path: "/workspace/log.txt".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(entry.path, "/workspace/log.txt");
+    assert_eq!(entry.name, "log.txt");
+    assert!(!entry.is_dir);
+    assert_eq!(entry.size, Some(8));
+    assert!(entry.modified.is_some());
+
+    client
+        .rename(RenameRequest {
+            from: "/workspace/log.txt".to_string(),
+            to: "/workspace/old.txt".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read(tmp.path().join("old.txt")).unwrap(),
+        b"one\ntwo\n"
+    );
+
+    client
+        .delete(DeleteRequest {
+            path: "/workspace/old.txt".to_string(),
+        })
+        .await
+        .unwrap();
+    let err = client
+        .stat(StatRequest {
+            path: "/workspace/old.txt".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_list_direct_and_recursive() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("README.md"), "readme").unwrap();
+    std::fs::write(tmp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let mut direct: Vec<String> = client
+        .list(ListRequest {
+            path: "/workspace".to_string(),
+            recursive: false,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .entries
+        .into_iter()
+        .map(|e| e.path)
+        .collect();
+    direct.sort();
+    assert_eq!(direct, vec!["/workspace/README.md", "/workspace/src"]);
+
+    let mut all: Vec<String> = client
+        .list(ListRequest {
+            path: "/workspace".to_string(),
+            recursive: true,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .entries
+        .into_iter()
+        .map(|e| e.path)
+        .collect();
+    all.sort();
+    assert_eq!(
+        all,
+        vec![
+            "/workspace/README.md",
+            "/workspace/src",
+            "/workspace/src/main.rs"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_grep_streams_matches() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("a.txt"), "TODO one\nnothing\n").unwrap();
+    std::fs::write(tmp.path().join("src/b.txt"), "TODO two\nTODO three\n").unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let mut stream = client
+        .grep(GrepRequest {
+            pattern: "TODO".to_string(),
+            path: "/workspace".to_string(),
+            recursive: true,
+            max_matches: 0,
+            max_depth: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut matches = Vec::new();
+    while let Some(m) = stream.message().await.unwrap() {
+        matches.push((m.path, m.line_number, m.line));
+    }
+    matches.sort();
+    assert_eq!(
+        matches,
+        vec![
+            ("/workspace/a.txt".to_string(), 1, "TODO one".to_string()),
+            (
+                "/workspace/src/b.txt".to_string(),
+                1,
+                "TODO two".to_string()
+            ),
+            (
+                "/workspace/src/b.txt".to_string(),
+                2,
+                "TODO three".to_string()
+            ),
+        ]
+    );
+
+    let mut limited = client
+        .grep(GrepRequest {
+            pattern: "TODO".to_string(),
+            path: "/workspace".to_string(),
+            recursive: true,
+            max_matches: 1,
+            max_depth: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut count = 0;
+    while limited.message().await.unwrap().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_grep_invalid_pattern() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let mut stream = client
+        .grep(GrepRequest {
+            pattern: "(".to_string(),
+            path: "/workspace".to_string(),
+            recursive: false,
+            max_matches: 0,
+            max_depth: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let err = stream.message().await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_search_without_searchers() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let err = client
+        .search(SearchRequest {
+            query: "anything".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_watch_reports_changes() {
+    let tmp = TempDir::new().unwrap();
+    let mut client = make_client(&tmp).await;
+
+    let mut stream = client
+        .watch(WatchRequest {
+            path: "/workspace".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    // Give the watcher a moment to start before changing files.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    std::fs::write(tmp.path().join("new.txt"), "hi").unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let event = stream.message().await.unwrap().unwrap();
+            if event.path == "/workspace/new.txt" {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("no watch event for the new file");
+    assert_eq!(event.path, "/workspace/new.txt");
+}
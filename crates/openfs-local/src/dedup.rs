@@ -0,0 +1,97 @@
+//! Chunk-level embedding deduplication.
+//!
+//! Vendored files, license headers and generated boilerplate produce many
+//! byte-identical chunks. [`EmbeddingCache`] maps a chunk's content hash to
+//! its embedding so each distinct chunk is embedded only once.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Bounded content-hash → embedding cache with FIFO eviction.
+#[derive(Debug)]
+pub struct EmbeddingCache {
+    capacity: usize,
+    entries: HashMap<String, Vec<f32>>,
+    order: VecDeque<String>,
+}
+
+impl EmbeddingCache {
+    /// Create a cache holding at most `capacity` embeddings.
+    pub fn new(capacity: usize) -> Self {
+        EmbeddingCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Look up the embedding for a content hash.
+    pub fn get(&self, hash: &str) -> Option<&Vec<f32>> {
+        self.entries.get(hash)
+    }
+
+    /// Insert an embedding, evicting the oldest entry if the cache is full.
+    pub fn insert(&mut self, hash: String, embedding: Vec<f32>) {
+        if self.capacity == 0 || self.entries.contains_key(&hash) {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        self.order.push_back(hash.clone());
+        self.entries.insert(hash, embedding);
+    }
+
+    /// Number of cached embeddings.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all cached embeddings.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut cache = EmbeddingCache::new(4);
+        cache.insert("a".to_string(), vec![1.0]);
+        assert_eq!(cache.get("a"), Some(&vec![1.0]));
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_oldest() {
+        let mut cache = EmbeddingCache::new(2);
+        cache.insert("a".to_string(), vec![1.0]);
+        cache.insert("b".to_string(), vec![2.0]);
+        cache.insert("c".to_string(), vec![3.0]);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let mut cache = EmbeddingCache::new(0);
+        cache.insert("a".to_string(), vec![1.0]);
+        assert!(cache.is_empty());
+    }
+}
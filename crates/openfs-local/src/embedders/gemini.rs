@@ -0,0 +1,556 @@
+#![cfg(feature = "embedder-gemini")]
+
+use super::{Embedder, EmbedderConfig};
+use crate::{EmbeddingResult, IndexingError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const AI_STUDIO_ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1beta";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Task type hint sent with each embedding request.
+///
+/// Gemini embedding models produce different vectors for documents and
+/// queries; indexing uses `RetrievalDocument` and search uses `RetrievalQuery`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeminiTaskType {
+    RetrievalDocument,
+    RetrievalQuery,
+}
+
+impl GeminiTaskType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            GeminiTaskType::RetrievalDocument => "RETRIEVAL_DOCUMENT",
+            GeminiTaskType::RetrievalQuery => "RETRIEVAL_QUERY",
+        }
+    }
+}
+
+/// A Google service account key file (the JSON downloaded from the console).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    pub private_key: String,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub token_uri: Option<String>,
+}
+
+impl ServiceAccountKey {
+    /// Load a service account key from a JSON file.
+    pub fn from_file(path: &Path) -> Result<Self, IndexingError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Vertex AI deployment settings.
+#[derive(Debug, Clone)]
+pub struct VertexConfig {
+    /// GCP project ID.
+    pub project: String,
+    /// Region, e.g. `us-central1`.
+    pub location: String,
+    /// Service account used to mint access tokens.
+    pub credentials: ServiceAccountKey,
+}
+
+/// How requests are authenticated.
+enum GeminiAuth {
+    /// AI Studio API key (`x-goog-api-key` header).
+    ApiKey(String),
+    /// Vertex AI with a service account exchanged for OAuth2 access tokens.
+    Vertex {
+        config: VertexConfig,
+        token: Mutex<Option<CachedToken>>,
+    },
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: u64,
+}
+
+#[derive(Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default = "default_token_ttl")]
+    expires_in: u64,
+}
+
+fn default_token_ttl() -> u64 {
+    3600
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AiStudioBatchRequest {
+    requests: Vec<AiStudioEmbedRequest>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AiStudioEmbedRequest {
+    model: String,
+    content: AiStudioContent,
+    task_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimensionality: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct AiStudioContent {
+    parts: Vec<AiStudioPart>,
+}
+
+#[derive(Serialize)]
+struct AiStudioPart {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct AiStudioBatchResponse {
+    embeddings: Vec<AiStudioEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct AiStudioEmbedding {
+    values: Vec<f32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VertexPredictRequest {
+    instances: Vec<VertexInstance>,
+    parameters: VertexParameters,
+}
+
+#[derive(Serialize)]
+struct VertexInstance {
+    content: String,
+    task_type: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VertexParameters {
+    output_dimensionality: usize,
+}
+
+#[derive(Deserialize)]
+struct VertexPredictResponse {
+    predictions: Vec<VertexPrediction>,
+}
+
+#[derive(Deserialize)]
+struct VertexPrediction {
+    embeddings: VertexEmbedding,
+}
+
+#[derive(Deserialize)]
+struct VertexEmbedding {
+    values: Vec<f32>,
+    #[serde(default)]
+    statistics: Option<VertexStatistics>,
+}
+
+#[derive(Deserialize)]
+struct VertexStatistics {
+    #[serde(default)]
+    token_count: Option<f64>,
+}
+
+/// Gemini embedding client.
+///
+/// Supports Google AI Studio (API key) and Vertex AI (service account).
+pub struct GeminiEmbedder {
+    config: EmbedderConfig,
+    client: reqwest::Client,
+    auth: GeminiAuth,
+}
+
+impl GeminiEmbedder {
+    /// Create an AI Studio client authenticated with `config.api_key`.
+    pub fn new(config: EmbedderConfig) -> Self {
+        let api_key = config.api_key.clone().unwrap_or_default();
+        Self::with_auth(config, GeminiAuth::ApiKey(api_key))
+    }
+
+    /// Create a Vertex AI client authenticated with a service account.
+    pub fn vertex(config: EmbedderConfig, vertex: VertexConfig) -> Self {
+        Self::with_auth(
+            config,
+            GeminiAuth::Vertex {
+                config: vertex,
+                token: Mutex::new(None),
+            },
+        )
+    }
+
+    /// Create from environment variables.
+    ///
+    /// Uses Vertex AI when `GOOGLE_APPLICATION_CREDENTIALS` points at a service
+    /// account key; otherwise falls back to an AI Studio key from
+    /// `GEMINI_API_KEY` or `GOOGLE_API_KEY`.
+    pub fn from_env(config: EmbedderConfig) -> Result<Self, IndexingError> {
+        if config.api_key.is_none() {
+            if let Ok(path) = std::env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+                let credentials = ServiceAccountKey::from_file(Path::new(&path))?;
+                let project = std::env::var("GOOGLE_CLOUD_PROJECT")
+                    .ok()
+                    .or_else(|| credentials.project_id.clone())
+                    .ok_or_else(|| {
+                        IndexingError::EmbeddingError(
+                            "Vertex AI requires GOOGLE_CLOUD_PROJECT or a project_id in the service account key".to_string(),
+                        )
+                    })?;
+                let location = std::env::var("GOOGLE_CLOUD_LOCATION")
+                    .unwrap_or_else(|_| "us-central1".to_string());
+                return Ok(Self::vertex(
+                    config,
+                    VertexConfig {
+                        project,
+                        location,
+                        credentials,
+                    },
+                ));
+            }
+        }
+
+        let mut config = config;
+        if config.api_key.is_none() {
+            config.api_key = std::env::var("GEMINI_API_KEY")
+                .or_else(|_| std::env::var("GOOGLE_API_KEY"))
+                .ok();
+        }
+        if config.api_key.is_none() {
+            return Err(IndexingError::EmbeddingError(
+                "Gemini requires an API key (GEMINI_API_KEY) or service account (GOOGLE_APPLICATION_CREDENTIALS)".to_string(),
+            ));
+        }
+        Ok(Self::new(config))
+    }
+
+    fn with_auth(config: EmbedderConfig, auth: GeminiAuth) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        GeminiEmbedder {
+            config,
+            client,
+            auth,
+        }
+    }
+
+    /// Embed texts with an explicit task type hint.
+    pub async fn embed_with_task(
+        &self,
+        texts: &[&str],
+        task: GeminiTaskType,
+    ) -> Result<EmbeddingResult, IndexingError> {
+        if texts.is_empty() {
+            return Ok(EmbeddingResult {
+                embeddings: vec![],
+                token_count: None,
+            });
+        }
+
+        let mut all_embeddings = Vec::with_capacity(texts.len());
+        let mut total_tokens = 0usize;
+
+        for batch in texts.chunks(self.config.batch_size) {
+            match &self.auth {
+                GeminiAuth::ApiKey(api_key) => {
+                    let embeddings = self.embed_ai_studio(api_key, batch, task).await?;
+                    all_embeddings.extend(embeddings);
+                }
+                GeminiAuth::Vertex { config, token } => {
+                    let access_token = self.access_token(config, token).await?;
+                    let (embeddings, tokens) = self
+                        .embed_vertex(config, &access_token, batch, task)
+                        .await?;
+                    all_embeddings.extend(embeddings);
+                    total_tokens += tokens;
+                }
+            }
+        }
+
+        Ok(EmbeddingResult {
+            embeddings: all_embeddings,
+            token_count: if total_tokens > 0 {
+                Some(total_tokens)
+            } else {
+                None
+            },
+        })
+    }
+
+    async fn embed_ai_studio(
+        &self,
+        api_key: &str,
+        batch: &[&str],
+        task: GeminiTaskType,
+    ) -> Result<Vec<Vec<f32>>, IndexingError> {
+        let endpoint = self
+            .config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| AI_STUDIO_ENDPOINT.to_string());
+        let model = format!("models/{}", self.config.model.trim_start_matches("models/"));
+
+        let request = AiStudioBatchRequest {
+            requests: batch
+                .iter()
+                .map(|text| AiStudioEmbedRequest {
+                    model: model.clone(),
+                    content: AiStudioContent {
+                        parts: vec![AiStudioPart {
+                            text: text.to_string(),
+                        }],
+                    },
+                    task_type: task.as_str(),
+                    output_dimensionality: Some(self.config.dimensions),
+                })
+                .collect(),
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/{}:batchEmbedContents", endpoint, model))
+            .header("x-goog-api-key", api_key)
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = super::error_for_status("Gemini API", response).await?;
+
+        let result: AiStudioBatchResponse = response.json().await?;
+        Ok(result.embeddings.into_iter().map(|e| e.values).collect())
+    }
+
+    async fn embed_vertex(
+        &self,
+        vertex: &VertexConfig,
+        access_token: &str,
+        batch: &[&str],
+        task: GeminiTaskType,
+    ) -> Result<(Vec<Vec<f32>>, usize), IndexingError> {
+        let endpoint =
+            self.config.endpoint.clone().unwrap_or_else(|| {
+                format!("https://{}-aiplatform.googleapis.com/v1", vertex.location)
+            });
+        let url = format!(
+            "{}/projects/{}/locations/{}/publishers/google/models/{}:predict",
+            endpoint, vertex.project, vertex.location, self.config.model
+        );
+
+        let request = VertexPredictRequest {
+            instances: batch
+                .iter()
+                .map(|text| VertexInstance {
+                    content: text.to_string(),
+                    task_type: task.as_str(),
+                })
+                .collect(),
+            parameters: VertexParameters {
+                output_dimensionality: self.config.dimensions,
+            },
+        };
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(access_token)
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = super::error_for_status("Vertex AI", response).await?;
+
+        let result: VertexPredictResponse = response.json().await?;
+        let mut tokens = 0usize;
+        let embeddings = result
+            .predictions
+            .into_iter()
+            .map(|p| {
+                if let Some(count) = p.embeddings.statistics.and_then(|s| s.token_count) {
+                    tokens += count as usize;
+                }
+                p.embeddings.values
+            })
+            .collect();
+        Ok((embeddings, tokens))
+    }
+
+    /// Return a cached access token, minting a new one shortly before expiry.
+    async fn access_token(
+        &self,
+        vertex: &VertexConfig,
+        cache: &Mutex<Option<CachedToken>>,
+    ) -> Result<String, IndexingError> {
+        let now = unix_now();
+        let mut guard = cache.lock().await;
+        if let Some(token) = guard.as_ref() {
+            if token.expires_at > now + 60 {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let credentials = &vertex.credentials;
+        let token_uri = credentials
+            .token_uri
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TOKEN_URI.to_string());
+        let claims = JwtClaims {
+            iss: &credentials.client_email,
+            scope: CLOUD_PLATFORM_SCOPE,
+            aud: &token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(credentials.private_key.as_bytes())
+            .map_err(|e| {
+                IndexingError::EmbeddingError(format!("Invalid service account key: {}", e))
+            })?;
+        let assertion = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &key,
+        )
+        .map_err(|e| IndexingError::EmbeddingError(format!("Failed to sign JWT: {}", e)))?;
+
+        let response = self
+            .client
+            .post(&token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(IndexingError::EmbeddingError(format!(
+                "Service account token exchange failed: {} - {}",
+                status, body
+            )));
+        }
+
+        let token: TokenResponse = response.json().await?;
+        let access_token = token.access_token.clone();
+        *guard = Some(CachedToken {
+            access_token: token.access_token,
+            expires_at: now + token.expires_in,
+        });
+        Ok(access_token)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[async_trait]
+impl Embedder for GeminiEmbedder {
+    async fn embed(&self, texts: &[&str]) -> Result<EmbeddingResult, IndexingError> {
+        self.embed_with_task(texts, GeminiTaskType::RetrievalDocument)
+            .await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, IndexingError> {
+        let result = self
+            .embed_with_task(&[text], GeminiTaskType::RetrievalQuery)
+            .await?;
+        result
+            .embeddings
+            .into_iter()
+            .next()
+            .ok_or_else(|| IndexingError::EmbeddingError("No embedding returned".to_string()))
+    }
+
+    fn dimensions(&self) -> usize {
+        self.config.dimensions
+    }
+
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_type_names() {
+        assert_eq!(
+            GeminiTaskType::RetrievalDocument.as_str(),
+            "RETRIEVAL_DOCUMENT"
+        );
+        assert_eq!(GeminiTaskType::RetrievalQuery.as_str(), "RETRIEVAL_QUERY");
+    }
+
+    #[test]
+    fn test_ai_studio_request_shape() {
+        let request = AiStudioBatchRequest {
+            requests: vec![AiStudioEmbedRequest {
+                model: "models/text-embedding-004".to_string(),
+                content: AiStudioContent {
+                    parts: vec![AiStudioPart {
+                        text: "hello".to_string(),
+                    }],
+                },
+                task_type: GeminiTaskType::RetrievalQuery.as_str(),
+                output_dimensionality: Some(768),
+            }],
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["requests"][0]["taskType"], "RETRIEVAL_QUERY");
+        assert_eq!(json["requests"][0]["outputDimensionality"], 768);
+        assert_eq!(json["requests"][0]["content"]["parts"][0]["text"], "hello");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires GEMINI_API_KEY or GOOGLE_APPLICATION_CREDENTIALS
+    async fn test_gemini_embedder() {
+        let config = EmbedderConfig {
+            model: "text-embedding-004".to_string(),
+            dimensions: 768,
+            ..Default::default()
+        };
+
+        let embedder = GeminiEmbedder::from_env(config).unwrap();
+        let result = embedder.embed(&["hello world"]).await.unwrap();
+        assert_eq!(result.embeddings.len(), 1);
+
+        let query = embedder.embed_query("hello").await.unwrap();
+        assert_eq!(query.len(), 768);
+    }
+}
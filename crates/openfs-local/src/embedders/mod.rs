@@ -12,6 +12,11 @@ mod openai;
 #[cfg(feature = "embedder-openai")]
 pub use openai::OpenAiEmbedder;
 
+#[cfg(feature = "embedder-gemini")]
+mod gemini;
+#[cfg(feature = "embedder-gemini")]
+pub use gemini::{GeminiEmbedder, GeminiTaskType, ServiceAccountKey, VertexConfig};
+
 use std::sync::Arc;
 
 use crate::{EmbeddingResult, IndexingError};
@@ -69,6 +74,14 @@ pub trait Embedder: Send + Sync {
             .ok_or_else(|| IndexingError::EmbeddingError("No embedding returned".to_string()))
     }
 
+    /// Embed a search query.
+    ///
+    /// Providers that distinguish document and query embeddings override this;
+    /// the default is the same as [`Embedder::embed_one`].
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, IndexingError> {
+        self.embed_one(text).await
+    }
+
     /// Get the embedding dimensions.
     fn dimensions(&self) -> usize;
 
@@ -104,6 +117,44 @@ impl TextEmbedder for EmbedderAdapter {
     }
 }
 
+/// Convert a non-success HTTP response into an [`IndexingError`].
+///
+/// `429 Too Many Requests` maps to [`IndexingError::RateLimited`], carrying the
+/// server's `Retry-After` hint so the pipeline can back off accordingly.
+#[cfg(any(
+    feature = "embedder-ollama",
+    feature = "embedder-openai",
+    feature = "embedder-gemini"
+))]
+pub(crate) async fn error_for_status(
+    provider: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, IndexingError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::rate_limit::parse_retry_after);
+    let body = response.text().await.unwrap_or_default();
+
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(IndexingError::RateLimited {
+            message: format!("{} error: {} - {}", provider, status, body),
+            retry_after,
+        });
+    }
+
+    Err(IndexingError::EmbeddingError(format!(
+        "{} error: {} - {}",
+        provider, status, body
+    )))
+}
+
 /// Create an embedder based on provider name.
 pub fn create_embedder(
     provider: &str,
@@ -115,6 +166,8 @@ pub fn create_embedder(
         "ollama" => Ok(Box::new(OllamaEmbedder::new(config))),
         #[cfg(feature = "embedder-openai")]
         "openai" | "openai-compatible" => Ok(Box::new(OpenAiEmbedder::new(config))),
+        #[cfg(feature = "embedder-gemini")]
+        "gemini" | "vertex" | "vertex-ai" => Ok(Box::new(GeminiEmbedder::from_env(config)?)),
         _ => Err(IndexingError::EmbeddingError(format!(
             "Unknown embedding provider: {}",
             provider
@@ -64,14 +64,7 @@ impl Embedder for OllamaEmbedder {
                 .send()
                 .await?;
 
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(IndexingError::EmbeddingError(format!(
-                    "Ollama API error: {} - {}",
-                    status, body
-                )));
-            }
+            let response = super::error_for_status("Ollama API", response).await?;
 
             let result: OllamaEmbedResponse = response.json().await?;
             all_embeddings.extend(result.embeddings);
@@ -106,14 +106,7 @@ impl Embedder for OpenAiEmbedder {
                 .send()
                 .await?;
 
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(IndexingError::EmbeddingError(format!(
-                    "OpenAI API error: {} - {}",
-                    status, body
-                )));
-            }
+            let response = super::error_for_status("OpenAI API", response).await?;
 
             let mut result: OpenAiEmbedResponse = response.json().await?;
 
@@ -0,0 +1,249 @@
+//! Query expansion for sparse retrieval.
+//!
+//! Terse queries such as `auth bug` miss chunks that say "login" or
+//! "authentication" instead. [`QueryExpander`] adds related terms from a
+//! synonym list and, optionally, from an OpenAI-compatible chat completions
+//! endpoint asked to rewrite the query. The engine feeds the expanded query
+//! to sparse (BM25) retrieval only; dense retrieval embeds the query as
+//! written, since embeddings already capture related wording.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "expansion-llm")]
+use std::time::Duration;
+#[cfg(feature = "expansion-llm")]
+use tracing::warn;
+
+use crate::IndexingError;
+
+/// Configuration for query expansion.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryExpansionConfig {
+    /// Terms added when the query contains the key, e.g. `auth` →
+    /// `[authentication, login]`. Keys may span several words and match
+    /// case-insensitively on word boundaries.
+    #[serde(default)]
+    pub synonyms: HashMap<String, Vec<String>>,
+    /// OpenAI-compatible chat completions base URL asked for extra search
+    /// terms. Unset uses the synonym list only.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Model used for rewrites.
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// API key for the endpoint; defaults to `OPENAI_API_KEY`.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Most terms an LLM rewrite may add.
+    #[serde(default = "default_max_terms")]
+    pub max_terms: usize,
+}
+
+impl From<&openfs_config::ExpansionConfig> for QueryExpansionConfig {
+    fn from(config: &openfs_config::ExpansionConfig) -> Self {
+        QueryExpansionConfig {
+            synonyms: config
+                .synonyms
+                .iter()
+                .map(|(key, terms)| (key.clone(), terms.clone()))
+                .collect(),
+            endpoint: config.endpoint.clone(),
+            model: config.model.clone().unwrap_or_else(default_model),
+            api_key: None,
+            max_terms: config.max_terms.unwrap_or_else(default_max_terms),
+        }
+    }
+}
+
+fn default_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_max_terms() -> usize {
+    8
+}
+
+/// Expands queries with related terms.
+#[derive(Debug)]
+pub struct QueryExpander {
+    config: QueryExpansionConfig,
+    #[cfg(feature = "expansion-llm")]
+    client: Option<reqwest::Client>,
+}
+
+impl QueryExpander {
+    pub fn new(config: QueryExpansionConfig) -> Result<Self, IndexingError> {
+        #[cfg(feature = "expansion-llm")]
+        let client = match &config.endpoint {
+            Some(_) => {
+                let mut headers = reqwest::header::HeaderMap::new();
+                let api_key = config
+                    .api_key
+                    .clone()
+                    .or_else(|| std::env::var("OPENAI_API_KEY").ok());
+                if let Some(api_key) = api_key {
+                    let value = format!("Bearer {}", api_key).parse().map_err(|_| {
+                        IndexingError::HttpError("Invalid API key header".to_string())
+                    })?;
+                    headers.insert(reqwest::header::AUTHORIZATION, value);
+                }
+                Some(
+                    reqwest::Client::builder()
+                        .default_headers(headers)
+                        .timeout(Duration::from_secs(10))
+                        .build()?,
+                )
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "expansion-llm"))]
+        if config.endpoint.is_some() {
+            return Err(IndexingError::HttpError(
+                "LLM query expansion needs the expansion-llm feature".to_string(),
+            ));
+        }
+
+        Ok(QueryExpander {
+            config,
+            #[cfg(feature = "expansion-llm")]
+            client,
+        })
+    }
+
+    /// Terms related to `query` that it does not already contain.
+    ///
+    /// A failed LLM rewrite is logged and leaves the synonym terms.
+    pub async fn expand(&self, query: &str) -> Vec<String> {
+        let query_words = words(query);
+        let mut terms: Vec<String> = Vec::new();
+        let mut add = |term: &str| {
+            let term = term.trim();
+            let lower = term.to_lowercase();
+            if !term.is_empty()
+                && !query_words.contains(&lower)
+                && !terms.iter().any(|t| t.to_lowercase() == lower)
+            {
+                terms.push(term.to_string());
+            }
+        };
+
+        let mut keys: Vec<&String> = self.config.synonyms.keys().collect();
+        keys.sort();
+        for key in keys {
+            let key_words = words(key);
+            if !key_words.is_empty() && query_words.windows(key_words.len()).any(|w| w == key_words)
+            {
+                self.config.synonyms[key].iter().for_each(|term| add(term));
+            }
+        }
+
+        #[cfg(feature = "expansion-llm")]
+        if let Some(client) = &self.client {
+            match self.rewrite(client, query).await {
+                Ok(rewritten) => rewritten.iter().for_each(|term| add(term)),
+                Err(e) => warn!("Query expansion failed for '{}': {}", query, e),
+            }
+        }
+        terms
+    }
+
+    /// Ask the endpoint for search terms related to `query`.
+    #[cfg(feature = "expansion-llm")]
+    async fn rewrite(
+        &self,
+        client: &reqwest::Client,
+        query: &str,
+    ) -> Result<Vec<String>, IndexingError> {
+        let endpoint = self.config.endpoint.as_deref().unwrap_or_default();
+        let request = serde_json::json!({
+            "model": self.config.model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": format!(
+                        "List up to {} keywords or short phrases, such as synonyms and \
+                         related technical terms, that documents relevant to the search \
+                         query may use. Reply with a comma-separated list only.",
+                        self.config.max_terms
+                    ),
+                },
+                { "role": "user", "content": query },
+            ],
+            "max_tokens": 64,
+            "temperature": 0.0,
+        });
+        let response = client
+            .post(format!("{}/chat/completions", endpoint))
+            .json(&request)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(IndexingError::HttpError(format!(
+                "LLM API error: {} - {}",
+                status, body
+            )));
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        let content = body["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default();
+        Ok(content
+            .split([',', '\n'])
+            .map(|term| term.trim().trim_matches('"').to_string())
+            .filter(|term| !term.is_empty())
+            .take(self.config.max_terms)
+            .collect())
+    }
+}
+
+/// Lowercased alphanumeric words of `text`.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_synonym_expansion() {
+        let expander = QueryExpander::new(QueryExpansionConfig {
+            synonyms: HashMap::from([
+                (
+                    "auth".to_string(),
+                    vec!["authentication".to_string(), "login".to_string()],
+                ),
+                ("sign in".to_string(), vec!["login".to_string()]),
+                ("db".to_string(), vec!["database".to_string()]),
+            ]),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            expander.expand("Auth bug on sign-in").await,
+            ["authentication", "login"]
+        );
+        assert_eq!(expander.expand("login via auth").await, ["authentication"]);
+        assert!(expander.expand("authorize dbs").await.is_empty());
+    }
+
+    #[cfg(feature = "expansion-llm")]
+    #[tokio::test]
+    async fn test_failed_rewrite_keeps_synonyms() {
+        let expander = QueryExpander::new(QueryExpansionConfig {
+            synonyms: HashMap::from([("db".to_string(), vec!["database".to_string()])]),
+            endpoint: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(expander.expand("db timeout").await, ["database"]);
+    }
+}
@@ -1,6 +1,7 @@
 use super::TextExtractor;
 use crate::IndexingError;
 use async_trait::async_trait;
+use openfs_core::is_binary_content;
 
 /// Plain text extractor for UTF-8 text files.
 pub struct PlainTextExtractor {
@@ -37,12 +38,6 @@ impl PlainTextExtractor {
     fn get_extension(path: &str) -> Option<&str> {
         path.rsplit('.').next()
     }
-
-    fn is_likely_binary(content: &[u8]) -> bool {
-        // Check first 8KB for null bytes (common in binary files)
-        let check_len = content.len().min(8192);
-        content[..check_len].contains(&0)
-    }
 }
 
 impl Default for PlainTextExtractor {
@@ -55,7 +50,7 @@ impl Default for PlainTextExtractor {
 impl TextExtractor for PlainTextExtractor {
     async fn extract(&self, content: &[u8], path: &str) -> Result<String, IndexingError> {
         // Check for binary content
-        if Self::is_likely_binary(content) {
+        if is_binary_content(content) {
             return Err(IndexingError::UnsupportedFileType(format!(
                 "Binary file detected: {}",
                 path
@@ -121,5 +116,9 @@ mod tests {
         let binary_content = vec![0x00, 0x01, 0x02, 0x03];
         let result = extractor.extract(&binary_content, "/test.bin").await;
         assert!(result.is_err());
+
+        // Known binary formats are caught by their magic bytes alone.
+        let png_header = b"\x89PNG\r\n\x1a\n";
+        assert!(extractor.extract(png_header, "/image.md").await.is_err());
     }
 }
@@ -1,10 +1,13 @@
+use std::collections::HashSet;
 use std::path::Path;
 use std::time::Instant;
 
 use openfs_core::{Backend, VfsError};
 use tracing::{debug, info, warn};
 
-use crate::index_state::{FileInfo, IndexState};
+use crate::content_hash;
+use crate::index_state::{DeltaResult, FileInfo, IndexState};
+use crate::language::detect_language;
 use crate::pipeline::{IndexingPipeline, PipelineConfig};
 
 /// Result of an incremental indexing operation.
@@ -18,6 +21,12 @@ pub struct IncrementalResult {
     pub deleted_files: usize,
     /// Number of unchanged files skipped.
     pub unchanged_files: usize,
+    /// Number of files found under a new path with unchanged content, whose
+    /// chunks were moved instead of re-embedded. Not counted as new or
+    /// deleted.
+    pub renamed_files: usize,
+    /// Chunks moved by renames, reusing their stored embeddings.
+    pub chunks_reused: usize,
     /// Total chunks created.
     pub total_chunks: usize,
     /// Duration in milliseconds.
@@ -29,7 +38,9 @@ pub struct IncrementalResult {
 /// Incremental indexer that wraps an `IndexingPipeline` with state tracking.
 ///
 /// It compares the current state of files against the persisted `IndexState`
-/// and only re-indexes files that are new or modified.
+/// and only re-indexes files that are new or modified. A new file with the
+/// content of a deleted one is treated as a rename: its chunks are moved in
+/// the vector store rather than re-embedded.
 pub struct IncrementalIndexer {
     pipeline: IndexingPipeline,
     state: IndexState,
@@ -39,8 +50,15 @@ pub struct IncrementalIndexer {
 impl IncrementalIndexer {
     /// Create a new incremental indexer.
     pub fn new(config: PipelineConfig, state_path: &Path) -> Result<Self, VfsError> {
-        let pipeline = IndexingPipeline::new(config)?;
+        Ok(Self::with_pipeline(
+            IndexingPipeline::new(config)?,
+            state_path,
+        ))
+    }
 
+    /// Create an incremental indexer around an already configured pipeline
+    /// (for example one with a Chroma store attached).
+    pub fn with_pipeline(pipeline: IndexingPipeline, state_path: &Path) -> Self {
         let state = if state_path.exists() {
             IndexState::load(state_path).unwrap_or_else(|e| {
                 warn!("Failed to load index state, starting fresh: {}", e);
@@ -50,11 +68,11 @@ impl IncrementalIndexer {
             IndexState::new()
         };
 
-        Ok(IncrementalIndexer {
+        IncrementalIndexer {
             pipeline,
             state,
             state_path: state_path.to_path_buf(),
-        })
+        }
     }
 
     /// Get a reference to the underlying pipeline.
@@ -67,6 +85,20 @@ impl IncrementalIndexer {
         &self.state
     }
 
+    /// Compute which files under `dir_path` differ from the persisted state,
+    /// without indexing anything.
+    pub async fn compute_delta<B: Backend>(
+        &self,
+        backend: &B,
+        dir_path: &str,
+        recursive: bool,
+    ) -> Result<DeltaResult, VfsError> {
+        let mut current_files = Vec::new();
+        self.collect_file_info(backend, dir_path, recursive, &mut current_files)
+            .await?;
+        Ok(self.state.compute_delta(&current_files))
+    }
+
     /// Index a directory incrementally -- only new and modified files are processed.
     pub async fn index_directory<B: Backend>(
         &mut self,
@@ -111,27 +143,62 @@ impl IncrementalIndexer {
         let file_info_map: std::collections::HashMap<&str, &FileInfo> =
             current_files.iter().map(|f| (f.path.as_str(), f)).collect();
 
-        for path in &files_to_index {
-            match backend.read(path).await {
-                Ok(content) => match self.pipeline.index_file(path, &content).await {
-                    Ok(result) => {
-                        total_chunks += result.chunks_created;
-                        if let Some(info) = file_info_map.get(path.as_str()) {
-                            self.state.record_indexed(
-                                path,
-                                info.size,
-                                info.mtime,
-                                result.chunks_created,
-                            );
+        // Deleted files that new files may have been renamed from, by content hash
+        let mut rename_candidates = self.state.rename_candidates(&delta.deleted_files);
+        let mut renamed: HashSet<String> = HashSet::new();
+        let mut chunks_reused = 0;
+
+        for (idx, path) in files_to_index.iter().enumerate() {
+            let content = match backend.read(path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Failed to read {}: {}", path, e);
+                    errors.push((path.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            let hash = content_hash(&content);
+            let info = file_info_map.get(path.as_str());
+
+            let is_new = idx < delta.new_files.len();
+            if let Some(from) = is_new.then(|| rename_candidates.remove(&hash)).flatten() {
+                // A different language may need a different chunker
+                let text = String::from_utf8_lossy(&content);
+                if detect_language(&from, &text) == detect_language(path, &text) {
+                    match self.pipeline.rename_file(&from, path).await {
+                        Ok(moved) => {
+                            debug!("Moved {} chunks from {} to {}", moved, from, path);
+                            chunks_reused += moved;
+                            if let Some(info) = info {
+                                self.state
+                                    .record_renamed(&from, path, info.size, info.mtime);
+                            }
+                            renamed.insert(from);
+                            continue;
                         }
+                        Err(e) => warn!(
+                            "Failed to move chunks from {} to {}, re-indexing: {}",
+                            from, path, e
+                        ),
                     }
-                    Err(e) => {
-                        warn!("Failed to index {}: {}", path, e);
-                        errors.push((path.clone(), e.to_string()));
+                }
+            }
+
+            match self.pipeline.index_file(path, &content).await {
+                Ok(result) => {
+                    total_chunks += result.chunks_created;
+                    if let Some(info) = info {
+                        self.state.record_indexed_with_hash(
+                            path,
+                            info.size,
+                            info.mtime,
+                            result.chunks_created,
+                            hash,
+                        );
                     }
-                },
+                }
                 Err(e) => {
-                    warn!("Failed to read {}: {}", path, e);
+                    warn!("Failed to index {}: {}", path, e);
                     errors.push((path.clone(), e.to_string()));
                 }
             }
@@ -139,6 +206,9 @@ impl IncrementalIndexer {
 
         // Clean up deleted files
         for path in &delta.deleted_files {
+            if renamed.contains(path) {
+                continue;
+            }
             if let Err(e) = self.pipeline.delete_file(path).await {
                 warn!("Failed to clean up index for deleted file {}: {}", path, e);
             }
@@ -148,10 +218,12 @@ impl IncrementalIndexer {
         let duration_ms = start.elapsed().as_millis() as u64;
 
         Ok(IncrementalResult {
-            new_files: delta.new_files.len(),
+            new_files: delta.new_files.len() - renamed.len(),
             modified_files: delta.modified_files.len(),
-            deleted_files: delta.deleted_files.len(),
+            deleted_files: delta.deleted_files.len() - renamed.len(),
             unchanged_files: delta.unchanged_files.len(),
+            renamed_files: renamed.len(),
+            chunks_reused,
             total_chunks,
             duration_ms,
             errors,
@@ -169,14 +241,19 @@ impl IncrementalIndexer {
             self.pipeline.delete_file(path).await?;
             self.state.remove_file(path);
         } else {
-            let content = backend.read(path).await.map_err(VfsError::from)?;
             let stat = backend.stat(path).await.map_err(VfsError::from)?;
+            if !self.pipeline.accepts(path, stat.size) {
+                debug!("Skipping {} (excluded by index policy)", path);
+                return Ok(());
+            }
+            let content = backend.read(path).await.map_err(VfsError::from)?;
             let result = self.pipeline.index_file(path, &content).await?;
-            self.state.record_indexed(
+            self.state.record_indexed_with_hash(
                 path,
                 stat.size.unwrap_or(0),
                 stat.modified,
                 result.chunks_created,
+                content_hash(&content),
             );
         }
         Ok(())
@@ -187,6 +264,17 @@ impl IncrementalIndexer {
         self.state.save(&self.state_path).map_err(VfsError::Io)
     }
 
+    /// Persist the index state at `state_path` from now on, removing the
+    /// file it was kept in before.
+    pub fn move_state(&mut self, state_path: &Path) -> Result<(), VfsError> {
+        self.state.save(state_path).map_err(VfsError::Io)?;
+        if self.state_path != state_path && self.state_path.exists() {
+            std::fs::remove_file(&self.state_path).map_err(VfsError::Io)?;
+        }
+        self.state_path = state_path.to_path_buf();
+        Ok(())
+    }
+
     /// Recursively collect file info (path, size, mtime) from a directory.
     async fn collect_file_info<B: Backend>(
         &self,
@@ -203,7 +291,7 @@ impl IncrementalIndexer {
                     Box::pin(self.collect_file_info(backend, &entry.path, recursive, files))
                         .await?;
                 }
-            } else {
+            } else if self.pipeline.accepts(&entry.path, entry.size) {
                 files.push(FileInfo {
                     path: entry.path,
                     size: entry.size.unwrap_or(0),
@@ -220,9 +308,110 @@ impl IncrementalIndexer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use openfs_core::{
+        metadata_matches, BackendError, ChromaStore, QueryResult, SparseEmbedding, StoredDocument,
+    };
     use openfs_remote::MemoryBackend;
     use tempfile::TempDir;
 
+    /// Store keeping documents, with their embeddings, by ID.
+    #[derive(Default)]
+    struct MemoryChroma {
+        docs: Mutex<HashMap<String, StoredDocument>>,
+    }
+
+    fn source_path(doc: &StoredDocument) -> Option<&str> {
+        doc.metadata.as_ref()?.get("source_path")?.as_str()
+    }
+
+    #[async_trait]
+    impl ChromaStore for MemoryChroma {
+        async fn upsert(
+            &self,
+            path: &str,
+            content: &str,
+            embedding: Option<Vec<f32>>,
+            sparse_embedding: Option<SparseEmbedding>,
+            metadata: Option<HashMap<String, serde_json::Value>>,
+        ) -> Result<(), BackendError> {
+            self.docs.lock().unwrap().insert(
+                path.to_string(),
+                StoredDocument {
+                    id: path.to_string(),
+                    document: Some(content.to_string()),
+                    embedding,
+                    sparse_embedding,
+                    metadata,
+                },
+            );
+            Ok(())
+        }
+
+        async fn query_by_embedding(
+            &self,
+            _embedding: Vec<f32>,
+            _n_results: usize,
+        ) -> Result<Vec<QueryResult>, BackendError> {
+            Ok(Vec::new())
+        }
+
+        async fn query_by_sparse_embedding(
+            &self,
+            _query_sparse: &SparseEmbedding,
+            _n_results: usize,
+        ) -> Result<Vec<QueryResult>, BackendError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_by_metadata(
+            &self,
+            filter: serde_json::Value,
+        ) -> Result<Vec<StoredDocument>, BackendError> {
+            let found = self
+                .docs
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|doc| metadata_matches(doc.metadata.as_ref(), &filter))
+                .cloned()
+                .collect();
+            // Let concurrent indexing interleave, as a remote store would
+            tokio::task::yield_now().await;
+            Ok(found)
+        }
+
+        async fn delete_by_metadata(
+            &self,
+            filter: serde_json::Value,
+        ) -> Result<usize, BackendError> {
+            let mut docs = self.docs.lock().unwrap();
+            let before = docs.len();
+            docs.retain(|_, doc| !metadata_matches(doc.metadata.as_ref(), &filter));
+            Ok(before - docs.len())
+        }
+
+        async fn set_collection_metadata(
+            &self,
+            _metadata: HashMap<String, serde_json::Value>,
+        ) -> Result<(), BackendError> {
+            Ok(())
+        }
+
+        async fn get_collection_metadata(
+            &self,
+        ) -> Result<Option<HashMap<String, serde_json::Value>>, BackendError> {
+            Ok(None)
+        }
+
+        fn collection_name(&self) -> &str {
+            "test"
+        }
+    }
+
     fn make_indexer(tmp: &TempDir) -> IncrementalIndexer {
         let state_path = tmp.path().join(".openfs-index-state.json");
         IncrementalIndexer::new(PipelineConfig::default(), &state_path).unwrap()
@@ -283,6 +472,68 @@ mod tests {
         assert_eq!(result2.unchanged_files, 1);
     }
 
+    #[tokio::test]
+    async fn test_incremental_index_rename() {
+        let tmp = TempDir::new().unwrap();
+        let chroma = Arc::new(MemoryChroma::default());
+        let pipeline = IndexingPipeline::new(PipelineConfig::default())
+            .unwrap()
+            .with_chroma(chroma.clone() as Arc<dyn ChromaStore>);
+        let mut indexer =
+            IncrementalIndexer::with_pipeline(pipeline, &tmp.path().join("state.json"));
+
+        let backend = MemoryBackend::new();
+        backend
+            .write("/dir/notes.txt", b"Meeting notes")
+            .await
+            .unwrap();
+        backend.write("/dir/todo.txt", b"Buy milk").await.unwrap();
+        indexer
+            .index_directory(&backend, "/dir", true)
+            .await
+            .unwrap();
+        // Mark the stored embedding so a re-embedded chunk would show. The
+        // memory backend lists paths without a leading slash.
+        chroma
+            .docs
+            .lock()
+            .unwrap()
+            .values_mut()
+            .find(|doc| source_path(doc) == Some("dir/notes.txt"))
+            .unwrap()
+            .embedding = Some(vec![42.0]);
+
+        backend.delete("/dir/notes.txt").await.unwrap();
+        backend
+            .write("/dir/archive/notes.txt", b"Meeting notes")
+            .await
+            .unwrap();
+        backend.delete("/dir/todo.txt").await.unwrap();
+        backend.write("/dir/todo.md", b"Buy eggs").await.unwrap();
+        let result = indexer
+            .index_directory(&backend, "/dir", true)
+            .await
+            .unwrap();
+
+        assert_eq!(result.renamed_files, 1);
+        assert_eq!(result.chunks_reused, 1);
+        assert_eq!((result.new_files, result.deleted_files), (1, 1));
+        assert_eq!(result.total_chunks, 1);
+        let docs = chroma.docs.lock().unwrap();
+        assert_eq!(docs.len(), 2);
+        let moved = docs
+            .values()
+            .find(|doc| source_path(doc) == Some("dir/archive/notes.txt"))
+            .unwrap();
+        assert_eq!(moved.embedding, Some(vec![42.0]));
+        assert_eq!(
+            moved.metadata.as_ref().unwrap()["paths"],
+            serde_json::json!(["dir/archive/notes.txt"])
+        );
+        assert!(indexer.state().files.contains_key("dir/archive/notes.txt"));
+        assert!(!indexer.state().files.contains_key("dir/notes.txt"));
+    }
+
     #[tokio::test]
     async fn test_handle_change_create() {
         let tmp = TempDir::new().unwrap();
@@ -324,4 +575,82 @@ mod tests {
             .unwrap();
         assert_eq!(indexer.state().file_count(), 0);
     }
+
+    #[tokio::test]
+    async fn test_handle_change_delete_shared_chunk() {
+        let tmp = TempDir::new().unwrap();
+        let chroma = Arc::new(MemoryChroma::default());
+        let pipeline = IndexingPipeline::new(PipelineConfig::default())
+            .unwrap()
+            .with_chroma(chroma.clone() as Arc<dyn ChromaStore>);
+        let mut indexer =
+            IncrementalIndexer::with_pipeline(pipeline, &tmp.path().join("state.json"));
+        let paths = |chroma: &MemoryChroma| -> Vec<serde_json::Value> {
+            let docs = chroma.docs.lock().unwrap();
+            docs.values()
+                .map(|doc| doc.metadata.as_ref().unwrap()["paths"].clone())
+                .collect()
+        };
+
+        let backend = MemoryBackend::new();
+        backend.write("/a.txt", b"Shared content").await.unwrap();
+        backend.write("/b.txt", b"Shared content").await.unwrap();
+        for path in ["/a.txt", "/b.txt"] {
+            indexer.handle_change(&backend, path, false).await.unwrap();
+        }
+        assert_eq!(
+            paths(&chroma),
+            vec![serde_json::json!(["/a.txt", "/b.txt"])]
+        );
+
+        // Deleting one copy keeps the chunk for the other
+        indexer
+            .handle_change(&backend, "/a.txt", true)
+            .await
+            .unwrap();
+        assert_eq!(paths(&chroma), vec![serde_json::json!(["/b.txt"])]);
+        let docs = chroma.docs.lock().unwrap().clone();
+        assert_eq!(source_path(docs.values().next().unwrap()), Some("/b.txt"));
+
+        indexer
+            .handle_change(&backend, "/b.txt", true)
+            .await
+            .unwrap();
+        assert!(chroma.docs.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_index_directory_shared_chunk_concurrently() {
+        let chroma = Arc::new(MemoryChroma::default());
+        let config = PipelineConfig {
+            concurrency: 4,
+            ..Default::default()
+        };
+        let pipeline = IndexingPipeline::new(config)
+            .unwrap()
+            .with_chroma(chroma.clone() as Arc<dyn ChromaStore>);
+
+        let backend = MemoryBackend::new();
+        let files = ["/dir/a.txt", "/dir/b.txt", "/dir/c.txt", "/dir/d.txt"];
+        for path in files {
+            backend.write(path, b"Shared content").await.unwrap();
+        }
+        pipeline
+            .index_directory(&backend, "/dir", true)
+            .await
+            .unwrap();
+
+        let docs = chroma.docs.lock().unwrap().clone();
+        assert_eq!(docs.len(), 1);
+        let doc = docs.values().next().unwrap();
+        let mut paths: Vec<&str> = doc.metadata.as_ref().unwrap()["paths"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p.as_str())
+            .collect();
+        paths.sort();
+        // Paths are as the backend lists them
+        assert_eq!(paths, ["dir/a.txt", "dir/b.txt", "dir/c.txt", "dir/d.txt"]);
+    }
 }
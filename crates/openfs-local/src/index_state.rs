@@ -15,6 +15,12 @@ pub struct IndexState {
     pub version: String,
     /// When the state was last updated.
     pub last_updated: DateTime<Utc>,
+    /// Index schema (see [`PipelineConfig::schema_version`]) that files are
+    /// recorded under.
+    ///
+    /// [`PipelineConfig::schema_version`]: crate::PipelineConfig::schema_version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
     /// Per-file tracking data.
     pub files: HashMap<String, FileState>,
 }
@@ -33,6 +39,10 @@ pub struct FileState {
     /// BLAKE3 content hash (hex) -- used for deduplication.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub content_hash: Option<String>,
+    /// Index schema the file's chunks were written with; `None` for files
+    /// indexed before schemas were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
 }
 
 /// The result of computing a delta between current files and the index state.
@@ -87,6 +97,7 @@ impl IndexState {
         IndexState {
             version: "1".to_string(),
             last_updated: Utc::now(),
+            schema_version: None,
             files: HashMap::new(),
         }
     }
@@ -235,6 +246,7 @@ impl IndexState {
                 chunks,
                 indexed_at: now,
                 content_hash: None,
+                schema_version: self.schema_version.clone(),
             },
         );
         self.last_updated = now;
@@ -258,6 +270,7 @@ impl IndexState {
                 chunks,
                 indexed_at: now,
                 content_hash: Some(content_hash),
+                schema_version: self.schema_version.clone(),
             },
         );
         self.last_updated = now;
@@ -273,6 +286,60 @@ impl IndexState {
             .unwrap_or(false)
     }
 
+    /// Deleted files that a new file with the same content may have been
+    /// renamed from, keyed by content hash.
+    ///
+    /// Only files with a recorded hash, indexed under the current schema,
+    /// qualify; their chunks can be moved instead of re-embedded.
+    pub fn rename_candidates(&self, deleted_files: &[String]) -> HashMap<String, String> {
+        deleted_files
+            .iter()
+            .filter_map(|path| {
+                let state = self.files.get(path)?;
+                if state.schema_version != self.schema_version {
+                    return None;
+                }
+                Some((state.content_hash.clone()?, path.clone()))
+            })
+            .collect()
+    }
+
+    /// Move a file's state from `from` to `to`, keeping its chunk count,
+    /// content hash and schema.
+    pub fn record_renamed(
+        &mut self,
+        from: &str,
+        to: &str,
+        size: u64,
+        mtime: Option<DateTime<Utc>>,
+    ) {
+        if let Some(mut state) = self.files.remove(from) {
+            let now = Utc::now();
+            state.size = size;
+            state.mtime = mtime;
+            state.indexed_at = now;
+            self.files.insert(to.to_string(), state);
+            self.last_updated = now;
+        }
+    }
+
+    /// Record files indexed from now on under `schema_version`.
+    pub fn set_schema_version(&mut self, schema_version: &str) {
+        self.schema_version = Some(schema_version.to_string());
+    }
+
+    /// Files indexed under a schema other than `schema_version`, sorted.
+    pub fn stale_files(&self, schema_version: &str) -> Vec<String> {
+        let mut stale: Vec<String> = self
+            .files
+            .iter()
+            .filter(|(_, state)| state.schema_version.as_deref() != Some(schema_version))
+            .map(|(path, _)| path.clone())
+            .collect();
+        stale.sort();
+        stale
+    }
+
     /// Remove a file from the state (e.g., when deleted).
     pub fn remove_file(&mut self, path: &str) {
         self.files.remove(path);
@@ -387,6 +454,39 @@ mod tests {
         assert!(delta.unchanged_files.is_empty());
     }
 
+    #[test]
+    fn test_stale_files() {
+        let mut state = IndexState::new();
+        state.record_indexed("/legacy.txt", 100, None, 1);
+        state.set_schema_version("1-aaa");
+        state.record_indexed("/old.txt", 100, None, 1);
+        state.set_schema_version("1-bbb");
+        state.record_indexed("/current.txt", 100, None, 1);
+
+        assert_eq!(state.stale_files("1-bbb"), ["/legacy.txt", "/old.txt"]);
+        assert!(IndexState::new().stale_files("1-bbb").is_empty());
+    }
+
+    #[test]
+    fn test_rename_candidates() {
+        let mut state = IndexState::new();
+        state.record_indexed_with_hash("/legacy.txt", 5, None, 1, "aaa".to_string());
+        state.set_schema_version("1-bbb");
+        state.record_indexed_with_hash("/old.txt", 5, None, 2, "bbb".to_string());
+        state.record_indexed("/unhashed.txt", 5, None, 1);
+
+        let deleted = ["/legacy.txt", "/old.txt", "/unhashed.txt"].map(String::from);
+        let candidates = state.rename_candidates(&deleted);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates["bbb"], "/old.txt");
+
+        state.record_renamed("/old.txt", "/new.txt", 6, None);
+        assert!(!state.files.contains_key("/old.txt"));
+        let moved = &state.files["/new.txt"];
+        assert_eq!((moved.size, moved.chunks), (6, 2));
+        assert_eq!(moved.content_hash.as_deref(), Some("bbb"));
+    }
+
     #[test]
     fn test_default_path() {
         let path = IndexState::default_path(Path::new("/workspace"));
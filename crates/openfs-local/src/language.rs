@@ -0,0 +1,204 @@
+//! Per-file language detection.
+//!
+//! Source files are identified by extension, or by their shebang line when
+//! the extension says nothing. Prose files (Markdown, plain text and
+//! similar) are identified by the natural language they are written in,
+//! from counts of common function words. The result is stored with each
+//! chunk as `language` metadata, and picks the chunker in
+//! [`PipelineConfig::language_chunkers`](crate::PipelineConfig::language_chunkers).
+
+/// Extensions of prose files, whose natural language is detected instead.
+const PROSE_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc", "org", "text"];
+
+/// Frequent function words per natural language. Words shared between
+/// languages (such as "la") are left out so each hit is unambiguous.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "english",
+        &[
+            "the", "and", "of", "to", "is", "that", "it", "with", "for", "this", "are", "be",
+        ],
+    ),
+    (
+        "spanish",
+        &[
+            "el", "los", "las", "que", "y", "del", "por", "con", "para", "una", "es", "lo",
+        ],
+    ),
+    (
+        "french",
+        &[
+            "le", "les", "et", "des", "est", "une", "du", "pour", "dans", "qui", "sur", "pas",
+        ],
+    ),
+    (
+        "german",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "zu", "mit", "den", "sich", "auch",
+        ],
+    ),
+    (
+        "portuguese",
+        &[
+            "os", "não", "um", "uma", "em", "do", "da", "com", "para", "são", "mas", "como",
+        ],
+    ),
+];
+
+/// Fewest function-word hits before a natural language is reported.
+const MIN_STOPWORD_HITS: usize = 5;
+
+/// Detect the language of a file from its path and extracted text.
+///
+/// Returns a lowercase name such as `rust`, `python` or `english`, or `None`
+/// when nothing is recognized.
+pub fn detect_language(path: &str, text: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+
+    if let Some(language) = language_for_extension(&ext) {
+        return Some(language);
+    }
+    if let Some(language) = language_for_filename(name) {
+        return Some(language);
+    }
+    if let Some(language) = text.lines().next().and_then(language_for_shebang) {
+        return Some(language);
+    }
+    if ext.is_empty() || PROSE_EXTENSIONS.contains(&ext.as_str()) {
+        return natural_language(text);
+    }
+    None
+}
+
+/// Programming, markup and data languages by file extension.
+fn language_for_extension(ext: &str) -> Option<&'static str> {
+    let language = match ext {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "mts" | "cts" | "tsx" => "typescript",
+        "go" => "go",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        "clj" | "cljs" => "clojure",
+        "ex" | "exs" => "elixir",
+        "erl" => "erlang",
+        "hs" => "haskell",
+        "lua" => "lua",
+        "r" => "r",
+        "jl" => "julia",
+        "pl" | "pm" => "perl",
+        "sh" | "bash" | "zsh" => "shell",
+        "fish" => "fish",
+        "ps1" => "powershell",
+        "bat" => "batch",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" | "scss" | "sass" | "less" => "css",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "csv" | "tsv" => "csv",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Languages of well-known extensionless files.
+fn language_for_filename(name: &str) -> Option<&'static str> {
+    match name {
+        "Makefile" | "GNUmakefile" => Some("make"),
+        "Dockerfile" | "Containerfile" => Some("dockerfile"),
+        _ => None,
+    }
+}
+
+/// The interpreter named by a `#!` line.
+fn language_for_shebang(line: &str) -> Option<&'static str> {
+    let command = line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|w| !w.starts_with('-'))?;
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match interpreter {
+        "python" => Some("python"),
+        "sh" | "bash" | "zsh" | "dash" => Some("shell"),
+        "node" | "deno" | "bun" => Some("javascript"),
+        "ruby" => Some("ruby"),
+        "perl" => Some("perl"),
+        "php" => Some("php"),
+        "lua" => Some("lua"),
+        "fish" => Some("fish"),
+        _ => None,
+    }
+}
+
+/// The natural language with the most function-word hits, if it clearly
+/// leads.
+fn natural_language(text: &str) -> Option<&'static str> {
+    let mut hits = [0usize; STOPWORDS.len()];
+    for word in text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .take(2000)
+    {
+        let word = word.to_lowercase();
+        for (count, (_, stopwords)) in hits.iter_mut().zip(STOPWORDS) {
+            if stopwords.contains(&word.as_str()) {
+                *count += 1;
+            }
+        }
+    }
+
+    let (best, &best_hits) = hits.iter().enumerate().max_by_key(|(_, hits)| **hits)?;
+    let runner_up = hits
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != best)
+        .map(|(_, hits)| *hits)
+        .max()
+        .unwrap_or(0);
+    (best_hits >= MIN_STOPWORD_HITS && best_hits > runner_up * 2).then_some(STOPWORDS[best].0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("/src/main.rs", ""), Some("rust"));
+        assert_eq!(detect_language("/web/App.TSX", ""), Some("typescript"));
+        assert_eq!(detect_language("/Dockerfile", ""), Some("dockerfile"));
+        assert_eq!(
+            detect_language("/bin/deploy", "#!/usr/bin/env python3\nprint()"),
+            Some("python")
+        );
+        assert_eq!(detect_language("/run", "#!/bin/bash -e\n"), Some("shell"));
+        assert_eq!(detect_language("/data.bin", "\0\0"), None);
+
+        let english = "The cache is flushed to the backend when the mount is \
+                       unmounted, and it is safe to read from it at any time.";
+        assert_eq!(detect_language("/docs/guide.md", english), Some("english"));
+        let german = "Der Cache wird in das Backend geschrieben, und das ist \
+                      nicht nur sicher, sondern auch schnell, wenn die Datei klein ist.";
+        assert_eq!(detect_language("/docs/README", german), Some("german"));
+        assert_eq!(detect_language("/notes.txt", "todo: fix"), None);
+    }
+}
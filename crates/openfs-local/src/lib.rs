@@ -1,13 +1,19 @@
 pub mod chunkers;
 pub mod content_hash;
+pub mod dedup;
 pub mod embedders;
+pub mod expansion;
 pub mod extractors;
 pub mod incremental;
 pub mod index_state;
+pub mod language;
+pub mod loader;
 pub mod persistent_worker;
 pub mod pipeline;
+pub mod rate_limit;
 pub mod search;
 pub mod sparse;
+pub mod summarizers;
 pub mod types;
 pub mod watcher;
 pub mod work_queue;
@@ -15,17 +21,26 @@ pub mod work_queue;
 // Re-exports
 pub use chunkers::{Chunker, ChunkerConfig};
 pub use content_hash::{content_hash, content_hash_streaming};
+pub use dedup::EmbeddingCache;
 pub use embedders::{Embedder, EmbedderAdapter, EmbedderConfig};
+pub use expansion::{QueryExpander, QueryExpansionConfig};
 pub use extractors::{create_extractors, TextExtractor};
 pub use incremental::{IncrementalIndexer, IncrementalResult};
 pub use index_state::{FileInfo, IndexState, ReconcileAction, ReconcileResult};
-pub use persistent_worker::{PersistentEvent, PersistentIndexWorker};
-pub use pipeline::{IndexingPipeline, PipelineConfig};
-pub use search::{SearchConfig, SearchEngine, SearchMode};
+pub use language::detect_language;
+pub use loader::{DocOptions, Document, DocumentMetadata, LoadDocuments};
+pub use persistent_worker::{PersistentEvent, PersistentIndexWorker, ReembedMigration};
+pub use pipeline::{IndexingPipeline, PipelineConfig, ProgressCallback, INDEX_SCHEMA_VERSION};
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use search::{FusionMethod, ScoreNormalization, SearchConfig, SearchEngine, SearchMode};
 pub use sparse::SparseEncoder;
+pub use summarizers::{Summarizer, SummarizerConfig};
 pub use types::*;
 pub use watcher::{ChangeKind, FileChange, WatchEngine};
-pub use work_queue::{QueueEventType, QueueItem, QueueItemStatus, WorkQueue, WorkQueueConfig};
+pub use work_queue::{
+    DeadLetterItem, QueueEventType, QueueItem, QueueItemStatus, QueuePriority, WorkQueue,
+    WorkQueueConfig,
+};
 
 use thiserror::Error;
 
@@ -37,12 +52,21 @@ pub enum IndexingError {
     #[error("Embedding error: {0}")]
     EmbeddingError(String),
 
+    #[error("Summarization error: {0}")]
+    SummarizationError(String),
+
     #[error("Extraction error: {0}")]
     ExtractionError(String),
 
     #[error("HTTP error: {0}")]
     HttpError(String),
 
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<std::time::Duration>,
+    },
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -53,7 +77,13 @@ pub enum IndexingError {
     UnsupportedFileType(String),
 }
 
-#[cfg(feature = "embedder-ollama")]
+#[cfg(any(
+    feature = "embedder-ollama",
+    feature = "embedder-openai",
+    feature = "embedder-gemini",
+    feature = "summarizer-llm",
+    feature = "expansion-llm"
+))]
 impl From<reqwest::Error> for IndexingError {
     fn from(e: reqwest::Error) -> Self {
         IndexingError::HttpError(e.to_string())
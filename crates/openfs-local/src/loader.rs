@@ -0,0 +1,256 @@
+//! Loading files as documents for RAG frameworks.
+//!
+//! [`LoadDocuments::load_documents`] reads the files matching a glob and
+//! extracts and chunks their text with the same extractors and chunkers as
+//! the indexing pipeline, but stops before embedding: each chunk comes back
+//! with its source and position, for a framework such as LangChain or
+//! LlamaIndex to embed and store itself.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use globset::GlobBuilder;
+use openfs_core::{Entry, FileMeta, VfsError};
+use openfs_remote::Vfs;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::chunkers::create_chunker;
+use crate::extractors::create_extractors;
+use crate::ChunkerConfig;
+
+/// Options for [`LoadDocuments::load_documents`].
+#[derive(Debug, Clone)]
+pub struct DocOptions {
+    /// Chunking strategy (fixed, recursive, semantic). `None` returns each
+    /// file as a single document.
+    pub chunker_strategy: Option<String>,
+    /// Chunker configuration.
+    pub chunker: ChunkerConfig,
+    /// Skip files larger than this many bytes.
+    pub max_file_size: Option<u64>,
+}
+
+impl Default for DocOptions {
+    fn default() -> Self {
+        DocOptions {
+            chunker_strategy: Some("fixed".to_string()),
+            chunker: ChunkerConfig::default(),
+            max_file_size: None,
+        }
+    }
+}
+
+/// A chunk of a file's text with metadata about where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Document {
+    /// The text of the chunk.
+    pub text: String,
+    pub metadata: DocumentMetadata,
+}
+
+/// Metadata of a [`Document`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentMetadata {
+    /// VFS path of the file.
+    pub source: String,
+    /// Chunk index within the file (0-indexed).
+    pub chunk_index: usize,
+    /// Total number of chunks from the file.
+    pub total_chunks: usize,
+    /// Start line number (1-indexed).
+    pub start_line: usize,
+    /// End line number (1-indexed).
+    pub end_line: usize,
+    /// Last modification time of the file.
+    pub modified: Option<DateTime<Utc>>,
+    /// MIME type of the file, where known.
+    pub content_type: Option<String>,
+    /// The file's user metadata.
+    pub meta: FileMeta,
+}
+
+/// Load files as text documents, for frameworks that embed them themselves.
+#[async_trait]
+pub trait LoadDocuments {
+    /// Load the files matching `pattern` (e.g. `/workspace/docs/**/*.md`, or
+    /// a path, which loads the file or everything below the directory).
+    ///
+    /// Files no extractor supports, such as binaries, are skipped.
+    async fn load_documents(
+        &self,
+        pattern: &str,
+        options: &DocOptions,
+    ) -> Result<Vec<Document>, VfsError>;
+}
+
+#[async_trait]
+impl LoadDocuments for Vfs {
+    async fn load_documents(
+        &self,
+        pattern: &str,
+        options: &DocOptions,
+    ) -> Result<Vec<Document>, VfsError> {
+        let files = matching_files(self, pattern).await?;
+        let chunker = options
+            .chunker_strategy
+            .as_deref()
+            .map(|strategy| create_chunker(strategy, options.chunker.clone()))
+            .transpose()
+            .map_err(|e| VfsError::Config(format!("Failed to create chunker: {}", e)))?;
+        let extractors = create_extractors();
+
+        let mut documents = Vec::new();
+        for entry in files {
+            if options
+                .max_file_size
+                .is_some_and(|max| entry.size.unwrap_or(0) > max)
+            {
+                debug!("Skipping {}: larger than max_file_size", entry.path);
+                continue;
+            }
+            let Some(extractor) = extractors.iter().find(|e| e.supports(&entry.path)) else {
+                debug!("Skipping unsupported file: {}", entry.path);
+                continue;
+            };
+            let content = self.read(&entry.path).await?;
+            let text = match extractor.extract(&content, &entry.path).await {
+                Ok(text) => text,
+                Err(e) => {
+                    debug!("Skipping {}: {}", entry.path, e);
+                    continue;
+                }
+            };
+            let meta = self.list_meta(&entry.path).await.unwrap_or_default();
+            let metadata = |chunk_index, total_chunks, start_line, end_line| DocumentMetadata {
+                source: entry.path.clone(),
+                chunk_index,
+                total_chunks,
+                start_line,
+                end_line,
+                modified: entry.modified,
+                content_type: entry.content_type.clone(),
+                meta: meta.clone(),
+            };
+
+            match &chunker {
+                Some(chunker) => {
+                    let chunks = chunker
+                        .chunk(&text, &entry.path)
+                        .await
+                        .map_err(|e| VfsError::Indexing(e.to_string()))?;
+                    for chunk in chunks {
+                        documents.push(Document {
+                            metadata: metadata(
+                                chunk.chunk_index,
+                                chunk.total_chunks,
+                                chunk.start_line,
+                                chunk.end_line,
+                            ),
+                            text: chunk.content,
+                        });
+                    }
+                }
+                None => documents.push(Document {
+                    metadata: metadata(0, 1, 1, text.lines().count().max(1)),
+                    text,
+                }),
+            }
+        }
+        Ok(documents)
+    }
+}
+
+/// The files matching `pattern`, sorted by path.
+async fn matching_files(vfs: &Vfs, pattern: &str) -> Result<Vec<Entry>, VfsError> {
+    let Some(wildcard) = pattern.find(['*', '?', '[', '{']) else {
+        let entry = vfs.stat(pattern).await?;
+        if !entry.is_dir {
+            return Ok(vec![Entry {
+                path: pattern.to_string(),
+                ..entry
+            }]);
+        }
+        let mut files = vfs.walk(pattern).await?;
+        files.retain(|e| !e.is_dir);
+        return Ok(files);
+    };
+
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| VfsError::InvalidArgument(format!("Invalid glob '{}': {}", pattern, e)))?
+        .compile_matcher();
+    let root = match pattern[..wildcard].rfind('/') {
+        Some(0) | None => "/",
+        Some(slash) => &pattern[..slash],
+    };
+    let mut files = vfs.walk(root).await?;
+    files.retain(|e| !e.is_dir && matcher.is_match(&e.path));
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+
+    async fn memory_vfs() -> Vfs {
+        let config = VfsConfig::from_yaml(
+            r#"
+name: test
+backends:
+  mem:
+    type: memory
+mounts:
+  - path: /workspace
+    backend: mem
+"#,
+        )
+        .unwrap();
+        Vfs::from_config(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_load_documents() {
+        let vfs = memory_vfs().await;
+        vfs.write("/workspace/docs/a.md", b"# A\n\nAlpha.")
+            .await
+            .unwrap();
+        vfs.write("/workspace/docs/deep/b.md", b"Beta.")
+            .await
+            .unwrap();
+        vfs.write("/workspace/docs/c.txt", b"Gamma.").await.unwrap();
+        vfs.write("/workspace/docs/d.bin", &[0, 159, 146, 150])
+            .await
+            .unwrap();
+
+        let whole_files = DocOptions {
+            chunker_strategy: None,
+            ..Default::default()
+        };
+        let docs = vfs
+            .load_documents("/workspace/docs/**/*.md", &whole_files)
+            .await
+            .unwrap();
+        let sources: Vec<&str> = docs.iter().map(|d| d.metadata.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            ["/workspace/docs/a.md", "/workspace/docs/deep/b.md"]
+        );
+        assert_eq!(docs[0].text, "# A\n\nAlpha.");
+        assert_eq!(docs[0].metadata.end_line, 3);
+
+        let docs = vfs
+            .load_documents("/workspace/docs/*", &whole_files)
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 2, "binary and nested files are skipped");
+
+        let chunked = vfs
+            .load_documents("/workspace/docs", &DocOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(chunked.len(), 3);
+        assert!(chunked.iter().all(|d| d.metadata.total_chunks == 1));
+    }
+}
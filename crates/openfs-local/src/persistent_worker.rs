@@ -2,8 +2,12 @@
 //!
 //! Drop-in replacement for `IndexWorker` that survives process crashes,
 //! provides retry with exponential backoff, and has a dead letter queue.
+//!
+//! The worker can also run a [`ReembedMigration`], re-embedding a mount into
+//! a new collection at a fixed rate after the index schema changed.
 
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -11,15 +15,24 @@ use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 use crate::incremental::IncrementalIndexer;
-use crate::pipeline::PipelineConfig;
-use crate::work_queue::{QueueEventType, QueueItem, WorkQueue, WorkQueueConfig};
-use openfs_core::{Backend, VfsError};
+use crate::pipeline::{delete_path_chunks, PipelineConfig, SharedChunkLocks};
+use crate::work_queue::{QueueEventType, QueueItem, QueuePriority, WorkQueue, WorkQueueConfig};
+use openfs_core::{Backend, ChromaStore, VfsError, ACTIVE_COLLECTION_KEY};
 
 /// Events that can be sent to the persistent worker.
 #[derive(Debug)]
 pub enum PersistentEvent {
-    FileChanged { path: String },
-    FileDeleted { path: String },
+    FileChanged {
+        path: String,
+    },
+    FileDeleted {
+        path: String,
+    },
+    /// Incrementally re-index a directory, e.g. to catch up on changes made
+    /// while the worker was not running.
+    Rescan {
+        path: String,
+    },
     Shutdown,
 }
 
@@ -35,8 +48,16 @@ impl SyncQueue {
         }
     }
 
-    fn enqueue(&self, path: &str, event_type: QueueEventType) -> Result<(), String> {
-        self.inner.lock().unwrap().enqueue(path, event_type)
+    fn enqueue(
+        &self,
+        path: &str,
+        event_type: QueueEventType,
+        priority: QueuePriority,
+    ) -> Result<(), String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .enqueue_with_priority(path, event_type, priority)
     }
 
     fn fetch_ready(&self, batch_size: usize) -> Result<Vec<QueueItem>, String> {
@@ -61,6 +82,73 @@ impl SyncQueue {
 unsafe impl Send for SyncQueue {}
 unsafe impl Sync for SyncQueue {}
 
+/// A managed re-embedding of a directory into a new collection, for when the
+/// index schema changed, for example after switching embedding models.
+///
+/// Files are re-embedded at a fixed rate. Until all of them are done,
+/// searches keep using the old collection: changed files are indexed into
+/// the new collection only, while deletions reach both. Then the logical
+/// collection's [`ACTIVE_COLLECTION_KEY`] metadata is pointed at the new
+/// collection, which clients follow from then on.
+pub struct ReembedMigration {
+    /// Indexes into the new collection with the new settings. Its index state
+    /// records the migration's progress, so a restarted worker resumes it.
+    target: IncrementalIndexer,
+    state: ReembedState,
+}
+
+/// What the worker keeps of a [`ReembedMigration`] besides its indexer.
+struct ReembedState {
+    /// The collection searches use until the swap.
+    source: Arc<dyn ChromaStore>,
+    /// The collection clients connect to, whose metadata names the active one.
+    logical: Arc<dyn ChromaStore>,
+    /// Directory re-embedded.
+    root: String,
+    /// Where the target's index state is kept once it is active.
+    state_path: PathBuf,
+    /// Files re-embedded per second.
+    rate: f64,
+    /// Files still to re-embed.
+    pending: BTreeSet<String>,
+    /// Files re-embedded so far in this run.
+    done: usize,
+}
+
+impl ReembedMigration {
+    /// Re-embed `root` with `target`, whose pipeline must write to the new
+    /// collection. `source` is the collection being replaced and `logical`
+    /// the one clients connect to (the same as `source` before a first
+    /// migration). Once complete the target's index state moves to
+    /// `state_path`.
+    pub fn new(
+        target: IncrementalIndexer,
+        source: Arc<dyn ChromaStore>,
+        logical: Arc<dyn ChromaStore>,
+        root: &str,
+        state_path: &Path,
+    ) -> Self {
+        ReembedMigration {
+            target,
+            state: ReembedState {
+                source,
+                logical,
+                root: root.to_string(),
+                state_path: state_path.to_path_buf(),
+                rate: 1.0,
+                pending: BTreeSet::new(),
+                done: 0,
+            },
+        }
+    }
+
+    /// Re-embed at most `rate` files per second (default 1).
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.state.rate = rate;
+        self
+    }
+}
+
 /// A persistent background worker for indexing, backed by SQLite.
 pub struct PersistentIndexWorker {
     sender: mpsc::Sender<PersistentEvent>,
@@ -79,6 +167,72 @@ impl PersistentIndexWorker {
         queue_config: WorkQueueConfig,
         buffer_size: usize,
     ) -> Result<(Self, tokio::task::JoinHandle<()>), VfsError>
+    where
+        B: Backend + 'static,
+    {
+        let indexer = IncrementalIndexer::new(config, &state_path)?;
+        Self::spawn_with_indexer(backend, indexer, queue_path, queue_config, buffer_size)
+    }
+
+    /// Spawn a worker around an existing incremental indexer.
+    pub fn spawn_with_indexer<B>(
+        backend: Arc<B>,
+        indexer: IncrementalIndexer,
+        queue_path: PathBuf,
+        queue_config: WorkQueueConfig,
+        buffer_size: usize,
+    ) -> Result<(Self, tokio::task::JoinHandle<()>), VfsError>
+    where
+        B: Backend + 'static,
+    {
+        Self::spawn_inner(
+            backend,
+            indexer,
+            None,
+            queue_path,
+            queue_config,
+            buffer_size,
+        )
+    }
+
+    /// Spawn a worker that runs `migration`, then carries on indexing into
+    /// the new collection.
+    ///
+    /// Watched changes are indexed as usual throughout; only the backfill of
+    /// unchanged files is throttled.
+    pub fn spawn_with_reembed<B>(
+        backend: Arc<B>,
+        migration: ReembedMigration,
+        queue_path: PathBuf,
+        queue_config: WorkQueueConfig,
+        buffer_size: usize,
+    ) -> Result<(Self, tokio::task::JoinHandle<()>), VfsError>
+    where
+        B: Backend + 'static,
+    {
+        if migration.target.pipeline().chroma().is_none() {
+            return Err(VfsError::Indexing(
+                "Re-embedding needs a pipeline with a Chroma store".to_string(),
+            ));
+        }
+        Self::spawn_inner(
+            backend,
+            migration.target,
+            Some(migration.state),
+            queue_path,
+            queue_config,
+            buffer_size,
+        )
+    }
+
+    fn spawn_inner<B>(
+        backend: Arc<B>,
+        indexer: IncrementalIndexer,
+        reembed: Option<ReembedState>,
+        queue_path: PathBuf,
+        queue_config: WorkQueueConfig,
+        buffer_size: usize,
+    ) -> Result<(Self, tokio::task::JoinHandle<()>), VfsError>
     where
         B: Backend + 'static,
     {
@@ -94,9 +248,7 @@ impl PersistentIndexWorker {
         }
 
         let (tx, rx) = mpsc::channel(buffer_size);
-        let indexer = IncrementalIndexer::new(config, &state_path)?;
-
-        let handle = tokio::spawn(Self::run(rx, indexer, backend, queue));
+        let handle = tokio::spawn(Self::run(rx, indexer, backend, queue, reembed));
 
         Ok((PersistentIndexWorker { sender: tx }, handle))
     }
@@ -119,30 +271,60 @@ impl PersistentIndexWorker {
         mut indexer: IncrementalIndexer,
         backend: Arc<B>,
         queue: Arc<SyncQueue>,
+        mut reembed: Option<ReembedState>,
     ) where
         B: Backend + 'static,
     {
         info!("Persistent index worker started");
 
         let mut process_interval = tokio::time::interval(Duration::from_secs(1));
+        let reembed_period = reembed.as_ref().map_or(Duration::from_secs(1), |state| {
+            Duration::from_secs_f64(1.0 / state.rate)
+        });
+        let mut reembed_interval = tokio::time::interval(reembed_period);
+        if let Some(state) = &mut reembed {
+            info!(
+                "Re-embedding {} into collection {} at {} files/s",
+                state.root,
+                indexer
+                    .pipeline()
+                    .chroma()
+                    .map_or("", |chroma| chroma.collection_name()),
+                state.rate
+            );
+            Self::enqueue_rescan(
+                &queue,
+                &indexer,
+                &backend,
+                &state.root,
+                Some(&mut state.pending),
+            )
+            .await;
+        }
 
         loop {
             tokio::select! {
                 Some(event) = rx.recv() => {
                     match event {
                         PersistentEvent::FileChanged { path } => {
-                            if let Err(e) = queue.enqueue(&path, QueueEventType::Changed) {
+                            if let Err(e) = queue.enqueue(&path, QueueEventType::Changed, QueuePriority::Watcher) {
                                 error!("Failed to enqueue change for {}: {}", path, e);
                             }
                         }
                         PersistentEvent::FileDeleted { path } => {
-                            if let Err(e) = queue.enqueue(&path, QueueEventType::Deleted) {
+                            if let Err(e) = queue.enqueue(&path, QueueEventType::Deleted, QueuePriority::Watcher) {
                                 error!("Failed to enqueue delete for {}: {}", path, e);
                             }
                         }
+                        PersistentEvent::Rescan { path } => {
+                            let pending = reembed.as_mut().map(|state| &mut state.pending);
+                            Self::enqueue_rescan(&queue, &indexer, &backend, &path, pending).await;
+                        }
                         PersistentEvent::Shutdown => {
-                            info!("Persistent worker shutting down");
-                            Self::process_batch(&queue, &mut indexer, &backend).await;
+                            info!("Persistent worker shutting down, draining ready items");
+                            // Items still inside their debounce/backoff window stay
+                            // in the queue and are picked up on the next start.
+                            while Self::process_batch(&queue, &mut indexer, &backend, &mut reembed).await > 0 {}
                             if let Err(e) = indexer.persist_state() {
                                 error!("Failed to persist index state: {}", e);
                             }
@@ -151,7 +333,14 @@ impl PersistentIndexWorker {
                     }
                 }
                 _ = process_interval.tick() => {
-                    Self::process_batch(&queue, &mut indexer, &backend).await;
+                    Self::process_batch(&queue, &mut indexer, &backend, &mut reembed).await;
+                }
+                _ = reembed_interval.tick(), if reembed.is_some() => {
+                    if let Some(state) = &mut reembed {
+                        if Self::reembed_next(&queue, &mut indexer, &backend, state).await {
+                            reembed = None;
+                        }
+                    }
                 }
             }
         }
@@ -163,20 +352,73 @@ impl PersistentIndexWorker {
         info!("Persistent index worker stopped");
     }
 
+    /// Queue the files under `path` that differ from the index state. During
+    /// a migration new and modified files go to its throttled `pending` set
+    /// instead.
+    async fn enqueue_rescan<B>(
+        queue: &Arc<SyncQueue>,
+        indexer: &IncrementalIndexer,
+        backend: &Arc<B>,
+        path: &str,
+        pending: Option<&mut BTreeSet<String>>,
+    ) where
+        B: Backend + 'static,
+    {
+        let delta = match indexer.compute_delta(backend.as_ref(), path, true).await {
+            Ok(delta) => delta,
+            Err(e) => {
+                error!("Failed to rescan {}: {}", path, e);
+                return;
+            }
+        };
+
+        let changed = delta.new_files.iter().chain(delta.modified_files.iter());
+        let changed: Vec<_> = match pending {
+            Some(pending) => {
+                pending.extend(changed.cloned());
+                Vec::new()
+            }
+            None => changed
+                .map(|file| (file, QueueEventType::Changed))
+                .collect(),
+        };
+        let deleted = delta
+            .deleted_files
+            .iter()
+            .map(|file| (file, QueueEventType::Deleted));
+        for (file, event_type) in changed.into_iter().chain(deleted) {
+            if let Err(e) = queue.enqueue(file, event_type, QueuePriority::Backfill) {
+                error!("Failed to enqueue backfill for {}: {}", file, e);
+            }
+        }
+
+        info!(
+            "Rescan of {} queued {} new, {} modified, {} deleted files",
+            path,
+            delta.new_files.len(),
+            delta.modified_files.len(),
+            delta.deleted_files.len()
+        );
+    }
+
+    /// Process one batch of ready items, returning how many were fetched.
     async fn process_batch<B>(
         queue: &Arc<SyncQueue>,
         indexer: &mut IncrementalIndexer,
         backend: &Arc<B>,
-    ) where
+        reembed: &mut Option<ReembedState>,
+    ) -> usize
+    where
         B: Backend + 'static,
     {
         let items = match queue.fetch_ready(32) {
             Ok(items) => items,
             Err(e) => {
                 error!("Failed to fetch ready items: {}", e);
-                return;
+                return 0;
             }
         };
+        let fetched = items.len();
 
         for item in items {
             let deleted = item.event_type == QueueEventType::Deleted;
@@ -186,6 +428,23 @@ impl PersistentIndexWorker {
                 if deleted { "delete" } else { "index" }
             );
 
+            if let Some(state) = reembed {
+                state.pending.remove(&item.path);
+                if deleted {
+                    // Nothing else writes the old collection, so no other
+                    // task shares its chunk records
+                    let locks = SharedChunkLocks::default();
+                    if let Err(e) =
+                        delete_path_chunks(state.source.as_ref(), &locks, &item.path).await
+                    {
+                        warn!(
+                            "Failed to delete {} from the old collection: {}",
+                            item.path, e
+                        );
+                    }
+                }
+            }
+
             match indexer
                 .handle_change(backend.as_ref(), &item.path, deleted)
                 .await
@@ -203,15 +462,223 @@ impl PersistentIndexWorker {
                 }
             }
         }
+
+        fetched
+    }
+
+    /// Re-embed the next pending file of a migration, or swap collections
+    /// once none are left. Returns whether the migration is complete.
+    async fn reembed_next<B>(
+        queue: &Arc<SyncQueue>,
+        indexer: &mut IncrementalIndexer,
+        backend: &Arc<B>,
+        state: &mut ReembedState,
+    ) -> bool
+    where
+        B: Backend + 'static,
+    {
+        let Some(path) = state.pending.pop_first() else {
+            return match Self::swap_collection(indexer, state).await {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to swap in the re-embedded collection: {}", e);
+                    false
+                }
+            };
+        };
+
+        match indexer.handle_change(backend.as_ref(), &path, false).await {
+            Ok(()) => {
+                state.done += 1;
+                if state.done.is_multiple_of(100) {
+                    info!(
+                        "Re-embedded {} files under {}, {} to go",
+                        state.done,
+                        state.root,
+                        state.pending.len()
+                    );
+                }
+            }
+            Err(e) => {
+                // Retried through the work queue, with backoff.
+                warn!("Failed to re-embed {}: {}", path, e);
+                if let Err(qe) =
+                    queue.enqueue(&path, QueueEventType::Changed, QueuePriority::Backfill)
+                {
+                    error!("Failed to enqueue retry for {}: {}", path, qe);
+                }
+            }
+        }
+        if let Err(e) = indexer.persist_state() {
+            error!("Failed to persist index state: {}", e);
+        }
+        false
+    }
+
+    /// Point the logical collection at the re-embedded one and adopt the
+    /// migration's index state.
+    async fn swap_collection(
+        indexer: &mut IncrementalIndexer,
+        state: &ReembedState,
+    ) -> Result<(), VfsError> {
+        let target = match indexer.pipeline().chroma() {
+            Some(chroma) => chroma.collection_name().to_string(),
+            None => return Err(VfsError::Indexing("No collection to swap in".to_string())),
+        };
+        indexer.pipeline().record_schema_version().await?;
+
+        let mut metadata = state
+            .logical
+            .get_collection_metadata()
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?
+            .unwrap_or_default();
+        metadata.insert(ACTIVE_COLLECTION_KEY.to_string(), serde_json::json!(target));
+        state
+            .logical
+            .set_collection_metadata(metadata)
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?;
+        indexer.move_state(&state.state_path)?;
+
+        info!(
+            "Re-embedded {} ({} files this run); searches now use collection {} instead of {}",
+            state.root,
+            state.done,
+            target,
+            state.source.collection_name()
+        );
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+    use openfs_core::{
+        metadata_matches, BackendError, QueryResult, SparseEmbedding, StoredDocument,
+    };
     use openfs_remote::MemoryBackend;
     use tempfile::TempDir;
 
+    /// Named store keeping documents by ID, and its collection metadata.
+    struct MemoryChroma {
+        name: &'static str,
+        docs: Mutex<HashMap<String, StoredDocument>>,
+        metadata: Mutex<Option<HashMap<String, serde_json::Value>>>,
+    }
+
+    impl MemoryChroma {
+        fn new(name: &'static str) -> Self {
+            MemoryChroma {
+                name,
+                docs: Mutex::new(HashMap::new()),
+                metadata: Mutex::new(None),
+            }
+        }
+
+        fn sources(&self) -> BTreeSet<String> {
+            self.docs
+                .lock()
+                .unwrap()
+                .values()
+                .filter_map(source_path)
+                .map(str::to_string)
+                .collect()
+        }
+
+        fn metadata(&self, key: &str) -> Option<serde_json::Value> {
+            self.metadata.lock().unwrap().as_ref()?.get(key).cloned()
+        }
+    }
+
+    fn source_path(doc: &StoredDocument) -> Option<&str> {
+        doc.metadata.as_ref()?.get("source_path")?.as_str()
+    }
+
+    #[async_trait]
+    impl ChromaStore for MemoryChroma {
+        async fn upsert(
+            &self,
+            path: &str,
+            content: &str,
+            embedding: Option<Vec<f32>>,
+            sparse_embedding: Option<SparseEmbedding>,
+            metadata: Option<HashMap<String, serde_json::Value>>,
+        ) -> Result<(), BackendError> {
+            self.docs.lock().unwrap().insert(
+                path.to_string(),
+                StoredDocument {
+                    id: path.to_string(),
+                    document: Some(content.to_string()),
+                    embedding,
+                    sparse_embedding,
+                    metadata,
+                },
+            );
+            Ok(())
+        }
+
+        async fn query_by_embedding(
+            &self,
+            _embedding: Vec<f32>,
+            _n_results: usize,
+        ) -> Result<Vec<QueryResult>, BackendError> {
+            Ok(Vec::new())
+        }
+
+        async fn query_by_sparse_embedding(
+            &self,
+            _query_sparse: &SparseEmbedding,
+            _n_results: usize,
+        ) -> Result<Vec<QueryResult>, BackendError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_by_metadata(
+            &self,
+            filter: serde_json::Value,
+        ) -> Result<Vec<StoredDocument>, BackendError> {
+            let docs = self.docs.lock().unwrap();
+            Ok(docs
+                .values()
+                .filter(|doc| metadata_matches(doc.metadata.as_ref(), &filter))
+                .cloned()
+                .collect())
+        }
+
+        async fn delete_by_metadata(
+            &self,
+            filter: serde_json::Value,
+        ) -> Result<usize, BackendError> {
+            let mut docs = self.docs.lock().unwrap();
+            let before = docs.len();
+            docs.retain(|_, doc| !metadata_matches(doc.metadata.as_ref(), &filter));
+            Ok(before - docs.len())
+        }
+
+        async fn set_collection_metadata(
+            &self,
+            metadata: HashMap<String, serde_json::Value>,
+        ) -> Result<(), BackendError> {
+            *self.metadata.lock().unwrap() = Some(metadata);
+            Ok(())
+        }
+
+        async fn get_collection_metadata(
+            &self,
+        ) -> Result<Option<HashMap<String, serde_json::Value>>, BackendError> {
+            Ok(self.metadata.lock().unwrap().clone())
+        }
+
+        fn collection_name(&self) -> &str {
+            self.name
+        }
+    }
+
     #[tokio::test]
     async fn test_persistent_worker_basic() {
         let tmp = TempDir::new().unwrap();
@@ -246,4 +713,162 @@ mod tests {
         worker.shutdown().await.unwrap();
         handle.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_persistent_worker_drains_on_shutdown() {
+        let tmp = TempDir::new().unwrap();
+        let state_path = tmp.path().join(".openfs-index-state.json");
+        let queue_path = tmp.path().join("queue.db");
+
+        let backend = Arc::new(MemoryBackend::new());
+        for i in 0..40 {
+            backend
+                .write(&format!("/file{}.txt", i), b"Hello world")
+                .await
+                .unwrap();
+        }
+
+        let (worker, handle) = PersistentIndexWorker::spawn(
+            backend,
+            PipelineConfig::default(),
+            state_path.clone(),
+            queue_path.clone(),
+            WorkQueueConfig {
+                debounce_secs: 0,
+                ..Default::default()
+            },
+            64,
+        )
+        .unwrap();
+
+        for i in 0..40 {
+            worker
+                .send(PersistentEvent::FileChanged {
+                    path: format!("/file{}.txt", i),
+                })
+                .await
+                .unwrap();
+        }
+        worker.shutdown().await.unwrap();
+        handle.await.unwrap();
+
+        // More than one batch was queued; shutdown must have drained all of it.
+        let queue = WorkQueue::open(&queue_path, WorkQueueConfig::default()).unwrap();
+        assert_eq!(queue.pending_count().unwrap(), 0);
+        let state = crate::IndexState::load(&state_path).unwrap();
+        assert_eq!(state.files.len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_worker_rescan_backfills() {
+        let tmp = TempDir::new().unwrap();
+        let state_path = tmp.path().join(".openfs-index-state.json");
+        let queue_path = tmp.path().join("queue.db");
+
+        let backend = Arc::new(MemoryBackend::new());
+        backend.write("/docs/a.txt", b"Alpha").await.unwrap();
+        backend.write("/docs/b.txt", b"Beta").await.unwrap();
+
+        let (worker, handle) = PersistentIndexWorker::spawn(
+            backend,
+            PipelineConfig::default(),
+            state_path.clone(),
+            queue_path,
+            WorkQueueConfig {
+                debounce_secs: 0,
+                ..Default::default()
+            },
+            16,
+        )
+        .unwrap();
+
+        worker
+            .send(PersistentEvent::Rescan {
+                path: "/docs".to_string(),
+            })
+            .await
+            .unwrap();
+        worker.shutdown().await.unwrap();
+        handle.await.unwrap();
+
+        let state = crate::IndexState::load(&state_path).unwrap();
+        assert_eq!(state.files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_worker_reembed_swaps_collection() {
+        let tmp = TempDir::new().unwrap();
+        let state_path = tmp.path().join(".openfs-index-state.json");
+        let reembed_state_path = tmp.path().join("reembed.state.json");
+        let queue_path = tmp.path().join("queue.db");
+
+        let backend = Arc::new(MemoryBackend::new());
+        backend.write("/docs/a.txt", b"Alpha").await.unwrap();
+        backend.write("/docs/b.txt", b"Beta").await.unwrap();
+
+        // The old collection holds vectors from the previous embedder.
+        let old = Arc::new(MemoryChroma::new("old"));
+        for path in ["/docs/a.txt", "/docs/gone.txt"] {
+            let metadata = HashMap::from([("source_path".to_string(), serde_json::json!(path))]);
+            old.upsert(path, "old", Some(vec![0.0]), None, Some(metadata))
+                .await
+                .unwrap();
+        }
+        let new = Arc::new(MemoryChroma::new("new"));
+
+        let pipeline = crate::IndexingPipeline::new(PipelineConfig::default())
+            .unwrap()
+            .with_chroma(Arc::clone(&new) as Arc<dyn ChromaStore>);
+        let migration = ReembedMigration::new(
+            IncrementalIndexer::with_pipeline(pipeline, &reembed_state_path),
+            Arc::clone(&old) as Arc<dyn ChromaStore>,
+            Arc::clone(&old) as Arc<dyn ChromaStore>,
+            "/docs",
+            &state_path,
+        )
+        .with_rate(1.0);
+        let (worker, handle) = PersistentIndexWorker::spawn_with_reembed(
+            backend,
+            migration,
+            queue_path,
+            WorkQueueConfig {
+                debounce_secs: 0,
+                ..Default::default()
+            },
+            16,
+        )
+        .unwrap();
+
+        worker
+            .send(PersistentEvent::FileDeleted {
+                path: "/docs/gone.txt".to_string(),
+            })
+            .await
+            .unwrap();
+
+        // Until the swap, searches keep the old vectors, less deleted files.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(old.metadata(ACTIVE_COLLECTION_KEY).is_none());
+        for _ in 0..50 {
+            if old.metadata(ACTIVE_COLLECTION_KEY).is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        worker.shutdown().await.unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(
+            old.metadata(ACTIVE_COLLECTION_KEY),
+            Some(serde_json::json!("new"))
+        );
+        assert_eq!(old.sources(), BTreeSet::from(["/docs/a.txt".to_string()]));
+        assert_eq!(new.sources().len(), 2);
+        assert!(new.metadata("index_schema_version").is_some());
+
+        // The migration's index state became the mount's.
+        assert!(!reembed_state_path.exists());
+        let state = crate::IndexState::load(&state_path).unwrap();
+        assert_eq!(state.files.len(), 2);
+    }
 }
@@ -1,15 +1,38 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 
+use crate::dedup::EmbeddingCache;
+use crate::rate_limit::{estimate_tokens, RateLimitConfig, RateLimiter};
 use crate::{
-    chunkers, embedders, extractors, BulkIndexResult, Chunker, ChunkerConfig, EmbeddedChunk,
-    Embedder, EmbedderConfig, PipelineResult, SparseEncoder, SparseVector, TextExtractor,
+    chunkers, content_hash, embedders, extractors, language, summarizers, BulkIndexResult, Chunker,
+    ChunkerConfig, EmbeddedChunk, Embedder, EmbedderConfig, EmbeddingResult, IndexProgress,
+    PipelineResult, SparseEncoder, SparseVector, Summarizer, SummarizerConfig, TextExtractor,
 };
-use openfs_core::{Backend, ChromaStore, SparseEmbedding, VfsError};
-use tokio::sync::RwLock;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use openfs_config::{IndexConfig, IndexFilter};
+use openfs_core::{Backend, ChromaStore, SparseEmbedding, StoredDocument, VfsError};
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock, Semaphore};
 use tracing::{debug, info, warn};
 
+/// Version of the chunk metadata layout [`IndexingPipeline::index_file`]
+/// writes. Bump it when chunk metadata fields are added, removed or renamed.
+pub const INDEX_SCHEMA_VERSION: u32 = 3;
+
+/// Collection metadata key holding the index schema chunks were written with.
+const SCHEMA_VERSION_KEY: &str = "index_schema_version";
+
+/// Chunk metadata describing the chunk's place in its `source_path`; dropped
+/// when a shared chunk record moves to another of its paths.
+const LOCATION_KEYS: [&str; 6] = [
+    "file_summary",
+    "language",
+    "start_line",
+    "end_line",
+    "chunk_index",
+    "total_chunks",
+];
+
 /// Configuration for the indexing pipeline.
 #[derive(Debug, Clone)]
 pub struct PipelineConfig {
@@ -17,6 +40,9 @@ pub struct PipelineConfig {
     pub chunker_strategy: String,
     /// Chunker configuration.
     pub chunker: ChunkerConfig,
+    /// Chunker strategy per detected language (e.g. `rust` → `ast`); files
+    /// in other languages use `chunker_strategy`.
+    pub language_chunkers: HashMap<String, String>,
     /// Embedder provider (stub, ollama, openai).
     pub embedder_provider: String,
     /// Embedder configuration.
@@ -25,6 +51,22 @@ pub struct PipelineConfig {
     pub enable_sparse: bool,
     /// Batch size for embedding operations.
     pub batch_size: usize,
+    /// Maximum number of embedding requests in flight at once, across all
+    /// files; also how many files are read and chunked at once.
+    pub concurrency: usize,
+    /// Provider rate limits and retry policy.
+    pub rate_limit: RateLimitConfig,
+    /// Reuse embeddings for chunks with identical content, and store such
+    /// chunks once with every path they appear in.
+    pub dedup_chunks: bool,
+    /// Maximum number of chunk embeddings kept for deduplication.
+    pub embedding_cache_size: usize,
+    /// Summarizer provider (extractive, llm); `None` disables summaries.
+    pub summarizer_provider: Option<String>,
+    /// Summarizer configuration.
+    pub summarizer: SummarizerConfig,
+    /// Mount index policy restricting which files are indexed.
+    pub filter: Option<IndexFilter>,
 }
 
 impl Default for PipelineConfig {
@@ -32,22 +74,302 @@ impl Default for PipelineConfig {
         PipelineConfig {
             chunker_strategy: "fixed".to_string(),
             chunker: ChunkerConfig::default(),
+            language_chunkers: HashMap::new(),
             embedder_provider: "stub".to_string(),
             embedder: EmbedderConfig::default(),
             enable_sparse: true,
             batch_size: 32,
+            concurrency: 4,
+            rate_limit: RateLimitConfig::default(),
+            dedup_chunks: true,
+            embedding_cache_size: 10_000,
+            summarizer_provider: None,
+            summarizer: SummarizerConfig::default(),
+            filter: None,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// Build a pipeline configuration from a mount's declarative `index:` section.
+    ///
+    /// Unset fields keep their defaults; the include/exclude/size policy is
+    /// compiled into [`PipelineConfig::filter`].
+    pub fn from_index_config(mount_path: &str, index: &IndexConfig) -> Result<Self, VfsError> {
+        let mut config = PipelineConfig {
+            filter: Some(IndexFilter::new(mount_path, index)?),
+            ..Default::default()
+        };
+
+        if let Some(chunk) = &index.chunk {
+            config.chunker_strategy = config_name(&chunk.strategy)?;
+            config.chunker = ChunkerConfig {
+                chunk_size: chunk.size,
+                chunk_overlap: chunk.overlap,
+                min_chunk_size: chunk.size / 10,
+            };
+            config.language_chunkers = chunk
+                .languages
+                .iter()
+                .map(|(language, strategy)| Ok((language.clone(), config_name(strategy)?)))
+                .collect::<Result<_, VfsError>>()?;
+        }
+
+        if let Some(embedding) = &index.embedding {
+            config.embedder_provider = config_name(&embedding.provider)?;
+            config.embedder.dimensions = embedding.dimensions;
+            if let Some(model) = &embedding.model {
+                config.embedder.model = model.clone();
+            }
+            if let Some(concurrency) = embedding.concurrency {
+                config.concurrency = concurrency;
+            }
+            config.rate_limit.requests_per_minute = embedding.requests_per_minute;
+            config.rate_limit.tokens_per_minute = embedding.tokens_per_minute;
+        }
+
+        Ok(config)
+    }
+
+    /// Identifies what chunks indexed with this configuration look like:
+    /// [`INDEX_SCHEMA_VERSION`] plus a hash of the chunker, embedder,
+    /// summarizer and deduplication settings. Chunks written under a different schema need
+    /// re-indexing to be consistent with new ones.
+    pub fn schema_version(&self) -> String {
+        let mut language_chunkers: Vec<_> = self
+            .language_chunkers
+            .iter()
+            .map(|(language, strategy)| format!("{}={}", language, strategy))
+            .collect();
+        language_chunkers.sort();
+        let settings = format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.chunker_strategy,
+            language_chunkers.join(","),
+            self.chunker.chunk_size,
+            self.chunker.chunk_overlap,
+            self.chunker.min_chunk_size,
+            self.embedder_provider,
+            self.embedder.model,
+            self.embedder.dimensions,
+            self.summarizer_provider.as_deref().unwrap_or("none"),
+            self.dedup_chunks,
+        );
+        format!(
+            "{}-{}",
+            INDEX_SCHEMA_VERSION,
+            &content_hash(settings.as_bytes())[..12]
+        )
+    }
+}
+
+/// String metadata field of a stored chunk.
+fn metadata_str<'a>(doc: &'a StoredDocument, key: &str) -> Option<&'a str> {
+    doc.metadata.as_ref()?.get(key)?.as_str()
+}
+
+/// The paths a shared chunk record appears in.
+fn stored_paths(doc: &StoredDocument) -> Vec<String> {
+    doc.metadata
+        .as_ref()
+        .and_then(|m| m.get("paths"))
+        .and_then(|v| v.as_array())
+        .map(|paths| {
+            paths
+                .iter()
+                .filter_map(|p| p.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Metadata for the shared record of a chunk found in `path`, merged with
+/// the record already stored for its content hash.
+///
+/// The record keeps describing its first path; `metadata` (the chunk as it
+/// appears in `path`) replaces it when the record is new, already led by
+/// `path`, or written under another schema.
+fn shared_record_metadata(
+    path: &str,
+    metadata: HashMap<String, serde_json::Value>,
+    stored: Option<&StoredDocument>,
+    schema_version: &str,
+) -> HashMap<String, serde_json::Value> {
+    let mut paths = stored.map(stored_paths).unwrap_or_default();
+    let reusable = stored.filter(|doc| {
+        metadata_str(doc, "schema_version") == Some(schema_version)
+            && paths.first().is_some_and(|first| first != path)
+    });
+    let mut metadata = match reusable.and_then(|doc| doc.metadata.clone()) {
+        Some(stored) => stored,
+        None => {
+            paths.retain(|p| p != path);
+            paths.insert(0, path.to_string());
+            metadata
         }
+    };
+    if !paths.iter().any(|p| p == path) {
+        paths.push(path.to_string());
+    }
+    metadata.insert("paths".to_string(), serde_json::json!(paths));
+    metadata
+}
+
+/// Locks serializing changes to shared chunk records, by content hash. A
+/// record's `paths` is read, changed and written back, so files sharing a
+/// chunk must not update it at the same time.
+#[derive(Default)]
+pub(crate) struct SharedChunkLocks(std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>);
+
+impl SharedChunkLocks {
+    async fn lock(&self, hash: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            // Forget the locks nobody holds or waits for
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(hash.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
     }
 }
 
+/// The shared record stored for a content hash, read afresh.
+async fn shared_record(
+    chroma: &dyn ChromaStore,
+    hash: &str,
+) -> Result<Option<StoredDocument>, VfsError> {
+    let docs = chroma
+        .get_by_metadata(serde_json::json!({"content_hash": hash}))
+        .await
+        .map_err(|e| VfsError::Backend(Box::new(e)))?;
+    let id = format!("chunk_{}", hash);
+    let shared = docs.iter().position(|doc| doc.id == id);
+    Ok(match shared {
+        Some(i) => docs.into_iter().nth(i),
+        None => docs.into_iter().next(),
+    })
+}
+
+/// Rewrite a stored chunk under `id` with new metadata, keeping its content
+/// and embeddings.
+async fn upsert_stored(
+    chroma: &dyn ChromaStore,
+    id: &str,
+    doc: &StoredDocument,
+    metadata: HashMap<String, serde_json::Value>,
+) -> Result<(), VfsError> {
+    chroma
+        .upsert(
+            id,
+            doc.document.as_deref().unwrap_or_default(),
+            doc.embedding.clone(),
+            doc.sparse_embedding.clone(),
+            Some(metadata),
+        )
+        .await
+        .map_err(|e| VfsError::Backend(Box::new(e)))
+}
+
+/// Drop `path` from the shared chunk records whose content hash is not in
+/// `keep`, deleting records no other path references.
+async fn release_shared_chunks(
+    chroma: &dyn ChromaStore,
+    locks: &SharedChunkLocks,
+    path: &str,
+    keep: &HashSet<&str>,
+) -> Result<(), VfsError> {
+    let docs = chroma
+        .get_by_metadata(serde_json::json!({"paths": {"$contains": path}}))
+        .await
+        .map_err(|e| VfsError::Backend(Box::new(e)))?;
+    for doc in &docs {
+        let Some(hash) = metadata_str(doc, "content_hash") else {
+            continue;
+        };
+        if keep.contains(hash) {
+            continue;
+        }
+        let _guard = locks.lock(hash).await;
+        // Another file may have changed the record since the lookup above
+        let Some(doc) = shared_record(chroma, hash).await? else {
+            continue;
+        };
+        let doc = &doc;
+        let mut paths = stored_paths(doc);
+        if !paths.iter().any(|p| p == path) {
+            continue;
+        }
+        paths.retain(|p| p != path);
+        let Some(first) = paths.first() else {
+            chroma
+                .delete_by_metadata(serde_json::json!({"content_hash": hash}))
+                .await
+                .map_err(|e| VfsError::Backend(Box::new(e)))?;
+            continue;
+        };
+        if doc.embedding.is_none() {
+            return Err(VfsError::Backend(Box::new(PipelineError(format!(
+                "Stored chunk {} has no embedding",
+                doc.id
+            )))));
+        }
+        let mut metadata = doc.metadata.clone().unwrap_or_default();
+        if metadata_str(doc, "source_path") == Some(path) {
+            // The remaining paths' positions are unknown until they are re-indexed
+            for key in LOCATION_KEYS {
+                metadata.remove(key);
+            }
+            metadata.insert("source_path".to_string(), serde_json::json!(first));
+        }
+        metadata.insert("paths".to_string(), serde_json::json!(paths));
+        upsert_stored(chroma, &doc.id, doc, metadata).await?;
+    }
+    Ok(())
+}
+
+/// Remove `path`'s chunks from `chroma`.
+///
+/// Shared chunk records only lose `path` from their `paths`, and are deleted
+/// once no path references them; per-path chunks are deleted outright.
+pub(crate) async fn delete_path_chunks(
+    chroma: &dyn ChromaStore,
+    locks: &SharedChunkLocks,
+    path: &str,
+) -> Result<(), VfsError> {
+    release_shared_chunks(chroma, locks, path, &HashSet::new()).await?;
+    chroma
+        .delete_by_metadata(serde_json::json!({"source_path": path}))
+        .await
+        .map_err(|e| VfsError::Backend(Box::new(e)))?;
+    Ok(())
+}
+
+/// The snake_case config name of a serde enum value (e.g. `ChunkStrategy::Ast` → "ast").
+fn config_name<T: serde::Serialize>(value: &T) -> Result<String, VfsError> {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or_else(|| VfsError::Config("Unsupported index config value".to_string()))
+}
+
+/// Callback invoked with a progress snapshot after each file is indexed.
+pub type ProgressCallback = Arc<dyn Fn(&IndexProgress) + Send + Sync>;
+
 /// An indexing pipeline that coordinates text extraction, chunking, and embedding.
 pub struct IndexingPipeline {
     config: PipelineConfig,
     chunker: Box<dyn Chunker>,
+    language_chunkers: HashMap<String, Box<dyn Chunker>>,
     embedder: Box<dyn Embedder>,
     extractor: extractors::PlainTextExtractor,
     sparse_encoder: Arc<RwLock<SparseEncoder>>,
     chroma: Option<Arc<dyn ChromaStore>>,
+    rate_limiter: Arc<RateLimiter>,
+    embed_permits: Semaphore,
+    shared_chunk_locks: SharedChunkLocks,
+    embedding_cache: RwLock<EmbeddingCache>,
+    summarizer: Option<Box<dyn Summarizer>>,
+    progress: Option<ProgressCallback>,
 }
 
 impl IndexingPipeline {
@@ -55,19 +377,49 @@ impl IndexingPipeline {
     pub fn new(config: PipelineConfig) -> Result<Self, VfsError> {
         let chunker = chunkers::create_chunker(&config.chunker_strategy, config.chunker.clone())
             .map_err(|e| VfsError::Config(format!("Failed to create chunker: {}", e)))?;
+        let language_chunkers = config
+            .language_chunkers
+            .iter()
+            .map(|(language, strategy)| {
+                chunkers::create_chunker(strategy, config.chunker.clone())
+                    .map(|chunker| (language.to_lowercase(), chunker))
+                    .map_err(|e| {
+                        VfsError::Config(format!(
+                            "Failed to create chunker for {}: {}",
+                            language, e
+                        ))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
         let embedder =
             embedders::create_embedder(&config.embedder_provider, config.embedder.clone())
                 .map_err(|e| VfsError::Config(format!("Failed to create embedder: {}", e)))?;
+        let summarizer = config
+            .summarizer_provider
+            .as_deref()
+            .map(|provider| summarizers::create_summarizer(provider, config.summarizer.clone()))
+            .transpose()
+            .map_err(|e| VfsError::Config(format!("Failed to create summarizer: {}", e)))?;
         let extractor = extractors::PlainTextExtractor::new();
         let sparse_encoder = Arc::new(RwLock::new(SparseEncoder::new()));
+        let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.clone()));
+        let embed_permits = Semaphore::new(config.concurrency.max(1));
+        let embedding_cache = RwLock::new(EmbeddingCache::new(config.embedding_cache_size));
 
         Ok(IndexingPipeline {
             config,
             chunker,
+            language_chunkers,
             embedder,
             extractor,
             sparse_encoder,
             chroma: None,
+            rate_limiter,
+            embed_permits,
+            shared_chunk_locks: SharedChunkLocks::default(),
+            embedding_cache,
+            summarizer,
+            progress: None,
         })
     }
 
@@ -77,6 +429,47 @@ impl IndexingPipeline {
         self
     }
 
+    /// The connected Chroma backend, if any.
+    pub fn chroma(&self) -> Option<&Arc<dyn ChromaStore>> {
+        self.chroma.as_ref()
+    }
+
+    /// Report progress while indexing directories.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Whether the configured index policy allows indexing `path`.
+    ///
+    /// Always true when no policy is configured.
+    pub fn accepts(&self, path: &str, size: Option<u64>) -> bool {
+        self.config
+            .filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(path, size))
+    }
+
+    /// Get the shared rate limiter.
+    pub fn rate_limiter(&self) -> Arc<RateLimiter> {
+        Arc::clone(&self.rate_limiter)
+    }
+
+    /// Embed one batch of texts within the provider's rate limits, holding
+    /// one of the `concurrency` request permits shared by all files.
+    async fn embed_batch(&self, texts: &[&str]) -> Result<EmbeddingResult, VfsError> {
+        let _permit = self
+            .embed_permits
+            .acquire()
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?;
+        let tokens = texts.iter().map(|t| estimate_tokens(t)).sum();
+        self.rate_limiter
+            .run(tokens, || self.embedder.embed(texts))
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))
+    }
+
     /// Index a single file.
     pub async fn index_file(&self, path: &str, content: &[u8]) -> Result<PipelineResult, VfsError> {
         let start = Instant::now();
@@ -88,33 +481,135 @@ impl IndexingPipeline {
             .await
             .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?;
 
-        // Chunk the text
-        let chunks = self
-            .chunker
+        // Chunk the text with the chunker for its language, if one is set
+        let language = language::detect_language(path, &text);
+        let chunker = language
+            .and_then(|language| self.language_chunkers.get(language))
+            .unwrap_or(&self.chunker);
+        let mut chunks = chunker
             .chunk(&text, path)
             .await
             .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?;
+        if let Some(language) = language {
+            for chunk in &mut chunks {
+                chunk
+                    .metadata
+                    .insert("language".to_string(), language.to_string());
+            }
+        }
 
         debug!("Created {} chunks for {}", chunks.len(), path);
 
-        // Embed chunks in batches
-        let mut embedded_chunks = Vec::new();
-        for chunk_batch in chunks.chunks(self.config.batch_size) {
-            let texts: Vec<&str> = chunk_batch.iter().map(|c| c.content.as_str()).collect();
-            let embeddings = self
-                .embedder
-                .embed(&texts)
-                .await
-                .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?;
+        // Summarize the whole file once; the summary is stored with every chunk
+        let summary = match &self.summarizer {
+            Some(summarizer) => match summarizer.summarize(path, &text).await {
+                Ok(summary) if !summary.is_empty() => Some(summary),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Failed to summarize {}: {}", path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // Hash chunk contents so identical chunks are embedded only once
+        let hashes: Vec<String> = chunks
+            .iter()
+            .map(|c| content_hash(c.content.as_bytes()))
+            .collect();
+        let schema_version = self.config.schema_version();
+
+        // With dedup, chunks are stored once per content hash: look up the
+        // records this file's chunks already have, from this or another path
+        let mut stored: HashMap<String, StoredDocument> = HashMap::new();
+        if let Some(chroma) = self.chroma.as_ref().filter(|_| self.config.dedup_chunks) {
+            let distinct: HashSet<&str> = hashes.iter().map(String::as_str).collect();
+            if !distinct.is_empty() {
+                let filter = serde_json::json!({"content_hash": {"$in": distinct}});
+                for doc in chroma
+                    .get_by_metadata(filter)
+                    .await
+                    .map_err(|e| VfsError::Backend(Box::new(e)))?
+                {
+                    if let Some(hash) = metadata_str(&doc, "content_hash") {
+                        stored.insert(hash.to_string(), doc);
+                    }
+                }
+            }
+        }
+
+        let mut cached: Vec<Option<Vec<f32>>> = vec![None; chunks.len()];
+        let mut slot_of = vec![0usize; chunks.len()];
+        let mut unique: Vec<usize> = Vec::new();
+        {
+            let cache = self.embedding_cache.read().await;
+            let mut first_seen: HashMap<&str, usize> = HashMap::new();
+            for (idx, hash) in hashes.iter().enumerate() {
+                if self.config.dedup_chunks {
+                    let stored_embedding = stored
+                        .get(hash)
+                        .filter(|doc| metadata_str(doc, "schema_version") == Some(&schema_version))
+                        .and_then(|doc| doc.embedding.as_ref());
+                    if let Some(embedding) = cache.get(hash).or(stored_embedding) {
+                        cached[idx] = Some(embedding.clone());
+                        continue;
+                    }
+                    if let Some(&slot) = first_seen.get(hash.as_str()) {
+                        slot_of[idx] = slot;
+                        continue;
+                    }
+                    first_seen.insert(hash, unique.len());
+                }
+                slot_of[idx] = unique.len();
+                unique.push(idx);
+            }
+        }
+        let chunks_deduplicated = chunks.len() - unique.len();
+        if chunks_deduplicated > 0 {
+            debug!(
+                "Reusing embeddings for {} duplicate chunks in {}",
+                chunks_deduplicated, path
+            );
+        }
+
+        // Embed unique chunks in batches; `embed_batch` bounds the requests in flight
+        let texts: Vec<&str> = unique.iter().map(|&i| chunks[i].content.as_str()).collect();
+        let requests: Vec<_> = texts
+            .chunks(self.config.batch_size.max(1))
+            .map(|batch| self.embed_batch(batch))
+            .collect();
+        let results: Vec<EmbeddingResult> = stream::iter(requests)
+            .buffered(self.config.concurrency.max(1))
+            .try_collect()
+            .await?;
+        let fresh: Vec<Vec<f32>> = results.into_iter().flat_map(|r| r.embeddings).collect();
+        if fresh.len() != texts.len() {
+            return Err(VfsError::Backend(Box::new(PipelineError(format!(
+                "Embedder returned {} embeddings for {} chunks",
+                fresh.len(),
+                texts.len()
+            )))));
+        }
 
-            for (chunk, embedding) in chunk_batch.iter().zip(embeddings.embeddings) {
-                embedded_chunks.push(EmbeddedChunk {
-                    chunk: chunk.clone(),
-                    embedding,
-                });
+        if self.config.dedup_chunks {
+            let mut cache = self.embedding_cache.write().await;
+            for (&idx, embedding) in unique.iter().zip(&fresh) {
+                cache.insert(hashes[idx].clone(), embedding.clone());
             }
         }
 
+        let embedded_chunks: Vec<EmbeddedChunk> = chunks
+            .iter()
+            .enumerate()
+            .map(|(idx, chunk)| EmbeddedChunk {
+                chunk: chunk.clone(),
+                embedding: cached[idx]
+                    .take()
+                    .unwrap_or_else(|| fresh[slot_of[idx]].clone()),
+            })
+            .collect();
+
         // Update sparse encoder and compute sparse vectors if enabled
         let mut sparse_vectors: Vec<Option<SparseVector>> = Vec::new();
         if self.config.enable_sparse {
@@ -135,9 +630,25 @@ impl IndexingPipeline {
 
         // Store in Chroma if configured
         if let Some(chroma) = &self.chroma {
+            let mut written: HashSet<&str> = HashSet::new();
             for (idx, embedded) in embedded_chunks.iter().enumerate() {
                 let chunk = &embedded.chunk;
+                if self.config.dedup_chunks && !written.insert(&hashes[idx]) {
+                    // Stored once already for an earlier identical chunk
+                    continue;
+                }
                 let mut metadata: HashMap<String, serde_json::Value> = HashMap::new();
+                metadata.insert("content_hash".to_string(), serde_json::json!(hashes[idx]));
+                metadata.insert(
+                    "schema_version".to_string(),
+                    serde_json::json!(schema_version),
+                );
+                if let Some(summary) = &summary {
+                    metadata.insert("file_summary".to_string(), serde_json::json!(summary));
+                }
+                if let Some(language) = language {
+                    metadata.insert("language".to_string(), serde_json::json!(language));
+                }
                 metadata.insert(
                     "source_path".to_string(),
                     serde_json::json!(chunk.source_path),
@@ -156,8 +667,19 @@ impl IndexingPipeline {
                     serde_json::json!(chunk.total_chunks),
                 );
 
-                // Create a unique ID for this chunk
-                let chunk_path = format!("{}#chunk_{}", chunk.source_path, chunk.chunk_index);
+                // Shared chunks are keyed by content, others by path and position
+                let (chunk_path, metadata, _guard) = if self.config.dedup_chunks {
+                    let guard = self.shared_chunk_locks.lock(&hashes[idx]).await;
+                    // Re-read the record: another file may have added itself
+                    // since the lookup above
+                    let current = shared_record(chroma.as_ref(), &hashes[idx]).await?;
+                    let metadata =
+                        shared_record_metadata(path, metadata, current.as_ref(), &schema_version);
+                    (format!("chunk_{}", hashes[idx]), metadata, Some(guard))
+                } else {
+                    let chunk_path = format!("{}#chunk_{}", chunk.source_path, chunk.chunk_index);
+                    (chunk_path, metadata, None)
+                };
 
                 // Convert sparse vector to SparseEmbedding for Chroma
                 let sparse_embedding =
@@ -180,6 +702,11 @@ impl IndexingPipeline {
                     .await
                     .map_err(|e| VfsError::Backend(Box::new(e)))?;
             }
+            if self.config.dedup_chunks {
+                // Chunks the file no longer contains stop referencing it
+                release_shared_chunks(chroma.as_ref(), &self.shared_chunk_locks, path, &written)
+                    .await?;
+            }
             debug!(
                 "Stored {} chunks in Chroma for {}",
                 embedded_chunks.len(),
@@ -193,6 +720,8 @@ impl IndexingPipeline {
             path: path.to_string(),
             chunks_created: embedded_chunks.len(),
             chunks_deleted: 0,
+            chunks_deduplicated,
+            summary,
             duration_ms,
         })
     }
@@ -221,31 +750,41 @@ impl IndexingPipeline {
             dir_path
         );
 
-        for path in paths_to_index {
-            // Check if extractor supports this file type
-            if !self.extractor.supports(&path) {
-                debug!("Skipping unsupported file: {}", path);
-                files_skipped += 1;
-                continue;
-            }
+        let mut progress = IndexProgress {
+            files_total: paths_to_index.len(),
+            ..Default::default()
+        };
 
-            match backend.read(&path).await {
-                Ok(content) => match self.index_file(&path, &content).await {
-                    Ok(result) => {
-                        files_processed += 1;
-                        total_chunks += result.chunks_created;
-                    }
-                    Err(e) => {
-                        warn!("Failed to index {}: {}", path, e);
-                        errors.push((path, e.to_string()));
-                        files_skipped += 1;
-                    }
-                },
-                Err(e) => {
-                    warn!("Failed to read {}: {}", path, e);
-                    errors.push((path, e.to_string()));
+        let tasks: Vec<_> = paths_to_index
+            .into_iter()
+            .map(|path| self.index_path(backend, path))
+            .collect();
+        let mut outcomes = stream::iter(tasks).buffer_unordered(self.config.concurrency.max(1));
+
+        while let Some((path, bytes, outcome)) = outcomes.next().await {
+            progress.bytes_processed += bytes as u64;
+            match outcome {
+                Some(Ok(result)) => {
+                    files_processed += 1;
+                    total_chunks += result.chunks_created;
+                }
+                Some(Err(e)) => {
+                    errors.push((path, e));
                     files_skipped += 1;
+                    progress.files_failed += 1;
                 }
+                None => {
+                    files_skipped += 1;
+                    progress.files_skipped += 1;
+                }
+            }
+
+            if let Some(callback) = &self.progress {
+                progress.files_processed = files_processed;
+                progress.chunks_embedded = total_chunks;
+                progress.rate_limit_retries = self.rate_limiter.retries();
+                progress.elapsed = start.elapsed();
+                callback(&progress);
             }
         }
 
@@ -260,6 +799,38 @@ impl IndexingPipeline {
         })
     }
 
+    /// Read and index one file for [`index_directory`](Self::index_directory).
+    ///
+    /// Returns the number of bytes read, and `None` as the outcome when the
+    /// file type is unsupported.
+    async fn index_path<B: Backend>(
+        &self,
+        backend: &B,
+        path: String,
+    ) -> (String, usize, Option<Result<PipelineResult, String>>) {
+        // Check if extractor supports this file type
+        if !self.extractor.supports(&path) {
+            debug!("Skipping unsupported file: {}", path);
+            return (path, 0, None);
+        }
+
+        let mut bytes = 0;
+        let outcome = match backend.read(&path).await {
+            Ok(content) => {
+                bytes = content.len();
+                self.index_file(&path, &content).await.map_err(|e| {
+                    warn!("Failed to index {}: {}", path, e);
+                    e.to_string()
+                })
+            }
+            Err(e) => {
+                warn!("Failed to read {}: {}", path, e);
+                Err(e.to_string())
+            }
+        };
+        (path, bytes, Some(outcome))
+    }
+
     /// Recursively collect file paths from a directory.
     async fn collect_files<B: Backend>(
         &self,
@@ -278,7 +849,7 @@ impl IndexingPipeline {
                 if recursive {
                     Box::pin(self.collect_files(backend, &entry.path, recursive, paths)).await?;
                 }
-            } else {
+            } else if self.accepts(&entry.path, entry.size) {
                 paths.push(entry.path);
             }
         }
@@ -287,16 +858,91 @@ impl IndexingPipeline {
     }
 
     /// Delete indexed content for a file.
+    ///
+    /// Chunks shared with other files stay indexed for those files.
     pub async fn delete_file(&self, path: &str) -> Result<(), VfsError> {
         if let Some(chroma) = &self.chroma {
-            // Delete all chunks for this source_path using metadata filter
-            let filter = serde_json::json!({"source_path": path});
+            delete_path_chunks(chroma.as_ref(), &self.shared_chunk_locks, path).await?;
+        }
+        Ok(())
+    }
+
+    /// Move a file's chunks from `from` to `to`, keeping their stored
+    /// embeddings instead of re-embedding the content.
+    ///
+    /// Returns the number of chunks moved. Fails before writing anything if
+    /// a stored chunk has no embedding.
+    pub async fn rename_file(&self, from: &str, to: &str) -> Result<usize, VfsError> {
+        let Some(chroma) = &self.chroma else {
+            return Ok(0);
+        };
+        let shared = chroma
+            .get_by_metadata(serde_json::json!({"paths": {"$contains": from}}))
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?;
+        let filter = serde_json::json!({"source_path": from});
+        let mut per_path = chroma
+            .get_by_metadata(filter.clone())
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?;
+        per_path.retain(|doc| {
+            doc.metadata
+                .as_ref()
+                .is_none_or(|m| !m.contains_key("paths"))
+        });
+        if shared
+            .iter()
+            .chain(&per_path)
+            .any(|doc| doc.embedding.is_none())
+        {
+            return Err(VfsError::Backend(Box::new(PipelineError(format!(
+                "Stored chunks of {} have no embeddings",
+                from
+            )))));
+        }
+
+        for doc in &shared {
+            let Some(hash) = metadata_str(doc, "content_hash") else {
+                continue;
+            };
+            let _guard = self.shared_chunk_locks.lock(hash).await;
+            // Another file may have changed the record since the lookup above
+            let Some(doc) = shared_record(chroma.as_ref(), hash).await? else {
+                continue;
+            };
+            let doc = &doc;
+            if !stored_paths(doc).iter().any(|p| p == from) {
+                continue;
+            }
+            let mut metadata = doc.metadata.clone().unwrap_or_default();
+            let paths: Vec<String> = stored_paths(doc)
+                .into_iter()
+                .map(|p| if p == from { to.to_string() } else { p })
+                .collect();
+            if metadata_str(doc, "source_path") == Some(from) {
+                metadata.insert("source_path".to_string(), serde_json::json!(to));
+            }
+            metadata.insert("paths".to_string(), serde_json::json!(paths));
+            upsert_stored(chroma.as_ref(), &doc.id, doc, metadata).await?;
+        }
+
+        for doc in &per_path {
+            let mut metadata = doc.metadata.clone().unwrap_or_default();
+            metadata.insert("source_path".to_string(), serde_json::json!(to));
+            let chunk_index = metadata
+                .get("chunk_index")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0);
+            let id = format!("{}#chunk_{}", to, chunk_index);
+            upsert_stored(chroma.as_ref(), &id, doc, metadata).await?;
+        }
+        if !per_path.is_empty() {
             chroma
                 .delete_by_metadata(filter)
                 .await
                 .map_err(|e| VfsError::Backend(Box::new(e)))?;
         }
-        Ok(())
+        Ok(shared.len() + per_path.len())
     }
 
     /// Get the sparse encoder for query encoding.
@@ -312,7 +958,7 @@ impl IndexingPipeline {
     /// Embed a query string.
     pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>, VfsError> {
         self.embedder
-            .embed_one(query)
+            .embed_query(query)
             .await
             .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))
     }
@@ -327,26 +973,74 @@ impl IndexingPipeline {
 
     /// Persist SparseEncoder state to Chroma collection metadata.
     pub async fn persist_sparse_encoder(&self) -> Result<(), VfsError> {
+        if self.chroma.is_none() {
+            return Ok(());
+        }
+
+        let json = {
+            let encoder = self.sparse_encoder.read().await;
+            encoder
+                .to_json()
+                .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?
+        };
+        self.update_collection_metadata("sparse_encoder_state", serde_json::json!(json))
+            .await?;
+
+        debug!("Persisted SparseEncoder state to Chroma collection metadata");
+        Ok(())
+    }
+
+    /// The index schema of this pipeline's configuration.
+    pub fn schema_version(&self) -> String {
+        self.config.schema_version()
+    }
+
+    /// The index schema recorded in the Chroma collection's metadata, if any.
+    pub async fn stored_schema_version(&self) -> Result<Option<String>, VfsError> {
         let chroma = match &self.chroma {
             Some(c) => c,
-            None => return Ok(()),
+            None => return Ok(None),
         };
+        let metadata = chroma
+            .get_collection_metadata()
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?;
+        Ok(metadata
+            .and_then(|meta| meta.get(SCHEMA_VERSION_KEY).cloned())
+            .and_then(|value| value.as_str().map(str::to_string)))
+    }
 
-        let encoder = self.sparse_encoder.read().await;
-        let json = encoder
-            .to_json()
-            .map_err(|e| VfsError::Backend(Box::new(PipelineError(e.to_string()))))?;
-
-        let mut metadata = HashMap::new();
-        metadata.insert("sparse_encoder_state".to_string(), serde_json::json!(json));
+    /// Record this pipeline's index schema in the Chroma collection's
+    /// metadata, once every chunk in the collection was written with it.
+    pub async fn record_schema_version(&self) -> Result<(), VfsError> {
+        self.update_collection_metadata(
+            SCHEMA_VERSION_KEY,
+            serde_json::json!(self.schema_version()),
+        )
+        .await
+    }
 
+    /// Set one collection metadata key, keeping the others (Chroma replaces
+    /// the whole map on update).
+    async fn update_collection_metadata(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<(), VfsError> {
+        let chroma = match &self.chroma {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        let mut metadata = chroma
+            .get_collection_metadata()
+            .await
+            .map_err(|e| VfsError::Backend(Box::new(e)))?
+            .unwrap_or_default();
+        metadata.insert(key.to_string(), value);
         chroma
             .set_collection_metadata(metadata)
             .await
-            .map_err(|e| VfsError::Backend(Box::new(e)))?;
-
-        debug!("Persisted SparseEncoder state to Chroma collection metadata");
-        Ok(())
+            .map_err(|e| VfsError::Backend(Box::new(e)))
     }
 
     /// Load SparseEncoder state from Chroma collection metadata.
@@ -438,6 +1132,227 @@ mod tests {
         assert!(result.total_chunks >= 3);
     }
 
+    #[test]
+    fn test_index_progress_eta() {
+        let mut progress = IndexProgress {
+            files_total: 10,
+            ..Default::default()
+        };
+        assert_eq!(progress.eta(), None);
+        assert_eq!(progress.fraction(), 0.0);
+
+        progress.files_processed = 3;
+        progress.files_failed = 1;
+        progress.elapsed = std::time::Duration::from_secs(8);
+        assert_eq!(progress.files_done(), 4);
+        assert_eq!(progress.fraction(), 0.4);
+        assert_eq!(progress.eta(), Some(std::time::Duration::from_secs(12)));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_progress_callback() {
+        use std::sync::Mutex;
+
+        let config = PipelineConfig {
+            batch_size: 1,
+            concurrency: 8,
+            ..Default::default()
+        };
+        let snapshots = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&snapshots);
+        let pipeline = IndexingPipeline::new(config)
+            .unwrap()
+            .with_progress(Arc::new(move |p: &IndexProgress| {
+                sink.lock().unwrap().push(p.clone());
+            }));
+
+        let backend = MemoryBackend::new();
+        for i in 0..10 {
+            backend
+                .write(&format!("/dir/file{}.txt", i), b"Some content to embed")
+                .await
+                .unwrap();
+        }
+        backend.write("/dir/image.png", b"\x89PNG").await.unwrap();
+
+        let result = pipeline
+            .index_directory(&backend, "/dir", true)
+            .await
+            .unwrap();
+        assert_eq!(result.files_processed, 10);
+
+        let snapshots = snapshots.lock().unwrap();
+        assert_eq!(snapshots.len(), 11);
+        let last = snapshots.last().unwrap();
+        assert_eq!(last.files_total, 11);
+        assert_eq!(last.files_processed + last.files_skipped, 11);
+        assert_eq!(last.files_failed, 0);
+        assert_eq!(last.files_done(), 11);
+        assert_eq!(last.bytes_processed, 10 * 21);
+        assert_eq!(last.chunks_embedded, result.total_chunks);
+        assert_eq!(last.eta(), Some(std::time::Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_dedups_identical_chunks() {
+        let config = PipelineConfig {
+            chunker: ChunkerConfig {
+                chunk_size: 20,
+                chunk_overlap: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pipeline = IndexingPipeline::new(config).unwrap();
+
+        let license = b"Licensed under MIT.";
+        let first = pipeline.index_file("/a/LICENSE", license).await.unwrap();
+        assert_eq!(first.chunks_deduplicated, 0);
+
+        let second = pipeline.index_file("/b/LICENSE", license).await.unwrap();
+        assert_eq!(second.chunks_created, first.chunks_created);
+        assert_eq!(second.chunks_deduplicated, second.chunks_created);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_dedup_disabled() {
+        let config = PipelineConfig {
+            dedup_chunks: false,
+            ..Default::default()
+        };
+        let pipeline = IndexingPipeline::new(config).unwrap();
+
+        let content = b"Same content";
+        pipeline.index_file("/a.txt", content).await.unwrap();
+        let result = pipeline.index_file("/b.txt", content).await.unwrap();
+        assert_eq!(result.chunks_deduplicated, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_index_policy() {
+        let index = IndexConfig {
+            enabled: true,
+            include: vec!["**/*.md".to_string()],
+            exclude: vec!["vendor/**".to_string()],
+            max_file_size: Some(openfs_config::HumanBytes(16)),
+            chunk: Some(openfs_config::ChunkConfig {
+                strategy: openfs_config::ChunkStrategy::Recursive,
+                size: 256,
+                overlap: 32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = PipelineConfig::from_index_config("/docs", &index).unwrap();
+        assert_eq!(config.chunker_strategy, "recursive");
+        assert_eq!(config.chunker.chunk_size, 256);
+        let pipeline = IndexingPipeline::new(config).unwrap();
+
+        let backend = MemoryBackend::new();
+        backend.write("/docs/a.md", b"Short note").await.unwrap();
+        backend.write("/docs/b.txt", b"Not markdown").await.unwrap();
+        backend
+            .write("/docs/vendor/c.md", b"Vendored")
+            .await
+            .unwrap();
+        backend
+            .write("/docs/big.md", b"This file is over the size limit")
+            .await
+            .unwrap();
+
+        let result = pipeline
+            .index_directory(&backend, "/docs", true)
+            .await
+            .unwrap();
+        assert_eq!(result.files_processed, 1);
+        assert_eq!(result.files_skipped, 0);
+    }
+
+    #[test]
+    fn test_pipeline_config_embedding_limits() {
+        let index = IndexConfig {
+            enabled: true,
+            embedding: Some(openfs_config::EmbeddingConfig {
+                concurrency: Some(2),
+                requests_per_minute: Some(60),
+                tokens_per_minute: Some(10_000),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = PipelineConfig::from_index_config("/docs", &index).unwrap();
+        assert_eq!(config.concurrency, 2);
+        assert_eq!(config.rate_limit.requests_per_minute, Some(60));
+        assert_eq!(config.rate_limit.tokens_per_minute, Some(10_000));
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let config = PipelineConfig::default();
+        let version = config.schema_version();
+        assert!(version.starts_with(&format!("{}-", INDEX_SCHEMA_VERSION)));
+        assert_eq!(version, PipelineConfig::default().schema_version());
+
+        let rechunked = PipelineConfig {
+            chunker: ChunkerConfig {
+                chunk_size: 256,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_ne!(rechunked.schema_version(), version);
+        let throttled = PipelineConfig {
+            batch_size: 8,
+            ..Default::default()
+        };
+        assert_eq!(throttled.schema_version(), version);
+    }
+
+    #[tokio::test]
+    async fn test_language_chunkers() {
+        let chunker = ChunkerConfig {
+            chunk_size: 40,
+            chunk_overlap: 0,
+            min_chunk_size: 1,
+        };
+        let config = PipelineConfig {
+            chunker: chunker.clone(),
+            language_chunkers: HashMap::from([("rust".to_string(), "recursive".to_string())]),
+            ..Default::default()
+        };
+        assert_ne!(
+            config.schema_version(),
+            PipelineConfig::default().schema_version()
+        );
+        let pipeline = IndexingPipeline::new(config).unwrap();
+
+        let text = "fn parse() { todo!() }\n\nfn check() { todo!() }\n\n\
+                    fn write() { todo!() }\n\nfn close() { todo!() }\n";
+        let count = |strategy: &str| {
+            let chunker = chunkers::create_chunker(strategy, chunker.clone()).unwrap();
+            async move { chunker.chunk(text, "/x").await.unwrap().len() }
+        };
+        let (fixed, recursive) = (count("fixed").await, count("recursive").await);
+        assert_ne!(fixed, recursive);
+
+        let rust = pipeline
+            .index_file("/lib.rs", text.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(rust.chunks_created, recursive);
+        let other = pipeline
+            .index_file("/lib.txt", text.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(other.chunks_created, fixed);
+
+        let unknown = PipelineConfig {
+            language_chunkers: HashMap::from([("rust".to_string(), "magic".to_string())]),
+            ..Default::default()
+        };
+        assert!(IndexingPipeline::new(unknown).is_err());
+    }
+
     #[tokio::test]
     async fn test_pipeline_embed_query() {
         let config = PipelineConfig::default();
@@ -0,0 +1,330 @@
+//! Rate-limit aware request scheduling for embedding providers.
+//!
+//! Hosted embedding APIs enforce requests-per-minute and tokens-per-minute
+//! quotas. [`RateLimiter`] keeps concurrent batches inside a configured budget
+//! and retries `429` responses with backoff, honoring `Retry-After` hints.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::IndexingError;
+
+/// Configuration for provider rate limiting.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Maximum number of retries for a rate-limited request.
+    pub max_retries: u32,
+    /// Initial backoff when the provider gives no `Retry-After` hint.
+    pub initial_backoff: Duration,
+    /// Upper bound for exponential backoff.
+    pub max_backoff: Duration,
+    /// Request budget per minute (None = unlimited).
+    pub requests_per_minute: Option<usize>,
+    /// Token budget per minute (None = unlimited).
+    pub tokens_per_minute: Option<usize>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+        }
+    }
+}
+
+/// A token bucket refilled continuously over one minute.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: usize) -> Self {
+        let capacity = limit.max(1) as f64;
+        Bucket {
+            capacity,
+            available: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Time until `amount` units are available (zero if available now).
+    fn wait_for(&self, amount: f64) -> Duration {
+        let amount = amount.min(self.capacity);
+        if self.available >= amount {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((amount - self.available) / self.refill_per_sec)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Budgets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+/// Shared rate limiter for concurrent embedding requests.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    budgets: Mutex<Budgets>,
+    retries: AtomicUsize,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter with the given configuration.
+    pub fn new(config: RateLimitConfig) -> Self {
+        let budgets = Budgets {
+            requests: config.requests_per_minute.map(Bucket::per_minute),
+            tokens: config.tokens_per_minute.map(Bucket::per_minute),
+        };
+        RateLimiter {
+            config,
+            budgets: Mutex::new(budgets),
+            retries: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the rate limit configuration.
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Number of rate-limited requests retried so far.
+    pub fn retries(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Wait until the budget allows one request of `tokens` tokens, then consume it.
+    pub async fn acquire(&self, tokens: usize) {
+        loop {
+            let wait = {
+                let mut budgets = self.budgets.lock().await;
+                let now = Instant::now();
+                let mut wait = Duration::ZERO;
+                if let Some(bucket) = budgets.requests.as_mut() {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_for(1.0));
+                }
+                if let Some(bucket) = budgets.tokens.as_mut() {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_for(tokens as f64));
+                }
+                if wait.is_zero() {
+                    if let Some(bucket) = budgets.requests.as_mut() {
+                        bucket.available -= 1.0;
+                    }
+                    if let Some(bucket) = budgets.tokens.as_mut() {
+                        bucket.available -= (tokens as f64).min(bucket.capacity);
+                    }
+                    return;
+                }
+                wait
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Compute the delay before retry number `attempt` (0-based).
+    ///
+    /// A server-provided `Retry-After` takes precedence over exponential backoff.
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(delay) = retry_after {
+            return delay;
+        }
+        let factor = 2u32.saturating_pow(attempt.min(16));
+        self.config
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.config.max_backoff)
+    }
+
+    /// Run `op` within the budget, retrying when the provider rate-limits it.
+    pub async fn run<T, F, Fut>(&self, tokens: usize, mut op: F) -> Result<T, IndexingError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, IndexingError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.acquire(tokens).await;
+            match op().await {
+                Err(IndexingError::RateLimited {
+                    message,
+                    retry_after,
+                }) if attempt < self.config.max_retries => {
+                    let delay = self.backoff(attempt, retry_after);
+                    warn!(
+                        "Rate limited (attempt {}), retrying in {:?}: {}",
+                        attempt + 1,
+                        delay,
+                        message
+                    );
+                    self.retries.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+/// Rough token estimate for budgeting (about four bytes per token).
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4).max(1)
+}
+
+/// Parse an HTTP `Retry-After` value (delay in seconds or an HTTP date).
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+        assert_eq!(
+            parse_retry_after(" 1.5 "),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        });
+        assert_eq!(limiter.backoff(0, None), Duration::from_millis(100));
+        assert_eq!(limiter.backoff(2, None), Duration::from_millis(400));
+        assert_eq!(limiter.backoff(10, None), Duration::from_millis(500));
+        assert_eq!(
+            limiter.backoff(0, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 1);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_retries_rate_limited() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let calls = AtomicU32::new(0);
+
+        let result = limiter
+            .run(1, || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(IndexingError::RateLimited {
+                        message: "slow down".to_string(),
+                        retry_after: Some(Duration::from_secs(1)),
+                    })
+                } else {
+                    Ok(42)
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result, 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(limiter.retries(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_gives_up_after_max_retries() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_retries: 2,
+            ..Default::default()
+        });
+        let calls = AtomicU32::new(0);
+
+        let result: Result<(), _> = limiter
+            .run(1, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(IndexingError::RateLimited {
+                    message: "slow down".to_string(),
+                    retry_after: None,
+                })
+            })
+            .await;
+
+        assert!(matches!(result, Err(IndexingError::RateLimited { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_does_not_retry_other_errors() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let calls = AtomicU32::new(0);
+
+        let result: Result<(), _> = limiter
+            .run(1, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(IndexingError::EmbeddingError("bad request".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_budget_delays_requests() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            tokens_per_minute: Some(600),
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        limiter.acquire(600).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Bucket is empty; 300 tokens refill at 10/sec.
+        limiter.acquire(300).await;
+        assert!(start.elapsed() >= Duration::from_secs(30));
+    }
+}
@@ -1,10 +1,16 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::types::{Chunk, SearchResult};
-use openfs_core::{ChromaStore, QueryResult as ChromaQueryResult, SparseEmbedding, VfsError};
+use crate::types::{Chunk, FileSummaryResult, SearchResult};
+use async_trait::async_trait;
+use openfs_config::MountConfig;
+use openfs_core::{
+    ChromaStore, QueryResult as ChromaQueryResult, SearchHit, SearchOptions, Searcher,
+    SparseEmbedding, VfsError,
+};
 
-use crate::pipeline::IndexingPipeline;
+use crate::expansion::QueryExpander;
+use crate::pipeline::{IndexingPipeline, PipelineConfig};
 
 /// Search mode configuration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -18,6 +24,31 @@ pub enum SearchMode {
     Hybrid,
 }
 
+/// How hybrid search combines dense and sparse results.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FusionMethod {
+    /// Weighted sum of the (optionally normalized) scores.
+    #[default]
+    WeightedSum,
+    /// Weighted reciprocal rank fusion: each source adds
+    /// `weight / (k + rank)`, so only the order of its results counts.
+    ReciprocalRank {
+        /// Rank offset damping the top results; 60 is the usual choice.
+        k: f32,
+    },
+}
+
+/// How dense and sparse scores are rescaled before a weighted sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreNormalization {
+    /// Use the scores as returned by the store.
+    #[default]
+    None,
+    /// Rescale each source's scores to 0.0–1.0 by its lowest and highest
+    /// result, so unbounded BM25 scores do not drown out dense scores.
+    MinMax,
+}
+
 /// Configuration for search queries.
 #[derive(Debug, Clone)]
 pub struct SearchConfig {
@@ -31,6 +62,16 @@ pub struct SearchConfig {
     pub dense_weight: f32,
     /// Weight for sparse scores in hybrid mode (0.0 to 1.0).
     pub sparse_weight: f32,
+    /// How hybrid mode combines the two result lists.
+    pub fusion: FusionMethod,
+    /// Rescaling applied before a [`FusionMethod::WeightedSum`].
+    pub normalization: ScoreNormalization,
+    /// Adds related terms to the sparse query. Dense retrieval always
+    /// embeds the query as given.
+    pub expansion: Option<Arc<QueryExpander>>,
+    /// Chunk metadata values results must have, e.g. `language` → `rust`.
+    /// Matches are filtered after retrieval, from an over-fetched list.
+    pub filters: HashMap<String, String>,
 }
 
 impl Default for SearchConfig {
@@ -41,14 +82,120 @@ impl Default for SearchConfig {
             min_score: 0.0,
             dense_weight: 0.7,
             sparse_weight: 0.3,
+            fusion: FusionMethod::default(),
+            normalization: ScoreNormalization::default(),
+            expansion: None,
+            filters: HashMap::new(),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Weight dense scores by `alpha` and sparse scores by `1 - alpha`.
+    pub fn with_alpha(mut self, alpha: f32) -> Result<Self, VfsError> {
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(VfsError::InvalidArgument(format!(
+                "alpha must be between 0 and 1, got {}",
+                alpha
+            )));
+        }
+        self.dense_weight = alpha;
+        self.sparse_weight = 1.0 - alpha;
+        Ok(self)
+    }
+
+    /// Only return chunks whose `key` metadata equals `value`.
+    pub fn with_filter(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.filters.insert(key.into(), value.into());
+        self
+    }
+
+    /// Whether `chunk` has every filtered metadata value (case-insensitively).
+    fn matches(&self, chunk: &Chunk) -> bool {
+        self.filters.iter().all(|(key, value)| {
+            chunk
+                .metadata
+                .get(key)
+                .is_some_and(|v| v.eq_ignore_ascii_case(value))
+        })
+    }
+}
+
+impl From<openfs_config::FusionMethod> for FusionMethod {
+    fn from(fusion: openfs_config::FusionMethod) -> Self {
+        match fusion {
+            openfs_config::FusionMethod::Weighted => FusionMethod::WeightedSum,
+            openfs_config::FusionMethod::Rrf => FusionMethod::ReciprocalRank { k: 60.0 },
+        }
+    }
+}
+
+impl From<openfs_config::ScoreNormalization> for ScoreNormalization {
+    fn from(normalization: openfs_config::ScoreNormalization) -> Self {
+        match normalization {
+            openfs_config::ScoreNormalization::None => ScoreNormalization::None,
+            openfs_config::ScoreNormalization::MinMax => ScoreNormalization::MinMax,
+        }
+    }
+}
+
+impl TryFrom<openfs_config::SearchMode> for SearchMode {
+    type Error = VfsError;
+
+    fn try_from(mode: openfs_config::SearchMode) -> Result<Self, Self::Error> {
+        match mode {
+            openfs_config::SearchMode::Dense => Ok(SearchMode::Dense),
+            openfs_config::SearchMode::Sparse => Ok(SearchMode::Sparse),
+            openfs_config::SearchMode::Hybrid => Ok(SearchMode::Hybrid),
+            other => Err(VfsError::Config(format!(
+                "Search mode {:?} is not supported by the semantic search engine",
+                other
+            ))),
         }
     }
 }
 
+/// A chunk found by hybrid search, with its 1-based rank and score in each
+/// source that returned it.
+struct HybridMatch {
+    chunk: Chunk,
+    dense: Option<(usize, f32)>,
+    sparse: Option<(usize, f32)>,
+}
+
+/// Lowest and highest score in `results`, when min-max normalizing.
+fn score_range(
+    results: &[ChromaQueryResult],
+    normalization: ScoreNormalization,
+) -> Option<(f32, f32)> {
+    match normalization {
+        ScoreNormalization::None => None,
+        ScoreNormalization::MinMax => results.iter().fold(None, |range, r| {
+            Some(range.map_or((r.score, r.score), |(min, max): (f32, f32)| {
+                (min.min(r.score), max.max(r.score))
+            }))
+        }),
+    }
+}
+
+/// Rescale `score` into 0.0–1.0 within `range`, if one is given.
+fn normalize(score: f32, range: Option<(f32, f32)>) -> f32 {
+    match range {
+        Some((min, max)) if max > min => (score - min) / (max - min),
+        Some(_) => 1.0,
+        None => score,
+    }
+}
+
 /// Search engine that queries Chroma for both dense and sparse search.
 pub struct SearchEngine {
     pipeline: Arc<IndexingPipeline>,
     chroma: Option<Arc<dyn ChromaStore>>,
+    default_mode: SearchMode,
+    expansion: Option<Arc<QueryExpander>>,
+    /// Weights, fusion and normalization of hybrid queries served through
+    /// [`Searcher`]; the other fields are unused.
+    hybrid: SearchConfig,
 }
 
 impl SearchEngine {
@@ -57,7 +204,54 @@ impl SearchEngine {
         SearchEngine {
             pipeline,
             chroma: None,
+            default_mode: SearchMode::default(),
+            expansion: None,
+            hybrid: SearchConfig::default(),
+        }
+    }
+
+    /// Create a search engine for a mount's `index:` policy.
+    ///
+    /// Queries are embedded with the mount's configured embedder, and the
+    /// first entry of `index.search_modes` becomes the default mode.
+    /// `index.expansion` and `index.hybrid` apply to searches through
+    /// [`Searcher`].
+    pub fn for_mount(mount: &MountConfig, chroma: Arc<dyn ChromaStore>) -> Result<Self, VfsError> {
+        let (config, default_mode) = match &mount.index {
+            Some(index) => (
+                PipelineConfig::from_index_config(&mount.path, index)?,
+                index
+                    .search_modes
+                    .first()
+                    .map(|mode| SearchMode::try_from(*mode))
+                    .transpose()?
+                    .unwrap_or_default(),
+            ),
+            None => (PipelineConfig::default(), SearchMode::default()),
+        };
+        let pipeline = Arc::new(IndexingPipeline::new(config)?);
+        let mut engine = SearchEngine {
+            chroma: Some(chroma),
+            default_mode,
+            ..SearchEngine::new(pipeline)
+        };
+        if let Some(expansion) = mount
+            .index
+            .as_ref()
+            .and_then(|index| index.expansion.as_ref())
+        {
+            let expander = QueryExpander::new(expansion.into()).map_err(|e| {
+                VfsError::Config(format!("Failed to set up query expansion: {}", e))
+            })?;
+            engine = engine.with_query_expansion(Arc::new(expander));
         }
+        if let Some(hybrid) = mount.index.as_ref().and_then(|index| index.hybrid.as_ref()) {
+            engine = engine.with_fusion(hybrid.fusion.into(), hybrid.normalize.into());
+            if let Some(alpha) = hybrid.alpha {
+                engine = engine.with_alpha(alpha)?;
+            }
+        }
+        Ok(engine)
     }
 
     /// Set the Chroma backend for search.
@@ -66,11 +260,54 @@ impl SearchEngine {
         self
     }
 
+    /// Expand queries served through [`Searcher`] with `expander`.
+    pub fn with_query_expansion(mut self, expander: Arc<QueryExpander>) -> Self {
+        self.expansion = Some(expander);
+        self
+    }
+
+    /// Fuse hybrid queries served through [`Searcher`] with `fusion`,
+    /// rescaling scores by `normalization` first.
+    pub fn with_fusion(mut self, fusion: FusionMethod, normalization: ScoreNormalization) -> Self {
+        self.hybrid.fusion = fusion;
+        self.hybrid.normalization = normalization;
+        self
+    }
+
+    /// Weight dense scores of hybrid queries served through [`Searcher`] by
+    /// `alpha` and sparse scores by `1 - alpha`.
+    pub fn with_alpha(mut self, alpha: f32) -> Result<Self, VfsError> {
+        self.hybrid = self.hybrid.with_alpha(alpha)?;
+        Ok(self)
+    }
+
     /// Search for documents matching the query.
     pub async fn search(
         &self,
         query: &str,
         config: &SearchConfig,
+    ) -> Result<Vec<SearchResult>, VfsError> {
+        if config.filters.is_empty() {
+            return self.search_mode(query, config).await;
+        }
+
+        // Chroma queries take no metadata filter; over-fetch so enough
+        // matches survive filtering.
+        let fetch_config = SearchConfig {
+            limit: config.limit * 5,
+            ..config.clone()
+        };
+        let mut results = self.search_mode(query, &fetch_config).await?;
+        results.retain(|r| config.matches(&r.chunk));
+        results.truncate(config.limit);
+        Ok(results)
+    }
+
+    /// Search with the configured mode, without metadata filters.
+    async fn search_mode(
+        &self,
+        query: &str,
+        config: &SearchConfig,
     ) -> Result<Vec<SearchResult>, VfsError> {
         match config.mode {
             SearchMode::Dense => self.search_dense(query, config).await,
@@ -79,6 +316,54 @@ impl SearchEngine {
         }
     }
 
+    /// Search and collapse chunk matches into one result per file.
+    ///
+    /// Each result carries the file summary stored at index time instead of
+    /// raw chunk text. Files are ranked by their best-matching chunk.
+    pub async fn search_summaries(
+        &self,
+        query: &str,
+        config: &SearchConfig,
+    ) -> Result<Vec<FileSummaryResult>, VfsError> {
+        // Over-fetch chunks so enough distinct files survive grouping
+        let chunk_config = SearchConfig {
+            limit: config.limit * 3,
+            ..config.clone()
+        };
+        let results = self.search(query, &chunk_config).await?;
+
+        let mut by_file: Vec<FileSummaryResult> = Vec::new();
+        for result in results {
+            let summary = result.chunk.metadata.get("file_summary").cloned();
+            match by_file
+                .iter_mut()
+                .find(|f| f.source_path == result.chunk.source_path)
+            {
+                Some(file) => {
+                    file.matched_chunks += 1;
+                    file.score = file.score.max(result.score);
+                    if file.summary.is_none() {
+                        file.summary = summary;
+                    }
+                }
+                None => by_file.push(FileSummaryResult {
+                    source_path: result.chunk.source_path,
+                    summary,
+                    score: result.score,
+                    matched_chunks: 1,
+                }),
+            }
+        }
+
+        by_file.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        by_file.truncate(config.limit);
+        Ok(by_file)
+    }
+
     /// Perform dense (embedding-based) search.
     async fn search_dense(
         &self,
@@ -110,11 +395,7 @@ impl SearchEngine {
             VfsError::Config("Chroma backend required for sparse search".to_string())
         })?;
 
-        let query_vector = self.pipeline.encode_sparse_query(query).await?;
-        let query_sparse = SparseEmbedding {
-            indices: query_vector.indices,
-            values: query_vector.values,
-        };
+        let query_sparse = self.sparse_query(query, config).await?;
 
         let results = chroma
             .query_by_sparse_embedding(&query_sparse, config.limit)
@@ -138,6 +419,28 @@ impl SearchEngine {
         Ok(search_results)
     }
 
+    /// Encode the sparse query, with expansion terms appended.
+    async fn sparse_query(
+        &self,
+        query: &str,
+        config: &SearchConfig,
+    ) -> Result<SparseEmbedding, VfsError> {
+        let terms = match &config.expansion {
+            Some(expander) => expander.expand(query).await,
+            None => Vec::new(),
+        };
+        let query_vector = if terms.is_empty() {
+            self.pipeline.encode_sparse_query(query).await?
+        } else {
+            let expanded = format!("{} {}", query, terms.join(" "));
+            self.pipeline.encode_sparse_query(&expanded).await?
+        };
+        Ok(SparseEmbedding {
+            indices: query_vector.indices,
+            values: query_vector.values,
+        })
+    }
+
     /// Perform hybrid search combining dense and sparse results from Chroma.
     async fn search_hybrid(
         &self,
@@ -156,58 +459,61 @@ impl SearchEngine {
             .map_err(|e| VfsError::Backend(Box::new(e)))?;
 
         // Get sparse results from Chroma
-        let query_vector = self.pipeline.encode_sparse_query(query).await?;
-        let query_sparse = SparseEmbedding {
-            indices: query_vector.indices,
-            values: query_vector.values,
-        };
+        let query_sparse = self.sparse_query(query, config).await?;
         let sparse_results = chroma
             .query_by_sparse_embedding(&query_sparse, config.limit * 2)
             .await
             .map_err(|e| VfsError::Backend(Box::new(e)))?;
 
-        // Build score maps
-        let mut combined_scores: HashMap<String, (Option<Chunk>, f32, f32)> = HashMap::new();
-
-        // Add dense scores
-        for result in &dense_results {
+        // Merge the two lists by chunk, keeping each source's rank and score.
+        let mut merged: HashMap<String, HybridMatch> = HashMap::new();
+        for (rank, result) in dense_results.iter().enumerate() {
             let chunk = self.result_to_chunk(result);
-            let chunk_id = chunk.id.clone();
-            combined_scores.insert(chunk_id, (Some(chunk), result.score, 0.0));
+            merged.entry(chunk.id.clone()).or_insert(HybridMatch {
+                chunk,
+                dense: Some((rank + 1, result.score)),
+                sparse: None,
+            });
         }
-
-        // Add sparse scores
-        for result in &sparse_results {
+        for (rank, result) in sparse_results.iter().enumerate() {
             let chunk = self.result_to_chunk(result);
-            let chunk_id = chunk.id.clone();
-            combined_scores
-                .entry(chunk_id)
-                .and_modify(|(_, _, s)| *s = result.score)
-                .or_insert((Some(chunk), 0.0, result.score));
+            merged
+                .entry(chunk.id.clone())
+                .or_insert(HybridMatch {
+                    chunk,
+                    dense: None,
+                    sparse: None,
+                })
+                .sparse = Some((rank + 1, result.score));
         }
 
-        // Calculate hybrid scores
-        let mut results: Vec<SearchResult> = combined_scores
-            .into_iter()
-            .filter_map(|(_, (chunk_opt, dense_score, sparse_score))| {
-                chunk_opt.map(|chunk| {
-                    let score =
-                        config.dense_weight * dense_score + config.sparse_weight * sparse_score;
-                    SearchResult {
-                        chunk,
-                        score,
-                        dense_score: if dense_score > 0.0 {
-                            Some(dense_score)
-                        } else {
-                            None
-                        },
-                        sparse_score: if sparse_score > 0.0 {
-                            Some(sparse_score)
-                        } else {
-                            None
-                        },
+        let dense_range = score_range(&dense_results, config.normalization);
+        let sparse_range = score_range(&sparse_results, config.normalization);
+        let mut results: Vec<SearchResult> = merged
+            .into_values()
+            .map(|m| {
+                let score = match config.fusion {
+                    FusionMethod::WeightedSum => {
+                        let normalized = |source: Option<(usize, f32)>, range| {
+                            source.map_or(0.0, |(_, score)| normalize(score, range))
+                        };
+                        config.dense_weight * normalized(m.dense, dense_range)
+                            + config.sparse_weight * normalized(m.sparse, sparse_range)
                     }
-                })
+                    FusionMethod::ReciprocalRank { k } => {
+                        let reciprocal = |source: Option<(usize, f32)>| {
+                            source.map_or(0.0, |(rank, _)| 1.0 / (k + rank as f32))
+                        };
+                        config.dense_weight * reciprocal(m.dense)
+                            + config.sparse_weight * reciprocal(m.sparse)
+                    }
+                };
+                SearchResult {
+                    chunk: m.chunk,
+                    score,
+                    dense_score: m.dense.map(|(_, score)| score),
+                    sparse_score: m.sparse.map(|(_, score)| score),
+                }
             })
             .filter(|r| r.score > config.min_score)
             .collect();
@@ -276,6 +582,13 @@ impl SearchEngine {
             .and_then(|v| v.as_u64())
             .unwrap_or(1) as usize;
 
+        let mut chunk_metadata = HashMap::new();
+        for key in ["file_summary", "language"] {
+            if let Some(value) = metadata.and_then(|m| m.get(key)).and_then(|v| v.as_str()) {
+                chunk_metadata.insert(key.to_string(), value.to_string());
+            }
+        }
+
         Chunk {
             id: result.id.clone(),
             source_path,
@@ -286,15 +599,62 @@ impl SearchEngine {
             end_line,
             chunk_index,
             total_chunks,
-            metadata: HashMap::new(),
+            metadata: chunk_metadata,
         }
     }
 }
 
+#[async_trait]
+impl Searcher for SearchEngine {
+    async fn search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchHit>, VfsError> {
+        let mode = match options.mode {
+            Some(mode) => SearchMode::try_from(mode)?,
+            None => self.default_mode,
+        };
+        // Over-fetch when filtering so enough results survive.
+        let fetch = if options.is_filtered() {
+            options.limit * 3
+        } else {
+            options.limit
+        };
+        let config = SearchConfig {
+            mode,
+            limit: fetch,
+            min_score: options.min_score,
+            dense_weight: self.hybrid.dense_weight,
+            sparse_weight: self.hybrid.sparse_weight,
+            fusion: self.hybrid.fusion,
+            normalization: self.hybrid.normalization,
+            expansion: self.expansion.clone(),
+            ..Default::default()
+        };
+
+        let results = SearchEngine::search(self, query, &config).await?;
+        Ok(results
+            .into_iter()
+            .filter(|r| options.matches_path(&r.chunk.source_path))
+            .take(options.limit)
+            .map(|r| SearchHit {
+                path: r.chunk.source_path,
+                score: r.score,
+                dense_score: r.dense_score,
+                sparse_score: r.sparse_score,
+                content: r.chunk.content,
+                start_line: r.chunk.start_line,
+                end_line: r.chunk.end_line,
+                metadata: r.chunk.metadata,
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pipeline::PipelineConfig;
 
     #[tokio::test]
     async fn test_search_engine_requires_chroma() {
@@ -320,6 +680,431 @@ mod tests {
         assert!(engine.search("hello", &search_config).await.is_err());
     }
 
+    /// Minimal store that returns every document for any query.
+    struct ListAllChroma {
+        docs: std::sync::Mutex<Vec<ChromaQueryResult>>,
+        sparse_queries: std::sync::Mutex<Vec<SparseEmbedding>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChromaStore for ListAllChroma {
+        async fn upsert(
+            &self,
+            path: &str,
+            content: &str,
+            _embedding: Option<Vec<f32>>,
+            _sparse_embedding: Option<SparseEmbedding>,
+            metadata: Option<HashMap<String, serde_json::Value>>,
+        ) -> Result<(), openfs_core::BackendError> {
+            self.docs.lock().unwrap().push(ChromaQueryResult {
+                id: path.to_string(),
+                document: Some(content.to_string()),
+                distance: 0.0,
+                score: 1.0,
+                metadata,
+            });
+            Ok(())
+        }
+
+        async fn query_by_embedding(
+            &self,
+            _embedding: Vec<f32>,
+            n_results: usize,
+        ) -> Result<Vec<ChromaQueryResult>, openfs_core::BackendError> {
+            let docs = self.docs.lock().unwrap();
+            Ok(docs.iter().take(n_results).cloned().collect())
+        }
+
+        async fn query_by_sparse_embedding(
+            &self,
+            query_sparse: &SparseEmbedding,
+            n_results: usize,
+        ) -> Result<Vec<ChromaQueryResult>, openfs_core::BackendError> {
+            self.sparse_queries
+                .lock()
+                .unwrap()
+                .push(query_sparse.clone());
+            let docs = self.docs.lock().unwrap();
+            Ok(docs.iter().take(n_results).cloned().collect())
+        }
+
+        async fn get_by_metadata(
+            &self,
+            _filter: serde_json::Value,
+        ) -> Result<Vec<openfs_core::StoredDocument>, openfs_core::BackendError> {
+            Ok(Vec::new())
+        }
+
+        async fn delete_by_metadata(
+            &self,
+            _filter: serde_json::Value,
+        ) -> Result<usize, openfs_core::BackendError> {
+            Ok(0)
+        }
+
+        async fn set_collection_metadata(
+            &self,
+            _metadata: HashMap<String, serde_json::Value>,
+        ) -> Result<(), openfs_core::BackendError> {
+            Ok(())
+        }
+
+        async fn get_collection_metadata(
+            &self,
+        ) -> Result<Option<HashMap<String, serde_json::Value>>, openfs_core::BackendError> {
+            Ok(None)
+        }
+
+        fn collection_name(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_summaries_groups_by_file() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let config = PipelineConfig {
+            summarizer_provider: Some("extractive".to_string()),
+            chunker: crate::ChunkerConfig {
+                chunk_size: 30,
+                chunk_overlap: 0,
+                min_chunk_size: 1,
+            },
+            ..Default::default()
+        };
+        let pipeline = Arc::new(
+            IndexingPipeline::new(config)
+                .unwrap()
+                .with_chroma(chroma.clone() as Arc<dyn ChromaStore>),
+        );
+
+        let result = pipeline
+            .index_file(
+                "/src/lib.rs",
+                b"//! Parses configuration files.\nfn parse() {}\nfn validate() {}\nfn load() {}",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.summary.as_deref(),
+            Some("Parses configuration files.")
+        );
+        assert!(result.chunks_created > 1);
+        pipeline
+            .index_file("/README.md", b"A virtual filesystem for agents.")
+            .await
+            .unwrap();
+
+        let engine = SearchEngine::new(pipeline).with_chroma(chroma as Arc<dyn ChromaStore>);
+        let search_config = SearchConfig {
+            mode: SearchMode::Dense,
+            ..Default::default()
+        };
+        let files = engine
+            .search_summaries("config", &search_config)
+            .await
+            .unwrap();
+
+        assert_eq!(files.len(), 2);
+        let lib = files
+            .iter()
+            .find(|f| f.source_path == "/src/lib.rs")
+            .unwrap();
+        assert_eq!(lib.summary.as_deref(), Some("Parses configuration files."));
+        assert_eq!(lib.matched_chunks, result.chunks_created);
+    }
+
+    #[tokio::test]
+    async fn test_searcher_for_mount() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let mount = MountConfig {
+            path: "/workspace".to_string(),
+            backend: Some("local".to_string()),
+            collection: None,
+            mode: None,
+            read_only: false,
+            index: Some(openfs_config::IndexConfig {
+                enabled: true,
+                search_modes: vec![openfs_config::SearchMode::Sparse],
+                ..Default::default()
+            }),
+            sync: None,
+            watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
+        };
+
+        let engine = SearchEngine::for_mount(&mount, chroma.clone()).unwrap();
+        assert_eq!(engine.default_mode, SearchMode::Sparse);
+
+        let pipeline = IndexingPipeline::new(PipelineConfig::default())
+            .unwrap()
+            .with_chroma(chroma as Arc<dyn ChromaStore>);
+        pipeline
+            .index_file("/workspace/docs/guide.md", b"How to configure mounts.")
+            .await
+            .unwrap();
+        pipeline
+            .index_file("/workspace/src/main.rs", b"fn main() {}")
+            .await
+            .unwrap();
+
+        let options = SearchOptions {
+            path_prefix: Some("/workspace/docs".to_string()),
+            ..Default::default()
+        };
+        let hits = Searcher::search(&engine, "configure", &options)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "/workspace/docs/guide.md");
+
+        let options = SearchOptions {
+            mode: Some(openfs_config::SearchMode::Metadata),
+            ..Default::default()
+        };
+        assert!(Searcher::search(&engine, "configure", &options)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_searcher_uses_mount_fusion() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let mount = MountConfig {
+            path: "/workspace".to_string(),
+            backend: Some("local".to_string()),
+            collection: None,
+            mode: None,
+            read_only: false,
+            index: Some(openfs_config::IndexConfig {
+                enabled: true,
+                search_modes: vec![openfs_config::SearchMode::Hybrid],
+                hybrid: Some(openfs_config::HybridConfig {
+                    alpha: Some(0.4),
+                    fusion: openfs_config::FusionMethod::Rrf,
+                    normalize: openfs_config::ScoreNormalization::MinMax,
+                }),
+                ..Default::default()
+            }),
+            sync: None,
+            watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
+        };
+
+        let engine = SearchEngine::for_mount(&mount, chroma.clone()).unwrap();
+        assert_eq!(
+            engine.hybrid.fusion,
+            FusionMethod::ReciprocalRank { k: 60.0 }
+        );
+        assert_eq!(engine.hybrid.normalization, ScoreNormalization::MinMax);
+
+        let pipeline = IndexingPipeline::new(PipelineConfig::default())
+            .unwrap()
+            .with_chroma(chroma as Arc<dyn ChromaStore>);
+        pipeline
+            .index_file("/workspace/docs/guide.md", b"How to configure mounts.")
+            .await
+            .unwrap();
+
+        // Ranked first by both sources: 0.4 / 61 + 0.6 / 61.
+        let hits = Searcher::search(&engine, "configure", &SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(
+            (hits[0].score - 1.0 / 61.0).abs() < 1e-6,
+            "{}",
+            hits[0].score
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_expansion_feeds_sparse_query() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let pipeline = Arc::new(
+            IndexingPipeline::new(PipelineConfig::default())
+                .unwrap()
+                .with_chroma(chroma.clone() as Arc<dyn ChromaStore>),
+        );
+        pipeline
+            .index_file("/src/session.rs", b"fn login() {}")
+            .await
+            .unwrap();
+        pipeline
+            .index_file("/src/pool.rs", b"fn database() {}")
+            .await
+            .unwrap();
+
+        let expander = crate::QueryExpander::new(crate::QueryExpansionConfig {
+            synonyms: HashMap::from([("auth".to_string(), vec!["login".to_string()])]),
+            ..Default::default()
+        })
+        .unwrap();
+        let engine = SearchEngine::new(pipeline)
+            .with_chroma(chroma.clone() as Arc<dyn ChromaStore>)
+            .with_query_expansion(Arc::new(expander));
+
+        let search_config = SearchConfig {
+            mode: SearchMode::Sparse,
+            ..Default::default()
+        };
+        engine.search("auth", &search_config).await.unwrap();
+        Searcher::search(&engine, "auth", &SearchOptions::default())
+            .await
+            .unwrap();
+
+        let queries = chroma.sparse_queries.lock().unwrap();
+        assert!(queries[0].indices.is_empty());
+        assert_eq!(queries[1].indices.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_for_mount_query_expansion() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let mount = MountConfig {
+            path: "/workspace".to_string(),
+            backend: Some("local".to_string()),
+            collection: None,
+            mode: None,
+            read_only: false,
+            index: Some(openfs_config::IndexConfig {
+                enabled: true,
+                search_modes: vec![openfs_config::SearchMode::Sparse],
+                expansion: Some(openfs_config::ExpansionConfig {
+                    synonyms: [("auth".to_string(), vec!["login".to_string()])].into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            sync: None,
+            watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
+        };
+
+        let engine = SearchEngine::for_mount(&mount, chroma).unwrap();
+        let expander = engine.expansion.as_ref().unwrap();
+        assert_eq!(expander.expand("auth bug").await, vec!["login"]);
+    }
+
+    #[tokio::test]
+    async fn test_language_filter() {
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(Vec::new()),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let pipeline = Arc::new(
+            IndexingPipeline::new(PipelineConfig::default())
+                .unwrap()
+                .with_chroma(chroma.clone() as Arc<dyn ChromaStore>),
+        );
+        pipeline
+            .index_file(
+                "/README.md",
+                b"This is the guide to the project and its API.",
+            )
+            .await
+            .unwrap();
+        pipeline
+            .index_file("/src/lib.rs", b"pub fn parse() {}")
+            .await
+            .unwrap();
+        pipeline
+            .index_file("/tools/gen.py", b"def parse(): pass")
+            .await
+            .unwrap();
+
+        let engine = SearchEngine::new(pipeline).with_chroma(chroma as Arc<dyn ChromaStore>);
+        let search_config = SearchConfig {
+            mode: SearchMode::Dense,
+            limit: 1,
+            ..Default::default()
+        }
+        .with_filter("language", "Rust");
+        let results = engine.search("parse", &search_config).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.source_path, "/src/lib.rs");
+        assert_eq!(results[0].chunk.metadata["language"], "rust");
+
+        let search_config = search_config.with_filter("language", "english");
+        let results = engine.search("parse", &search_config).await.unwrap();
+        assert_eq!(results[0].chunk.source_path, "/README.md");
+        let search_config = search_config.with_filter("language", "klingon");
+        assert!(engine
+            .search("parse", &search_config)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fusion() {
+        let doc = |id: &str, score: f32| ChromaQueryResult {
+            id: id.to_string(),
+            document: Some(String::new()),
+            distance: 0.0,
+            score,
+            metadata: None,
+        };
+        let chroma = Arc::new(ListAllChroma {
+            docs: std::sync::Mutex::new(vec![doc("/a.md", 0.9), doc("/b.md", 0.5)]),
+            sparse_queries: std::sync::Mutex::new(Vec::new()),
+        });
+        let pipeline = Arc::new(IndexingPipeline::new(PipelineConfig::default()).unwrap());
+        let engine = SearchEngine::new(pipeline).with_chroma(chroma as Arc<dyn ChromaStore>);
+
+        let config = SearchConfig {
+            fusion: FusionMethod::ReciprocalRank { k: 60.0 },
+            min_score: -1.0,
+            ..Default::default()
+        };
+        let results = engine.search("query", &config).await.unwrap();
+        assert_eq!(results[0].chunk.source_path, "/a.md");
+        assert!((results[0].score - 1.0 / 61.0).abs() < 1e-6);
+        assert!((results[1].score - 1.0 / 62.0).abs() < 1e-6);
+
+        let config = SearchConfig {
+            normalization: ScoreNormalization::MinMax,
+            min_score: -1.0,
+            ..Default::default()
+        }
+        .with_alpha(0.5)
+        .unwrap();
+        let results = engine.search("query", &config).await.unwrap();
+        assert_eq!(results[0].score, 1.0);
+        assert_eq!(results[0].dense_score, Some(0.9));
+        assert_eq!(results[0].sparse_score, Some(0.9));
+        assert_eq!(results[1].score, 0.0);
+
+        assert!(SearchConfig::default().with_alpha(1.5).is_err());
+    }
+
     #[tokio::test]
     async fn test_search_config_default() {
         let config = SearchConfig::default();
@@ -0,0 +1,128 @@
+use super::Summarizer;
+use crate::IndexingError;
+use async_trait::async_trait;
+
+/// Comment markers stripped from leading documentation lines.
+const COMMENT_PREFIXES: &[&str] = &["//!", "///", "//", "#", "--", ";;", "/**", "/*", "*/", "*"];
+
+/// Extractive summarizer that needs no model.
+///
+/// Uses the file's leading comment block (module docs, license-free headers)
+/// when present, otherwise the first paragraph of text, truncated to a
+/// sentence boundary.
+pub struct ExtractiveSummarizer {
+    max_chars: usize,
+}
+
+impl ExtractiveSummarizer {
+    pub fn new(max_chars: usize) -> Self {
+        ExtractiveSummarizer { max_chars }
+    }
+
+    /// Produce a summary synchronously.
+    pub fn summarize_text(&self, text: &str) -> String {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .skip_while(|l| l.is_empty() || l.starts_with("#!"))
+            .collect();
+
+        // Prefer a leading comment block.
+        let mut comment = Vec::new();
+        for line in &lines {
+            match strip_comment(line) {
+                Some(stripped) => comment.push(stripped),
+                None => break,
+            }
+        }
+        let comment = collapse(&comment.join(" "));
+
+        let source = if comment.len() >= 20 {
+            comment
+        } else {
+            let paragraph: Vec<&str> = lines
+                .iter()
+                .skip_while(|l| strip_comment(l).is_some())
+                .skip_while(|l| l.is_empty())
+                .take_while(|l| !l.is_empty())
+                .copied()
+                .collect();
+            collapse(&paragraph.join(" "))
+        };
+
+        truncate_at_sentence(&source, self.max_chars)
+    }
+}
+
+#[async_trait]
+impl Summarizer for ExtractiveSummarizer {
+    async fn summarize(&self, _path: &str, text: &str) -> Result<String, IndexingError> {
+        Ok(self.summarize_text(text))
+    }
+
+    fn name(&self) -> &'static str {
+        "extractive"
+    }
+}
+
+fn strip_comment(line: &str) -> Option<&str> {
+    COMMENT_PREFIXES
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(|rest| rest.trim_end_matches("*/").trim())
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Truncate to at most `max_chars`, preferring the last sentence end.
+fn truncate_at_sentence(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    match cut.rfind(". ") {
+        Some(pos) if pos > max_chars / 3 => cut[..=pos].to_string(),
+        _ => format!("{}…", cut.trim_end()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uses_leading_doc_comment() {
+        let summarizer = ExtractiveSummarizer::new(200);
+        let text =
+            "//! Rate limiting for embedding providers.\n//! Retries 429s.\n\nuse std::time;\n";
+        assert_eq!(
+            summarizer.summarize_text(text),
+            "Rate limiting for embedding providers. Retries 429s."
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_first_paragraph() {
+        let summarizer = ExtractiveSummarizer::new(200);
+        let text = "\nThe quick brown fox\njumps over the lazy dog.\n\nSecond paragraph.";
+        assert_eq!(
+            summarizer.summarize_text(text),
+            "The quick brown fox jumps over the lazy dog."
+        );
+    }
+
+    #[test]
+    fn test_truncates_at_sentence() {
+        let summarizer = ExtractiveSummarizer::new(40);
+        let text = "First sentence is here. Second sentence is much longer than the limit.";
+        assert_eq!(summarizer.summarize_text(text), "First sentence is here.");
+    }
+
+    #[test]
+    fn test_empty_text() {
+        let summarizer = ExtractiveSummarizer::new(40);
+        assert_eq!(summarizer.summarize_text(""), "");
+    }
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "summarizer-llm")]
+
+use super::{ExtractiveSummarizer, Summarizer, SummarizerConfig};
+use crate::IndexingError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+/// Summarizer backed by an OpenAI-compatible chat completions endpoint.
+///
+/// Falls back to [`ExtractiveSummarizer`] when the endpoint fails, so a
+/// flaky LLM never blocks indexing.
+pub struct LlmSummarizer {
+    config: SummarizerConfig,
+    client: reqwest::Client,
+    endpoint: String,
+    fallback: ExtractiveSummarizer,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    max_tokens: usize,
+    temperature: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+impl LlmSummarizer {
+    pub fn new(config: SummarizerConfig) -> Self {
+        let endpoint = config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(ref api_key) = config.api_key {
+            headers.insert(
+                reqwest::header::AUTHORIZATION,
+                format!("Bearer {}", api_key).parse().unwrap(),
+            );
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_secs(60))
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        let fallback = ExtractiveSummarizer::new(config.max_chars);
+        LlmSummarizer {
+            config,
+            client,
+            endpoint,
+            fallback,
+        }
+    }
+
+    /// Create with API key from environment variable.
+    pub fn from_env(config: SummarizerConfig) -> Self {
+        let mut config = config;
+        if config.api_key.is_none() {
+            config.api_key = std::env::var("OPENAI_API_KEY").ok();
+        }
+        Self::new(config)
+    }
+
+    async fn complete(&self, path: &str, text: &str) -> Result<String, IndexingError> {
+        let input: String = text.chars().take(self.config.max_input_chars).collect();
+        let request = ChatRequest {
+            model: &self.config.model,
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: format!(
+                        "Summarize the file in at most {} characters. \
+                         Describe its purpose and main contents. Reply with the summary only.",
+                        self.config.max_chars
+                    ),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: format!("File: {}\n\n{}", path, input),
+                },
+            ],
+            max_tokens: self.config.max_chars / 3 + 16,
+            temperature: 0.0,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.endpoint))
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(IndexingError::SummarizationError(format!(
+                "LLM API error: {} - {}",
+                status, body
+            )));
+        }
+
+        let result: ChatResponse = response.json().await?;
+        result
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| IndexingError::SummarizationError("No summary returned".to_string()))
+    }
+}
+
+#[async_trait]
+impl Summarizer for LlmSummarizer {
+    async fn summarize(&self, path: &str, text: &str) -> Result<String, IndexingError> {
+        match self.complete(path, text).await {
+            Ok(summary) => Ok(summary),
+            Err(e) => {
+                warn!("LLM summary failed for {}, using extractive: {}", path, e);
+                Ok(self.fallback.summarize_text(text))
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_falls_back_when_endpoint_unreachable() {
+        let summarizer = LlmSummarizer::new(SummarizerConfig {
+            endpoint: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        });
+        let summary = summarizer
+            .summarize("/a.rs", "//! Parses config files.\nfn main() {}")
+            .await
+            .unwrap();
+        assert_eq!(summary, "Parses config files.");
+    }
+}
@@ -0,0 +1,82 @@
+mod extractive;
+
+pub use extractive::ExtractiveSummarizer;
+
+#[cfg(feature = "summarizer-llm")]
+mod llm;
+#[cfg(feature = "summarizer-llm")]
+pub use llm::LlmSummarizer;
+
+use crate::IndexingError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Configuration for a summarizer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizerConfig {
+    /// The model name to use (LLM summarizers only).
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// API endpoint (OpenAI-compatible chat completions base URL).
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// API key (for authenticated APIs).
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Maximum summary length in characters.
+    #[serde(default = "default_max_chars")]
+    pub max_chars: usize,
+    /// Maximum number of input characters sent to the summarizer.
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize,
+}
+
+fn default_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_max_chars() -> usize {
+    400
+}
+
+fn default_max_input_chars() -> usize {
+    16_000
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        SummarizerConfig {
+            model: default_model(),
+            endpoint: None,
+            api_key: None,
+            max_chars: default_max_chars(),
+            max_input_chars: default_max_input_chars(),
+        }
+    }
+}
+
+/// Trait for producing a short per-file summary at index time.
+#[async_trait]
+pub trait Summarizer: Send + Sync {
+    /// Summarize the extracted text of a file.
+    async fn summarize(&self, path: &str, text: &str) -> Result<String, IndexingError>;
+
+    /// Get the summarizer name.
+    fn name(&self) -> &'static str;
+}
+
+/// Create a summarizer based on provider name.
+pub fn create_summarizer(
+    provider: &str,
+    config: SummarizerConfig,
+) -> Result<Box<dyn Summarizer>, IndexingError> {
+    match provider.to_lowercase().as_str() {
+        "extractive" => Ok(Box::new(ExtractiveSummarizer::new(config.max_chars))),
+        #[cfg(feature = "summarizer-llm")]
+        "llm" | "openai" | "openai-compatible" => Ok(Box::new(LlmSummarizer::from_env(config))),
+        _ => Err(IndexingError::SummarizationError(format!(
+            "Unknown summarizer provider: {}",
+            provider
+        ))),
+    }
+}
@@ -97,6 +97,19 @@ pub struct SearchResult {
     pub sparse_score: Option<f32>,
 }
 
+/// File-level search result built from chunk matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSummaryResult {
+    /// The matching file.
+    pub source_path: String,
+    /// Summary stored at index time (if the file was summarized).
+    pub summary: Option<String>,
+    /// Best chunk score for this file.
+    pub score: f32,
+    /// Number of matching chunks from this file.
+    pub matched_chunks: usize,
+}
+
 /// Pipeline event for indexing.
 #[derive(Debug, Clone)]
 pub enum PipelineEvent {
@@ -127,6 +140,10 @@ pub struct PipelineResult {
     pub chunks_created: usize,
     /// Number of chunks deleted.
     pub chunks_deleted: usize,
+    /// Number of chunks whose embedding was reused from an identical chunk.
+    pub chunks_deduplicated: usize,
+    /// File-level summary, when a summarizer is configured.
+    pub summary: Option<String>,
     /// Processing time in milliseconds.
     pub duration_ms: u64,
 }
@@ -145,3 +162,52 @@ pub struct BulkIndexResult {
     /// Errors encountered (path -> error message).
     pub errors: Vec<(String, String)>,
 }
+
+/// Progress snapshot emitted while indexing a directory.
+#[derive(Debug, Clone, Default)]
+pub struct IndexProgress {
+    /// Number of files discovered for indexing.
+    pub files_total: usize,
+    /// Number of files indexed successfully.
+    pub files_processed: usize,
+    /// Number of files skipped because no extractor supports them.
+    pub files_skipped: usize,
+    /// Number of files that failed to read or index.
+    pub files_failed: usize,
+    /// Total chunks embedded so far.
+    pub chunks_embedded: usize,
+    /// Bytes of file content read so far.
+    pub bytes_processed: u64,
+    /// Rate-limited requests retried so far.
+    pub rate_limit_retries: usize,
+    /// Time since indexing started.
+    pub elapsed: std::time::Duration,
+}
+
+impl IndexProgress {
+    /// Files finished so far, whatever the outcome.
+    pub fn files_done(&self) -> usize {
+        self.files_processed + self.files_skipped + self.files_failed
+    }
+
+    /// Completed fraction in `0.0..=1.0`.
+    pub fn fraction(&self) -> f64 {
+        if self.files_total == 0 {
+            1.0
+        } else {
+            (self.files_done() as f64 / self.files_total as f64).min(1.0)
+        }
+    }
+
+    /// Estimated time remaining, extrapolated from the average time per file.
+    ///
+    /// `None` until at least one file has finished.
+    pub fn eta(&self) -> Option<std::time::Duration> {
+        let done = self.files_done();
+        if done == 0 {
+            return None;
+        }
+        let remaining = self.files_total.saturating_sub(done) as u32;
+        Some(self.elapsed / done as u32 * remaining)
+    }
+}
@@ -4,6 +4,7 @@
 //! - Upsert semantics (latest event per path wins -- natural debounce)
 //! - Configurable debounce window before processing
 //! - Retry with exponential backoff
+//! - Priority levels (user-triggered > watcher > backfill)
 //! - Dead letter queue for permanently failed items
 //! - WAL mode for write performance
 //! - Survives process crashes
@@ -11,7 +12,7 @@
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use tracing::{debug, warn};
 
 /// Status of a work queue item.
@@ -69,6 +70,36 @@ impl QueueEventType {
     }
 }
 
+/// Processing priority of a queued item. Higher priorities are fetched first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum QueuePriority {
+    /// Bulk catch-up work, e.g. a rescan after restart.
+    Backfill,
+    /// Changes reported by a file watcher.
+    #[default]
+    Watcher,
+    /// Explicitly requested by a user (e.g. retrying a dead letter).
+    User,
+}
+
+impl QueuePriority {
+    fn as_i64(&self) -> i64 {
+        match self {
+            QueuePriority::Backfill => 0,
+            QueuePriority::Watcher => 1,
+            QueuePriority::User => 2,
+        }
+    }
+
+    fn from_i64(value: i64) -> Self {
+        match value {
+            i64::MIN..=0 => QueuePriority::Backfill,
+            1 => QueuePriority::Watcher,
+            _ => QueuePriority::User,
+        }
+    }
+}
+
 /// A single item in the work queue.
 #[derive(Debug, Clone)]
 pub struct QueueItem {
@@ -76,6 +107,7 @@ pub struct QueueItem {
     pub path: String,
     pub event_type: QueueEventType,
     pub status: QueueItemStatus,
+    pub priority: QueuePriority,
     pub attempts: u32,
     pub last_error: Option<String>,
     pub created_at: u64,
@@ -84,6 +116,19 @@ pub struct QueueItem {
     pub process_after: u64,
 }
 
+/// An item that exhausted its retries and was moved to the dead letter queue.
+#[derive(Debug, Clone)]
+pub struct DeadLetterItem {
+    pub id: i64,
+    pub path: String,
+    pub event_type: QueueEventType,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    /// When the item was moved to the dead letter queue.
+    pub dead_at: u64,
+}
+
 /// Configuration for the work queue.
 #[derive(Debug, Clone)]
 pub struct WorkQueueConfig {
@@ -128,32 +173,7 @@ impl WorkQueue {
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA busy_timeout=5000;")
             .map_err(|e| format!("Failed to set pragmas: {}", e))?;
 
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS work_queue (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL UNIQUE,
-                event_type TEXT NOT NULL DEFAULT 'changed',
-                status TEXT NOT NULL DEFAULT 'pending',
-                attempts INTEGER NOT NULL DEFAULT 0,
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL,
-                process_after INTEGER NOT NULL
-            );
-            CREATE INDEX IF NOT EXISTS idx_wq_status_process
-                ON work_queue(status, process_after);
-            CREATE TABLE IF NOT EXISTS dead_letter (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL,
-                event_type TEXT NOT NULL,
-                attempts INTEGER NOT NULL,
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                dead_at INTEGER NOT NULL
-            );",
-        )
-        .map_err(|e| format!("Failed to create tables: {}", e))?;
-
+        init_schema(&conn)?;
         Ok(WorkQueue { conn, config })
     }
 
@@ -162,32 +182,7 @@ impl WorkQueue {
         let conn = Connection::open_in_memory()
             .map_err(|e| format!("Failed to open in-memory DB: {}", e))?;
 
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS work_queue (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL UNIQUE,
-                event_type TEXT NOT NULL DEFAULT 'changed',
-                status TEXT NOT NULL DEFAULT 'pending',
-                attempts INTEGER NOT NULL DEFAULT 0,
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL,
-                process_after INTEGER NOT NULL
-            );
-            CREATE INDEX IF NOT EXISTS idx_wq_status_process
-                ON work_queue(status, process_after);
-            CREATE TABLE IF NOT EXISTS dead_letter (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL,
-                event_type TEXT NOT NULL,
-                attempts INTEGER NOT NULL,
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                dead_at INTEGER NOT NULL
-            );",
-        )
-        .map_err(|e| format!("Failed to create tables: {}", e))?;
-
+        init_schema(&conn)?;
         Ok(WorkQueue { conn, config })
     }
 
@@ -196,42 +191,61 @@ impl WorkQueue {
     /// If the path already exists in the queue, updates the event type and resets
     /// the debounce window. This provides natural deduplication.
     pub fn enqueue(&self, path: &str, event_type: QueueEventType) -> Result<(), String> {
+        self.enqueue_with_priority(path, event_type, QueuePriority::default())
+    }
+
+    /// Enqueue an event with an explicit priority.
+    ///
+    /// Re-enqueueing a path never lowers its priority: a backfill event for a
+    /// file the user already asked to re-index stays at user priority.
+    pub fn enqueue_with_priority(
+        &self,
+        path: &str,
+        event_type: QueueEventType,
+        priority: QueuePriority,
+    ) -> Result<(), String> {
         let now = now_epoch();
         let process_after = now + self.config.debounce_secs;
 
         self.conn
             .execute(
-                "INSERT INTO work_queue (path, event_type, status, attempts, created_at, updated_at, process_after)
-                 VALUES (?1, ?2, 'pending', 0, ?3, ?3, ?4)
+                "INSERT INTO work_queue (path, event_type, status, priority, attempts, created_at, updated_at, process_after)
+                 VALUES (?1, ?2, 'pending', ?5, 0, ?3, ?3, ?4)
                  ON CONFLICT(path) DO UPDATE SET
                      event_type = ?2,
                      status = 'pending',
+                     priority = MAX(priority, ?5),
                      attempts = 0,
                      last_error = NULL,
                      updated_at = ?3,
                      process_after = ?4",
-                params![path, event_type.as_str(), now, process_after],
+                params![path, event_type.as_str(), now, process_after, priority.as_i64()],
             )
             .map_err(|e| format!("Failed to enqueue: {}", e))?;
 
-        debug!("Enqueued {} for {}", event_type.as_str(), path);
+        debug!(
+            "Enqueued {} for {} ({:?} priority)",
+            event_type.as_str(),
+            path,
+            priority
+        );
         Ok(())
     }
 
     /// Fetch the next batch of items ready for processing.
     ///
-    /// Returns items whose debounce window has elapsed and that are in pending status.
-    /// Atomically marks them as "processing".
+    /// Returns items whose debounce window has elapsed and that are in pending status,
+    /// highest priority first. Atomically marks them as "processing".
     pub fn fetch_ready(&self, batch_size: usize) -> Result<Vec<QueueItem>, String> {
         let now = now_epoch();
 
         // Select and mark as processing in one step
         let mut stmt = self.conn
             .prepare(
-                "SELECT id, path, event_type, status, attempts, last_error, created_at, updated_at, process_after
+                "SELECT id, path, event_type, status, priority, attempts, last_error, created_at, updated_at, process_after
                  FROM work_queue
                  WHERE status = 'pending' AND process_after <= ?1
-                 ORDER BY process_after ASC
+                 ORDER BY priority DESC, process_after ASC, id ASC
                  LIMIT ?2"
             )
             .map_err(|e| format!("Failed to prepare fetch: {}", e))?;
@@ -243,11 +257,12 @@ impl WorkQueue {
                     path: row.get(1)?,
                     event_type: QueueEventType::from_str(&row.get::<_, String>(2)?),
                     status: QueueItemStatus::from_str(&row.get::<_, String>(3)?),
-                    attempts: row.get::<_, u32>(4)?,
-                    last_error: row.get(5)?,
-                    created_at: row.get(6)?,
-                    updated_at: row.get(7)?,
-                    process_after: row.get(8)?,
+                    priority: QueuePriority::from_i64(row.get(4)?),
+                    attempts: row.get::<_, u32>(5)?,
+                    last_error: row.get(6)?,
+                    created_at: row.get(7)?,
+                    updated_at: row.get(8)?,
+                    process_after: row.get(9)?,
                 })
             })
             .map_err(|e| format!("Failed to fetch: {}", e))?
@@ -368,6 +383,80 @@ impl WorkQueue {
         Ok(items)
     }
 
+    /// Get all dead letter items with their ids, newest first.
+    pub fn dead_letter_items(&self) -> Result<Vec<DeadLetterItem>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, path, event_type, attempts, last_error, created_at, dead_at
+                 FROM dead_letter ORDER BY dead_at DESC, id DESC",
+            )
+            .map_err(|e| format!("Failed to prepare dead letter query: {}", e))?;
+
+        let items = stmt
+            .query_map([], |row| {
+                Ok(DeadLetterItem {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    event_type: QueueEventType::from_str(&row.get::<_, String>(2)?),
+                    attempts: row.get(3)?,
+                    last_error: row.get(4)?,
+                    created_at: row.get(5)?,
+                    dead_at: row.get(6)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query dead letters: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(items)
+    }
+
+    /// Move dead letters for `path` back into the queue at user priority.
+    ///
+    /// Returns the number of dead letter entries requeued.
+    pub fn retry_dead_letter(&self, path: &str) -> Result<usize, String> {
+        let event_type: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT event_type FROM dead_letter WHERE path = ?1 ORDER BY dead_at DESC, id DESC LIMIT 1",
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to look up dead letter: {}", e))?;
+        let Some(event_type) = event_type else {
+            return Ok(0);
+        };
+
+        self.enqueue_with_priority(
+            path,
+            QueueEventType::from_str(&event_type),
+            QueuePriority::User,
+        )?;
+        self.conn
+            .execute("DELETE FROM dead_letter WHERE path = ?1", params![path])
+            .map_err(|e| format!("Failed to remove dead letter: {}", e))
+    }
+
+    /// Move every dead letter back into the queue at user priority.
+    ///
+    /// Returns the number of distinct paths requeued.
+    pub fn retry_all_dead_letters(&self) -> Result<usize, String> {
+        let mut paths: Vec<String> = self
+            .dead_letter_items()?
+            .into_iter()
+            .map(|item| item.path)
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        for path in &paths {
+            self.retry_dead_letter(path)?;
+        }
+        Ok(paths.len())
+    }
+
     /// Clear the dead letter queue.
     pub fn clear_dead_letters(&self) -> Result<usize, String> {
         let count = self
@@ -404,6 +493,45 @@ impl WorkQueue {
     }
 }
 
+fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS work_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL UNIQUE,
+            event_type TEXT NOT NULL DEFAULT 'changed',
+            status TEXT NOT NULL DEFAULT 'pending',
+            priority INTEGER NOT NULL DEFAULT 1,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            process_after INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_wq_status_process
+            ON work_queue(status, process_after);
+        CREATE TABLE IF NOT EXISTS dead_letter (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            dead_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(|e| format!("Failed to create tables: {}", e))?;
+
+    // Queues created before priorities existed lack the column.
+    let has_priority = conn
+        .prepare("SELECT priority FROM work_queue LIMIT 0")
+        .is_ok();
+    if !has_priority {
+        conn.execute_batch("ALTER TABLE work_queue ADD COLUMN priority INTEGER NOT NULL DEFAULT 1")
+            .map_err(|e| format!("Failed to add priority column: {}", e))?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,6 +600,102 @@ mod tests {
         assert_eq!(q.dead_letter_count().unwrap(), 0);
     }
 
+    #[test]
+    fn test_priority_ordering() {
+        let q = make_queue();
+        q.enqueue_with_priority(
+            "/backfill.txt",
+            QueueEventType::Changed,
+            QueuePriority::Backfill,
+        )
+        .unwrap();
+        q.enqueue("/watched.txt", QueueEventType::Changed).unwrap();
+        q.enqueue_with_priority("/user.txt", QueueEventType::Changed, QueuePriority::User)
+            .unwrap();
+
+        let items = q.fetch_ready(10).unwrap();
+        let paths: Vec<&str> = items.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["/user.txt", "/watched.txt", "/backfill.txt"]);
+        assert_eq!(items[0].priority, QueuePriority::User);
+    }
+
+    #[test]
+    fn test_requeue_keeps_higher_priority() {
+        let q = make_queue();
+        q.enqueue_with_priority("/file.txt", QueueEventType::Changed, QueuePriority::User)
+            .unwrap();
+        q.enqueue_with_priority(
+            "/file.txt",
+            QueueEventType::Changed,
+            QueuePriority::Backfill,
+        )
+        .unwrap();
+
+        let items = q.fetch_ready(10).unwrap();
+        assert_eq!(items[0].priority, QueuePriority::User);
+    }
+
+    #[test]
+    fn test_dead_letter_and_retry() {
+        let q = make_queue();
+        q.enqueue("/poison.pdf", QueueEventType::Changed).unwrap();
+        for attempt in 0..3 {
+            // Backoff pushes process_after into the future; reset it to refetch.
+            q.conn
+                .execute("UPDATE work_queue SET process_after = 0", [])
+                .unwrap();
+            let items = q.fetch_ready(10).unwrap();
+            assert_eq!(items.len(), 1, "attempt {}", attempt);
+            q.fail(items[0].id, "extraction failed").unwrap();
+        }
+
+        assert_eq!(q.queue_size().unwrap(), 0);
+        let dead = q.dead_letter_items().unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].path, "/poison.pdf");
+        assert_eq!(dead[0].attempts, 3);
+        assert_eq!(dead[0].last_error.as_deref(), Some("extraction failed"));
+
+        assert_eq!(q.retry_dead_letter("/missing.txt").unwrap(), 0);
+        assert_eq!(q.retry_dead_letter("/poison.pdf").unwrap(), 1);
+        assert_eq!(q.dead_letter_count().unwrap(), 0);
+
+        let items = q.fetch_ready(10).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].attempts, 0);
+        assert_eq!(items[0].priority, QueuePriority::User);
+    }
+
+    #[test]
+    fn test_open_migrates_queue_without_priority() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("queue.db");
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE work_queue (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    path TEXT NOT NULL UNIQUE,
+                    event_type TEXT NOT NULL DEFAULT 'changed',
+                    status TEXT NOT NULL DEFAULT 'pending',
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL,
+                    process_after INTEGER NOT NULL
+                );
+                INSERT INTO work_queue (path, created_at, updated_at, process_after)
+                    VALUES ('/old.txt', 0, 0, 0);",
+            )
+            .unwrap();
+        }
+
+        let q = WorkQueue::open(&db_path, WorkQueueConfig::default()).unwrap();
+        let items = q.fetch_ready(10).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].priority, QueuePriority::Watcher);
+    }
+
     #[test]
     fn test_queue_item_status_roundtrip() {
         assert_eq!(
@@ -2,17 +2,25 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use openfs_local::{SearchConfig, SearchEngine};
-use openfs_remote::Vfs;
+use base64::Engine;
+use openfs_core::{allowed_mounts_note, Entry, ErrorCode, SearchOptions, Searcher, VfsError};
+use openfs_local::SearchEngine;
+use openfs_remote::{SessionOptions, Vfs};
 use tracing::{debug, warn};
 
+use crate::policy::McpPolicy;
 use crate::protocol::{McpToolDef, ToolCallResult};
 
+/// Matching lines returned by `openfs_grep` when `max_matches` is not given.
+const DEFAULT_MAX_GREP_MATCHES: usize = 100;
+
 /// Handles MCP tool calls by dispatching to the VFS.
 pub struct McpHandler {
     vfs: Arc<Vfs>,
     search_engine: Option<Arc<SearchEngine>>,
+    policy: McpPolicy,
 }
 
 impl McpHandler {
@@ -20,7 +28,30 @@ impl McpHandler {
         McpHandler {
             vfs,
             search_engine: None,
+            policy: McpPolicy::default(),
+        }
+    }
+
+    /// Restrict the exposed tools and confine paths to the policy's root.
+    pub fn with_policy(mut self, policy: McpPolicy) -> Result<Self, String> {
+        if let Some(mut tools) = policy.allowed_tools() {
+            let known = self.all_tool_definitions();
+            if let Some(unknown) = tools.find(|name| !known.iter().any(|t| t.name == *name)) {
+                return Err(format!("Unknown MCP tool in allowlist: {}", unknown));
+            }
         }
+        self.policy = policy;
+        Ok(self)
+    }
+
+    /// The session policy.
+    pub fn policy(&self) -> &McpPolicy {
+        &self.policy
+    }
+
+    /// The VFS this handler dispatches to.
+    pub fn vfs(&self) -> &Arc<Vfs> {
+        &self.vfs
     }
 
     /// Set an optional search engine for semantic search.
@@ -31,6 +62,42 @@ impl McpHandler {
 
     /// Return the list of tools this server exposes.
     pub fn tool_definitions(&self) -> Vec<McpToolDef> {
+        let customization = self.policy.tools_config();
+        let mounts_note = allowed_mounts_note(customization);
+        self.all_tool_definitions()
+            .into_iter()
+            .filter(|tool| self.policy.allows_tool(&tool.name))
+            .map(|mut tool| {
+                if let Some(tool_override) = customization.override_for(&tool.name) {
+                    if let Some(description) = &tool_override.description {
+                        tool.description = description.clone();
+                    }
+                    if let Some(name) = &tool_override.name {
+                        tool.name = name.clone();
+                    }
+                }
+                if let Some(note) = &mounts_note {
+                    add_path_note(&mut tool.input_schema, note);
+                }
+                tool
+            })
+            .collect()
+    }
+
+    /// The tool a call names, undoing any rename from the `tools:` config.
+    fn resolve_tool_name(&self, name: &str) -> String {
+        let customization = self.policy.tools_config();
+        if customization.overrides.is_empty() {
+            return name.to_string();
+        }
+        self.all_tool_definitions()
+            .into_iter()
+            .map(|tool| tool.name)
+            .find(|tool| tool != name && customization.exposed_name(tool) == name)
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn all_tool_definitions(&self) -> Vec<McpToolDef> {
         vec![
             McpToolDef {
                 name: "openfs_read".to_string(),
@@ -41,6 +108,19 @@ impl McpHandler {
                         "path": {
                             "type": "string",
                             "description": "The VFS path to the file to read"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Optional byte offset to start reading at. Range reads return no cas_token."
+                        },
+                        "length": {
+                            "type": "integer",
+                            "description": "Optional maximum number of bytes to read"
+                        },
+                        "encoding": {
+                            "type": "string",
+                            "enum": ["utf8", "base64"],
+                            "description": "Content encoding. Defaults to utf8; use base64 for binary files"
                         }
                     },
                     "required": ["path"]
@@ -60,6 +140,11 @@ impl McpHandler {
                             "type": "string",
                             "description": "The content to write"
                         },
+                        "encoding": {
+                            "type": "string",
+                            "enum": ["utf8", "base64"],
+                            "description": "Content encoding. Defaults to utf8; use base64 for binary files"
+                        },
                         "cas_token": {
                             "type": "string",
                             "description": "Optional CAS token from a previous openfs_read. If provided, the write will fail if the file has been modified since that read. The response includes the new cas_token on success."
@@ -68,6 +153,76 @@ impl McpHandler {
                     "required": ["path", "content"]
                 }),
             },
+            McpToolDef {
+                name: "openfs_edit_file".to_string(),
+                description: "Edit a file by applying a unified diff. Hunk context must match the current file; on conflict nothing is written.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The VFS path of the file to edit"
+                        },
+                        "diff": {
+                            "type": "string",
+                            "description": "Unified diff for this single file (@@ hunks with context lines)"
+                        }
+                    },
+                    "required": ["path", "diff"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_str_replace".to_string(),
+                description: "Replace an exact string in a file. Fails if the string is not found, or is found more than once and no occurrence is given.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The VFS path of the file to edit"
+                        },
+                        "old_str": {
+                            "type": "string",
+                            "description": "Exact text to replace, including enough context to be unique"
+                        },
+                        "new_str": {
+                            "type": "string",
+                            "description": "Replacement text"
+                        },
+                        "occurrence": {
+                            "type": "integer",
+                            "description": "Optional 1-indexed occurrence to replace when old_str is not unique"
+                        }
+                    },
+                    "required": ["path", "old_str", "new_str"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_merge".to_string(),
+                description: "Three-way merge: combine the changes two files made to a common base and write the result. Conflicting changes are written between <<<<<<< / ||||||| / ======= / >>>>>>> markers and listed in the result.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "base": {
+                            "type": "string",
+                            "description": "The VFS path of the common ancestor (missing means empty)"
+                        },
+                        "ours": {
+                            "type": "string",
+                            "description": "The VFS path of one changed version"
+                        },
+                        "theirs": {
+                            "type": "string",
+                            "description": "The VFS path of the other changed version"
+                        },
+                        "dest": {
+                            "type": "string",
+                            "description": "Where to write the merged file (default: ours)"
+                        }
+                    },
+                    "required": ["base", "ours", "theirs"]
+                }),
+            },
             McpToolDef {
                 name: "openfs_ls".to_string(),
                 description: "List files and directories at a path in the OpenFS virtual filesystem"
@@ -125,6 +280,37 @@ impl McpHandler {
                         "path": {
                             "type": "string",
                             "description": "Directory or file path to search in (defaults to /)"
+                        },
+                        "ignore_case": {
+                            "type": "boolean",
+                            "description": "Match case-insensitively"
+                        },
+                        "max_matches": {
+                            "type": "integer",
+                            "description": "Maximum number of matching lines (default: 100)"
+                        }
+                    },
+                    "required": ["pattern"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_find".to_string(),
+                description: "Find files and directories whose name matches a regex".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Regex matched against each entry name"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Directory to search below (defaults to /)"
+                        },
+                        "type": {
+                            "type": "string",
+                            "enum": ["file", "dir"],
+                            "description": "Only return files or only directories"
                         }
                     },
                     "required": ["pattern"]
@@ -143,6 +329,11 @@ impl McpHandler {
                         "content": {
                             "type": "string",
                             "description": "The content to append"
+                        },
+                        "encoding": {
+                            "type": "string",
+                            "enum": ["utf8", "base64"],
+                            "description": "Content encoding. Defaults to utf8; use base64 for binary files"
                         }
                     },
                     "required": ["path", "content"]
@@ -182,6 +373,24 @@ impl McpHandler {
                     "required": ["from", "to"]
                 }),
             },
+            McpToolDef {
+                name: "openfs_copy".to_string(),
+                description: "Copy a file to a new path, possibly on another mount".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "from": {
+                            "type": "string",
+                            "description": "The VFS path of the file to copy"
+                        },
+                        "to": {
+                            "type": "string",
+                            "description": "The destination VFS path"
+                        }
+                    },
+                    "required": ["from", "to"]
+                }),
+            },
             McpToolDef {
                 name: "openfs_read_batch".to_string(),
                 description: "Read multiple files in a single request. Returns results for each path, including errors for individual failures.".to_string(),
@@ -252,9 +461,12 @@ impl McpHandler {
                             "type": "array",
                             "items": { "type": "string" },
                             "description": "Array of VFS paths to prefetch into cache"
+                        },
+                        "glob": {
+                            "type": "string",
+                            "description": "Glob of VFS paths to prefetch, e.g. /workspace/src/**/*.rs"
                         }
-                    },
-                    "required": ["paths"]
+                    }
                 }),
             },
             McpToolDef {
@@ -271,11 +483,101 @@ impl McpHandler {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum number of results (default: 10)"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Only return results under this path"
+                        },
+                        "meta": {
+                            "type": "object",
+                            "description": "Only return results from files with these metadata values",
+                            "additionalProperties": { "type": "string" }
                         }
                     },
                     "required": ["query"]
                 }),
             },
+            McpToolDef {
+                name: "openfs_get_meta".to_string(),
+                description: "Get the key-value metadata attached to a file".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The VFS path of the file"
+                        },
+                        "key": {
+                            "type": "string",
+                            "description": "Return only this key (default: all keys)"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_set_meta".to_string(),
+                description: "Attach a key-value pair to a file, or remove a key with a null value"
+                    .to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The VFS path of the file"
+                        },
+                        "key": {
+                            "type": "string",
+                            "description": "Metadata key: lowercase letters, digits, '-', '_' and '.'"
+                        },
+                        "value": {
+                            "type": ["string", "null"],
+                            "description": "Value to set, or null to remove the key"
+                        }
+                    },
+                    "required": ["path", "key", "value"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_session_create".to_string(),
+                description: "Create an empty scratch workspace for this run, mounted under /sessions/<id>. It is deleted when its TTL passes or it is ended".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "ttl_seconds": {
+                            "type": "integer",
+                            "description": "Seconds until the workspace is deleted (default: 3600)"
+                        },
+                        "backend": {
+                            "type": "string",
+                            "description": "An fs or s3 backend to store the files on (default: memory)"
+                        }
+                    }
+                }),
+            },
+            McpToolDef {
+                name: "openfs_session_end".to_string(),
+                description: "Delete a session workspace and its files".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "The session ID from openfs_session_create"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            },
+            McpToolDef {
+                name: "openfs_session_list".to_string(),
+                description: "List live session workspaces with their paths and expiry times"
+                    .to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
         ]
     }
 
@@ -285,49 +587,109 @@ impl McpHandler {
         name: &str,
         arguments: Option<HashMap<String, serde_json::Value>>,
     ) -> ToolCallResult {
-        let args = arguments.unwrap_or_default();
+        let mut args = arguments.unwrap_or_default();
         debug!("Tool call: {} with {:?}", name, args);
+        let name = self.resolve_tool_name(name);
+        let name = name.as_str();
+
+        if !self.policy.allows_tool(name) {
+            return ToolCallResult::coded_error(
+                format!("Tool not enabled for this session: {}", name),
+                ErrorCode::PermissionDenied,
+            );
+        }
+        if let Err(e) = self.policy.scope_arguments(name, &mut args) {
+            return ToolCallResult::vfs_error(e.to_string(), &e);
+        }
 
         match name {
             "openfs_read" => self.handle_read(&args).await,
             "openfs_write" => self.handle_write(&args).await,
+            "openfs_edit_file" => self.handle_edit_file(&args).await,
+            "openfs_str_replace" => self.handle_str_replace(&args).await,
+            "openfs_merge" => self.handle_merge(&args).await,
             "openfs_append" => self.handle_append(&args).await,
             "openfs_ls" => self.handle_ls(&args).await,
             "openfs_stat" => self.handle_stat(&args).await,
             "openfs_delete" => self.handle_delete(&args).await,
             "openfs_grep" => self.handle_grep(&args).await,
+            "openfs_find" => self.handle_find(&args).await,
             "openfs_exists" => self.handle_exists(&args).await,
             "openfs_rename" => self.handle_rename(&args).await,
+            "openfs_copy" => self.handle_copy(&args).await,
             "openfs_read_batch" => self.handle_read_batch(&args).await,
             "openfs_write_batch" => self.handle_write_batch(&args).await,
             "openfs_delete_batch" => self.handle_delete_batch(&args).await,
             "openfs_cache_stats" => self.handle_cache_stats().await,
             "openfs_prefetch" => self.handle_prefetch(&args).await,
             "openfs_search" => self.handle_search(&args).await,
+            "openfs_get_meta" => self.handle_get_meta(&args).await,
+            "openfs_set_meta" => self.handle_set_meta(&args).await,
+            "openfs_session_create" => self.handle_session_create(&args).await,
+            "openfs_session_end" => self.handle_session_end(&args).await,
+            "openfs_session_list" => self.handle_session_list().await,
+            _ if name.starts_with("vfs_") => self.handle_generated_tool(name, &args).await,
             _ => ToolCallResult::error(format!("Unknown tool: {}", name)),
         }
     }
 
+    /// Run a call to one of the generic tools from `openfs tools`, for agents
+    /// set up with those definitions rather than this server's.
+    async fn handle_generated_tool(
+        &self,
+        name: &str,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> ToolCallResult {
+        let args = serde_json::Value::Object(args.clone().into_iter().collect());
+        match self.vfs.execute_tool(name, &args).await {
+            Ok(result) => match serde_json::to_string_pretty(&result) {
+                Ok(json) => ToolCallResult::text(json),
+                Err(e) => ToolCallResult::error(format!("Failed to serialize result: {}", e)),
+            },
+            Err(e) => ToolCallResult::vfs_error(format!("{} failed: {}", name, e), &e),
+        }
+    }
+
     async fn handle_read(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
         let path = match args.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return ToolCallResult::error("Missing required parameter: path".to_string()),
         };
+        let base64 = match wants_base64(args) {
+            Ok(base64) => base64,
+            Err(e) => return e,
+        };
+        let offset = args.get("offset").and_then(|v| v.as_u64());
+        let length = args.get("length").and_then(|v| v.as_u64());
 
-        match self.vfs.read_with_cas_token(path).await {
-            Ok((content, cas_token)) => match String::from_utf8(content) {
-                Ok(text) => {
-                    let mut result = serde_json::json!({ "content": text });
-                    if let Some(token) = cas_token {
-                        result["cas_token"] = serde_json::json!(token);
+        let read = if offset.is_some() || length.is_some() {
+            self.vfs
+                .read_range(path, offset.unwrap_or(0), length)
+                .await
+                .map(|content| (content, None))
+        } else {
+            self.vfs.read_with_cas_token(path).await
+        };
+
+        match read {
+            Ok((content, cas_token)) => {
+                let mut result = if base64 {
+                    serde_json::json!({
+                        "content": base64::engine::general_purpose::STANDARD.encode(&content),
+                        "encoding": "base64",
+                    })
+                } else {
+                    match String::from_utf8(content) {
+                        Ok(text) => serde_json::json!({ "content": text }),
+                        Err(_) => return ToolCallResult::text("[binary content]".to_string()),
                     }
-                    ToolCallResult::text(
-                        serde_json::to_string(&result).unwrap_or(text),
-                    )
+                };
+                if let Some(token) = cas_token {
+                    result["cas_token"] = serde_json::json!(token);
                 }
-                Err(_) => ToolCallResult::text("[binary content]".to_string()),
-            },
-            Err(e) => ToolCallResult::error(format!("Failed to read {}: {}", path, e)),
+                ToolCallResult::text(serde_json::to_string(&result).unwrap_or_default())
+            }
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to read {}: {}", path, e), &e),
         }
     }
 
@@ -336,21 +698,15 @@ impl McpHandler {
             Some(p) => p,
             None => return ToolCallResult::error("Missing required parameter: path".to_string()),
         };
-        let content = match args.get("content").and_then(|v| v.as_str()) {
-            Some(c) => c,
-            None => {
-                return ToolCallResult::error("Missing required parameter: content".to_string())
-            }
+        let content = match content_bytes(args) {
+            Ok(content) => content,
+            Err(e) => return e,
         };
         let cas_token = args.get("cas_token").and_then(|v| v.as_str());
 
         if let Some(token) = cas_token {
             // Conditional write with CAS
-            match self
-                .vfs
-                .compare_and_swap(path, Some(token), content.as_bytes())
-                .await
-            {
+            match self.vfs.compare_and_swap(path, Some(token), &content).await {
                 Ok(new_token) => {
                     let result = serde_json::json!({
                         "status": "ok",
@@ -361,51 +717,161 @@ impl McpHandler {
                     ToolCallResult::text(serde_json::to_string(&result).unwrap_or_default())
                 }
                 Err(e) => {
-                    // Check for CAS conflict
                     let err_str = e.to_string();
-                    if err_str.contains("precondition") || err_str.contains("Precondition") {
+                    if e.code() == ErrorCode::PreconditionFailed {
                         let result = serde_json::json!({
                             "status": "conflict",
                             "error": err_str,
+                            "code": e.code(),
                             "path": path,
                             "hint": "The file was modified since your last read. Read the file again to get the latest cas_token, then retry your write.",
                         });
-                        ToolCallResult::error(
+                        ToolCallResult::vfs_error(
                             serde_json::to_string(&result).unwrap_or(err_str),
+                            &e,
                         )
                     } else {
-                        ToolCallResult::error(format!("Failed to write {}: {}", path, e))
+                        ToolCallResult::vfs_error(format!("Failed to write {}: {}", path, e), &e)
                     }
                 }
             }
         } else {
             // Unconditional write (original behavior)
-            match self.vfs.write(path, content.as_bytes()).await {
+            match self.vfs.write(path, &content).await {
                 Ok(()) => {
                     ToolCallResult::text(format!("Wrote {} bytes to {}", content.len(), path))
                 }
-                Err(e) => ToolCallResult::error(format!("Failed to write {}: {}", path, e)),
+                Err(e) => ToolCallResult::vfs_error(format!("Failed to write {}: {}", path, e), &e),
             }
         }
     }
 
-    async fn handle_append(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+    async fn handle_edit_file(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let path = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: path".to_string()),
+        };
+        let diff = match args.get("diff").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => return ToolCallResult::error("Missing required parameter: diff".to_string()),
+        };
+
+        match self.vfs.apply_patch(path, diff).await {
+            Ok(summary) => {
+                let result = serde_json::json!({
+                    "status": "ok",
+                    "path": path,
+                    "hunks": summary.hunks,
+                    "lines_added": summary.added,
+                    "lines_removed": summary.removed,
+                });
+                ToolCallResult::text(result.to_string())
+            }
+            Err(e @ VfsError::Patch { .. }) => {
+                let result = serde_json::json!({
+                    "status": "conflict",
+                    "error": e.to_string(),
+                    "path": path,
+                    "hint": "The diff does not match the current file. Read the file again and regenerate the diff.",
+                });
+                ToolCallResult::vfs_error(result.to_string(), &e)
+            }
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to edit {}: {}", path, e), &e),
+        }
+    }
+
+    async fn handle_merge(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let mut paths = Vec::new();
+        for key in ["base", "ours", "theirs"] {
+            match args.get(key).and_then(|v| v.as_str()) {
+                Some(path) => paths.push(path),
+                None => {
+                    return ToolCallResult::error(format!("Missing required parameter: {}", key))
+                }
+            }
+        }
+        let (base, ours, theirs) = (paths[0], paths[1], paths[2]);
+        let dest = args.get("dest").and_then(|v| v.as_str()).unwrap_or(ours);
+
+        match self.vfs.merge(base, ours, theirs, dest).await {
+            Ok(merge) => {
+                let result = serde_json::json!({
+                    "status": if merge.conflicts.is_empty() { "merged" } else { "conflicts" },
+                    "path": dest,
+                    "conflicts": merge.conflicts,
+                });
+                ToolCallResult::text(result.to_string())
+            }
+            Err(e) => {
+                ToolCallResult::vfs_error(format!("Failed to merge into {}: {}", dest, e), &e)
+            }
+        }
+    }
+
+    async fn handle_str_replace(
+        &self,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> ToolCallResult {
         let path = match args.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return ToolCallResult::error("Missing required parameter: path".to_string()),
         };
-        let content = match args.get("content").and_then(|v| v.as_str()) {
-            Some(c) => c,
+        let old_str = match args.get("old_str").and_then(|v| v.as_str()) {
+            Some(s) => s,
             None => {
-                return ToolCallResult::error("Missing required parameter: content".to_string())
+                return ToolCallResult::error("Missing required parameter: old_str".to_string())
             }
         };
-
-        match self.vfs.append(path, content.as_bytes()).await {
-            Ok(()) => {
-                ToolCallResult::text(format!("Appended {} bytes to {}", content.len(), path))
+        let new_str = match args.get("new_str").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => {
+                return ToolCallResult::error("Missing required parameter: new_str".to_string())
+            }
+        };
+        let occurrence = args
+            .get("occurrence")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+
+        match self
+            .vfs
+            .str_replace(path, old_str, new_str, occurrence)
+            .await
+        {
+            Ok(line) => {
+                let result = serde_json::json!({
+                    "status": "ok",
+                    "path": path,
+                    "line": line,
+                });
+                ToolCallResult::text(result.to_string())
+            }
+            Err(e @ VfsError::Patch { .. }) => {
+                let result = serde_json::json!({
+                    "status": "no_match",
+                    "error": e.to_string(),
+                    "path": path,
+                    "hint": "old_str must match the file exactly once. Include more surrounding context, or pass occurrence.",
+                });
+                ToolCallResult::vfs_error(result.to_string(), &e)
             }
-            Err(e) => ToolCallResult::error(format!("Failed to append to {}: {}", path, e)),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to edit {}: {}", path, e), &e),
+        }
+    }
+
+    async fn handle_append(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let path = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: path".to_string()),
+        };
+        let content = match content_bytes(args) {
+            Ok(content) => content,
+            Err(e) => return e,
+        };
+
+        match self.vfs.append(path, &content).await {
+            Ok(()) => ToolCallResult::text(format!("Appended {} bytes to {}", content.len(), path)),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to append to {}: {}", path, e), &e),
         }
     }
 
@@ -414,23 +880,12 @@ impl McpHandler {
 
         match self.vfs.list(path).await {
             Ok(entries) => {
-                let json_entries: Vec<serde_json::Value> = entries
-                    .iter()
-                    .map(|entry| {
-                        serde_json::json!({
-                            "path": entry.path,
-                            "name": entry.name,
-                            "is_dir": entry.is_dir,
-                            "size": entry.size,
-                            "modified": entry.modified.map(|m| m.to_rfc3339()),
-                        })
-                    })
-                    .collect();
+                let json_entries: Vec<serde_json::Value> = entries.iter().map(entry_json).collect();
                 ToolCallResult::text(
                     serde_json::to_string(&json_entries).unwrap_or_else(|_| "[]".to_string()),
                 )
             }
-            Err(e) => ToolCallResult::error(format!("Failed to list {}: {}", path, e)),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to list {}: {}", path, e), &e),
         }
     }
 
@@ -448,6 +903,8 @@ impl McpHandler {
                     "is_dir": entry.is_dir,
                     "size": entry.size,
                     "modified": entry.modified.map(|m| m.to_rfc3339()),
+                    "content_type": entry.content_type,
+                    "meta": entry.meta,
                 });
                 match serde_json::to_string_pretty(&result) {
                     Ok(json) => ToolCallResult::text(json),
@@ -456,7 +913,7 @@ impl McpHandler {
                     }
                 }
             }
-            Err(e) => ToolCallResult::error(format!("Failed to stat {}: {}", path, e)),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to stat {}: {}", path, e), &e),
         }
     }
 
@@ -468,7 +925,7 @@ impl McpHandler {
 
         match self.vfs.delete(path).await {
             Ok(()) => ToolCallResult::text(format!("Deleted {}", path)),
-            Err(e) => ToolCallResult::error(format!("Failed to delete {}: {}", path, e)),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to delete {}: {}", path, e), &e),
         }
     }
 
@@ -480,15 +937,26 @@ impl McpHandler {
             }
         };
         let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("/");
-
-        let regex = match regex::Regex::new(pattern) {
+        let ignore_case = args
+            .get("ignore_case")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let max_matches = args
+            .get("max_matches")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_GREP_MATCHES);
+
+        let regex = match regex::RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+        {
             Ok(r) => r,
             Err(e) => return ToolCallResult::error(format!("Invalid regex: {}", e)),
         };
 
-        // Collect files to search
         let mut matches = Vec::new();
-        if let Err(e) = self.grep_recursive(&regex, path, &mut matches).await {
+        if let Err(e) = self.grep_files(&regex, path, max_matches, &mut matches).await {
             warn!("Grep error in {}: {}", path, e);
         }
 
@@ -507,32 +975,86 @@ impl McpHandler {
         )
     }
 
-    async fn grep_recursive(
+    /// Grep `path` if it is a file, otherwise every file below it.
+    async fn grep_files(
         &self,
         regex: &regex::Regex,
         path: &str,
+        max_matches: usize,
         matches: &mut Vec<(String, usize, String)>,
     ) -> Result<(), openfs_core::VfsError> {
-        let entries = self.vfs.list(path).await?;
-        for entry in entries {
-            if entry.is_dir {
-                Box::pin(self.grep_recursive(regex, &entry.path, matches)).await?;
-            } else if let Ok(content) = self.vfs.read(&entry.path).await {
-                if let Ok(text) = String::from_utf8(content) {
-                    for (i, line) in text.lines().enumerate() {
-                        if regex.is_match(line) {
-                            matches.push((entry.path.clone(), i + 1, line.to_string()));
-                            if matches.len() >= 100 {
-                                return Ok(());
-                            }
-                        }
-                    }
+        let files = match self.vfs.stat(path).await {
+            Ok(entry) if !entry.is_dir => vec![path.to_string()],
+            _ => self
+                .vfs
+                .walk(path)
+                .await?
+                .into_iter()
+                .filter(|entry| !entry.is_dir)
+                .map(|entry| entry.path)
+                .collect(),
+        };
+
+        for file in files {
+            let Ok(content) = self.vfs.read(&file).await else {
+                continue;
+            };
+            let Ok(text) = String::from_utf8(content) else {
+                continue;
+            };
+            for (i, line) in text.lines().enumerate() {
+                if matches.len() >= max_matches {
+                    return Ok(());
+                }
+                if regex.is_match(line) {
+                    matches.push((file.clone(), i + 1, line.to_string()));
                 }
             }
         }
         Ok(())
     }
 
+    async fn handle_find(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let pattern = match args.get("pattern").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => {
+                return ToolCallResult::error("Missing required parameter: pattern".to_string())
+            }
+        };
+        let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("/");
+        let want_dirs = match args.get("type").and_then(|v| v.as_str()) {
+            None => None,
+            Some("file") => Some(false),
+            Some("dir") => Some(true),
+            Some(other) => {
+                return ToolCallResult::error(format!(
+                    "Invalid type: {} (expected file or dir)",
+                    other
+                ))
+            }
+        };
+
+        let regex = match regex::Regex::new(pattern) {
+            Ok(r) => r,
+            Err(e) => return ToolCallResult::error(format!("Invalid regex: {}", e)),
+        };
+
+        match self.vfs.walk(path).await {
+            Ok(entries) => {
+                let json_entries: Vec<serde_json::Value> = entries
+                    .iter()
+                    .filter(|entry| want_dirs.is_none_or(|dirs| entry.is_dir == dirs))
+                    .filter(|entry| regex.is_match(&entry.name))
+                    .map(entry_json)
+                    .collect();
+                ToolCallResult::text(
+                    serde_json::to_string(&json_entries).unwrap_or_else(|_| "[]".to_string()),
+                )
+            }
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to search {}: {}", path, e), &e),
+        }
+    }
+
     async fn handle_exists(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
         let path = match args.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
@@ -544,7 +1066,67 @@ impl McpHandler {
                 let result = serde_json::json!({ "exists": exists });
                 ToolCallResult::text(serde_json::to_string(&result).unwrap_or_default())
             }
-            Err(e) => ToolCallResult::error(format!("Failed to check existence of {}: {}", path, e)),
+            Err(e) => ToolCallResult::vfs_error(
+                format!("Failed to check existence of {}: {}", path, e),
+                &e,
+            ),
+        }
+    }
+
+    async fn handle_get_meta(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let path = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: path".to_string()),
+        };
+
+        let result = match args.get("key").and_then(|v| v.as_str()) {
+            Some(key) => self
+                .vfs
+                .get_meta(path, key)
+                .await
+                .map(|value| serde_json::json!({ "key": key, "value": value })),
+            None => self
+                .vfs
+                .list_meta(path)
+                .await
+                .map(|meta| serde_json::json!({ "meta": meta })),
+        };
+        match result {
+            Ok(result) => ToolCallResult::text(result.to_string()),
+            Err(e) => {
+                ToolCallResult::vfs_error(format!("Failed to read metadata of {}: {}", path, e), &e)
+            }
+        }
+    }
+
+    async fn handle_set_meta(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let path = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: path".to_string()),
+        };
+        let key = match args.get("key").and_then(|v| v.as_str()) {
+            Some(k) => k,
+            None => return ToolCallResult::error("Missing required parameter: key".to_string()),
+        };
+
+        let result = match args.get("value") {
+            Some(serde_json::Value::String(value)) => self
+                .vfs
+                .set_meta(path, key, value)
+                .await
+                .map(|()| format!("Set {} on {}", key, path)),
+            None | Some(serde_json::Value::Null) => self
+                .vfs
+                .remove_meta(path, key)
+                .await
+                .map(|_| format!("Removed {} from {}", key, path)),
+            Some(_) => return ToolCallResult::error("value must be a string or null".to_string()),
+        };
+        match result {
+            Ok(message) => ToolCallResult::text(message),
+            Err(e) => {
+                ToolCallResult::vfs_error(format!("Failed to set metadata on {}: {}", path, e), &e)
+            }
         }
     }
 
@@ -560,7 +1142,39 @@ impl McpHandler {
 
         match self.vfs.rename(from, to).await {
             Ok(()) => ToolCallResult::text(format!("Renamed {} to {}", from, to)),
-            Err(e) => ToolCallResult::error(format!("Failed to rename {} to {}: {}", from, to, e)),
+            Err(e) => {
+                ToolCallResult::vfs_error(format!("Failed to rename {} to {}: {}", from, to, e), &e)
+            }
+        }
+    }
+
+    async fn handle_copy(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
+        let from = match args.get("from").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: from".to_string()),
+        };
+        let to = match args.get("to").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolCallResult::error("Missing required parameter: to".to_string()),
+        };
+
+        let content = match self.vfs.read(from).await {
+            Ok(content) => content,
+            Err(e) => {
+                return ToolCallResult::vfs_error(format!("Failed to read {}: {}", from, e), &e)
+            }
+        };
+        match self.vfs.write(to, &content).await {
+            Ok(()) => {
+                let result = serde_json::json!({
+                    "status": "ok",
+                    "from": from,
+                    "to": to,
+                    "bytes": content.len(),
+                });
+                ToolCallResult::text(result.to_string())
+            }
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to write {}: {}", to, e), &e),
         }
     }
 
@@ -584,7 +1198,9 @@ impl McpHandler {
                     Ok(text) => serde_json::json!({ "path": path, "content": text }),
                     Err(_) => serde_json::json!({ "path": path, "content": "[binary content]" }),
                 },
-                Err(e) => serde_json::json!({ "path": path, "error": e.to_string() }),
+                Err(e) => {
+                    serde_json::json!({ "path": path, "error": e.to_string(), "code": e.code() })
+                }
             })
             .collect();
 
@@ -621,7 +1237,7 @@ impl McpHandler {
             .zip(results.iter())
             .map(|((path, _), result)| match result {
                 Ok(()) => serde_json::json!({ "path": path, "status": "ok" }),
-                Err(e) => serde_json::json!({ "path": path, "status": "error", "error": e.to_string() }),
+                Err(e) => serde_json::json!({ "path": path, "status": "error", "error": e.to_string(), "code": e.code() }),
             })
             .collect();
 
@@ -647,7 +1263,7 @@ impl McpHandler {
             .zip(results.iter())
             .map(|(path, result)| match result {
                 Ok(()) => serde_json::json!({ "path": path, "status": "ok" }),
-                Err(e) => serde_json::json!({ "path": path, "status": "error", "error": e.to_string() }),
+                Err(e) => serde_json::json!({ "path": path, "status": "error", "error": e.to_string(), "code": e.code() }),
             })
             .collect();
 
@@ -669,18 +1285,92 @@ impl McpHandler {
         ToolCallResult::text(result.to_string())
     }
 
+    async fn handle_session_create(
+        &self,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> ToolCallResult {
+        if let Err(e) = self.vfs.expire_sessions().await {
+            warn!("Failed to expire sessions: {}", e);
+        }
+        let mut options = SessionOptions::default();
+        if let Some(ttl) = args.get("ttl_seconds") {
+            match ttl.as_u64() {
+                Some(secs) => options.ttl = Duration::from_secs(secs),
+                None => {
+                    return ToolCallResult::error(
+                        "ttl_seconds must be a non-negative integer".to_string(),
+                    )
+                }
+            }
+        }
+        options.backend = args
+            .get("backend")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        match self.vfs.create_session(options).await {
+            Ok(session) => ToolCallResult::text(serde_json::json!(session).to_string()),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to create session: {}", e), &e),
+        }
+    }
+
+    async fn handle_session_end(
+        &self,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> ToolCallResult {
+        let id = match args.get("id").and_then(|v| v.as_str()) {
+            Some(id) => id,
+            None => return ToolCallResult::error("Missing required parameter: id".to_string()),
+        };
+        // Only sessions the caller can reach may be ended.
+        let sessions = self.vfs.sessions().await;
+        if !sessions
+            .iter()
+            .any(|s| s.id == id && self.policy.is_within_root(&s.path))
+        {
+            let e = VfsError::NotFound(format!("session {}", id));
+            return ToolCallResult::vfs_error(format!("Failed to end session {}: {}", id, e), &e);
+        }
+        match self.vfs.end_session(id).await {
+            Ok(()) => ToolCallResult::text(serde_json::json!({"ended": id}).to_string()),
+            Err(e) => ToolCallResult::vfs_error(format!("Failed to end session {}: {}", id, e), &e),
+        }
+    }
+
+    async fn handle_session_list(&self) -> ToolCallResult {
+        if let Err(e) = self.vfs.expire_sessions().await {
+            warn!("Failed to expire sessions: {}", e);
+        }
+        let sessions: Vec<_> = self
+            .vfs
+            .sessions()
+            .await
+            .into_iter()
+            .filter(|s| self.policy.is_within_root(&s.path))
+            .collect();
+        ToolCallResult::text(serde_json::json!({ "sessions": sessions }).to_string())
+    }
+
     async fn handle_prefetch(&self, args: &HashMap<String, serde_json::Value>) -> ToolCallResult {
-        let paths = match args.get("paths").and_then(|v| v.as_array()) {
-            Some(arr) => arr
-                .iter()
+        let paths = args.get("paths").and_then(|v| v.as_array()).map(|arr| {
+            arr.iter()
                 .filter_map(|v| v.as_str())
-                .collect::<Vec<&str>>(),
-            None => {
-                return ToolCallResult::error("Missing required parameter: paths".to_string())
+                .collect::<Vec<&str>>()
+        });
+        let glob = args.get("glob").and_then(|v| v.as_str());
+
+        let (prefetched, errors) = match (paths, glob) {
+            (Some(paths), None) => self.vfs.prefetch(&paths).await,
+            (None, Some(glob)) => match self.vfs.prefetch_glob(glob).await {
+                Ok(counts) => counts,
+                Err(e) => return ToolCallResult::vfs_error(format!("Prefetch failed: {}", e), &e),
+            },
+            _ => {
+                return ToolCallResult::error(
+                    "Provide exactly one of the parameters: paths, glob".to_string(),
+                )
             }
         };
-
-        let (prefetched, errors) = self.vfs.prefetch(&paths).await;
         let result = serde_json::json!({
             "prefetched": prefetched,
             "errors": errors,
@@ -696,41 +1386,112 @@ impl McpHandler {
 
         let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
 
-        let engine = match &self.search_engine {
-            Some(e) => e,
-            None => {
-                return ToolCallResult::error(
-                    "Semantic search not available. Configure a Chroma backend and search engine to enable it. Use grep for regex search.".to_string(),
-                );
-            }
+        let meta = match args.get("meta") {
+            None | Some(serde_json::Value::Null) => Default::default(),
+            Some(meta) => match serde_json::from_value(meta.clone()) {
+                Ok(meta) => meta,
+                Err(_) => {
+                    return ToolCallResult::error("meta must map keys to string values".to_string())
+                }
+            },
         };
-
-        let config = SearchConfig {
+        let options = SearchOptions {
             limit,
+            path_prefix: args.get("path").and_then(|v| v.as_str()).map(String::from),
+            meta,
             ..Default::default()
         };
 
-        match engine.search(query, &config).await {
-            Ok(results) => {
-                if results.is_empty() {
+        // An explicitly attached engine wins; otherwise route through the
+        // searchers registered on the VFS mounts.
+        let results = match &self.search_engine {
+            Some(engine) => match Searcher::search(engine.as_ref(), query, &options).await {
+                Ok(hits) if !options.meta.is_empty() => {
+                    Ok(self.vfs.retain_meta_matches(hits, &options).await)
+                }
+                results => results,
+            },
+            None => self.vfs.search(query, &options).await,
+        };
+
+        match results {
+            Ok(hits) => {
+                if hits.is_empty() {
                     return ToolCallResult::text("No results found.".to_string());
                 }
                 let mut lines = Vec::new();
-                for result in &results {
+                for hit in &hits {
                     lines.push(format!(
                         "[{:.3}] {} {}",
-                        result.score,
-                        result.chunk.source_path,
-                        result.chunk.content.chars().take(200).collect::<String>()
+                        hit.score,
+                        hit.path,
+                        hit.content.chars().take(200).collect::<String>()
                     ));
                 }
                 ToolCallResult::text(lines.join("\n"))
             }
-            Err(e) => ToolCallResult::error(format!("Search failed: {}", e)),
+            Err(VfsError::Config(_)) if self.search_engine.is_none() => ToolCallResult::error(
+                "Semantic search not available. Configure a Chroma backend and search engine to enable it. Use grep for regex search.".to_string(),
+            ),
+            Err(e) => ToolCallResult::vfs_error(format!("Search failed: {}", e), &e),
+        }
+    }
+}
+
+/// Append `note` to the description of each path parameter in `schema`.
+fn add_path_note(schema: &mut serde_json::Value, note: &str) {
+    let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) else {
+        return;
+    };
+    for key in ["path", "from", "to", "paths"] {
+        if let Some(description) = properties
+            .get_mut(key)
+            .and_then(|p| p.get_mut("description"))
+        {
+            let text = description.as_str().unwrap_or_default();
+            *description = format!("{} {}", text, note).into();
         }
     }
 }
 
+/// Whether the `encoding` argument asks for base64 rather than UTF-8 text.
+fn wants_base64(args: &HashMap<String, serde_json::Value>) -> Result<bool, ToolCallResult> {
+    match args.get("encoding").and_then(|v| v.as_str()) {
+        None | Some("utf8") => Ok(false),
+        Some("base64") => Ok(true),
+        Some(other) => Err(ToolCallResult::error(format!(
+            "Unknown encoding: {} (expected utf8 or base64)",
+            other
+        ))),
+    }
+}
+
+/// The `content` argument as bytes, decoded per `encoding`.
+fn content_bytes(args: &HashMap<String, serde_json::Value>) -> Result<Vec<u8>, ToolCallResult> {
+    let content = args
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolCallResult::error("Missing required parameter: content".to_string()))?;
+    if wants_base64(args)? {
+        base64::engine::general_purpose::STANDARD
+            .decode(content)
+            .map_err(|e| ToolCallResult::error(format!("Invalid base64 content: {}", e)))
+    } else {
+        Ok(content.as_bytes().to_vec())
+    }
+}
+
+/// JSON shape of an entry in `openfs_ls` and `openfs_find` results.
+fn entry_json(entry: &Entry) -> serde_json::Value {
+    serde_json::json!({
+        "path": entry.path,
+        "name": entry.name,
+        "is_dir": entry.is_dir,
+        "size": entry.size,
+        "modified": entry.modified.map(|m| m.to_rfc3339()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -773,6 +1534,10 @@ mounts:
         assert!(names.contains(&"openfs_exists"));
         assert!(names.contains(&"openfs_rename"));
         assert!(names.contains(&"openfs_search"));
+        assert!(names.contains(&"openfs_edit_file"));
+        assert!(names.contains(&"openfs_str_replace"));
+        assert!(names.contains(&"openfs_get_meta"));
+        assert!(names.contains(&"openfs_set_meta"));
     }
 
     #[tokio::test]
@@ -799,6 +1564,56 @@ mounts:
         assert_eq!(parsed["content"], "hello world");
     }
 
+    #[tokio::test]
+    async fn test_binary_range_read_and_append() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+
+        let call = |name: &'static str, args: serde_json::Value| {
+            let args: HashMap<String, serde_json::Value> = serde_json::from_value(args).unwrap();
+            let handler = &handler;
+            async move { handler.call_tool(name, Some(args)).await }
+        };
+        let text = |result: &ToolCallResult| match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+
+        // 0xff is not valid UTF-8.
+        let result = call(
+            "openfs_write",
+            serde_json::json!({"path": "/workspace/bin", "content": "AP8B", "encoding": "base64"}),
+        )
+        .await;
+        assert!(result.is_error.is_none());
+        let result = call(
+            "openfs_append",
+            serde_json::json!({"path": "/workspace/bin", "content": "Ag==", "encoding": "base64"}),
+        )
+        .await;
+        assert!(result.is_error.is_none());
+        assert_eq!(
+            std::fs::read(tmp.path().join("bin")).unwrap(),
+            vec![0, 0xff, 1, 2]
+        );
+
+        let result = call(
+            "openfs_read",
+            serde_json::json!({"path": "/workspace/bin", "offset": 1, "length": 2, "encoding": "base64"}),
+        )
+        .await;
+        let parsed: serde_json::Value = serde_json::from_str(&text(&result)).unwrap();
+        assert_eq!(parsed["content"], "/wE=");
+        assert_eq!(parsed["encoding"], "base64");
+        assert!(parsed.get("cas_token").is_none());
+
+        let result = call(
+            "openfs_write",
+            serde_json::json!({"path": "/workspace/bin", "content": "x", "encoding": "hex"}),
+        )
+        .await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
     #[tokio::test]
     async fn test_ls() {
         let tmp = TempDir::new().unwrap();
@@ -876,6 +1691,9 @@ mounts:
         args.insert("path".to_string(), serde_json::json!("/workspace/test.txt"));
         let result = handler.call_tool("openfs_read", Some(args)).await;
         assert_eq!(result.is_error, Some(true));
+        let error = &result.structured_content.as_ref().unwrap()["error"];
+        assert_eq!(error["code"], "NOT_FOUND");
+        assert_eq!(error["retryable"], false);
     }
 
     #[tokio::test]
@@ -981,6 +1799,98 @@ mounts:
         assert_eq!(parsed["content"], "rename me");
     }
 
+    #[tokio::test]
+    async fn test_grep_options() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        std::fs::write(tmp.path().join("a.txt"), "Foo\nfoo\nbar\nFOO").unwrap();
+
+        let mut args = HashMap::new();
+        args.insert("pattern".to_string(), serde_json::json!("foo"));
+        args.insert("path".to_string(), serde_json::json!("/workspace"));
+        args.insert("ignore_case".to_string(), serde_json::json!(true));
+        let result = handler.call_tool("openfs_grep", Some(args.clone())).await;
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+        let matches: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(matches.len(), 3);
+
+        args.insert("max_matches".to_string(), serde_json::json!(2));
+        let result = handler.call_tool("openfs_grep", Some(args)).await;
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+        let matches: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        std::fs::create_dir_all(tmp.path().join("src/tests")).unwrap();
+        std::fs::write(tmp.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(tmp.path().join("src/tests/util.rs"), "").unwrap();
+        std::fs::write(tmp.path().join("README.md"), "").unwrap();
+
+        let mut args = HashMap::new();
+        args.insert("pattern".to_string(), serde_json::json!(r"\.rs$"));
+        args.insert("path".to_string(), serde_json::json!("/workspace"));
+        let result = handler.call_tool("openfs_find", Some(args)).await;
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+        let entries: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e["path"].as_str().unwrap()).collect();
+        assert_eq!(paths, vec!["/workspace/src/main.rs", "/workspace/src/tests/util.rs"]);
+
+        let mut args = HashMap::new();
+        args.insert("pattern".to_string(), serde_json::json!("s"));
+        args.insert("path".to_string(), serde_json::json!("/workspace"));
+        args.insert("type".to_string(), serde_json::json!("dir"));
+        let result = handler.call_tool("openfs_find", Some(args)).await;
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+        let entries: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        assert!(entries.iter().all(|e| e["is_dir"] == true));
+        assert_eq!(entries.len(), 2);
+
+        let mut args = HashMap::new();
+        args.insert("pattern".to_string(), serde_json::json!("x"));
+        args.insert("type".to_string(), serde_json::json!("socket"));
+        let result = handler.call_tool("openfs_find", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        std::fs::write(tmp.path().join("a.txt"), "copy me").unwrap();
+
+        let mut args = HashMap::new();
+        args.insert("from".to_string(), serde_json::json!("/workspace/a.txt"));
+        args.insert("to".to_string(), serde_json::json!("/workspace/sub/b.txt"));
+        let result = handler.call_tool("openfs_copy", Some(args)).await;
+        assert!(result.is_error.is_none());
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).unwrap(),
+            "copy me"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("sub/b.txt")).unwrap(),
+            "copy me"
+        );
+
+        let mut args = HashMap::new();
+        args.insert("from".to_string(), serde_json::json!("/workspace/missing.txt"));
+        args.insert("to".to_string(), serde_json::json!("/workspace/c.txt"));
+        let result = handler.call_tool("openfs_copy", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
     #[tokio::test]
     async fn test_rename_missing_params() {
         let tmp = TempDir::new().unwrap();
@@ -1222,4 +2132,392 @@ mounts:
         assert_eq!(parsed["prefetched"], 1);
         assert_eq!(parsed["errors"], 1);
     }
+
+    #[tokio::test]
+    async fn test_prefetch_glob() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("docs")).unwrap();
+        std::fs::write(tmp.path().join("docs/a.md"), "a").unwrap();
+        std::fs::write(tmp.path().join("docs/b.txt"), "b").unwrap();
+
+        let handler = make_handler(&tmp).await;
+
+        let mut args = HashMap::new();
+        args.insert("glob".to_string(), serde_json::json!("/workspace/**/*.md"));
+        let result = handler.call_tool("openfs_prefetch", Some(args)).await;
+        assert!(result.is_error.is_none());
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text,
+        };
+        let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(parsed["prefetched"], 1);
+        assert_eq!(parsed["errors"], 0);
+
+        let result = handler
+            .call_tool("openfs_prefetch", Some(HashMap::new()))
+            .await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_edit_file() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("app.py"), "def main():\n    return 1\n").unwrap();
+        let handler = make_handler(&tmp).await;
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace/app.py"));
+        args.insert(
+            "diff".to_string(),
+            serde_json::json!("@@ -1,2 +1,2 @@\n def main():\n-    return 1\n+    return 2\n"),
+        );
+        let result = handler
+            .call_tool("openfs_edit_file", Some(args.clone()))
+            .await;
+        assert!(result.is_error.is_none());
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("app.py")).unwrap(),
+            "def main():\n    return 2\n"
+        );
+
+        // The same diff no longer matches.
+        let result = handler.call_tool("openfs_edit_file", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text,
+        };
+        let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(parsed["status"], "conflict");
+    }
+
+    #[tokio::test]
+    async fn test_str_replace() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("app.py"), "x = 1\ny = 1\n").unwrap();
+        let handler = make_handler(&tmp).await;
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace/app.py"));
+        args.insert("old_str".to_string(), serde_json::json!("= 1"));
+        args.insert("new_str".to_string(), serde_json::json!("= 2"));
+        let result = handler
+            .call_tool("openfs_str_replace", Some(args.clone()))
+            .await;
+        assert_eq!(result.is_error, Some(true));
+
+        args.insert("occurrence".to_string(), serde_json::json!(2));
+        let result = handler.call_tool("openfs_str_replace", Some(args)).await;
+        assert!(result.is_error.is_none());
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text,
+        };
+        let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(parsed["line"], 2);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("app.py")).unwrap(),
+            "x = 1\ny = 2\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_config_renames_and_hides() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a.txt"), "hello").unwrap();
+        let tools_config = openfs_config::ToolsConfig {
+            hide: vec!["delete".to_string()],
+            mounts: vec!["/workspace".to_string()],
+            overrides: [(
+                "read".to_string(),
+                openfs_config::ToolOverride {
+                    name: Some("read_file".to_string()),
+                    description: Some("Read a file.".to_string()),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let handler = make_handler(&tmp)
+            .await
+            .with_policy(McpPolicy::default().with_tools_config(tools_config))
+            .unwrap();
+
+        let tools = handler.tool_definitions();
+        assert!(!tools.iter().any(|t| t.name == "openfs_delete"));
+        assert!(!tools.iter().any(|t| t.name == "openfs_read"));
+        let read = tools.iter().find(|t| t.name == "read_file").unwrap();
+        assert_eq!(read.description, "Read a file.");
+        let path = read.input_schema["properties"]["path"]["description"]
+            .as_str()
+            .unwrap();
+        assert!(path.ends_with("Must be under /workspace."), "{}", path);
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace/a.txt"));
+        let result = handler.call_tool("read_file", Some(args.clone())).await;
+        assert!(result.is_error.is_none());
+        let result = handler.call_tool("openfs_delete", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+        assert!(tmp.path().join("a.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_policy_scoped_to_root() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("project")).unwrap();
+        std::fs::write(tmp.path().join("project/a.txt"), "inside").unwrap();
+        std::fs::write(tmp.path().join("secret.txt"), "outside").unwrap();
+
+        let config = openfs_config::McpConfig {
+            profile: openfs_config::McpProfile::ReadOnly,
+            root: Some("/workspace/project".to_string()),
+            ..Default::default()
+        };
+        let handler = make_handler(&tmp)
+            .await
+            .with_policy(McpPolicy::from_config(&config).unwrap())
+            .unwrap();
+
+        let names: Vec<String> = handler
+            .tool_definitions()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert!(names.contains(&"openfs_read".to_string()));
+        assert!(!names.contains(&"openfs_write".to_string()));
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace/new.txt"));
+        args.insert("content".to_string(), serde_json::json!("x"));
+        let result = handler.call_tool("openfs_write", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+
+        let mut args = HashMap::new();
+        args.insert(
+            "path".to_string(),
+            serde_json::json!("/workspace/project/a.txt"),
+        );
+        let result = handler.call_tool("openfs_read", Some(args)).await;
+        assert!(result.is_error.is_none());
+
+        let mut args = HashMap::new();
+        args.insert(
+            "path".to_string(),
+            serde_json::json!("/workspace/project/../secret.txt"),
+        );
+        let result = handler.call_tool("openfs_read", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+
+        // ls without a path lists the session root, not the VFS root.
+        let result = handler.call_tool("openfs_ls", None).await;
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text,
+        };
+        assert!(text.contains("a.txt"));
+        assert!(!text.contains("secret.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_generated_tools_scoped_to_root() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("project")).unwrap();
+        std::fs::write(tmp.path().join("project/a.txt"), "inside").unwrap();
+        std::fs::write(tmp.path().join("secret.txt"), "outside").unwrap();
+
+        let config = openfs_config::McpConfig {
+            root: Some("/workspace/project".to_string()),
+            ..Default::default()
+        };
+        let handler = make_handler(&tmp)
+            .await
+            .with_policy(McpPolicy::from_config(&config).unwrap())
+            .unwrap();
+
+        let result = handler.call_tool("vfs_list", None).await;
+        assert!(result.is_error.is_none());
+        let text = match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text,
+        };
+        assert!(text.contains("a.txt"));
+        assert!(!text.contains("secret.txt"));
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace"));
+        let result = handler.call_tool("vfs_list", Some(args)).await;
+        assert_eq!(result.is_error, Some(true));
+
+        let result = handler.call_tool("vfs_mounts", None).await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_session_tools_respect_root() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        let result = handler.call_tool("openfs_session_create", None).await;
+        assert!(result.is_error.is_none());
+
+        let config = openfs_config::McpConfig {
+            root: Some("/workspace/project".to_string()),
+            ..Default::default()
+        };
+        let rooted = McpHandler::new(handler.vfs.clone())
+            .with_policy(McpPolicy::from_config(&config).unwrap())
+            .unwrap();
+        assert!(!rooted
+            .tool_definitions()
+            .iter()
+            .any(|t| t.name.starts_with("openfs_session_")));
+        for tool in ["openfs_session_create", "openfs_session_list"] {
+            let result = rooted.call_tool(tool, None).await;
+            assert_eq!(result.is_error, Some(true), "{}", tool);
+        }
+        assert_eq!(handler.vfs.sessions().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_policy_rejects_unknown_tool() {
+        let tmp = TempDir::new().unwrap();
+        let config = openfs_config::McpConfig {
+            tools: vec!["read".to_string(), "teleport".to_string()],
+            ..Default::default()
+        };
+        let policy = McpPolicy::from_config(&config).unwrap();
+        assert!(make_handler(&tmp).await.with_policy(policy).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_metadata_tools() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        handler
+            .vfs
+            .write("/workspace/notes.md", b"# notes")
+            .await
+            .unwrap();
+
+        let call = |name: &'static str, args: serde_json::Value| {
+            let args: HashMap<String, serde_json::Value> = serde_json::from_value(args).unwrap();
+            handler.call_tool(name, Some(args))
+        };
+        let text = |result: &ToolCallResult| match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+
+        let result = call(
+            "openfs_set_meta",
+            serde_json::json!({"path": "/workspace/notes.md", "key": "owner", "value": "indexer"}),
+        )
+        .await;
+        assert!(result.is_error.is_none(), "{}", text(&result));
+        let result = call(
+            "openfs_stat",
+            serde_json::json!({"path": "/workspace/notes.md"}),
+        )
+        .await;
+        assert!(text(&result).contains("\"owner\": \"indexer\""));
+
+        let result = call(
+            "openfs_get_meta",
+            serde_json::json!({"path": "/workspace/notes.md", "key": "owner"}),
+        )
+        .await;
+        assert_eq!(text(&result), r#"{"key":"owner","value":"indexer"}"#);
+
+        let result = call(
+            "openfs_set_meta",
+            serde_json::json!({"path": "/workspace/notes.md", "key": "Owner!", "value": "x"}),
+        )
+        .await;
+        assert_eq!(result.is_error, Some(true));
+
+        call(
+            "openfs_set_meta",
+            serde_json::json!({"path": "/workspace/notes.md", "key": "owner", "value": null}),
+        )
+        .await;
+        let result = call(
+            "openfs_get_meta",
+            serde_json::json!({"path": "/workspace/notes.md"}),
+        )
+        .await;
+        assert_eq!(text(&result), r#"{"meta":{}}"#);
+    }
+
+    #[tokio::test]
+    async fn test_generated_tool_calls() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        let text = |result: &ToolCallResult| match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+
+        let args: HashMap<String, serde_json::Value> = serde_json::from_value(
+            serde_json::json!({"path": "/workspace/a.txt", "content": "hi"}),
+        )
+        .unwrap();
+        let result = handler.call_tool("vfs_write", Some(args)).await;
+        assert!(result.is_error.is_none(), "{}", text(&result));
+        assert_eq!(handler.vfs.read("/workspace/a.txt").await.unwrap(), b"hi");
+
+        let result = handler.call_tool("vfs_read", None).await;
+        assert_eq!(result.is_error, Some(true));
+        let error = &result.structured_content.as_ref().unwrap()["error"];
+        assert_eq!(error["code"], "INVALID_ARGUMENT");
+    }
+
+    #[tokio::test]
+    async fn test_session_tools() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        let text = |result: &ToolCallResult| match &result.content[0] {
+            crate::protocol::ToolContent::Text { text } => text.clone(),
+        };
+
+        let result = handler.call_tool("openfs_session_create", None).await;
+        let session: serde_json::Value = serde_json::from_str(&text(&result)).unwrap();
+        let id = session["id"].as_str().unwrap();
+        let path = session["path"].as_str().unwrap();
+        assert_eq!(path, format!("/sessions/{}", id));
+        handler
+            .vfs
+            .write(&format!("{}/scratch.txt", path), b"x")
+            .await
+            .unwrap();
+
+        let result = handler.call_tool("openfs_session_list", None).await;
+        assert!(text(&result).contains(id));
+
+        let args: HashMap<String, serde_json::Value> =
+            [("id".to_string(), serde_json::json!(id))].into();
+        let result = handler.call_tool("openfs_session_end", Some(args)).await;
+        assert!(result.is_error.is_none(), "{}", text(&result));
+        assert!(handler.vfs.sessions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_merge_tool() {
+        let tmp = TempDir::new().unwrap();
+        let handler = make_handler(&tmp).await;
+        for (name, content) in [("base", "a\nb\n"), ("ours", "A\nb\n"), ("theirs", "a\nB\n")] {
+            handler
+                .vfs
+                .write(&format!("/workspace/{}.txt", name), content.as_bytes())
+                .await
+                .unwrap();
+        }
+
+        let args: HashMap<String, serde_json::Value> = serde_json::from_value(serde_json::json!({
+            "base": "/workspace/base.txt",
+            "ours": "/workspace/ours.txt",
+            "theirs": "/workspace/theirs.txt",
+        }))
+        .unwrap();
+        let result = handler.call_tool("openfs_merge", Some(args)).await;
+        let crate::protocol::ToolContent::Text { text } = &result.content[0];
+        let result: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(result["status"], "conflicts");
+        assert_eq!(result["conflicts"][0]["base_start"], 1);
+        let merged = handler.vfs.read("/workspace/ours.txt").await.unwrap();
+        assert!(String::from_utf8(merged).unwrap().contains("======="));
+    }
 }
@@ -1,7 +1,11 @@
 mod handler;
+mod policy;
 mod protocol;
+mod resources;
 mod server;
 
 pub use handler::McpHandler;
+pub use policy::{McpPolicy, READ_ONLY_TOOLS};
 pub use protocol::*;
+pub use resources::{path_to_uri, uri_to_path, ResourceProvider};
 pub use server::McpServer;
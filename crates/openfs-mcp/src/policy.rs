@@ -0,0 +1,433 @@
+//! Session policy — which tools are exposed and which paths they may touch.
+
+use std::collections::{BTreeSet, HashMap};
+
+use openfs_config::{McpConfig, McpProfile, ToolsConfig};
+use openfs_core::{sanitize_path, PathSanitizer, VfsError};
+use openfs_remote::SESSIONS_ROOT;
+
+/// Tools exposed by the `read_only` profile.
+pub const READ_ONLY_TOOLS: &[&str] = &[
+    "openfs_read",
+    "openfs_ls",
+    "openfs_stat",
+    "openfs_grep",
+    "openfs_find",
+    "openfs_exists",
+    "openfs_read_batch",
+    "openfs_cache_stats",
+    "openfs_prefetch",
+    "openfs_search",
+    "openfs_get_meta",
+];
+
+/// Restricts the tools and paths available to an MCP session.
+#[derive(Debug, Clone, Default)]
+pub struct McpPolicy {
+    /// Allowed tool names; `None` allows every tool.
+    tools: Option<BTreeSet<String>>,
+    /// Normalized path prefix the session is confined to.
+    root: Option<String>,
+    /// Checks every path argument before it is used.
+    sanitizer: PathSanitizer,
+    /// The `tools:` config section: hidden tools, allowed mounts, renames.
+    customization: ToolsConfig,
+}
+
+impl McpPolicy {
+    /// Build a policy from the `mcp:` config section.
+    pub fn from_config(config: &McpConfig) -> Result<Self, String> {
+        let profile: Option<BTreeSet<String>> = match config.profile {
+            McpProfile::ReadOnly => Some(READ_ONLY_TOOLS.iter().map(|t| t.to_string()).collect()),
+            _ => None,
+        };
+
+        let tools = if config.tools.is_empty() {
+            profile
+        } else {
+            let mut tools = BTreeSet::new();
+            for name in &config.tools {
+                let name = tool_name(name);
+                if let Some(profile) = &profile {
+                    if !profile.contains(&name) {
+                        return Err(format!(
+                            "Tool '{}' is not available in the {:?} profile",
+                            name, config.profile
+                        ));
+                    }
+                }
+                tools.insert(name);
+            }
+            Some(tools)
+        };
+
+        let root = match &config.root {
+            Some(root) => {
+                Some(normalize_path(root).ok_or_else(|| format!("Invalid MCP root '{}'", root))?)
+            }
+            None => None,
+        };
+
+        Ok(McpPolicy {
+            tools,
+            root,
+            sanitizer: PathSanitizer::default(),
+            customization: ToolsConfig::default(),
+        })
+    }
+
+    /// Check path arguments with `sanitizer`, e.g. one denying suspicious
+    /// paths, instead of the default.
+    pub fn with_sanitizer(mut self, sanitizer: PathSanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Apply the `tools:` config section: hide its hidden tools, confine
+    /// paths to its mounts and rename tools as it says.
+    pub fn with_tools_config(mut self, config: ToolsConfig) -> Self {
+        self.customization = config;
+        self
+    }
+
+    /// The `tools:` config section the policy applies.
+    pub fn tools_config(&self) -> &ToolsConfig {
+        &self.customization
+    }
+
+    /// Tools named by the policy, if it restricts them.
+    pub fn allowed_tools(&self) -> Option<impl Iterator<Item = &str>> {
+        self.tools
+            .as_ref()
+            .map(|tools| tools.iter().map(String::as_str))
+    }
+
+    /// Whether `tool` may be listed and called.
+    ///
+    /// The `openfs_session_*` tools mount workspaces under [`SESSIONS_ROOT`],
+    /// so a root that does not include it disables them.
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.tools.as_ref().is_none_or(|tools| tools.contains(tool))
+            && !self.customization.hides(tool)
+            && (!tool.starts_with("openfs_session_") || self.is_within_root(SESSIONS_ROOT))
+    }
+
+    /// Path prefix the session is confined to.
+    pub fn root(&self) -> Option<&str> {
+        self.root.as_deref()
+    }
+
+    /// Whether the normalized `path` lies within the session root, if any.
+    pub fn is_within_root(&self, path: &str) -> bool {
+        self.root
+            .as_deref()
+            .is_none_or(|root| is_within(path, root))
+    }
+
+    /// Normalize `path` and check it lies within the session root.
+    pub fn check_path(&self, path: &str) -> Result<String, VfsError> {
+        let invalid = |reason: String| VfsError::InvalidPath {
+            path: path.to_string(),
+            reason,
+        };
+        let normalized = self
+            .sanitizer
+            .sanitize(path)
+            .map_err(|violation| invalid(violation.to_string()))?;
+        match &self.root {
+            Some(root) if !is_within(&normalized, root) => {
+                Err(invalid(format!("outside the session root '{}'", root)))
+            }
+            _ if !self.customization.allows_path(&normalized) => Err(invalid(format!(
+                "outside the mounts tools may use ({})",
+                self.customization.mounts.join(", ")
+            ))),
+            _ => Ok(normalized),
+        }
+    }
+
+    /// Resolve a prefetch glob against the session root and check the
+    /// directory it walks lies within the root. Globs may not use `..`.
+    pub fn check_glob(&self, glob: &str) -> Result<String, VfsError> {
+        if glob.split('/').any(|part| part == "..") {
+            return Err(VfsError::InvalidPath {
+                path: glob.to_string(),
+                reason: "globs may not use '..'".to_string(),
+            });
+        }
+        let resolved = match &self.root {
+            Some(root) if !glob.starts_with('/') => {
+                format!("{}/{}", root.trim_end_matches('/'), glob)
+            }
+            _ => glob.to_string(),
+        };
+        let literal: Vec<&str> = resolved
+            .split('/')
+            .take_while(|part| !part.contains(['*', '?', '[', '{']))
+            .collect();
+        let walked = match literal.join("/") {
+            dir if dir.is_empty() => "/".to_string(),
+            dir => dir,
+        };
+        self.check_path(&walked)
+            .map_err(|_| VfsError::InvalidPath {
+                path: glob.to_string(),
+                reason: match &self.root {
+                    Some(root) => format!("matches files outside the session root '{}'", root),
+                    None => "matches files outside the mounts tools may use".to_string(),
+                },
+            })?;
+        Ok(resolved)
+    }
+
+    /// Check (and normalize in place) every path argument of a tool call.
+    ///
+    /// Listing and search tools, including the generated `vfs_*` ones, default
+    /// to the session root when no path is given. `vfs_mounts` is refused in a
+    /// rooted session, since it would list mounts outside the root.
+    pub fn scope_arguments(
+        &self,
+        tool: &str,
+        args: &mut HashMap<String, serde_json::Value>,
+    ) -> Result<(), VfsError> {
+        if let Some(root) = &self.root {
+            if tool == "vfs_mounts" {
+                return Err(VfsError::InvalidArgument(format!(
+                    "{} is not available in a session confined to '{}'",
+                    tool, root
+                )));
+            }
+            if matches!(
+                tool,
+                "openfs_ls"
+                    | "openfs_grep"
+                    | "openfs_find"
+                    | "openfs_search"
+                    | "vfs_list"
+                    | "vfs_search"
+            ) && !args.contains_key("path")
+            {
+                args.insert("path".to_string(), serde_json::json!(root));
+            }
+        }
+
+        for key in ["path", "from", "to", "base", "ours", "theirs", "dest"] {
+            if let Some(serde_json::Value::String(path)) = args.get_mut(key) {
+                *path = self.check_path(path)?;
+            }
+        }
+        if let Some(serde_json::Value::Array(paths)) = args.get_mut("paths") {
+            for path in paths.iter_mut() {
+                if let serde_json::Value::String(path) = path {
+                    *path = self.check_path(path)?;
+                }
+            }
+        }
+        if let Some(serde_json::Value::String(glob)) = args.get_mut("glob") {
+            *glob = self.check_glob(glob)?;
+        }
+        if let Some(serde_json::Value::Array(files)) = args.get_mut("files") {
+            for file in files.iter_mut() {
+                if let Some(serde_json::Value::String(path)) = file.get_mut("path") {
+                    *path = self.check_path(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accept tool names with or without the `openfs_` prefix.
+fn tool_name(name: &str) -> String {
+    if name.starts_with("openfs_") {
+        name.to_string()
+    } else {
+        format!("openfs_{}", name)
+    }
+}
+
+/// Resolve `.` and `..` segments. Returns None for paths the default
+/// sanitizer refuses, such as a `..` escaping `/`.
+pub(crate) fn normalize_path(path: &str) -> Option<String> {
+    sanitize_path(path).ok()
+}
+
+/// Whether `path` equals `root` or lies below it.
+pub(crate) fn is_within(path: &str, root: &str) -> bool {
+    let root = root.trim_end_matches('/');
+    root.is_empty()
+        || path == root
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_profile() {
+        let config = McpConfig {
+            profile: McpProfile::ReadOnly,
+            ..Default::default()
+        };
+        let policy = McpPolicy::from_config(&config).unwrap();
+        assert!(policy.allows_tool("openfs_read"));
+        assert!(policy.allows_tool("openfs_search"));
+        assert!(!policy.allows_tool("openfs_write"));
+        assert!(!policy.allows_tool("openfs_delete"));
+
+        let config = McpConfig {
+            profile: McpProfile::ReadOnly,
+            tools: vec!["read".to_string(), "write".to_string()],
+            ..Default::default()
+        };
+        assert!(McpPolicy::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_tool_allowlist() {
+        let config = McpConfig {
+            tools: vec!["read".to_string(), "openfs_ls".to_string()],
+            ..Default::default()
+        };
+        let policy = McpPolicy::from_config(&config).unwrap();
+        assert!(policy.allows_tool("openfs_read"));
+        assert!(policy.allows_tool("openfs_ls"));
+        assert!(!policy.allows_tool("openfs_grep"));
+        assert!(McpPolicy::default().allows_tool("openfs_delete"));
+    }
+
+    #[test]
+    fn test_scope_arguments() {
+        let config = McpConfig {
+            root: Some("/workspace/project/".to_string()),
+            ..Default::default()
+        };
+        let policy = McpPolicy::from_config(&config).unwrap();
+
+        let mut args = HashMap::new();
+        policy.scope_arguments("openfs_ls", &mut args).unwrap();
+        assert_eq!(args["path"], "/workspace/project");
+
+        let mut args = HashMap::new();
+        args.insert(
+            "path".to_string(),
+            serde_json::json!("/workspace/project/./src/../lib.rs"),
+        );
+        policy.scope_arguments("openfs_read", &mut args).unwrap();
+        assert_eq!(args["path"], "/workspace/project/lib.rs");
+
+        let mut args = HashMap::new();
+        args.insert(
+            "path".to_string(),
+            serde_json::json!("/workspace/project/../secrets.env"),
+        );
+        assert!(policy.scope_arguments("openfs_read", &mut args).is_err());
+
+        let mut args = HashMap::new();
+        args.insert(
+            "files".to_string(),
+            serde_json::json!([{"path": "/workspace/other/a.txt", "content": "x"}]),
+        );
+        assert!(policy
+            .scope_arguments("openfs_write_batch", &mut args)
+            .is_err());
+
+        let mut args = HashMap::new();
+        args.insert("query".to_string(), serde_json::json!("secrets"));
+        policy.scope_arguments("vfs_search", &mut args).unwrap();
+        assert_eq!(args["path"], "/workspace/project");
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), serde_json::json!("/workspace"));
+        assert!(policy.scope_arguments("vfs_list", &mut args).is_err());
+
+        assert!(policy
+            .scope_arguments("vfs_mounts", &mut HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_session_tools_need_sessions_within_root() {
+        let rooted = |root: &str| {
+            McpPolicy::from_config(&McpConfig {
+                root: Some(root.to_string()),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        assert!(McpPolicy::default().allows_tool("openfs_session_create"));
+        assert!(rooted("/").allows_tool("openfs_session_create"));
+        assert!(rooted("/sessions").allows_tool("openfs_session_list"));
+
+        let policy = rooted("/workspace/project");
+        assert!(!policy.allows_tool("openfs_session_create"));
+        assert!(!policy.allows_tool("openfs_session_end"));
+        assert!(!policy.allows_tool("openfs_session_list"));
+        assert!(policy.allows_tool("openfs_read"));
+    }
+
+    #[test]
+    fn test_check_glob() {
+        let config = McpConfig {
+            root: Some("/workspace/project/".to_string()),
+            ..Default::default()
+        };
+        let policy = McpPolicy::from_config(&config).unwrap();
+
+        assert_eq!(
+            policy.check_glob("src/**/*.rs").unwrap(),
+            "/workspace/project/src/**/*.rs"
+        );
+        assert_eq!(
+            policy.check_glob("/workspace/project/*.md").unwrap(),
+            "/workspace/project/*.md"
+        );
+        assert!(policy.check_glob("/workspace/**/*.env").is_err());
+        assert!(policy.check_glob("/**").is_err());
+        assert!(policy.check_glob("../other/*.md").is_err());
+        assert!(policy.check_glob("src/../../*.md").is_err());
+
+        let mut args = HashMap::new();
+        args.insert("glob".to_string(), serde_json::json!("**/*.md"));
+        policy
+            .scope_arguments("openfs_prefetch", &mut args)
+            .unwrap();
+        assert_eq!(args["glob"], "/workspace/project/**/*.md");
+
+        // Without a root, globs are left as they are.
+        let policy = McpPolicy::default();
+        assert_eq!(policy.check_glob("/**/*.md").unwrap(), "/**/*.md");
+    }
+
+    #[test]
+    fn test_tools_config() {
+        let policy = McpPolicy::default().with_tools_config(ToolsConfig {
+            hide: vec!["delete".to_string()],
+            mounts: vec!["/workspace".to_string()],
+            ..Default::default()
+        });
+        assert!(!policy.allows_tool("openfs_delete"));
+        assert!(policy.allows_tool("openfs_read"));
+        assert!(policy.check_path("/workspace/a.txt").is_ok());
+        let err = policy.check_path("/docs/a.txt").unwrap_err();
+        assert!(err.to_string().contains("(/workspace)"), "{}", err);
+    }
+
+    #[test]
+    fn test_sanitizer_denies_suspicious_paths() {
+        let policy = McpPolicy::default();
+        assert!(policy.check_path("/workspace/a\0.txt").is_err());
+        assert_eq!(
+            policy.check_path("/workspace/src/../a.txt").unwrap(),
+            "/workspace/a.txt"
+        );
+
+        let strict = policy.with_sanitizer(PathSanitizer::default().with_deny_suspicious(true));
+        let err = strict.check_path("/workspace/src/../a.txt").unwrap_err();
+        assert!(err.to_string().contains("'..' segment"), "{}", err);
+        assert!(strict.check_path("/workspace/a.txt").is_ok());
+    }
+}
@@ -1,6 +1,7 @@
 //! MCP (Model Context Protocol) JSON-RPC types.
 //!
-//! Implements the subset of MCP needed for tool serving over stdio transport.
+//! Implements the subset of MCP needed for tool and resource serving over
+//! stdio transport.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -44,7 +45,8 @@ impl JsonRpcResponse {
             error: Some(JsonRpcError {
                 code,
                 message,
-                data: None,
+                data: openfs_core::current_request_id()
+                    .map(|request_id| serde_json::json!({ "request_id": request_id })),
             }),
         }
     }
@@ -65,12 +67,34 @@ pub const INVALID_REQUEST: i32 = -32600;
 pub const METHOD_NOT_FOUND: i32 = -32601;
 pub const INVALID_PARAMS: i32 = -32602;
 pub const INTERNAL_ERROR: i32 = -32603;
+pub const RESOURCE_NOT_FOUND: i32 = -32002;
+
+/// JSON-RPC notification (server-initiated, no id).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: serde_json::Value) -> Self {
+        JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        }
+    }
+}
 
 /// MCP server capabilities.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourcesCapability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +103,14 @@ pub struct ToolsCapability {
     pub list_changed: Option<bool>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesCapability {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscribe: Option<bool>,
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
 /// MCP server info.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerInfo {
@@ -133,6 +165,13 @@ pub struct ToolCallResult {
     pub content: Vec<ToolContent>,
     #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    /// Machine-readable details; for errors, `{"error": {"code", ...}}`.
+    #[serde(
+        rename = "structuredContent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structured_content: Option<serde_json::Value>,
 }
 
 impl ToolCallResult {
@@ -140,6 +179,7 @@ impl ToolCallResult {
         ToolCallResult {
             content: vec![ToolContent::Text { text }],
             is_error: None,
+            structured_content: None,
         }
     }
 
@@ -147,10 +187,89 @@ impl ToolCallResult {
         ToolCallResult {
             content: vec![ToolContent::Text { text: message }],
             is_error: Some(true),
+            structured_content: None,
+        }
+    }
+
+    /// An error with `code` in the structured content.
+    pub fn coded_error(message: String, code: openfs_core::ErrorCode) -> Self {
+        let mut body = serde_json::json!({ "code": code, "message": message, "retryable": false });
+        if let Some(request_id) = openfs_core::current_request_id() {
+            body["request_id"] = request_id.into();
+        }
+        ToolCallResult {
+            structured_content: Some(serde_json::json!({ "error": body })),
+            ..ToolCallResult::error(message)
+        }
+    }
+
+    /// An error caused by `err`, with its code in the structured content.
+    pub fn vfs_error(message: String, err: &openfs_core::VfsError) -> Self {
+        ToolCallResult {
+            structured_content: Some(serde_json::json!({ "error": err.to_json() })),
+            ..ToolCallResult::error(message)
         }
     }
 }
 
+/// MCP resource definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// MCP resource template definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// MCP resource list result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceListResult {
+    pub resources: Vec<McpResource>,
+}
+
+/// MCP resource template list result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplateListResult {
+    #[serde(rename = "resourceTemplates")]
+    pub resource_templates: Vec<McpResourceTemplate>,
+}
+
+/// Params for `resources/read`, `resources/subscribe` and `resources/unsubscribe`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceUriParams {
+    pub uri: String,
+}
+
+/// Contents of a read resource. Exactly one of `text` or `blob` (base64) is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+/// MCP resource read result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceReadResult {
+    pub contents: Vec<ResourceContents>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,6 +325,7 @@ mod tests {
                 tools: Some(ToolsCapability {
                     list_changed: Some(false),
                 }),
+                resources: None,
             },
             server_info: ServerInfo {
                 name: "openfs".to_string(),
@@ -244,6 +364,26 @@ mod tests {
         assert!(json.contains("openfs_read"));
     }
 
+    #[test]
+    fn test_resource_contents_serialize() {
+        let contents = ResourceContents {
+            uri: "openfs:///workspace/a.md".to_string(),
+            mime_type: Some("text/markdown".to_string()),
+            text: Some("# A".to_string()),
+            blob: None,
+        };
+        let json = serde_json::to_value(&contents).unwrap();
+        assert_eq!(json["mimeType"], "text/markdown");
+        assert!(json.get("blob").is_none());
+
+        let notification = JsonRpcNotification::new(
+            "notifications/resources/updated",
+            serde_json::json!({ "uri": contents.uri }),
+        );
+        let json = serde_json::to_string(&notification).unwrap();
+        assert!(!json.contains("\"id\""));
+    }
+
     #[test]
     fn test_request_without_params() {
         let json = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#;
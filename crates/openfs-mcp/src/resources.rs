@@ -0,0 +1,488 @@
+//! MCP resources — exposes mounted files as `openfs:///<vfs path>` resources.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use openfs_core::{detect_content_type, is_text_content_type, VfsError, OCTET_STREAM, TEXT_PLAIN};
+use openfs_local::WatchEngine;
+use openfs_remote::Vfs;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::policy::{is_within, normalize_path};
+use crate::protocol::*;
+
+/// URI scheme prefix for OpenFS resources.
+pub const URI_PREFIX: &str = "openfs://";
+
+/// MIME type reported for directories (including mount roots).
+const DIRECTORY_MIME_TYPE: &str = "inode/directory";
+
+/// Convert a VFS path to a resource URI (`/workspace/a.md` → `openfs:///workspace/a.md`).
+pub fn path_to_uri(path: &str) -> String {
+    let path = format!("/{}", path.trim_matches('/'));
+    let mut uri = String::with_capacity(URI_PREFIX.len() + path.len());
+    uri.push_str(URI_PREFIX);
+    for c in path.chars() {
+        match c {
+            ' ' | '%' | '#' | '?' => uri.push_str(&format!("%{:02X}", c as u32)),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+/// Convert a resource URI back to a normalized VFS path. Returns None for
+/// foreign URIs.
+pub fn uri_to_path(uri: &str) -> Option<String> {
+    let encoded = uri.strip_prefix(URI_PREFIX)?;
+    if !encoded.starts_with('/') {
+        return None;
+    }
+
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    normalize_path(&String::from_utf8(decoded).ok()?)
+}
+
+fn rpc_error(code: i32, message: String) -> JsonRpcError {
+    JsonRpcError {
+        code,
+        message,
+        data: None,
+    }
+}
+
+fn parse_uri(uri: &str) -> Result<String, JsonRpcError> {
+    uri_to_path(uri).ok_or_else(|| {
+        rpc_error(
+            INVALID_PARAMS,
+            format!(
+                "Invalid resource URI '{}': expected {}/<path>",
+                uri, URI_PREFIX
+            ),
+        )
+    })
+}
+
+fn vfs_error(uri: &str, e: VfsError) -> JsonRpcError {
+    match e {
+        VfsError::NotFound(_) | VfsError::NoMount(_) => {
+            rpc_error(RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri))
+        }
+        e => rpc_error(INTERNAL_ERROR, format!("Failed to read {}: {}", uri, e)),
+    }
+}
+
+/// Serves `resources/*` requests and tracks subscriptions.
+pub struct ResourceProvider {
+    vfs: Arc<Vfs>,
+    /// Path prefix the session is confined to.
+    root: Option<String>,
+    /// Subscribed VFS paths.
+    subscriptions: Mutex<BTreeSet<String>>,
+}
+
+impl ResourceProvider {
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        ResourceProvider {
+            vfs,
+            root: None,
+            subscriptions: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Only expose resources under `root`.
+    pub fn with_root(mut self, root: Option<String>) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Resource roots: each mount, or the session root where it lies inside a mount.
+    fn roots(&self) -> Vec<(String, openfs_config::MountConfig)> {
+        self.vfs
+            .effective_config()
+            .mounts
+            .iter()
+            .filter_map(|mount| match &self.root {
+                None => Some((mount.path.clone(), mount.clone())),
+                Some(root) if is_within(&mount.path, root) => {
+                    Some((mount.path.clone(), mount.clone()))
+                }
+                Some(root) if is_within(root, &mount.path) => Some((root.clone(), mount.clone())),
+                Some(_) => None,
+            })
+            .collect()
+    }
+
+    /// Parse a URI and check it is inside the session root.
+    fn resolve(&self, uri: &str) -> Result<String, JsonRpcError> {
+        let path = parse_uri(uri)?;
+        match &self.root {
+            Some(root) if !is_within(&path, root) => Err(rpc_error(
+                INVALID_PARAMS,
+                format!("Resource '{}' is outside the session root '{}'", uri, root),
+            )),
+            _ => Ok(path),
+        }
+    }
+
+    /// List mount roots as resources.
+    pub fn list(&self) -> ResourceListResult {
+        let resources = self
+            .roots()
+            .into_iter()
+            .map(|(path, mount)| {
+                let backend = mount.backend.as_deref().unwrap_or("default");
+                let access = if mount.read_only { ", read-only" } else { "" };
+                McpResource {
+                    uri: path_to_uri(&path),
+                    name: path,
+                    description: Some(format!("OpenFS mount ({}{})", backend, access)),
+                    mime_type: Some(DIRECTORY_MIME_TYPE.to_string()),
+                }
+            })
+            .collect();
+        ResourceListResult { resources }
+    }
+
+    /// List one URI template per mount for addressing files below it.
+    pub fn templates(&self) -> ResourceTemplateListResult {
+        let resource_templates = self
+            .roots()
+            .into_iter()
+            .map(|(path, _)| McpResourceTemplate {
+                uri_template: format!("{}/{{+path}}", path_to_uri(&path)),
+                name: format!("Files under {}", path),
+                description: Some(format!("Read any file or directory in {}", path)),
+            })
+            .collect();
+        ResourceTemplateListResult { resource_templates }
+    }
+
+    /// Read a resource. Directories are returned as a JSON listing of child URIs.
+    pub async fn read(&self, uri: &str) -> Result<ResourceReadResult, JsonRpcError> {
+        let path = self.resolve(uri)?;
+        let entry = self.vfs.stat(&path).await.map_err(|e| vfs_error(uri, e))?;
+
+        let contents = if entry.is_dir {
+            let entries = self.vfs.list(&path).await.map_err(|e| vfs_error(uri, e))?;
+            let listing: Vec<serde_json::Value> = entries
+                .iter()
+                .map(|e| {
+                    let child = format!("{}/{}", path.trim_end_matches('/'), e.name);
+                    serde_json::json!({
+                        "uri": path_to_uri(&child),
+                        "name": e.name,
+                        "is_dir": e.is_dir,
+                        "size": e.size,
+                    })
+                })
+                .collect();
+            ResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: Some(serde_json::Value::Array(listing).to_string()),
+                blob: None,
+            }
+        } else {
+            let content = self.vfs.read(&path).await.map_err(|e| vfs_error(uri, e))?;
+            let mime_type = detect_content_type(&path, &content);
+            match String::from_utf8(content) {
+                Ok(text) if is_text_content_type(mime_type) => ResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(mime_type.to_string()),
+                    text: Some(text),
+                    blob: None,
+                },
+                result => {
+                    let bytes = match result {
+                        Ok(text) => text.into_bytes(),
+                        Err(e) => e.into_bytes(),
+                    };
+                    let mime_type = match mime_type {
+                        TEXT_PLAIN => OCTET_STREAM,
+                        other => other,
+                    };
+                    ResourceContents {
+                        uri: uri.to_string(),
+                        mime_type: Some(mime_type.to_string()),
+                        text: None,
+                        blob: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+                    }
+                }
+            }
+        };
+
+        Ok(ResourceReadResult {
+            contents: vec![contents],
+        })
+    }
+
+    /// Subscribe to updates for a resource. Subscribing to a directory
+    /// reports changes anywhere below it.
+    pub fn subscribe(&self, uri: &str) -> Result<(), JsonRpcError> {
+        let path = self.resolve(uri)?;
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path);
+        Ok(())
+    }
+
+    /// Remove a subscription.
+    pub fn unsubscribe(&self, uri: &str) -> Result<(), JsonRpcError> {
+        let path = parse_uri(uri)?;
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&path);
+        Ok(())
+    }
+
+    /// URIs of subscriptions affected by a change to `path`.
+    pub fn subscribed_uris(&self, path: &str) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|sub| is_within(path, sub))
+            .map(|sub| path_to_uri(sub))
+            .collect()
+    }
+
+    /// Watch every local `fs` mount and send `notifications/resources/updated`
+    /// for subscribed resources. Returns the number of mounts being watched.
+    ///
+    /// Besides the subscribed `uri`, each notification carries the changed
+    /// VFS `path`, the change `kind` and an RFC 3339 `timestamp`.
+    pub fn spawn_watcher(
+        self: &Arc<Self>,
+        tx: mpsc::UnboundedSender<JsonRpcNotification>,
+    ) -> Result<usize, VfsError> {
+        let mut watched = 0;
+        for mount in &self.vfs.effective_config().mounts {
+            let Some(root) = self.vfs.resolve_fs_path(&mount.path) else {
+                continue;
+            };
+            // notify reports absolute paths, so match against the canonical root.
+            let root: PathBuf = match root.canonicalize() {
+                Ok(root) => root,
+                Err(e) => {
+                    warn!("Not watching {}: {}", mount.path, e);
+                    continue;
+                }
+            };
+
+            let mut engine = WatchEngine::new()?;
+            engine.watch_path(&root)?;
+            let mut rx = engine
+                .take_receiver()
+                .ok_or_else(|| VfsError::Watch("Watch receiver already taken".to_string()))?;
+
+            let provider = Arc::clone(self);
+            let tx = tx.clone();
+            let mount_path = mount.path.trim_end_matches('/').to_string();
+            tokio::spawn(async move {
+                // Keep the watcher alive for as long as events are forwarded.
+                let _engine = engine;
+                while let Some(change) = rx.recv().await {
+                    let Ok(relative) = change.path.strip_prefix(&root) else {
+                        continue;
+                    };
+                    let relative = relative.to_string_lossy();
+                    let vfs_path = if relative.is_empty() {
+                        mount_path.clone()
+                    } else {
+                        format!("{}/{}", mount_path, relative)
+                    };
+
+                    let timestamp = DateTime::<Utc>::from(change.timestamp).to_rfc3339();
+                    for uri in provider.subscribed_uris(&vfs_path) {
+                        debug!(uri = %uri, kind = %change.kind, "resource updated");
+                        let notification = JsonRpcNotification::new(
+                            "notifications/resources/updated",
+                            serde_json::json!({
+                                "uri": uri,
+                                "path": vfs_path,
+                                "kind": change.kind.to_string(),
+                                "timestamp": timestamp,
+                            }),
+                        );
+                        if tx.send(notification).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+            watched += 1;
+        }
+        Ok(watched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+    use tempfile::TempDir;
+
+    async fn make_provider(tmp: &TempDir) -> Arc<ResourceProvider> {
+        let yaml = format!(
+            r#"
+name: test
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: local
+"#,
+            tmp.path().to_str().unwrap()
+        );
+        let config = VfsConfig::from_yaml(&yaml).unwrap();
+        let vfs = Arc::new(Vfs::from_config(config).await.unwrap());
+        Arc::new(ResourceProvider::new(vfs))
+    }
+
+    #[test]
+    fn test_uri_roundtrip() {
+        assert_eq!(path_to_uri("/workspace/a.md"), "openfs:///workspace/a.md");
+        assert_eq!(
+            path_to_uri("/workspace/my notes.md"),
+            "openfs:///workspace/my%20notes.md"
+        );
+        assert_eq!(
+            uri_to_path("openfs:///workspace/my%20notes.md").as_deref(),
+            Some("/workspace/my notes.md")
+        );
+        assert_eq!(
+            uri_to_path("openfs:///workspace/").as_deref(),
+            Some("/workspace")
+        );
+        assert_eq!(uri_to_path("file:///workspace/a.md"), None);
+        assert_eq!(uri_to_path("openfs://workspace/a.md"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_and_directory() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("notes.md"), "# Notes").unwrap();
+        std::fs::write(tmp.path().join("image.png"), [0x89, 0x50, 0xff, 0xfe]).unwrap();
+        let provider = make_provider(&tmp).await;
+
+        let result = provider.read("openfs:///workspace/notes.md").await.unwrap();
+        assert_eq!(result.contents[0].text.as_deref(), Some("# Notes"));
+        assert_eq!(
+            result.contents[0].mime_type.as_deref(),
+            Some("text/markdown")
+        );
+
+        let result = provider
+            .read("openfs:///workspace/image.png")
+            .await
+            .unwrap();
+        assert_eq!(result.contents[0].blob.as_deref(), Some("iVD//g=="));
+
+        let result = provider.read("openfs:///workspace").await.unwrap();
+        let listing: serde_json::Value =
+            serde_json::from_str(result.contents[0].text.as_deref().unwrap()).unwrap();
+        let uris: Vec<&str> = listing
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["uri"].as_str().unwrap())
+            .collect();
+        assert!(uris.contains(&"openfs:///workspace/notes.md"));
+
+        let err = provider
+            .read("openfs:///workspace/missing.md")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_uris() {
+        let tmp = TempDir::new().unwrap();
+        let provider = make_provider(&tmp).await;
+
+        provider.subscribe("openfs:///workspace/docs").unwrap();
+        provider.subscribe("openfs:///workspace/a.md").unwrap();
+        assert_eq!(
+            provider.subscribed_uris("/workspace/docs/guide.md"),
+            vec!["openfs:///workspace/docs"]
+        );
+        assert_eq!(
+            provider.subscribed_uris("/workspace/a.md"),
+            vec!["openfs:///workspace/a.md"]
+        );
+        assert!(provider.subscribed_uris("/workspace/b.md").is_empty());
+
+        provider.unsubscribe("openfs:///workspace/a.md").unwrap();
+        assert!(provider.subscribed_uris("/workspace/a.md").is_empty());
+        assert!(provider.subscribe("http://example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_root() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("project")).unwrap();
+        std::fs::write(tmp.path().join("project/a.md"), "A").unwrap();
+        std::fs::write(tmp.path().join("secret.env"), "KEY=1").unwrap();
+        let vfs = make_provider(&tmp).await.vfs.clone();
+        let provider = ResourceProvider::new(vfs).with_root(Some("/workspace/project".to_string()));
+
+        let list = provider.list();
+        assert_eq!(list.resources.len(), 1);
+        assert_eq!(list.resources[0].uri, "openfs:///workspace/project");
+
+        assert!(provider
+            .read("openfs:///workspace/project/a.md")
+            .await
+            .is_ok());
+        let err = provider
+            .read("openfs:///workspace/project/../secret.env")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_sends_updated_notification() {
+        let tmp = TempDir::new().unwrap();
+        let provider = make_provider(&tmp).await;
+        provider.subscribe("openfs:///workspace/live.txt").unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        assert_eq!(provider.spawn_watcher(tx).unwrap(), 1);
+
+        std::fs::write(tmp.path().join("live.txt"), "changed").unwrap();
+        let notification = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("timed out waiting for notification")
+            .unwrap();
+        assert_eq!(notification.method, "notifications/resources/updated");
+        let params = notification.params.unwrap();
+        assert_eq!(params["uri"], "openfs:///workspace/live.txt");
+        assert_eq!(params["path"], "/workspace/live.txt");
+        assert!(["created", "modified"].contains(&params["kind"].as_str().unwrap()));
+        assert!(DateTime::parse_from_rfc3339(params["timestamp"].as_str().unwrap()).is_ok());
+    }
+}
@@ -1,49 +1,92 @@
 //! MCP server — reads JSON-RPC from stdin, writes to stdout.
 
-#[cfg(test)]
 use std::sync::Arc;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tracing::{debug, error, info};
+use futures::stream::{FuturesUnordered, StreamExt};
+use openfs_core::{new_request_id, with_request_id};
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
 
 use crate::handler::McpHandler;
 use crate::protocol::*;
+use crate::resources::ResourceProvider;
+
+/// Requests handled at once; further input waits until one finishes.
+const MAX_IN_FLIGHT: usize = 64;
 
 /// MCP server that communicates over stdio.
 pub struct McpServer {
     handler: McpHandler,
+    resources: Arc<ResourceProvider>,
 }
 
 impl McpServer {
     pub fn new(handler: McpHandler) -> Self {
-        McpServer { handler }
+        let resources = Arc::new(
+            ResourceProvider::new(handler.vfs().clone())
+                .with_root(handler.policy().root().map(String::from)),
+        );
+        McpServer { handler, resources }
     }
 
     /// Run the server, reading JSON-RPC messages from stdin and writing responses to stdout.
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
-        let reader = BufReader::new(stdin);
-        let mut lines = reader.lines();
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
 
-        info!("OpenFS MCP server started (stdio transport)");
+    /// Serve JSON-RPC messages, one per line, until `input` closes.
+    ///
+    /// Requests run concurrently, so a slow tool call does not hold up the
+    /// ones behind it; responses are written as they complete and clients
+    /// match them by id. Resource update notifications from the watch
+    /// subsystem are interleaved with responses.
+    pub async fn serve<R, W>(
+        &self,
+        input: R,
+        mut output: W,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = BufReader::new(input).lines();
+        let mut input_open = true;
+        let mut in_flight = FuturesUnordered::new();
+
+        let (notify_tx, mut notify_rx) = mpsc::unbounded_channel();
+        match self.resources.spawn_watcher(notify_tx) {
+            Ok(watched) => debug!("Watching {} mount(s) for resource updates", watched),
+            Err(e) => warn!("Resource update notifications disabled: {}", e),
+        }
 
-        while let Some(line) = lines.next_line().await? {
-            let line = line.trim().to_string();
-            if line.is_empty() {
-                continue;
-            }
+        info!("OpenFS MCP server started (stdio transport)");
 
-            debug!("Received: {}", line);
+        while input_open || !in_flight.is_empty() {
+            tokio::select! {
+                line = lines.next_line(), if input_open && in_flight.len() < MAX_IN_FLIGHT => {
+                    let Some(line) = line? else {
+                        input_open = false;
+                        continue;
+                    };
+                    let line = line.trim().to_string();
+                    if line.is_empty() {
+                        continue;
+                    }
 
-            let response = self.handle_message(&line).await;
+                    debug!("Received: {}", line);
 
-            if let Some(resp) = response {
-                let json = serde_json::to_string(&resp)?;
-                debug!("Sending: {}", json);
-                stdout.write_all(json.as_bytes()).await?;
-                stdout.write_all(b"\n").await?;
-                stdout.flush().await?;
+                    in_flight.push(async move { self.handle_message(&line).await });
+                }
+                Some(resp) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Some(resp) = resp {
+                        write_message(&mut output, &resp).await?;
+                    }
+                }
+                Some(notification) = notify_rx.recv() => {
+                    write_message(&mut output, &notification).await?;
+                }
             }
         }
 
@@ -53,6 +96,9 @@ impl McpServer {
 
     /// Process a single JSON-RPC message and return an optional response.
     /// Returns None for notifications (no id).
+    ///
+    /// The request runs under the ID in `params._meta.requestId`, or a new
+    /// one, which error responses report.
     pub async fn handle_message(&self, line: &str) -> Option<JsonRpcResponse> {
         let request: JsonRpcRequest = match serde_json::from_str(line) {
             Ok(req) => req,
@@ -72,6 +118,18 @@ impl McpServer {
             return None;
         }
 
+        let request_id = request
+            .params
+            .as_ref()
+            .and_then(|params| params.pointer("/_meta/requestId"))
+            .and_then(|id| id.as_str())
+            .map(String::from)
+            .unwrap_or_else(new_request_id);
+        with_request_id(request_id, self.dispatch(request)).await
+    }
+
+    /// Answer a request, as part of [`handle_message`](Self::handle_message).
+    async fn dispatch(&self, request: JsonRpcRequest) -> Option<JsonRpcResponse> {
         let id = request.id.clone();
 
         match request.method.as_str() {
@@ -82,6 +140,10 @@ impl McpServer {
                         tools: Some(ToolsCapability {
                             list_changed: Some(false),
                         }),
+                        resources: Some(ResourcesCapability {
+                            subscribe: Some(true),
+                            list_changed: Some(false),
+                        }),
                     },
                     server_info: ServerInfo {
                         name: "openfs-mcp".to_string(),
@@ -159,6 +221,31 @@ impl McpServer {
                     )),
                 }
             }
+            "resources/list" => Some(to_response(id, self.resources.list())),
+            "resources/templates/list" => Some(to_response(id, self.resources.templates())),
+            "resources/read" => {
+                let result = match uri_params(request.params) {
+                    Ok(params) => self.resources.read(&params.uri).await,
+                    Err(e) => Err(e),
+                };
+                Some(match result {
+                    Ok(result) => to_response(id, result),
+                    Err(e) => JsonRpcResponse::error(id, e.code, e.message),
+                })
+            }
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let result = uri_params(request.params).and_then(|params| {
+                    if request.method == "resources/subscribe" {
+                        self.resources.subscribe(&params.uri)
+                    } else {
+                        self.resources.unsubscribe(&params.uri)
+                    }
+                });
+                Some(match result {
+                    Ok(()) => JsonRpcResponse::success(id, serde_json::json!({})),
+                    Err(e) => JsonRpcResponse::error(id, e.code, e.message),
+                })
+            }
             "ping" => Some(JsonRpcResponse::success(id, serde_json::json!({}))),
             _ => Some(JsonRpcResponse::error(
                 id,
@@ -169,6 +256,39 @@ impl McpServer {
     }
 }
 
+/// Write one JSON-RPC message as a line.
+async fn write_message<W: AsyncWrite + Unpin, T: Serialize>(
+    output: &mut W,
+    message: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let json = serde_json::to_string(message)?;
+    debug!("Sending: {}", json);
+    output.write_all(json.as_bytes()).await?;
+    output.write_all(b"\n").await?;
+    output.flush().await?;
+    Ok(())
+}
+
+fn to_response<T: Serialize>(id: Option<serde_json::Value>, result: T) -> JsonRpcResponse {
+    match serde_json::to_value(result) {
+        Ok(v) => JsonRpcResponse::success(id, v),
+        Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, format!("Serialization error: {}", e)),
+    }
+}
+
+fn uri_params(params: Option<serde_json::Value>) -> Result<ResourceUriParams, JsonRpcError> {
+    let params = params.ok_or_else(|| JsonRpcError {
+        code: INVALID_PARAMS,
+        message: "Missing params".to_string(),
+        data: None,
+    })?;
+    serde_json::from_value(params).map_err(|e| JsonRpcError {
+        code: INVALID_PARAMS,
+        message: format!("Invalid params: {}", e),
+        data: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,6 +378,22 @@ mounts:
         assert_eq!(parsed["content"], "written via mcp");
     }
 
+    #[tokio::test]
+    async fn test_errors_report_the_request_id() {
+        let tmp = TempDir::new().unwrap();
+        let server = make_server(&tmp).await;
+
+        let msg = r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"openfs_read","arguments":{"path":"/workspace/missing.txt"},"_meta":{"requestId":"req-42"}}}"#;
+        let resp = server.handle_message(msg).await.unwrap();
+        let result = resp.result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["error"]["request_id"], "req-42");
+
+        let msg = r#"{"jsonrpc":"2.0","id":5,"method":"unknown/method"}"#;
+        let error = server.handle_message(msg).await.unwrap().error.unwrap();
+        assert!(error.data.unwrap()["request_id"].is_string());
+    }
+
     #[tokio::test]
     async fn test_notification_no_response() {
         let tmp = TempDir::new().unwrap();
@@ -315,6 +451,112 @@ mounts:
         assert!(text.contains("file2.txt"));
     }
 
+    #[tokio::test]
+    async fn test_resources_list_and_read() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("readme.md"), "# OpenFS").unwrap();
+        let server = make_server(&tmp).await;
+
+        let msg = r#"{"jsonrpc":"2.0","id":10,"method":"resources/list"}"#;
+        let result = server.handle_message(msg).await.unwrap().result.unwrap();
+        assert_eq!(result["resources"][0]["uri"], "openfs:///workspace");
+
+        let msg = r#"{"jsonrpc":"2.0","id":11,"method":"resources/templates/list"}"#;
+        let result = server.handle_message(msg).await.unwrap().result.unwrap();
+        assert_eq!(
+            result["resourceTemplates"][0]["uriTemplate"],
+            "openfs:///workspace/{+path}"
+        );
+
+        let msg = r#"{"jsonrpc":"2.0","id":12,"method":"resources/read","params":{"uri":"openfs:///workspace/readme.md"}}"#;
+        let result = server.handle_message(msg).await.unwrap().result.unwrap();
+        assert_eq!(result["contents"][0]["text"], "# OpenFS");
+        assert_eq!(result["contents"][0]["mimeType"], "text/markdown");
+
+        let msg = r#"{"jsonrpc":"2.0","id":13,"method":"resources/read","params":{"uri":"openfs:///workspace/nope.md"}}"#;
+        let resp = server.handle_message(msg).await.unwrap();
+        assert_eq!(resp.error.unwrap().code, RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_resources_subscribe() {
+        let tmp = TempDir::new().unwrap();
+        let server = make_server(&tmp).await;
+
+        let msg = r#"{"jsonrpc":"2.0","id":14,"method":"initialize","params":{}}"#;
+        let result = server.handle_message(msg).await.unwrap().result.unwrap();
+        assert_eq!(result["capabilities"]["resources"]["subscribe"], true);
+
+        let msg = r#"{"jsonrpc":"2.0","id":15,"method":"resources/subscribe","params":{"uri":"openfs:///workspace/a.md"}}"#;
+        assert!(server.handle_message(msg).await.unwrap().error.is_none());
+
+        let msg = r#"{"jsonrpc":"2.0","id":16,"method":"resources/subscribe","params":{"uri":"https://example.com"}}"#;
+        let resp = server.handle_message(msg).await.unwrap();
+        assert_eq!(resp.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    /// Blocks every search until released.
+    struct GatedSearcher(Arc<tokio::sync::Notify>);
+
+    #[async_trait::async_trait]
+    impl openfs_core::Searcher for GatedSearcher {
+        async fn search(
+            &self,
+            _query: &str,
+            _options: &openfs_core::SearchOptions,
+        ) -> Result<Vec<openfs_core::SearchHit>, openfs_core::VfsError> {
+            self.0.notified().await;
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_answers_around_slow_calls() {
+        let tmp = TempDir::new().unwrap();
+        let server = make_server(&tmp).await;
+        let gate = Arc::new(tokio::sync::Notify::new());
+        server
+            .handler
+            .vfs()
+            .set_searcher("/workspace", Arc::new(GatedSearcher(gate.clone())));
+
+        let (mut client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, client_out) = tokio::io::duplex(4096);
+        let client = async move {
+            let mut responses = BufReader::new(client_out).lines();
+            client_in
+                .write_all(
+                    concat!(
+                        r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"openfs_search","arguments":{"query":"q"}}}"#,
+                        "\n",
+                        r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#,
+                        "\n",
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+
+            let mut ids = Vec::new();
+            let first: serde_json::Value =
+                serde_json::from_str(&responses.next_line().await.unwrap().unwrap()).unwrap();
+            ids.push(first["id"].as_u64().unwrap());
+
+            gate.notify_one();
+            let second: serde_json::Value =
+                serde_json::from_str(&responses.next_line().await.unwrap().unwrap()).unwrap();
+            ids.push(second["id"].as_u64().unwrap());
+
+            // Closing the input ends the server once nothing is in flight.
+            drop(client_in);
+            ids
+        };
+
+        let (served, ids) = tokio::join!(server.serve(server_in, server_out), client);
+        assert!(served.is_ok());
+        assert_eq!(ids, vec![2, 1]);
+    }
+
     #[tokio::test]
     async fn test_missing_params() {
         let tmp = TempDir::new().unwrap();
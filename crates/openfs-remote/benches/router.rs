@@ -0,0 +1,65 @@
+//! Mount resolution as the number of mounts grows.
+//!
+//! Run with `cargo bench -p openfs-remote --bench router`.
+
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use openfs_config::PathPolicyConfig;
+use openfs_core::Backend;
+use openfs_remote::{MemoryBackend, Mount, Router};
+
+fn router(mounts: usize, case_fold: bool) -> Router {
+    let backend: Arc<dyn Backend> = Arc::new(MemoryBackend::new());
+    let mut all = vec![Mount {
+        path: "/".to_string(),
+        backend: backend.clone(),
+        read_only: false,
+        path_policy: PathPolicyConfig::default(),
+    }];
+    all.extend((0..mounts).map(|i| Mount {
+        path: format!("/tenants/t{}/data", i),
+        backend: backend.clone(),
+        read_only: false,
+        path_policy: PathPolicyConfig {
+            case_fold,
+            ..PathPolicyConfig::default()
+        },
+    }));
+    Router::new(all)
+}
+
+fn bench_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for mounts in [10, 100, 1000] {
+        let path = format!("/tenants/t{}/data/reports/q1.md", mounts / 2);
+        for (name, case_fold) in [("exact", false), ("case_fold", true)] {
+            let router = router(mounts, case_fold);
+            group.bench_with_input(BenchmarkId::new(name, mounts), &path, |b, path| {
+                b.iter(|| router.resolve(black_box(path)).unwrap().1)
+            });
+        }
+        let router = router(mounts, false);
+        group.bench_with_input(
+            BenchmarkId::new("root_fallback", mounts),
+            &mounts,
+            |b, _| b.iter(|| router.resolve(black_box("/home/user/notes.md")).unwrap().1),
+        );
+    }
+    group.finish();
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for mounts in [10, 100, 1000] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(mounts),
+            &mounts,
+            |b, &mounts| b.iter(|| router(mounts, false)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_resolve, bench_build);
+criterion_main!(benches);
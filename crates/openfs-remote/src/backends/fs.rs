@@ -1,3 +1,4 @@
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
@@ -5,14 +6,20 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use std::path::Component;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::{debug, instrument};
 
-use openfs_core::{Backend, BackendError, Entry};
+use openfs_core::{Backend, BackendError, Entry, FileMeta};
+
+/// Directory under the root holding user metadata, one JSON file per file,
+/// mirroring the tree beside it.
+const META_DIR: &str = ".openfs-meta";
 
 /// Local filesystem backend.
 pub struct FsBackend {
     root: PathBuf,
+    /// Makes each compare-and-swap's check and write one step.
+    cas_lock: tokio::sync::Mutex<()>,
 }
 
 impl FsBackend {
@@ -34,7 +41,10 @@ impl FsBackend {
             root.canonicalize().map_err(BackendError::Io)?
         };
 
-        Ok(FsBackend { root })
+        Ok(FsBackend {
+            root,
+            cas_lock: tokio::sync::Mutex::new(()),
+        })
     }
 
     /// Resolve a relative path to an absolute path, preventing directory traversal.
@@ -52,6 +62,14 @@ impl FsBackend {
             }
         }
 
+        if rel
+            .components()
+            .next()
+            .is_some_and(|first| first.as_os_str() == META_DIR)
+        {
+            return Err(BackendError::PermissionDenied(trimmed.to_string()));
+        }
+
         let full_path = self.root.join(rel);
 
         // Find the nearest existing ancestor and ensure it resolves under root.
@@ -81,6 +99,72 @@ impl FsBackend {
             .unwrap_or(0);
         format!("{}:{}", metadata.len(), modified_ns)
     }
+
+    /// Where the user metadata of the file at `full_path` is kept.
+    fn meta_path(&self, full_path: &Path) -> Option<PathBuf> {
+        let rel = full_path.strip_prefix(&self.root).ok()?;
+        if rel.as_os_str().is_empty() {
+            return None;
+        }
+        Some(self.root.join(META_DIR).join(rel))
+    }
+
+    async fn load_meta(&self, full_path: &Path) -> Result<FileMeta, BackendError> {
+        let Some(meta_path) = self.meta_path(full_path) else {
+            return Ok(FileMeta::new());
+        };
+        match fs::read(&meta_path).await {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| {
+                BackendError::Other(format!(
+                    "invalid metadata in {}: {}",
+                    meta_path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FileMeta::new()),
+            Err(e) => Err(BackendError::Io(e)),
+        }
+    }
+
+    /// Drop or move the metadata kept for a deleted or renamed path.
+    async fn move_meta(&self, from: &Path, to: Option<&Path>) -> Result<(), BackendError> {
+        let Some(from_meta) = self.meta_path(from) else {
+            return Ok(());
+        };
+        let Some(to_meta) = to.and_then(|to| self.meta_path(to)) else {
+            return remove_meta(&from_meta).await;
+        };
+        // What was kept for a replaced destination no longer applies.
+        remove_meta(&to_meta).await?;
+        if !fs::try_exists(&from_meta).await.map_err(BackendError::Io)? {
+            return Ok(());
+        }
+        if let Some(parent) = to_meta.parent() {
+            fs::create_dir_all(parent).await.map_err(BackendError::Io)?;
+        }
+        fs::rename(&from_meta, &to_meta)
+            .await
+            .map_err(BackendError::Io)
+    }
+
+    /// Sibling to write a file's new content to before moving it in place.
+    fn staging_path(full_path: &Path) -> PathBuf {
+        let mut staging = full_path.as_os_str().to_owned();
+        staging.push(format!(".cas-tmp-{}", std::process::id()));
+        PathBuf::from(staging)
+    }
+}
+
+async fn remove_meta(meta_path: &Path) -> Result<(), BackendError> {
+    let result = if meta_path.is_dir() {
+        fs::remove_dir_all(meta_path).await
+    } else {
+        fs::remove_file(meta_path).await
+    };
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(BackendError::Io(e)),
+        _ => Ok(()),
+    }
 }
 
 #[async_trait]
@@ -98,6 +182,35 @@ impl Backend for FsBackend {
         })
     }
 
+    #[instrument(skip(self), fields(backend = "fs", path = %path))]
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        let full_path = self.resolve_path(path)?;
+        debug!(full_path = ?full_path, offset, len, "reading file range");
+        let mut file = fs::File::open(&full_path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                BackendError::NotFound(path.to_string())
+            } else {
+                BackendError::Io(e)
+            }
+        })?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(BackendError::Io)?;
+
+        let mut content = Vec::new();
+        match len {
+            Some(len) => file.take(len).read_to_end(&mut content).await,
+            None => file.read_to_end(&mut content).await,
+        }
+        .map_err(BackendError::Io)?;
+        Ok(content)
+    }
+
     #[instrument(skip(self, content), fields(backend = "fs", path = %path, size = content.len()))]
     async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         let full_path = self.resolve_path(path)?;
@@ -118,14 +231,20 @@ impl Backend for FsBackend {
         path: &str,
     ) -> Result<(Vec<u8>, Option<String>), BackendError> {
         let full_path = self.resolve_path(path)?;
-        let content = self.read(path).await?;
-        let metadata = fs::metadata(&full_path).await.map_err(|e| {
+        // Content and token come from one handle; compare-and-swap replaces
+        // the file rather than rewriting it, so they always match.
+        let mut file = fs::File::open(&full_path).await.map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 BackendError::NotFound(path.to_string())
             } else {
                 BackendError::Io(e)
             }
         })?;
+        let metadata = file.metadata().await.map_err(BackendError::Io)?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .await
+            .map_err(BackendError::Io)?;
         Ok((content, Some(Self::cas_token_for_metadata(&metadata))))
     }
 
@@ -136,6 +255,7 @@ impl Backend for FsBackend {
         content: &[u8],
     ) -> Result<Option<String>, BackendError> {
         let full_path = self.resolve_path(path)?;
+        let _guard = self.cas_lock.lock().await;
 
         if let Some(expected_token) = expected {
             let actual_token = match fs::metadata(&full_path).await {
@@ -156,7 +276,12 @@ impl Backend for FsBackend {
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent).await.map_err(BackendError::Io)?;
         }
-        fs::write(&full_path, content)
+        // Replace the file whole so readers never see it half written.
+        let staging = Self::staging_path(&full_path);
+        fs::write(&staging, content)
+            .await
+            .map_err(BackendError::Io)?;
+        fs::rename(&staging, &full_path)
             .await
             .map_err(BackendError::Io)?;
 
@@ -164,6 +289,44 @@ impl Backend for FsBackend {
         Ok(Some(Self::cas_token_for_metadata(&metadata)))
     }
 
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let full_path = self.resolve_path(path)?;
+        let _guard = self.cas_lock.lock().await;
+
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await.map_err(BackendError::Io)?;
+        }
+        // Linking fails if the name is taken, also against other processes.
+        let staging = Self::staging_path(&full_path);
+        fs::write(&staging, content)
+            .await
+            .map_err(BackendError::Io)?;
+        let linked = fs::hard_link(&staging, &full_path).await;
+        let _ = fs::remove_file(&staging).await;
+        match linked {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let actual = fs::metadata(&full_path)
+                    .await
+                    .map(|metadata| Self::cas_token_for_metadata(&metadata))
+                    .unwrap_or_else(|_| "unknown".to_string());
+                return Err(BackendError::PreconditionFailed {
+                    path: path.to_string(),
+                    expected: "absent".to_string(),
+                    actual,
+                });
+            }
+            Err(e) => return Err(BackendError::Io(e)),
+        }
+
+        let metadata = fs::metadata(&full_path).await.map_err(BackendError::Io)?;
+        Ok(Some(Self::cas_token_for_metadata(&metadata)))
+    }
+
     #[instrument(skip(self, content), fields(backend = "fs", path = %path, size = content.len()))]
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         let full_path = self.resolve_path(path)?;
@@ -181,7 +344,10 @@ impl Backend for FsBackend {
             .await
             .map_err(BackendError::Io)?;
 
-        file.write_all(content).await.map_err(BackendError::Io)
+        file.write_all(content).await.map_err(BackendError::Io)?;
+        // tokio writes on a blocking thread; flush so the data has landed
+        // before we return.
+        file.flush().await.map_err(BackendError::Io)
     }
 
     #[instrument(skip(self), fields(backend = "fs", path = %path))]
@@ -192,7 +358,7 @@ impl Backend for FsBackend {
         if full_path.is_dir() {
             fs::remove_dir_all(&full_path)
                 .await
-                .map_err(BackendError::Io)
+                .map_err(BackendError::Io)?;
         } else {
             fs::remove_file(&full_path).await.map_err(|e| {
                 if e.kind() == std::io::ErrorKind::NotFound {
@@ -200,8 +366,9 @@ impl Backend for FsBackend {
                 } else {
                     BackendError::Io(e)
                 }
-            })
+            })?;
         }
+        self.move_meta(&full_path, None).await
     }
 
     #[instrument(skip(self), fields(backend = "fs", path = %path))]
@@ -229,6 +396,9 @@ impl Backend for FsBackend {
         while let Some(entry) = read_dir.next_entry().await.map_err(BackendError::Io)? {
             let metadata = entry.metadata().await.map_err(BackendError::Io)?;
             let name = entry.file_name().to_string_lossy().to_string();
+            if full_path == self.root && name == META_DIR {
+                continue;
+            }
 
             let entry_path = if path.is_empty() || path == "/" {
                 format!("/{}", name)
@@ -283,15 +453,67 @@ impl Backend for FsBackend {
         if metadata.is_dir() {
             Ok(Entry::dir(path.to_string(), name, modified))
         } else {
-            Ok(Entry::file(
-                path.to_string(),
-                name,
-                metadata.len(),
-                modified,
-            ))
+            Ok(
+                Entry::file(path.to_string(), name, metadata.len(), modified)
+                    .with_meta(self.load_meta(&full_path).await?),
+            )
         }
     }
 
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        let full_path = self.resolve_path(path)?;
+        if !full_path.is_file() {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        self.load_meta(&full_path).await
+    }
+
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        let full_path = self.resolve_path(path)?;
+        if !full_path.is_file() {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let Some(meta_path) = self.meta_path(&full_path) else {
+            return Err(BackendError::NotFound(path.to_string()));
+        };
+        if meta.is_empty() {
+            return remove_meta(&meta_path).await;
+        }
+        if let Some(parent) = meta_path.parent() {
+            fs::create_dir_all(parent).await.map_err(BackendError::Io)?;
+        }
+        let json = serde_json::to_vec(meta).map_err(|e| BackendError::Other(e.to_string()))?;
+        fs::write(&meta_path, json).await.map_err(BackendError::Io)
+    }
+
+    #[instrument(skip(self), fields(backend = "fs", path = %path))]
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        let full_path = self.resolve_path(path)?;
+        debug!(full_path = ?full_path, %modified, "setting modification time");
+        let file = fs::File::options()
+            .write(true)
+            .open(&full_path)
+            .await
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    BackendError::NotFound(path.to_string())
+                } else {
+                    BackendError::Io(e)
+                }
+            })?
+            .into_std()
+            .await;
+        tokio::task::spawn_blocking(move || file.set_modified(modified.into()))
+            .await
+            .map_err(|e| BackendError::Other(e.to_string()))?
+            .map_err(BackendError::Io)?;
+        Ok(true)
+    }
+
     #[instrument(skip(self), fields(backend = "fs", from = %from, to = %to))]
     async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
         let from_path = self.resolve_path(from)?;
@@ -309,7 +531,8 @@ impl Backend for FsBackend {
             } else {
                 BackendError::Io(e)
             }
-        })
+        })?;
+        self.move_meta(&from_path, Some(&to_path)).await
     }
 }
 
@@ -409,4 +632,61 @@ mod tests {
             .unwrap_err();
         assert!(matches!(err, BackendError::PreconditionFailed { .. }));
     }
+
+    #[tokio::test]
+    async fn test_create_if_absent() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = FsBackend::new(temp_dir.path()).unwrap();
+
+        let token = backend
+            .create_if_absent("dir/new.txt", b"v1")
+            .await
+            .expect("create should succeed")
+            .expect("token should be present");
+        let (content, read_token) = backend.read_with_cas_token("dir/new.txt").await.unwrap();
+        assert_eq!(content, b"v1");
+        assert_eq!(read_token.as_deref(), Some(token.as_str()));
+
+        let err = backend
+            .create_if_absent("dir/new.txt", b"v2")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BackendError::PreconditionFailed { .. }));
+        assert_eq!(backend.read("dir/new.txt").await.unwrap(), b"v1");
+        // The staging file is cleaned up either way.
+        assert_eq!(backend.list("dir").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_meta_sidecar() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = FsBackend::new(temp_dir.path()).unwrap();
+        backend.write("docs/a.txt", b"a").await.unwrap();
+
+        let meta = FileMeta::from([("lang".to_string(), "en".to_string())]);
+        backend.write_meta("docs/a.txt", &meta).await.unwrap();
+        assert_eq!(backend.read_meta("docs/a.txt").await.unwrap(), meta);
+        assert_eq!(backend.stat("docs/a.txt").await.unwrap().meta, meta);
+        assert!(backend.write_meta("docs/b.txt", &meta).await.is_err());
+
+        // The sidecar store is neither listed nor reachable as a path.
+        let names: Vec<String> = backend
+            .list("")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["docs"]);
+        assert!(matches!(
+            backend.read(".openfs-meta/docs/a.txt").await,
+            Err(BackendError::PermissionDenied(_))
+        ));
+
+        backend.rename("docs", "moved").await.unwrap();
+        assert_eq!(backend.read_meta("moved/a.txt").await.unwrap(), meta);
+        backend.write("moved/b.txt", b"b").await.unwrap();
+        backend.rename("moved/b.txt", "moved/a.txt").await.unwrap();
+        assert!(backend.read_meta("moved/a.txt").await.unwrap().is_empty());
+    }
 }
@@ -1,14 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
-use openfs_core::{Backend, BackendError, Entry};
+use openfs_core::{slice_range, Backend, BackendError, Entry, FileMeta, UploadedPart};
 
 /// In-memory backend for testing.
 pub struct MemoryBackend {
     files: RwLock<HashMap<String, (Vec<u8>, DateTime<Utc>, u64)>>,
+    /// User metadata, kept beside the files and moved with them.
+    meta: RwLock<HashMap<String, FileMeta>>,
+    /// Multipart uploads in progress, by id.
+    uploads: RwLock<HashMap<String, Upload>>,
+    next_upload: AtomicU64,
+}
+
+/// A multipart upload in progress.
+struct Upload {
+    path: String,
+    parts: BTreeMap<u32, Vec<u8>>,
 }
 
 impl MemoryBackend {
@@ -16,8 +28,20 @@ impl MemoryBackend {
     pub fn new() -> Self {
         MemoryBackend {
             files: RwLock::new(HashMap::new()),
+            meta: RwLock::new(HashMap::new()),
+            uploads: RwLock::new(HashMap::new()),
+            next_upload: AtomicU64::new(1),
         }
     }
+
+    /// Number of multipart uploads started and not yet completed or aborted.
+    pub fn open_uploads(&self) -> usize {
+        self.uploads.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+fn part_etag(content: &[u8]) -> String {
+    blake3::hash(content).to_hex()[..16].to_string()
 }
 
 impl Default for MemoryBackend {
@@ -38,6 +62,21 @@ impl Backend for MemoryBackend {
             .ok_or_else(|| BackendError::NotFound(path.to_string()))
     }
 
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        let normalized = normalize_path(path);
+
+        files
+            .get(&normalized)
+            .map(|(content, _, _)| slice_range(content, offset, len).to_vec())
+            .ok_or_else(|| BackendError::NotFound(path.to_string()))
+    }
+
     async fn read_with_cas_token(
         &self,
         path: &str,
@@ -62,6 +101,21 @@ impl Backend for MemoryBackend {
         Ok(())
     }
 
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        let mut files = self.files.write().unwrap_or_else(|e| e.into_inner());
+        let normalized = normalize_path(path);
+
+        let (_, mtime, _) = files
+            .get_mut(&normalized)
+            .ok_or_else(|| BackendError::NotFound(path.to_string()))?;
+        *mtime = modified;
+        Ok(true)
+    }
+
     async fn compare_and_swap(
         &self,
         path: &str,
@@ -95,6 +149,25 @@ impl Backend for MemoryBackend {
         Ok(Some(new_version.to_string()))
     }
 
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let mut files = self.files.write().unwrap_or_else(|e| e.into_inner());
+        let normalized = normalize_path(path);
+
+        if let Some((_, _, version)) = files.get(&normalized) {
+            return Err(BackendError::PreconditionFailed {
+                path: path.to_string(),
+                expected: "absent".to_string(),
+                actual: version.to_string(),
+            });
+        }
+        files.insert(normalized, (content.to_vec(), Utc::now(), 1));
+        Ok(Some("1".to_string()))
+    }
+
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         let mut files = self.files.write().unwrap_or_else(|e| e.into_inner());
         let normalized = normalize_path(path);
@@ -113,6 +186,10 @@ impl Backend for MemoryBackend {
         let normalized = normalize_path(path);
 
         if files.remove(&normalized).is_some() {
+            self.meta
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&normalized);
             Ok(())
         } else {
             Err(BackendError::NotFound(path.to_string()))
@@ -130,7 +207,7 @@ impl Backend for MemoryBackend {
 
         let mut entries = HashMap::new();
 
-        for (file_path, (content, mtime, _)) in files.iter() {
+        for (file_path, (content, mtime, version)) in files.iter() {
             let relative = if prefix.is_empty() {
                 file_path.clone()
             } else if file_path.starts_with(&prefix) {
@@ -174,7 +251,8 @@ impl Backend for MemoryBackend {
                         first_component.to_string(),
                         content.len() as u64,
                         Some(*mtime),
-                    ),
+                    )
+                    .with_etag(version.to_string()),
                 );
             }
         }
@@ -210,12 +288,20 @@ impl Backend for MemoryBackend {
         // Check for exact file match
         if let Some((content, mtime, _)) = files.get(&normalized) {
             let name = normalized.rsplit('/').next().unwrap_or(&normalized);
+            let meta = self
+                .meta
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&normalized)
+                .cloned()
+                .unwrap_or_default();
             return Ok(Entry::file(
                 normalized.clone(),
                 name.to_string(),
                 content.len() as u64,
                 Some(*mtime),
-            ));
+            )
+            .with_meta(meta));
         }
 
         // Check for directory
@@ -241,9 +327,110 @@ impl Backend for MemoryBackend {
             .remove(&from_normalized)
             .ok_or_else(|| BackendError::NotFound(from.to_string()))?;
 
+        let mut meta = self.meta.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(file_meta) = meta.remove(&from_normalized) {
+            meta.insert(to_normalized.clone(), file_meta);
+        }
         files.insert(to_normalized, entry);
         Ok(())
     }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        let normalized = normalize_path(path);
+        if !files.contains_key(&normalized) {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let meta = self.meta.read().unwrap_or_else(|e| e.into_inner());
+        Ok(meta.get(&normalized).cloned().unwrap_or_default())
+    }
+
+    async fn write_meta(&self, path: &str, file_meta: &FileMeta) -> Result<(), BackendError> {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        let normalized = normalize_path(path);
+        if !files.contains_key(&normalized) {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let mut meta = self.meta.write().unwrap_or_else(|e| e.into_inner());
+        if file_meta.is_empty() {
+            meta.remove(&normalized);
+        } else {
+            meta.insert(normalized, file_meta.clone());
+        }
+        Ok(())
+    }
+
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        let id = self.next_upload.fetch_add(1, Ordering::Relaxed).to_string();
+        let mut uploads = self.uploads.write().unwrap_or_else(|e| e.into_inner());
+        uploads.insert(
+            id.clone(),
+            Upload {
+                path: normalize_path(path),
+                parts: BTreeMap::new(),
+            },
+        );
+        Ok(Some(id))
+    }
+
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        let mut uploads = self.uploads.write().unwrap_or_else(|e| e.into_inner());
+        let upload = uploads
+            .get_mut(upload_id)
+            .filter(|upload| upload.path == normalize_path(path))
+            .ok_or_else(|| BackendError::NotFound(format!("upload {}", upload_id)))?;
+        upload.parts.insert(number, content.to_vec());
+        Ok(UploadedPart {
+            number,
+            etag: part_etag(content),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        let content = {
+            let uploads = self.uploads.read().unwrap_or_else(|e| e.into_inner());
+            let upload = uploads
+                .get(upload_id)
+                .filter(|upload| upload.path == normalize_path(path))
+                .ok_or_else(|| BackendError::NotFound(format!("upload {}", upload_id)))?;
+            let mut content = Vec::new();
+            for part in parts {
+                match upload.parts.get(&part.number) {
+                    Some(stored) if part_etag(stored) == part.etag => content.extend(stored),
+                    _ => {
+                        return Err(BackendError::Other(format!(
+                            "upload {}: part {} is missing or changed",
+                            upload_id, part.number
+                        )))
+                    }
+                }
+            }
+            content
+        };
+        self.write(path, &content).await?;
+        self.uploads
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(upload_id);
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, _path: &str, upload_id: &str) -> Result<(), BackendError> {
+        let mut uploads = self.uploads.write().unwrap_or_else(|e| e.into_inner());
+        uploads.remove(upload_id);
+        Ok(())
+    }
 }
 
 /// Normalize a path by removing leading/trailing slashes.
@@ -295,6 +482,24 @@ mod tests {
         assert!(!backend.exists("test.txt").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_set_modified() {
+        let backend = MemoryBackend::new();
+        backend.write("test.txt", b"hello").await.unwrap();
+
+        let modified = DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert!(backend.set_modified("test.txt", modified).await.unwrap());
+        let entry = backend.stat("test.txt").await.unwrap();
+        assert_eq!(entry.modified, Some(modified));
+
+        assert!(matches!(
+            backend.set_modified("missing.txt", modified).await,
+            Err(BackendError::NotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_compare_and_swap_success_and_mismatch() {
         let backend = MemoryBackend::new();
@@ -221,6 +221,42 @@ impl Backend for PostgresBackend {
         Ok(Some(modified.to_rfc3339()))
     }
 
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let path = Self::normalize_path(path);
+        let query = format!(
+            r#"INSERT INTO {} (path, content, size, modified) VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (path) DO NOTHING
+            RETURNING modified"#,
+            self.table_name
+        );
+        let row: Option<(DateTime<Utc>,)> = sqlx::query_as(&query)
+            .bind(&path)
+            .bind(content)
+            .bind(content.len() as i64)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| BackendError::Other(format!("Postgres create failed: {}", e)))?;
+
+        match row {
+            Some((modified,)) => Ok(Some(modified.to_rfc3339())),
+            None => {
+                let actual = match self.read_with_cas_token(&path).await {
+                    Ok((_bytes, token)) => token.unwrap_or_else(|| "unknown".to_string()),
+                    Err(err) => return Err(err),
+                };
+                Err(BackendError::PreconditionFailed {
+                    path,
+                    expected: "absent".to_string(),
+                    actual,
+                })
+            }
+        }
+    }
+
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         let path = Self::normalize_path(path);
         let existing = match self.read(&path).await {
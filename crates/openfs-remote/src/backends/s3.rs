@@ -1,11 +1,62 @@
 use std::collections::HashSet;
 
 use async_trait::async_trait;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::SdkError;
 use aws_sdk_s3::primitives::DateTime as AwsDateTime;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, MetadataDirective};
 use chrono::{DateTime, Utc};
 
 use openfs_config::Secret;
-use openfs_core::{Backend, BackendError, Entry};
+use openfs_core::{
+    detect_content_type, Backend, BackendError, Entry, FileMeta, UploadedPart, OCTET_STREAM,
+};
+
+/// Map an S3 request error, recognizing throttling (`503 SlowDown`, `429`)
+/// so that sync retries it after the `Retry-After` the service sent, if any,
+/// and network failures and other server errors so that they can be retried
+/// too.
+fn s3_error<E>(operation: &str, err: SdkError<E, HttpResponse>) -> BackendError {
+    match &err {
+        SdkError::TimeoutError(_) => {
+            return BackendError::Timeout {
+                operation: format!("S3 {}", operation),
+                path: String::new(),
+            }
+        }
+        SdkError::DispatchFailure(_) => {
+            return BackendError::ConnectionFailed {
+                backend: "s3".to_string(),
+                source: Box::new(std::io::Error::other(format!(
+                    "{} failed: {}",
+                    operation, err
+                ))),
+            }
+        }
+        _ => {}
+    }
+    if let Some(response) = err.raw_response() {
+        let status = response.status().as_u16();
+        if matches!(status, 429 | 503) {
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(std::time::Duration::from_secs);
+            return BackendError::Throttled {
+                backend: "s3".to_string(),
+                retry_after,
+            };
+        }
+        if (500..600).contains(&status) {
+            return BackendError::Unavailable {
+                backend: "s3".to_string(),
+                message: format!("{} returned {}", operation, status),
+            };
+        }
+    }
+    BackendError::Other(format!("S3 {} failed: {}", operation, err))
+}
 
 /// S3-compatible storage backend configuration.
 #[derive(Debug, Clone)]
@@ -109,6 +160,20 @@ impl S3Backend {
     }
 }
 
+/// Percent-encode a key for the `x-amz-copy-source` header, keeping `/`.
+fn encode_copy_source(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
 #[async_trait]
 impl Backend for S3Backend {
     async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
@@ -138,6 +203,48 @@ impl Backend for S3Backend {
         Ok(body.into_bytes().to_vec())
     }
 
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        if len == Some(0) {
+            return Ok(Vec::new());
+        }
+        let key = self.path_to_key(path);
+        let range = match len {
+            Some(len) => format!("bytes={}-{}", offset, offset.saturating_add(len) - 1),
+            None => format!("bytes={}-", offset),
+        };
+
+        let response = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .range(range)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            // A range starting at or past the end of the object.
+            Err(e) if e.to_string().contains("InvalidRange") => return Ok(Vec::new()),
+            Err(e) if e.to_string().contains("NoSuchKey") => {
+                return Err(BackendError::NotFound(path.to_string()))
+            }
+            Err(e) => return Err(BackendError::Other(format!("S3 get failed: {}", e))),
+        };
+
+        let body = response
+            .body
+            .collect()
+            .await
+            .map_err(|e| BackendError::Other(format!("S3 read body failed: {}", e)))?;
+
+        Ok(body.into_bytes().to_vec())
+    }
+
     async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         let key = self.path_to_key(path);
 
@@ -145,10 +252,11 @@ impl Backend for S3Backend {
             .put_object()
             .bucket(&self.bucket)
             .key(&key)
+            .content_type(detect_content_type(path, content))
             .body(content.to_vec().into())
             .send()
             .await
-            .map_err(|e| BackendError::Other(format!("S3 put failed: {}", e)))?;
+            .map_err(|e| s3_error("put", e))?;
 
         Ok(())
     }
@@ -175,7 +283,7 @@ impl Backend for S3Backend {
             .key(&key)
             .send()
             .await
-            .map_err(|e| BackendError::Other(format!("S3 delete failed: {}", e)))?;
+            .map_err(|e| s3_error("delete", e))?;
 
         Ok(())
     }
@@ -241,12 +349,12 @@ impl Backend for S3Backend {
                         })
                         .map(|dt: DateTime<Utc>| dt.with_timezone(&Utc));
 
-                    entries.push(Entry::file(
-                        self.key_to_path(key),
-                        name,
-                        size.unwrap_or(0),
-                        modified,
-                    ));
+                    let mut entry =
+                        Entry::file(self.key_to_path(key), name, size.unwrap_or(0), modified);
+                    if let Some(etag) = obj.e_tag() {
+                        entry = entry.with_etag(etag);
+                    }
+                    entries.push(entry);
                 }
             }
 
@@ -312,12 +420,161 @@ impl Backend for S3Backend {
             .and_then(|t: &AwsDateTime| DateTime::from_timestamp(t.secs(), t.subsec_nanos()))
             .map(|dt: DateTime<Utc>| dt.with_timezone(&Utc));
 
-        Ok(Entry::file(
-            path.to_string(),
-            Self::filename(path),
-            size,
-            modified,
-        ))
+        let meta = response
+            .metadata()
+            .map(|meta| meta.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+
+        let entry =
+            Entry::file(path.to_string(), Self::filename(path), size, modified).with_meta(meta);
+        // Objects uploaded without a type get a generic one; the extension
+        // says more.
+        Ok(match response.content_type() {
+            Some(t) if !matches!(t, "binary/octet-stream" | OCTET_STREAM) => {
+                entry.with_content_type(t)
+            }
+            _ => entry,
+        })
+    }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        Ok(self.stat(path).await?.meta)
+    }
+
+    /// Object metadata can only be replaced by copying the object onto
+    /// itself; the copy is conditional on the content not changing meanwhile.
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        let key = self.path_to_key(path);
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("NotFound") || e.to_string().contains("NoSuchKey") {
+                    BackendError::NotFound(path.to_string())
+                } else {
+                    BackendError::Other(format!("S3 head failed: {}", e))
+                }
+            })?;
+
+        let mut request = self
+            .client
+            .copy_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .copy_source(format!("{}/{}", self.bucket, encode_copy_source(&key)))
+            .metadata_directive(MetadataDirective::Replace)
+            .set_metadata(Some(meta.clone().into_iter().collect()))
+            .set_content_type(head.content_type().map(String::from));
+        if let Some(etag) = head.e_tag() {
+            request = request.copy_source_if_match(etag);
+        }
+        request.send().await.map_err(|e| s3_error("copy", e))?;
+        Ok(())
+    }
+
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        let response = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(self.path_to_key(path))
+            .send()
+            .await
+            .map_err(|e| s3_error("create multipart upload", e))?;
+        let upload_id = response.upload_id().ok_or_else(|| {
+            BackendError::Other("S3 create multipart upload returned no id".to_string())
+        })?;
+        Ok(Some(upload_id.to_string()))
+    }
+
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        let response = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(self.path_to_key(path))
+            .upload_id(upload_id)
+            .part_number(number as i32)
+            .body(content.to_vec().into())
+            .send()
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("NoSuchUpload") {
+                    BackendError::NotFound(format!("upload {}", upload_id))
+                } else {
+                    s3_error("upload part", e)
+                }
+            })?;
+        let etag = response
+            .e_tag()
+            .ok_or_else(|| BackendError::Other("S3 upload part returned no ETag".to_string()))?;
+        Ok(UploadedPart {
+            number,
+            etag: etag.to_string(),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        let parts = parts
+            .iter()
+            .map(|part| {
+                CompletedPart::builder()
+                    .part_number(part.number as i32)
+                    .e_tag(&part.etag)
+                    .build()
+            })
+            .collect();
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(self.path_to_key(path))
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("NoSuchUpload") {
+                    BackendError::NotFound(format!("upload {}", upload_id))
+                } else {
+                    s3_error("complete multipart upload", e)
+                }
+            })?;
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        match self
+            .client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(self.path_to_key(path))
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if e.to_string().contains("NoSuchUpload") => Ok(()),
+            Err(e) => Err(s3_error("abort multipart upload", e)),
+        }
     }
 }
 
@@ -2,9 +2,19 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use openfs_core::{Backend, BackendError, CacheConfig, CacheStats, Entry, LruCache, VfsError};
-
-use crate::sync::{SyncConfig, SyncEngine, SyncMode, SyncStats};
+use chrono::{DateTime, Utc};
+use openfs_core::{
+    slice_range, Backend, BackendError, CacheConfig, CacheStats, Entry, FileMeta, LruCache,
+    VfsError,
+};
+use tokio::sync::Mutex;
+
+use crate::conflict::{ConflictTracker, FlushOutcome};
+use crate::disk_cache::{DiskCache, DiskCacheStats};
+use crate::log_file::LogPaths;
+use crate::multipart::MultipartUploader;
+use crate::pull::{PullSummary, RemoteSnapshot};
+use crate::sync::{PathSyncStatus, SyncConfig, SyncEngine, SyncMode, SyncStats};
 use crate::wal::WriteAheadLog;
 
 /// A backend wrapper that adds caching and sync capabilities.
@@ -13,8 +23,18 @@ pub struct CachedBackend<B: Backend> {
     inner: Arc<B>,
     /// The cache layer.
     cache: Arc<LruCache>,
+    /// Second cache level on disk, if configured.
+    disk: Option<Arc<DiskCache>>,
     /// The sync engine.
     sync: Arc<SyncEngine>,
+    /// Base versions of cached paths, for write-back conflict detection.
+    conflicts: Arc<ConflictTracker>,
+    /// Uploads large write-back files in resumable parts.
+    multipart: Arc<MultipartUploader>,
+    /// The remote files as of the last pull, see [`Self::pull`].
+    remote: Mutex<Option<RemoteSnapshot>>,
+    /// Log files, whose appends always go straight to the inner backend.
+    log_paths: Option<LogPaths>,
     /// Whether this is a read-only mount.
     read_only: bool,
 }
@@ -29,8 +49,17 @@ impl<B: Backend> CachedBackend<B> {
     ) -> Self {
         CachedBackend {
             inner: Arc::new(inner),
+            disk: open_disk_cache(&cache_config),
             cache: Arc::new(LruCache::new(cache_config)),
+            conflicts: Arc::new(ConflictTracker::new(sync_config.conflict, None)),
+            multipart: Arc::new(MultipartUploader::new(
+                sync_config.multipart_threshold,
+                sync_config.multipart_part_size,
+                None,
+            )),
             sync: Arc::new(SyncEngine::new(sync_config)),
+            remote: Mutex::new(None),
+            log_paths: None,
             read_only,
         }
     }
@@ -45,12 +74,32 @@ impl<B: Backend> CachedBackend<B> {
     ) -> Self {
         CachedBackend {
             inner: Arc::new(inner),
+            disk: open_disk_cache(&cache_config),
             cache: Arc::new(LruCache::new(cache_config)),
+            conflicts: Arc::new(ConflictTracker::new(
+                sync_config.conflict,
+                Some(Arc::clone(&wal)),
+            )),
+            multipart: Arc::new(MultipartUploader::new(
+                sync_config.multipart_threshold,
+                sync_config.multipart_part_size,
+                Some(Arc::clone(&wal)),
+            )),
             sync: Arc::new(SyncEngine::with_wal(sync_config, wal)),
+            remote: Mutex::new(None),
+            log_paths: None,
             read_only,
         }
     }
 
+    /// Send appends to files matching `paths` straight to the inner backend,
+    /// even in write-back mode, so a [`crate::LogBackend`] below stores each
+    /// as a segment instead of the whole file being rewritten on flush.
+    pub fn with_log_paths(mut self, paths: LogPaths) -> Self {
+        self.log_paths = Some(paths);
+        self
+    }
+
     /// Create with just caching (no sync).
     pub fn with_cache(inner: B, cache_config: CacheConfig) -> Self {
         Self::new(inner, cache_config, SyncConfig::default(), false)
@@ -89,11 +138,21 @@ impl<B: Backend> CachedBackend<B> {
         self.cache.stats().await
     }
 
+    /// Get disk cache statistics, if the disk cache is enabled.
+    pub fn disk_cache_stats(&self) -> Option<DiskCacheStats> {
+        self.disk.as_ref().map(|disk| disk.stats())
+    }
+
     /// Get sync statistics.
     pub async fn sync_stats(&self) -> SyncStats {
         self.sync.stats().await
     }
 
+    /// Get the write-back sync state of each path.
+    pub async fn path_sync_statuses(&self) -> Result<Vec<PathSyncStatus>, VfsError> {
+        self.sync.path_statuses().await
+    }
+
     /// Get the sync mode.
     pub fn sync_mode(&self) -> SyncMode {
         self.sync.mode()
@@ -108,9 +167,156 @@ impl<B: Backend> CachedBackend<B> {
         self.sync.start(flush_fn).await;
     }
 
-    /// Clear the cache.
+    /// Start background sync for write-back mode, flushing each write
+    /// conditionally on the remote version it was based on. Conflicts are
+    /// resolved by the configured strategy.
+    pub async fn start_write_back(&self) {
+        let inner = Arc::clone(&self.inner);
+        let cache = Arc::clone(&self.cache);
+        let disk = self.disk.clone();
+        let conflicts = Arc::clone(&self.conflicts);
+        let multipart = Arc::clone(&self.multipart);
+        self.sync
+            .start(move |path, content| {
+                let inner = Arc::clone(&inner);
+                let cache = Arc::clone(&cache);
+                let disk = disk.clone();
+                let conflicts = Arc::clone(&conflicts);
+                let multipart = Arc::clone(&multipart);
+                async move {
+                    // Large files without a base version to check against go
+                    // in parts, resuming an upload interrupted by a crash.
+                    if multipart.applies(&content)
+                        && !conflicts.has_base(&path)
+                        && multipart.upload(inner.as_ref(), &path, &content).await?
+                    {
+                        return Ok(());
+                    }
+                    let outcome = conflicts.flush(inner.as_ref(), &path, &content).await?;
+                    if outcome == FlushOutcome::Written {
+                        return Ok(());
+                    }
+                    // Replace the local copy, unless it was written again meanwhile.
+                    if cache.get(&path).await.as_deref() == Some(content.as_slice()) {
+                        match outcome {
+                            FlushOutcome::Merged(merged) => cache.put(&path, merged).await,
+                            _ => {
+                                cache.remove(&path).await;
+                            }
+                        }
+                    }
+                    if let Some(disk) = disk {
+                        disk.remove(&path);
+                    }
+                    Ok(())
+                }
+            })
+            .await;
+    }
+
+    /// Pull remote changes into the cache (pull-mirror and two-way mode).
+    /// The first pull only records the remote files; later ones re-read
+    /// cached files changed remotely and drop those deleted remotely. Paths
+    /// with a pending local write are left to its flush, which resolves them
+    /// with the conflict strategy.
+    pub async fn pull(&self) -> Result<PullSummary, BackendError> {
+        let mut remote = self.remote.lock().await;
+        let snapshot = RemoteSnapshot::take(self.inner.as_ref()).await?;
+        let (Some(previous), Some(current)) = (remote.replace(snapshot), remote.as_ref()) else {
+            return Ok(PullSummary::default());
+        };
+        let changes = previous.changes(current);
+
+        // Cache keys may carry a leading slash; snapshot paths do not.
+        let cached: HashMap<String, String> = self
+            .cache
+            .entries()
+            .await
+            .into_iter()
+            .map(|(key, _)| (key.trim_matches('/').to_string(), key))
+            .collect();
+        let mut summary = PullSummary::default();
+        for (path, deleted) in changes
+            .changed
+            .iter()
+            .map(|path| (path, false))
+            .chain(changes.deleted.iter().map(|path| (path, true)))
+        {
+            let key = cached.get(path).map(String::as_str).unwrap_or(path);
+            self.sync.acquire_path_lock(key).await;
+            let result = self.pull_path(key, deleted, &mut summary).await;
+            self.sync.release_path_lock(key).await;
+            if let Err(e) = result {
+                tracing::warn!("Failed to pull remote change to {}: {}", key, e);
+            }
+        }
+        summary.changes = changes;
+        Ok(summary)
+    }
+
+    /// Bring the cached copy of one remotely changed path up to date.
+    async fn pull_path(
+        &self,
+        path: &str,
+        deleted: bool,
+        summary: &mut PullSummary,
+    ) -> Result<(), BackendError> {
+        if self.sync.pending_contains(path).await {
+            summary.deferred += 1;
+            return Ok(());
+        }
+        self.invalidate_disk(path);
+        let Some(before) = self.cache.get(path).await else {
+            self.conflicts.forget(path);
+            return Ok(());
+        };
+
+        let (content, token) = if deleted {
+            (None, None)
+        } else {
+            match self.inner.read_with_cas_token(path).await {
+                Ok((content, token)) => (Some(content), token),
+                Err(BackendError::NotFound(_)) => (None, None),
+                Err(e) => return Err(e),
+            }
+        };
+        // A local write since the read wins; it will be flushed.
+        if self.sync.pending_contains(path).await
+            || self.cache.get(path).await.as_ref() != Some(&before)
+        {
+            summary.deferred += 1;
+            return Ok(());
+        }
+        match content {
+            Some(content) => {
+                if self.sync.is_write_back() {
+                    self.conflicts.observe(path, token, &content);
+                }
+                if content != before {
+                    self.cache.put(path, content).await;
+                    summary.refreshed += 1;
+                }
+            }
+            None => {
+                self.cache.remove(path).await;
+                self.conflicts.forget(path);
+                summary.dropped += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the conflict tracker of this backend.
+    pub fn conflicts(&self) -> &ConflictTracker {
+        &self.conflicts
+    }
+
+    /// Clear the cache, including the disk cache.
     pub async fn clear_cache(&self) {
         self.cache.clear().await;
+        if let Some(disk) = &self.disk {
+            disk.clear();
+        }
     }
 
     /// Prune expired cache entries.
@@ -123,7 +329,7 @@ impl<B: Backend> CachedBackend<B> {
         let mut warmed = 0;
         for path in paths {
             if !self.cache.contains(path).await {
-                if let Ok(content) = self.inner.read(path).await {
+                if let Ok(content) = self.read_uncached(path).await {
                     self.cache.put(path, content).await;
                     warmed += 1;
                 }
@@ -146,6 +352,54 @@ impl<B: Backend> CachedBackend<B> {
     pub fn inner(&self) -> &B {
         &self.inner
     }
+
+    /// Read past the memory cache: from disk, else from the backend (and
+    /// keep the result on disk).
+    async fn read_uncached(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        if let Some(content) = self.disk.as_ref().and_then(|disk| disk.get(path)) {
+            return Ok(content);
+        }
+        if !self.sync.is_write_back() {
+            let content = self.inner.read(path).await?;
+            if let Some(disk) = &self.disk {
+                disk.put(path, &content);
+            }
+            return Ok(content);
+        }
+
+        let (content, token) = self.inner.read_with_cas_token(path).await?;
+        // The backend copy is stale while a write-back is pending.
+        if !self.sync.pending_contains(path).await {
+            self.conflicts.observe(path, token, &content);
+            if let Some(disk) = &self.disk {
+                disk.put(path, &content);
+            }
+        }
+        Ok(content)
+    }
+
+    /// Drop a changed path from the disk cache. The memory cache holds the
+    /// new content where there is any.
+    fn invalidate_disk(&self, path: &str) {
+        if let Some(disk) = &self.disk {
+            disk.remove(path);
+        }
+    }
+}
+
+fn open_disk_cache(config: &CacheConfig) -> Option<Arc<DiskCache>> {
+    let disk = config.disk.as_ref().filter(|_| config.enabled)?;
+    match DiskCache::open(disk.clone()) {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to open disk cache at {}: {}. Continuing without it.",
+                disk.dir.display(),
+                e
+            );
+            None
+        }
+    }
 }
 
 #[async_trait]
@@ -156,8 +410,8 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
             return Ok(content);
         }
 
-        // Cache miss - read from backend
-        let content = self.inner.read(path).await?;
+        // Cache miss - read from disk or backend
+        let content = self.read_uncached(path).await?;
 
         // Store in cache
         self.cache.put(path, content.clone()).await;
@@ -165,11 +419,30 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
         Ok(content)
     }
 
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        // Serve from cache when possible; a partial read is not cached.
+        if let Some(content) = self.cache.get(path).await {
+            return Ok(slice_range(&content, offset, len).to_vec());
+        }
+        if let Some(content) = self.disk.as_ref().and_then(|disk| disk.get(path)) {
+            return Ok(slice_range(&content, offset, len).to_vec());
+        }
+        self.inner.read_range(path, offset, len).await
+    }
+
     async fn read_with_cas_token(
         &self,
         path: &str,
     ) -> Result<(Vec<u8>, Option<String>), BackendError> {
         let (content, token) = self.inner.read_with_cas_token(path).await?;
+        if self.sync.is_write_back() && !self.sync.pending_contains(path).await {
+            self.conflicts.observe(path, token.clone(), &content);
+        }
         self.cache.put(path, content.clone()).await;
         Ok((content, token))
     }
@@ -179,6 +452,8 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
             return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
         }
 
+        self.invalidate_disk(path);
+
         match self.sync.mode() {
             SyncMode::WriteThrough => {
                 // Write to backend immediately
@@ -186,13 +461,14 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
                 // Update cache
                 self.cache.put(path, content.to_vec()).await;
             }
-            SyncMode::WriteBack => {
+            SyncMode::WriteBack | SyncMode::TwoWay => {
                 // Update cache immediately
                 self.cache.put(path, content.to_vec()).await;
                 // Queue for background sync
+                let base = self.conflicts.base_token(path);
                 if let Err(e) = self
                     .sync
-                    .queue_write(path.to_string(), content.to_vec())
+                    .queue_write_with_base(path.to_string(), content.to_vec(), base)
                     .await
                 {
                     tracing::warn!(
@@ -222,8 +498,10 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
             return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
         }
 
+        self.invalidate_disk(path);
+
         match self.sync.mode() {
-            SyncMode::WriteBack => Err(BackendError::Other(
+            SyncMode::WriteBack | SyncMode::TwoWay => Err(BackendError::Other(
                 "compare_and_swap is not supported in write-back mode".to_string(),
             )),
             SyncMode::WriteThrough | SyncMode::None | SyncMode::PullMirror => {
@@ -234,12 +512,63 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
         }
     }
 
-    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
         if self.read_only {
             return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
         }
 
         match self.sync.mode() {
+            SyncMode::WriteBack | SyncMode::TwoWay => Err(BackendError::Other(
+                "create_if_absent is not supported in write-back mode".to_string(),
+            )),
+            SyncMode::WriteThrough | SyncMode::None | SyncMode::PullMirror => {
+                let token = self.inner.create_if_absent(path, content).await?;
+                self.invalidate_disk(path);
+                self.cache.put(path, content.to_vec()).await;
+                Ok(token)
+            }
+        }
+    }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        self.inner.read_meta(path).await
+    }
+
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        if self.read_only {
+            return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
+        }
+        // Metadata goes straight to the backend, which must have the file.
+        if self.sync.is_write_back() && self.sync.pending_contains(path).await {
+            return Err(BackendError::Other(format!(
+                "'{}' has writes not yet synced; flush before setting metadata",
+                path
+            )));
+        }
+        self.inner.write_meta(path, meta).await
+    }
+
+    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        if self.read_only {
+            return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
+        }
+
+        let mut mode = self.sync.mode();
+        // A whole write still queued for a log must land before the append.
+        if mode.is_write_back()
+            && self
+                .log_paths
+                .as_ref()
+                .is_some_and(|logs| logs.matches(path))
+            && !self.sync.pending_contains(path).await
+        {
+            mode = SyncMode::WriteThrough;
+        }
+        match mode {
             SyncMode::WriteThrough => {
                 self.inner.append(path, content).await?;
                 if self.cache.contains(path).await {
@@ -250,7 +579,7 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
                     }
                 }
             }
-            SyncMode::WriteBack => {
+            SyncMode::WriteBack | SyncMode::TwoWay => {
                 let current = match self.read(path).await {
                     Ok(data) => data,
                     Err(BackendError::NotFound(_)) => Vec::new(),
@@ -260,7 +589,12 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
                 new_content.extend_from_slice(content);
 
                 self.cache.put(path, new_content.clone()).await;
-                if let Err(e) = self.sync.queue_write(path.to_string(), new_content).await {
+                let base = self.conflicts.base_token(path);
+                if let Err(e) = self
+                    .sync
+                    .queue_write_with_base(path.to_string(), new_content, base)
+                    .await
+                {
                     tracing::warn!(
                         "Failed to queue append for {}: {}. Data is cached but may not sync.",
                         path,
@@ -279,6 +613,8 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
                 }
             }
         }
+        // After the match: a write-back append reads the old content first.
+        self.invalidate_disk(path);
 
         Ok(())
     }
@@ -288,8 +624,10 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
             return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
         }
 
+        self.invalidate_disk(path);
+
         match self.sync.mode() {
-            SyncMode::WriteBack => {
+            SyncMode::WriteBack | SyncMode::TwoWay => {
                 // Serialize with any in-flight flush for this path.
                 self.sync.acquire_path_lock(path).await;
 
@@ -317,6 +655,7 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
 
                 // Remove from cache immediately for local consistency
                 self.cache.remove(path).await;
+                self.conflicts.forget(path);
 
                 self.sync.release_path_lock(path).await;
             }
@@ -332,7 +671,7 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
     async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
         let mut entries = self.inner.list(path).await?;
 
-        if self.sync.mode() != SyncMode::WriteBack {
+        if !self.sync.is_write_back() {
             return Ok(entries);
         }
 
@@ -412,7 +751,7 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
             return Ok(true);
         }
 
-        if self.sync.mode() == SyncMode::WriteBack {
+        if self.sync.is_write_back() {
             let normalized = path.trim_matches('/');
             if !normalized.is_empty() {
                 let dir_prefix = format!("{}/", normalized);
@@ -426,7 +765,7 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
     }
 
     async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
-        if self.sync.mode() == SyncMode::WriteBack {
+        if self.sync.is_write_back() {
             if let Some(content) = self.cache.get(path).await {
                 let normalized = path.trim_matches('/');
                 let name = normalized.rsplit('/').next().unwrap_or(normalized);
@@ -451,12 +790,33 @@ impl<B: Backend + Send + Sync + 'static> Backend for CachedBackend<B> {
         self.inner.stat(path).await
     }
 
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        if self.read_only {
+            return Err(BackendError::Other(format!("Mount is read-only: {}", path)));
+        }
+        // A write-back file may not have reached the backend yet, and its
+        // flush would replace the time anyway.
+        if self.sync.is_write_back() && self.cache.get(path).await.is_some() {
+            return Ok(false);
+        }
+        self.inner.set_modified(path, modified).await
+    }
+
     async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
         if self.read_only {
             return Err(BackendError::Other(format!("Mount is read-only: {}", from)));
         }
 
-        if self.sync.mode() == SyncMode::WriteBack {
+        self.invalidate_disk(from);
+        self.invalidate_disk(to);
+
+        if self.sync.is_write_back() {
+            self.conflicts.forget(from);
+            self.conflicts.forget(to);
             let cached_content = self.cache.get(from).await;
             let mut queued_write: Option<Vec<u8>> = cached_content.clone();
             let backend_exists = self.inner.exists(from).await?;
@@ -535,6 +895,7 @@ impl<B: Backend + Send + Sync + 'static> CachedBackend<B> {
 mod tests {
     use super::*;
     use crate::backends::MemoryBackend;
+    use crate::conflict::ConflictResolution;
 
     #[tokio::test]
     async fn test_cached_backend_read_cache_hit() {
@@ -559,6 +920,39 @@ mod tests {
         assert_eq!(stats.hits, 1);
     }
 
+    #[tokio::test]
+    async fn test_cached_backend_disk_cache_survives_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = CacheConfig {
+            disk: Some(openfs_core::DiskCacheConfig {
+                dir: dir.path().to_path_buf(),
+                max_size: 1024 * 1024,
+                ttl: std::time::Duration::from_secs(60),
+            }),
+            ..Default::default()
+        };
+
+        let inner = MemoryBackend::new();
+        inner.write("/big.bin", b"remote bytes").await.unwrap();
+        let cached = CachedBackend::with_cache(inner, config.clone());
+        cached.read("/big.bin").await.unwrap();
+        assert_eq!(cached.disk_cache_stats().unwrap().entries, 1);
+
+        // A new process with an empty memory cache reads from disk, not the
+        // backend (which no longer has the file).
+        let cached = CachedBackend::with_cache(MemoryBackend::new(), config);
+        assert_eq!(cached.read("/big.bin").await.unwrap(), b"remote bytes");
+        assert_eq!(
+            cached.read_range("/big.bin", 7, None).await.unwrap(),
+            b"bytes"
+        );
+        assert_eq!(cached.disk_cache_stats().unwrap().hits, 1);
+
+        cached.write("/big.bin", b"new").await.unwrap();
+        cached.clear_cache().await;
+        assert_eq!(cached.read("/big.bin").await.unwrap(), b"new");
+    }
+
     #[tokio::test]
     async fn test_cached_backend_write_through() {
         let inner = MemoryBackend::new();
@@ -637,6 +1031,81 @@ mod tests {
         assert!(matches!(err, BackendError::PreconditionFailed { .. }));
     }
 
+    #[tokio::test]
+    async fn test_cached_backend_two_way_pull() {
+        let inner = MemoryBackend::new();
+        for path in ["a.txt", "b.txt", "c.txt"] {
+            inner.write(path, b"v1").await.unwrap();
+        }
+        let sync_config = SyncConfig {
+            mode: SyncMode::TwoWay,
+            flush_interval: std::time::Duration::from_secs(3600),
+            ..Default::default()
+        };
+        let cached = CachedBackend::new(inner, CacheConfig::default(), sync_config, false);
+        cached.start_write_back().await;
+        assert_eq!(cached.pull().await.unwrap(), PullSummary::default());
+        for path in ["a.txt", "b.txt", "c.txt"] {
+            cached.read(path).await.unwrap();
+        }
+
+        cached.inner().write("a.txt", b"remote v2").await.unwrap();
+        cached.inner().delete("b.txt").await.unwrap();
+        // Changed on both sides: the pending local write is left to its flush.
+        cached.write("c.txt", b"local").await.unwrap();
+        cached.inner().write("c.txt", b"remote").await.unwrap();
+
+        let summary = cached.pull().await.unwrap();
+        assert_eq!(summary.changes.changed, vec!["a.txt", "c.txt"]);
+        assert_eq!(summary.changes.deleted, vec!["b.txt"]);
+        assert_eq!(
+            (summary.refreshed, summary.dropped, summary.deferred),
+            (1, 1, 1)
+        );
+        assert_eq!(cached.read("a.txt").await.unwrap(), b"remote v2");
+        assert!(matches!(
+            cached.read("b.txt").await,
+            Err(BackendError::NotFound(_))
+        ));
+        assert_eq!(cached.read("c.txt").await.unwrap(), b"local");
+
+        // The flush finds the remote changed and applies the conflict strategy.
+        cached.shutdown_sync().await;
+        assert_eq!(cached.inner().read("c.txt").await.unwrap(), b"local");
+        let conflicts = cached.conflicts().conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].resolution, ConflictResolution::Overwritten);
+    }
+
+    #[tokio::test]
+    async fn test_cached_backend_write_back_multipart() {
+        let wal = Arc::new(WriteAheadLog::in_memory(Default::default()).unwrap());
+        let sync_config = SyncConfig {
+            mode: SyncMode::WriteBack,
+            flush_interval: std::time::Duration::from_millis(50),
+            multipart_threshold: 16,
+            multipart_part_size: 8,
+            ..Default::default()
+        };
+        let cached = CachedBackend::new_with_wal(
+            MemoryBackend::new(),
+            CacheConfig::default(),
+            sync_config,
+            false,
+            Arc::clone(&wal),
+        );
+        cached.start_write_back().await;
+        let content: Vec<u8> = (0..40u8).collect();
+        cached.write("big.bin", &content).await.unwrap();
+        cached.write("small.txt", b"small").await.unwrap();
+        cached.shutdown_sync().await;
+
+        assert_eq!(cached.inner().read("big.bin").await.unwrap(), content);
+        assert_eq!(cached.inner().read("small.txt").await.unwrap(), b"small");
+        assert_eq!(cached.inner().open_uploads(), 0);
+        assert!(wal.multipart_upload("big.bin").unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_cached_backend_compare_and_swap_write_back_unsupported() {
         let inner = MemoryBackend::new();
@@ -7,7 +7,7 @@ use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use openfs_core::{Backend, BackendError, ChromaStore, Entry, QueryResult, SparseEmbedding, TextEmbedder};
+use openfs_core::{Backend, BackendError, ChromaStore, Entry, QueryResult, SparseEmbedding, StoredDocument, TextEmbedder, ACTIVE_COLLECTION_KEY};
 
 const DEFAULT_TENANT: &str = "default_tenant";
 const DEFAULT_DATABASE: &str = "default_database";
@@ -99,6 +99,8 @@ struct GetDocumentsResponse {
     documents: Option<Vec<Option<String>>>,
     #[serde(default)]
     metadatas: Option<Vec<Option<HashMap<String, serde_json::Value>>>>,
+    #[serde(default)]
+    embeddings: Option<Vec<Option<Vec<f32>>>>,
     /// Per-record versions (log offsets). Populated when include=["versions"].
     #[serde(default)]
     versions: Option<Vec<Option<i64>>>,
@@ -254,6 +256,61 @@ impl ChromaHttpBackend {
             .map_err(|err| (reqwest::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
     }
 
+    /// Open another collection on the same server, with this client's
+    /// credentials, tenant and database. The collection is created if missing.
+    pub async fn open_collection(&self, collection_name: &str) -> Result<Self, BackendError> {
+        let collection = match self.api_version {
+            ChromaApiVersion::V1 => {
+                Self::create_collection_v1(&self.client, &self.endpoint, collection_name).await?
+            }
+            ChromaApiVersion::V2 => Self::create_collection_v2(
+                &self.client,
+                &self.endpoint,
+                collection_name,
+                &self.tenant,
+                &self.database,
+            )
+            .await
+            .map_err(|(status, body)| {
+                BackendError::Other(format!(
+                    "Failed to create collection: {} - {}",
+                    status, body
+                ))
+            })?,
+        };
+        Ok(ChromaHttpBackend {
+            client: self.client.clone(),
+            endpoint: self.endpoint.clone(),
+            collection_id: collection.id,
+            collection_name: collection.name,
+            api_version: self.api_version,
+            tenant: self.tenant.clone(),
+            database: self.database.clone(),
+            embedder: self.embedder.clone(),
+        })
+    }
+
+    /// The collection this one's [`ACTIVE_COLLECTION_KEY`] metadata points
+    /// to, or `None` when it holds its own chunks.
+    pub async fn active_collection(&self) -> Result<Option<Self>, BackendError> {
+        let active = self
+            .get_collection_metadata()
+            .await?
+            .and_then(|meta| meta.get(ACTIVE_COLLECTION_KEY).cloned())
+            .and_then(|value| value.as_str().map(str::to_string));
+        match active {
+            Some(name) if name != self.collection_name => {
+                Ok(Some(self.open_collection(&name).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Switch to the collection this one points to, if any.
+    pub async fn follow_active(self) -> Result<Self, BackendError> {
+        Ok(self.active_collection().await?.unwrap_or(self))
+    }
+
     fn collection_url(&self) -> String {
         match self.api_version {
             ChromaApiVersion::V1 => {
@@ -453,23 +510,7 @@ impl ChromaHttpBackend {
                 .and_then(|m| m.get(i))
                 .and_then(|m| m.clone());
 
-            // Extract sparse vector from metadata
-            let sparse = metadata.as_ref().and_then(|m| {
-                let indices = m.get("_sparse_indices")?.as_array()?;
-                let values = m.get("_sparse_values")?.as_array()?;
-                let indices: Vec<u32> = indices
-                    .iter()
-                    .filter_map(|v| v.as_u64().map(|n| n as u32))
-                    .collect();
-                let values: Vec<f32> = values
-                    .iter()
-                    .filter_map(|v| v.as_f64().map(|n| n as f32))
-                    .collect();
-                if indices.is_empty() {
-                    return None;
-                }
-                Some(SparseEmbedding { indices, values })
-            });
+            let sparse = metadata.as_ref().and_then(sparse_from_metadata);
 
             if let Some(doc_sparse) = sparse {
                 let score = sparse_dot_product(query_sparse, &doc_sparse);
@@ -501,6 +542,24 @@ impl ChromaHttpBackend {
     }
 }
 
+/// Extract the sparse vector stored in a document's metadata.
+fn sparse_from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Option<SparseEmbedding> {
+    let indices = metadata.get("_sparse_indices")?.as_array()?;
+    let values = metadata.get("_sparse_values")?.as_array()?;
+    let indices: Vec<u32> = indices
+        .iter()
+        .filter_map(|v| v.as_u64().map(|n| n as u32))
+        .collect();
+    let values: Vec<f32> = values
+        .iter()
+        .filter_map(|v| v.as_f64().map(|n| n as f32))
+        .collect();
+    if indices.is_empty() {
+        return None;
+    }
+    Some(SparseEmbedding { indices, values })
+}
+
 /// Compute sparse dot product between two sparse vectors.
 fn sparse_dot_product(a: &SparseEmbedding, b: &SparseEmbedding) -> f32 {
     let b_map: HashMap<u32, f32> = b
@@ -631,6 +690,67 @@ impl ChromaStore for ChromaHttpBackend {
         self.score_sparse_results(&result, query_sparse, n_results)
     }
 
+    async fn get_by_metadata(
+        &self,
+        filter: serde_json::Value,
+    ) -> Result<Vec<StoredDocument>, BackendError> {
+        let request = GetDocumentsRequest {
+            ids: None,
+            r#where: Some(filter),
+            include: Some(vec![
+                "documents".to_string(),
+                "metadatas".to_string(),
+                "embeddings".to_string(),
+            ]),
+        };
+
+        let response = self
+            .client
+            .post(self.collection_op_url("get"))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| BackendError::Other(format!("Chroma request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(BackendError::Other(format!(
+                "Failed to get documents: {} - {}",
+                status, body
+            )));
+        }
+
+        let result: GetDocumentsResponse = response
+            .json()
+            .await
+            .map_err(|e| BackendError::Other(format!("Failed to parse response: {}", e)))?;
+
+        let mut documents = result.documents.unwrap_or_default().into_iter();
+        let mut metadatas = result.metadatas.unwrap_or_default().into_iter();
+        let mut embeddings = result.embeddings.unwrap_or_default().into_iter();
+        Ok(result
+            .ids
+            .into_iter()
+            .map(|id| {
+                // The sparse vector rides along in metadata; hand it back separately.
+                let mut metadata = metadatas.next().flatten();
+                let sparse_embedding = metadata.as_ref().and_then(sparse_from_metadata);
+                if let Some(metadata) = &mut metadata {
+                    metadata.remove("_sparse_indices");
+                    metadata.remove("_sparse_values");
+                }
+                StoredDocument {
+                    id,
+                    document: documents.next().flatten(),
+                    embedding: embeddings.next().flatten(),
+                    sparse_embedding,
+                    metadata,
+                }
+            })
+            .collect())
+    }
+
     async fn delete_by_metadata(&self, filter: serde_json::Value) -> Result<usize, BackendError> {
         let request = GetDocumentsRequest {
             ids: None,
@@ -0,0 +1,470 @@
+//! Write-back conflict detection.
+//!
+//! [`ConflictTracker`] remembers, per path, the CAS token of the remote
+//! version the local copy was read from (its base). A write-back flush
+//! writes conditionally on that base; when the remote file moved on, the
+//! mount's [`ConflictStrategy`] decides what happens and the conflict is
+//! recorded (in the WAL when there is one).
+//!
+//! Paths written without being read first have no base and are written
+//! unconditionally, as are paths on backends without CAS tokens. Outbox
+//! entries keep the base token, so writes replayed after a restart are
+//! checked the same way.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use openfs_config::ConflictStrategy;
+use openfs_core::{merge3_with_markers, Backend, BackendError};
+use tracing::warn;
+
+use crate::wal::WriteAheadLog;
+
+/// How a conflict was resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// The local version replaced the remote one.
+    Overwritten,
+    /// The remote version was kept and the local one written to `copy`.
+    KeptBoth { copy: String },
+    /// Local and remote changes were merged.
+    Merged,
+    /// Nothing was written; the local write was dead-lettered.
+    Failed,
+}
+
+impl ConflictResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictResolution::Overwritten => "overwritten",
+            ConflictResolution::KeptBoth { .. } => "kept_both",
+            ConflictResolution::Merged => "merged",
+            ConflictResolution::Failed => "failed",
+        }
+    }
+
+    /// Rebuild a resolution from [`Self::as_str`] and the copy path.
+    pub fn from_parts(resolution: &str, copy: Option<String>) -> Self {
+        match (resolution, copy) {
+            ("kept_both", Some(copy)) => ConflictResolution::KeptBoth { copy },
+            ("overwritten", _) => ConflictResolution::Overwritten,
+            ("merged", _) => ConflictResolution::Merged,
+            _ => ConflictResolution::Failed,
+        }
+    }
+
+    /// The path the local version was written to, for `KeptBoth`.
+    pub fn copy(&self) -> Option<&str> {
+        match self {
+            ConflictResolution::KeptBoth { copy } => Some(copy),
+            _ => None,
+        }
+    }
+}
+
+/// A write-back flush that found the remote file changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    pub path: String,
+    /// CAS token of the version the local copy was based on.
+    pub base: String,
+    /// CAS token of the remote version found instead.
+    pub remote: String,
+    pub resolution: ConflictResolution,
+    /// Unix seconds.
+    pub detected_at: i64,
+}
+
+/// What a flush left at the remote path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlushOutcome {
+    /// The flushed content.
+    Written,
+    /// A merge of the flushed content and the remote changes.
+    Merged(Vec<u8>),
+    /// The remote version; the flushed content went to a conflict copy.
+    KeptRemote,
+}
+
+/// The remote version a local copy came from.
+#[derive(Debug, Clone)]
+struct Base {
+    token: String,
+    /// Kept only for three-way merges.
+    content: Option<Vec<u8>>,
+}
+
+/// Tracks base versions and resolves conflicts for one mount.
+pub struct ConflictTracker {
+    strategy: ConflictStrategy,
+    bases: Mutex<HashMap<String, Base>>,
+    wal: Option<Arc<WriteAheadLog>>,
+    /// Conflicts seen, when there is no WAL to record them in.
+    seen: Mutex<Vec<SyncConflict>>,
+}
+
+impl ConflictTracker {
+    pub fn new(strategy: ConflictStrategy, wal: Option<Arc<WriteAheadLog>>) -> Self {
+        ConflictTracker {
+            strategy,
+            bases: Mutex::new(HashMap::new()),
+            wal,
+            seen: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn strategy(&self) -> ConflictStrategy {
+        self.strategy
+    }
+
+    /// Remember that the local copy of `path` is the remote version `token`.
+    pub fn observe(&self, path: &str, token: Option<String>, content: &[u8]) {
+        let mut bases = self.bases.lock().unwrap_or_else(|e| e.into_inner());
+        match token {
+            Some(token) => {
+                let content =
+                    (self.strategy == ConflictStrategy::ThreeWayMerge).then(|| content.to_vec());
+                bases.insert(path.to_string(), Base { token, content });
+            }
+            None => {
+                bases.remove(path);
+            }
+        }
+    }
+
+    /// Forget the base of `path`, e.g. after it was deleted or renamed.
+    pub fn forget(&self, path: &str) {
+        let mut bases = self.bases.lock().unwrap_or_else(|e| e.into_inner());
+        bases.remove(path);
+    }
+
+    /// Remember the base token of `path` from an outbox entry written before
+    /// a restart. Its content is not known, so a conflict is never merged.
+    pub fn restore(&self, path: &str, token: String) {
+        let mut bases = self.bases.lock().unwrap_or_else(|e| e.into_inner());
+        bases.insert(
+            path.to_string(),
+            Base {
+                token,
+                content: None,
+            },
+        );
+    }
+
+    /// Whether a remote version of `path` is known to check writes against.
+    pub fn has_base(&self, path: &str) -> bool {
+        self.base(path).is_some()
+    }
+
+    /// CAS token of the remote version the local copy of `path` is based on.
+    pub fn base_token(&self, path: &str) -> Option<String> {
+        self.base(path).map(|base| base.token)
+    }
+
+    fn base(&self, path: &str) -> Option<Base> {
+        let bases = self.bases.lock().unwrap_or_else(|e| e.into_inner());
+        bases.get(path).cloned()
+    }
+
+    /// Conflicts recorded so far, oldest first.
+    pub fn conflicts(&self) -> Result<Vec<SyncConflict>, String> {
+        match &self.wal {
+            Some(wal) => wal.conflicts(),
+            None => Ok(self.seen.lock().unwrap_or_else(|e| e.into_inner()).clone()),
+        }
+    }
+
+    /// Drop the recorded conflicts. Returns how many there were.
+    pub fn clear(&self) -> Result<usize, String> {
+        match &self.wal {
+            Some(wal) => wal.clear_conflicts(),
+            None => {
+                let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+                let count = seen.len();
+                seen.clear();
+                Ok(count)
+            }
+        }
+    }
+
+    fn record(&self, conflict: SyncConflict) {
+        warn!(
+            "Write-back conflict on {} ({}): base {}, remote {}",
+            conflict.path,
+            conflict.resolution.as_str(),
+            conflict.base,
+            conflict.remote
+        );
+        match &self.wal {
+            Some(wal) => {
+                if let Err(e) = wal.record_conflict(&conflict) {
+                    warn!("Failed to record conflict for {}: {}", conflict.path, e);
+                }
+            }
+            None => self
+                .seen
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(conflict),
+        }
+    }
+
+    /// Write `content` to `path`, conditional on the remote still being the
+    /// version the local copy was based on.
+    pub async fn flush(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        content: &[u8],
+    ) -> Result<FlushOutcome, BackendError> {
+        let Some(base) = self.base(path) else {
+            let token = backend.compare_and_swap(path, None, content).await?;
+            self.observe(path, token, content);
+            return Ok(FlushOutcome::Written);
+        };
+        match backend
+            .compare_and_swap(path, Some(&base.token), content)
+            .await
+        {
+            Ok(token) => {
+                self.observe(path, token, content);
+                Ok(FlushOutcome::Written)
+            }
+            Err(BackendError::PreconditionFailed { actual, .. }) => {
+                self.resolve(backend, path, content, base, actual).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn resolve(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        content: &[u8],
+        base: Base,
+        remote: String,
+    ) -> Result<FlushOutcome, BackendError> {
+        let detected_at = now_unix();
+        let (resolution, outcome) = match self.strategy {
+            ConflictStrategy::Overwrite => {
+                let token = backend.compare_and_swap(path, None, content).await?;
+                self.observe(path, token, content);
+                (ConflictResolution::Overwritten, FlushOutcome::Written)
+            }
+            ConflictStrategy::Fail => {
+                self.record(SyncConflict {
+                    path: path.to_string(),
+                    base: base.token.clone(),
+                    remote: remote.clone(),
+                    resolution: ConflictResolution::Failed,
+                    detected_at,
+                });
+                return Err(BackendError::PreconditionFailed {
+                    path: path.to_string(),
+                    expected: base.token,
+                    actual: remote,
+                });
+            }
+            ConflictStrategy::KeepBoth => {
+                self.keep_both(backend, path, content, detected_at).await?
+            }
+            ConflictStrategy::ThreeWayMerge => {
+                match self.merge(backend, path, content, &base).await? {
+                    Some(merged) => (ConflictResolution::Merged, FlushOutcome::Merged(merged)),
+                    None => self.keep_both(backend, path, content, detected_at).await?,
+                }
+            }
+        };
+        self.record(SyncConflict {
+            path: path.to_string(),
+            base: base.token,
+            remote,
+            resolution,
+            detected_at,
+        });
+        Ok(outcome)
+    }
+
+    async fn keep_both(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        content: &[u8],
+        detected_at: i64,
+    ) -> Result<(ConflictResolution, FlushOutcome), BackendError> {
+        let copy = conflict_copy_path(path, detected_at);
+        backend.write(&copy, content).await?;
+        self.forget(path);
+        Ok((
+            ConflictResolution::KeptBoth { copy },
+            FlushOutcome::KeptRemote,
+        ))
+    }
+
+    /// Merge `content` with the current remote text and write the result,
+    /// unless the remote changes again meanwhile. `None` when the versions
+    /// are not text or change the same lines.
+    async fn merge(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        content: &[u8],
+        base: &Base,
+    ) -> Result<Option<Vec<u8>>, BackendError> {
+        let Some(base_content) = &base.content else {
+            return Ok(None);
+        };
+        let (remote, token) = match backend.read_with_cas_token(path).await {
+            Ok(read) => read,
+            Err(BackendError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let (Ok(base_text), Ok(ours), Ok(theirs)) = (
+            std::str::from_utf8(base_content),
+            std::str::from_utf8(content),
+            std::str::from_utf8(&remote),
+        ) else {
+            return Ok(None);
+        };
+        let merge = merge3_with_markers(base_text, ours, theirs, "local", "remote");
+        if !merge.conflicts.is_empty() {
+            let lines: Vec<String> = merge
+                .conflicts
+                .iter()
+                .map(|c| c.base_start.to_string())
+                .collect();
+            warn!(
+                path,
+                "Local and remote changes conflict at base line(s) {}",
+                lines.join(", ")
+            );
+            return Ok(None);
+        }
+        let merged = merge.text;
+        match backend
+            .compare_and_swap(path, token.as_deref(), merged.as_bytes())
+            .await
+        {
+            Ok(token) => {
+                self.observe(path, token, merged.as_bytes());
+                Ok(Some(merged.into_bytes()))
+            }
+            Err(BackendError::PreconditionFailed { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Where `keep_both` puts the local version: `notes.txt` becomes
+/// `notes.conflict-<unix seconds>.txt`.
+pub fn conflict_copy_path(path: &str, detected_at: i64) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), path),
+    };
+    match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => format!(
+            "{}{}.conflict-{}{}",
+            dir,
+            &name[..dot],
+            detected_at,
+            &name[dot..]
+        ),
+        None => format!("{}{}.conflict-{}", dir, name, detected_at),
+    }
+}
+
+fn now_unix() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    async fn conflicted(
+        strategy: ConflictStrategy,
+        local: &[u8],
+    ) -> (MemoryBackend, ConflictTracker) {
+        let backend = MemoryBackend::new();
+        backend.write("notes.txt", b"a\nb\nc\n").await.unwrap();
+        let tracker = ConflictTracker::new(strategy, None);
+        let (content, token) = backend.read_with_cas_token("notes.txt").await.unwrap();
+        tracker.observe("notes.txt", token, &content);
+        // Someone else changes the file.
+        backend.write("notes.txt", b"a\nb\nC\n").await.unwrap();
+        let _ = tracker.flush(&backend, "notes.txt", local).await;
+        (backend, tracker)
+    }
+
+    #[test]
+    fn test_conflict_copy_path() {
+        assert_eq!(
+            conflict_copy_path("docs/notes.txt", 7),
+            "docs/notes.conflict-7.txt"
+        );
+        assert_eq!(conflict_copy_path("Makefile", 7), "Makefile.conflict-7");
+        assert_eq!(conflict_copy_path("a/.env", 7), "a/.env.conflict-7");
+    }
+
+    #[tokio::test]
+    async fn test_flush_without_conflict() {
+        let backend = MemoryBackend::new();
+        let tracker = ConflictTracker::new(ConflictStrategy::Fail, None);
+        let outcome = tracker.flush(&backend, "new.txt", b"one").await.unwrap();
+        assert_eq!(outcome, FlushOutcome::Written);
+        // The write became the base of the next one.
+        let outcome = tracker.flush(&backend, "new.txt", b"two").await.unwrap();
+        assert_eq!(outcome, FlushOutcome::Written);
+        assert!(tracker.conflicts().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conflict_strategies() {
+        let (backend, tracker) = conflicted(ConflictStrategy::Overwrite, b"mine\n").await;
+        assert_eq!(backend.read("notes.txt").await.unwrap(), b"mine\n");
+        assert_eq!(
+            tracker.conflicts().unwrap()[0].resolution,
+            ConflictResolution::Overwritten
+        );
+
+        let (backend, tracker) = conflicted(ConflictStrategy::Fail, b"mine\n").await;
+        assert_eq!(backend.read("notes.txt").await.unwrap(), b"a\nb\nC\n");
+        assert_eq!(
+            tracker.conflicts().unwrap()[0].resolution,
+            ConflictResolution::Failed
+        );
+
+        let (backend, tracker) = conflicted(ConflictStrategy::KeepBoth, b"mine\n").await;
+        assert_eq!(backend.read("notes.txt").await.unwrap(), b"a\nb\nC\n");
+        let conflict = &tracker.conflicts().unwrap()[0];
+        let copy = conflict.resolution.copy().unwrap();
+        assert!(copy.starts_with("notes.conflict-"), "{}", copy);
+        assert_eq!(backend.read(copy).await.unwrap(), b"mine\n");
+        assert_eq!(tracker.clear().unwrap(), 1);
+        assert!(tracker.conflicts().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_three_way_merge() {
+        let (backend, tracker) = conflicted(ConflictStrategy::ThreeWayMerge, b"A\nb\nc\n").await;
+        assert_eq!(backend.read("notes.txt").await.unwrap(), b"A\nb\nC\n");
+        assert_eq!(
+            tracker.conflicts().unwrap()[0].resolution,
+            ConflictResolution::Merged
+        );
+
+        // Overlapping edits fall back to keeping both.
+        let (backend, tracker) = conflicted(ConflictStrategy::ThreeWayMerge, b"a\nb\nc2\n").await;
+        assert_eq!(backend.read("notes.txt").await.unwrap(), b"a\nb\nC\n");
+        assert!(matches!(
+            tracker.conflicts().unwrap()[0].resolution,
+            ConflictResolution::KeptBoth { .. }
+        ));
+    }
+}
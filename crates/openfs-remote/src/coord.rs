@@ -0,0 +1,297 @@
+//! Coordination between agents sharing a mount, without an external system.
+//!
+//! A [`Lease`] is a small JSON file listing who holds it and until when. It
+//! is only ever changed by compare-and-swap, so of several agents racing to
+//! claim it, as many succeed as it has permits: one for leader election
+//! ([`Lease::leader`]), more for a named semaphore ([`Lease::semaphore`]).
+//! Holders keep their claim by calling [`Lease::try_acquire`] again well
+//! within the lease's time to live; a claim not renewed in time lapses, so a
+//! crashed holder blocks the others for at most that long.
+//!
+//! The mount holding the lease files needs a backend with compare-and-swap
+//! (local files, Postgres, Chroma, memory) and must not be write-back.
+//! Expiry is judged by each agent's own clock.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use openfs_core::{BackendError, VfsError};
+use serde::{Deserialize, Serialize};
+
+use crate::vfs::Vfs;
+
+/// Attempts at a compare-and-swap before giving up to contention.
+const MAX_ATTEMPTS: usize = 16;
+
+/// One holder of a lease.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseHolder {
+    pub holder: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LeaseFile {
+    holders: Vec<LeaseHolder>,
+}
+
+/// A lease file claimed by `holder`.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    path: String,
+    holder: String,
+    ttl: Duration,
+    permits: usize,
+}
+
+impl Lease {
+    /// Leader election: one holder at a time, e.g. for the "indexer" role.
+    pub fn leader(path: impl Into<String>, holder: impl Into<String>, ttl: Duration) -> Self {
+        Self::semaphore(path, holder, ttl, 1)
+    }
+
+    /// A named semaphore with `permits` holders at a time.
+    pub fn semaphore(
+        path: impl Into<String>,
+        holder: impl Into<String>,
+        ttl: Duration,
+        permits: usize,
+    ) -> Self {
+        Lease {
+            path: path.into(),
+            holder: holder.into(),
+            ttl,
+            permits: permits.max(1),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Claim a permit, or extend ours by the time to live. Returns whether
+    /// this holder has one; `false` means every permit is taken.
+    pub async fn try_acquire(&self, vfs: &Vfs) -> Result<bool, VfsError> {
+        let expires_at = Utc::now() + chrono::Duration::from_std(self.ttl).unwrap_or_default();
+        self.update(vfs, |holders| {
+            if let Some(ours) = holders.iter_mut().find(|h| h.holder == self.holder) {
+                ours.expires_at = expires_at;
+                return true;
+            }
+            if holders.len() >= self.permits {
+                return false;
+            }
+            holders.push(LeaseHolder {
+                holder: self.holder.clone(),
+                expires_at,
+            });
+            true
+        })
+        .await
+    }
+
+    /// Give up our permit. Returns whether we held one.
+    pub async fn release(&self, vfs: &Vfs) -> Result<bool, VfsError> {
+        self.update(vfs, |holders| {
+            let before = holders.len();
+            holders.retain(|h| h.holder != self.holder);
+            holders.len() < before
+        })
+        .await
+    }
+
+    /// Whether this holder has an unexpired permit.
+    pub async fn is_held(&self, vfs: &Vfs) -> Result<bool, VfsError> {
+        Ok(self
+            .holders(vfs)
+            .await?
+            .iter()
+            .any(|h| h.holder == self.holder))
+    }
+
+    /// The unexpired holders, in the order they claimed their permits.
+    pub async fn holders(&self, vfs: &Vfs) -> Result<Vec<LeaseHolder>, VfsError> {
+        Ok(self
+            .read(vfs)
+            .await?
+            .map(|(holders, _)| holders)
+            .unwrap_or_default())
+    }
+
+    /// Unexpired holders and the CAS token, or `None` without a lease file.
+    async fn read(
+        &self,
+        vfs: &Vfs,
+    ) -> Result<Option<(Vec<LeaseHolder>, Option<String>)>, VfsError> {
+        let (content, token) = match vfs.read_with_cas_token(&self.path).await {
+            Ok(read) => read,
+            Err(VfsError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let file: LeaseFile = serde_json::from_slice(&content).map_err(|e| {
+            VfsError::Config(format!("lease file {} is not valid: {}", self.path, e))
+        })?;
+        let now = Utc::now();
+        let holders = file
+            .holders
+            .into_iter()
+            .filter(|h| h.expires_at > now)
+            .collect();
+        Ok(Some((holders, token)))
+    }
+
+    /// Apply `change` to the unexpired holders and store them if it returns
+    /// `true`, retrying when another agent changed the file first.
+    async fn update(
+        &self,
+        vfs: &Vfs,
+        change: impl Fn(&mut Vec<LeaseHolder>) -> bool,
+    ) -> Result<bool, VfsError> {
+        for _ in 0..MAX_ATTEMPTS {
+            let Some((mut holders, token)) = self.read(vfs).await? else {
+                // Create the lease empty; the claim itself then goes through
+                // compare-and-swap like any other.
+                match self.write(vfs, None, &[]).await {
+                    Ok(()) => {}
+                    Err(e) if is_precondition_failed(&e) => {}
+                    Err(e) => return Err(e),
+                }
+                continue;
+            };
+            let Some(token) = token else {
+                return Err(VfsError::Config(format!(
+                    "lease {} needs a backend with compare-and-swap",
+                    self.path
+                )));
+            };
+            if !change(&mut holders) {
+                return Ok(false);
+            }
+            match self.write(vfs, Some(&token), &holders).await {
+                Ok(()) => return Ok(true),
+                Err(e) if is_precondition_failed(&e) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(VfsError::Config(format!(
+            "lease {} is contended: gave up after {} attempts",
+            self.path, MAX_ATTEMPTS
+        )))
+    }
+
+    /// Store `holders`, replacing the lease at `token`, or creating it.
+    async fn write(
+        &self,
+        vfs: &Vfs,
+        token: Option<&str>,
+        holders: &[LeaseHolder],
+    ) -> Result<(), VfsError> {
+        let file = LeaseFile {
+            holders: holders.to_vec(),
+        };
+        let json = serde_json::to_vec(&file)
+            .map_err(|e| VfsError::Config(format!("lease {}: {}", self.path, e)))?;
+        match token {
+            Some(token) => vfs.compare_and_swap(&self.path, Some(token), &json).await?,
+            None => vfs.create_if_absent(&self.path, &json).await?,
+        };
+        Ok(())
+    }
+}
+
+fn is_precondition_failed(error: &VfsError) -> bool {
+    match error {
+        VfsError::Backend(boxed) => boxed
+            .downcast_ref::<BackendError>()
+            .is_some_and(|e| matches!(e, BackendError::PreconditionFailed { .. })),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+    use tempfile::TempDir;
+
+    async fn vfs(root: &TempDir) -> Vfs {
+        let yaml = format!(
+            r#"
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /shared
+    backend: local
+"#,
+            root.path().display()
+        );
+        Vfs::from_config(VfsConfig::from_yaml(&yaml).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_one_leader_among_racing_agents() {
+        let root = TempDir::new().unwrap();
+        let vfs = vfs(&root).await;
+        let ttl = Duration::from_secs(60);
+
+        let claims = (0..8).map(|n| {
+            let vfs = &vfs;
+            async move {
+                let lease = Lease::leader("/shared/.coord/indexer", format!("agent-{}", n), ttl);
+                lease.try_acquire(vfs).await.map(|won| (lease, won))
+            }
+        });
+        let results: Vec<(Lease, bool)> = futures::future::join_all(claims)
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let winners: Vec<&Lease> = results
+            .iter()
+            .filter(|(_, won)| *won)
+            .map(|(l, _)| l)
+            .collect();
+        assert_eq!(winners.len(), 1);
+        let leader = winners[0];
+        assert!(leader.is_held(&vfs).await.unwrap());
+        // The leader renews; the others stay out until it lets go.
+        assert!(leader.try_acquire(&vfs).await.unwrap());
+        let (other, _) = results.iter().find(|(_, won)| !won).unwrap();
+        assert!(!other.try_acquire(&vfs).await.unwrap());
+        assert!(leader.release(&vfs).await.unwrap());
+        assert!(other.try_acquire(&vfs).await.unwrap());
+        assert_eq!(other.holders(&vfs).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_semaphore_permits_and_expiry() {
+        let root = TempDir::new().unwrap();
+        let vfs = vfs(&root).await;
+        let path = "/shared/.coord/embedders";
+        let short = Lease::semaphore(path, "a", Duration::from_millis(50), 2);
+        let b = Lease::semaphore(path, "b", Duration::from_secs(60), 2);
+        let c = Lease::semaphore(path, "c", Duration::from_secs(60), 2);
+
+        assert!(short.try_acquire(&vfs).await.unwrap());
+        assert!(b.try_acquire(&vfs).await.unwrap());
+        assert!(!c.try_acquire(&vfs).await.unwrap());
+
+        // A holder that stops renewing lapses.
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(!short.is_held(&vfs).await.unwrap());
+        assert!(c.try_acquire(&vfs).await.unwrap());
+        let holders: Vec<String> = b
+            .holders(&vfs)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|h| h.holder)
+            .collect();
+        assert_eq!(holders, vec!["b", "c"]);
+        assert!(!short.release(&vfs).await.unwrap());
+    }
+}
@@ -0,0 +1,319 @@
+//! On-disk cache of file contents.
+//!
+//! [`DiskCache`] keeps one file per cached path, named by the BLAKE3 hash of
+//! the path. Each file starts with a small header (when it was stored and
+//! the full path, to rule out hash collisions), followed by the content.
+//! A file's modification time is bumped on every hit, so eviction can drop
+//! the least recently used files first.
+
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use openfs_core::DiskCacheConfig;
+use tracing::{debug, warn};
+
+const ENTRY_EXTENSION: &str = "entry";
+
+/// Disk cache statistics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskCacheStats {
+    /// Directory holding the cache.
+    pub dir: PathBuf,
+    /// Number of cached files.
+    pub entries: usize,
+    /// Bytes used on disk.
+    pub bytes: u64,
+    /// Bytes allowed before eviction.
+    pub max_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A size-bounded cache of file contents in a directory.
+pub struct DiskCache {
+    config: DiskCacheConfig,
+    bytes: AtomicU64,
+    entries: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Serializes eviction scans.
+    evicting: Mutex<()>,
+}
+
+impl DiskCache {
+    /// Open the cache, creating its directory and counting what is already
+    /// there from earlier runs.
+    pub fn open(config: DiskCacheConfig) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+        let cache = DiskCache {
+            config,
+            bytes: AtomicU64::new(0),
+            entries: AtomicUsize::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evicting: Mutex::new(()),
+        };
+        let files = cache.scan()?;
+        cache.entries.store(files.len(), Ordering::Relaxed);
+        cache
+            .bytes
+            .store(files.iter().map(|f| f.1).sum(), Ordering::Relaxed);
+        Ok(cache)
+    }
+
+    /// Get the cached content for `key`, if present and not expired.
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.entry_path(key);
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(_) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        let Some((stored, entry_key, body)) = decode(&content) else {
+            self.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        if entry_key != key {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let age = SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(stored))
+            .unwrap_or_default();
+        if age > self.config.ttl {
+            debug!(key = %key, "disk cache entry expired");
+            self.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        // Mark as recently used for eviction.
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(body.to_vec())
+    }
+
+    /// Store `content` for `key`, evicting older files if over the limit.
+    pub fn put(&self, key: &str, content: &[u8]) {
+        let encoded = encode(key, content);
+        let size = encoded.len() as u64;
+        if size > self.config.max_size {
+            debug!(key = %key, size, "entry too large for disk cache");
+            return;
+        }
+
+        let path = self.entry_path(key);
+        let previous = std::fs::metadata(&path).map(|m| m.len()).ok();
+        if let Err(e) = write_atomic(&path, &encoded) {
+            warn!(key = %key, error = %e, "failed to write disk cache entry");
+            return;
+        }
+        match previous {
+            Some(previous) => {
+                self.bytes.fetch_sub(previous, Ordering::Relaxed);
+            }
+            None => {
+                self.entries.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if self.bytes.fetch_add(size, Ordering::Relaxed) + size > self.config.max_size {
+            self.evict();
+        }
+    }
+
+    /// Drop the cached content for `key`.
+    pub fn remove(&self, key: &str) {
+        let path = self.entry_path(key);
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            if std::fs::remove_file(&path).is_ok() {
+                self.bytes.fetch_sub(metadata.len(), Ordering::Relaxed);
+                self.entries.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Drop every cached file.
+    pub fn clear(&self) {
+        let _guard = self.evicting.lock().unwrap_or_else(|e| e.into_inner());
+        for (path, _, _) in self.scan().unwrap_or_default() {
+            let _ = std::fs::remove_file(path);
+        }
+        self.bytes.store(0, Ordering::Relaxed);
+        self.entries.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> DiskCacheStats {
+        DiskCacheStats {
+            dir: self.config.dir.clone(),
+            entries: self.entries.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            max_bytes: self.config.max_size,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Remove least recently used files until the cache fits its limit.
+    /// The counters are reset from the directory, correcting any drift.
+    fn evict(&self) {
+        let Ok(_guard) = self.evicting.try_lock() else {
+            return;
+        };
+        let Ok(mut files) = self.scan() else {
+            return;
+        };
+        files.sort_by_key(|(_, _, used)| *used);
+        let mut bytes: u64 = files.iter().map(|f| f.1).sum();
+        let mut entries = files.len();
+        for (path, size, _) in files {
+            if bytes <= self.config.max_size {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(_) => continue,
+            }
+            bytes -= size;
+            entries -= 1;
+        }
+        self.bytes.store(bytes, Ordering::Relaxed);
+        self.entries.store(entries, Ordering::Relaxed);
+    }
+
+    /// Every entry file with its size and last use.
+    fn scan(&self) -> std::io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.config.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != ENTRY_EXTENSION) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+            files.push((path, metadata.len(), used));
+        }
+        Ok(files)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let hash = blake3::hash(key.as_bytes()).to_hex();
+        self.config
+            .dir
+            .join(format!("{}.{}", hash, ENTRY_EXTENSION))
+    }
+}
+
+/// Header: store time (u64 seconds), key length (u32), key; then content.
+fn encode(key: &str, content: &[u8]) -> Vec<u8> {
+    let stored = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut encoded = Vec::with_capacity(12 + key.len() + content.len());
+    encoded.extend_from_slice(&stored.to_le_bytes());
+    encoded.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    encoded.extend_from_slice(key.as_bytes());
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+fn decode(encoded: &[u8]) -> Option<(u64, &str, &[u8])> {
+    let stored = u64::from_le_bytes(encoded.get(..8)?.try_into().ok()?);
+    let key_len = u32::from_le_bytes(encoded.get(8..12)?.try_into().ok()?) as usize;
+    let key = std::str::from_utf8(encoded.get(12..12 + key_len)?).ok()?;
+    Some((stored, key, &encoded[12 + key_len..]))
+}
+
+/// Write through a temporary file so readers never see a partial entry.
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let tmp = path.with_extension(format!(
+        "tmp.{}.{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(content)?;
+    drop(file);
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open(dir: &TempDir, max_size: u64, ttl: Duration) -> DiskCache {
+        DiskCache::open(DiskCacheConfig {
+            dir: dir.path().to_path_buf(),
+            max_size,
+            ttl,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_disk_cache_survives_reopen() {
+        let dir = TempDir::new().unwrap();
+        let cache = open(&dir, 1024 * 1024, Duration::from_secs(60));
+        cache.put("docs/a.txt", b"hello");
+        assert_eq!(cache.get("docs/a.txt").unwrap(), b"hello");
+        assert!(cache.get("docs/b.txt").is_none());
+        drop(cache);
+
+        let cache = open(&dir, 1024 * 1024, Duration::from_secs(60));
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(cache.get("docs/a.txt").unwrap(), b"hello");
+
+        cache.remove("docs/a.txt");
+        assert!(cache.get("docs/a.txt").is_none());
+        assert_eq!(cache.stats().bytes, 0);
+    }
+
+    #[test]
+    fn test_disk_cache_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        // Room for two 100-byte entries with their headers.
+        let cache = open(&dir, 250, Duration::from_secs(60));
+        cache.put("a", &[b'a'; 100]);
+        cache.put("b", &[b'b'; 100]);
+        // Make "a" the most recently used.
+        let old = SystemTime::now() - Duration::from_secs(10);
+        let file = std::fs::File::options()
+            .write(true)
+            .open(cache.entry_path("b"))
+            .unwrap();
+        file.set_modified(old).unwrap();
+        cache.put("c", &[b'c'; 100]);
+
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn test_disk_cache_ttl() {
+        let dir = TempDir::new().unwrap();
+        let cache = open(&dir, 1024, Duration::ZERO);
+        cache.put("a", b"stale");
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -1,9 +1,13 @@
 //! Shared grep implementation for regex-based file searching.
 
+use std::sync::Arc;
+
 use regex::Regex;
+use tracing::debug;
 
+use crate::trigram::{TrigramIndex, TrigramQuery};
 use crate::vfs::Vfs;
-use openfs_core::VfsError;
+use openfs_core::{is_binary_content, Entry, VfsError};
 
 /// A single grep match.
 #[derive(Debug, Clone)]
@@ -21,6 +25,8 @@ pub struct GrepOptions {
     pub max_matches: usize,
     /// Maximum directory recursion depth.
     pub max_depth: usize,
+    /// Skip files whose trigrams rule out a match, and index the files read.
+    pub trigram_index: Option<Arc<TrigramIndex>>,
 }
 
 impl Default for GrepOptions {
@@ -29,6 +35,7 @@ impl Default for GrepOptions {
             recursive: false,
             max_matches: 1000,
             max_depth: 10,
+            trigram_index: None,
         }
     }
 }
@@ -44,41 +51,74 @@ pub async fn grep(
     path: &str,
     options: &GrepOptions,
 ) -> Result<Vec<GrepMatch>, VfsError> {
-    let re = Regex::new(pattern).map_err(|e| VfsError::Config(format!("Invalid regex: {}", e)))?;
     let mut matches = Vec::new();
+    grep_each(vfs, pattern, path, options, |m| matches.push(m)).await?;
+    Ok(matches)
+}
+
+/// Like [`grep`], but hands each match to `on_match` as soon as it is found
+/// instead of collecting them. Returns the number of matches.
+pub async fn grep_each(
+    vfs: &Vfs,
+    pattern: &str,
+    path: &str,
+    options: &GrepOptions,
+    mut on_match: impl FnMut(GrepMatch) + Send,
+) -> Result<usize, VfsError> {
+    let re = Regex::new(pattern).map_err(|e| VfsError::Config(format!("Invalid regex: {}", e)))?;
+    let query = TrigramQuery::from_regex(pattern);
+    let index = options
+        .trigram_index
+        .as_deref()
+        .map(|index| (index, &query));
+    let mut matches = Matches {
+        found: 0,
+        max: options.max_matches,
+        on_match: &mut on_match,
+    };
 
     // Try reading as a file first
     if let Ok(content) = vfs.read(path).await {
-        grep_content(path, &content, &re, &mut matches, options.max_matches);
-        return Ok(matches);
+        grep_content(path, &content, &re, &mut matches);
+        return Ok(matches.found);
     }
 
     // Otherwise treat as directory
     if options.recursive {
-        grep_recursive(
-            vfs,
-            path,
-            &re,
-            &mut matches,
-            options.max_matches,
-            options.max_depth,
-        )
-        .await;
+        grep_recursive(vfs, path, &re, &mut matches, options.max_depth, index).await;
     } else {
-        grep_directory(vfs, path, &re, &mut matches, options.max_matches).await;
+        grep_directory(vfs, path, &re, &mut matches, index).await;
     }
 
-    Ok(matches)
+    Ok(matches.found)
 }
 
-fn grep_content(path: &str, content: &[u8], re: &Regex, matches: &mut Vec<GrepMatch>, max: usize) {
-    let text = match std::str::from_utf8(content) {
-        Ok(t) => t,
-        Err(_) => return, // Skip binary files
-    };
+/// Matches found so far, up to `max`.
+struct Matches<'a> {
+    found: usize,
+    max: usize,
+    on_match: &'a mut (dyn FnMut(GrepMatch) + Send),
+}
+
+impl Matches<'_> {
+    fn is_full(&self) -> bool {
+        self.found >= self.max
+    }
+
+    fn push(&mut self, m: GrepMatch) {
+        self.found += 1;
+        (self.on_match)(m);
+    }
+}
+
+fn grep_content(path: &str, content: &[u8], re: &Regex, matches: &mut Matches<'_>) {
+    if is_binary_content(content) {
+        return;
+    }
+    let text = String::from_utf8_lossy(content);
 
     for (i, line) in text.lines().enumerate() {
-        if matches.len() >= max {
+        if matches.is_full() {
             return;
         }
         if re.is_match(line) {
@@ -99,23 +139,45 @@ fn join_path(dir: &str, name: &str) -> String {
     }
 }
 
+/// Grep one listed file, unless the trigram index rules it out.
+async fn grep_file(
+    vfs: &Vfs,
+    path: &str,
+    entry: &Entry,
+    re: &Regex,
+    matches: &mut Matches<'_>,
+    index: Option<(&TrigramIndex, &TrigramQuery)>,
+) {
+    if let Some((index, query)) = index {
+        if !index.may_match(path, entry, query) {
+            return;
+        }
+    }
+    if let Ok(content) = vfs.read(path).await {
+        if let Some((index, _)) = index {
+            if let Err(e) = index.update(path, entry, &content) {
+                debug!(path = %path, "could not index trigrams: {}", e);
+            }
+        }
+        grep_content(path, &content, re, matches);
+    }
+}
+
 async fn grep_directory(
     vfs: &Vfs,
     path: &str,
     re: &Regex,
-    matches: &mut Vec<GrepMatch>,
-    max: usize,
+    matches: &mut Matches<'_>,
+    index: Option<(&TrigramIndex, &TrigramQuery)>,
 ) {
     if let Ok(entries) = vfs.list(path).await {
         for entry in entries {
-            if matches.len() >= max {
+            if matches.is_full() {
                 return;
             }
             if !entry.is_dir {
                 let full_path = join_path(path, &entry.name);
-                if let Ok(content) = vfs.read(&full_path).await {
-                    grep_content(&full_path, &content, re, matches, max);
-                }
+                grep_file(vfs, &full_path, &entry, re, matches, index).await;
             }
         }
     }
@@ -125,23 +187,31 @@ async fn grep_recursive(
     vfs: &Vfs,
     path: &str,
     re: &Regex,
-    matches: &mut Vec<GrepMatch>,
-    max: usize,
+    matches: &mut Matches<'_>,
     depth: usize,
+    index: Option<(&TrigramIndex, &TrigramQuery)>,
 ) {
-    if depth == 0 || matches.len() >= max {
+    if depth == 0 || matches.is_full() {
         return;
     }
     if let Ok(entries) = vfs.list(path).await {
         for entry in entries {
-            if matches.len() >= max {
+            if matches.is_full() {
                 return;
             }
             let full_path = join_path(path, &entry.name);
             if entry.is_dir {
-                Box::pin(grep_recursive(vfs, &full_path, re, matches, max, depth - 1)).await;
-            } else if let Ok(content) = vfs.read(&full_path).await {
-                grep_content(&full_path, &content, re, matches, max);
+                Box::pin(grep_recursive(
+                    vfs,
+                    &full_path,
+                    re,
+                    matches,
+                    depth - 1,
+                    index,
+                ))
+                .await;
+            } else {
+                grep_file(vfs, &full_path, &entry, re, matches, index).await;
             }
         }
     }
@@ -204,6 +274,10 @@ mounts:
         vfs.write("/workspace/b.txt", b"goodbye world")
             .await
             .unwrap();
+        // Binary files are skipped even when the bytes would match.
+        vfs.write("/workspace/c.dat", b"hello\0\x01\x02")
+            .await
+            .unwrap();
 
         let matches = grep(&vfs, "hello", "/workspace", &GrepOptions::default())
             .await
@@ -265,4 +339,40 @@ mounts:
         let result = grep(&vfs, "[invalid", "/workspace", &GrepOptions::default()).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_grep_trigram_index() {
+        let tmp = TempDir::new().unwrap();
+        let config = make_config(tmp.path().to_str().unwrap());
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        vfs.write("/workspace/a.txt", b"hello top").await.unwrap();
+        vfs.write("/workspace/sub/b.txt", b"goodbye nested")
+            .await
+            .unwrap();
+
+        let index = Arc::new(TrigramIndex::in_memory().unwrap());
+        let opts = GrepOptions {
+            recursive: true,
+            trigram_index: Some(index.clone()),
+            ..Default::default()
+        };
+        let matches = grep(&vfs, "hello", "/workspace", &opts).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(index.len(), 2);
+
+        // Pruned files are not read: a stale entry for an unchanged listing
+        // hides a match, which is why watch events invalidate entries.
+        let b = vfs.stat("/workspace/sub/b.txt").await.unwrap();
+        index
+            .update("/workspace/sub/b.txt", &b, b"nothing here")
+            .unwrap();
+        let matches = grep(&vfs, "goodbye", "/workspace", &opts).await.unwrap();
+        assert!(matches.is_empty());
+
+        index.invalidate("/workspace/sub").unwrap();
+        let matches = grep(&vfs, "goodbye", "/workspace", &opts).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "/workspace/sub/b.txt");
+    }
 }
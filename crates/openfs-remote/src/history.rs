@@ -0,0 +1,310 @@
+//! The `/.history` tree: earlier states of the mounts, read from snapshots.
+//!
+//! `/.history/<point>/<path>` is `<path>` as it was at `<point>`, which is a
+//! snapshot id, a snapshot label or `@` followed by a time (see
+//! [`HistoryPoint::parse`]). Each mount resolves the point against its own
+//! snapshots, so a label given to snapshots of several mounts shows all of
+//! them. Nothing below `/.history` can be written.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use openfs_core::{Backend, BackendError, Entry};
+
+use crate::snapshot::{HistoryPoint, SnapshotManifest, SnapshotStore};
+use crate::vfs::{is_same_or_under, snapshot_store_in, SnapshotDir};
+
+/// Where the history tree is routed.
+pub const HISTORY_ROOT: &str = "/.history";
+
+/// Backend serving the history tree of the mounts it was built with.
+pub struct HistoryBackend {
+    /// Mount paths without a trailing slash, longest first.
+    mounts: Vec<String>,
+    dir: SnapshotDir,
+    /// Manifests already read, by file. Snapshots are never rewritten.
+    manifests: Mutex<HashMap<PathBuf, Arc<SnapshotManifest>>>,
+}
+
+/// The snapshot of each mount that has one at a history point, longest
+/// mount path first.
+type Snapshots<'a> = Vec<(&'a str, Arc<SnapshotManifest>)>;
+
+impl HistoryBackend {
+    pub fn new(mounts: impl IntoIterator<Item = String>, dir: SnapshotDir) -> Self {
+        let mut mounts: Vec<String> = mounts
+            .into_iter()
+            .map(|mount| mount.trim_end_matches('/').to_string())
+            .collect();
+        mounts.sort_by_key(|mount| std::cmp::Reverse(mount.len()));
+        HistoryBackend {
+            mounts,
+            dir,
+            manifests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Content of the file at VFS path `path` as it was at `point`.
+    pub fn read_at(&self, path: &str, point: &HistoryPoint) -> Result<Vec<u8>, BackendError> {
+        let path = format!("/{}", path.trim_matches('/'));
+        let snapshots = self.at(point)?;
+        let (mount, manifest) = self
+            .owner(&snapshots, &path)
+            .ok_or_else(|| BackendError::NotFound(path.clone()))?;
+        let file = manifest
+            .files
+            .get(relative(&path, mount))
+            .ok_or_else(|| BackendError::NotFound(path.clone()))?;
+        Ok(self.store()?.blob(&file.hash)?)
+    }
+
+    fn store(&self) -> Result<SnapshotStore, BackendError> {
+        snapshot_store_in(&self.dir).map_err(|e| BackendError::Other(e.to_string()))
+    }
+
+    /// Snapshots of `mount`, oldest first.
+    fn manifests(
+        &self,
+        store: &SnapshotStore,
+        mount: &str,
+    ) -> Result<Vec<Arc<SnapshotManifest>>, BackendError> {
+        let mut manifests = Vec::new();
+        for path in store.manifest_paths(mount)? {
+            let cached = self
+                .manifests
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&path)
+                .cloned();
+            let manifest = match cached {
+                Some(manifest) => manifest,
+                None => {
+                    let manifest = Arc::new(SnapshotStore::load(&path)?);
+                    self.manifests
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(path, Arc::clone(&manifest));
+                    manifest
+                }
+            };
+            manifests.push(manifest);
+        }
+        Ok(manifests)
+    }
+
+    fn at(&self, point: &HistoryPoint) -> Result<Snapshots<'_>, BackendError> {
+        let store = self.store()?;
+        let mut snapshots = Vec::new();
+        for mount in &self.mounts {
+            let manifests = self.manifests(&store, mount)?;
+            if let Some(manifest) = point.pick(&manifests) {
+                snapshots.push((mount.as_str(), Arc::clone(manifest)));
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// The snapshot of the mount `path` belongs to, if that mount has one.
+    fn owner<'s>(
+        &self,
+        snapshots: &'s Snapshots<'_>,
+        path: &str,
+    ) -> Option<(&'s str, &'s SnapshotManifest)> {
+        let mount = self
+            .mounts
+            .iter()
+            .find(|mount| is_same_or_under(path, mount))?;
+        snapshots
+            .iter()
+            .find(|(candidate, _)| candidate == mount)
+            .map(|(mount, manifest)| (*mount, manifest.as_ref()))
+    }
+
+    /// Directories and files in `dir` (a VFS path without a trailing slash)
+    /// at the point `snapshots` were taken, with entry paths below `at`.
+    fn children(&self, snapshots: &Snapshots<'_>, dir: &str, at: &str) -> Vec<Entry> {
+        let mut children = BTreeMap::new();
+        let mut add = |entry: Entry| {
+            children
+                .entry((!entry.is_dir, entry.name.clone()))
+                .or_insert(entry);
+        };
+
+        // Mounts below `dir` appear as directories.
+        for (mount, manifest) in snapshots {
+            if mount.len() > dir.len() && is_same_or_under(mount, dir) {
+                let rest = mount[dir.len()..].trim_start_matches('/');
+                let name = rest.split('/').next().unwrap_or(rest);
+                add(Entry::dir(
+                    join(at, name),
+                    name.to_string(),
+                    Some(manifest.created_at),
+                ));
+            }
+        }
+
+        if let Some((mount, manifest)) = self.owner(snapshots, dir) {
+            let prefix = join(relative(dir, mount), "");
+            let files = manifest
+                .files
+                .range(prefix.clone()..)
+                .take_while(|(path, _)| path.starts_with(&prefix));
+            for (path, file) in files {
+                let modified = Some(manifest.created_at);
+                add(match path[prefix.len()..].split_once('/') {
+                    Some((name, _)) => Entry::dir(join(at, name), name.to_string(), modified),
+                    None => {
+                        let name = &path[prefix.len()..];
+                        Entry::file(join(at, name), name.to_string(), file.size, modified)
+                    }
+                });
+            }
+        }
+        // Directories first, then files, each alphabetically.
+        children.into_values().collect()
+    }
+
+    /// Every snapshot id and label, each as a directory.
+    fn points(&self) -> Result<Vec<Entry>, BackendError> {
+        let store = self.store()?;
+        let mut points: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+        for mount in &self.mounts {
+            for manifest in self.manifests(&store, mount)? {
+                let names = std::iter::once(&manifest.id).chain(manifest.label.as_ref());
+                for name in names {
+                    let created = points.entry(name.clone()).or_insert(manifest.created_at);
+                    *created = (*created).max(manifest.created_at);
+                }
+            }
+        }
+        Ok(points
+            .into_iter()
+            .map(|(name, created)| Entry::dir(name.clone(), name, Some(created)))
+            .collect())
+    }
+}
+
+/// Split a backend path into the history point and the VFS path below it.
+fn split(path: &str) -> (&str, String) {
+    let relative = path.trim_matches('/');
+    match relative.split_once('/') {
+        Some((point, rest)) => (point, format!("/{}", rest)),
+        None => (relative, String::new()),
+    }
+}
+
+/// `path` relative to `mount`, which it lies under.
+fn relative<'p>(path: &'p str, mount: &str) -> &'p str {
+    path[mount.len()..].trim_start_matches('/')
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn read_only(path: &str) -> BackendError {
+    BackendError::PermissionDenied(format!("'{}' is in the read-only history", path))
+}
+
+#[async_trait]
+impl Backend for HistoryBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        match split(path) {
+            (point, vfs_path) if !vfs_path.is_empty() => {
+                self.read_at(&vfs_path, &HistoryPoint::parse(point))
+            }
+            _ => Err(BackendError::NotFound(path.to_string())),
+        }
+    }
+
+    async fn write(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn append(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn rename(&self, from: &str, _to: &str) -> Result<(), BackendError> {
+        Err(read_only(from))
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        let (point, vfs_path) = split(path);
+        if point.is_empty() {
+            return self.points();
+        }
+        let snapshots = self.at(&HistoryPoint::parse(point))?;
+        let dir = vfs_path.trim_end_matches('/');
+        let children = self.children(&snapshots, dir, path.trim_matches('/'));
+        if children.is_empty() && !snapshots.iter().any(|(mount, _)| *mount == dir) {
+            return Err(match self.stat(path).await {
+                Ok(_) => BackendError::NotADirectory(path.to_string()),
+                Err(e) => e,
+            });
+        }
+        Ok(children)
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(BackendError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        let relative_path = path.trim_matches('/');
+        let (point, vfs_path) = split(path);
+        let name = relative_path.rsplit('/').next().unwrap_or_default();
+        if point.is_empty() {
+            let root = HISTORY_ROOT.trim_start_matches('/');
+            return Ok(Entry::dir(String::new(), root.to_string(), None));
+        }
+
+        let snapshots = self.at(&HistoryPoint::parse(point))?;
+        let newest = snapshots.iter().map(|(_, m)| m.created_at).max();
+        if vfs_path.is_empty() {
+            return match newest {
+                Some(created) => Ok(Entry::dir(
+                    relative_path.to_string(),
+                    name.to_string(),
+                    Some(created),
+                )),
+                None => Err(BackendError::NotFound(path.to_string())),
+            };
+        }
+
+        if let Some((mount, manifest)) = self.owner(&snapshots, &vfs_path) {
+            if let Some(file) = manifest.files.get(relative(&vfs_path, mount)) {
+                return Ok(Entry::file(
+                    relative_path.to_string(),
+                    name.to_string(),
+                    file.size,
+                    Some(manifest.created_at),
+                ));
+            }
+        }
+        let is_mount = snapshots.iter().any(|(mount, _)| *mount == vfs_path);
+        if is_mount || !self.children(&snapshots, &vfs_path, "").is_empty() {
+            return Ok(Entry::dir(
+                relative_path.to_string(),
+                name.to_string(),
+                newest,
+            ));
+        }
+        Err(BackendError::NotFound(path.to_string()))
+    }
+}
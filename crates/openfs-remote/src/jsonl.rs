@@ -0,0 +1,213 @@
+//! JSON Lines files as append-only event streams.
+//!
+//! [`Vfs::append_jsonl`] appends one record per line, and
+//! [`Vfs::read_jsonl_since`] returns the records added since a
+//! [`JsonlCursor`], so a file can serve as a durable queue between agents
+//! on any backend. Cursors are plain strings when serialized, for consumers
+//! to store between runs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use openfs_core::VfsError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::Vfs;
+
+/// Position in a JSON Lines file: the byte offset after the last record
+/// read, and the file's ETag at the time, where the backend lists one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonlCursor {
+    offset: u64,
+    etag: Option<String>,
+}
+
+impl JsonlCursor {
+    /// A cursor before the first record.
+    pub fn start() -> Self {
+        JsonlCursor::default()
+    }
+
+    /// Byte offset of the next record.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl fmt::Display for JsonlCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.etag {
+            Some(etag) => write!(f, "{}:{}", self.offset, etag),
+            None => write!(f, "{}", self.offset),
+        }
+    }
+}
+
+impl FromStr for JsonlCursor {
+    type Err = VfsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (offset, etag) = match s.split_once(':') {
+            Some((offset, etag)) => (offset, Some(etag.to_string())),
+            None => (s, None),
+        };
+        let offset = offset
+            .parse()
+            .map_err(|_| VfsError::InvalidArgument(format!("Invalid JSONL cursor: {}", s)))?;
+        Ok(JsonlCursor { offset, etag })
+    }
+}
+
+/// Records read by [`Vfs::read_jsonl_since`].
+#[derive(Debug, Clone)]
+pub struct JsonlRecords<T> {
+    pub records: Vec<T>,
+    /// Where the next read should start.
+    pub cursor: JsonlCursor,
+}
+
+impl Vfs {
+    /// Append `value` to a JSON Lines file as a single line, creating the
+    /// file if needed.
+    pub async fn append_jsonl<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        value: &T,
+    ) -> Result<(), VfsError> {
+        let mut line = serde_json::to_vec(value)
+            .map_err(|e| VfsError::InvalidArgument(format!("Cannot serialize record: {}", e)))?;
+        line.push(b'\n');
+        self.append(path, &line).await
+    }
+
+    /// Read the records appended to a JSON Lines file after `cursor`.
+    ///
+    /// Only complete lines are returned, so a record being appended is read
+    /// on the next call. A missing file reads as empty, and a file that has
+    /// shrunk below the cursor (rewritten or truncated) is read again from
+    /// the start. When the backend lists ETags and the file's is unchanged,
+    /// nothing is read.
+    pub async fn read_jsonl_since<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        cursor: &JsonlCursor,
+    ) -> Result<JsonlRecords<T>, VfsError> {
+        let entry = match self.stat(path).await {
+            Ok(entry) => entry,
+            Err(VfsError::NotFound(_)) => {
+                return Ok(JsonlRecords {
+                    records: Vec::new(),
+                    cursor: JsonlCursor::start(),
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        if entry.etag.is_some() && entry.etag == cursor.etag {
+            return Ok(JsonlRecords {
+                records: Vec::new(),
+                cursor: cursor.clone(),
+            });
+        }
+
+        let size = entry.size.unwrap_or(0);
+        let start = if size < cursor.offset {
+            0
+        } else {
+            cursor.offset
+        };
+        let content = if size == start {
+            Vec::new()
+        } else {
+            self.read_range(path, start, Some(size - start)).await?
+        };
+
+        let complete = content
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+        let mut records = Vec::new();
+        let mut line_start = start;
+        for line in content[..complete].split_inclusive(|&b| b == b'\n') {
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                records.push(serde_json::from_slice(line).map_err(|e| {
+                    VfsError::Config(format!(
+                        "{} has an invalid record at byte {}: {}",
+                        path, line_start, e
+                    ))
+                })?);
+            }
+            line_start += line.len() as u64;
+        }
+        let etag = if complete == content.len() {
+            entry.etag
+        } else {
+            None
+        };
+        Ok(JsonlRecords {
+            records,
+            cursor: JsonlCursor {
+                offset: start + complete as u64,
+                etag,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_jsonl_tail() {
+        let config = VfsConfig::from_yaml(
+            r#"
+backends:
+  mem:
+    type: memory
+mounts:
+  - path: /workspace
+    backend: mem
+"#,
+        )
+        .unwrap();
+        let vfs = Vfs::from_config(config).await.unwrap();
+        let path = "/workspace/events.jsonl";
+
+        let empty = vfs
+            .read_jsonl_since::<Value>(path, &JsonlCursor::start())
+            .await
+            .unwrap();
+        assert!(empty.records.is_empty());
+
+        vfs.append_jsonl(path, &json!({"n": 1})).await.unwrap();
+        vfs.append_jsonl(path, &json!({"n": 2})).await.unwrap();
+        let first = vfs
+            .read_jsonl_since::<Value>(path, &JsonlCursor::start())
+            .await
+            .unwrap();
+        assert_eq!(first.records, [json!({"n": 1}), json!({"n": 2})]);
+
+        // A half-written record waits for its newline.
+        vfs.append(path, b"{\"n\": 3}").await.unwrap();
+        let cursor: JsonlCursor = first.cursor.to_string().parse().unwrap();
+        let partial = vfs.read_jsonl_since::<Value>(path, &cursor).await.unwrap();
+        assert!(partial.records.is_empty());
+        vfs.append(path, b"\n").await.unwrap();
+        let next = vfs
+            .read_jsonl_since::<Value>(path, &partial.cursor)
+            .await
+            .unwrap();
+        assert_eq!(next.records, [json!({"n": 3})]);
+
+        vfs.write(path, b"{\"n\": 4}\n").await.unwrap();
+        let rewritten = vfs
+            .read_jsonl_since::<Value>(path, &next.cursor)
+            .await
+            .unwrap();
+        assert_eq!(rewritten.records, [json!({"n": 4})]);
+        assert!("x".parse::<JsonlCursor>().is_err());
+    }
+}
@@ -1,20 +1,54 @@
 pub mod backends;
 pub mod cached_backend;
 pub mod chroma_http;
+pub mod conflict;
+pub mod coord;
+pub mod disk_cache;
 pub mod grep;
+pub mod jsonl;
+pub mod history;
+pub mod log_file;
+pub mod merge;
+pub mod multipart;
+pub mod prefetch;
+pub mod pull;
+pub mod replica;
+pub mod retry;
 pub mod router;
+pub mod session;
+pub mod snapshot;
 pub mod sync;
+pub mod tags;
+pub mod tool_exec;
+pub mod trigram;
+pub mod view;
 pub mod vfs;
 pub mod wal;
 
 pub use backends::{FsBackend, MemoryBackend};
 pub use cached_backend::{CachedBackend, CachedBackendStatus};
 pub use chroma_http::ChromaHttpBackend;
-pub use grep::{grep, GrepMatch, GrepOptions};
+pub use conflict::{ConflictResolution, SyncConflict};
+pub use coord::{Lease, LeaseHolder};
+pub use disk_cache::{DiskCache, DiskCacheStats};
+pub use grep::{grep, grep_each, GrepMatch, GrepOptions};
+pub use jsonl::{JsonlCursor, JsonlRecords};
+pub use history::{HistoryBackend, HISTORY_ROOT};
+pub use log_file::{LogBackend, LogPaths};
+pub use multipart::MultipartUploader;
+pub use prefetch::PrefetchGlob;
+pub use pull::{PullSummary, RemoteChangeEvent, RemoteChanges, RemoteSnapshot};
+pub use replica::ReplicatedBackend;
+pub use retry::{CircuitState, CircuitStatus, RetryBackend};
 pub use router::{Mount, Router};
-pub use sync::{SyncConfig, SyncMode, SyncStats};
-pub use vfs::{MountSyncStatus, Vfs};
-pub use wal::{WalConfig, WriteAheadLog};
+pub use session::{Session, SessionOptions, DEFAULT_SESSION_TTL, SESSIONS_ROOT};
+pub use snapshot::{HistoryPoint, RestoreSummary, SnapshotDiff, SnapshotInfo};
+pub use sync::{PathSyncState, PathSyncStatus, SyncConfig, SyncMode, SyncStats};
+pub use tags::{TagBackend, TAGS_ROOT};
+pub use trigram::{TrigramIndex, TrigramQuery, TRIGRAM_INDEX_FILE};
+pub use view::ViewBackend;
+pub use vfs::{DirUsage, LineRange, MountHealth, MountSyncStatus, ReloadSummary, Vfs};
+pub use wal::{CheckpointReport, CheckpointStats, CheckpointTrigger, WalConfig, WriteAheadLog};
 
 #[cfg(feature = "s3")]
 pub use backends::{S3Backend, S3Config};
@@ -0,0 +1,649 @@
+//! Append-optimized log files.
+//!
+//! Appending to an object rewrites all of it, and appenders racing on one
+//! file lose lines or split each other's. [`LogBackend`] keeps the files a
+//! mount's `log.paths` select as a hidden `.<name>.segments` directory next
+//! to them instead: every append is written as a segment of its own, ending
+//! in a newline, and once `compact_after` segments have collected they are
+//! merged into one base. Reads, stats and listings see a single file with
+//! the base and segments concatenated in order.
+//!
+//! Segments are named by sequence number and a per-process writer id, so
+//! processes appending at once never overwrite each other's segments.
+//! Appends and compactions within one process are serialized per file.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use openfs_config::LogConfig;
+use openfs_core::{slice_range, Backend, BackendError, Entry, FileMeta, UploadedPart, VfsError};
+
+/// Segments collected before a log is compacted, unless configured.
+pub const DEFAULT_COMPACT_AFTER: usize = 64;
+
+const SEGMENTS_SUFFIX: &str = ".segments";
+const BASE_SUFFIX: &str = ".base";
+
+/// The files kept as logs, by glob relative to the mount.
+#[derive(Debug, Clone)]
+pub struct LogPaths(GlobSet);
+
+impl LogPaths {
+    /// `*` and `?` stay within one path component; `**` crosses directories.
+    pub fn new(patterns: &[String]) -> Result<Self, VfsError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern.trim_start_matches('/'))
+                .literal_separator(true)
+                .build()
+                .map_err(|e| VfsError::Config(format!("invalid glob '{}': {}", pattern, e)))?;
+            builder.add(glob);
+        }
+        let globs = builder
+            .build()
+            .map_err(|e| VfsError::Config(format!("invalid log paths: {}", e)))?;
+        Ok(LogPaths(globs))
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.0.is_match(path.trim_start_matches('/'))
+    }
+}
+
+/// Backend storing log files as segments, over any other backend.
+pub struct LogBackend {
+    inner: Arc<dyn Backend>,
+    paths: LogPaths,
+    compact_after: usize,
+    /// Distinguishes this process's segments from those of other writers.
+    writer: String,
+    /// Serializes appends, writes and compactions per log file.
+    locks: std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// The objects a log file is stored as, from a listing of its segments
+/// directory. Names are relative to that directory.
+#[derive(Debug, Default)]
+struct Pieces {
+    /// The newest base.
+    base: Option<String>,
+    /// Segments written after the base, oldest first.
+    segments: Vec<String>,
+    /// Older bases and segments already merged into the base, left behind by
+    /// an interrupted compaction.
+    stale: Vec<String>,
+    /// Highest sequence number in use.
+    last: u64,
+    /// Bytes in the base and segments.
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl Pieces {
+    fn is_empty(&self) -> bool {
+        self.base.is_none() && self.segments.is_empty()
+    }
+
+    /// Every object in the directory.
+    fn all(&self) -> impl Iterator<Item = &String> {
+        self.base.iter().chain(&self.segments).chain(&self.stale)
+    }
+}
+
+impl LogBackend {
+    pub fn new(inner: Arc<dyn Backend>, config: &LogConfig) -> Result<Self, VfsError> {
+        let seed = format!(
+            "{}-{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        Ok(LogBackend {
+            inner,
+            paths: LogPaths::new(&config.paths)?,
+            compact_after: config.compact_after.unwrap_or(DEFAULT_COMPACT_AFTER).max(1),
+            writer: blake3::hash(seed.as_bytes()).to_hex()[..8].to_string(),
+            locks: std::sync::Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn paths(&self) -> &LogPaths {
+        &self.paths
+    }
+
+    fn lock(&self, path: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+        locks
+            .entry(path.trim_start_matches('/').to_string())
+            .or_default()
+            .clone()
+    }
+
+    async fn pieces(&self, path: &str) -> Result<Pieces, BackendError> {
+        let entries = match self.inner.list(&segments_dir(path)).await {
+            Ok(entries) => entries,
+            Err(BackendError::NotFound(_)) => return Ok(Pieces::default()),
+            Err(e) => return Err(e),
+        };
+        let mut bases = Vec::new();
+        let mut segments = Vec::new();
+        let mut last = 0;
+        for entry in entries.into_iter().filter(|entry| !entry.is_dir) {
+            let Some(seq) = sequence(&entry.name) else {
+                continue;
+            };
+            last = last.max(seq);
+            if entry.name.ends_with(BASE_SUFFIX) {
+                bases.push(entry);
+            } else {
+                segments.push(entry);
+            }
+        }
+        // Names sort by sequence number, then writer.
+        bases.sort_by(|a, b| a.name.cmp(&b.name));
+        segments.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut pieces = Pieces {
+            last,
+            ..Default::default()
+        };
+        let base = bases.pop();
+        pieces
+            .stale
+            .extend(bases.into_iter().map(|entry| entry.name));
+        // A base holds every segment up to the one it is named after.
+        let merged = base
+            .as_ref()
+            .map(|base| base.name.trim_end_matches(BASE_SUFFIX).to_string());
+        for entry in base.into_iter().chain(segments) {
+            let is_base = entry.name.ends_with(BASE_SUFFIX);
+            if !is_base && merged.as_ref().is_some_and(|merged| entry.name <= *merged) {
+                pieces.stale.push(entry.name);
+                continue;
+            }
+            pieces.size += entry.size.unwrap_or_default();
+            pieces.modified = pieces.modified.max(entry.modified);
+            if is_base {
+                pieces.base = Some(entry.name);
+            } else {
+                pieces.segments.push(entry.name);
+            }
+        }
+        Ok(pieces)
+    }
+
+    /// The plain file at `path`, which a log without a base starts with.
+    async fn plain(&self, path: &str) -> Result<Option<Vec<u8>>, BackendError> {
+        match self.inner.read(path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(BackendError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn read_pieces(&self, path: &str, pieces: &Pieces) -> Result<Vec<u8>, BackendError> {
+        let dir = segments_dir(path);
+        let mut content = match &pieces.base {
+            Some(base) => self.inner.read(&join(&dir, base)).await?,
+            None => self.plain(path).await?.unwrap_or_default(),
+        };
+        for segment in &pieces.segments {
+            content.extend(self.inner.read(&join(&dir, segment)).await?);
+        }
+        Ok(content)
+    }
+
+    async fn read_log(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        let mut attempts = 0;
+        loop {
+            let pieces = self.pieces(path).await?;
+            if pieces.is_empty() {
+                return self.inner.read(path).await;
+            }
+            match self.read_pieces(path, &pieces).await {
+                // Another process compacted the log while it was read.
+                Err(BackendError::NotFound(_)) if attempts < 3 => attempts += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Replace the log with a base holding `content`. The caller holds the
+    /// file's lock.
+    async fn replace(
+        &self,
+        path: &str,
+        pieces: &Pieces,
+        content: &[u8],
+    ) -> Result<(), BackendError> {
+        let dir = segments_dir(path);
+        let name = format!("{:020}-{}{}", pieces.last + 1, self.writer, BASE_SUFFIX);
+        self.inner.write(&join(&dir, &name), content).await?;
+        self.remove(path, pieces).await
+    }
+
+    /// Delete `pieces` and the plain file at `path`.
+    async fn remove(&self, path: &str, pieces: &Pieces) -> Result<(), BackendError> {
+        let dir = segments_dir(path);
+        for name in pieces.all() {
+            match self.inner.delete(&join(&dir, name)).await {
+                Ok(()) | Err(BackendError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match self.inner.delete(path).await {
+            Ok(()) | Err(BackendError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Merge the base and segments into a new base. The caller holds the
+    /// file's lock.
+    async fn compact(&self, path: &str) -> Result<(), BackendError> {
+        let pieces = self.pieces(path).await?;
+        let Some(last) = pieces.segments.last() else {
+            return Ok(());
+        };
+        let content = self.read_pieces(path, &pieces).await?;
+        let dir = segments_dir(path);
+        self.inner
+            .write(&join(&dir, &format!("{}{}", last, BASE_SUFFIX)), &content)
+            .await?;
+        tracing::debug!(
+            path = %path,
+            segments = pieces.segments.len(),
+            bytes = content.len(),
+            "compacted log"
+        );
+        self.remove(path, &pieces).await
+    }
+
+    async fn stat_log(&self, path: &str) -> Result<Option<Entry>, BackendError> {
+        let pieces = self.pieces(path).await?;
+        if pieces.is_empty() {
+            return Ok(None);
+        }
+        let mut size = pieces.size;
+        if pieces.base.is_none() {
+            match self.inner.stat(path).await {
+                Ok(plain) => size += plain.size.unwrap_or_default(),
+                Err(BackendError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        Ok(Some(Entry::file(
+            path.to_string(),
+            name,
+            size,
+            pieces.modified,
+        )))
+    }
+}
+
+/// The directory holding the pieces of the log at `path`.
+fn segments_dir(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/.{}{}", dir, name, SEGMENTS_SUFFIX),
+        None => format!(".{}{}", path, SEGMENTS_SUFFIX),
+    }
+}
+
+/// The path of the log file a listed segments directory belongs to.
+fn log_path(entry: &Entry) -> Option<String> {
+    if !entry.is_dir {
+        return None;
+    }
+    let name = entry
+        .name
+        .strip_prefix('.')?
+        .strip_suffix(SEGMENTS_SUFFIX)
+        .filter(|name| !name.is_empty())?;
+    Some(match entry.path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, name),
+        None => name.to_string(),
+    })
+}
+
+fn sequence(name: &str) -> Option<u64> {
+    name.split('-').next()?.parse().ok()
+}
+
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir, name)
+}
+
+#[async_trait]
+impl Backend for LogBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        if self.paths.matches(path) {
+            return self.read_log(path).await;
+        }
+        self.inner.read(path).await
+    }
+
+    async fn read_with_cas_token(
+        &self,
+        path: &str,
+    ) -> Result<(Vec<u8>, Option<String>), BackendError> {
+        if self.paths.matches(path) {
+            return Ok((self.read_log(path).await?, None));
+        }
+        self.inner.read_with_cas_token(path).await
+    }
+
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        if self.paths.matches(path) {
+            let content = self.read_log(path).await?;
+            return Ok(slice_range(&content, offset, len).to_vec());
+        }
+        self.inner.read_range(path, offset, len).await
+    }
+
+    async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        if !self.paths.matches(path) {
+            return self.inner.write(path, content).await;
+        }
+        let lock = self.lock(path);
+        let _guard = lock.lock().await;
+        let pieces = self.pieces(path).await?;
+        self.replace(path, &pieces, content).await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        path: &str,
+        expected: Option<&str>,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        if !self.paths.matches(path) {
+            return self.inner.compare_and_swap(path, expected, content).await;
+        }
+        if expected.is_some() {
+            return Err(BackendError::Other(format!(
+                "compare_and_swap is not supported for log file '{}'",
+                path
+            )));
+        }
+        self.write(path, content).await?;
+        Ok(None)
+    }
+
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        if self.paths.matches(path) {
+            return Err(BackendError::Other(format!(
+                "create_if_absent is not supported for log file '{}'",
+                path
+            )));
+        }
+        self.inner.create_if_absent(path, content).await
+    }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        if self.paths.matches(path) {
+            return Ok(FileMeta::new());
+        }
+        self.inner.read_meta(path).await
+    }
+
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        if self.paths.matches(path) {
+            return Err(BackendError::Other(format!(
+                "metadata is not supported for log file '{}'",
+                path
+            )));
+        }
+        self.inner.write_meta(path, meta).await
+    }
+
+    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        if !self.paths.matches(path) {
+            return self.inner.append(path, content).await;
+        }
+        if content.is_empty() {
+            return Ok(());
+        }
+        let lock = self.lock(path);
+        let _guard = lock.lock().await;
+        let pieces = self.pieces(path).await?;
+        let mut segment = content.to_vec();
+        if !segment.ends_with(b"\n") {
+            segment.push(b'\n');
+        }
+        let name = format!("{:020}-{}", pieces.last + 1, self.writer);
+        self.inner
+            .write(&join(&segments_dir(path), &name), &segment)
+            .await?;
+        if pieces.segments.len() + 1 >= self.compact_after {
+            self.compact(path).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        if !self.paths.matches(path) {
+            return self.inner.delete(path).await;
+        }
+        let lock = self.lock(path);
+        let _guard = lock.lock().await;
+        let pieces = self.pieces(path).await?;
+        if pieces.is_empty() && pieces.stale.is_empty() {
+            return self.inner.delete(path).await;
+        }
+        self.remove(path, &pieces).await?;
+        match self.inner.delete(&segments_dir(path)).await {
+            Ok(()) | Err(BackendError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        let mut entries = self.inner.list(path).await?;
+        let is_log = |entry: &Entry| log_path(entry).filter(|log| self.paths.matches(log));
+        let logs: Vec<String> = entries.iter().filter_map(is_log).collect();
+        if logs.is_empty() {
+            return Ok(entries);
+        }
+        // Each log is listed as one file in place of its segments.
+        entries.retain(|entry| is_log(entry).is_none());
+        for log in logs {
+            if let Some(entry) = self.stat_log(&log).await? {
+                entries.retain(|other| other.name != entry.name);
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        if self.paths.matches(path) && !self.pieces(path).await?.is_empty() {
+            return Ok(true);
+        }
+        self.inner.exists(path).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        if self.paths.matches(path) {
+            if let Some(entry) = self.stat_log(path).await? {
+                return Ok(entry);
+            }
+        }
+        self.inner.stat(path).await
+    }
+
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        if self.paths.matches(path) {
+            return Ok(false);
+        }
+        self.inner.set_modified(path, modified).await
+    }
+
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        // Whole writes of a log go through `write`, which stores a base.
+        if self.paths.matches(path) {
+            return Ok(None);
+        }
+        self.inner.begin_multipart(path).await
+    }
+
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        self.inner
+            .upload_part(path, upload_id, number, content)
+            .await
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        self.inner.complete_multipart(path, upload_id, parts).await
+    }
+
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        self.inner.abort_multipart(path, upload_id).await
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
+        if !self.paths.matches(from) && !self.paths.matches(to) {
+            return self.inner.rename(from, to).await;
+        }
+        let content = self.read(from).await?;
+        self.write(to, &content).await?;
+        self.delete(from).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    fn log_backend(compact_after: usize) -> (Arc<MemoryBackend>, LogBackend) {
+        let inner = Arc::new(MemoryBackend::new());
+        let config = LogConfig {
+            paths: vec!["logs/*.jsonl".to_string()],
+            compact_after: Some(compact_after),
+        };
+        let backend = LogBackend::new(inner.clone(), &config).unwrap();
+        (inner, backend)
+    }
+
+    #[tokio::test]
+    async fn test_appends_are_whole_line_segments() {
+        let (inner, backend) = log_backend(64);
+        backend
+            .write("logs/old.jsonl", b"{\"n\":0}\n")
+            .await
+            .unwrap();
+        inner.write("logs/run.jsonl", b"{\"n\":0}\n").await.unwrap();
+
+        let appends = (1..=20).map(|n| {
+            let backend = &backend;
+            async move {
+                let line = format!("{{\"n\":{}}}", n);
+                backend.append("/logs/run.jsonl", line.as_bytes()).await
+            }
+        });
+        for result in futures::future::join_all(appends).await {
+            result.unwrap();
+        }
+
+        let content = String::from_utf8(backend.read("logs/run.jsonl").await.unwrap()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 21);
+        assert_eq!(lines[0], "{\"n\":0}");
+        for n in 0..=20 {
+            assert!(lines.contains(&format!("{{\"n\":{}}}", n).as_str()));
+        }
+        let stat = backend.stat("logs/run.jsonl").await.unwrap();
+        assert_eq!(stat.size, Some(content.len() as u64));
+        assert_eq!(
+            backend
+                .read_range("logs/run.jsonl", 0, Some(7))
+                .await
+                .unwrap(),
+            b"{\"n\":0}"
+        );
+
+        // The segments directory is listed as the file it holds.
+        let mut names: Vec<String> = backend
+            .list("logs")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["old.jsonl", "run.jsonl"]);
+        assert!(!inner.exists("logs/old.jsonl").await.unwrap());
+
+        // Other files are left alone.
+        backend.append("logs/notes.txt", b"a").await.unwrap();
+        backend.append("logs/notes.txt", b"b").await.unwrap();
+        assert_eq!(backend.read("logs/notes.txt").await.unwrap(), b"ab");
+    }
+
+    #[tokio::test]
+    async fn test_compaction_and_replacement() {
+        let (inner, backend) = log_backend(3);
+        for n in 0..7 {
+            backend
+                .append("logs/a.jsonl", format!("{}\n", n).as_bytes())
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            backend.read("logs/a.jsonl").await.unwrap(),
+            b"0\n1\n2\n3\n4\n5\n6\n"
+        );
+        // Two compactions left one base and the one segment since.
+        let pieces = backend.pieces("logs/a.jsonl").await.unwrap();
+        assert!(pieces.base.is_some());
+        assert_eq!(pieces.segments.len(), 1);
+        assert!(pieces.stale.is_empty());
+        assert_eq!(inner.list("logs/.a.jsonl.segments").await.unwrap().len(), 2);
+
+        backend.write("logs/a.jsonl", b"fresh\n").await.unwrap();
+        backend.append("logs/a.jsonl", b"more").await.unwrap();
+        assert_eq!(
+            backend.read("logs/a.jsonl").await.unwrap(),
+            b"fresh\nmore\n"
+        );
+
+        backend
+            .rename("logs/a.jsonl", "logs/b.jsonl")
+            .await
+            .unwrap();
+        assert!(!backend.exists("logs/a.jsonl").await.unwrap());
+        assert_eq!(
+            backend.read("logs/b.jsonl").await.unwrap(),
+            b"fresh\nmore\n"
+        );
+        backend.delete("logs/b.jsonl").await.unwrap();
+        assert!(!backend.exists("logs/b.jsonl").await.unwrap());
+        assert!(backend.list("logs").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+//! Three-way merges of text files in the VFS.
+
+use openfs_core::{merge3_with_markers, MergeResult, VfsError};
+
+use crate::Vfs;
+
+impl Vfs {
+    /// Merge the changes `ours` and `theirs` each made to `base` and write
+    /// the result to `dest`, which may be one of the inputs.
+    ///
+    /// Where the two sides changed the same lines differently, both versions
+    /// are written between diff3-style conflict markers labelled with the
+    /// paths, and listed in the returned [`MergeResult::conflicts`]. A missing
+    /// `base` is treated as empty, for files both sides created.
+    pub async fn merge(
+        &self,
+        base: &str,
+        ours: &str,
+        theirs: &str,
+        dest: &str,
+    ) -> Result<MergeResult, VfsError> {
+        let base_text = match self.read(base).await {
+            Ok(content) => text(base, content)?,
+            Err(VfsError::NotFound(_)) => String::new(),
+            Err(e) => return Err(e),
+        };
+        let ours_text = text(ours, self.read(ours).await?)?;
+        let theirs_text = text(theirs, self.read(theirs).await?)?;
+
+        let merge = merge3_with_markers(&base_text, &ours_text, &theirs_text, ours, theirs);
+        self.write(dest, merge.text.as_bytes()).await?;
+        Ok(merge)
+    }
+}
+
+fn text(path: &str, content: Vec<u8>) -> Result<String, VfsError> {
+    String::from_utf8(content)
+        .map_err(|_| VfsError::InvalidArgument(format!("{} is not UTF-8 text", path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+
+    #[tokio::test]
+    async fn test_vfs_merge() {
+        let config = VfsConfig::from_yaml(
+            r#"
+backends:
+  mem:
+    type: memory
+mounts:
+  - path: /workspace
+    backend: mem
+"#,
+        )
+        .unwrap();
+        let vfs = Vfs::from_config(config).await.unwrap();
+        vfs.write("/workspace/base.md", b"title\nbody\nend\n")
+            .await
+            .unwrap();
+        vfs.write("/workspace/ours.md", b"Title\nbody\nend\n")
+            .await
+            .unwrap();
+        vfs.write("/workspace/theirs.md", b"title\nbody\nThe end\n")
+            .await
+            .unwrap();
+
+        let merge = vfs
+            .merge(
+                "/workspace/base.md",
+                "/workspace/ours.md",
+                "/workspace/theirs.md",
+                "/workspace/merged.md",
+            )
+            .await
+            .unwrap();
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(
+            vfs.read("/workspace/merged.md").await.unwrap(),
+            b"Title\nbody\nThe end\n"
+        );
+
+        vfs.write("/workspace/theirs.md", b"TITLE\nbody\nend\n")
+            .await
+            .unwrap();
+        let merge = vfs
+            .merge(
+                "/workspace/base.md",
+                "/workspace/ours.md",
+                "/workspace/theirs.md",
+                "/workspace/ours.md",
+            )
+            .await
+            .unwrap();
+        assert_eq!(merge.conflicts.len(), 1);
+        let merged = String::from_utf8(vfs.read("/workspace/ours.md").await.unwrap()).unwrap();
+        assert!(merged.starts_with("<<<<<<< /workspace/ours.md\nTitle\n"));
+        assert!(merged.contains(">>>>>>> /workspace/theirs.md\nbody\n"));
+    }
+}
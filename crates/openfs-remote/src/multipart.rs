@@ -0,0 +1,258 @@
+//! Resumable multipart uploads.
+//!
+//! [`MultipartUploader`] writes large files in parts, recording the upload
+//! id and every stored part in the WAL. If the process dies mid-upload, the
+//! next attempt at the same content (such as the outbox replay on startup)
+//! finds the recorded upload and sends only the parts still missing.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use openfs_core::{Backend, BackendError, UploadedPart};
+
+use crate::wal::WriteAheadLog;
+
+/// Uploads files of at least `threshold` bytes in parts of `part_size`.
+pub struct MultipartUploader {
+    threshold: u64,
+    part_size: u64,
+    wal: Option<Arc<WriteAheadLog>>,
+}
+
+impl MultipartUploader {
+    pub fn new(threshold: u64, part_size: u64, wal: Option<Arc<WriteAheadLog>>) -> Self {
+        MultipartUploader {
+            threshold,
+            part_size: part_size.max(1),
+            wal,
+        }
+    }
+
+    /// Whether `content` is large enough to upload in parts.
+    pub fn applies(&self, content: &[u8]) -> bool {
+        content.len() as u64 >= self.threshold
+    }
+
+    /// Upload `content` to `path` in parts, resuming a recorded upload of
+    /// the same content. Returns `false`, having written nothing, when the
+    /// backend cannot upload in parts.
+    pub async fn upload(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        content: &[u8],
+    ) -> Result<bool, BackendError> {
+        let hash = blake3::hash(content).to_hex().to_string();
+        let mut recorded = self.recorded(path)?;
+        loop {
+            let resumed = recorded.is_some();
+            let (upload_id, parts) = match recorded.take() {
+                Some(progress)
+                    if progress.content_hash == hash && progress.part_size == self.part_size =>
+                {
+                    (progress.upload_id, progress.parts)
+                }
+                stale => {
+                    if let Some(stale) = stale {
+                        // Replaced by newer content; its parts are of no use.
+                        let _ = backend.abort_multipart(path, &stale.upload_id).await;
+                        self.wal(|wal| wal.finish_multipart(path))?;
+                    }
+                    let Some(upload_id) = backend.begin_multipart(path).await? else {
+                        return Ok(false);
+                    };
+                    self.wal(|wal| wal.begin_multipart(path, &upload_id, &hash, self.part_size))?;
+                    (upload_id, Vec::new())
+                }
+            };
+
+            match self.send(backend, path, &upload_id, content, parts).await {
+                // The backend no longer knows the recorded upload (it
+                // expired or was aborted): start over.
+                Err(BackendError::NotFound(_)) if resumed => {
+                    self.wal(|wal| wal.finish_multipart(path))?;
+                }
+                result => return result.map(|()| true),
+            }
+        }
+    }
+
+    /// Upload the parts not in `parts` yet, then complete the upload.
+    async fn send(
+        &self,
+        backend: &dyn Backend,
+        path: &str,
+        upload_id: &str,
+        content: &[u8],
+        mut parts: Vec<UploadedPart>,
+    ) -> Result<(), BackendError> {
+        let stored: HashSet<u32> = parts.iter().map(|part| part.number).collect();
+        for (index, chunk) in content.chunks(self.part_size as usize).enumerate() {
+            let number = index as u32 + 1;
+            if stored.contains(&number) {
+                continue;
+            }
+            let part = backend.upload_part(path, upload_id, number, chunk).await?;
+            self.wal(|wal| wal.record_part(upload_id, &part))?;
+            parts.push(part);
+        }
+        parts.sort_by_key(|part| part.number);
+        backend.complete_multipart(path, upload_id, &parts).await?;
+        self.wal(|wal| wal.finish_multipart(path))
+    }
+
+    fn recorded(&self, path: &str) -> Result<Option<crate::wal::MultipartProgress>, BackendError> {
+        match &self.wal {
+            Some(wal) => wal
+                .multipart_upload(path)
+                .map_err(|e| BackendError::Other(format!("WAL multipart read failed: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    fn wal(
+        &self,
+        record: impl FnOnce(&WriteAheadLog) -> Result<(), String>,
+    ) -> Result<(), BackendError> {
+        match &self.wal {
+            Some(wal) => {
+                record(wal).map_err(|e| BackendError::Other(format!("WAL multipart failed: {}", e)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::WalConfig;
+    use crate::MemoryBackend;
+    use async_trait::async_trait;
+    use openfs_core::Entry;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Memory backend whose part uploads fail once `fail_at` parts were sent.
+    struct Interrupted {
+        inner: MemoryBackend,
+        sent: AtomicU32,
+        fail_at: Option<u32>,
+    }
+
+    #[async_trait]
+    impl Backend for Interrupted {
+        async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+            self.inner.read(path).await
+        }
+        async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.inner.write(path, content).await
+        }
+        async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.inner.append(path, content).await
+        }
+        async fn delete(&self, path: &str) -> Result<(), BackendError> {
+            self.inner.delete(path).await
+        }
+        async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+            self.inner.list(path).await
+        }
+        async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+            self.inner.exists(path).await
+        }
+        async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+            self.inner.stat(path).await
+        }
+        async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+            self.inner.begin_multipart(path).await
+        }
+        async fn upload_part(
+            &self,
+            path: &str,
+            upload_id: &str,
+            number: u32,
+            content: &[u8],
+        ) -> Result<UploadedPart, BackendError> {
+            if Some(self.sent.load(Ordering::SeqCst)) == self.fail_at {
+                return Err(BackendError::Other("connection lost".to_string()));
+            }
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            self.inner
+                .upload_part(path, upload_id, number, content)
+                .await
+        }
+        async fn complete_multipart(
+            &self,
+            path: &str,
+            upload_id: &str,
+            parts: &[UploadedPart],
+        ) -> Result<(), BackendError> {
+            self.inner.complete_multipart(path, upload_id, parts).await
+        }
+        async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+            self.inner.abort_multipart(path, upload_id).await
+        }
+    }
+
+    fn wal() -> Arc<WriteAheadLog> {
+        Arc::new(WriteAheadLog::in_memory(WalConfig::default()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_upload_resumes_after_interruption() {
+        let wal = wal();
+        let content: Vec<u8> = (0..100u8).collect();
+        let mut backend = Interrupted {
+            inner: MemoryBackend::new(),
+            sent: AtomicU32::new(0),
+            fail_at: Some(4),
+        };
+
+        let uploader = MultipartUploader::new(50, 10, Some(Arc::clone(&wal)));
+        assert!(uploader.applies(&content));
+        assert!(uploader
+            .upload(&backend, "big.bin", &content)
+            .await
+            .is_err());
+        let progress = wal.multipart_upload("big.bin").unwrap().unwrap();
+        assert_eq!(progress.parts.len(), 4);
+
+        // A new uploader, as after a restart, sends only the other six parts.
+        backend.fail_at = None;
+        let uploader = MultipartUploader::new(50, 10, Some(Arc::clone(&wal)));
+        assert!(uploader
+            .upload(&backend, "big.bin", &content)
+            .await
+            .unwrap());
+        assert_eq!(backend.sent.load(Ordering::SeqCst), 10);
+        assert_eq!(backend.inner.read("big.bin").await.unwrap(), content);
+        assert_eq!(backend.inner.open_uploads(), 0);
+        assert!(wal.multipart_upload("big.bin").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upload_restarts_for_new_content() {
+        let wal = wal();
+        let backend = Interrupted {
+            inner: MemoryBackend::new(),
+            sent: AtomicU32::new(0),
+            fail_at: Some(2),
+        };
+        let uploader = MultipartUploader::new(1, 10, Some(Arc::clone(&wal)));
+        assert!(uploader.upload(&backend, "a.bin", &[1; 30]).await.is_err());
+        assert_eq!(backend.inner.open_uploads(), 1);
+
+        let backend = Interrupted {
+            fail_at: None,
+            ..backend
+        };
+        assert!(uploader.upload(&backend, "a.bin", &[2; 25]).await.unwrap());
+        assert_eq!(backend.inner.read("a.bin").await.unwrap(), vec![2; 25]);
+        // The stale upload was aborted rather than left behind.
+        assert_eq!(backend.inner.open_uploads(), 0);
+
+        // Backends without multipart uploads are left to a plain write.
+        let dir = tempfile::tempdir().unwrap();
+        let plain = crate::FsBackend::new(dir.path()).unwrap();
+        assert!(!uploader.upload(&plain, "a.bin", &[3; 25]).await.unwrap());
+    }
+}
@@ -0,0 +1,155 @@
+//! Cache warming.
+//!
+//! [`PrefetchGlob`] expands a glob into the files it matches by walking only
+//! the directory below its literal prefix, and [`read_all`] reads files with
+//! bounded concurrency so they land in the caches of the backend they are
+//! read through.
+
+use std::collections::BTreeSet;
+
+use futures::StreamExt;
+use globset::{GlobBuilder, GlobMatcher};
+use openfs_core::{Backend, BackendError, VfsError};
+
+/// Reads in flight at once while prefetching.
+pub const PREFETCH_CONCURRENCY: usize = 16;
+
+/// A compiled prefetch pattern.
+///
+/// `*` and `?` stay within one path component; `**` crosses directories.
+/// A pattern without glob characters names a single file.
+#[derive(Debug, Clone)]
+pub struct PrefetchGlob {
+    pattern: String,
+    root: String,
+    matcher: GlobMatcher,
+}
+
+impl PrefetchGlob {
+    pub fn new(pattern: &str) -> Result<Self, VfsError> {
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| VfsError::Config(format!("invalid glob '{}': {}", pattern, e)))?
+            .compile_matcher();
+        let literal: Vec<&str> = pattern
+            .split('/')
+            .take_while(|part| !part.contains(['*', '?', '[', '{']))
+            .collect();
+        let mut root = literal.join("/");
+        if root.is_empty() && pattern.starts_with('/') {
+            root = "/".to_string();
+        }
+        Ok(PrefetchGlob {
+            pattern: pattern.to_string(),
+            root,
+            matcher,
+        })
+    }
+
+    /// The directory to walk: the components before the first glob character.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Whether the pattern names one file rather than a set of files.
+    pub fn is_literal(&self) -> bool {
+        self.root == self.pattern
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.matcher.is_match(path)
+    }
+}
+
+/// Files in `backend` matched by any of `globs`, which are relative to the
+/// backend root.
+pub async fn matching_files(
+    backend: &dyn Backend,
+    globs: &[PrefetchGlob],
+) -> Result<Vec<String>, BackendError> {
+    let mut files = BTreeSet::new();
+    for glob in globs {
+        if glob.is_literal() {
+            files.insert(glob.root().to_string());
+            continue;
+        }
+        let mut pending = vec![glob.root().trim_end_matches('/').to_string()];
+        while let Some(dir) = pending.pop() {
+            let entries = match backend.list(&dir).await {
+                Ok(entries) => entries,
+                Err(BackendError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let path = if dir.is_empty() {
+                    entry.name
+                } else {
+                    format!("{}/{}", dir, entry.name)
+                };
+                if entry.is_dir {
+                    pending.push(path);
+                } else if glob.is_match(&path) {
+                    files.insert(path);
+                }
+            }
+        }
+    }
+    Ok(files.into_iter().collect())
+}
+
+/// Run `reads`, at most [`PREFETCH_CONCURRENCY`] at a time. Returns how many
+/// succeeded and how many failed.
+pub async fn read_all<Fut, E>(reads: impl IntoIterator<Item = Fut>) -> (usize, usize)
+where
+    Fut: std::future::Future<Output = Result<Vec<u8>, E>>,
+{
+    futures::stream::iter(reads)
+        .buffer_unordered(PREFETCH_CONCURRENCY)
+        .fold((0, 0), |(succeeded, failed), result| async move {
+            match result {
+                Ok(_) => (succeeded + 1, failed),
+                Err(_) => (succeeded, failed + 1),
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    #[test]
+    fn test_glob_root() {
+        let glob = PrefetchGlob::new("/workspace/src/**/*.rs").unwrap();
+        assert_eq!(glob.root(), "/workspace/src");
+        assert!(!glob.is_literal());
+        assert!(glob.is_match("/workspace/src/a/b.rs"));
+        assert!(!glob.is_match("/workspace/README.md"));
+
+        assert_eq!(PrefetchGlob::new("/*.md").unwrap().root(), "/");
+        assert_eq!(PrefetchGlob::new("docs/*.md").unwrap().root(), "docs");
+        assert!(PrefetchGlob::new("docs/index.md").unwrap().is_literal());
+        assert!(PrefetchGlob::new("docs/[").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_matching_files_and_read_all() {
+        let backend = MemoryBackend::new();
+        for path in ["src/lib.rs", "src/io/read.rs", "README.md", "docs/a.md"] {
+            backend.write(path, b"x").await.unwrap();
+        }
+        let globs = [
+            PrefetchGlob::new("src/**/*.rs").unwrap(),
+            PrefetchGlob::new("*.md").unwrap(),
+            PrefetchGlob::new("missing/**").unwrap(),
+        ];
+        let files = matching_files(&backend, &globs).await.unwrap();
+        assert_eq!(files, vec!["README.md", "src/io/read.rs", "src/lib.rs"]);
+
+        let paths = ["README.md", "nope.txt"];
+        let (succeeded, failed) = read_all(paths.iter().map(|path| backend.read(path))).await;
+        assert_eq!((succeeded, failed), (1, 1));
+    }
+}
@@ -0,0 +1,193 @@
+//! Remote change detection.
+//!
+//! [`RemoteSnapshot`] records the size, modification time and ETag (where
+//! the backend lists one) of every file in a backend. Comparing an older
+//! snapshot with a newer one gives the files changed and deleted remotely in
+//! between, without reading any content.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use openfs_core::{Backend, BackendError};
+
+/// What a listing says about one remote file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    size: Option<u64>,
+    modified: Option<DateTime<Utc>>,
+    etag: Option<String>,
+}
+
+/// The files in a backend at one point in time, by path relative to its root.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteSnapshot {
+    files: HashMap<String, Version>,
+}
+
+/// Files that differ between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteChanges {
+    /// Files added or modified, sorted.
+    pub changed: Vec<String>,
+    /// Files removed, sorted.
+    pub deleted: Vec<String>,
+}
+
+impl RemoteChanges {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.deleted.is_empty()
+    }
+
+    /// One event per changed file, with paths under `mount_path`.
+    pub fn events(&self, mount_path: &str) -> Vec<RemoteChangeEvent> {
+        let mount = mount_path.trim_end_matches('/');
+        let event = |path: &String, deleted| RemoteChangeEvent {
+            path: format!("{}/{}", mount, path),
+            deleted,
+        };
+        self.changed
+            .iter()
+            .map(|path| event(path, false))
+            .chain(self.deleted.iter().map(|path| event(path, true)))
+            .collect()
+    }
+}
+
+/// A file found changed by a pull, see [`crate::Vfs::subscribe_remote_changes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteChangeEvent {
+    /// VFS path of the file.
+    pub path: String,
+    /// Whether the file was deleted, rather than added or modified.
+    pub deleted: bool,
+}
+
+/// What one pull did to a cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PullSummary {
+    /// Remote changes since the previous pull.
+    pub changes: RemoteChanges,
+    /// Cached files replaced by their new remote content.
+    pub refreshed: usize,
+    /// Cached files dropped because they were deleted remotely.
+    pub dropped: usize,
+    /// Changed files left to a pending local write.
+    pub deferred: usize,
+}
+
+impl RemoteSnapshot {
+    /// List every file in `backend`.
+    pub async fn take(backend: &dyn Backend) -> Result<Self, BackendError> {
+        let mut files = HashMap::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            let entries = match backend.list(&dir).await {
+                Ok(entries) => entries,
+                // Removed while walking.
+                Err(BackendError::NotFound(_)) if !dir.is_empty() => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let path = if dir.is_empty() {
+                    entry.name
+                } else {
+                    format!("{}/{}", dir, entry.name)
+                };
+                if entry.is_dir {
+                    pending.push(path);
+                } else {
+                    files.insert(
+                        path,
+                        Version {
+                            size: entry.size,
+                            modified: entry.modified,
+                            etag: entry.etag,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(RemoteSnapshot { files })
+    }
+
+    /// Number of files in the snapshot.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// What changed from this snapshot to `newer`.
+    pub fn changes(&self, newer: &RemoteSnapshot) -> RemoteChanges {
+        let mut changed: Vec<String> = newer
+            .files
+            .iter()
+            .filter(|(path, version)| self.files.get(*path) != Some(version))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut deleted: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| !newer.files.contains_key(*path))
+            .cloned()
+            .collect();
+        changed.sort();
+        deleted.sort();
+        RemoteChanges { changed, deleted }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    #[tokio::test]
+    async fn test_snapshot_changes() {
+        let backend = MemoryBackend::new();
+        for path in ["a.txt", "docs/b.md", "docs/c.md"] {
+            backend.write(path, b"v1").await.unwrap();
+        }
+        let before = RemoteSnapshot::take(&backend).await.unwrap();
+        assert_eq!(before.len(), 3);
+        assert!(before.changes(&before).is_empty());
+
+        backend.write("docs/b.md", b"version 2").await.unwrap();
+        backend.write("docs/new.md", b"v1").await.unwrap();
+        backend.delete("a.txt").await.unwrap();
+        let after = RemoteSnapshot::take(&backend).await.unwrap();
+        let changes = before.changes(&after);
+        assert_eq!(
+            changes,
+            RemoteChanges {
+                changed: vec!["docs/b.md".to_string(), "docs/new.md".to_string()],
+                deleted: vec!["a.txt".to_string()],
+            }
+        );
+        let events = changes.events("/mirror/");
+        assert_eq!(events[0].path, "/mirror/docs/b.md");
+        assert_eq!(
+            events[2],
+            RemoteChangeEvent {
+                path: "/mirror/a.txt".to_string(),
+                deleted: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_compares_etags() {
+        let backend = MemoryBackend::new();
+        backend.write("a.txt", b"v1").await.unwrap();
+        let before = RemoteSnapshot::take(&backend).await.unwrap();
+        let modified = before.files["a.txt"].modified.unwrap();
+
+        // Same size and time: only the ETag tells the versions apart.
+        backend.write("a.txt", b"v2").await.unwrap();
+        backend.set_modified("a.txt", modified).await.unwrap();
+        let after = RemoteSnapshot::take(&backend).await.unwrap();
+        assert_eq!(before.changes(&after).changed, vec!["a.txt"]);
+    }
+}
@@ -0,0 +1,466 @@
+//! Read replicas of a mount's backend, for mounts with a `replicas` section.
+//!
+//! [`ReplicatedBackend`] sends every request to the primary backend first.
+//! Reads that fail transiently there fail over to the replicas in order, and
+//! with `hedge_after` a read the primary is slow to answer is also sent to
+//! the first replica, the first good answer winning. Replicas may lag behind
+//! the primary, so reads served by them can be stale, and an answer from the
+//! primary, such as `NotFound`, is always final.
+//!
+//! Writes go to the primary only. Each path a write changes is then queued,
+//! per replica, on a write-back [`SyncEngine`], whose flush copies the
+//! primary's current version of the path to the replica, or deletes it there,
+//! and retries failures with the mount's sync retry policy. Copying the
+//! current version rather than replaying each write lets the queue coalesce
+//! writes to the same path. The queue is kept in memory, so replication
+//! still pending when the process dies is lost.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use openfs_core::{Backend, BackendError, Entry, FileMeta, UploadedPart, VfsError};
+use tracing::{debug, warn};
+
+use crate::sync::{SyncConfig, SyncEngine, SyncMode, SyncStats};
+
+struct Replica {
+    name: String,
+    backend: Arc<dyn Backend>,
+    /// Paths waiting to be copied to this replica.
+    sync: SyncEngine,
+}
+
+/// Backend reading from a primary with replicas to fall back on, and
+/// replicating writes to them in the background.
+pub struct ReplicatedBackend {
+    primary: Arc<dyn Backend>,
+    replicas: Vec<Replica>,
+    hedge_after: Option<Duration>,
+}
+
+impl ReplicatedBackend {
+    /// Replicate `primary` to `replicas`, given by name, queueing copies on
+    /// sync engines configured like `sync`.
+    pub async fn new(
+        primary: Arc<dyn Backend>,
+        replicas: Vec<(String, Arc<dyn Backend>)>,
+        hedge_after: Option<Duration>,
+        sync: SyncConfig,
+    ) -> Self {
+        let sync = SyncConfig {
+            mode: SyncMode::WriteBack,
+            ..sync
+        };
+        let mut started = Vec::with_capacity(replicas.len());
+        for (name, backend) in replicas {
+            let engine = SyncEngine::new(sync.clone());
+            let (source, target) = (primary.clone(), backend.clone());
+            engine
+                .start(move |path, _content| {
+                    let (source, target) = (source.clone(), target.clone());
+                    async move {
+                        copy_path(source.as_ref(), target.as_ref(), path)
+                            .await
+                            .map_err(VfsError::from)
+                    }
+                })
+                .await;
+            started.push(Replica {
+                name,
+                backend,
+                sync: engine,
+            });
+        }
+        ReplicatedBackend {
+            primary,
+            replicas: started,
+            hedge_after,
+        }
+    }
+
+    /// Replication progress of each replica, by backend name.
+    pub async fn replication_stats(&self) -> Vec<(String, SyncStats)> {
+        let mut stats = Vec::with_capacity(self.replicas.len());
+        for replica in &self.replicas {
+            stats.push((replica.name.clone(), replica.sync.stats().await));
+        }
+        stats
+    }
+
+    /// Copy everything queued to the replicas and stop replicating.
+    pub async fn flush(&self) {
+        for replica in &self.replicas {
+            replica.sync.shutdown().await;
+        }
+    }
+
+    /// Queue `paths` for copying to every replica.
+    async fn replicate(&self, paths: &[&str]) {
+        for replica in &self.replicas {
+            for path in paths {
+                // The flush copies whatever the primary holds then.
+                if let Err(e) = replica.sync.queue_write(path.to_string(), Vec::new()).await {
+                    warn!(
+                        "Failed to queue replication of '{}' to '{}': {}",
+                        path, replica.name, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Send a read to the primary, hedging and failing over to replicas.
+    async fn read_from<'a, T, F, Fut>(&'a self, request: F) -> Result<T, BackendError>
+    where
+        F: Fn(&'a dyn Backend) -> Fut,
+        Fut: Future<Output = Result<T, BackendError>>,
+    {
+        let primary = request(self.primary.as_ref());
+        let result = match (self.hedge_after, self.replicas.first()) {
+            (Some(delay), Some(first)) => {
+                tokio::pin!(primary);
+                match tokio::time::timeout(delay, &mut primary).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        debug!("Primary slow to answer; hedging read to '{}'", first.name);
+                        let hedge = request(first.backend.as_ref());
+                        tokio::pin!(hedge);
+                        tokio::select! {
+                            result = &mut primary => match result {
+                                Err(e) if e.is_transient() => hedge.await.map_err(|_| e),
+                                result => result,
+                            },
+                            result = &mut hedge => match result {
+                                Ok(value) => Ok(value),
+                                // A lagging replica's answer does not beat the primary's.
+                                Err(_) => primary.await,
+                            },
+                        }
+                    }
+                }
+            }
+            _ => primary.await,
+        };
+        match result {
+            Err(e) if e.is_transient() => self.fail_over(&request, e).await,
+            result => result,
+        }
+    }
+
+    /// Retry a read the primary failed with `err` on each replica in turn.
+    async fn fail_over<'a, T, F, Fut>(
+        &'a self,
+        request: &F,
+        err: BackendError,
+    ) -> Result<T, BackendError>
+    where
+        F: Fn(&'a dyn Backend) -> Fut,
+        Fut: Future<Output = Result<T, BackendError>>,
+    {
+        for replica in &self.replicas {
+            match request(replica.backend.as_ref()).await {
+                Ok(value) => {
+                    debug!("Read failed over to '{}' after: {}", replica.name, err);
+                    return Ok(value);
+                }
+                Err(e) => debug!("Replica '{}' failed too: {}", replica.name, e),
+            }
+        }
+        Err(err)
+    }
+}
+
+/// Make `path` on `target` match `source`: copy a file with its metadata,
+/// a directory with everything below it, or delete what `source` no longer
+/// has.
+fn copy_path<'a>(
+    source: &'a dyn Backend,
+    target: &'a dyn Backend,
+    path: String,
+) -> BoxFuture<'a, Result<(), BackendError>> {
+    Box::pin(async move {
+        match source.stat(&path).await {
+            Ok(entry) if entry.is_dir => {
+                for child in source.list(&path).await? {
+                    let child_path = match path.trim_end_matches('/') {
+                        "" => child.name,
+                        dir => format!("{}/{}", dir, child.name),
+                    };
+                    copy_path(source, target, child_path).await?;
+                }
+                Ok(())
+            }
+            Ok(_) => {
+                let content = source.read(&path).await?;
+                target.write(&path, &content).await?;
+                if let Ok(meta) = source.read_meta(&path).await {
+                    if let Err(e) = target.write_meta(&path, &meta).await {
+                        debug!("Metadata of '{}' not replicated: {}", path, e);
+                    }
+                }
+                Ok(())
+            }
+            Err(BackendError::NotFound(_)) => match target.delete(&path).await {
+                Ok(()) | Err(BackendError::NotFound(_)) => Ok(()),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    })
+}
+
+#[async_trait]
+impl Backend for ReplicatedBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        self.read_from(|backend| backend.read(path)).await
+    }
+    async fn read_with_cas_token(
+        &self,
+        path: &str,
+    ) -> Result<(Vec<u8>, Option<String>), BackendError> {
+        // Tokens are checked against the primary, so they must come from it.
+        self.primary.read_with_cas_token(path).await
+    }
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        self.read_from(|backend| backend.read_range(path, offset, len))
+            .await
+    }
+    async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.primary.write(path, content).await?;
+        self.replicate(&[path]).await;
+        Ok(())
+    }
+    async fn compare_and_swap(
+        &self,
+        path: &str,
+        expected: Option<&str>,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let token = self
+            .primary
+            .compare_and_swap(path, expected, content)
+            .await?;
+        self.replicate(&[path]).await;
+        Ok(token)
+    }
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        let token = self.primary.create_if_absent(path, content).await?;
+        self.replicate(&[path]).await;
+        Ok(token)
+    }
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        self.read_from(|backend| backend.read_meta(path)).await
+    }
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        self.primary.write_meta(path, meta).await?;
+        self.replicate(&[path]).await;
+        Ok(())
+    }
+    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.primary.append(path, content).await?;
+        self.replicate(&[path]).await;
+        Ok(())
+    }
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        self.primary.delete(path).await?;
+        self.replicate(&[path]).await;
+        Ok(())
+    }
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        self.read_from(|backend| backend.list(path)).await
+    }
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        self.read_from(|backend| backend.exists(path)).await
+    }
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        self.read_from(|backend| backend.stat(path)).await
+    }
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        self.primary.set_modified(path, modified).await
+    }
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        self.primary.begin_multipart(path).await
+    }
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        self.primary
+            .upload_part(path, upload_id, number, content)
+            .await
+    }
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        self.primary
+            .complete_multipart(path, upload_id, parts)
+            .await?;
+        self.replicate(&[path]).await;
+        Ok(())
+    }
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        self.primary.abort_multipart(path, upload_id).await
+    }
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
+        self.primary.rename(from, to).await?;
+        self.replicate(&[from, to]).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// A memory backend that can be taken down or slowed down.
+    #[derive(Default)]
+    struct Switchable {
+        inner: MemoryBackend,
+        down: AtomicBool,
+        slow: AtomicBool,
+    }
+
+    impl Switchable {
+        async fn check(&self) -> Result<(), BackendError> {
+            if self.slow.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            if self.down.load(Ordering::SeqCst) {
+                return Err(BackendError::Unavailable {
+                    backend: "primary".to_string(),
+                    message: "down".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Backend for Switchable {
+        async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+            self.check().await?;
+            self.inner.read(path).await
+        }
+        async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.inner.write(path, content).await
+        }
+        async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.inner.append(path, content).await
+        }
+        async fn delete(&self, path: &str) -> Result<(), BackendError> {
+            self.inner.delete(path).await
+        }
+        async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+            self.check().await?;
+            self.inner.list(path).await
+        }
+        async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+            self.check().await?;
+            self.inner.exists(path).await
+        }
+        async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+            self.inner.stat(path).await
+        }
+        async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
+            self.inner.rename(from, to).await
+        }
+    }
+
+    async fn replicated(
+        hedge_after: Option<Duration>,
+    ) -> (ReplicatedBackend, Arc<Switchable>, Arc<MemoryBackend>) {
+        let primary = Arc::new(Switchable::default());
+        let replica = Arc::new(MemoryBackend::new());
+        let sync = SyncConfig {
+            flush_interval: Duration::from_millis(10),
+            ..SyncConfig::default()
+        };
+        let backend = ReplicatedBackend::new(
+            primary.clone(),
+            vec![("west".to_string(), replica.clone() as Arc<dyn Backend>)],
+            hedge_after,
+            sync,
+        )
+        .await;
+        (backend, primary, replica)
+    }
+
+    #[tokio::test]
+    async fn test_writes_replicate_in_the_background() {
+        let (backend, _primary, replica) = replicated(None).await;
+        backend.write("a.txt", b"one").await.unwrap();
+        backend.append("a.txt", b" two").await.unwrap();
+        backend.write("dir/b.txt", b"b").await.unwrap();
+        backend.rename("dir/b.txt", "moved/b.txt").await.unwrap();
+        backend.write("gone.txt", b"x").await.unwrap();
+        backend.delete("gone.txt").await.unwrap();
+        backend.flush().await;
+
+        assert_eq!(replica.read("a.txt").await.unwrap(), b"one two");
+        assert_eq!(replica.read("moved/b.txt").await.unwrap(), b"b");
+        assert!(!replica.exists("dir/b.txt").await.unwrap());
+        assert!(!replica.exists("gone.txt").await.unwrap());
+        let stats = backend.replication_stats().await;
+        assert_eq!(stats[0].0, "west");
+        assert_eq!(stats[0].1.pending, 0);
+        assert_eq!(stats[0].1.failed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reads_fail_over_to_replicas() {
+        let (backend, primary, replica) = replicated(None).await;
+        primary.inner.write("a.txt", b"primary").await.unwrap();
+        replica.write("a.txt", b"replica").await.unwrap();
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"primary");
+
+        primary.down.store(true, Ordering::SeqCst);
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"replica");
+        assert!(backend.exists("a.txt").await.unwrap());
+
+        // The primary's answer is final, even when a replica has the file.
+        primary.down.store(false, Ordering::SeqCst);
+        replica.write("only-replica.txt", b"x").await.unwrap();
+        assert!(matches!(
+            backend.read("only-replica.txt").await,
+            Err(BackendError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_reads_are_hedged() {
+        let (backend, primary, replica) = replicated(Some(Duration::from_millis(20))).await;
+        primary.inner.write("a.txt", b"primary").await.unwrap();
+        replica.write("a.txt", b"replica").await.unwrap();
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"primary");
+
+        primary.slow.store(true, Ordering::SeqCst);
+        let started = std::time::Instant::now();
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"replica");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,556 @@
+//! Retries and a circuit breaker for flaky backends.
+//!
+//! [`RetryBackend`] wraps the backend of a mount with a `retry` section.
+//! Requests failing with an error the policy retries (see
+//! [`RetryOn`]) are tried again after a backoff, so a transient S3 500 or a
+//! network blip does not reach the caller. Requests that could take effect
+//! twice if an attempt failed after the backend applied it are tried once:
+//! appends, deletes, renames, conditional writes and the start and end of a
+//! multipart upload.
+//!
+//! With a circuit breaker, retryable failures in a row are counted, and at
+//! the threshold the breaker opens: requests fail at once with
+//! [`BackendError::CircuitOpen`] instead of piling onto a failing backend.
+//! Once `open_for` has passed the breaker is half-open and lets one trial
+//! request through, which closes it on success and opens it again on failure.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use openfs_config::{CircuitBreakerConfig, RequestRetryConfig, RetryOn};
+use openfs_core::{Backend, BackendError, Entry, FileMeta, UploadedPart};
+use tracing::{debug, warn};
+
+use crate::sync::RetryBackoff;
+
+/// State of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent.
+    Closed,
+    /// Requests fail at once.
+    Open,
+    /// The next request is sent as a trial.
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// A snapshot of a circuit breaker, see [`Vfs::circuit_breakers`].
+///
+/// [`Vfs::circuit_breakers`]: crate::Vfs::circuit_breakers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    /// Retryable failures since the last success.
+    pub consecutive_failures: u32,
+    /// How often the breaker has opened.
+    pub times_opened: u64,
+    /// How long until an open breaker lets a trial request through.
+    pub retry_in: Option<Duration>,
+}
+
+/// The kind of transient failure `err` is, if it is one.
+pub fn retry_class(err: &BackendError) -> Option<RetryOn> {
+    use std::io::ErrorKind;
+    match err {
+        BackendError::ConnectionFailed { .. } => Some(RetryOn::Connection),
+        BackendError::Timeout { .. } => Some(RetryOn::Timeout),
+        BackendError::Throttled { .. } => Some(RetryOn::Throttled),
+        BackendError::Unavailable { .. } => Some(RetryOn::ServerError),
+        BackendError::Io(e) => match e.kind() {
+            ErrorKind::TimedOut => Some(RetryOn::Timeout),
+            ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::Interrupted => Some(RetryOn::Connection),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open trial request was let through, if one is out.
+    trial_started: Option<Instant>,
+    times_opened: u64,
+}
+
+impl CircuitBreaker {
+    fn new(config: &CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            failure_threshold: config.failure_threshold.max(1),
+            open_for: config.open_for.as_duration(),
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a request may be sent, and if so whether it is the trial.
+    fn admit(&self, backend: &str) -> Result<bool, BackendError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(opened_at) = state.opened_at else {
+            return Ok(false);
+        };
+        let open = |retry_after| BackendError::CircuitOpen {
+            backend: backend.to_string(),
+            retry_after,
+        };
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.open_for {
+            return Err(open(self.open_for - elapsed));
+        }
+        // A trial that never reported back (its request was dropped) does not
+        // hold the breaker forever.
+        if state
+            .trial_started
+            .is_some_and(|started| started.elapsed() < self.open_for)
+        {
+            return Err(open(Duration::ZERO));
+        }
+        state.trial_started = Some(Instant::now());
+        Ok(true)
+    }
+
+    fn record(&self, backend: &str, succeeded: bool, trial: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if succeeded {
+            if state.opened_at.is_some() && trial {
+                debug!("Circuit breaker for backend '{}' closed", backend);
+            }
+            if trial || state.opened_at.is_none() {
+                *state = BreakerState {
+                    times_opened: state.times_opened,
+                    ..BreakerState::default()
+                };
+            }
+            return;
+        }
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        let reopen = trial && state.opened_at.is_some();
+        if reopen
+            || (state.opened_at.is_none() && state.consecutive_failures >= self.failure_threshold)
+        {
+            warn!(
+                "Circuit breaker for backend '{}' opened after {} failure(s); pausing requests for {:?}",
+                backend, state.consecutive_failures, self.open_for
+            );
+            state.opened_at = Some(Instant::now());
+            state.trial_started = None;
+            state.times_opened += 1;
+        }
+    }
+
+    fn status(&self) -> CircuitStatus {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (state_now, retry_in) = match state.opened_at {
+            None => (CircuitState::Closed, None),
+            Some(opened_at) => match self.open_for.checked_sub(opened_at.elapsed()) {
+                Some(left) if !left.is_zero() => (CircuitState::Open, Some(left)),
+                _ => (CircuitState::HalfOpen, None),
+            },
+        };
+        CircuitStatus {
+            state: state_now,
+            consecutive_failures: state.consecutive_failures,
+            times_opened: state.times_opened,
+            retry_in,
+        }
+    }
+}
+
+/// Backend wrapper retrying transient failures, with an optional circuit
+/// breaker.
+pub struct RetryBackend {
+    inner: Arc<dyn Backend>,
+    /// Backend name, for errors and logs.
+    name: String,
+    max_attempts: u32,
+    backoff: RetryBackoff,
+    retry_on: Vec<RetryOn>,
+    breaker: Option<CircuitBreaker>,
+}
+
+impl RetryBackend {
+    pub fn new(inner: Arc<dyn Backend>, name: &str, config: &RequestRetryConfig) -> Self {
+        RetryBackend {
+            inner,
+            name: name.to_string(),
+            max_attempts: config.max_attempts.max(1),
+            backoff: RetryBackoff {
+                base: config.backoff.as_duration(),
+                max: config.max_backoff.as_duration(),
+                strategy: config.strategy,
+                jitter: config.jitter,
+            },
+            retry_on: config.retry_on.clone(),
+            breaker: config.circuit_breaker.as_ref().map(CircuitBreaker::new),
+        }
+    }
+
+    /// The circuit breaker's state, if there is one.
+    pub fn circuit_status(&self) -> Option<CircuitStatus> {
+        self.breaker.as_ref().map(CircuitBreaker::status)
+    }
+
+    fn is_retryable(&self, err: &BackendError) -> bool {
+        retry_class(err).is_some_and(|class| self.retry_on.contains(&class))
+    }
+
+    /// Run `request`, retrying retryable failures if `idempotent`.
+    async fn call<T, F, Fut>(&self, idempotent: bool, request: F) -> Result<T, BackendError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, BackendError>>,
+    {
+        let attempts = if idempotent { self.max_attempts } else { 1 };
+        let mut attempt = 0;
+        loop {
+            let trial = match &self.breaker {
+                Some(breaker) => breaker.admit(&self.name)?,
+                None => false,
+            };
+            let result = request().await;
+            let retryable = matches!(&result, Err(e) if self.is_retryable(e));
+            if let Some(breaker) = &self.breaker {
+                breaker.record(&self.name, !retryable, trial);
+            }
+            if !retryable || attempt + 1 >= attempts {
+                return result;
+            }
+            let retry_after = result.err().and_then(|e| {
+                debug!(
+                    "Request to backend '{}' failed (attempt {} of {}): {}",
+                    self.name,
+                    attempt + 1,
+                    attempts,
+                    e
+                );
+                e.retry_after()
+            });
+            tokio::time::sleep(self.backoff.delay(attempt, retry_after)).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for RetryBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        self.call(true, || self.inner.read(path)).await
+    }
+    async fn read_with_cas_token(
+        &self,
+        path: &str,
+    ) -> Result<(Vec<u8>, Option<String>), BackendError> {
+        self.call(true, || self.inner.read_with_cas_token(path))
+            .await
+    }
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        self.call(true, || self.inner.read_range(path, offset, len))
+            .await
+    }
+    async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.call(true, || self.inner.write(path, content)).await
+    }
+    async fn compare_and_swap(
+        &self,
+        path: &str,
+        expected: Option<&str>,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        self.call(false, || {
+            self.inner.compare_and_swap(path, expected, content)
+        })
+        .await
+    }
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        self.call(false, || self.inner.create_if_absent(path, content))
+            .await
+    }
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        self.call(true, || self.inner.read_meta(path)).await
+    }
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        self.call(true, || self.inner.write_meta(path, meta)).await
+    }
+    async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+        self.call(false, || self.inner.append(path, content)).await
+    }
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        self.call(false, || self.inner.delete(path)).await
+    }
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        self.call(true, || self.inner.list(path)).await
+    }
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        self.call(true, || self.inner.exists(path)).await
+    }
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        self.call(true, || self.inner.stat(path)).await
+    }
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        self.call(true, || self.inner.set_modified(path, modified))
+            .await
+    }
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        self.call(false, || self.inner.begin_multipart(path)).await
+    }
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        // Uploading a part number again replaces the part.
+        self.call(true, || {
+            self.inner.upload_part(path, upload_id, number, content)
+        })
+        .await
+    }
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        self.call(false, || {
+            self.inner.complete_multipart(path, upload_id, parts)
+        })
+        .await
+    }
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        self.call(true, || self.inner.abort_multipart(path, upload_id))
+            .await
+    }
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
+        self.call(false, || self.inner.rename(from, to)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+    use openfs_config::HumanDuration;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the first `failures` reads with `error`, then reads from memory.
+    struct Flaky {
+        inner: MemoryBackend,
+        failures: AtomicU32,
+        calls: AtomicU32,
+        error: fn() -> BackendError,
+    }
+
+    impl Flaky {
+        fn new(failures: u32, error: fn() -> BackendError) -> Arc<Self> {
+            Arc::new(Flaky {
+                inner: MemoryBackend::new(),
+                failures: AtomicU32::new(failures),
+                calls: AtomicU32::new(0),
+                error,
+            })
+        }
+
+        fn fail(&self) -> Result<(), BackendError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let left = self.failures.load(Ordering::SeqCst);
+            if left > 0 {
+                self.failures.store(left - 1, Ordering::SeqCst);
+                return Err((self.error)());
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Backend for Flaky {
+        async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+            self.fail()?;
+            self.inner.read(path).await
+        }
+        async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.inner.write(path, content).await
+        }
+        async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
+            self.fail()?;
+            self.inner.append(path, content).await
+        }
+        async fn delete(&self, path: &str) -> Result<(), BackendError> {
+            self.inner.delete(path).await
+        }
+        async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+            self.inner.list(path).await
+        }
+        async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+            self.inner.exists(path).await
+        }
+        async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+            self.inner.stat(path).await
+        }
+    }
+
+    fn server_error() -> BackendError {
+        BackendError::Unavailable {
+            backend: "s3".to_string(),
+            message: "500".to_string(),
+        }
+    }
+
+    fn config(max_attempts: u32) -> RequestRetryConfig {
+        RequestRetryConfig {
+            max_attempts,
+            backoff: HumanDuration(Duration::from_millis(1)),
+            max_backoff: HumanDuration(Duration::from_millis(1)),
+            jitter: false,
+            ..RequestRetryConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let flaky = Flaky::new(2, server_error);
+        flaky.inner.write("a.txt", b"hi").await.unwrap();
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config(3));
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"hi");
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        // Out of attempts: the last error surfaces.
+        let flaky = Flaky::new(5, server_error);
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config(3));
+        assert!(matches!(
+            backend.read("a.txt").await,
+            Err(BackendError::Unavailable { .. })
+        ));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_only_configured_idempotent_failures_are_retried() {
+        // Appends could apply twice, so they are tried once.
+        let flaky = Flaky::new(1, server_error);
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config(3));
+        assert!(backend.append("log.txt", b"line\n").await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+
+        // Not a transient failure.
+        let flaky = Flaky::new(1, || BackendError::PermissionDenied("a.txt".to_string()));
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config(3));
+        assert!(backend.read("a.txt").await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+
+        // Throttling is transient, but left out of `retry_on`.
+        let flaky = Flaky::new(1, || BackendError::Throttled {
+            backend: "s3".to_string(),
+            retry_after: None,
+        });
+        let config = RequestRetryConfig {
+            retry_on: vec![RetryOn::ServerError],
+            ..config(3)
+        };
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config);
+        assert!(backend.read("a.txt").await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let flaky = Flaky::new(2, server_error);
+        flaky.inner.write("a.txt", b"hi").await.unwrap();
+        let config = RequestRetryConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_for: HumanDuration(Duration::from_millis(50)),
+            }),
+            ..config(1)
+        };
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config);
+        assert_eq!(
+            backend.circuit_status().unwrap().state,
+            CircuitState::Closed
+        );
+
+        assert!(backend.read("a.txt").await.is_err());
+        assert!(backend.read("a.txt").await.is_err());
+        let status = backend.circuit_status().unwrap();
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.times_opened, 1);
+        assert!(status.retry_in.is_some());
+
+        // Open: requests fail without reaching the backend.
+        let err = backend.read("a.txt").await.unwrap_err();
+        assert!(matches!(err, BackendError::CircuitOpen { .. }));
+        assert!(err.is_transient());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(
+            backend.circuit_status().unwrap().state,
+            CircuitState::HalfOpen
+        );
+        // The trial succeeds and closes the breaker.
+        assert_eq!(backend.read("a.txt").await.unwrap(), b"hi");
+        let status = backend.circuit_status().unwrap();
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.times_opened, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_trial_reopens_the_breaker() {
+        let flaky = Flaky::new(3, server_error);
+        let config = RequestRetryConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_for: HumanDuration(Duration::from_millis(30)),
+            }),
+            ..config(1)
+        };
+        let backend = RetryBackend::new(flaky.clone(), "s3", &config);
+        assert!(backend.read("a.txt").await.is_err());
+        assert!(backend.read("a.txt").await.is_err());
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        assert!(matches!(
+            backend.read("a.txt").await,
+            Err(BackendError::Unavailable { .. })
+        ));
+        let status = backend.circuit_status().unwrap();
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.times_opened, 2);
+    }
+}
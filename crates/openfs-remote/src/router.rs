@@ -1,90 +1,176 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use openfs_core::{Backend, VfsError};
+use openfs_config::{PathPolicyConfig, TrailingSlash};
+use openfs_core::{Backend, PathSanitizer, PathTrie, VfsError};
+use unicode_normalization::UnicodeNormalization;
 
 /// Mount information for routing.
 pub struct Mount {
     pub path: String,
     pub backend: Arc<dyn Backend>,
     pub read_only: bool,
+    /// Normalization applied to paths below the mount.
+    pub path_policy: PathPolicyConfig,
 }
 
 /// Router that dispatches paths to the appropriate backend.
+///
+/// Mount paths are kept in a [`PathTrie`], so resolving a path costs one
+/// walk over its components however many mounts there are. A router is
+/// immutable; [`Vfs::reload`](crate::Vfs::reload) builds a new one and swaps
+/// it in with the rest of the mount state.
 pub struct Router {
-    /// Mounts sorted by path length (longest first) for longest-prefix matching.
     mounts: Vec<Mount>,
+    /// Mount paths, for longest-prefix matching.
+    paths: PathTrie,
+    /// Index into `mounts` by normalized mount path.
+    by_path: HashMap<String, usize>,
+    /// Lowercased paths of case-folding mounts, which match in any case.
+    folded_paths: PathTrie,
+    by_folded_path: HashMap<String, usize>,
+    sanitizer: PathSanitizer,
 }
 
 impl Router {
     /// Create a new router with the given mounts.
-    pub fn new(mut mounts: Vec<Mount>) -> Self {
-        // Sort by path length descending for longest-prefix matching
-        mounts.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
-        Router { mounts }
+    ///
+    /// Of several mounts at the same path, the first one wins.
+    pub fn new(mounts: Vec<Mount>) -> Self {
+        let mut paths = PathTrie::new();
+        let mut by_path = HashMap::new();
+        let mut folded_paths = PathTrie::new();
+        let mut by_folded_path = HashMap::new();
+        for (index, mount) in mounts.iter().enumerate() {
+            let key = prefix(&mount.path, usize::MAX);
+            paths.insert(&key);
+            by_path.entry(key).or_insert(index);
+            if mount.path_policy.case_fold {
+                let key = prefix(&mount.path.to_lowercase(), usize::MAX);
+                folded_paths.insert(&key);
+                by_folded_path.entry(key).or_insert(index);
+            }
+        }
+        Router {
+            mounts,
+            paths,
+            by_path,
+            folded_paths,
+            by_folded_path,
+            sanitizer: PathSanitizer::default(),
+        }
+    }
+
+    /// Check paths with `sanitizer` instead of the default, lenient one.
+    pub fn with_sanitizer(mut self, sanitizer: PathSanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
     }
 
     /// Resolve a path to its backend and relative path.
     /// Returns (backend, relative_path, read_only).
+    ///
+    /// The path is sanitized first, and the relative path has been
+    /// normalized by the mount's path policy. Paths either refuses fail with
+    /// [`VfsError::InvalidPath`].
     pub fn resolve(&self, path: &str) -> Result<(&dyn Backend, String, bool), VfsError> {
-        let normalized = normalize_path(path);
+        let normalized =
+            self.sanitizer
+                .sanitize(path)
+                .map_err(|violation| VfsError::InvalidPath {
+                    path: path.to_string(),
+                    reason: violation.to_string(),
+                })?;
 
-        for mount in &self.mounts {
-            if let Some(relative) = strip_mount_prefix(&normalized, &mount.path) {
-                return Ok((mount.backend.as_ref(), relative, mount.read_only));
-            }
-        }
-
-        Err(VfsError::NoMount(path.to_string()))
+        let (mount, depth) = self
+            .lookup(&normalized)
+            .ok_or_else(|| VfsError::NoMount(path.to_string()))?;
+        let relative = normalized
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .skip(depth)
+            .collect::<Vec<_>>()
+            .join("/");
+        let relative = apply_path_policy(path, relative, &mount.path_policy)?;
+        Ok((mount.backend.as_ref(), relative, mount.read_only))
     }
 
     /// Get the mount for a path (for checking read-only status, etc.).
     #[allow(dead_code)]
     pub fn get_mount(&self, path: &str) -> Option<&Mount> {
-        let normalized = normalize_path(path);
-        self.mounts
-            .iter()
-            .find(|mount| strip_mount_prefix(&normalized, &mount.path).is_some())
+        let normalized = self.sanitizer.sanitize(path).ok()?;
+        self.lookup(&normalized).map(|(mount, _)| mount)
     }
-}
-
-/// Normalize a path by ensuring it starts with / and has no trailing slash.
-fn normalize_path(path: &str) -> String {
-    let mut normalized = if path.starts_with('/') {
-        path.to_string()
-    } else {
-        format!("/{}", path)
-    };
 
-    // Remove trailing slash unless it's the root
-    if normalized.len() > 1 && normalized.ends_with('/') {
-        normalized.pop();
+    /// The mount with the longest path that is a prefix of `normalized`,
+    /// and how many components of `normalized` that path has. Case-folding
+    /// mounts also match their own path in any case; on a tie the mount
+    /// matching exactly wins.
+    fn lookup(&self, normalized: &str) -> Option<(&Mount, usize)> {
+        let exact = self
+            .paths
+            .longest_prefix(normalized)
+            .map(|depth| (self.by_path[&prefix(normalized, depth)], depth));
+        let folded = if self.folded_paths.is_empty() {
+            None
+        } else {
+            let lowered = normalized.to_lowercase();
+            self.folded_paths
+                .longest_prefix(&lowered)
+                .map(|depth| (self.by_folded_path[&prefix(&lowered, depth)], depth))
+        };
+        let (index, depth) = match (exact, folded) {
+            (Some(exact), Some(folded)) if folded.1 > exact.1 => folded,
+            (Some(exact), _) => exact,
+            (None, folded) => folded?,
+        };
+        Some((&self.mounts[index], depth))
     }
-
-    normalized
 }
 
-/// Strip the mount prefix from a path and return the relative path.
-fn strip_mount_prefix(path: &str, mount_path: &str) -> Option<String> {
-    let mount_normalized = mount_path.trim_end_matches('/');
+/// The first `depth` components of `path`, as an absolute path.
+fn prefix(path: &str, depth: usize) -> String {
+    let components: Vec<&str> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .take(depth)
+        .collect();
+    format!("/{}", components.join("/"))
+}
 
-    if path == mount_normalized {
-        // Exact match - return empty path (root of mount)
-        return Some(String::new());
+/// Normalize `relative`, the part of `path` below its mount, as `policy` says.
+fn apply_path_policy(
+    path: &str,
+    relative: String,
+    policy: &PathPolicyConfig,
+) -> Result<String, VfsError> {
+    let invalid = |reason: String| VfsError::InvalidPath {
+        path: path.to_string(),
+        reason,
+    };
+    if policy.trailing_slash == TrailingSlash::Reject && !relative.is_empty() && path.ends_with('/')
+    {
+        return Err(invalid(
+            "trailing slashes are not allowed on this mount".to_string(),
+        ));
     }
-
-    if let Some(suffix) = path.strip_prefix(mount_normalized) {
-        if let Some(relative) = suffix.strip_prefix('/') {
-            // Path is under this mount
-            return Some(relative.to_string());
-        }
+    let mut relative = relative;
+    if policy.unicode_nfc {
+        relative = relative.nfc().collect();
     }
-
-    // Special case: root mount "/"
-    if mount_normalized.is_empty() || mount_normalized == "/" {
-        return Some(path.trim_start_matches('/').to_string());
+    if policy.case_fold {
+        relative = relative.to_lowercase();
     }
-
-    None
+    if let Some(max) = policy.max_length {
+        if relative.len() > max {
+            return Err(invalid(format!(
+                "{} bytes below the mount, over the limit of {}",
+                relative.len(),
+                max
+            )));
+        }
+    }
+    Ok(relative)
 }
 
 #[cfg(test)]
@@ -130,11 +216,13 @@ mod tests {
                 path: "/".to_string(),
                 backend: Arc::new(MockBackend),
                 read_only: false,
+                path_policy: PathPolicyConfig::default(),
             },
             Mount {
                 path: "/workspace".to_string(),
                 backend: Arc::new(MockBackend),
                 read_only: false,
+                path_policy: PathPolicyConfig::default(),
             },
         ]);
 
@@ -153,6 +241,7 @@ mod tests {
             path: "/workspace".to_string(),
             backend: Arc::new(MockBackend),
             read_only: false,
+            path_policy: PathPolicyConfig::default(),
         }]);
 
         let (_, relative, _) = router.resolve("/workspace").unwrap();
@@ -165,9 +254,124 @@ mod tests {
             path: "/workspace".to_string(),
             backend: Arc::new(MockBackend),
             read_only: false,
+            path_policy: PathPolicyConfig::default(),
         }]);
 
         let result = router.resolve("/other/file.txt");
         assert!(matches!(result, Err(VfsError::NoMount(_))));
     }
+
+    #[test]
+    fn test_path_policy() {
+        let router = Router::new(vec![
+            Mount {
+                path: "/s3".to_string(),
+                backend: Arc::new(MockBackend),
+                read_only: false,
+                path_policy: PathPolicyConfig {
+                    case_fold: true,
+                    unicode_nfc: true,
+                    trailing_slash: TrailingSlash::Reject,
+                    max_length: Some(16),
+                },
+            },
+            Mount {
+                path: "/workspace".to_string(),
+                backend: Arc::new(MockBackend),
+                read_only: false,
+                path_policy: PathPolicyConfig::default(),
+            },
+        ]);
+
+        let (_, relative, _) = router.resolve("/S3/Reports/Q1.md").unwrap();
+        assert_eq!(relative, "reports/q1.md");
+        // "é" composed and decomposed resolve alike.
+        let (_, composed, _) = router.resolve("/s3/caf\u{e9}").unwrap();
+        let (_, decomposed, _) = router.resolve("/s3/cafe\u{301}").unwrap();
+        assert_eq!(composed, decomposed);
+
+        let invalid = |path| matches!(router.resolve(path), Err(VfsError::InvalidPath { .. }));
+        assert!(invalid("/s3/reports/"));
+        assert!(invalid("/s3/a-much-longer-file-name.md"));
+        assert!(router.resolve("/s3/").is_ok());
+
+        // Other mounts are untouched.
+        let (_, relative, _) = router.resolve("/workspace/Notes/").unwrap();
+        assert_eq!(relative, "Notes");
+        assert!(matches!(
+            router.resolve("/WORKSPACE/a"),
+            Err(VfsError::NoMount(_))
+        ));
+    }
+
+    #[test]
+    fn test_paths_are_sanitized() {
+        let mounts = || {
+            vec![Mount {
+                path: "/workspace".to_string(),
+                backend: Arc::new(MockBackend) as Arc<dyn Backend>,
+                read_only: false,
+                path_policy: PathPolicyConfig::default(),
+            }]
+        };
+        let router = Router::new(mounts());
+        let (_, relative, _) = router.resolve("/workspace/a/../b.txt").unwrap();
+        assert_eq!(relative, "b.txt");
+        assert!(matches!(
+            router.resolve("/workspace/../../etc/passwd"),
+            Err(VfsError::InvalidPath { .. })
+        ));
+        assert!(matches!(
+            router.resolve("/workspace/a\0"),
+            Err(VfsError::InvalidPath { .. })
+        ));
+
+        let strict = Router::new(mounts())
+            .with_sanitizer(PathSanitizer::default().with_deny_suspicious(true));
+        assert!(matches!(
+            strict.resolve("/workspace/a/../b.txt"),
+            Err(VfsError::InvalidPath { .. })
+        ));
+        assert!(strict.resolve("/workspace/b.txt").is_ok());
+    }
+
+    #[test]
+    fn test_many_mounts() {
+        let mount = |path: String, case_fold: bool| Mount {
+            path,
+            backend: Arc::new(MockBackend) as Arc<dyn Backend>,
+            read_only: false,
+            path_policy: PathPolicyConfig {
+                case_fold,
+                ..PathPolicyConfig::default()
+            },
+        };
+        let mut mounts: Vec<Mount> = (0..500)
+            .map(|i| mount(format!("/tenants/t{}/", i), false))
+            .collect();
+        mounts.push(mount("/tenants".to_string(), false));
+        mounts.push(mount("/Shared".to_string(), true));
+        mounts.push(mount("/shared/exact".to_string(), false));
+        let router = Router::new(mounts);
+
+        let (_, relative, _) = router.resolve("/tenants/t42/a/b.md").unwrap();
+        assert_eq!(relative, "a/b.md");
+        assert!(std::ptr::eq(
+            router.get_mount("/tenants/t42/a").unwrap(),
+            &router.mounts[42]
+        ));
+        // Component-wise: t4200 is not below t42.
+        let (_, relative, _) = router.resolve("/tenants/t4200/x").unwrap();
+        assert_eq!(relative, "t4200/x");
+        // A deeper exact mount wins over a case-folding one, but the
+        // case-folding mount matches in any case.
+        let (_, relative, _) = router.resolve("/shared/exact/x").unwrap();
+        assert_eq!(relative, "x");
+        let (_, relative, _) = router.resolve("/SHARED/Exact/X").unwrap();
+        assert_eq!(relative, "exact/x");
+        assert!(matches!(
+            router.resolve("/tenant"),
+            Err(VfsError::NoMount(_))
+        ));
+    }
 }
@@ -0,0 +1,259 @@
+//! Ephemeral workspaces for agent runs.
+//!
+//! [`Vfs::create_session`] mounts a scratch area at `/sessions/<id>`, kept in
+//! memory or under `sessions/<id>` of a configured `fs` or `s3` backend, and
+//! [`Vfs::end_session`] deletes its files and unmounts it. Each session has a
+//! TTL; [`Vfs::expire_sessions`] ends the ones past it.
+//!
+//! Session mounts are added with [`Vfs::reload`], so reloading from a config
+//! file that does not list them ends them without deleting their files.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use openfs_config::{BackendConfig, MemoryBackendConfig, MountConfig};
+use openfs_core::{new_request_id, VfsError};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::Vfs;
+
+/// Where session workspaces are mounted.
+pub const SESSIONS_ROOT: &str = "/sessions";
+
+/// How long a session lives unless [`SessionOptions::ttl`] says otherwise.
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(3600);
+
+/// Options for [`Vfs::create_session`].
+#[derive(Debug, Clone)]
+pub struct SessionOptions {
+    /// How long the session lives before [`Vfs::expire_sessions`] ends it.
+    pub ttl: Duration,
+    /// An `fs` or `s3` backend to keep the files in, under `sessions/<id>`.
+    /// `None` keeps them in memory.
+    pub backend: Option<String>,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        SessionOptions {
+            ttl: DEFAULT_SESSION_TTL,
+            backend: None,
+        }
+    }
+}
+
+/// A session workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Session {
+    pub id: String,
+    /// VFS path of the workspace.
+    pub path: String,
+    /// The backend it is stored on; `None` for memory.
+    pub backend: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Vfs {
+    /// Mount a new, empty session workspace and return it.
+    pub async fn create_session(&self, options: SessionOptions) -> Result<Session, VfsError> {
+        let ttl = chrono::Duration::from_std(options.ttl)
+            .map_err(|_| VfsError::InvalidArgument("Session TTL is too long".to_string()))?;
+        let mut sessions = self.sessions.lock().await;
+        let id = new_request_id();
+        let mount_backend = format!("session-{}", id);
+        let backend_config = match &options.backend {
+            None => BackendConfig::Memory(MemoryBackendConfig::default()),
+            Some(name) => scoped_backend(self, name, &id)?,
+        };
+
+        let mut config = (*self.effective_config()).clone();
+        config
+            .backends
+            .insert(mount_backend.clone(), backend_config);
+        config.mounts.push(MountConfig {
+            path: format!("{}/{}", SESSIONS_ROOT, id),
+            backend: Some(mount_backend),
+            collection: None,
+            mode: None,
+            read_only: false,
+            index: None,
+            sync: None,
+            watch: None,
+            cache: None,
+            log: None,
+            path_policy: None,
+            retry: None,
+            replicas: None,
+        });
+        self.reload(config).await?;
+
+        let created_at = Utc::now();
+        let session = Session {
+            path: format!("{}/{}", SESSIONS_ROOT, id),
+            id,
+            backend: options.backend,
+            created_at,
+            expires_at: created_at + ttl,
+        };
+        info!(session = %session.id, path = %session.path, "created session");
+        sessions.push(session.clone());
+        Ok(session)
+    }
+
+    /// Delete a session's files and unmount it.
+    pub async fn end_session(&self, id: &str) -> Result<(), VfsError> {
+        let mut sessions = self.sessions.lock().await;
+        let index = sessions
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| VfsError::NotFound(format!("session {}", id)))?;
+        self.remove_session(&sessions[index]).await?;
+        sessions.remove(index);
+        Ok(())
+    }
+
+    /// End every session past its TTL, returning their IDs.
+    pub async fn expire_sessions(&self) -> Result<Vec<String>, VfsError> {
+        let mut sessions = self.sessions.lock().await;
+        let now = Utc::now();
+        let mut expired = Vec::new();
+        for session in sessions.iter().filter(|s| s.expires_at <= now) {
+            self.remove_session(session).await?;
+            expired.push(session.id.clone());
+        }
+        sessions.retain(|s| !expired.contains(&s.id));
+        Ok(expired)
+    }
+
+    /// The live sessions, oldest first.
+    pub async fn sessions(&self) -> Vec<Session> {
+        self.sessions.lock().await.clone()
+    }
+
+    async fn remove_session(&self, session: &Session) -> Result<(), VfsError> {
+        if session.backend.is_some() {
+            // Deepest paths first, so directories are empty when reached.
+            for entry in self.walk(&session.path).await?.iter().rev() {
+                if let Err(e) = self.delete(&entry.path).await {
+                    if !entry.is_dir {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        let mount_backend = format!("session-{}", session.id);
+        let mut config = (*self.effective_config()).clone();
+        let root = match config.backends.shift_remove(&mount_backend) {
+            Some(BackendConfig::Fs(fs)) => Some(fs.root),
+            _ => None,
+        };
+        config.mounts.retain(|m| m.path != session.path);
+        self.reload(config).await?;
+
+        if let Some(root) = root {
+            if let Err(e) = std::fs::remove_dir(&root) {
+                warn!(root = %root, "could not remove session directory: {}", e);
+            }
+        }
+        info!(session = %session.id, "ended session");
+        Ok(())
+    }
+}
+
+/// `name`'s config with its root or prefix moved to `sessions/<id>`.
+fn scoped_backend(vfs: &Vfs, name: &str, id: &str) -> Result<BackendConfig, VfsError> {
+    let config = vfs.effective_config();
+    let subdir = format!("sessions/{}", id);
+    match config.backends.get(name) {
+        Some(BackendConfig::Fs(fs)) => {
+            let mut fs = fs.clone();
+            fs.root = format!("{}/{}", fs.root.trim_end_matches('/'), subdir);
+            std::fs::create_dir_all(&fs.root)?;
+            Ok(BackendConfig::Fs(fs))
+        }
+        Some(BackendConfig::S3(s3)) => {
+            let mut s3 = s3.clone();
+            s3.prefix = Some(match s3.prefix.as_deref().map(|p| p.trim_matches('/')) {
+                Some(prefix) if !prefix.is_empty() => format!("{}/{}", prefix, subdir),
+                _ => subdir,
+            });
+            Ok(BackendConfig::S3(s3))
+        }
+        Some(_) => Err(VfsError::InvalidArgument(format!(
+            "Backend '{}' cannot hold sessions; use an fs or s3 backend",
+            name
+        ))),
+        None => Err(VfsError::InvalidArgument(format!(
+            "Unknown backend '{}'",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let tmp = TempDir::new().unwrap();
+        let yaml = format!(
+            r#"
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: local
+"#,
+            tmp.path().display()
+        );
+        let vfs = Vfs::from_config(VfsConfig::from_yaml(&yaml).unwrap())
+            .await
+            .unwrap();
+
+        let scratch = vfs.create_session(SessionOptions::default()).await.unwrap();
+        let stored = vfs
+            .create_session(SessionOptions {
+                ttl: Duration::ZERO,
+                backend: Some("local".to_string()),
+            })
+            .await
+            .unwrap();
+        assert_ne!(scratch.path, stored.path);
+
+        vfs.write(&format!("{}/notes.md", scratch.path), b"a")
+            .await
+            .unwrap();
+        vfs.write(&format!("{}/out/report.md", stored.path), b"b")
+            .await
+            .unwrap();
+        let on_disk = tmp.path().join("sessions").join(&stored.id);
+        assert!(on_disk.join("out/report.md").exists());
+
+        assert_eq!(
+            vfs.expire_sessions().await.unwrap(),
+            std::slice::from_ref(&stored.id)
+        );
+        assert!(!on_disk.exists());
+        assert!(vfs
+            .read(&format!("{}/out/report.md", stored.path))
+            .await
+            .is_err());
+        assert_eq!(vfs.sessions().await, std::slice::from_ref(&scratch));
+
+        vfs.end_session(&scratch.id).await.unwrap();
+        assert!(vfs
+            .read(&format!("{}/notes.md", scratch.path))
+            .await
+            .is_err());
+        assert!(vfs.end_session(&scratch.id).await.is_err());
+        assert!(vfs.exists("/workspace").await.unwrap());
+    }
+}
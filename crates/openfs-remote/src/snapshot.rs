@@ -0,0 +1,346 @@
+//! Point-in-time snapshots of a mount.
+//!
+//! A [`SnapshotStore`] keeps file contents as blobs named by their BLAKE3
+//! hash, and one JSON manifest per snapshot mapping each file of the mount to
+//! the hash of its content. Files unchanged between snapshots share a blob,
+//! so a snapshot stores only what changed since the ones before it.
+
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One file in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// BLAKE3 hash of the content, as hex.
+    pub hash: String,
+    pub size: u64,
+}
+
+impl SnapshotFile {
+    pub fn of(content: &[u8]) -> Self {
+        SnapshotFile {
+            hash: blake3::hash(content).to_hex().to_string(),
+            size: content.len() as u64,
+        }
+    }
+}
+
+/// The files of a mount at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub id: String,
+    /// Mount path the snapshot was taken of.
+    pub mount: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Files by path relative to the mount.
+    pub files: BTreeMap<String, SnapshotFile>,
+}
+
+impl SnapshotManifest {
+    pub fn new(mount: &str, label: Option<&str>, files: BTreeMap<String, SnapshotFile>) -> Self {
+        let created_at = Utc::now();
+        SnapshotManifest {
+            // Sorts by creation time.
+            id: created_at.format("%Y%m%dT%H%M%S%6fZ").to_string(),
+            mount: mount.to_string(),
+            label: label.map(str::to_string),
+            created_at,
+            files,
+        }
+    }
+
+    pub fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            id: self.id.clone(),
+            mount: self.mount.clone(),
+            label: self.label.clone(),
+            created_at: self.created_at,
+            files: self.files.len(),
+            bytes: self.files.values().map(|file| file.size).sum(),
+        }
+    }
+
+    /// What changed from this snapshot to `newer` files.
+    pub fn diff(&self, newer: &BTreeMap<String, SnapshotFile>) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        for (path, file) in newer {
+            match self.files.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(old) if old.hash != file.hash => diff.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.deleted = self
+            .files
+            .keys()
+            .filter(|path| !newer.contains_key(*path))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// Summary of a snapshot, without its file list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub mount: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub files: usize,
+    /// Total size of the files.
+    pub bytes: u64,
+}
+
+/// Files that differ between two states of a mount, by path relative to the
+/// mount, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// What restoring a snapshot did to a mount.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    /// Files written back with their snapshot content.
+    pub written: usize,
+    /// Files deleted because they were not in the snapshot.
+    pub deleted: usize,
+    /// Files already as in the snapshot.
+    pub unchanged: usize,
+}
+
+/// A point in a mount's history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryPoint {
+    /// The snapshot with this id, or else the newest one with this label.
+    Snapshot(String),
+    /// The newest snapshot taken at or before this time.
+    Time(DateTime<Utc>),
+}
+
+impl HistoryPoint {
+    /// `@` followed by an RFC 3339 time (`@2026-01-31T12:00:00Z`) names a
+    /// time; anything else a snapshot.
+    pub fn parse(name: &str) -> Self {
+        name.strip_prefix('@')
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| HistoryPoint::Time(time.with_timezone(&Utc)))
+            .unwrap_or_else(|| HistoryPoint::Snapshot(name.to_string()))
+    }
+
+    /// The snapshot this point refers to among the snapshots of one mount,
+    /// oldest first.
+    pub fn pick<'a, M: std::borrow::Borrow<SnapshotManifest>>(
+        &self,
+        manifests: &'a [M],
+    ) -> Option<&'a M> {
+        let mut manifests = manifests.iter();
+        match self {
+            HistoryPoint::Snapshot(name) => manifests
+                .clone()
+                .find(|m| &m.borrow().id == name)
+                .or_else(|| manifests.rfind(|m| m.borrow().label.as_ref() == Some(name))),
+            HistoryPoint::Time(time) => manifests.rfind(|m| m.borrow().created_at <= *time),
+        }
+    }
+}
+
+/// Blobs and manifests in a directory.
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    /// The store in `dir`, which is created on the first snapshot.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        SnapshotStore { dir: dir.into() }
+    }
+
+    /// Store `content`, unless a blob with its hash is already there.
+    pub fn put_blob(&self, content: &[u8]) -> std::io::Result<SnapshotFile> {
+        let file = SnapshotFile::of(content);
+        let path = self.blob_path(&file.hash);
+        if !path.exists() {
+            std::fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+            write_atomic(&path, content)?;
+        }
+        Ok(file)
+    }
+
+    pub fn has_blob(&self, hash: &str) -> bool {
+        self.blob_path(hash).exists()
+    }
+
+    pub fn blob(&self, hash: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.blob_path(hash))
+    }
+
+    pub fn save(&self, manifest: &SnapshotManifest) -> std::io::Result<()> {
+        let dir = self.manifest_dir(&manifest.mount);
+        std::fs::create_dir_all(&dir)?;
+        let json = serde_json::to_vec_pretty(manifest)?;
+        write_atomic(&dir.join(format!("{}.json", manifest.id)), &json)
+    }
+
+    /// Snapshots of `mount`, oldest first.
+    pub fn list(&self, mount: &str) -> std::io::Result<Vec<SnapshotManifest>> {
+        self.manifest_paths(mount)?
+            .iter()
+            .map(|path| Self::load(path))
+            .collect()
+    }
+
+    /// The manifest files of `mount`, oldest first.
+    pub fn manifest_paths(&self, mount: &str) -> std::io::Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(self.manifest_dir(mount)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        // Ids sort by creation time.
+        paths.sort();
+        Ok(paths)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<SnapshotManifest> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// The snapshot of `mount` that `name` refers to, see [`HistoryPoint::parse`].
+    pub fn find(&self, mount: &str, name: &str) -> std::io::Result<Option<SnapshotManifest>> {
+        let manifests = self.list(mount)?;
+        Ok(HistoryPoint::parse(name).pick(&manifests).cloned())
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.dir
+            .join("blobs")
+            .join(&hash[..2.min(hash.len())])
+            .join(hash)
+    }
+
+    fn manifest_dir(&self, mount: &str) -> PathBuf {
+        let mount = mount.trim_matches('/');
+        let name = if mount.is_empty() {
+            "root".to_string()
+        } else {
+            mount
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect()
+        };
+        self.dir.join("manifests").join(name)
+    }
+}
+
+/// Write through a temporary file so readers never see a partial file.
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let tmp = path.with_extension(format!(
+        "tmp.{}.{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(content)?;
+    drop(file);
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &[u8])]) -> BTreeMap<String, SnapshotFile> {
+        entries
+            .iter()
+            .map(|(path, content)| (path.to_string(), SnapshotFile::of(content)))
+            .collect()
+    }
+
+    #[test]
+    fn test_store_dedupes_blobs_and_finds_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(dir.path());
+        let a = store.put_blob(b"same").unwrap();
+        let b = store.put_blob(b"same").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(store.blob(&a.hash).unwrap(), b"same");
+
+        let first = SnapshotManifest::new("/work", Some("before"), files(&[("a", b"1")]));
+        store.save(&first).unwrap();
+        let second = SnapshotManifest::new("/work", Some("before"), files(&[("a", b"2")]));
+        assert!(second.id > first.id);
+        store.save(&second).unwrap();
+
+        let listed = store.list("/work/").unwrap();
+        assert_eq!(listed, vec![first.clone(), second.clone()]);
+        assert!(store.list("/other").unwrap().is_empty());
+        assert_eq!(store.find("/work", &first.id).unwrap(), Some(first));
+        // A label names the newest snapshot carrying it.
+        assert_eq!(store.find("/work", "before").unwrap(), Some(second));
+        assert_eq!(store.find("/work", "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_history_point() {
+        let mut old = SnapshotManifest::new("/", Some("old"), BTreeMap::new());
+        old.id = "20260101T000000000000Z".to_string();
+        old.created_at = "2026-01-01T00:00:00Z".parse().unwrap();
+        let new = SnapshotManifest::new("/", None, BTreeMap::new());
+        let manifests = [old.clone(), new.clone()];
+
+        let point = HistoryPoint::parse("@2026-06-01T00:00:00+02:00");
+        assert!(matches!(point, HistoryPoint::Time(_)));
+        assert_eq!(point.pick(&manifests), Some(&old));
+        assert_eq!(
+            HistoryPoint::parse("@2025-01-01T00:00:00Z").pick(&manifests),
+            None
+        );
+        assert_eq!(HistoryPoint::parse(&new.id).pick(&manifests), Some(&new));
+        assert_eq!(HistoryPoint::parse("old").pick(&manifests), Some(&old));
+        assert_eq!(
+            HistoryPoint::parse("@yesterday"),
+            HistoryPoint::Snapshot("@yesterday".to_string())
+        );
+    }
+
+    #[test]
+    fn test_manifest_diff() {
+        let old = SnapshotManifest::new(
+            "/",
+            None,
+            files(&[("keep", b"k"), ("edit", b"v1"), ("gone", b"g")]),
+        );
+        let diff = old.diff(&files(&[("keep", b"k"), ("edit", b"v2"), ("new", b"n")]));
+        assert_eq!(diff.added, vec!["new"]);
+        assert_eq!(diff.modified, vec!["edit"]);
+        assert_eq!(diff.deleted, vec!["gone"]);
+        assert!(old.diff(&old.files).is_empty());
+        assert_eq!(old.info().bytes, 4);
+    }
+}
@@ -1,14 +1,16 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use openfs_config::BackoffStrategy;
-use tokio::sync::{Mutex, Notify, RwLock};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use openfs_config::{BackoffStrategy, ConflictStrategy};
+use tokio::sync::{watch, Mutex, Notify, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-use crate::wal::{WalOpType, WriteAheadLog};
+use crate::wal::{OutboxStatus, WalOpType, WriteAheadLog};
 use openfs_core::VfsError;
 
 /// Sync mode for a mount.
@@ -21,8 +23,22 @@ pub enum SyncMode {
     WriteThrough,
     /// Write-back: writes go to local first, then flushed to remote in background.
     WriteBack,
-    /// Pull-mirror: read-only, pulls from remote on cache miss.
+    /// Pull-mirror: read-only, pulls from remote on cache miss and on a schedule.
     PullMirror,
+    /// Two-way: write-back, plus remote changes pulled into the cache periodically.
+    TwoWay,
+}
+
+impl SyncMode {
+    /// Whether writes go to local first and are flushed in the background.
+    pub fn is_write_back(self) -> bool {
+        matches!(self, SyncMode::WriteBack | SyncMode::TwoWay)
+    }
+
+    /// Whether remote changes are pulled into the cache on a schedule.
+    pub fn pulls_remote_changes(self) -> bool {
+        matches!(self, SyncMode::PullMirror | SyncMode::TwoWay)
+    }
 }
 
 /// A pending write operation for write-back mode.
@@ -36,6 +52,8 @@ pub struct PendingWrite {
     pub op_id: u64,
     /// Number of retry attempts.
     pub attempts: u32,
+    /// Earliest time of the next attempt, after a failure.
+    pub retry_at: Option<Instant>,
 }
 
 /// Configuration for the sync engine.
@@ -47,12 +65,26 @@ pub struct SyncConfig {
     pub max_pending: usize,
     /// Flush interval for write-back mode.
     pub flush_interval: Duration,
+    /// Writes sent to the backend at once during a flush.
+    pub flush_concurrency: usize,
+    /// Write-back files of at least this many bytes are uploaded in parts.
+    pub multipart_threshold: u64,
+    /// Bytes per part of a multipart upload.
+    pub multipart_part_size: u64,
     /// Maximum retry attempts for failed writes.
     pub max_retries: u32,
     /// Base backoff duration between retries.
     pub retry_backoff: Duration,
+    /// Upper bound for the backoff between retries.
+    pub max_backoff: Duration,
     /// Backoff strategy for retries.
     pub backoff_strategy: BackoffStrategy,
+    /// Randomize retry delays.
+    pub jitter: bool,
+    /// How write-back resolves files that changed remotely.
+    pub conflict: ConflictStrategy,
+    /// Pull interval for pull-mirror and two-way mode.
+    pub pull_interval: Duration,
 }
 
 impl Default for SyncConfig {
@@ -61,9 +93,28 @@ impl Default for SyncConfig {
             mode: SyncMode::None,
             max_pending: 1000,
             flush_interval: Duration::from_secs(5),
+            flush_concurrency: 8,
+            multipart_threshold: 64 * 1024 * 1024,
+            multipart_part_size: 8 * 1024 * 1024,
             max_retries: 3,
             retry_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
             backoff_strategy: BackoffStrategy::Exponential,
+            jitter: true,
+            conflict: ConflictStrategy::Overwrite,
+            pull_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SyncConfig {
+    /// The delay schedule for retrying failed writes.
+    pub fn backoff(&self) -> RetryBackoff {
+        RetryBackoff {
+            base: self.retry_backoff,
+            max: self.max_backoff,
+            strategy: self.backoff_strategy,
+            jitter: self.jitter,
         }
     }
 }
@@ -78,6 +129,41 @@ pub fn compute_backoff(base: Duration, attempt: u32, strategy: BackoffStrategy)
     }
 }
 
+/// Delay schedule for retrying failed sync operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBackoff {
+    /// Delay before the first retry.
+    pub base: Duration,
+    /// Upper bound for any computed delay.
+    pub max: Duration,
+    pub strategy: BackoffStrategy,
+    /// Pick each delay at random between half and all of the computed one.
+    pub jitter: bool,
+}
+
+impl RetryBackoff {
+    /// Delay before retry number `attempt` (0-based). A `retry_after` hint
+    /// from the backend is honored when it asks for longer.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let mut delay = compute_backoff(self.base, attempt, self.strategy).min(self.max);
+        if self.jitter {
+            delay = jittered(delay);
+        }
+        match retry_after {
+            Some(after) => delay.max(after),
+            None => delay,
+        }
+    }
+}
+
+/// A random duration between half of `delay` and `delay`.
+fn jittered(delay: Duration) -> Duration {
+    use std::hash::BuildHasher;
+    let random = std::collections::hash_map::RandomState::new().hash_one(Instant::now());
+    let half = delay / 2;
+    half + half.mul_f64(random as f64 / u64::MAX as f64)
+}
+
 /// Sync engine statistics.
 #[derive(Debug, Clone, Default)]
 pub struct SyncStats {
@@ -89,10 +175,76 @@ pub struct SyncStats {
     pub failed: u64,
     /// Number of retries.
     pub retries: u64,
+    /// Writes merged into one already queued for the same path, each
+    /// saving an upload.
+    pub coalesced: u64,
+    /// Number of writes given up on and left in the dead-letter queue.
+    pub dead_lettered: u64,
     /// Last sync time.
     pub last_sync: Option<Instant>,
 }
 
+/// Where a path stands in write-back sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSyncState {
+    /// Waiting for the next flush.
+    Queued,
+    /// Being written to the remote.
+    Uploading,
+    /// The last attempt failed. A write that is not `retrying` has been
+    /// given up on and is in the dead-letter queue.
+    Failed { reason: String, retrying: bool },
+    /// Written to the remote.
+    Synced,
+}
+
+impl PathSyncState {
+    /// Stable name: `queued`, `uploading`, `failed` or `synced`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathSyncState::Queued => "queued",
+            PathSyncState::Uploading => "uploading",
+            PathSyncState::Failed { .. } => "failed",
+            PathSyncState::Synced => "synced",
+        }
+    }
+}
+
+/// Write-back sync state of one path, see [`SyncEngine::path_statuses`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSyncStatus {
+    pub path: String,
+    pub state: PathSyncState,
+    /// Failed attempts at writing the current content.
+    pub attempts: u32,
+    /// When the path was last written to the remote.
+    pub last_synced: Option<DateTime<Utc>>,
+}
+
+impl PathSyncStatus {
+    fn new(path: &str) -> Self {
+        PathSyncStatus {
+            path: path.to_string(),
+            state: PathSyncState::Synced,
+            attempts: 0,
+            last_synced: None,
+        }
+    }
+}
+
+/// What the flush loop last did with a path.
+#[derive(Debug, Clone, Default)]
+struct PathRecord {
+    uploading: bool,
+    attempts: u32,
+    error: Option<String>,
+    synced_at: Option<DateTime<Utc>>,
+}
+
+/// Past this many tracked paths, those last flushed successfully are
+/// forgotten (the WAL keeps their sync times, when there is one).
+const MAX_TRACKED_PATHS: usize = 10_000;
+
 /// Sync engine for managing write-back operations.
 pub struct SyncEngine {
     config: SyncConfig,
@@ -108,12 +260,17 @@ pub struct SyncEngine {
     op_seq: Arc<AtomicU64>,
     /// Stats tracking.
     stats: Arc<RwLock<SyncStats>>,
+    /// Per-path flush outcomes.
+    paths: Arc<RwLock<HashMap<String, PathRecord>>>,
     /// Flush task handle.
     flush_handle: Mutex<Option<JoinHandle<()>>>,
     /// Outbox drain task handle.
     outbox_handle: Mutex<Option<JoinHandle<()>>>,
     /// Flag to signal shutdown.
     shutdown: Arc<RwLock<bool>>,
+    /// Wakes the background loops when shutdown is signalled, so they do
+    /// not sleep out their interval first.
+    shutdown_wake: watch::Sender<bool>,
     /// Optional WAL for crash-safe writes.
     wal: Option<Arc<WriteAheadLog>>,
 }
@@ -129,9 +286,11 @@ impl SyncEngine {
             in_flight_notify: Arc::new(Notify::new()),
             op_seq: Arc::new(AtomicU64::new(0)),
             stats: Arc::new(RwLock::new(SyncStats::default())),
+            paths: Arc::new(RwLock::new(HashMap::new())),
             flush_handle: Mutex::new(None),
             outbox_handle: Mutex::new(None),
             shutdown: Arc::new(RwLock::new(false)),
+            shutdown_wake: watch::Sender::new(false),
             wal: None,
         }
     }
@@ -146,9 +305,11 @@ impl SyncEngine {
             in_flight_notify: Arc::new(Notify::new()),
             op_seq: Arc::new(AtomicU64::new(0)),
             stats: Arc::new(RwLock::new(SyncStats::default())),
+            paths: Arc::new(RwLock::new(HashMap::new())),
             flush_handle: Mutex::new(None),
             outbox_handle: Mutex::new(None),
             shutdown: Arc::new(RwLock::new(false)),
+            shutdown_wake: watch::Sender::new(false),
             wal: Some(wal),
         }
     }
@@ -164,7 +325,7 @@ impl SyncEngine {
         F: Fn(String, Vec<u8>) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<(), VfsError>> + Send,
     {
-        if self.config.mode != SyncMode::WriteBack {
+        if !self.config.mode.is_write_back() {
             return;
         }
 
@@ -177,17 +338,35 @@ impl SyncEngine {
         let tombstones = Arc::clone(&self.tombstones);
         let stats = Arc::clone(&self.stats);
         let shutdown = Arc::clone(&self.shutdown);
+        let mut wake = self.shutdown_wake.subscribe();
         let config = self.config.clone();
         let in_flight = Arc::clone(&self.in_flight);
         let in_flight_notify = Arc::clone(&self.in_flight_notify);
-        let flush_fn = Arc::new(flush_fn);
         let wal = self.wal.clone();
+        let paths = Arc::clone(&self.paths);
+        let tracked_wal = self.wal.clone();
+        let flush_fn = Arc::new(move |path: String, content: Vec<u8>| {
+            let paths = Arc::clone(&paths);
+            let wal = tracked_wal.clone();
+            let flush = flush_fn(path.clone(), content);
+            async move {
+                let mut records = paths.write().await;
+                records.entry(path.clone()).or_default().uploading = true;
+                drop(records);
+                let result = flush.await;
+                record_flush(&paths, wal.as_deref(), &path, &result).await;
+                result
+            }
+        });
 
         let handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(config.flush_interval);
 
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = wake.changed() => {}
+                }
 
                 if *shutdown.read().await {
                     break;
@@ -206,8 +385,11 @@ impl SyncEngine {
                 .await;
             }
 
-            // Final flush on shutdown
+            // Final flush on shutdown, without waiting out retry delays
             info!("Sync engine shutting down, flushing remaining writes");
+            for write in pending.write().await.iter_mut() {
+                write.retry_at = None;
+            }
             flush_pending(
                 &pending,
                 &tombstones,
@@ -226,6 +408,18 @@ impl SyncEngine {
 
     /// Queue a write operation (for write-back mode).
     pub async fn queue_write(&self, path: String, content: Vec<u8>) -> Result<(), VfsError> {
+        self.queue_write_with_base(path, content, None).await
+    }
+
+    /// Queue a write based on the remote version `base_token`. The token is
+    /// kept in the outbox, so replaying the write after a restart can still
+    /// detect a conflict.
+    pub async fn queue_write_with_base(
+        &self,
+        path: String,
+        content: Vec<u8>,
+        base_token: Option<String>,
+    ) -> Result<(), VfsError> {
         // Log to WAL for crash safety
         if let Some(wal) = &self.wal {
             let wal_id = wal
@@ -234,28 +428,48 @@ impl SyncEngine {
             wal.mark_applied(wal_id)
                 .map_err(|e| VfsError::Config(format!("WAL mark_applied failed: {}", e)))?;
 
-            wal.enqueue_outbox(WalOpType::Write, &path, Some(&content), "")
-                .map_err(|e| VfsError::Config(format!("Outbox enqueue failed: {}", e)))?;
+            wal.enqueue_outbox_with_base(
+                WalOpType::Write,
+                &path,
+                Some(&content),
+                "",
+                base_token.as_deref(),
+            )
+            .map_err(|e| VfsError::Config(format!("Outbox enqueue failed: {}", e)))?;
         }
 
         self.ensure_started().await?;
 
         let op_id = self.op_seq.fetch_add(1, Ordering::Relaxed) + 1;
         let mut pending_guard = self.pending_writes.write().await;
-        if pending_guard.len() >= self.config.max_pending {
-            return Err(VfsError::Config("Sync queue full".to_string()));
-        }
-        pending_guard.push_back(PendingWrite {
-            path,
-            content,
-            op_id,
-
-            attempts: 0,
-        });
+        // A write waiting to be retried is superseded by the new content.
+        pending_guard.retain(|p| p.path != path || p.retry_at.is_none());
+        // So is one waiting for the next flush, keeping its place in the queue.
+        let coalesced = match pending_guard.iter_mut().find(|p| p.path == path) {
+            Some(existing) => {
+                existing.content = content;
+                existing.op_id = op_id;
+                true
+            }
+            None => {
+                if pending_guard.len() >= self.config.max_pending {
+                    return Err(VfsError::Config("Sync queue full".to_string()));
+                }
+                pending_guard.push_back(PendingWrite {
+                    path,
+                    content,
+                    op_id,
+                    attempts: 0,
+                    retry_at: None,
+                });
+                false
+            }
+        };
         let pending_len = pending_guard.len();
         drop(pending_guard);
         let mut stats_guard = self.stats.write().await;
         stats_guard.pending = pending_len;
+        stats_guard.coalesced += u64::from(coalesced);
         Ok(())
     }
 
@@ -273,6 +487,8 @@ impl SyncEngine {
             // a durable delete item that would otherwise remain pending forever.
             wal.complete_outbox_for_path(&path)
                 .map_err(|e| VfsError::Config(format!("Outbox clear failed for delete: {}", e)))?;
+            wal.forget_synced(&path)
+                .map_err(|e| VfsError::Config(format!("Sync state clear failed: {}", e)))?;
         }
 
         self.ensure_started().await?;
@@ -281,6 +497,7 @@ impl SyncEngine {
         let mut tomb_guard = self.tombstones.write().await;
         tomb_guard.insert(path.clone(), op_id);
         drop(tomb_guard);
+        self.paths.write().await.remove(&path);
 
         let mut pending_guard = self.pending_writes.write().await;
         pending_guard.retain(|p| p.path != path);
@@ -308,30 +525,36 @@ impl SyncEngine {
 
         let op_id = self.op_seq.fetch_add(1, Ordering::Relaxed) + 1;
         let mut pending_guard = self.pending_writes.write().await;
-        if let Some(existing) = pending_guard.iter_mut().find(|p| p.path == path) {
-            existing.content.extend(content);
-            existing.op_id = op_id;
-        } else {
-            if pending_guard.len() >= self.config.max_pending {
-                return Err(VfsError::Config("Sync queue full".to_string()));
+        let coalesced = match pending_guard.iter_mut().find(|p| p.path == path) {
+            Some(existing) => {
+                existing.content.extend(content);
+                existing.op_id = op_id;
+                true
             }
-            pending_guard.push_back(PendingWrite {
-                path,
-                content,
-                op_id,
-
-                attempts: 0,
-            });
-        }
+            None => {
+                if pending_guard.len() >= self.config.max_pending {
+                    return Err(VfsError::Config("Sync queue full".to_string()));
+                }
+                pending_guard.push_back(PendingWrite {
+                    path,
+                    content,
+                    op_id,
+                    attempts: 0,
+                    retry_at: None,
+                });
+                false
+            }
+        };
         let pending_len = pending_guard.len();
         drop(pending_guard);
         let mut stats_guard = self.stats.write().await;
         stats_guard.pending = pending_len;
+        stats_guard.coalesced += u64::from(coalesced);
         Ok(())
     }
 
     async fn ensure_started(&self) -> Result<(), VfsError> {
-        if self.config.mode != SyncMode::WriteBack {
+        if !self.config.mode.is_write_back() {
             return Err(VfsError::Config(
                 "Sync engine not in write-back mode".to_string(),
             ));
@@ -381,6 +604,7 @@ impl SyncEngine {
         };
 
         let shutdown = Arc::clone(&self.shutdown);
+        let mut wake = self.shutdown_wake.subscribe();
         let stats = Arc::clone(&self.stats);
         let sync_fn = Arc::new(sync_fn);
 
@@ -388,7 +612,10 @@ impl SyncEngine {
             let mut interval = tokio::time::interval(Duration::from_secs(2));
 
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = wake.changed() => {}
+                }
 
                 if *shutdown.read().await {
                     break;
@@ -419,11 +646,20 @@ impl SyncEngine {
                             debug!("Outbox synced: {}", entry.path);
                         }
                         Err(e) => {
-                            if let Err(fail_err) = wal.fail_outbox(entry.id, &e.to_string()) {
+                            let recorded = if e.is_transient() {
+                                wal.fail_outbox_after(entry.id, &e.to_string(), e.retry_after())
+                            } else {
+                                wal.dead_letter(entry.id, &e.to_string())
+                            };
+                            if let Err(fail_err) = recorded {
                                 warn!("Failed to record outbox failure: {}", fail_err);
                             }
                             let mut s = stats.write().await;
-                            s.retries += 1;
+                            if e.is_transient() {
+                                s.retries += 1;
+                            } else {
+                                s.dead_lettered += 1;
+                            }
                             warn!("Outbox sync failed for {}: {}", entry.path, e);
                         }
                     }
@@ -478,6 +714,72 @@ impl SyncEngine {
         self.stats.read().await.clone()
     }
 
+    /// Sync state of every path written through the engine, sorted. With a
+    /// WAL this includes paths synced or dead-lettered by earlier runs.
+    pub async fn path_statuses(&self) -> Result<Vec<PathSyncStatus>, VfsError> {
+        let mut statuses: BTreeMap<String, PathSyncStatus> = BTreeMap::new();
+        if let Some(wal) = &self.wal {
+            let read_err = |e| VfsError::Config(format!("Failed to read sync state: {}", e));
+            for (path, synced_at) in wal.synced_paths().map_err(read_err)? {
+                let mut status = PathSyncStatus::new(&path);
+                status.last_synced = DateTime::from_timestamp(synced_at, 0);
+                statuses.insert(path, status);
+            }
+            for entry in wal.outbox_entries().map_err(read_err)? {
+                let status = statuses
+                    .entry(entry.path.clone())
+                    .or_insert_with(|| PathSyncStatus::new(&entry.path));
+                let state = match entry.status {
+                    OutboxStatus::Pending => PathSyncState::Queued,
+                    OutboxStatus::Processing => PathSyncState::Uploading,
+                    // Dead-lettered content may since have been replaced.
+                    OutboxStatus::Failed
+                        if status.last_synced.map(|t| t.timestamp())
+                            >= entry.last_attempt.or(Some(entry.created_at)) =>
+                    {
+                        continue
+                    }
+                    OutboxStatus::Failed => PathSyncState::Failed {
+                        reason: entry.error.unwrap_or_default(),
+                        retrying: false,
+                    },
+                };
+                status.state = state;
+                status.attempts = entry.attempts;
+            }
+        }
+
+        for (path, record) in self.paths.read().await.iter() {
+            let status = statuses
+                .entry(path.clone())
+                .or_insert_with(|| PathSyncStatus::new(path));
+            status.last_synced = record.synced_at.or(status.last_synced);
+            status.attempts = record.attempts;
+            status.state = match &record.error {
+                _ if record.uploading => PathSyncState::Uploading,
+                Some(reason) => PathSyncState::Failed {
+                    reason: reason.clone(),
+                    retrying: false,
+                },
+                None => PathSyncState::Synced,
+            };
+        }
+        for write in self.pending_writes.read().await.iter() {
+            let status = statuses
+                .entry(write.path.clone())
+                .or_insert_with(|| PathSyncStatus::new(&write.path));
+            status.state = match (&status.state, write.retry_at) {
+                (PathSyncState::Failed { reason, .. }, Some(_)) => PathSyncState::Failed {
+                    reason: reason.clone(),
+                    retrying: true,
+                },
+                _ => PathSyncState::Queued,
+            };
+            status.attempts = write.attempts;
+        }
+        Ok(statuses.into_values().collect())
+    }
+
     /// Get number of pending writes.
     pub async fn pending_count(&self) -> usize {
         self.pending_writes.read().await.len()
@@ -486,6 +788,7 @@ impl SyncEngine {
     /// Shutdown the sync engine.
     pub async fn shutdown(&self) {
         *self.shutdown.write().await = true;
+        self.shutdown_wake.send_replace(true);
 
         let handle = self.flush_handle.lock().await.take();
         if let Some(h) = handle {
@@ -509,7 +812,7 @@ impl SyncEngine {
 
     /// Check if write-back mode is active.
     pub fn is_write_back(&self) -> bool {
-        self.config.mode == SyncMode::WriteBack
+        self.config.mode.is_write_back()
     }
 
     /// Check if write-through mode is active.
@@ -546,6 +849,37 @@ async fn release_path_lock(
     notify.notify_waiters();
 }
 
+/// Record the outcome of one flush of `path`.
+async fn record_flush(
+    paths: &RwLock<HashMap<String, PathRecord>>,
+    wal: Option<&WriteAheadLog>,
+    path: &str,
+    result: &Result<(), VfsError>,
+) {
+    let mut paths = paths.write().await;
+    let record = paths.entry(path.to_string()).or_default();
+    record.uploading = false;
+    match result {
+        Ok(()) => {
+            record.attempts = 0;
+            record.error = None;
+            record.synced_at = Some(Utc::now());
+            if let Some(wal) = wal {
+                if let Err(e) = wal.record_synced(path) {
+                    warn!("Failed to record sync time for {}: {}", path, e);
+                }
+            }
+        }
+        Err(e) => {
+            record.attempts += 1;
+            record.error = Some(e.to_string());
+        }
+    }
+    if paths.len() > MAX_TRACKED_PATHS {
+        paths.retain(|_, record| record.uploading || record.error.is_some());
+    }
+}
+
 async fn flush_pending<F, Fut>(
     pending: &Arc<RwLock<VecDeque<PendingWrite>>>,
     tombstones: &Arc<RwLock<HashMap<String, u64>>>,
@@ -559,115 +893,113 @@ async fn flush_pending<F, Fut>(
     F: Fn(String, Vec<u8>) -> Fut + Send + Sync + 'static,
     Fut: std::future::Future<Output = Result<(), VfsError>> + Send,
 {
+    // Writes waiting out a retry delay stay queued.
+    let now = Instant::now();
     let writes_to_flush: Vec<PendingWrite> = {
         let mut pending_guard = pending.write().await;
-        pending_guard.drain(..).collect()
+        let (ready, waiting): (VecDeque<_>, VecDeque<_>) = pending_guard
+            .drain(..)
+            .partition(|w| w.retry_at.is_none_or(|at| at <= now));
+        *pending_guard = waiting;
+        ready.into()
     };
 
     {
         let mut stats_guard = stats.write().await;
-        stats_guard.pending = 0;
+        stats_guard.pending = pending.read().await.len();
     }
 
-    for mut write in writes_to_flush {
-        acquire_path_lock(&write.path, in_flight, in_flight_notify).await;
-
-        let skip = {
-            let tomb_guard = tombstones.read().await;
-            tomb_guard
-                .get(&write.path)
-                .map(|&tomb_id| write.op_id <= tomb_id)
-                .unwrap_or(false)
-        };
-        if skip {
-            release_path_lock(&write.path, in_flight, in_flight_notify).await;
-            continue;
-        }
-
-        if write.attempts > 0 {
-            let backoff = compute_backoff(
-                config.retry_backoff,
-                write.attempts - 1,
-                config.backoff_strategy,
-            );
-            tokio::time::sleep(backoff).await;
-        }
-
-        let result = flush_fn(write.path.clone(), write.content.clone()).await;
+    // Distinct paths are flushed concurrently; there is one write per path.
+    futures::stream::iter(writes_to_flush)
+        .for_each_concurrent(config.flush_concurrency.max(1), |mut write| async move {
+            acquire_path_lock(&write.path, in_flight, in_flight_notify).await;
+
+            let skip = {
+                let tomb_guard = tombstones.read().await;
+                tomb_guard
+                    .get(&write.path)
+                    .map(|&tomb_id| write.op_id <= tomb_id)
+                    .unwrap_or(false)
+            };
+            if skip {
+                release_path_lock(&write.path, in_flight, in_flight_notify).await;
+                return;
+            }
 
-        release_path_lock(&write.path, in_flight, in_flight_notify).await;
+            let result = flush_fn(write.path.clone(), write.content.clone()).await;
 
-        match result {
-            Ok(()) => {
-                let mut stats_guard = stats.write().await;
-                stats_guard.synced += 1;
-                stats_guard.last_sync = Some(Instant::now());
-                debug!("Synced: {}", write.path);
-                drop(stats_guard);
+            release_path_lock(&write.path, in_flight, in_flight_notify).await;
 
-                if let Some(wal) = wal {
-                    if let Err(e) = wal.complete_outbox_for_path(&write.path) {
-                        warn!(
-                            "Failed to clear durable outbox for {} after flush: {}",
-                            write.path, e
-                        );
+            match result {
+                Ok(()) => {
+                    let mut stats_guard = stats.write().await;
+                    stats_guard.synced += 1;
+                    stats_guard.last_sync = Some(Instant::now());
+                    debug!("Synced: {}", write.path);
+                    drop(stats_guard);
+
+                    if let Some(wal) = wal {
+                        if let Err(e) = wal.complete_outbox_for_path(&write.path) {
+                            warn!(
+                                "Failed to clear durable outbox for {} after flush: {}",
+                                write.path, e
+                            );
+                        }
                     }
                 }
-            }
-            Err(e) => {
-                let is_transient = is_transient_error(&e);
-                write.attempts += 1;
-
-                if is_transient && write.attempts < config.max_retries {
-                    let mut pending_guard = pending.write().await;
-                    pending_guard.push_back(write);
-                    let pending_len = pending_guard.len();
-                    drop(pending_guard);
+                Err(e) => {
+                    let is_transient = e.is_transient();
+                    write.attempts += 1;
+
+                    if is_transient && write.attempts < config.max_retries {
+                        let delay = config.backoff().delay(write.attempts - 1, e.retry_after());
+                        write.retry_at = Some(Instant::now() + delay);
+
+                        let mut pending_guard = pending.write().await;
+                        // Drop the retry if the path was written again meanwhile.
+                        if !pending_guard
+                            .iter()
+                            .any(|p| p.path == write.path && p.op_id > write.op_id)
+                        {
+                            pending_guard.push_back(write);
+                        }
+                        let pending_len = pending_guard.len();
+                        drop(pending_guard);
 
-                    let mut stats_guard = stats.write().await;
-                    stats_guard.pending = pending_len;
-                    stats_guard.retries += 1;
-                    warn!("Sync failed (transient), will retry: {}", e);
-                } else {
-                    let mut stats_guard = stats.write().await;
-                    stats_guard.failed += 1;
-                    if is_transient {
-                        error!("Sync failed after {} attempts: {}", config.max_retries, e);
+                        let mut stats_guard = stats.write().await;
+                        stats_guard.pending = pending_len;
+                        stats_guard.retries += 1;
+                        warn!("Sync failed (transient), will retry in {:?}: {}", delay, e);
                     } else {
-                        error!("Sync failed (non-transient, not retrying): {}", e);
+                        let mut stats_guard = stats.write().await;
+                        stats_guard.failed += 1;
+                        stats_guard.dead_lettered += 1;
+                        drop(stats_guard);
+                        if is_transient {
+                            error!("Sync failed after {} attempts: {}", write.attempts, e);
+                        } else {
+                            error!("Sync failed (non-transient, not retrying): {}", e);
+                        }
+                        if let Some(wal) = wal {
+                            if let Err(wal_err) = wal.dead_letter_path(&write.path, &e.to_string())
+                            {
+                                warn!(
+                                    "Failed to dead-letter {} in durable outbox: {}",
+                                    write.path, wal_err
+                                );
+                            }
+                        }
                     }
                 }
             }
-        }
-    }
+        })
+        .await;
 
     let pending_len = pending.read().await.len();
     let mut stats_guard = stats.write().await;
     stats_guard.pending = pending_len;
 }
 
-/// Check if a VfsError wraps a transient backend error.
-fn is_transient_error(err: &VfsError) -> bool {
-    match err {
-        VfsError::Backend(boxed) => {
-            if let Some(backend_err) = boxed.downcast_ref::<openfs_core::BackendError>() {
-                backend_err.is_transient()
-            } else {
-                false
-            }
-        }
-        VfsError::Io(io_err) => matches!(
-            io_err.kind(),
-            std::io::ErrorKind::ConnectionRefused
-                | std::io::ErrorKind::ConnectionReset
-                | std::io::ErrorKind::ConnectionAborted
-                | std::io::ErrorKind::TimedOut
-                | std::io::ErrorKind::Interrupted
-        ),
-        _ => false,
-    }
-}
-
 impl Drop for SyncEngine {
     fn drop(&mut self) {
         // Use try_write/try_lock to avoid futures::executor::block_on deadlocks
@@ -782,4 +1114,216 @@ mod tests {
             Duration::from_secs(4)
         );
     }
+
+    #[test]
+    fn test_retry_backoff_delay() {
+        let backoff = RetryBackoff {
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            strategy: BackoffStrategy::Exponential,
+            jitter: false,
+        };
+        assert_eq!(backoff.delay(1, None), Duration::from_secs(2));
+        assert_eq!(backoff.delay(10, None), Duration::from_secs(5));
+        assert_eq!(
+            backoff.delay(0, Some(Duration::from_secs(30))),
+            Duration::from_secs(30)
+        );
+
+        let jittered = RetryBackoff {
+            jitter: true,
+            ..backoff
+        };
+        for _ in 0..20 {
+            let delay = jittered.delay(2, None);
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_back_retry_then_dead_letter() {
+        let wal = Arc::new(
+            WriteAheadLog::in_memory(crate::wal::WalConfig {
+                recover_on_startup: false,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let config = SyncConfig {
+            mode: SyncMode::WriteBack,
+            flush_interval: Duration::from_millis(20),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(30),
+            jitter: false,
+            ..Default::default()
+        };
+        let engine = SyncEngine::with_wal(config, Arc::clone(&wal));
+
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_clone = Arc::clone(&attempts);
+        engine
+            .start(move |_path, _content| {
+                let attempts = Arc::clone(&attempts_clone);
+                async move {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Err(VfsError::from(openfs_core::BackendError::Throttled {
+                        backend: "test".to_string(),
+                        retry_after: None,
+                    }))
+                }
+            })
+            .await;
+        engine
+            .queue_write("/a.txt".to_string(), b"aaa".to_vec())
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let stats = engine.stats().await;
+        assert_eq!(stats.retries, 2);
+        assert_eq!(stats.dead_lettered, 1);
+        assert_eq!(stats.pending, 0);
+        let failed = wal.get_failed().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].path, "/a.txt");
+
+        engine.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_path_statuses() {
+        let wal = Arc::new(
+            WriteAheadLog::in_memory(crate::wal::WalConfig {
+                recover_on_startup: false,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let config = SyncConfig {
+            mode: SyncMode::WriteBack,
+            flush_interval: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let engine = SyncEngine::with_wal(config.clone(), Arc::clone(&wal));
+        engine
+            .start(|path, _content| async move {
+                if path == "/bad.txt" {
+                    Err(VfsError::Config("rejected".to_string()))
+                } else {
+                    Ok(())
+                }
+            })
+            .await;
+        for path in ["/a.txt", "/bad.txt"] {
+            engine
+                .queue_write(path.to_string(), b"x".to_vec())
+                .await
+                .unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        engine.shutdown().await;
+
+        let statuses = engine.path_statuses().await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].path, "/a.txt");
+        assert_eq!(statuses[0].state, PathSyncState::Synced);
+        assert!(statuses[0].last_synced.is_some());
+        assert_eq!(
+            statuses[1].state,
+            PathSyncState::Failed {
+                reason: "Config error: rejected".to_string(),
+                retrying: false,
+            }
+        );
+        assert_eq!(statuses[1].attempts, 1);
+
+        // A later run sees the same state through the WAL.
+        let later = SyncEngine::with_wal(config.clone(), Arc::clone(&wal));
+        let recovered = later.path_statuses().await.unwrap();
+        assert_eq!(recovered[0].state, PathSyncState::Synced);
+        assert_eq!(recovered[1].state.as_str(), "failed");
+
+        // Writes wait for the next tick.
+        let later = SyncEngine::with_wal(
+            SyncConfig {
+                flush_interval: Duration::from_millis(500),
+                ..config
+            },
+            wal,
+        );
+        later.start(|_path, _content| async move { Ok(()) }).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        later
+            .queue_write("/bad.txt".to_string(), b"y".to_vec())
+            .await
+            .unwrap();
+        let statuses = later.path_statuses().await.unwrap();
+        assert_eq!(statuses[1].state, PathSyncState::Queued);
+        later.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_write_back_coalesces_and_flushes_concurrently() {
+        let config = SyncConfig {
+            mode: SyncMode::WriteBack,
+            flush_interval: Duration::from_millis(100),
+            flush_concurrency: 4,
+            ..Default::default()
+        };
+        let engine = SyncEngine::new(config);
+
+        let uploads = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let active = Arc::new(AtomicU32::new(0));
+        let most_active = Arc::new(AtomicU32::new(0));
+        let (uploads_clone, active_clone, most_clone) = (
+            Arc::clone(&uploads),
+            Arc::clone(&active),
+            Arc::clone(&most_active),
+        );
+        engine
+            .start(move |path, content| {
+                let uploads = Arc::clone(&uploads_clone);
+                let active = Arc::clone(&active_clone);
+                let most_active = Arc::clone(&most_clone);
+                async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_active.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    uploads.lock().unwrap().push((path, content));
+                    Ok(())
+                }
+            })
+            .await;
+        // Let the immediate first tick pass.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        for i in 0..50 {
+            engine
+                .queue_write("/log.txt".to_string(), format!("v{}", i).into_bytes())
+                .await
+                .unwrap();
+        }
+        engine
+            .queue_append("/log.txt".to_string(), b"!".to_vec())
+            .await
+            .unwrap();
+        for path in ["/a.txt", "/b.txt", "/c.txt"] {
+            engine
+                .queue_write(path.to_string(), b"x".to_vec())
+                .await
+                .unwrap();
+        }
+        assert_eq!(engine.pending_count().await, 4);
+        assert_eq!(engine.stats().await.coalesced, 50);
+        engine.shutdown().await;
+
+        let uploads = uploads.lock().unwrap();
+        assert_eq!(uploads.len(), 4);
+        let log = uploads.iter().find(|(path, _)| path == "/log.txt").unwrap();
+        assert_eq!(log.1, b"v49!");
+        assert!(most_active.load(Ordering::SeqCst) > 1);
+    }
 }
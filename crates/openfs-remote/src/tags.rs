@@ -0,0 +1,252 @@
+//! Tags, and the `/tags` tree that gathers tagged files across mounts.
+//!
+//! A tag is file metadata under the key `tag.<name>` (see [`Vfs::add_tag`]),
+//! so it is kept wherever the file's backend keeps metadata and follows the
+//! file when it is renamed. `/tags/<tag>/` mirrors the VFS tree with only the
+//! files carrying `<tag>`, which are read from their mounts. Like views, the
+//! tree is found by walking the mounts, so it is always current. Nothing
+//! below `/tags` can be written.
+//!
+//! [`Vfs::add_tag`]: crate::Vfs::add_tag
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use openfs_core::{Backend, BackendError, Entry, FileMeta};
+
+use crate::router::{Mount, Router};
+
+/// Where the tag tree is routed.
+pub const TAGS_ROOT: &str = "/tags";
+
+/// Metadata key prefix marking a tag.
+pub(crate) const TAG_KEY_PREFIX: &str = "tag.";
+
+/// Value stored under a tag's key.
+pub(crate) const TAG_VALUE: &str = "1";
+
+/// The tags in a file's metadata.
+pub fn tags_in(meta: &FileMeta) -> impl Iterator<Item = &str> {
+    meta.keys()
+        .filter_map(|key| key.strip_prefix(TAG_KEY_PREFIX))
+}
+
+/// Backend serving the tag tree of the mounts it was built with.
+pub struct TagBackend {
+    /// Mount paths without a trailing slash, with their backends.
+    mounts: Vec<(String, Arc<dyn Backend>)>,
+    sources: Router,
+}
+
+impl TagBackend {
+    pub fn new(mounts: Vec<Mount>) -> Self {
+        TagBackend {
+            mounts: mounts
+                .iter()
+                .map(|m| (m.path.trim_end_matches('/').to_string(), m.backend.clone()))
+                .collect(),
+            sources: Router::new(mounts),
+        }
+    }
+
+    /// Every tag in use, with the VFS paths of the files carrying it.
+    pub async fn tagged(&self) -> Result<BTreeMap<String, BTreeSet<String>>, BackendError> {
+        let mut tagged: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (mount, backend) in &self.mounts {
+            let mut pending = vec![String::new()];
+            while let Some(dir) = pending.pop() {
+                let entries = match backend.list(&dir).await {
+                    Ok(entries) => entries,
+                    Err(BackendError::NotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                };
+                for entry in entries {
+                    let relative = join(&dir, &entry.name);
+                    if entry.is_dir {
+                        pending.push(relative);
+                        continue;
+                    }
+                    // Backends without metadata have no tags.
+                    let Ok(meta) = backend.read_meta(&relative).await else {
+                        continue;
+                    };
+                    for tag in tags_in(&meta) {
+                        tagged
+                            .entry(tag.to_string())
+                            .or_default()
+                            .insert(format!("{}/{}", mount, relative));
+                    }
+                }
+            }
+        }
+        Ok(tagged)
+    }
+
+    /// The mount backend and path within it of the file at `vfs_path`, if
+    /// that file carries `tag`.
+    async fn tagged_file(
+        &self,
+        tag: &str,
+        vfs_path: &str,
+    ) -> Result<(&dyn Backend, String), BackendError> {
+        let (backend, inner, _) = self
+            .sources
+            .resolve(vfs_path)
+            .map_err(|_| BackendError::NotFound(vfs_path.to_string()))?;
+        let key = format!("{}{}", TAG_KEY_PREFIX, tag);
+        match backend.read_meta(&inner).await {
+            Ok(meta) if meta.contains_key(&key) => Ok((backend, inner)),
+            _ => Err(BackendError::NotFound(vfs_path.to_string())),
+        }
+    }
+
+    /// The files tagged `tag`, as VFS paths.
+    async fn files(&self, tag: &str) -> Result<BTreeSet<String>, BackendError> {
+        Ok(self.tagged().await?.remove(tag).unwrap_or_default())
+    }
+}
+
+/// Split a backend path into the tag and the VFS path below it.
+fn split(path: &str) -> (&str, String) {
+    let relative = path.trim_matches('/');
+    match relative.split_once('/') {
+        Some((tag, rest)) => (tag, format!("/{}", rest)),
+        None => (relative, String::new()),
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn read_only(path: &str) -> BackendError {
+    BackendError::PermissionDenied(format!(
+        "'{}' is in the read-only tag tree; tag files with `openfs tag`",
+        path
+    ))
+}
+
+#[async_trait]
+impl Backend for TagBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        let (tag, vfs_path) = split(path);
+        if vfs_path.is_empty() {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let (backend, inner) = self.tagged_file(tag, &vfs_path).await?;
+        backend.read(&inner).await
+    }
+
+    async fn write(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn append(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn rename(&self, from: &str, _to: &str) -> Result<(), BackendError> {
+        Err(read_only(from))
+    }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        let (tag, vfs_path) = split(path);
+        let (backend, inner) = self.tagged_file(tag, &vfs_path).await?;
+        backend.read_meta(&inner).await
+    }
+
+    async fn write_meta(&self, path: &str, _meta: &FileMeta) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        let (tag, vfs_path) = split(path);
+        if tag.is_empty() {
+            return Ok(self
+                .tagged()
+                .await?
+                .into_keys()
+                .map(|tag| Entry::dir(tag.clone(), tag, None))
+                .collect());
+        }
+
+        let at = path.trim_matches('/');
+        let prefix = format!("{}/", vfs_path.trim_end_matches('/'));
+        let mut children = BTreeMap::new();
+        for file in self.files(tag).await? {
+            let Some(rest) = file.strip_prefix(&prefix) else {
+                continue;
+            };
+            let entry = match rest.split_once('/') {
+                Some((name, _)) => Entry::dir(join(at, name), name.to_string(), None),
+                None => match self
+                    .stat(&format!("{}{}", at, &file[vfs_path.len()..]))
+                    .await
+                {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                },
+            };
+            children
+                .entry((!entry.is_dir, entry.name.clone()))
+                .or_insert(entry);
+        }
+        if children.is_empty() {
+            return Err(match self.stat(path).await {
+                Ok(_) => BackendError::NotADirectory(path.to_string()),
+                Err(e) => e,
+            });
+        }
+        // Directories first, then files, each alphabetically.
+        Ok(children.into_values().collect())
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(BackendError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        let relative = path.trim_matches('/');
+        let name = relative.rsplit('/').next().unwrap_or_default();
+        let (tag, vfs_path) = split(path);
+        if tag.is_empty() {
+            let root = TAGS_ROOT.trim_start_matches('/');
+            return Ok(Entry::dir(String::new(), root.to_string(), None));
+        }
+
+        if !vfs_path.is_empty() {
+            if let Ok((backend, inner)) = self.tagged_file(tag, &vfs_path).await {
+                let entry = backend.stat(&inner).await?;
+                return Ok(Entry {
+                    path: relative.to_string(),
+                    name: name.to_string(),
+                    ..entry
+                });
+            }
+        }
+        // A directory exists when it holds a tagged file.
+        let prefix = format!("{}/", vfs_path.trim_end_matches('/'));
+        if self
+            .files(tag)
+            .await?
+            .iter()
+            .any(|file| file.starts_with(&prefix))
+        {
+            return Ok(Entry::dir(relative.to_string(), name.to_string(), None));
+        }
+        Err(BackendError::NotFound(path.to_string()))
+    }
+}
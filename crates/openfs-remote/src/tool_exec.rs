@@ -0,0 +1,206 @@
+//! Running tool calls against a [`Vfs`].
+//!
+//! [`generate_tools`](openfs_core::generate_tools) describes the VFS to a
+//! model as tools; [`Vfs::execute_tool`] runs the calls the model makes, so
+//! agent frameworks don't each map tool names and arguments to Vfs methods.
+//! Names and arguments are checked against the same definitions, including
+//! the renames, hidden tools and mount restrictions of the `tools:` section.
+
+use openfs_core::{find_tool, sanitize_path, Entry, SearchOptions, VfsError};
+use serde_json::{json, Value};
+
+use crate::Vfs;
+
+impl Vfs {
+    /// Run the tool call `name` with JSON `args` and return its result.
+    ///
+    /// Unknown or hidden tools and arguments that don't match the tool's
+    /// parameters fail with [`VfsError::InvalidArgument`]; failures of the
+    /// operation itself are returned as they are.
+    pub async fn execute_tool(&self, name: &str, args: &Value) -> Result<Value, VfsError> {
+        let config = self.effective_config();
+        let tool = find_tool(&config, name)
+            .ok_or_else(|| VfsError::InvalidArgument(format!("Unknown tool: {}", name)))?;
+        tool.check_arguments(args)
+            .map_err(VfsError::InvalidArgument)?;
+
+        let path = match args.get("path").and_then(Value::as_str) {
+            Some(path) => {
+                let normalized =
+                    sanitize_path(path).map_err(|violation| VfsError::InvalidPath {
+                        path: path.to_string(),
+                        reason: violation.to_string(),
+                    })?;
+                if let Some(tools_config) = &config.tools {
+                    if !tools_config.allows_path(&normalized) {
+                        return Err(VfsError::InvalidPath {
+                            path: path.to_string(),
+                            reason: format!(
+                                "outside the mounts tools may use ({})",
+                                tools_config.mounts.join(", ")
+                            ),
+                        });
+                    }
+                }
+                Some(normalized)
+            }
+            None => None,
+        };
+        let path_arg = path.as_deref().unwrap_or("/");
+        let content_arg = args.get("content").and_then(Value::as_str).unwrap_or("");
+
+        match tool.name.as_str() {
+            "vfs_read" => {
+                let content = self.read(path_arg).await?;
+                let content = match String::from_utf8(content) {
+                    Ok(text) => text,
+                    Err(e) => format!("[binary content, {} bytes]", e.as_bytes().len()),
+                };
+                Ok(json!({ "path": path_arg, "content": content }))
+            }
+            "vfs_write" => {
+                self.write(path_arg, content_arg.as_bytes()).await?;
+                Ok(json!({ "path": path_arg, "bytes_written": content_arg.len() }))
+            }
+            "vfs_append" => {
+                self.append(path_arg, content_arg.as_bytes()).await?;
+                Ok(json!({ "path": path_arg, "bytes_appended": content_arg.len() }))
+            }
+            "vfs_delete" => {
+                self.delete(path_arg).await?;
+                Ok(json!({ "path": path_arg, "deleted": true }))
+            }
+            "vfs_list" => {
+                let entries = self.list(path_arg).await?;
+                Ok(json!({
+                    "path": path_arg,
+                    "entries": entries.iter().map(entry_json).collect::<Vec<_>>(),
+                }))
+            }
+            "vfs_exists" => {
+                let exists = self.exists(path_arg).await?;
+                Ok(json!({ "path": path_arg, "exists": exists }))
+            }
+            "vfs_stat" => Ok(entry_json(&self.stat(path_arg).await?)),
+            "vfs_search" => {
+                let query = args.get("query").and_then(Value::as_str).unwrap_or("");
+                let mut options = SearchOptions {
+                    path_prefix: path,
+                    ..Default::default()
+                };
+                if let Some(limit) = args.get("limit").and_then(Value::as_u64) {
+                    options.limit = limit as usize;
+                }
+                let hits = self.search(query, &options).await?;
+                Ok(json!({ "query": query, "results": hits }))
+            }
+            "vfs_mounts" => {
+                let mounts: Vec<Value> = config
+                    .mounts
+                    .iter()
+                    .map(|mount| json!({ "path": mount.path, "read_only": mount.read_only }))
+                    .collect();
+                Ok(json!({ "mounts": mounts }))
+            }
+            other => Err(VfsError::InvalidArgument(format!(
+                "Tool {} cannot be executed",
+                other
+            ))),
+        }
+    }
+}
+
+fn entry_json(entry: &Entry) -> Value {
+    json!({
+        "path": entry.path,
+        "name": entry.name,
+        "is_dir": entry.is_dir,
+        "size": entry.size,
+        "modified": entry.modified.map(|m| m.to_rfc3339()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openfs_config::VfsConfig;
+    use openfs_core::ErrorCode;
+
+    async fn memory_vfs() -> Vfs {
+        let config = VfsConfig::from_yaml(
+            r#"
+name: test
+backends:
+  mem:
+    type: memory
+mounts:
+  - path: /workspace
+    backend: mem
+"#,
+        )
+        .unwrap();
+        Vfs::from_config(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool() {
+        let vfs = memory_vfs().await;
+
+        let written = vfs
+            .execute_tool(
+                "vfs_write",
+                &json!({"path": "/workspace/notes.md", "content": "hello"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(written["bytes_written"], 5);
+
+        let read = vfs
+            .execute_tool("vfs_read", &json!({"path": "/workspace/./notes.md"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            read,
+            json!({"path": "/workspace/notes.md", "content": "hello"})
+        );
+
+        let listed = vfs
+            .execute_tool("vfs_list", &json!({"path": "/workspace"}))
+            .await
+            .unwrap();
+        assert_eq!(listed["entries"][0]["name"], "notes.md");
+
+        let mounts = vfs.execute_tool("vfs_mounts", &Value::Null).await.unwrap();
+        assert_eq!(mounts["mounts"][0]["path"], "/workspace");
+
+        let missing = vfs
+            .execute_tool("vfs_read", &json!({"path": "/workspace/nope"}))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool_rejects_bad_calls() {
+        let vfs = memory_vfs().await;
+
+        let unknown = vfs
+            .execute_tool("vfs_format", &json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(unknown.code(), ErrorCode::InvalidArgument);
+
+        let missing = vfs
+            .execute_tool("vfs_write", &json!({"path": "/workspace/a"}))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), ErrorCode::InvalidArgument);
+        assert!(missing.to_string().contains("'content'"), "{missing}");
+
+        let wrong_type = vfs
+            .execute_tool("vfs_read", &json!({"path": ["/workspace/a"]}))
+            .await
+            .unwrap_err();
+        assert_eq!(wrong_type.code(), ErrorCode::InvalidArgument);
+    }
+}
@@ -0,0 +1,386 @@
+//! Trigram index for pruning grep.
+//!
+//! [`TrigramIndex`] stores, per file, a Bloom filter of the byte trigrams in
+//! its content together with the size, modification time and ETag it was
+//! built from, in a SQLite database. [`TrigramQuery`] works out from a regex
+//! the trigrams any matching line must contain, in the manner of codesearch
+//! and zoekt, so grep only reads the files whose filter may hold them.
+//!
+//! Entries are only trusted while the file's listing still shows the version
+//! they were built from, and grep rebuilds them from the files it reads.
+//! Watch events remove entries as files change.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use openfs_core::{Entry, VfsError};
+use regex_syntax::hir::{Hir, HirKind};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// The index file kept next to the WAL databases.
+pub const TRIGRAM_INDEX_FILE: &str = "trigrams.db";
+
+/// Filter bits per distinct trigram; about 10% false positives with three
+/// hashes.
+const BITS_PER_TRIGRAM: usize = 5;
+const HASHES: u64 = 3;
+
+type Trigram = [u8; 3];
+
+/// The trigrams a line must contain to match a regex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrigramQuery {
+    /// Any line may match.
+    All,
+    Trigram(Trigram),
+    And(Vec<TrigramQuery>),
+    Or(Vec<TrigramQuery>),
+}
+
+impl TrigramQuery {
+    /// The query for `pattern`, or [`TrigramQuery::All`] when it does not
+    /// parse or requires no literal text of three bytes or more.
+    pub fn from_regex(pattern: &str) -> Self {
+        match regex_syntax::parse(pattern) {
+            Ok(hir) => analyze(&hir).query,
+            Err(_) => TrigramQuery::All,
+        }
+    }
+
+    /// Whether a text containing the trigrams `has` accepts may match.
+    pub fn matches(&self, has: &impl Fn(&Trigram) -> bool) -> bool {
+        match self {
+            TrigramQuery::All => true,
+            TrigramQuery::Trigram(trigram) => has(trigram),
+            TrigramQuery::And(queries) => queries.iter().all(|q| q.matches(has)),
+            TrigramQuery::Or(queries) => queries.iter().any(|q| q.matches(has)),
+        }
+    }
+
+    fn and(queries: Vec<TrigramQuery>) -> Self {
+        let mut required: Vec<TrigramQuery> = Vec::new();
+        for query in queries {
+            match query {
+                TrigramQuery::All => {}
+                TrigramQuery::And(inner) => required.extend(inner),
+                query => required.push(query),
+            }
+        }
+        match required.len() {
+            0 => TrigramQuery::All,
+            1 => required.pop().unwrap(),
+            _ => TrigramQuery::And(required),
+        }
+    }
+
+    fn or(queries: Vec<TrigramQuery>) -> Self {
+        if queries.is_empty() || queries.contains(&TrigramQuery::All) {
+            return TrigramQuery::All;
+        }
+        TrigramQuery::Or(queries)
+    }
+
+    fn literal(bytes: &[u8]) -> Self {
+        TrigramQuery::and(
+            bytes
+                .windows(3)
+                .map(|w| TrigramQuery::Trigram([w[0], w[1], w[2]]))
+                .collect(),
+        )
+    }
+}
+
+/// What a regex node requires.
+struct Analysis {
+    /// The exact text it matches, when that is fixed.
+    exact: Option<Vec<u8>>,
+    query: TrigramQuery,
+}
+
+fn analyze(hir: &Hir) -> Analysis {
+    match hir.kind() {
+        // Assertions match no text, so the literals around them still adjoin.
+        HirKind::Empty | HirKind::Look(_) => Analysis {
+            exact: Some(Vec::new()),
+            query: TrigramQuery::All,
+        },
+        HirKind::Literal(literal) => Analysis {
+            exact: Some(literal.0.to_vec()),
+            query: TrigramQuery::literal(&literal.0),
+        },
+        HirKind::Class(_) => Analysis {
+            exact: None,
+            query: TrigramQuery::All,
+        },
+        HirKind::Capture(capture) => analyze(&capture.sub),
+        HirKind::Repetition(repetition) => {
+            let sub = analyze(&repetition.sub);
+            match (repetition.min, repetition.max) {
+                (0, _) => Analysis {
+                    exact: None,
+                    query: TrigramQuery::All,
+                },
+                (1, Some(1)) => sub,
+                _ => Analysis {
+                    exact: None,
+                    query: sub.query,
+                },
+            }
+        }
+        HirKind::Concat(subs) => {
+            let mut required = Vec::new();
+            let mut run: Option<Vec<u8>> = None;
+            let mut exact = Some(Vec::new());
+            for sub in subs.iter().map(analyze) {
+                exact = match (exact, &sub.exact) {
+                    (Some(mut exact), Some(text)) => {
+                        exact.extend_from_slice(text);
+                        Some(exact)
+                    }
+                    _ => None,
+                };
+                match &sub.exact {
+                    Some(text) => run.get_or_insert_with(Vec::new).extend_from_slice(text),
+                    None => {
+                        if let Some(run) = run.take() {
+                            required.push(TrigramQuery::literal(&run));
+                        }
+                        required.push(sub.query);
+                    }
+                }
+            }
+            if let Some(run) = run {
+                required.push(TrigramQuery::literal(&run));
+            }
+            Analysis {
+                exact,
+                query: TrigramQuery::and(required),
+            }
+        }
+        HirKind::Alternation(subs) => Analysis {
+            exact: None,
+            query: TrigramQuery::or(subs.iter().map(|sub| analyze(sub).query).collect()),
+        },
+    }
+}
+
+/// A set of trigrams, with false positives.
+struct Bloom(Vec<u8>);
+
+impl Bloom {
+    fn of(content: &[u8]) -> Self {
+        let trigrams: HashSet<Trigram> = content.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+        let bits = (trigrams.len() * BITS_PER_TRIGRAM)
+            .next_power_of_two()
+            .max(64);
+        let mut bloom = Bloom(vec![0; bits / 8]);
+        for trigram in &trigrams {
+            for bit in bloom.bits(trigram) {
+                bloom.0[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        bloom
+    }
+
+    fn contains(&self, trigram: &Trigram) -> bool {
+        self.bits(trigram)
+            .all(|bit| self.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn bits(&self, trigram: &Trigram) -> impl Iterator<Item = usize> {
+        // FNV-1a, split into two hashes for double hashing.
+        let hash = trigram.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let len = self.0.len() as u64 * 8;
+        (0..HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+}
+
+/// Per-file trigram filters, persisted in SQLite.
+pub struct TrigramIndex {
+    conn: Mutex<Connection>,
+}
+
+impl TrigramIndex {
+    /// Open or create the index at `path`.
+    pub fn open(path: &Path) -> Result<Self, VfsError> {
+        let conn = Connection::open(path).map_err(index_error)?;
+        Self::init(conn)
+    }
+
+    /// An index that lasts as long as the value.
+    pub fn in_memory() -> Result<Self, VfsError> {
+        Self::init(Connection::open_in_memory().map_err(index_error)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, VfsError> {
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;
+            CREATE TABLE IF NOT EXISTS trigram_files (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified INTEGER,
+                etag TEXT,
+                bloom BLOB NOT NULL
+            );",
+        )
+        .map_err(index_error)?;
+        Ok(TrigramIndex {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// `.openfs/trigrams.db`, or [`TRIGRAM_INDEX_FILE`] in `OPENFS_WAL_DIR`.
+    pub fn default_path() -> Result<PathBuf, VfsError> {
+        Ok(crate::vfs::wal_dir()?.join(TRIGRAM_INDEX_FILE))
+    }
+
+    /// Whether the file at `path`, listed as `entry`, may have lines matching
+    /// `query`. Files not indexed at their listed version may.
+    pub fn may_match(&self, path: &str, entry: &Entry, query: &TrigramQuery) -> bool {
+        if *query == TrigramQuery::All || !has_version(entry) {
+            return true;
+        }
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let row = conn
+            .query_row(
+                "SELECT bloom FROM trigram_files
+                 WHERE path = ?1 AND size = ?2 AND modified IS ?3 AND etag IS ?4",
+                params![path, size(entry), modified(entry), entry.etag],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional();
+        match row {
+            Ok(Some(bloom)) if !bloom.is_empty() => {
+                let bloom = Bloom(bloom);
+                query.matches(&|trigram| bloom.contains(trigram))
+            }
+            _ => true,
+        }
+    }
+
+    /// Index `content` as the file at `path`, listed as `entry`.
+    ///
+    /// Listings without a modification time or ETag can't tell later
+    /// versions apart, so such files are not indexed.
+    pub fn update(&self, path: &str, entry: &Entry, content: &[u8]) -> Result<(), VfsError> {
+        if !has_version(entry) {
+            return Ok(());
+        }
+        let bloom = Bloom::of(content);
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT OR REPLACE INTO trigram_files (path, size, modified, etag, bloom)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![path, size(entry), modified(entry), entry.etag, bloom.0],
+        )
+        .map_err(index_error)?;
+        Ok(())
+    }
+
+    /// Forget `path` and everything below it, returning how many files were
+    /// forgotten.
+    pub fn invalidate(&self, path: &str) -> Result<usize, VfsError> {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "DELETE FROM trigram_files WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+            params![path, prefix],
+        )
+        .map_err(index_error)
+    }
+
+    /// The number of files indexed.
+    pub fn len(&self) -> usize {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.query_row("SELECT COUNT(*) FROM trigram_files", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_or(0, |count| count as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn has_version(entry: &Entry) -> bool {
+    entry.modified.is_some() || entry.etag.is_some()
+}
+
+fn size(entry: &Entry) -> i64 {
+    entry.size.unwrap_or(0) as i64
+}
+
+fn modified(entry: &Entry) -> Option<i64> {
+    entry.modified.map(|m| m.timestamp_millis())
+}
+
+fn index_error(e: rusqlite::Error) -> VfsError {
+    VfsError::Indexing(format!("trigram index: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn trigram(s: &str) -> TrigramQuery {
+        let b = s.as_bytes();
+        TrigramQuery::Trigram([b[0], b[1], b[2]])
+    }
+
+    #[test]
+    fn test_query_from_regex() {
+        assert_eq!(
+            TrigramQuery::from_regex("hello"),
+            TrigramQuery::And(vec![trigram("hel"), trigram("ell"), trigram("llo")])
+        );
+        assert_eq!(
+            TrigramQuery::from_regex(r"^fn (main|test)\b"),
+            TrigramQuery::And(vec![
+                trigram("fn "),
+                TrigramQuery::Or(vec![
+                    TrigramQuery::And(vec![trigram("mai"), trigram("ain")]),
+                    TrigramQuery::And(vec![trigram("tes"), trigram("est")]),
+                ]),
+            ])
+        );
+        assert_eq!(
+            TrigramQuery::from_regex("abc.*xyz"),
+            TrigramQuery::And(vec![trigram("abc"), trigram("xyz")])
+        );
+        assert_eq!(TrigramQuery::from_regex("ab|cde"), TrigramQuery::All);
+        assert_eq!(TrigramQuery::from_regex("(?i)hello"), TrigramQuery::All);
+        assert_eq!(TrigramQuery::from_regex("(abc)?"), TrigramQuery::All);
+        assert_eq!(TrigramQuery::from_regex("[invalid"), TrigramQuery::All);
+    }
+
+    #[test]
+    fn test_index_prunes_current_entries() {
+        let index = TrigramIndex::in_memory().unwrap();
+        let entry = Entry::file("a.rs".to_string(), "a.rs".to_string(), 12, Some(Utc::now()));
+        let hello = TrigramQuery::from_regex("hello");
+        let absent = TrigramQuery::from_regex("goodbye");
+
+        assert!(index.may_match("/w/a.rs", &entry, &absent));
+        index.update("/w/a.rs", &entry, b"hello world\n").unwrap();
+        assert_eq!(index.len(), 1);
+        assert!(index.may_match("/w/a.rs", &entry, &hello));
+        assert!(!index.may_match("/w/a.rs", &entry, &absent));
+
+        // A newer version of the file is not judged by the old filter.
+        let changed = Entry {
+            size: Some(14),
+            ..entry.clone()
+        };
+        assert!(index.may_match("/w/a.rs", &changed, &absent));
+
+        assert_eq!(index.invalidate("/w").unwrap(), 1);
+        assert!(index.may_match("/w/a.rs", &entry, &absent));
+        assert!(index.is_empty());
+    }
+}
@@ -1,20 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::StreamExt;
 use openfs_config::{
-    BackendConfig, DefaultsConfig, MountMode, SyncConfig as MountSyncConfig, VfsConfig, WriteMode,
+    BackendConfig, CacheEviction, DefaultsConfig, MountCacheConfig, MountMode, PathPolicyConfig,
+    RetryConfig, SyncConfig as MountSyncConfig, VfsConfig, WriteMode,
 };
 use tracing::{debug, info, instrument, warn};
 
 use crate::backends;
 use crate::cached_backend::CachedBackend;
 use crate::chroma_http::ChromaHttpBackend;
+use crate::conflict::{ConflictResolution, ConflictTracker, SyncConflict};
+use crate::disk_cache::DiskCacheStats;
+use crate::history::{HistoryBackend, HISTORY_ROOT};
+use crate::log_file::LogBackend;
+use crate::prefetch::{self, PrefetchGlob};
+use crate::pull::{PullSummary, RemoteChangeEvent};
+use crate::replica::ReplicatedBackend;
+use crate::retry::{CircuitStatus, RetryBackend};
 use crate::router::{Mount, Router};
-use crate::sync::{SyncConfig, SyncMode};
+use crate::snapshot::{
+    HistoryPoint, RestoreSummary, SnapshotDiff, SnapshotFile, SnapshotInfo, SnapshotManifest,
+    SnapshotStore,
+};
+use crate::sync::{PathSyncStatus, SyncConfig, SyncMode, SyncStats};
+use crate::tags::{TagBackend, TAGS_ROOT, TAG_KEY_PREFIX, TAG_VALUE};
+use crate::view::ViewBackend;
 use crate::wal::{OutboxEntry, OutboxStatus, WalConfig, WalOpType, WriteAheadLog};
-use openfs_core::{Backend, BackendError, CacheConfig, Entry, VfsError};
+use openfs_core::{
+    apply_patch, detect_content_type, is_binary_content, patch_creates_file, str_replace, Backend,
+    BackendError, CacheConfig, Entry, EvictionPolicy, FileMeta, PatchError, PatchSummary,
+    PathSanitizer, SearchHit, SearchOptions, Searcher, UploadedPart, VfsError, SNIFF_LEN,
+};
+use serde::de::DeserializeOwned;
 
 /// Wrapper to hold `Arc<dyn Backend>` as a concrete type for `CachedBackend<B>`.
 #[derive(Clone)]
@@ -31,6 +54,14 @@ impl Backend for DynBackend {
     ) -> Result<(Vec<u8>, Option<String>), BackendError> {
         self.0.read_with_cas_token(path).await
     }
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        self.0.read_range(path, offset, len).await
+    }
     async fn write(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         self.0.write(path, content).await
     }
@@ -42,6 +73,19 @@ impl Backend for DynBackend {
     ) -> Result<Option<String>, BackendError> {
         self.0.compare_and_swap(path, expected, content).await
     }
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        self.0.create_if_absent(path, content).await
+    }
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        self.0.read_meta(path).await
+    }
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        self.0.write_meta(path, meta).await
+    }
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         self.0.append(path, content).await
     }
@@ -57,6 +101,36 @@ impl Backend for DynBackend {
     async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
         self.0.stat(path).await
     }
+    async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, BackendError> {
+        self.0.set_modified(path, modified).await
+    }
+    async fn begin_multipart(&self, path: &str) -> Result<Option<String>, BackendError> {
+        self.0.begin_multipart(path).await
+    }
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        number: u32,
+        content: &[u8],
+    ) -> Result<UploadedPart, BackendError> {
+        self.0.upload_part(path, upload_id, number, content).await
+    }
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), BackendError> {
+        self.0.complete_multipart(path, upload_id, parts).await
+    }
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<(), BackendError> {
+        self.0.abort_multipart(path, upload_id).await
+    }
     async fn rename(&self, from: &str, to: &str) -> Result<(), BackendError> {
         self.0.rename(from, to).await
     }
@@ -70,10 +144,151 @@ fn cache_config_for_mode(mode: MountMode) -> CacheConfig {
             | MountMode::WriteBack
             | MountMode::RemoteCached
             | MountMode::PullMirror
+            | MountMode::TwoWay
     );
     config
 }
 
+/// A mount's cache settings, else the defaults.
+fn cache_settings<'a>(
+    mount: &'a openfs_config::MountConfig,
+    defaults: Option<&'a DefaultsConfig>,
+) -> Option<&'a MountCacheConfig> {
+    mount
+        .cache
+        .as_ref()
+        .or_else(|| defaults.and_then(|d| d.cache.as_ref()))
+}
+
+/// The memory cache for a mount: whether it caches follows the mode, its
+/// size and eviction policy follow the cache settings.
+fn cache_config_for_mount(
+    mode: MountMode,
+    mount: &openfs_config::MountConfig,
+    defaults: Option<&DefaultsConfig>,
+) -> CacheConfig {
+    let mut config = cache_config_for_mode(mode);
+    if let Some(cache) = cache_settings(mount, defaults) {
+        if let Some(max_size) = &cache.max_size {
+            config.max_size = usize::try_from(max_size.as_bytes()).unwrap_or(usize::MAX);
+        }
+        if let Some(eviction) = cache.eviction {
+            config.eviction = match eviction {
+                CacheEviction::TinyLfu => EvictionPolicy::TinyLfu,
+                CacheEviction::Lru => EvictionPolicy::Lru,
+            };
+        }
+    }
+    config.disk = disk_cache_for_mount(mount, defaults);
+    config
+}
+
+/// The disk cache for a mount (its own settings, else the defaults), in a
+/// subdirectory named after the mount.
+fn disk_cache_for_mount(
+    mount: &openfs_config::MountConfig,
+    defaults: Option<&DefaultsConfig>,
+) -> Option<openfs_core::DiskCacheConfig> {
+    let disk = cache_settings(mount, defaults)?.disk.as_ref()?;
+    Some(openfs_core::DiskCacheConfig {
+        dir: PathBuf::from(&disk.dir).join(sanitize_mount_for_filename(&mount.path)),
+        max_size: disk.max_size.as_bytes(),
+        ttl: disk.ttl.as_duration(),
+    })
+}
+
+/// Globs of files to warm when the mount starts (its own settings, else the
+/// defaults), relative to the mount.
+fn warm_globs_for_mount(
+    mount: &openfs_config::MountConfig,
+    defaults: Option<&DefaultsConfig>,
+) -> Vec<PrefetchGlob> {
+    let Some(cache) = cache_settings(mount, defaults) else {
+        return Vec::new();
+    };
+    cache
+        .warm
+        .iter()
+        .filter_map(
+            |pattern| match PrefetchGlob::new(pattern.trim_start_matches('/')) {
+                Ok(glob) => Some(glob),
+                Err(e) => {
+                    warn!("Skipping cache warm pattern for {}: {}", mount.path, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Read the files matched by `globs` through `backend`, filling its caches.
+async fn warm_mount(
+    mount_path: String,
+    backend: Arc<CachedBackend<DynBackend>>,
+    globs: Vec<PrefetchGlob>,
+) {
+    let files = match prefetch::matching_files(backend.as_ref(), &globs).await {
+        Ok(files) => files,
+        Err(e) => {
+            warn!("Cache warm for {} failed to list files: {}", mount_path, e);
+            return;
+        }
+    };
+    // Owned futures: the task must not borrow across the spawn boundary.
+    let reads = files.into_iter().map(|path| {
+        let backend = backend.clone();
+        async move { backend.read(&path).await }
+    });
+    let (warmed, failed) = prefetch::read_all(reads).await;
+    info!(
+        "Warmed cache for {}: {} file(s), {} failed",
+        mount_path, warmed, failed
+    );
+}
+
+/// Pull remote changes into a mount's cache every `interval`, publishing
+/// the changed files. The first pull, right away, records what the remote
+/// holds.
+async fn pull_periodically(
+    mount_path: String,
+    backend: Arc<CachedBackend<DynBackend>>,
+    interval: std::time::Duration,
+    remote_changes: tokio::sync::broadcast::Sender<RemoteChangeEvent>,
+) {
+    // A zero interval would make `interval` panic.
+    let mut ticker = tokio::time::interval(interval.max(std::time::Duration::from_secs(1)));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        match backend.pull().await {
+            Ok(summary) if !summary.changes.is_empty() => {
+                debug!(
+                    "Pulled {} remote change(s) into {}: {} refreshed, {} dropped, {} deferred",
+                    summary.changes.changed.len() + summary.changes.deleted.len(),
+                    mount_path,
+                    summary.refreshed,
+                    summary.dropped,
+                    summary.deferred
+                );
+                publish_remote_changes(&remote_changes, &mount_path, &summary);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Pull of remote changes into {} failed: {}", mount_path, e),
+        }
+    }
+}
+
+/// Send one event per changed file. Nobody listening is not an error.
+fn publish_remote_changes(
+    remote_changes: &tokio::sync::broadcast::Sender<RemoteChangeEvent>,
+    mount_path: &str,
+    summary: &PullSummary,
+) {
+    for event in summary.changes.events(mount_path) {
+        let _ = remote_changes.send(event);
+    }
+}
+
 fn sync_config_for_mount(
     mode: MountMode,
     mount_sync: Option<&MountSyncConfig>,
@@ -85,6 +300,7 @@ fn sync_config_for_mount(
         MountMode::WriteThrough => SyncMode::WriteThrough,
         MountMode::WriteBack => SyncMode::WriteBack,
         MountMode::PullMirror => SyncMode::PullMirror,
+        MountMode::TwoWay => SyncMode::TwoWay,
         MountMode::RemoteCached => SyncMode::WriteThrough,
         _ => SyncMode::None,
     };
@@ -98,25 +314,89 @@ fn sync_config_for_mount(
         if let Some(interval) = sync_cfg.interval.as_ref() {
             config.flush_interval = interval.as_duration();
         }
+        config.conflict = sync_cfg.conflict;
+        if let Some(interval) = sync_cfg.pull_interval.as_ref() {
+            config.pull_interval = interval.as_duration();
+        }
+        if let Some(concurrency) = sync_cfg.flush_concurrency {
+            config.flush_concurrency = concurrency;
+        }
+        if let Some(threshold) = sync_cfg.multipart_threshold.as_ref() {
+            config.multipart_threshold = threshold.as_bytes();
+        }
+        if let Some(part_size) = sync_cfg.multipart_part_size.as_ref() {
+            config.multipart_part_size = part_size.as_bytes();
+        }
     }
 
+    let retry = sync_override
+        .and_then(|s| s.retry.clone())
+        .unwrap_or_default();
+    apply_retry_config(&mut config, &retry);
+
     config.mode = sync_mode;
     config
 }
 
-fn wal_dir() -> Result<PathBuf, VfsError> {
+fn apply_retry_config(config: &mut SyncConfig, retry: &RetryConfig) {
+    config.max_retries = retry.max_attempts;
+    config.retry_backoff = retry.backoff.as_duration();
+    config.max_backoff = retry.max_backoff.as_duration();
+    config.backoff_strategy = retry.strategy;
+    config.jitter = retry.jitter;
+}
+
+/// The durable outbox retries on the mount's schedule, at whole seconds.
+fn wal_config_for_sync(sync: &SyncConfig) -> WalConfig {
+    WalConfig {
+        max_retries: sync.max_retries,
+        base_backoff_secs: sync.retry_backoff.as_secs().max(1),
+        max_backoff_secs: sync.max_backoff.as_secs().max(1),
+        backoff_strategy: sync.backoff_strategy,
+        jitter: sync.jitter,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn wal_dir() -> Result<PathBuf, VfsError> {
     if let Ok(path) = std::env::var("OPENFS_WAL_DIR") {
         let path = PathBuf::from(path);
         std::fs::create_dir_all(&path).map_err(VfsError::from)?;
         return Ok(path);
     }
 
+    // Unit tests keep their WAL databases out of the source tree.
+    #[cfg(test)]
+    let cwd = tests::wal_root();
+    #[cfg(not(test))]
     let cwd = std::env::current_dir().map_err(VfsError::from)?;
     let dir = cwd.join(".openfs");
     std::fs::create_dir_all(&dir).map_err(VfsError::from)?;
     Ok(dir)
 }
 
+/// Snapshots are kept under `OPENFS_SNAPSHOT_DIR`, or else next to the WAL
+/// databases.
+fn default_snapshot_dir() -> Result<PathBuf, VfsError> {
+    match std::env::var("OPENFS_SNAPSHOT_DIR") {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(wal_dir()?.join("snapshots")),
+    }
+}
+
+/// The snapshot directory set with [`Vfs::set_snapshot_dir`], shared with the
+/// `/.history` tree.
+pub(crate) type SnapshotDir = Arc<std::sync::RwLock<Option<PathBuf>>>;
+
+pub(crate) fn snapshot_store_in(dir: &SnapshotDir) -> Result<SnapshotStore, VfsError> {
+    let configured = dir.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let dir = match configured {
+        Some(dir) => dir,
+        None => default_snapshot_dir()?,
+    };
+    Ok(SnapshotStore::new(dir))
+}
+
 fn sanitize_mount_for_filename(mount_path: &str) -> String {
     let trimmed = mount_path.trim_matches('/');
     if trimmed.is_empty() {
@@ -128,6 +408,51 @@ fn sanitize_mount_for_filename(mount_path: &str) -> String {
         .collect()
 }
 
+fn find_snapshot(
+    store: &SnapshotStore,
+    mount: &str,
+    name: &str,
+) -> Result<SnapshotManifest, VfsError> {
+    store
+        .find(mount, name)?
+        .ok_or_else(|| VfsError::NotFound(format!("snapshot '{}' of {}", name, mount)))
+}
+
+/// Whether `path` equals `base` or lies below it.
+pub(crate) fn is_same_or_under(path: &str, base: &str) -> bool {
+    let base = base.trim_end_matches('/');
+    base.is_empty()
+        || path == base
+        || path
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn validate_meta_key(key: &str) -> Result<(), VfsError> {
+    let valid = !key.is_empty()
+        && key.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'-' | b'_' | b'.')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(VfsError::Config(format!(
+            "invalid metadata key '{}': use lowercase letters, digits, '-', '_' and '.'",
+            key
+        )))
+    }
+}
+
+fn validate_tag(tag: &str) -> Result<(), VfsError> {
+    if tag.is_empty() || tag.starts_with('.') {
+        return Err(VfsError::Config(format!(
+            "invalid tag '{}': use lowercase letters, digits, '-', '_' and '.'",
+            tag
+        )));
+    }
+    validate_meta_key(&format!("{}{}", TAG_KEY_PREFIX, tag))
+}
+
 fn wal_path_for_mount(mount_path: &str) -> Result<PathBuf, VfsError> {
     Ok(wal_dir()?.join(format!(
         "wal_{}.db",
@@ -138,16 +463,31 @@ fn wal_path_for_mount(mount_path: &str) -> Result<PathBuf, VfsError> {
 async fn apply_outbox_entry(
     backend: Arc<dyn Backend>,
     entry: &OutboxEntry,
+    conflicts: &ConflictTracker,
 ) -> Result<(), VfsError> {
     match entry.op_type {
         WalOpType::Write => {
             let content = entry.content.clone().ok_or_else(|| {
                 VfsError::Config(format!("Outbox write entry {} missing content", entry.id))
             })?;
-            backend
-                .write(&entry.path, &content)
-                .await
-                .map_err(VfsError::from)
+            match &entry.base_token {
+                // Check the write against the version it was based on, as
+                // the flush would have before the restart.
+                Some(base) => {
+                    if !conflicts.has_base(&entry.path) {
+                        conflicts.restore(&entry.path, base.clone());
+                    }
+                    conflicts
+                        .flush(backend.as_ref(), &entry.path, &content)
+                        .await
+                        .map(|_| ())
+                        .map_err(VfsError::from)
+                }
+                None => backend
+                    .write(&entry.path, &content)
+                    .await
+                    .map_err(VfsError::from),
+            }
         }
         WalOpType::Append => {
             let content = entry.content.clone().ok_or_else(|| {
@@ -169,6 +509,7 @@ async fn apply_outbox_entry(
 async fn replay_outbox_entries(
     wal: &WriteAheadLog,
     backend: Arc<dyn Backend>,
+    conflicts: &ConflictTracker,
 ) -> Result<usize, VfsError> {
     let mut applied = 0usize;
 
@@ -190,7 +531,7 @@ async fn replay_outbox_entries(
                 VfsError::Config(format!("Failed to mark outbox processing: {}", e))
             })?;
 
-            match apply_outbox_entry(backend.clone(), &entry).await {
+            match apply_outbox_entry(backend.clone(), &entry, conflicts).await {
                 Ok(()) => {
                     wal.complete_outbox(entry.id).map_err(|e| {
                         VfsError::Config(format!("Failed to complete outbox entry: {}", e))
@@ -198,7 +539,11 @@ async fn replay_outbox_entries(
                     applied += 1;
                 }
                 Err(e) => {
-                    let _ = wal.fail_outbox(entry.id, &e.to_string());
+                    let _ = if e.is_transient() {
+                        wal.fail_outbox_after(entry.id, &e.to_string(), e.retry_after())
+                    } else {
+                        wal.dead_letter(entry.id, &e.to_string())
+                    };
                     warn!("Outbox replay failed for {}: {}", entry.path, e);
                 }
             }
@@ -291,11 +636,138 @@ async fn create_backend(
     }
 }
 
+/// The backend instance a mount is configured to use.
+fn mount_backend(
+    backends: &HashMap<String, Arc<dyn Backend>>,
+    mount_config: &openfs_config::MountConfig,
+) -> Result<Arc<dyn Backend>, VfsError> {
+    let backend_name = mount_config.backend.as_ref().ok_or_else(|| {
+        VfsError::Config(format!(
+            "Mount '{}' has no backend specified",
+            mount_config.path
+        ))
+    })?;
+    backends.get(backend_name).cloned().ok_or_else(|| {
+        VfsError::Config(format!(
+            "Backend '{}' not found for mount '{}'",
+            backend_name, mount_config.path
+        ))
+    })
+}
+
+/// Backend instances paired with their names.
+type NamedBackends = Vec<(String, Arc<dyn Backend>)>;
+
+/// The backends `mount_config` reads from besides its own, by name.
+fn replica_backends(
+    backends: &HashMap<String, Arc<dyn Backend>>,
+    mount_config: &openfs_config::MountConfig,
+) -> Result<NamedBackends, VfsError> {
+    let names = mount_config.replicas.iter().flat_map(|r| &r.backends);
+    names
+        .map(|name| {
+            let backend = backends.get(name).cloned().ok_or_else(|| {
+                VfsError::Config(format!(
+                    "Replica backend '{}' not found for mount '{}'",
+                    name, mount_config.path
+                ))
+            })?;
+            Ok((name.clone(), backend))
+        })
+        .collect()
+}
+
+/// Remote change events buffered per subscriber, see [`Vfs::subscribe_remote_changes`].
+pub const REMOTE_CHANGE_CAPACITY: usize = 1024;
+
 /// The main VFS struct that coordinates backends and routing.
 pub struct Vfs {
-    config: VfsConfig,
+    /// The mount table. [`Vfs::reload`] swaps it whole; operations take a
+    /// clone of the `Arc` up front, so one in flight during a reload finishes
+    /// against the mounts it started with.
+    state: std::sync::RwLock<Arc<VfsState>>,
+    /// Serializes reloads.
+    reload_lock: tokio::sync::Mutex<()>,
+    /// Searchers registered per mount path, see [`Vfs::set_searcher`].
+    searchers: std::sync::RwLock<Vec<(String, Arc<dyn Searcher>)>>,
+    /// Files found changed by pulls, see [`Vfs::subscribe_remote_changes`].
+    remote_changes: tokio::sync::broadcast::Sender<RemoteChangeEvent>,
+    /// Where snapshots are kept, see [`Vfs::set_snapshot_dir`].
+    snapshot_dir: SnapshotDir,
+    /// Live session workspaces, see [`Vfs::create_session`].
+    pub(crate) sessions: tokio::sync::Mutex<Vec<crate::session::Session>>,
+}
+
+struct VfsState {
+    config: Arc<VfsConfig>,
     router: Router,
-    mount_runtimes: Vec<MountRuntime>,
+    mount_runtimes: Vec<Arc<MountRuntime>>,
+    /// Snapshot contents for [`Vfs::read_at`], and the `/.history` tree
+    /// unless a mount or view is in its place.
+    history: Arc<HistoryBackend>,
+}
+
+impl VfsState {
+    fn new(
+        config: VfsConfig,
+        mount_runtimes: Vec<Arc<MountRuntime>>,
+        snapshot_dir: &SnapshotDir,
+    ) -> Result<Self, VfsError> {
+        let mounts = || {
+            mount_runtimes
+                .iter()
+                .map(|runtime| Mount {
+                    path: runtime.mount_path.clone(),
+                    backend: runtime.cached_backend.clone(),
+                    read_only: runtime.read_only,
+                    path_policy: runtime.path_policy.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+        // Views read through the mounts' caches, so they see pending writes.
+        let mut routes = mounts();
+        for view in &config.views {
+            routes.push(Mount {
+                path: view.path.trim_end_matches('/').to_string(),
+                backend: Arc::new(ViewBackend::new(view, mounts())?),
+                read_only: true,
+                path_policy: PathPolicyConfig::default(),
+            });
+        }
+        let history = Arc::new(HistoryBackend::new(
+            mount_runtimes.iter().map(|r| r.mount_path.clone()),
+            Arc::clone(snapshot_dir),
+        ));
+        if !routes
+            .iter()
+            .any(|route| is_same_or_under(&route.path, HISTORY_ROOT))
+        {
+            routes.push(Mount {
+                path: HISTORY_ROOT.to_string(),
+                backend: history.clone(),
+                read_only: true,
+                path_policy: PathPolicyConfig::default(),
+            });
+        }
+        if !routes
+            .iter()
+            .any(|route| is_same_or_under(&route.path, TAGS_ROOT))
+        {
+            routes.push(Mount {
+                path: TAGS_ROOT.to_string(),
+                backend: Arc::new(TagBackend::new(mounts())),
+                read_only: true,
+                path_policy: PathPolicyConfig::default(),
+            });
+        }
+        let sanitizer = PathSanitizer::from_config(&config);
+        Ok(VfsState {
+            config: Arc::new(config),
+            router: Router::new(routes).with_sanitizer(sanitizer),
+            mount_runtimes,
+            history,
+        })
+    }
 }
 
 struct MountRuntime {
@@ -303,8 +775,353 @@ struct MountRuntime {
     backend_name: String,
     sync_mode: SyncMode,
     read_only: bool,
+    path_policy: PathPolicyConfig,
+    /// The backend instance as created, before any wrapping. Reload reuses
+    /// it for other mounts on the same backend.
+    origin: Arc<dyn Backend>,
     backend: Arc<dyn Backend>,
     cached_backend: Arc<CachedBackend<DynBackend>>,
+    /// The retrying wrapper of the backend, if the mount retries requests.
+    retry_backend: Option<Arc<RetryBackend>>,
+    /// The replicating wrapper of the backend, if the mount has replicas.
+    replicated: Option<Arc<ReplicatedBackend>>,
+    /// The settings the runtime was built from, see [`runtime_fingerprint`].
+    fingerprint: serde_json::Value,
+    /// Background cache warming started with the mount, if configured.
+    warm_task: Option<tokio::task::JoinHandle<()>>,
+    /// Periodic pull of remote changes, for pull-mirror and two-way mounts.
+    pull_task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for MountRuntime {
+    fn drop(&mut self) {
+        for task in [&self.warm_task, &self.pull_task].into_iter().flatten() {
+            task.abort();
+        }
+    }
+}
+
+impl MountRuntime {
+    /// Stop background sync and replay the durable outbox, so nothing written
+    /// through this runtime is left behind. Returns whether it was write-back.
+    async fn flush(&self) -> Result<bool, VfsError> {
+        if let Some(replicated) = &self.replicated {
+            replicated.flush().await;
+        }
+        if !self.sync_mode.is_write_back() {
+            return Ok(false);
+        }
+
+        self.cached_backend.shutdown_sync().await;
+
+        if let Some(wal) = self.cached_backend.wal() {
+            let replayed = replay_outbox_entries(
+                wal.as_ref(),
+                self.backend.clone(),
+                self.cached_backend.conflicts(),
+            )
+            .await?;
+            if replayed > 0 {
+                info!(
+                    "Replayed {} outbox operation(s) during flush for mount {}",
+                    replayed, self.mount_path
+                );
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Mounts added, removed and changed by [`Vfs::reload`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Mounts whose settings changed. Their backend and cache are rebuilt
+    /// only if a backend, mode, read-only or sync setting changed.
+    pub updated: Vec<String>,
+}
+
+impl ReloadSummary {
+    /// Whether the reload changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// The settings a mount's runtime is built from. Two mounts with the same
+/// fingerprint can share a runtime; other settings (indexing, watch, the
+/// collection) are read from the config as needed.
+fn runtime_fingerprint(
+    config: &VfsConfig,
+    mount: &openfs_config::MountConfig,
+) -> serde_json::Value {
+    let backend = mount
+        .backend
+        .as_ref()
+        .and_then(|name| config.backends.get(name));
+    serde_json::json!({
+        "backend": mount.backend,
+        "backend_config": backend,
+        "mode": mount.mode,
+        "read_only": mount.read_only,
+        "sync": mount.sync,
+        "cache": mount.cache,
+        "log": mount.log,
+        "path_policy": mount.path_policy,
+        "retry": mount.retry,
+        "replicas": mount.replicas,
+        "replica_configs": mount
+            .replicas
+            .iter()
+            .flat_map(|r| &r.backends)
+            .map(|name| config.backends.get(name))
+            .collect::<Vec<_>>(),
+        "defaults": config.defaults,
+    })
+}
+
+/// Build the runtime (cache, sync and WAL) for one mount.
+async fn build_mount_runtime(
+    config: &VfsConfig,
+    mount_config: &openfs_config::MountConfig,
+    raw_backend: Arc<dyn Backend>,
+    replicas: NamedBackends,
+    remote_changes: &tokio::sync::broadcast::Sender<RemoteChangeEvent>,
+) -> Result<MountRuntime, VfsError> {
+    let backend_name = mount_config.backend.clone().unwrap_or_default();
+    let origin = raw_backend.clone();
+    let retry_backend = mount_config
+        .retry
+        .as_ref()
+        .map(|retry| Arc::new(RetryBackend::new(raw_backend.clone(), &backend_name, retry)));
+    let raw_backend: Arc<dyn Backend> = match &retry_backend {
+        Some(backend) => backend.clone(),
+        None => raw_backend,
+    };
+    let mount_mode = mount_config.mode.unwrap_or(MountMode::LocalIndexed);
+    let sync_config = sync_config_for_mount(
+        mount_mode,
+        mount_config.sync.as_ref(),
+        config.defaults.as_ref(),
+    );
+    let replicated = match &mount_config.replicas {
+        Some(replicas_config) if !replicas.is_empty() => Some(Arc::new(
+            ReplicatedBackend::new(
+                raw_backend.clone(),
+                replicas,
+                replicas_config
+                    .hedge_after
+                    .as_ref()
+                    .map(|hedge| hedge.as_duration()),
+                sync_config.clone(),
+            )
+            .await,
+        )),
+        _ => None,
+    };
+    let raw_backend: Arc<dyn Backend> = match &replicated {
+        Some(backend) => backend.clone(),
+        None => raw_backend,
+    };
+    let mut log_paths = None;
+    let raw_backend: Arc<dyn Backend> = match &mount_config.log {
+        Some(log) => {
+            let backend = LogBackend::new(raw_backend, log)?;
+            log_paths = Some(backend.paths().clone());
+            Arc::new(backend)
+        }
+        None => raw_backend,
+    };
+    let read_only = mount_config.read_only || mount_mode == MountMode::PullMirror;
+    let mut cache_config =
+        cache_config_for_mount(mount_mode, mount_config, config.defaults.as_ref());
+    if sync_config.mode.is_write_back() {
+        cache_config.enabled = true;
+    }
+    let caches_reads = cache_config.enabled;
+
+    let cached_backend = if sync_config.mode.is_write_back() {
+        let wal_path = wal_path_for_mount(&mount_config.path)?;
+        let wal = Arc::new(
+            WriteAheadLog::new(&wal_path, wal_config_for_sync(&sync_config)).map_err(|e| {
+                VfsError::Config(format!(
+                    "Failed to initialize WAL for mount '{}': {}",
+                    mount_config.path, e
+                ))
+            })?,
+        );
+
+        let cached_backend = CachedBackend::new_with_wal(
+            DynBackend(raw_backend.clone()),
+            cache_config,
+            sync_config.clone(),
+            read_only,
+            Arc::clone(&wal),
+        );
+        let recovered = replay_outbox_entries(
+            wal.as_ref(),
+            raw_backend.clone(),
+            cached_backend.conflicts(),
+        )
+        .await?;
+        if recovered > 0 {
+            info!(
+                "Recovered {} outbox operation(s) for mount {}",
+                recovered, mount_config.path
+            );
+        }
+        cached_backend
+    } else {
+        CachedBackend::new(
+            DynBackend(raw_backend.clone()),
+            cache_config,
+            sync_config.clone(),
+            read_only,
+        )
+    };
+    let cached_backend = Arc::new(match log_paths {
+        Some(paths) => cached_backend.with_log_paths(paths),
+        None => cached_backend,
+    });
+
+    if sync_config.mode.is_write_back() {
+        cached_backend.start_write_back().await;
+    }
+    let pull_task = sync_config.mode.pulls_remote_changes().then(|| {
+        tokio::spawn(pull_periodically(
+            mount_config.path.clone(),
+            cached_backend.clone(),
+            sync_config.pull_interval,
+            remote_changes.clone(),
+        ))
+    });
+
+    let warm_globs = warm_globs_for_mount(mount_config, config.defaults.as_ref());
+    let warm_task = if warm_globs.is_empty() {
+        None
+    } else if !caches_reads {
+        debug!(
+            "Mount {} does not cache reads; skipping cache warm",
+            mount_config.path
+        );
+        None
+    } else {
+        Some(tokio::spawn(warm_mount(
+            mount_config.path.clone(),
+            cached_backend.clone(),
+            warm_globs,
+        )))
+    };
+
+    Ok(MountRuntime {
+        mount_path: mount_config.path.clone(),
+        backend_name,
+        sync_mode: sync_config.mode,
+        read_only,
+        path_policy: mount_config.path_policy.clone().unwrap_or_default(),
+        origin,
+        backend: raw_backend,
+        cached_backend,
+        retry_backend,
+        replicated,
+        fingerprint: runtime_fingerprint(config, mount_config),
+        warm_task,
+        pull_task,
+    })
+}
+
+/// Lines to select with [`Vfs::read_lines`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineRange {
+    /// The first `n` lines.
+    Head(usize),
+    /// The last `n` lines.
+    Tail(usize),
+    /// Zero-indexed lines `start..end`.
+    Span(std::ops::Range<usize>),
+}
+
+/// Initial range-read size for [`Vfs::read_lines`]; doubled on each further read.
+const LINE_CHUNK: u64 = 64 * 1024;
+
+fn count_newlines(content: &[u8]) -> usize {
+    content.iter().filter(|b| **b == b'\n').count()
+}
+
+/// Read lines `range` from the start of a file, fetching only as much as needed.
+async fn read_leading_lines(
+    backend: &dyn Backend,
+    path: &str,
+    range: std::ops::Range<usize>,
+) -> Result<Vec<u8>, BackendError> {
+    let mut content = Vec::new();
+    let mut newlines = 0;
+    let mut chunk = LINE_CHUNK;
+    while newlines < range.end {
+        let data = backend
+            .read_range(path, content.len() as u64, Some(chunk))
+            .await?;
+        let eof = (data.len() as u64) < chunk;
+        newlines += count_newlines(&data);
+        content.extend_from_slice(&data);
+        if eof {
+            break;
+        }
+        chunk = chunk.saturating_mul(2);
+    }
+
+    Ok(content
+        .split_inclusive(|b| *b == b'\n')
+        .skip(range.start)
+        .take(range.end.saturating_sub(range.start))
+        .collect::<Vec<_>>()
+        .concat())
+}
+
+/// Read the last `n` lines of a file, fetching backwards from the end.
+async fn read_trailing_lines(
+    backend: &dyn Backend,
+    path: &str,
+    n: usize,
+) -> Result<Vec<u8>, BackendError> {
+    let size = backend.stat(path).await?.size.unwrap_or(0);
+    let mut content = Vec::new();
+    let mut start = size;
+    let mut chunk = LINE_CHUNK;
+    while start > 0 && n > 0 {
+        let offset = start.saturating_sub(chunk);
+        let mut data = backend
+            .read_range(path, offset, Some(start - offset))
+            .await?;
+        data.extend_from_slice(&content);
+        content = data;
+        start = offset;
+
+        // A final newline ends the last line rather than starting a new one,
+        // so `n` complete lines need `n` newlines before it.
+        let body = content.strip_suffix(b"\n").unwrap_or(&content);
+        if count_newlines(body) >= n {
+            break;
+        }
+        chunk = chunk.saturating_mul(2);
+    }
+
+    let lines: Vec<&[u8]> = content.split_inclusive(|b| *b == b'\n').collect();
+    Ok(lines[lines.len().saturating_sub(n)..].concat())
+}
+
+/// Recursive size and file counts for a directory, from [`Vfs::usage`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirUsage {
+    /// VFS path of the directory (or file).
+    pub path: String,
+    /// Total size of all files below the path, in bytes.
+    pub bytes: u64,
+    /// Number of files below the path.
+    pub files: u64,
+    /// Usage of each immediate subdirectory.
+    pub children: Vec<DirUsage>,
 }
 
 #[derive(Debug, Clone)]
@@ -317,12 +1134,32 @@ pub struct MountSyncStatus {
     pub synced: u64,
     pub failed: u64,
     pub retries: u64,
+    /// Writes merged into a queued write to the same path, each saving an
+    /// upload.
+    pub coalesced: u64,
+    /// Writes given up on since the mount started.
+    pub dead_lettered: u64,
+    /// Recorded write-back conflicts, see [`Vfs::sync_conflicts`].
+    pub conflicts: usize,
     pub outbox_pending: Option<usize>,
     pub outbox_processing: Option<usize>,
     pub outbox_failed: Option<usize>,
     pub outbox_wal_unapplied: Option<usize>,
 }
 
+/// Result of probing one mount's backend, see [`Vfs::probe_mounts`].
+#[derive(Debug, Clone)]
+pub struct MountHealth {
+    pub mount_path: String,
+    pub backend_name: String,
+    /// Whether the backend answered within the timeout.
+    pub healthy: bool,
+    /// How long the probe took (the timeout, if it ran out).
+    pub latency: std::time::Duration,
+    /// Why the probe failed.
+    pub error: Option<String>,
+}
+
 impl Vfs {
     /// Create a new VFS from a configuration.
     pub async fn from_config(config: VfsConfig) -> Result<Self, VfsError> {
@@ -338,121 +1175,279 @@ impl Vfs {
         }
 
         // Build mounts
-        let mut mounts = Vec::new();
+        let (remote_changes, _) = tokio::sync::broadcast::channel(REMOTE_CHANGE_CAPACITY);
         let mut mount_runtimes = Vec::new();
         for mount_config in &effective_config.mounts {
-            let backend_name = mount_config.backend.as_ref().ok_or_else(|| {
-                VfsError::Config(format!(
-                    "Mount '{}' has no backend specified",
-                    mount_config.path
-                ))
-            })?;
+            let raw_backend = mount_backend(&backend_instances, mount_config)?;
+            let replicas = replica_backends(&backend_instances, mount_config)?;
+            let runtime = build_mount_runtime(
+                &effective_config,
+                mount_config,
+                raw_backend,
+                replicas,
+                &remote_changes,
+            )
+            .await?;
+            mount_runtimes.push(Arc::new(runtime));
+        }
 
-            let raw_backend = backend_instances.get(backend_name).ok_or_else(|| {
-                VfsError::Config(format!(
-                    "Backend '{}' not found for mount '{}'",
-                    backend_name, mount_config.path
-                ))
-            })?;
+        let snapshot_dir = SnapshotDir::default();
+        Ok(Vfs {
+            state: std::sync::RwLock::new(Arc::new(VfsState::new(
+                effective_config,
+                mount_runtimes,
+                &snapshot_dir,
+            )?)),
+            reload_lock: tokio::sync::Mutex::new(()),
+            searchers: std::sync::RwLock::new(Vec::new()),
+            remote_changes,
+            snapshot_dir,
+            sessions: tokio::sync::Mutex::new(Vec::new()),
+        })
+    }
 
-            let mount_mode = mount_config.mode.unwrap_or(MountMode::LocalIndexed);
-            let read_only = mount_config.read_only || mount_mode == MountMode::PullMirror;
-            let mut cache_config = cache_config_for_mode(mount_mode);
-            let sync_config = sync_config_for_mount(
-                mount_mode,
-                mount_config.sync.as_ref(),
-                effective_config.defaults.as_ref(),
-            );
-            if sync_config.mode == SyncMode::WriteBack {
-                cache_config.enabled = true;
-            }
-
-            let sync_ref = raw_backend.clone();
-            let cached_backend = if sync_config.mode == SyncMode::WriteBack {
-                let wal_path = wal_path_for_mount(&mount_config.path)?;
-                let wal = Arc::new(WriteAheadLog::new(&wal_path, WalConfig::default()).map_err(
-                    |e| {
-                        VfsError::Config(format!(
-                            "Failed to initialize WAL for mount '{}': {}",
-                            mount_config.path, e
-                        ))
-                    },
-                )?);
-
-                let recovered = replay_outbox_entries(wal.as_ref(), raw_backend.clone()).await?;
-                if recovered > 0 {
-                    info!(
-                        "Recovered {} outbox operation(s) for mount {}",
-                        recovered, mount_config.path
-                    );
-                }
+    fn state(&self) -> Arc<VfsState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-                Arc::new(CachedBackend::new_with_wal(
-                    DynBackend(raw_backend.clone()),
-                    cache_config,
-                    sync_config.clone(),
-                    read_only,
-                    wal,
-                ))
-            } else {
-                Arc::new(CachedBackend::new(
-                    DynBackend(raw_backend.clone()),
-                    cache_config,
-                    sync_config.clone(),
-                    read_only,
-                ))
+    /// Apply a new configuration while the VFS is in use.
+    ///
+    /// Mounts are matched by path. A mount whose backend, mode, read-only,
+    /// sync and cache settings are unchanged keeps its backend, cache and sync
+    /// task.
+    /// Other mounts are built fresh, reusing backend connections whose config
+    /// did not change. Operations already in flight finish against the old
+    /// mounts. Write-back mounts that are removed or rebuilt are flushed once
+    /// the new mounts are serving; anything that fails to sync stays in the
+    /// mount's WAL and is replayed when the mount is next built.
+    ///
+    /// An invalid config or a mount that fails to build aborts the reload and
+    /// leaves the current mounts serving, with their sync still running.
+    pub async fn reload(&self, config: VfsConfig) -> Result<ReloadSummary, VfsError> {
+        let _guard = self.reload_lock.lock().await;
+        let effective_config = config.effective();
+        effective_config.validate_or_err()?;
+        let old = self.state();
+
+        let mut summary = ReloadSummary::default();
+        let mut kept: HashMap<&str, Arc<MountRuntime>> = HashMap::new();
+        let mut retired = Vec::new();
+        for runtime in &old.mount_runtimes {
+            let path = runtime.mount_path.as_str();
+            let Some(mount) = effective_config.mounts.iter().find(|m| m.path == path) else {
+                summary.removed.push(path.to_string());
+                retired.push(runtime.clone());
+                continue;
             };
-
-            if sync_config.mode == SyncMode::WriteBack {
-                cached_backend
-                    .start_sync(move |path, content| {
-                        let backend = sync_ref.clone();
-                        async move { backend.write(&path, &content).await.map_err(VfsError::from) }
-                    })
-                    .await;
+            let old_mount = old.config.mounts.iter().find(|m| m.path == path);
+            if runtime_fingerprint(&effective_config, mount) != runtime.fingerprint {
+                summary.updated.push(path.to_string());
+                retired.push(runtime.clone());
+                continue;
             }
+            if serde_json::to_value(old_mount).ok() != serde_json::to_value(mount).ok() {
+                summary.updated.push(path.to_string());
+            }
+            kept.insert(path, runtime.clone());
+        }
+        for mount in &effective_config.mounts {
+            if !old
+                .mount_runtimes
+                .iter()
+                .any(|r| r.mount_path == mount.path)
+            {
+                summary.added.push(mount.path.clone());
+            }
+        }
 
-            let mount_backend: Arc<dyn Backend> = cached_backend.clone();
-
-            mounts.push(Mount {
-                path: mount_config.path.clone(),
-                backend: mount_backend,
-                read_only,
-            });
-
-            mount_runtimes.push(MountRuntime {
-                mount_path: mount_config.path.clone(),
-                backend_name: backend_name.clone(),
-                sync_mode: sync_config.mode,
-                read_only,
-                backend: raw_backend.clone(),
-                cached_backend,
+        // Start the backends that new and rebuilt mounts need before touching
+        // anything, reusing old instances whose config is unchanged.
+        let mut backend_instances: HashMap<String, Arc<dyn Backend>> = HashMap::new();
+        let needed = effective_config
+            .mounts
+            .iter()
+            .filter(|mount| !kept.contains_key(mount.path.as_str()))
+            .flat_map(|mount| {
+                let replicas = mount.replicas.iter().flat_map(|r| &r.backends);
+                mount.backend.iter().chain(replicas)
             });
+        for name in needed {
+            if backend_instances.contains_key(name) {
+                continue;
+            }
+            let Some(backend_config) = effective_config.backends.get(name) else {
+                continue;
+            };
+            let unchanged = serde_json::to_value(old.config.backends.get(name)).ok()
+                == serde_json::to_value(Some(backend_config)).ok();
+            let existing = old
+                .mount_runtimes
+                .iter()
+                .find(|r| unchanged && r.backend_name == *name);
+            let backend = match existing {
+                Some(runtime) => runtime.origin.clone(),
+                None => create_backend(name, backend_config).await?,
+            };
+            backend_instances.insert(name.clone(), backend);
         }
 
-        let router = Router::new(mounts);
+        // Build the whole new table before touching the old one, so a failure
+        // leaves the current mounts serving with their sync running.
+        let mut mount_runtimes = Vec::with_capacity(effective_config.mounts.len());
+        let mut built = Vec::new();
+        let mut failure = None;
+        for mount in &effective_config.mounts {
+            if let Some(runtime) = kept.get(mount.path.as_str()) {
+                mount_runtimes.push(runtime.clone());
+                continue;
+            }
+            let runtime = async {
+                let raw_backend = mount_backend(&backend_instances, mount)?;
+                let replicas = replica_backends(&backend_instances, mount)?;
+                build_mount_runtime(
+                    &effective_config,
+                    mount,
+                    raw_backend,
+                    replicas,
+                    &self.remote_changes,
+                )
+                .await
+            }
+            .await;
+            match runtime {
+                Ok(runtime) => {
+                    let runtime = Arc::new(runtime);
+                    built.push(runtime.clone());
+                    mount_runtimes.push(runtime);
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        // Views are cheap to build, so they are always rebuilt.
+        let state = match failure {
+            Some(e) => Err(e),
+            None => VfsState::new(effective_config, mount_runtimes, &self.snapshot_dir),
+        };
+        let state = match state {
+            Ok(state) => state,
+            Err(e) => {
+                // Nothing was written through the discarded runtimes; just
+                // stop the sync they started.
+                for runtime in &built {
+                    runtime.cached_backend.shutdown_sync().await;
+                }
+                return Err(e);
+            }
+        };
+        {
+            let mut searchers = self.searchers.write().unwrap_or_else(|e| e.into_inner());
+            searchers.retain(|(path, _)| state.config.mounts.iter().any(|m| m.path == *path));
+        }
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(state);
+
+        // Drain the retired mounts only now that nothing new routes to them,
+        // so writes that raced the swap go out with this flush.
+        for runtime in &retired {
+            if let Err(e) = runtime.flush().await {
+                warn!(
+                    "Failed to flush retired mount {}; its WAL keeps the pending writes: {}",
+                    runtime.mount_path, e
+                );
+            }
+        }
 
-        Ok(Vfs {
-            config: effective_config,
-            router,
-            mount_runtimes,
-        })
+        if !summary.is_empty() {
+            info!(
+                added = ?summary.added,
+                removed = ?summary.removed,
+                updated = ?summary.updated,
+                "reloaded configuration"
+            );
+        }
+        Ok(summary)
     }
 
     /// Read the contents of a file.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn read(&self, path: &str) -> Result<Vec<u8>, VfsError> {
-        let (backend, relative, _) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
         debug!(relative = %relative, "resolved path");
         backend.read(&relative).await.map_err(VfsError::from)
     }
 
-    /// Read a file and return an optional CAS token for conditional writes.
-    pub async fn read_with_cas_token(
+    /// Read up to `len` bytes of a file starting at `offset`.
+    ///
+    /// Uses native range reads where the backend supports them.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, VfsError> {
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
+        debug!(relative = %relative, "resolved path (range read)");
+        backend
+            .read_range(&relative, offset, len)
+            .await
+            .map_err(VfsError::from)
+    }
+
+    /// Read a range of lines from a file without reading all of it.
+    ///
+    /// Lines keep their trailing newline. Large files are read in growing
+    /// chunks from the start (or, for [`LineRange::Tail`], from the end).
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn read_lines(&self, path: &str, range: LineRange) -> Result<Vec<u8>, VfsError> {
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
+        debug!(relative = %relative, ?range, "resolved path (line read)");
+        match range {
+            LineRange::Head(n) => read_leading_lines(backend, &relative, 0..n).await,
+            LineRange::Span(range) => read_leading_lines(backend, &relative, range).await,
+            LineRange::Tail(n) => read_trailing_lines(backend, &relative, n).await,
+        }
+        .map_err(VfsError::from)
+    }
+
+    /// Read a JSON file into `T`.
+    pub async fn read_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, VfsError> {
+        let content = self.read(path).await?;
+        serde_json::from_slice(&content)
+            .map_err(|e| VfsError::Config(format!("{} is not valid JSON: {}", path, e)))
+    }
+
+    /// Read a YAML file into `T`.
+    pub async fn read_yaml<T: DeserializeOwned>(&self, path: &str) -> Result<T, VfsError> {
+        let content = self.read(path).await?;
+        serde_yaml::from_slice(&content)
+            .map_err(|e| VfsError::Config(format!("{} is not valid YAML: {}", path, e)))
+    }
+
+    /// Whether a file is binary, judged from its first bytes.
+    pub async fn is_binary(&self, path: &str) -> Result<bool, VfsError> {
+        let head = self.read_range(path, 0, Some(SNIFF_LEN as u64)).await?;
+        Ok(is_binary_content(&head))
+    }
+
+    /// The MIME type of a file, sniffed from its first bytes and its name.
+    pub async fn content_type(&self, path: &str) -> Result<&'static str, VfsError> {
+        let head = self.read_range(path, 0, Some(SNIFF_LEN as u64)).await?;
+        Ok(detect_content_type(path, &head))
+    }
+
+    /// Read a file and return an optional CAS token for conditional writes.
+    pub async fn read_with_cas_token(
         &self,
         path: &str,
     ) -> Result<(Vec<u8>, Option<String>), VfsError> {
-        let (backend, relative, _) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
         debug!(relative = %relative, "resolved path (CAS read)");
         backend
             .read_with_cas_token(&relative)
@@ -470,7 +1465,8 @@ impl Vfs {
         expected: Option<&str>,
         content: &[u8],
     ) -> Result<Option<String>, VfsError> {
-        let (backend, relative, read_only) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
         if read_only {
             return Err(VfsError::ReadOnly(path.to_string()));
         }
@@ -481,10 +1477,33 @@ impl Vfs {
             .map_err(VfsError::from)
     }
 
+    /// Create a file only if nothing exists at `path` yet.
+    ///
+    /// Returns the new CAS token on success; a taken path fails with a
+    /// precondition error from the backend.
+    #[instrument(skip(self, content), fields(path = %path, size = content.len()))]
+    pub async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, VfsError> {
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
+        if read_only {
+            return Err(VfsError::ReadOnly(path.to_string()));
+        }
+        debug!(relative = %relative, "resolved path (create if absent)");
+        backend
+            .create_if_absent(&relative, content)
+            .await
+            .map_err(VfsError::from)
+    }
+
     /// Write content to a file.
     #[instrument(skip(self, content), fields(path = %path, size = content.len()))]
     pub async fn write(&self, path: &str, content: &[u8]) -> Result<(), VfsError> {
-        let (backend, relative, read_only) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
         if read_only {
             return Err(VfsError::ReadOnly(path.to_string()));
         }
@@ -498,7 +1517,8 @@ impl Vfs {
     /// Append content to a file.
     #[instrument(skip(self, content), fields(path = %path, size = content.len()))]
     pub async fn append(&self, path: &str, content: &[u8]) -> Result<(), VfsError> {
-        let (backend, relative, read_only) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
         if read_only {
             return Err(VfsError::ReadOnly(path.to_string()));
         }
@@ -509,10 +1529,79 @@ impl Vfs {
             .map_err(VfsError::from)
     }
 
+    /// Apply a single-file unified diff to a file.
+    ///
+    /// Hunk context must match the current content, otherwise nothing is
+    /// written and [`VfsError::Patch`] names the conflicting line. The write is
+    /// conditional on the content read, so concurrent edits are not lost. A
+    /// patch that creates a file (`@@ -0,0 ...`) may target a missing path.
+    #[instrument(skip(self, diff), fields(path = %path))]
+    pub async fn apply_patch(&self, path: &str, diff: &str) -> Result<PatchSummary, VfsError> {
+        self.edit_text(path, patch_creates_file(diff), |original| {
+            apply_patch(original, diff)
+        })
+        .await
+    }
+
+    /// Replace one occurrence of `old` with `new` in a file.
+    ///
+    /// Fails with [`VfsError::Patch`] if `old` is absent, or occurs more than
+    /// once and no 1-indexed `occurrence` is given. Returns the line where the
+    /// replacement starts.
+    #[instrument(skip(self, old, new), fields(path = %path))]
+    pub async fn str_replace(
+        &self,
+        path: &str,
+        old: &str,
+        new: &str,
+        occurrence: Option<usize>,
+    ) -> Result<usize, VfsError> {
+        self.edit_text(path, false, |original| {
+            str_replace(original, old, new, occurrence)
+        })
+        .await
+    }
+
+    /// Read a text file, transform it and write it back conditionally on the
+    /// content read.
+    async fn edit_text<T>(
+        &self,
+        path: &str,
+        allow_missing: bool,
+        edit: impl FnOnce(&str) -> Result<(String, T), PatchError>,
+    ) -> Result<T, VfsError> {
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
+        if read_only {
+            return Err(VfsError::ReadOnly(path.to_string()));
+        }
+
+        let (original, cas_token) = match backend.read_with_cas_token(&relative).await {
+            Ok(read) => read,
+            Err(BackendError::NotFound(_)) if allow_missing => (Vec::new(), None),
+            Err(e) => return Err(e.into()),
+        };
+        let patch_error = |source| VfsError::Patch {
+            path: path.to_string(),
+            source,
+        };
+        let original =
+            String::from_utf8(original).map_err(|_| patch_error(PatchError::BinaryFile))?;
+        let (edited, result) = edit(&original).map_err(patch_error)?;
+
+        debug!(relative = %relative, "writing edited file");
+        backend
+            .compare_and_swap(&relative, cas_token.as_deref(), edited.as_bytes())
+            .await
+            .map_err(VfsError::from)?;
+        Ok(result)
+    }
+
     /// Delete a file.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn delete(&self, path: &str) -> Result<(), VfsError> {
-        let (backend, relative, read_only) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
         if read_only {
             return Err(VfsError::ReadOnly(path.to_string()));
         }
@@ -523,30 +1612,256 @@ impl Vfs {
     /// List entries in a directory.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn list(&self, path: &str) -> Result<Vec<Entry>, VfsError> {
-        let (backend, relative, _) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
         debug!(relative = %relative, "resolved path");
         backend.list(&relative).await.map_err(VfsError::from)
     }
 
+    /// List every file and directory below `path`, sorted by path.
+    ///
+    /// Entry paths are full VFS paths.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn walk(&self, path: &str) -> Result<Vec<Entry>, VfsError> {
+        let root = if path.len() > 1 {
+            path.trim_end_matches('/')
+        } else {
+            path
+        };
+        let mut entries = Vec::new();
+        let mut pending = vec![root.to_string()];
+        while let Some(dir) = pending.pop() {
+            for mut entry in self.list(&dir).await? {
+                entry.path = if dir == "/" {
+                    format!("/{}", entry.name)
+                } else {
+                    format!("{}/{}", dir, entry.name)
+                };
+                if entry.is_dir {
+                    pending.push(entry.path.clone());
+                }
+                entries.push(entry);
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// BLAKE3 checksum of a file's content, as a hex string.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn checksum(&self, path: &str) -> Result<String, VfsError> {
+        let content = self.read(path).await?;
+        Ok(blake3::hash(&content).to_hex().to_string())
+    }
+
+    /// Compute recursive size and file counts for a path.
+    ///
+    /// Directories are walked with [`Vfs::list`]; the result holds one
+    /// [`DirUsage`] per subdirectory. A file path yields a single-file usage.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn usage(&self, path: &str) -> Result<DirUsage, VfsError> {
+        let path = if path.len() > 1 {
+            path.trim_end_matches('/')
+        } else {
+            path
+        };
+        if let Ok(entry) = self.stat(path).await {
+            if !entry.is_dir {
+                return Ok(DirUsage {
+                    path: path.to_string(),
+                    bytes: entry.size.unwrap_or(0),
+                    files: 1,
+                    children: Vec::new(),
+                });
+            }
+        }
+        self.dir_usage(path.to_string()).await
+    }
+
+    fn dir_usage(&self, path: String) -> BoxFuture<'_, Result<DirUsage, VfsError>> {
+        Box::pin(async move {
+            let mut usage = DirUsage {
+                path: path.clone(),
+                ..Default::default()
+            };
+            for entry in self.list(&path).await? {
+                let child = if path == "/" {
+                    format!("/{}", entry.name)
+                } else {
+                    format!("{}/{}", path, entry.name)
+                };
+                if entry.is_dir {
+                    let child = self.dir_usage(child).await?;
+                    usage.bytes += child.bytes;
+                    usage.files += child.files;
+                    usage.children.push(child);
+                } else {
+                    usage.bytes += entry.size.unwrap_or(0);
+                    usage.files += 1;
+                }
+            }
+            Ok(usage)
+        })
+    }
+
     /// Check if a path exists.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn exists(&self, path: &str) -> Result<bool, VfsError> {
-        let (backend, relative, _) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
         backend.exists(&relative).await.map_err(VfsError::from)
     }
 
     /// Get metadata for a path.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn stat(&self, path: &str) -> Result<Entry, VfsError> {
-        let (backend, relative, _) = self.router.resolve(path)?;
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
         backend.stat(&relative).await.map_err(VfsError::from)
     }
 
+    /// All user metadata of a file.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn list_meta(&self, path: &str) -> Result<FileMeta, VfsError> {
+        let state = self.state();
+        let (backend, relative, _) = state.router.resolve(path)?;
+        backend.read_meta(&relative).await.map_err(VfsError::from)
+    }
+
+    /// One user metadata value of a file.
+    pub async fn get_meta(&self, path: &str, key: &str) -> Result<Option<String>, VfsError> {
+        Ok(self.list_meta(path).await?.remove(key))
+    }
+
+    /// Attach a key-value pair to a file, replacing any value under `key`.
+    ///
+    /// Keys are lowercase ASCII letters, digits, `-`, `_` and `.`, the set
+    /// object stores accept as metadata names.
+    #[instrument(skip(self, value), fields(path = %path, key = %key))]
+    pub async fn set_meta(&self, path: &str, key: &str, value: &str) -> Result<(), VfsError> {
+        validate_meta_key(key)?;
+        self.update_meta(path, |meta| {
+            meta.insert(key.to_string(), value.to_string());
+        })
+        .await
+    }
+
+    /// Remove a metadata key from a file. Returns whether it was set.
+    #[instrument(skip(self), fields(path = %path, key = %key))]
+    pub async fn remove_meta(&self, path: &str, key: &str) -> Result<bool, VfsError> {
+        let mut removed = false;
+        self.update_meta(path, |meta| removed = meta.remove(key).is_some())
+            .await?;
+        Ok(removed)
+    }
+
+    async fn update_meta(
+        &self,
+        path: &str,
+        change: impl FnOnce(&mut FileMeta),
+    ) -> Result<(), VfsError> {
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
+        if read_only {
+            return Err(VfsError::ReadOnly(path.to_string()));
+        }
+        let mut meta = backend.read_meta(&relative).await?;
+        change(&mut meta);
+        backend
+            .write_meta(&relative, &meta)
+            .await
+            .map_err(VfsError::from)
+    }
+
+    /// Tag a file. Tags are metadata under `tag.<tag>`, and the file is
+    /// listed under `/tags/<tag>/`.
+    #[instrument(skip(self), fields(path = %path, tag = %tag))]
+    pub async fn add_tag(&self, path: &str, tag: &str) -> Result<(), VfsError> {
+        validate_tag(tag)?;
+        self.set_meta(path, &format!("{}{}", TAG_KEY_PREFIX, tag), TAG_VALUE)
+            .await
+    }
+
+    /// Remove a tag from a file. Returns whether the file had it.
+    #[instrument(skip(self), fields(path = %path, tag = %tag))]
+    pub async fn remove_tag(&self, path: &str, tag: &str) -> Result<bool, VfsError> {
+        validate_tag(tag)?;
+        self.remove_meta(path, &format!("{}{}", TAG_KEY_PREFIX, tag))
+            .await
+    }
+
+    /// The tags of a file, sorted.
+    pub async fn tags(&self, path: &str) -> Result<Vec<String>, VfsError> {
+        let meta = self.list_meta(path).await?;
+        Ok(crate::tags::tags_in(&meta).map(str::to_string).collect())
+    }
+
+    /// The files carrying `tag` across all mounts, as sorted VFS paths.
+    pub async fn tagged(&self, tag: &str) -> Result<Vec<String>, VfsError> {
+        validate_tag(tag)?;
+        Ok(self
+            .tag_index()
+            .tagged()
+            .await?
+            .remove(tag)
+            .map(|files| files.into_iter().collect())
+            .unwrap_or_default())
+    }
+
+    /// Every tag in use, with its number of files.
+    pub async fn tag_counts(&self) -> Result<Vec<(String, usize)>, VfsError> {
+        Ok(self
+            .tag_index()
+            .tagged()
+            .await?
+            .into_iter()
+            .map(|(tag, files)| (tag, files.len()))
+            .collect())
+    }
+
+    fn tag_index(&self) -> TagBackend {
+        let state = self.state();
+        TagBackend::new(
+            state
+                .mount_runtimes
+                .iter()
+                .map(|runtime| Mount {
+                    path: runtime.mount_path.clone(),
+                    backend: runtime.cached_backend.clone(),
+                    read_only: runtime.read_only,
+                    path_policy: runtime.path_policy.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Set a file's modification time.
+    ///
+    /// Returns `false` when the mount's backend does not store modification
+    /// times.
+    #[instrument(skip(self), fields(path = %path))]
+    pub async fn set_modified(
+        &self,
+        path: &str,
+        modified: DateTime<Utc>,
+    ) -> Result<bool, VfsError> {
+        let state = self.state();
+        let (backend, relative, read_only) = state.router.resolve(path)?;
+        if read_only {
+            return Err(VfsError::ReadOnly(path.to_string()));
+        }
+        backend
+            .set_modified(&relative, modified)
+            .await
+            .map_err(VfsError::from)
+    }
+
     /// Rename/move a file or directory.
     #[instrument(skip(self), fields(from = %from, to = %to))]
     pub async fn rename(&self, from: &str, to: &str) -> Result<(), VfsError> {
-        let (from_backend, from_relative, from_read_only) = self.router.resolve(from)?;
-        let (to_backend, to_relative, to_read_only) = self.router.resolve(to)?;
+        let state = self.state();
+        let (from_backend, from_relative, from_read_only) = state.router.resolve(from)?;
+        let (to_backend, to_relative, to_read_only) = state.router.resolve(to)?;
 
         if from_read_only {
             return Err(VfsError::ReadOnly(from.to_string()));
@@ -600,19 +1915,34 @@ impl Vfs {
         futures::future::join_all(futures).await
     }
 
-    /// Prefetch paths into cache by reading them.
+    /// Prefetch paths into cache by reading them, a bounded number at a time.
+    /// Returns how many reads succeeded and how many failed.
     pub async fn prefetch(&self, paths: &[&str]) -> (usize, usize) {
-        let futures: Vec<_> = paths.iter().map(|p| self.read(p)).collect();
-        let results = futures::future::join_all(futures).await;
-        let success = results.iter().filter(|r| r.is_ok()).count();
-        let errors = results.len() - success;
-        (success, errors)
+        prefetch::read_all(paths.iter().map(|p| self.read(p))).await
+    }
+
+    /// Prefetch every file matched by a glob over VFS paths, such as
+    /// `/workspace/src/**/*.rs`. A path without glob characters is read as is.
+    pub async fn prefetch_glob(&self, pattern: &str) -> Result<(usize, usize), VfsError> {
+        let glob = PrefetchGlob::new(pattern)?;
+        if glob.is_literal() {
+            return Ok(self.prefetch(&[pattern]).await);
+        }
+        let files: Vec<String> = self
+            .walk(glob.root())
+            .await?
+            .into_iter()
+            .filter(|entry| !entry.is_dir && glob.is_match(&entry.path))
+            .map(|entry| entry.path)
+            .collect();
+        let paths: Vec<&str> = files.iter().map(String::as_str).collect();
+        Ok(self.prefetch(&paths).await)
     }
 
     /// Aggregate cache stats across all mounts.
     pub async fn cache_stats(&self) -> openfs_core::CacheStats {
         let mut aggregated = openfs_core::CacheStats::default();
-        for runtime in &self.mount_runtimes {
+        for runtime in &self.state().mount_runtimes {
             let stats = runtime.cached_backend.cache_stats().await;
             aggregated.hits += stats.hits;
             aggregated.misses += stats.misses;
@@ -624,16 +1954,56 @@ impl Vfs {
         aggregated
     }
 
+    /// Disk cache usage of each mount that has a disk cache, by mount path.
+    pub fn disk_cache_stats(&self) -> Vec<(String, DiskCacheStats)> {
+        self.state()
+            .mount_runtimes
+            .iter()
+            .filter_map(|runtime| {
+                let stats = runtime.cached_backend.disk_cache_stats()?;
+                Some((runtime.mount_path.clone(), stats))
+            })
+            .collect()
+    }
+
+    /// Circuit breaker state of each mount that has one, by mount path.
+    pub fn circuit_breakers(&self) -> Vec<(String, CircuitStatus)> {
+        self.state()
+            .mount_runtimes
+            .iter()
+            .filter_map(|runtime| {
+                let status = runtime.retry_backend.as_ref()?.circuit_status()?;
+                Some((runtime.mount_path.clone(), status))
+            })
+            .collect()
+    }
+
+    /// Replication progress of each replica, by mount path and replica
+    /// backend name.
+    pub async fn replication_stats(&self) -> Vec<(String, String, SyncStats)> {
+        let mut stats = Vec::new();
+        for runtime in &self.state().mount_runtimes {
+            let Some(replicated) = &runtime.replicated else {
+                continue;
+            };
+            for (replica, replica_stats) in replicated.replication_stats().await {
+                stats.push((runtime.mount_path.clone(), replica, replica_stats));
+            }
+        }
+        stats
+    }
+
     /// Get the effective configuration.
-    pub fn effective_config(&self) -> &VfsConfig {
-        &self.config
+    pub fn effective_config(&self) -> Arc<VfsConfig> {
+        self.state().config.clone()
     }
 
     /// Return per-mount sync status (including durable outbox counts when WAL is enabled).
     pub async fn sync_statuses(&self) -> Result<Vec<MountSyncStatus>, VfsError> {
-        let mut statuses = Vec::with_capacity(self.mount_runtimes.len());
+        let state = self.state();
+        let mut statuses = Vec::with_capacity(state.mount_runtimes.len());
 
-        for runtime in &self.mount_runtimes {
+        for runtime in &state.mount_runtimes {
             let sync = runtime.cached_backend.sync_stats().await;
             let outbox = runtime
                 .cached_backend
@@ -644,6 +2014,11 @@ impl Vfs {
                     })
                 })
                 .transpose()?;
+            let conflicts = runtime
+                .cached_backend
+                .conflicts()
+                .conflicts()
+                .map_err(|e| VfsError::Config(format!("Failed to read conflicts: {}", e)))?;
 
             statuses.push(MountSyncStatus {
                 mount_path: runtime.mount_path.clone(),
@@ -654,6 +2029,9 @@ impl Vfs {
                 synced: sync.synced,
                 failed: sync.failed,
                 retries: sync.retries,
+                coalesced: sync.coalesced,
+                dead_lettered: sync.dead_lettered,
+                conflicts: conflicts.len(),
                 outbox_pending: outbox.as_ref().map(|s| s.pending),
                 outbox_processing: outbox.as_ref().map(|s| s.processing),
                 outbox_failed: outbox.as_ref().map(|s| s.failed),
@@ -664,41 +2042,422 @@ impl Vfs {
         Ok(statuses)
     }
 
+    /// Check that every mount's backend is reachable by listing its root.
+    ///
+    /// Probes run concurrently against the uncached backend, so a warm cache
+    /// cannot hide an outage. Each gets `timeout`.
+    pub async fn probe_mounts(&self, timeout: std::time::Duration) -> Vec<MountHealth> {
+        let state = self.state();
+        let probes = state.mount_runtimes.iter().map(|runtime| async move {
+            let started = std::time::Instant::now();
+            let error = match tokio::time::timeout(timeout, runtime.backend.list("")).await {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some(format!("no response within {:?}", timeout)),
+            };
+            MountHealth {
+                mount_path: runtime.mount_path.clone(),
+                backend_name: runtime.backend_name.clone(),
+                healthy: error.is_none(),
+                latency: started.elapsed(),
+                error,
+            }
+        });
+        futures::future::join_all(probes).await
+    }
+
     /// Flush all write-back mounts and replay any remaining durable outbox entries.
     pub async fn flush_write_back(&self) -> Result<usize, VfsError> {
         let mut flushed_mounts = 0usize;
 
-        for runtime in &self.mount_runtimes {
-            if runtime.sync_mode != SyncMode::WriteBack {
+        for runtime in &self.state().mount_runtimes {
+            if runtime.flush().await? {
+                flushed_mounts += 1;
+            }
+        }
+
+        Ok(flushed_mounts)
+    }
+
+    /// Requeue dead-lettered writes on every write-back mount and replay them.
+    ///
+    /// Returns how many entries were requeued. Entries that fail again go
+    /// back through the mount's retry policy.
+    pub async fn retry_failed_sync(&self) -> Result<usize, VfsError> {
+        let mut requeued = 0usize;
+        for runtime in &self.state().mount_runtimes {
+            let Some(wal) = runtime.cached_backend.wal() else {
+                continue;
+            };
+            let count = wal.retry_all_failed().map_err(|e| {
+                VfsError::Config(format!("Failed to requeue failed entries: {}", e))
+            })?;
+            if count == 0 {
+                continue;
+            }
+            requeued += count;
+            let replayed = replay_outbox_entries(
+                wal.as_ref(),
+                runtime.backend.clone(),
+                runtime.cached_backend.conflicts(),
+            )
+            .await?;
+            info!(
+                "Requeued {} failed operation(s) for mount {}, {} replayed",
+                count, runtime.mount_path, replayed
+            );
+        }
+        Ok(requeued)
+    }
+
+    /// Pull remote changes into the cache of every pull-mirror and two-way
+    /// mount now, rather than waiting for the next scheduled pull.
+    pub async fn pull_remote_changes(&self) -> Result<Vec<(String, PullSummary)>, VfsError> {
+        let mut summaries = Vec::new();
+        for runtime in &self.state().mount_runtimes {
+            if !runtime.sync_mode.pulls_remote_changes() {
                 continue;
             }
+            let summary = runtime.cached_backend.pull().await?;
+            publish_remote_changes(&self.remote_changes, &runtime.mount_path, &summary);
+            summaries.push((runtime.mount_path.clone(), summary));
+        }
+        Ok(summaries)
+    }
+
+    /// Receive the files that pulls find changed on pull-mirror and two-way
+    /// mounts, e.g. to keep a search index current. A receiver that falls
+    /// more than [`REMOTE_CHANGE_CAPACITY`] events behind misses some.
+    pub fn subscribe_remote_changes(&self) -> tokio::sync::broadcast::Receiver<RemoteChangeEvent> {
+        self.remote_changes.subscribe()
+    }
 
-            runtime.cached_backend.shutdown_sync().await;
+    /// Write-back sync state of every path on every mount, with full paths.
+    pub async fn sync_status(&self) -> Result<Vec<PathSyncStatus>, VfsError> {
+        let mut statuses = Vec::new();
+        for runtime in &self.state().mount_runtimes {
+            let mount = runtime.mount_path.trim_end_matches('/');
+            let paths = runtime.cached_backend.path_sync_statuses().await?;
+            statuses.extend(paths.into_iter().map(|mut status| {
+                status.path = format!("{}/{}", mount, status.path.trim_start_matches('/'));
+                status
+            }));
+        }
+        Ok(statuses)
+    }
 
-            if let Some(wal) = runtime.cached_backend.wal() {
-                let replayed = replay_outbox_entries(wal.as_ref(), runtime.backend.clone()).await?;
-                if replayed > 0 {
-                    info!(
-                        "Replayed {} outbox operation(s) during flush for mount {}",
-                        replayed, runtime.mount_path
-                    );
+    /// Write-back conflicts recorded on every mount, with full paths.
+    pub fn sync_conflicts(&self) -> Result<Vec<SyncConflict>, VfsError> {
+        let mut conflicts = Vec::new();
+        for runtime in &self.state().mount_runtimes {
+            let mount = runtime.mount_path.trim_end_matches('/');
+            let recorded = runtime
+                .cached_backend
+                .conflicts()
+                .conflicts()
+                .map_err(|e| VfsError::Config(format!("Failed to read conflicts: {}", e)))?;
+            conflicts.extend(recorded.into_iter().map(|mut conflict| {
+                let full = |path: &str| format!("{}/{}", mount, path.trim_start_matches('/'));
+                conflict.path = full(&conflict.path);
+                if let ConflictResolution::KeptBoth { copy } = &mut conflict.resolution {
+                    *copy = full(copy);
                 }
+                conflict
+            }));
+        }
+        Ok(conflicts)
+    }
+
+    /// Forget the recorded write-back conflicts. Returns how many there were.
+    pub fn clear_sync_conflicts(&self) -> Result<usize, VfsError> {
+        let mut cleared = 0;
+        for runtime in &self.state().mount_runtimes {
+            cleared += runtime
+                .cached_backend
+                .conflicts()
+                .clear()
+                .map_err(|e| VfsError::Config(format!("Failed to clear conflicts: {}", e)))?;
+        }
+        Ok(cleared)
+    }
+
+    /// Keep snapshots in `dir` instead of `OPENFS_SNAPSHOT_DIR` or the
+    /// `snapshots` directory next to the WAL databases.
+    pub fn set_snapshot_dir(&self, dir: impl Into<PathBuf>) {
+        *self.snapshot_dir.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+    }
+
+    /// Record the content of every file on `mount` as a snapshot, which
+    /// [`Vfs::restore_snapshot`] can roll the mount back to. Contents are
+    /// stored once per distinct file content, across snapshots.
+    pub async fn snapshot(
+        &self,
+        mount: &str,
+        label: Option<&str>,
+    ) -> Result<SnapshotInfo, VfsError> {
+        let mount = self.mount_path(mount)?;
+        let store = self.snapshot_store()?;
+        let mut files = BTreeMap::new();
+        self.visit_mount_files(&mount, |path, content| {
+            files.insert(path, store.put_blob(&content)?);
+            Ok(())
+        })
+        .await?;
+        let manifest = SnapshotManifest::new(&mount, label, files);
+        store.save(&manifest)?;
+        info!(
+            "Snapshot {} of {}: {} files",
+            manifest.id,
+            mount,
+            manifest.files.len()
+        );
+        Ok(manifest.info())
+    }
+
+    /// Snapshots of `mount`, oldest first.
+    pub fn snapshots(&self, mount: &str) -> Result<Vec<SnapshotInfo>, VfsError> {
+        let mount = self.mount_path(mount)?;
+        let manifests = self.snapshot_store()?.list(&mount)?;
+        Ok(manifests.iter().map(SnapshotManifest::info).collect())
+    }
+
+    /// Put `mount` back as it was in `snapshot` (an id, or the label of the
+    /// newest snapshot carrying it): files that differ are rewritten and
+    /// files added since are deleted. Empty directories are left in place.
+    pub async fn restore_snapshot(
+        &self,
+        mount: &str,
+        snapshot: &str,
+    ) -> Result<RestoreSummary, VfsError> {
+        let mount = self.mount_path(mount)?;
+        let store = self.snapshot_store()?;
+        let manifest = find_snapshot(&store, &mount, snapshot)?;
+        if let Some(missing) = manifest.files.values().find(|f| !store.has_blob(&f.hash)) {
+            return Err(VfsError::NotFound(format!(
+                "content {} of snapshot {}",
+                missing.hash, manifest.id
+            )));
+        }
+        let current = self.current_files(&mount).await?;
+
+        let base = mount.trim_end_matches('/');
+        let mut summary = RestoreSummary::default();
+        for (path, file) in &manifest.files {
+            if current.get(path).is_some_and(|now| now.hash == file.hash) {
+                summary.unchanged += 1;
+                continue;
             }
+            let content = store.blob(&file.hash)?;
+            self.write(&format!("{}/{}", base, path), &content).await?;
+            summary.written += 1;
+        }
+        for path in current.keys().filter(|p| !manifest.files.contains_key(*p)) {
+            self.delete(&format!("{}/{}", base, path)).await?;
+            summary.deleted += 1;
+        }
+        info!(
+            "Restored {} to snapshot {}: {} written, {} deleted",
+            mount, manifest.id, summary.written, summary.deleted
+        );
+        Ok(summary)
+    }
+
+    /// Files changed on `mount` from snapshot `from` to snapshot `to`, or to
+    /// the mount's current state when `to` is `None`.
+    pub async fn diff_snapshot(
+        &self,
+        mount: &str,
+        from: &str,
+        to: Option<&str>,
+    ) -> Result<SnapshotDiff, VfsError> {
+        let mount = self.mount_path(mount)?;
+        let store = self.snapshot_store()?;
+        let from = find_snapshot(&store, &mount, from)?;
+        let to = match to {
+            Some(to) => find_snapshot(&store, &mount, to)?.files,
+            None => self.current_files(&mount).await?,
+        };
+        Ok(from.diff(&to))
+    }
+
+    /// Content of the file at `path` as it was at `point`, read from the
+    /// snapshot of its mount without restoring it. The same content is at
+    /// `/.history/<point>/<path>`.
+    pub async fn read_at(&self, path: &str, point: &HistoryPoint) -> Result<Vec<u8>, VfsError> {
+        Ok(self.state().history.read_at(path, point)?)
+    }
+
+    /// The configured mount path matching `mount`.
+    fn mount_path(&self, mount: &str) -> Result<String, VfsError> {
+        let wanted = mount.trim_end_matches('/');
+        self.state()
+            .mount_runtimes
+            .iter()
+            .find(|runtime| runtime.mount_path.trim_end_matches('/') == wanted)
+            .map(|runtime| runtime.mount_path.clone())
+            .ok_or_else(|| VfsError::NoMount(mount.to_string()))
+    }
 
-            flushed_mounts += 1;
+    fn snapshot_store(&self) -> Result<SnapshotStore, VfsError> {
+        snapshot_store_in(&self.snapshot_dir)
+    }
+
+    /// Hash of every file on `mount`, by path relative to the mount.
+    async fn current_files(&self, mount: &str) -> Result<BTreeMap<String, SnapshotFile>, VfsError> {
+        let mut files = BTreeMap::new();
+        self.visit_mount_files(mount, |path, content| {
+            files.insert(path, SnapshotFile::of(&content));
+            Ok(())
+        })
+        .await?;
+        Ok(files)
+    }
+
+    /// Read every file on `mount`, leaving out mounts nested below it, and
+    /// hand each to `visit` with its path relative to the mount.
+    async fn visit_mount_files(
+        &self,
+        mount: &str,
+        mut visit: impl FnMut(String, Vec<u8>) -> Result<(), VfsError>,
+    ) -> Result<(), VfsError> {
+        let base = mount.trim_end_matches('/');
+        let nested: Vec<String> = self
+            .state()
+            .mount_runtimes
+            .iter()
+            .map(|runtime| runtime.mount_path.trim_end_matches('/').to_string())
+            .filter(|other| other.len() > base.len() && is_same_or_under(other, base))
+            .collect();
+        let paths: Vec<String> = self
+            .walk(mount)
+            .await?
+            .into_iter()
+            .filter(|entry| {
+                !entry.is_dir && !nested.iter().any(|n| is_same_or_under(&entry.path, n))
+            })
+            .map(|entry| entry.path)
+            .collect();
+        let mut reads = futures::stream::iter(paths)
+            .map(|path| async move {
+                let content = self.read(&path).await;
+                (path, content)
+            })
+            .buffered(prefetch::PREFETCH_CONCURRENCY);
+        while let Some((path, content)) = reads.next().await {
+            let relative = path[base.len()..].trim_start_matches('/').to_string();
+            visit(relative, content?)?;
         }
+        Ok(())
+    }
 
-        Ok(flushed_mounts)
+    /// Register the searcher that serves `search` queries for a mount.
+    ///
+    /// Replaces any searcher previously registered for the same mount. The
+    /// same searcher may be registered for several mounts sharing a collection.
+    pub fn set_searcher(&self, mount_path: &str, searcher: Arc<dyn Searcher>) {
+        let mount_path = format!("/{}", mount_path.trim_matches('/'));
+        let mut searchers = self.searchers.write().unwrap_or_else(|e| e.into_inner());
+        searchers.retain(|(path, _)| *path != mount_path);
+        searchers.push((mount_path, searcher));
+    }
+
+    /// Search indexed content across mounts.
+    ///
+    /// Queries every mount with a registered searcher (narrowed to mounts
+    /// overlapping `options.path_prefix`), merges the hits and returns the
+    /// `options.limit` best by score.
+    #[instrument(skip(self, options), fields(query = %query))]
+    pub async fn search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchHit>, VfsError> {
+        let targets: Vec<Arc<dyn Searcher>> = {
+            let searchers = self.searchers.read().unwrap_or_else(|e| e.into_inner());
+            if searchers.is_empty() {
+                return Err(VfsError::Config(
+                    "Search is not configured: no mount has a searcher".to_string(),
+                ));
+            }
+
+            let prefix = options
+                .path_prefix
+                .as_deref()
+                .map(|p| format!("/{}", p.trim_matches('/')));
+            let mut targets: Vec<Arc<dyn Searcher>> = Vec::new();
+            for (mount_path, searcher) in searchers.iter() {
+                let overlaps = match &prefix {
+                    None => true,
+                    Some(prefix) => {
+                        is_same_or_under(prefix, mount_path) || is_same_or_under(mount_path, prefix)
+                    }
+                };
+                // Mounts sharing a collection share a searcher; query it once.
+                if overlaps && !targets.iter().any(|t| Arc::ptr_eq(t, searcher)) {
+                    targets.push(searcher.clone());
+                }
+            }
+            targets
+        };
+
+        let mut hits = Vec::new();
+        for searcher in targets {
+            hits.extend(
+                searcher
+                    .search(query, options)
+                    .await?
+                    .into_iter()
+                    .filter(|hit| options.matches_path(&hit.path)),
+            );
+        }
+        if !options.meta.is_empty() {
+            hits = self.retain_meta_matches(hits, options).await;
+        }
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(options.limit);
+        Ok(hits)
+    }
+
+    /// Keep the hits whose files carry the metadata `options` asks for.
+    ///
+    /// Metadata is looked up when searching rather than stored in the index,
+    /// so filters always see the current values.
+    pub async fn retain_meta_matches(
+        &self,
+        hits: Vec<SearchHit>,
+        options: &SearchOptions,
+    ) -> Vec<SearchHit> {
+        let mut metas: HashMap<String, bool> = HashMap::new();
+        let mut kept = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let matches = match metas.get(&hit.path) {
+                Some(matches) => *matches,
+                None => {
+                    let matches = self
+                        .list_meta(&hit.path)
+                        .await
+                        .is_ok_and(|meta| options.matches_meta(&meta));
+                    metas.insert(hit.path.clone(), matches);
+                    matches
+                }
+            };
+            if matches {
+                kept.push(hit);
+            }
+        }
+        kept
     }
 
     /// Resolve a VFS path to its physical filesystem path.
     /// Returns None for non-fs backends (S3, Postgres, Chroma, API).
     pub fn resolve_fs_path(&self, vfs_path: &str) -> Option<std::path::PathBuf> {
-        for mount_config in &self.config.mounts {
+        let state = self.state();
+        for mount_config in &state.config.mounts {
             let mount_path = mount_config.path.trim_end_matches('/');
             if vfs_path == mount_path || vfs_path.starts_with(&format!("{}/", mount_path)) {
                 if let Some(ref backend_name) = mount_config.backend {
-                    if let Some(backend_config) = self.config.backends.get(backend_name) {
+                    if let Some(backend_config) = state.config.backends.get(backend_name) {
                         if let BackendConfig::Fs(fs_config) = backend_config {
                             let relative = if vfs_path == mount_path {
                                 ""
@@ -720,8 +2479,19 @@ impl Vfs {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use openfs_config::ConflictStrategy;
     use tempfile::TempDir;
 
+    /// Stands in for the working directory in [`wal_dir`], for the life of
+    /// the test process.
+    pub(super) fn wal_root() -> PathBuf {
+        static WAL_ROOT: std::sync::OnceLock<TempDir> = std::sync::OnceLock::new();
+        WAL_ROOT
+            .get_or_init(|| TempDir::new().unwrap())
+            .path()
+            .to_path_buf()
+    }
+
     fn make_config(root: &str) -> VfsConfig {
         let yaml = format!(
             r#"
@@ -831,18 +2601,177 @@ mounts:
     }
 
     #[tokio::test]
-    async fn test_vfs_no_mount() {
+    async fn test_vfs_views() {
         let temp_dir = TempDir::new().unwrap();
-        let config = make_config(temp_dir.path().to_str().unwrap());
+        let mut config = make_config(temp_dir.path().to_str().unwrap());
+        config.views.push(openfs_config::ViewConfig {
+            path: "/views/rust-src".to_string(),
+            source: "/workspace".to_string(),
+            glob: Some("src/**/*.rs".to_string()),
+            grep: None,
+        });
         let vfs = Vfs::from_config(config).await.unwrap();
 
-        let result = vfs.read("/nonexistent/file.txt").await;
-        assert!(matches!(result, Err(VfsError::NoMount(_))));
+        vfs.write("/workspace/src/main.rs", b"fn main() {}")
+            .await
+            .unwrap();
+        vfs.write("/workspace/notes.md", b"notes").await.unwrap();
+
+        let entries = vfs.walk("/views/rust-src").await.unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["/views/rust-src/src", "/views/rust-src/src/main.rs"]
+        );
+        assert_eq!(
+            vfs.read("/views/rust-src/src/main.rs").await.unwrap(),
+            b"fn main() {}"
+        );
+        let result = vfs.write("/views/rust-src/src/lib.rs", b"").await;
+        assert!(matches!(result, Err(VfsError::ReadOnly(_))));
     }
 
-    #[tokio::test]
-    async fn test_vfs_effective_config() {
-        let temp_dir = TempDir::new().unwrap();
+    fn make_cached_config(root: &str, warm: &str) -> VfsConfig {
+        let yaml = format!(
+            r#"
+name: test-vfs-cached
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: local
+    mode: write_through
+    cache:
+      warm: [{}]
+"#,
+            root, warm
+        );
+        VfsConfig::from_yaml(&yaml).unwrap()
+    }
+
+    #[test]
+    fn test_cache_config_for_mount() {
+        let config = VfsConfig::from_yaml(
+            r#"
+backends:
+  local:
+    type: fs
+    root: /tmp
+mounts:
+  - path: /a
+    backend: local
+    mode: remote_cached
+    cache:
+      eviction: lru
+  - path: /b
+    backend: local
+    mode: remote_cached
+defaults:
+  cache:
+    max_size: 8MB
+"#,
+        )
+        .unwrap();
+        let defaults = config.defaults.as_ref();
+
+        let a = cache_config_for_mount(MountMode::RemoteCached, &config.mounts[0], defaults);
+        assert_eq!(a.eviction, EvictionPolicy::Lru);
+        assert_eq!(a.max_size, CacheConfig::default().max_size);
+
+        let b = cache_config_for_mount(MountMode::RemoteCached, &config.mounts[1], defaults);
+        assert_eq!(b.eviction, EvictionPolicy::TinyLfu);
+        assert_eq!(b.max_size, 8 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_sync_config_for_mount_retry() {
+        let config = VfsConfig::from_yaml(
+            r#"
+backends:
+  local:
+    type: fs
+    root: /tmp
+mounts:
+  - path: /a
+    backend: local
+    mode: write_back
+    sync:
+      retry:
+        max_attempts: 8
+        backoff: 500ms
+        strategy: linear
+        jitter: false
+  - path: /b
+    backend: local
+    mode: write_back
+"#,
+        )
+        .unwrap();
+
+        let a = sync_config_for_mount(MountMode::WriteBack, config.mounts[0].sync.as_ref(), None);
+        assert_eq!(a.max_retries, 8);
+        assert_eq!(a.retry_backoff, std::time::Duration::from_millis(500));
+        assert!(!a.jitter);
+        let wal = wal_config_for_sync(&a);
+        assert_eq!(wal.max_retries, 8);
+        assert_eq!(wal.base_backoff_secs, 1);
+
+        let b = sync_config_for_mount(MountMode::WriteBack, config.mounts[1].sync.as_ref(), None);
+        assert_eq!(b.max_retries, RetryConfig::default().max_attempts);
+        assert!(b.jitter);
+    }
+
+    #[tokio::test]
+    async fn test_vfs_prefetch_glob() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src/io")).unwrap();
+        std::fs::write(temp_dir.path().join("src/lib.rs"), "lib").unwrap();
+        std::fs::write(temp_dir.path().join("src/io/read.rs"), "read").unwrap();
+        std::fs::write(temp_dir.path().join("notes.md"), "notes").unwrap();
+        let config = make_cached_config(temp_dir.path().to_str().unwrap(), "");
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        let (warmed, failed) = vfs.prefetch_glob("/workspace/src/**/*.rs").await.unwrap();
+        assert_eq!((warmed, failed), (2, 0));
+        assert_eq!(vfs.cache_stats().await.entries, 2);
+
+        let (warmed, failed) = vfs.prefetch_glob("/workspace/missing.md").await.unwrap();
+        assert_eq!((warmed, failed), (0, 1));
+        assert!(vfs.prefetch_glob("/workspace/[").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_cache_warm_on_start() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("docs")).unwrap();
+        std::fs::write(temp_dir.path().join("docs/a.md"), "a").unwrap();
+        std::fs::write(temp_dir.path().join("docs/b.md"), "b").unwrap();
+        std::fs::write(temp_dir.path().join("docs/c.txt"), "c").unwrap();
+        let config = make_cached_config(temp_dir.path().to_str().unwrap(), "\"docs/*.md\"");
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while vfs.cache_stats().await.entries < 2 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(vfs.cache_stats().await.entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_vfs_no_mount() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = make_config(temp_dir.path().to_str().unwrap());
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        let result = vfs.read("/nonexistent/file.txt").await;
+        assert!(matches!(result, Err(VfsError::NoMount(_))));
+    }
+
+    #[tokio::test]
+    async fn test_vfs_effective_config() {
+        let temp_dir = TempDir::new().unwrap();
         let yaml = format!(
             r#"
 backends:
@@ -906,6 +2835,159 @@ mounts:
         assert_eq!(content, b"content");
     }
 
+    #[tokio::test]
+    async fn test_vfs_snapshot_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+        vfs.set_snapshot_dir(snapshot_dir.path());
+        vfs.write("/workspace/a.txt", b"a1").await.unwrap();
+        vfs.write("/workspace/src/b.rs", b"b1").await.unwrap();
+
+        let info = vfs.snapshot("/workspace/", Some("before")).await.unwrap();
+        assert_eq!((info.files, info.bytes), (2, 4));
+        assert_eq!(vfs.snapshots("/workspace").unwrap(), vec![info.clone()]);
+
+        // An agent run edits, deletes and adds files.
+        vfs.write("/workspace/a.txt", b"a2").await.unwrap();
+        vfs.delete("/workspace/src/b.rs").await.unwrap();
+        vfs.write("/workspace/c.txt", b"c").await.unwrap();
+        let diff = vfs
+            .diff_snapshot("/workspace", "before", None)
+            .await
+            .unwrap();
+        assert_eq!(diff.added, vec!["c.txt"]);
+        assert_eq!(diff.modified, vec!["a.txt"]);
+        assert_eq!(diff.deleted, vec!["src/b.rs"]);
+
+        let summary = vfs.restore_snapshot("/workspace", &info.id).await.unwrap();
+        assert_eq!(
+            (summary.written, summary.deleted, summary.unchanged),
+            (2, 1, 0)
+        );
+        assert_eq!(vfs.read("/workspace/a.txt").await.unwrap(), b"a1");
+        assert_eq!(vfs.read("/workspace/src/b.rs").await.unwrap(), b"b1");
+        assert!(!vfs.exists("/workspace/c.txt").await.unwrap());
+        assert!(vfs
+            .diff_snapshot("/workspace", "before", None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            vfs.snapshot("/nope", None).await,
+            Err(VfsError::NoMount(_))
+        ));
+        assert!(matches!(
+            vfs.restore_snapshot("/workspace", "missing").await,
+            Err(VfsError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_vfs_read_at_and_history_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+        vfs.set_snapshot_dir(snapshot_dir.path());
+        vfs.write("/workspace/a.txt", b"v1").await.unwrap();
+        vfs.write("/workspace/src/b.rs", b"b").await.unwrap();
+        let first = vfs.snapshot("/workspace", Some("v1")).await.unwrap();
+        vfs.write("/workspace/a.txt", b"v2").await.unwrap();
+        let second = vfs.snapshot("/workspace", None).await.unwrap();
+        vfs.write("/workspace/a.txt", b"v3").await.unwrap();
+
+        let by_label = HistoryPoint::parse("v1");
+        assert_eq!(
+            vfs.read_at("/workspace/a.txt", &by_label).await.unwrap(),
+            b"v1"
+        );
+        let at = format!("@{}", second.created_at.to_rfc3339());
+        assert_eq!(
+            vfs.read_at("/workspace/a.txt", &HistoryPoint::parse(&at))
+                .await
+                .unwrap(),
+            b"v2"
+        );
+        assert!(matches!(
+            vfs.read_at("/workspace/c.txt", &by_label).await,
+            Err(VfsError::NotFound(_))
+        ));
+
+        // The same states through ordinary paths, read-only.
+        let path = format!("/.history/{}/workspace/a.txt", first.id);
+        assert_eq!(vfs.read(&path).await.unwrap(), b"v1");
+        assert_eq!(
+            vfs.read("/.history/v1/workspace/src/b.rs").await.unwrap(),
+            b"b"
+        );
+        let names = |entries: Vec<Entry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.name).collect()
+        };
+        assert_eq!(
+            names(vfs.list("/.history").await.unwrap()),
+            vec![first.id.clone(), second.id.clone(), "v1".to_string()]
+        );
+        assert_eq!(
+            names(vfs.list("/.history/v1").await.unwrap()),
+            vec!["workspace"]
+        );
+        assert_eq!(
+            names(vfs.list("/.history/v1/workspace").await.unwrap()),
+            vec!["src", "a.txt"]
+        );
+        let entry = vfs.stat("/.history/v1/workspace/a.txt").await.unwrap();
+        assert_eq!((entry.is_dir, entry.size), (false, Some(2)));
+        assert!(vfs.stat("/.history/v1/workspace/src").await.unwrap().is_dir);
+        assert!(!vfs.exists("/.history/v1/workspace/c.txt").await.unwrap());
+        assert!(!vfs.exists("/.history/nope").await.unwrap());
+        assert!(vfs.write(&path, b"x").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_log_files_append_segments_under_write_back() {
+        let mount_path = "/wb_log_test";
+        let _ = std::fs::remove_file(wal_path_for_mount(mount_path).unwrap());
+        let temp_dir = TempDir::new().unwrap();
+        let mut config =
+            make_write_back_config(temp_dir.path().to_str().unwrap(), mount_path, "50ms");
+        config.mounts[0].log = Some(openfs_config::LogConfig {
+            paths: vec!["**/*.jsonl".to_string()],
+            compact_after: None,
+        });
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        let appends = (0..10).map(|n| {
+            let vfs = &vfs;
+            async move {
+                let line = format!("{{\"n\":{}}}\n", n);
+                vfs.append("/wb_log_test/agents/run.jsonl", line.as_bytes())
+                    .await
+            }
+        });
+        for result in futures::future::join_all(appends).await {
+            result.unwrap();
+        }
+
+        // Stored as segments right away rather than rewritten on flush.
+        let segments = temp_dir.path().join("agents/.run.jsonl.segments");
+        assert_eq!(std::fs::read_dir(&segments).unwrap().count(), 10);
+        let content = vfs.read("/wb_log_test/agents/run.jsonl").await.unwrap();
+        assert_eq!(String::from_utf8(content).unwrap().lines().count(), 10);
+        let entries = vfs.list("/wb_log_test/agents").await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "run.jsonl");
+        assert!(vfs.sync_status().await.unwrap().is_empty());
+
+        vfs.flush_write_back().await.unwrap();
+        drop(vfs);
+        let _ = std::fs::remove_file(wal_path_for_mount(mount_path).unwrap());
+    }
+
     #[tokio::test]
     async fn test_vfs_flush_write_back() {
         let mount_path = "/wb_flush_test";
@@ -923,6 +3005,198 @@ mounts:
 
         let on_disk = std::fs::read(temp_dir.path().join("file.txt")).unwrap();
         assert_eq!(on_disk, b"flush me");
+
+        let statuses = vfs.sync_status().await.unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].path, "/wb_flush_test/file.txt");
+        assert_eq!(statuses[0].state, crate::PathSyncState::Synced);
+        assert!(statuses[0].last_synced.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_write_back_keeps_both_on_conflict() {
+        let mount_path = "/wb_conflict_test";
+        let wal_path = wal_path_for_mount(mount_path).unwrap();
+        let _ = std::fs::remove_file(&wal_path);
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config =
+            make_write_back_config(temp_dir.path().to_str().unwrap(), mount_path, "24h");
+        config.mounts[0].sync.as_mut().unwrap().conflict = ConflictStrategy::KeepBoth;
+        std::fs::write(temp_dir.path().join("notes.txt"), "v1\n").unwrap();
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        assert_eq!(
+            vfs.read("/wb_conflict_test/notes.txt").await.unwrap(),
+            b"v1\n"
+        );
+        // Another writer changes the file behind the cache.
+        std::fs::write(temp_dir.path().join("notes.txt"), "remote v2\n").unwrap();
+        vfs.write("/wb_conflict_test/notes.txt", b"local\n")
+            .await
+            .unwrap();
+        vfs.flush_write_back().await.unwrap();
+
+        let on_disk = std::fs::read(temp_dir.path().join("notes.txt")).unwrap();
+        assert_eq!(on_disk, b"remote v2\n");
+        let conflicts = vfs.sync_conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, "/wb_conflict_test/notes.txt");
+        let copy = conflicts[0].resolution.copy().unwrap();
+        assert!(
+            copy.starts_with("/wb_conflict_test/notes.conflict-"),
+            "{}",
+            copy
+        );
+        let copy_on_disk = temp_dir
+            .path()
+            .join(copy.trim_start_matches("/wb_conflict_test/"));
+        assert_eq!(std::fs::read(copy_on_disk).unwrap(), b"local\n");
+        assert_eq!(vfs.sync_statuses().await.unwrap()[0].conflicts, 1);
+        assert_eq!(vfs.clear_sync_conflicts().unwrap(), 1);
+
+        drop(vfs);
+        let _ = std::fs::remove_file(&wal_path);
+    }
+
+    #[tokio::test]
+    async fn test_vfs_replayed_write_back_detects_conflict_after_restart() {
+        let mount_path = "/wb_replay_conflict_test";
+        let wal_path = wal_path_for_mount(mount_path).unwrap();
+        let _ = std::fs::remove_file(&wal_path);
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config =
+            make_write_back_config(temp_dir.path().to_str().unwrap(), mount_path, "24h");
+        config.mounts[0].sync.as_mut().unwrap().conflict = ConflictStrategy::KeepBoth;
+        std::fs::write(temp_dir.path().join("notes.txt"), "v1\n").unwrap();
+
+        {
+            let vfs = Vfs::from_config(config.clone()).await.unwrap();
+            assert_eq!(
+                vfs.read("/wb_replay_conflict_test/notes.txt").await.unwrap(),
+                b"v1\n"
+            );
+            vfs.write("/wb_replay_conflict_test/notes.txt", b"local\n")
+                .await
+                .unwrap();
+            // Intentionally drop without flush to simulate abrupt process end.
+        }
+        // Another writer changes the file before the restart.
+        std::fs::write(temp_dir.path().join("notes.txt"), "remote v2\n").unwrap();
+
+        let vfs = Vfs::from_config(config).await.unwrap();
+        let on_disk = std::fs::read(temp_dir.path().join("notes.txt")).unwrap();
+        assert_eq!(on_disk, b"remote v2\n");
+        let conflicts = vfs.sync_conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        let copy = conflicts[0].resolution.copy().unwrap();
+        let copy_on_disk = temp_dir
+            .path()
+            .join(copy.trim_start_matches("/wb_replay_conflict_test/"));
+        assert_eq!(std::fs::read(copy_on_disk).unwrap(), b"local\n");
+
+        drop(vfs);
+        let _ = std::fs::remove_file(&wal_path);
+    }
+
+    #[tokio::test]
+    async fn test_vfs_two_way_pulls_remote_changes() {
+        let mount_path = "/two_way_test";
+        let wal_path = wal_path_for_mount(mount_path).unwrap();
+        let _ = std::fs::remove_file(&wal_path);
+
+        let temp_dir = TempDir::new().unwrap();
+        let yaml = format!(
+            r#"
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: {}
+    backend: local
+    mode: two_way
+    sync:
+      interval: 100ms
+      pull_interval: 1h
+"#,
+            temp_dir.path().display(),
+            mount_path
+        );
+        std::fs::write(temp_dir.path().join("a.txt"), "v1\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "v1\n").unwrap();
+        let vfs = Vfs::from_config(VfsConfig::from_yaml(&yaml).unwrap())
+            .await
+            .unwrap();
+        vfs.pull_remote_changes().await.unwrap();
+        assert_eq!(vfs.read("/two_way_test/a.txt").await.unwrap(), b"v1\n");
+        assert_eq!(vfs.read("/two_way_test/b.txt").await.unwrap(), b"v1\n");
+
+        // Another writer changes the remote behind the cache.
+        std::fs::write(temp_dir.path().join("a.txt"), "remote v2\n").unwrap();
+        std::fs::remove_file(temp_dir.path().join("b.txt")).unwrap();
+        // The scheduled first pull may race this one; either finds the changes.
+        let summaries = vfs.pull_remote_changes().await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].0, mount_path);
+        assert_eq!(
+            vfs.read("/two_way_test/a.txt").await.unwrap(),
+            b"remote v2\n"
+        );
+        assert!(vfs.read("/two_way_test/b.txt").await.is_err());
+
+        // Local writes are pushed as in write-back mode.
+        vfs.write("/two_way_test/c.txt", b"local").await.unwrap();
+        vfs.flush_write_back().await.unwrap();
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("c.txt")).unwrap(),
+            b"local"
+        );
+
+        drop(vfs);
+        for suffix in ["", "-shm", "-wal"] {
+            let _ = std::fs::remove_file(format!("{}{}", wal_path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vfs_pull_mirror_publishes_remote_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let yaml = format!(
+            r#"
+backends:
+  local:
+    type: fs
+    root: {}
+mounts:
+  - path: /mirror
+    backend: local
+    mode: pull_mirror
+    sync:
+      pull_interval: 1h
+"#,
+            temp_dir.path().display()
+        );
+        std::fs::write(temp_dir.path().join("a.txt"), "v1\n").unwrap();
+        let vfs = Vfs::from_config(VfsConfig::from_yaml(&yaml).unwrap())
+            .await
+            .unwrap();
+        let mut changes = vfs.subscribe_remote_changes();
+        vfs.pull_remote_changes().await.unwrap();
+        assert_eq!(vfs.read("/mirror/a.txt").await.unwrap(), b"v1\n");
+
+        std::fs::write(temp_dir.path().join("a.txt"), "remote v2\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "new\n").unwrap();
+        vfs.pull_remote_changes().await.unwrap();
+        assert_eq!(vfs.read("/mirror/a.txt").await.unwrap(), b"remote v2\n");
+
+        let mut events = [changes.try_recv().unwrap(), changes.try_recv().unwrap()];
+        events.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(events[0].path, "/mirror/a.txt");
+        assert_eq!(events[1].path, "/mirror/b.txt");
+        assert!(!events[0].deleted && !events[1].deleted);
+        assert!(changes.try_recv().is_err());
     }
 
     #[tokio::test]
@@ -952,4 +3226,615 @@ mounts:
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn test_vfs_apply_patch() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        vfs.write("/workspace/notes.md", b"# Notes\n\n- one\n- two\n")
+            .await
+            .unwrap();
+        let diff =
+            "--- a/notes.md\n+++ b/notes.md\n@@ -3,2 +3,3 @@\n - one\n-- two\n+- 2\n+- three\n";
+        let summary = vfs.apply_patch("/workspace/notes.md", diff).await.unwrap();
+        assert_eq!((summary.added, summary.removed), (2, 1));
+        assert_eq!(
+            vfs.read("/workspace/notes.md").await.unwrap(),
+            b"# Notes\n\n- one\n- 2\n- three\n"
+        );
+
+        // Re-applying conflicts and leaves the file untouched.
+        let err = vfs
+            .apply_patch("/workspace/notes.md", diff)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            VfsError::Patch {
+                source: PatchError::Conflict { line: 4, .. },
+                ..
+            }
+        ));
+
+        let create = "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+created\n";
+        vfs.apply_patch("/workspace/new.txt", create).await.unwrap();
+        assert_eq!(vfs.read("/workspace/new.txt").await.unwrap(), b"created\n");
+    }
+
+    #[tokio::test]
+    async fn test_vfs_read_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        let log: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        vfs.write("/workspace/app.log", log.as_bytes())
+            .await
+            .unwrap();
+
+        let head = vfs
+            .read_lines("/workspace/app.log", LineRange::Head(2))
+            .await
+            .unwrap();
+        assert_eq!(head, b"line 0\nline 1\n");
+        let tail = vfs
+            .read_lines("/workspace/app.log", LineRange::Tail(2))
+            .await
+            .unwrap();
+        assert_eq!(tail, b"line 19998\nline 19999\n");
+        let span = vfs
+            .read_lines("/workspace/app.log", LineRange::Span(10_000..10_002))
+            .await
+            .unwrap();
+        assert_eq!(span, b"line 10000\nline 10001\n");
+
+        vfs.write("/workspace/short.txt", b"a\nb").await.unwrap();
+        let tail = vfs
+            .read_lines("/workspace/short.txt", LineRange::Tail(5))
+            .await
+            .unwrap();
+        assert_eq!(tail, b"a\nb");
+
+        let range = vfs
+            .read_range("/workspace/app.log", 7, Some(6))
+            .await
+            .unwrap();
+        assert_eq!(range, b"line 1");
+        let past_end = vfs
+            .read_range("/workspace/short.txt", 10, None)
+            .await
+            .unwrap();
+        assert!(past_end.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        vfs.write("/workspace/a.txt", b"12345").await.unwrap();
+        vfs.write("/workspace/src/main.rs", b"fn main() {}")
+            .await
+            .unwrap();
+        vfs.write("/workspace/src/util/mod.rs", b"//")
+            .await
+            .unwrap();
+
+        let usage = vfs.usage("/workspace/").await.unwrap();
+        assert_eq!(usage.path, "/workspace");
+        assert_eq!(usage.bytes, 19);
+        assert_eq!(usage.files, 3);
+        assert_eq!(usage.children.len(), 1);
+        let src = &usage.children[0];
+        assert_eq!(src.path, "/workspace/src");
+        assert_eq!((src.bytes, src.files), (14, 2));
+        assert_eq!(src.children[0].path, "/workspace/src/util");
+
+        let file = vfs.usage("/workspace/a.txt").await.unwrap();
+        assert_eq!((file.bytes, file.files), (5, 1));
+        assert!(vfs.usage("/workspace/missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_reload_diffs_mounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let config = |extra: &str| {
+            VfsConfig::from_yaml(&format!(
+                "backends:\n  local:\n    type: fs\n    root: {}\nmounts:\n  - path: /workspace\n    backend: local\n{}",
+                root, extra
+            ))
+            .unwrap()
+        };
+        let vfs = Vfs::from_config(config("  - path: /scratch\n    backend: local\n"))
+            .await
+            .unwrap();
+        vfs.write("/workspace/a.txt", b"a").await.unwrap();
+        let workspace = vfs.state().mount_runtimes[0].clone();
+        let in_flight = vfs.state();
+
+        let summary = vfs
+            .reload(config(
+                "    collection: docs\n  - path: /docs\n    backend: local\n    read_only: true\n",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(summary.added, vec!["/docs"]);
+        assert_eq!(summary.removed, vec!["/scratch"]);
+        assert_eq!(summary.updated, vec!["/workspace"]);
+
+        // A collection change keeps the mount's runtime and cache.
+        assert!(Arc::ptr_eq(&vfs.state().mount_runtimes[0], &workspace));
+        assert_eq!(vfs.read("/docs/a.txt").await.unwrap(), b"a");
+        assert!(matches!(
+            vfs.write("/docs/b.txt", b"b").await,
+            Err(VfsError::ReadOnly(_))
+        ));
+        assert!(matches!(
+            vfs.read("/scratch/a.txt").await,
+            Err(VfsError::NoMount(_))
+        ));
+        // Work that started before the reload still sees the old mounts.
+        assert!(in_flight.router.resolve("/scratch/a.txt").is_ok());
+
+        // An invalid config changes nothing.
+        let mut invalid = config("");
+        invalid.mounts[0].backend = Some("missing".to_string());
+        assert!(vfs.reload(invalid).await.is_err());
+        assert!(vfs.exists("/docs/a.txt").await.unwrap());
+
+        assert!(vfs
+            .reload(vfs.effective_config().as_ref().clone())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_reload_flushes_removed_write_back_mount() {
+        let mount_path = "/wb_reload_test";
+        let wal_path = wal_path_for_mount(mount_path).unwrap();
+        let _ = std::fs::remove_file(&wal_path);
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let vfs = Vfs::from_config(make_write_back_config(root, mount_path, "24h"))
+            .await
+            .unwrap();
+        vfs.write("/wb_reload_test/file.txt", b"flush me")
+            .await
+            .unwrap();
+
+        let summary = vfs.reload(make_config(root)).await.unwrap();
+        assert_eq!(summary.removed, vec![mount_path]);
+        let on_disk = std::fs::read(temp_dir.path().join("file.txt")).unwrap();
+        assert_eq!(on_disk, b"flush me");
+    }
+
+    #[tokio::test]
+    async fn test_vfs_reload_failure_keeps_old_mounts_syncing() {
+        let mount_path = "/wb_reload_keep";
+        let broken_path = "/wb_reload_broken";
+        let _ = std::fs::remove_file(wal_path_for_mount(mount_path).unwrap());
+        // A directory where the WAL database should be fails the build.
+        let broken_wal = wal_path_for_mount(broken_path).unwrap();
+        let _ = std::fs::remove_file(&broken_wal);
+        std::fs::create_dir_all(&broken_wal).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let vfs = Vfs::from_config(make_write_back_config(root, mount_path, "100ms"))
+            .await
+            .unwrap();
+
+        // Rebuilds the write-back mount and adds one that cannot start.
+        let mut next = make_write_back_config(root, mount_path, "200ms");
+        let mut broken = next.mounts[0].clone();
+        broken.path = broken_path.to_string();
+        next.mounts.push(broken);
+        assert!(vfs.reload(next).await.is_err());
+        std::fs::remove_dir(&broken_wal).unwrap();
+
+        vfs.write("/wb_reload_keep/after.txt", b"still synced")
+            .await
+            .unwrap();
+        let on_disk = temp_dir.path().join("after.txt");
+        for _ in 0..50 {
+            if on_disk.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(std::fs::read(&on_disk).unwrap(), b"still synced");
+    }
+
+    #[tokio::test]
+    async fn test_vfs_probe_mounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("data");
+        std::fs::create_dir(&root).unwrap();
+        let config = make_config(root.to_str().unwrap());
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        let health = vfs.probe_mounts(std::time::Duration::from_secs(5)).await;
+        assert_eq!(health.len(), 1);
+        assert_eq!(health[0].mount_path, "/workspace");
+        assert_eq!(health[0].backend_name, "local");
+        assert!(health[0].healthy);
+        assert!(health[0].error.is_none());
+
+        std::fs::remove_dir(&root).unwrap();
+        let health = vfs.probe_mounts(std::time::Duration::from_secs(5)).await;
+        assert!(!health[0].healthy);
+        assert!(health[0].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_set_modified() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        vfs.write("/workspace/a.txt", b"hello").await.unwrap();
+        let modified = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert!(vfs.set_modified("/workspace/a.txt", modified).await.unwrap());
+
+        let entry = vfs.stat("/workspace/a.txt").await.unwrap();
+        assert_eq!(entry.modified, Some(modified));
+        assert!(vfs.set_modified("/workspace/missing.txt", modified).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_walk_and_checksum() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        vfs.write("/workspace/b.txt", b"same").await.unwrap();
+        vfs.write("/workspace/a/c.txt", b"same").await.unwrap();
+
+        let paths: Vec<String> = vfs
+            .walk("/workspace")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["/workspace/a", "/workspace/a/c.txt", "/workspace/b.txt"]
+        );
+
+        let checksum = vfs.checksum("/workspace/b.txt").await.unwrap();
+        assert_eq!(checksum.len(), 64);
+        assert_eq!(checksum, vfs.checksum("/workspace/a/c.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_str_replace() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        vfs.write(
+            "/workspace/config.toml",
+            b"debug = false\nverbose = false\n",
+        )
+        .await
+        .unwrap();
+        let line = vfs
+            .str_replace(
+                "/workspace/config.toml",
+                "verbose = false",
+                "verbose = true",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(line, 2);
+        assert_eq!(
+            vfs.read("/workspace/config.toml").await.unwrap(),
+            b"debug = false\nverbose = true\n"
+        );
+
+        let err = vfs
+            .str_replace("/workspace/config.toml", "= ", "= !", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            VfsError::Patch {
+                source: PatchError::AmbiguousMatch { count: 2 },
+                ..
+            }
+        ));
+        assert!(vfs
+            .str_replace("/workspace/missing.toml", "a", "b", None)
+            .await
+            .is_err());
+    }
+
+    /// Searcher that returns one fixed hit per configured path.
+    struct FixedSearcher {
+        hits: Vec<(&'static str, f32)>,
+    }
+
+    #[async_trait]
+    impl Searcher for FixedSearcher {
+        async fn search(
+            &self,
+            _query: &str,
+            _options: &SearchOptions,
+        ) -> Result<Vec<SearchHit>, VfsError> {
+            Ok(self
+                .hits
+                .iter()
+                .map(|(path, score)| SearchHit {
+                    path: path.to_string(),
+                    score: *score,
+                    dense_score: None,
+                    sparse_score: None,
+                    content: String::new(),
+                    start_line: 1,
+                    end_line: 1,
+                    metadata: HashMap::new(),
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vfs_search_merges_mount_searchers() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+
+        let options = SearchOptions::default();
+        assert!(vfs.search("query", &options).await.is_err());
+
+        vfs.set_searcher(
+            "/workspace",
+            Arc::new(FixedSearcher {
+                hits: vec![("/workspace/a.md", 0.4), ("/workspace/b.md", 0.9)],
+            }),
+        );
+        vfs.set_searcher(
+            "/notes",
+            Arc::new(FixedSearcher {
+                hits: vec![("/notes/c.md", 0.7)],
+            }),
+        );
+
+        let hits = vfs.search("query", &options).await.unwrap();
+        let paths: Vec<&str> = hits.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, ["/workspace/b.md", "/notes/c.md", "/workspace/a.md"]);
+
+        let options = SearchOptions {
+            limit: 1,
+            path_prefix: Some("/workspace".to_string()),
+            ..Default::default()
+        };
+        let hits = vfs.search("query", &options).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "/workspace/b.md");
+    }
+
+    #[tokio::test]
+    async fn test_vfs_file_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+        vfs.write("/workspace/a.md", b"a").await.unwrap();
+        vfs.write("/workspace/b.md", b"b").await.unwrap();
+
+        vfs.set_meta("/workspace/a.md", "status", "reviewed")
+            .await
+            .unwrap();
+        vfs.set_meta("/workspace/a.md", "owner", "ana")
+            .await
+            .unwrap();
+        assert_eq!(
+            vfs.get_meta("/workspace/a.md", "status").await.unwrap(),
+            Some("reviewed".to_string())
+        );
+        let entry = vfs.stat("/workspace/a.md").await.unwrap();
+        assert_eq!(entry.meta.len(), 2);
+        assert!(vfs
+            .set_meta("/workspace/a.md", "Bad Key", "x")
+            .await
+            .is_err());
+        assert!(vfs.set_meta("/workspace/none.md", "k", "v").await.is_err());
+
+        // Metadata moves with the file and is dropped with it.
+        vfs.rename("/workspace/a.md", "/workspace/c.md")
+            .await
+            .unwrap();
+        assert_eq!(vfs.list_meta("/workspace/c.md").await.unwrap().len(), 2);
+        assert!(vfs.remove_meta("/workspace/c.md", "owner").await.unwrap());
+        assert!(!vfs.remove_meta("/workspace/c.md", "owner").await.unwrap());
+
+        vfs.set_searcher(
+            "/workspace",
+            Arc::new(FixedSearcher {
+                hits: vec![("/workspace/b.md", 0.9), ("/workspace/c.md", 0.4)],
+            }),
+        );
+        let mut options = SearchOptions::default();
+        options
+            .meta
+            .insert("status".to_string(), "reviewed".to_string());
+        let hits = vfs.search("query", &options).await.unwrap();
+        let paths: Vec<&str> = hits.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, ["/workspace/c.md"]);
+
+        vfs.delete("/workspace/c.md").await.unwrap();
+        vfs.write("/workspace/c.md", b"c").await.unwrap();
+        assert!(vfs.list_meta("/workspace/c.md").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_circuit_breakers() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = make_config(temp_dir.path().to_str().unwrap());
+        config.mounts[0].retry = Some(openfs_config::RequestRetryConfig {
+            circuit_breaker: Some(openfs_config::CircuitBreakerConfig::default()),
+            ..Default::default()
+        });
+        let vfs = Vfs::from_config(config).await.unwrap();
+
+        vfs.write("/workspace/a.txt", b"hi").await.unwrap();
+        assert_eq!(vfs.read("/workspace/a.txt").await.unwrap(), b"hi");
+        let breakers = vfs.circuit_breakers();
+        assert_eq!(breakers.len(), 1);
+        assert_eq!(breakers[0].0, "/workspace");
+        assert_eq!(breakers[0].1.state, crate::CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_vfs_replicates_to_replicas() {
+        let primary_dir = TempDir::new().unwrap();
+        let replica_dir = TempDir::new().unwrap();
+        let yaml = format!(
+            r#"
+backends:
+  primary:
+    type: fs
+    root: {}
+  replica:
+    type: fs
+    root: {}
+mounts:
+  - path: /workspace
+    backend: primary
+    replicas:
+      backends: [replica]
+"#,
+            primary_dir.path().display(),
+            replica_dir.path().display()
+        );
+        let vfs = Vfs::from_config(VfsConfig::from_yaml(&yaml).unwrap())
+            .await
+            .unwrap();
+
+        vfs.write("/workspace/a.txt", b"hi").await.unwrap();
+        let stats = vfs.replication_stats().await;
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            (stats[0].0.as_str(), stats[0].1.as_str()),
+            ("/workspace", "replica")
+        );
+
+        vfs.flush_write_back().await.unwrap();
+        assert_eq!(
+            std::fs::read(replica_dir.path().join("a.txt")).unwrap(),
+            b"hi"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vfs_tags_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+        vfs.write("/workspace/notes/a.md", b"a").await.unwrap();
+        vfs.write("/workspace/b.md", b"b").await.unwrap();
+        vfs.write("/workspace/c.md", b"c").await.unwrap();
+
+        vfs.add_tag("/workspace/notes/a.md", "important")
+            .await
+            .unwrap();
+        vfs.add_tag("/workspace/b.md", "important").await.unwrap();
+        vfs.add_tag("/workspace/b.md", "draft").await.unwrap();
+        assert!(vfs.add_tag("/workspace/c.md", "Not/Valid").await.is_err());
+        assert_eq!(
+            vfs.tags("/workspace/b.md").await.unwrap(),
+            ["draft", "important"]
+        );
+        assert_eq!(
+            vfs.tagged("important").await.unwrap(),
+            ["/workspace/b.md", "/workspace/notes/a.md"]
+        );
+
+        let names =
+            |entries: Vec<Entry>| -> Vec<String> { entries.into_iter().map(|e| e.name).collect() };
+        assert_eq!(
+            names(vfs.list("/tags").await.unwrap()),
+            ["draft", "important"]
+        );
+        assert_eq!(
+            names(vfs.list("/tags/important/workspace").await.unwrap()),
+            ["notes", "b.md"]
+        );
+        assert_eq!(
+            vfs.read("/tags/important/workspace/notes/a.md")
+                .await
+                .unwrap(),
+            b"a"
+        );
+        assert!(vfs.read("/tags/important/workspace/c.md").await.is_err());
+        assert!(matches!(
+            vfs.write("/tags/important/workspace/b.md", b"x").await,
+            Err(VfsError::ReadOnly(_))
+        ));
+
+        assert!(vfs
+            .remove_tag("/workspace/b.md", "important")
+            .await
+            .unwrap());
+        assert_eq!(
+            names(vfs.list("/tags/important/workspace").await.unwrap()),
+            ["notes"]
+        );
+        assert!(!vfs.exists("/tags/draft/workspace/notes").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_vfs_typed_reads_and_content_type() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Task {
+            id: u32,
+            done: bool,
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let vfs = Vfs::from_config(make_config(temp_dir.path().to_str().unwrap()))
+            .await
+            .unwrap();
+        vfs.write("/workspace/task.json", br#"{"id": 7, "done": true}"#)
+            .await
+            .unwrap();
+        vfs.write("/workspace/task.yaml", b"id: 8\ndone: false\n")
+            .await
+            .unwrap();
+        vfs.write("/workspace/logo.png", b"\x89PNG\r\n\x1a\n\0\0")
+            .await
+            .unwrap();
+
+        let task: Task = vfs.read_json("/workspace/task.json").await.unwrap();
+        assert_eq!(task, Task { id: 7, done: true });
+        let task: Task = vfs.read_yaml("/workspace/task.yaml").await.unwrap();
+        assert_eq!(task, Task { id: 8, done: false });
+        assert!(vfs.read_json::<Task>("/workspace/task.yaml").await.is_err());
+
+        assert!(vfs.is_binary("/workspace/logo.png").await.unwrap());
+        assert!(!vfs.is_binary("/workspace/task.json").await.unwrap());
+        assert_eq!(
+            vfs.content_type("/workspace/logo.png").await.unwrap(),
+            "image/png"
+        );
+        let entry = vfs.stat("/workspace/task.yaml").await.unwrap();
+        assert_eq!(entry.content_type.as_deref(), Some("application/yaml"));
+    }
 }
@@ -0,0 +1,312 @@
+//! Views: read-only virtual directories defined in config.
+//!
+//! A [`ViewBackend`] is routed like any other mount. It has no storage of its
+//! own; listing it walks the view's source and keeps the files its
+//! [`ViewFilter`] selects, so the view is always current. Files appear at
+//! their path relative to the source and are read from the source mount.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use openfs_config::{ViewConfig, ViewFilter};
+use openfs_core::{Backend, BackendError, Entry, FileMeta, VfsError};
+
+use crate::router::{Mount, Router};
+
+/// Backend serving one view over the mounts it was built with.
+pub struct ViewBackend {
+    path: String,
+    source: String,
+    sources: Router,
+    filter: ViewFilter,
+}
+
+impl ViewBackend {
+    /// Create the view, reading from `mounts`.
+    pub fn new(view: &ViewConfig, mounts: Vec<Mount>) -> Result<Self, VfsError> {
+        Ok(ViewBackend {
+            path: view.path.clone(),
+            source: view.source.trim_end_matches('/').to_string(),
+            sources: Router::new(mounts),
+            filter: ViewFilter::new(view)?,
+        })
+    }
+
+    /// The source backend and the path within it for a view-relative path.
+    fn resolve(&self, relative: &str) -> Result<(&dyn Backend, String), BackendError> {
+        let path = if relative.is_empty() {
+            self.source.clone()
+        } else {
+            format!("{}/{}", self.source, relative)
+        };
+        let (backend, inner, _) = self
+            .sources
+            .resolve(&path)
+            .map_err(|_| BackendError::NotFound(path.clone()))?;
+        Ok((backend, inner))
+    }
+
+    /// Whether the file at `relative` belongs to the view.
+    async fn selects(&self, backend: &dyn Backend, inner: &str, relative: &str) -> bool {
+        if !self.filter.matches_path(relative) {
+            return false;
+        }
+        if !self.filter.needs_content() {
+            return true;
+        }
+        match backend.read(inner).await {
+            Ok(content) => self.filter.matches_content(&content),
+            Err(_) => false,
+        }
+    }
+
+    /// Every file in the view, with paths relative to the source.
+    async fn files(&self) -> Result<Vec<Entry>, BackendError> {
+        let (backend, root) = self.resolve("")?;
+        let mut files = Vec::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            let inner_dir = join(&root, &dir);
+            for entry in backend.list(&inner_dir).await? {
+                let relative = join(&dir, &entry.name);
+                if entry.is_dir {
+                    pending.push(relative);
+                } else if self
+                    .selects(backend, &join(&root, &relative), &relative)
+                    .await
+                {
+                    files.push(Entry {
+                        path: relative,
+                        ..entry
+                    });
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn read_only(path: &str) -> BackendError {
+    BackendError::PermissionDenied(format!("'{}' is in a read-only view", path))
+}
+
+#[async_trait]
+impl Backend for ViewBackend {
+    async fn read(&self, path: &str) -> Result<Vec<u8>, BackendError> {
+        let relative = path.trim_matches('/');
+        let (backend, inner) = self.resolve(relative)?;
+        if !self.filter.matches_path(relative) {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let content = backend.read(&inner).await?;
+        if !self.filter.matches_content(&content) {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        Ok(content)
+    }
+
+    async fn read_range(
+        &self,
+        path: &str,
+        offset: u64,
+        len: Option<u64>,
+    ) -> Result<Vec<u8>, BackendError> {
+        let relative = path.trim_matches('/');
+        if self.filter.needs_content() {
+            let content = self.read(relative).await?;
+            return Ok(openfs_core::slice_range(&content, offset, len).to_vec());
+        }
+        if !self.filter.matches_path(relative) {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        let (backend, inner) = self.resolve(relative)?;
+        backend.read_range(&inner, offset, len).await
+    }
+
+    async fn write(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn append(&self, path: &str, _content: &[u8]) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn rename(&self, from: &str, _to: &str) -> Result<(), BackendError> {
+        Err(read_only(from))
+    }
+
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        let entry = self.stat(path).await?;
+        if entry.is_dir {
+            return Err(BackendError::NotFound(path.to_string()));
+        }
+        Ok(entry.meta)
+    }
+
+    async fn write_meta(&self, path: &str, _meta: &FileMeta) -> Result<(), BackendError> {
+        Err(read_only(path))
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<Entry>, BackendError> {
+        let relative = path.trim_matches('/');
+        let prefix = join(relative, "");
+        let mut children = BTreeMap::new();
+        let mut is_file = false;
+        for file in self.files().await? {
+            if file.path == relative {
+                is_file = true;
+                continue;
+            }
+            let Some(rest) = file.path.strip_prefix(&prefix) else {
+                continue;
+            };
+            let entry = match rest.split_once('/') {
+                Some((dir, _)) => Entry::dir(join(relative, dir), dir.to_string(), None),
+                None => file.clone(),
+            };
+            children
+                .entry((!entry.is_dir, entry.name.clone()))
+                .or_insert(entry);
+        }
+
+        if children.is_empty() && !relative.is_empty() {
+            return Err(if is_file {
+                BackendError::NotADirectory(path.to_string())
+            } else {
+                BackendError::NotFound(path.to_string())
+            });
+        }
+        // Directories first, then files, each alphabetically.
+        Ok(children.into_values().collect())
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, BackendError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(BackendError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn stat(&self, path: &str) -> Result<Entry, BackendError> {
+        let relative = path.trim_matches('/');
+        if relative.is_empty() {
+            let name = self.path.rsplit('/').next().unwrap_or_default();
+            return Ok(Entry::dir(String::new(), name.to_string(), None));
+        }
+
+        let (backend, inner) = self.resolve(relative)?;
+        if let Ok(entry) = backend.stat(&inner).await {
+            if !entry.is_dir && self.selects(backend, &inner, relative).await {
+                return Ok(Entry {
+                    path: relative.to_string(),
+                    ..entry
+                });
+            }
+        }
+        // A directory exists in the view when it holds a selected file.
+        let prefix = join(relative, "");
+        if self
+            .files()
+            .await?
+            .iter()
+            .any(|f| f.path.starts_with(&prefix))
+        {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            return Ok(Entry::dir(relative.to_string(), name.to_string(), None));
+        }
+        Err(BackendError::NotFound(path.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+    use std::sync::Arc;
+
+    async fn view(glob: Option<&str>, grep: Option<&str>) -> ViewBackend {
+        let backend = MemoryBackend::new();
+        backend
+            .write("src/lib.rs", b"pub fn a() {}\n")
+            .await
+            .unwrap();
+        backend
+            .write("src/io/read.rs", b"// TODO: error handling\n")
+            .await
+            .unwrap();
+        backend.write("README.md", b"TODO\n").await.unwrap();
+        let config = ViewConfig {
+            path: "/views/test".to_string(),
+            source: "/workspace".to_string(),
+            glob: glob.map(str::to_string),
+            grep: grep.map(str::to_string),
+        };
+        let mounts = vec![Mount {
+            path: "/workspace".to_string(),
+            backend: Arc::new(backend),
+            read_only: false,
+            path_policy: Default::default(),
+        }];
+        ViewBackend::new(&config, mounts).unwrap()
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_glob_view() {
+        let view = view(Some("src/**/*.rs"), None).await;
+        assert_eq!(names(&view.list("").await.unwrap()), vec!["src"]);
+        assert_eq!(
+            names(&view.list("src").await.unwrap()),
+            vec!["io", "lib.rs"]
+        );
+        assert_eq!(
+            view.read("src/io/read.rs").await.unwrap(),
+            b"// TODO: error handling\n"
+        );
+        assert!(view.stat("src/io").await.unwrap().is_dir);
+        assert!(!view.exists("README.md").await.unwrap());
+        assert!(matches!(
+            view.read("README.md").await,
+            Err(BackendError::NotFound(_))
+        ));
+        assert!(matches!(
+            view.write("src/new.rs", b"").await,
+            Err(BackendError::PermissionDenied(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_grep_view() {
+        let view = view(None, Some("TODO")).await;
+        assert_eq!(
+            names(&view.list("").await.unwrap()),
+            vec!["src", "README.md"]
+        );
+        assert_eq!(names(&view.list("src").await.unwrap()), vec!["io"]);
+        assert!(view.read("src/lib.rs").await.is_err());
+        assert_eq!(
+            view.read_range("README.md", 0, Some(2)).await.unwrap(),
+            b"TO"
+        );
+        assert!(matches!(
+            view.list("src/io/read.rs").await,
+            Err(BackendError::NotADirectory(_))
+        ));
+    }
+}
@@ -5,12 +5,19 @@
 //! survives process crashes. On startup, the outbox is replayed to
 //! ensure no operations are lost.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use rusqlite::{params, Connection};
+use openfs_config::BackoffStrategy;
+use openfs_core::UploadedPart;
+use rusqlite::{params, Connection, OptionalExtension};
 use tracing::{debug, warn};
 
+use crate::conflict::{ConflictResolution, SyncConflict};
+use crate::sync::RetryBackoff;
+
 /// Type of operation in the WAL.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WalOpType {
@@ -92,6 +99,9 @@ pub struct OutboxEntry {
     pub created_at: i64,
     pub last_attempt: Option<i64>,
     pub error: Option<String>,
+    /// CAS token of the remote version the write was based on, so a replay
+    /// after a restart still detects conflicting remote changes.
+    pub base_token: Option<String>,
 }
 
 /// Per-mount sync profile.
@@ -135,6 +145,12 @@ pub struct WalConfig {
     pub max_retries: u32,
     /// Base backoff in seconds for retries.
     pub base_backoff_secs: u64,
+    /// Longest backoff in seconds between retries.
+    pub max_backoff_secs: u64,
+    /// How the backoff grows between retries.
+    pub backoff_strategy: BackoffStrategy,
+    /// Randomize retry delays.
+    pub jitter: bool,
     /// Whether to auto-recover stuck entries on startup.
     pub recover_on_startup: bool,
     /// Maximum age (seconds) for a processing entry before it's considered stuck.
@@ -143,6 +159,12 @@ pub struct WalConfig {
     pub auto_checkpoint_threshold: usize,
     /// Max age (seconds) of applied WAL entries to keep during checkpoint.
     pub checkpoint_max_age_secs: i64,
+    /// Auto-checkpoint once the database and its WAL file exceed this many
+    /// bytes (0 = disabled).
+    pub checkpoint_max_bytes: u64,
+    /// Auto-checkpoint when this many seconds passed since the last
+    /// checkpoint (0 = disabled).
+    pub checkpoint_interval_secs: u64,
 }
 
 impl Default for WalConfig {
@@ -150,10 +172,27 @@ impl Default for WalConfig {
         Self {
             max_retries: 5,
             base_backoff_secs: 2,
+            max_backoff_secs: 300,
+            backoff_strategy: BackoffStrategy::Exponential,
+            jitter: true,
             recover_on_startup: true,
             stuck_timeout_secs: 300,
             auto_checkpoint_threshold: 500,
             checkpoint_max_age_secs: 86400, // 24 hours
+            checkpoint_max_bytes: 64 * 1024 * 1024,
+            checkpoint_interval_secs: 3600,
+        }
+    }
+}
+
+impl WalConfig {
+    /// The delay schedule for retrying failed outbox entries.
+    pub fn backoff(&self) -> RetryBackoff {
+        RetryBackoff {
+            base: Duration::from_secs(self.base_backoff_secs),
+            max: Duration::from_secs(self.max_backoff_secs),
+            strategy: self.backoff_strategy,
+            jitter: self.jitter,
         }
     }
 }
@@ -165,6 +204,11 @@ impl Default for WalConfig {
 pub struct WriteAheadLog {
     conn: Mutex<Connection>,
     config: WalConfig,
+    /// Database file, `None` when in memory.
+    path: Option<PathBuf>,
+    /// Entries applied since the last checkpoint.
+    applied_since_checkpoint: AtomicUsize,
+    last_checkpoint: Mutex<Instant>,
 }
 
 // Safety: WriteAheadLog is used behind a Mutex, so only one thread
@@ -201,7 +245,9 @@ impl WriteAheadLog {
                 attempts INTEGER NOT NULL DEFAULT 0,
                 created_at INTEGER NOT NULL,
                 last_attempt INTEGER,
-                error TEXT
+                next_attempt INTEGER,
+                error TEXT,
+                base_token TEXT
             );
 
             CREATE TABLE IF NOT EXISTS sync_profiles (
@@ -209,16 +255,54 @@ impl WriteAheadLog {
                 profile TEXT NOT NULL DEFAULT 'local_first'
             );
 
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                base_token TEXT NOT NULL,
+                remote_token TEXT NOT NULL,
+                resolution TEXT NOT NULL,
+                copy_path TEXT,
+                detected_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS synced_paths (
+                path TEXT PRIMARY KEY,
+                synced_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS multipart_uploads (
+                path TEXT PRIMARY KEY,
+                upload_id TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                part_size INTEGER NOT NULL,
+                started_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS multipart_parts (
+                upload_id TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                etag TEXT NOT NULL,
+                PRIMARY KEY (upload_id, number)
+            );
+
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                reason TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                duration_us INTEGER NOT NULL,
+                pruned INTEGER NOT NULL,
+                bytes_before INTEGER NOT NULL,
+                bytes_after INTEGER NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_wal_applied ON wal_log(applied);
             CREATE INDEX IF NOT EXISTS idx_outbox_status ON outbox(status);
             CREATE INDEX IF NOT EXISTS idx_outbox_path ON outbox(path);",
         )
         .map_err(|e| format!("Failed to create WAL tables: {}", e))?;
+        migrate(&conn)?;
 
-        let wal = Self {
-            conn: Mutex::new(conn),
-            config,
-        };
+        let wal = Self::from_conn(conn, Some(path.to_path_buf()), config);
 
         if wal.config.recover_on_startup {
             wal.recover_stuck()?;
@@ -253,7 +337,9 @@ impl WriteAheadLog {
                 attempts INTEGER NOT NULL DEFAULT 0,
                 created_at INTEGER NOT NULL,
                 last_attempt INTEGER,
-                error TEXT
+                next_attempt INTEGER,
+                error TEXT,
+                base_token TEXT
             );
 
             CREATE TABLE IF NOT EXISTS sync_profiles (
@@ -261,16 +347,70 @@ impl WriteAheadLog {
                 profile TEXT NOT NULL DEFAULT 'local_first'
             );
 
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                base_token TEXT NOT NULL,
+                remote_token TEXT NOT NULL,
+                resolution TEXT NOT NULL,
+                copy_path TEXT,
+                detected_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS synced_paths (
+                path TEXT PRIMARY KEY,
+                synced_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS multipart_uploads (
+                path TEXT PRIMARY KEY,
+                upload_id TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                part_size INTEGER NOT NULL,
+                started_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS multipart_parts (
+                upload_id TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                etag TEXT NOT NULL,
+                PRIMARY KEY (upload_id, number)
+            );
+
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                reason TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                duration_us INTEGER NOT NULL,
+                pruned INTEGER NOT NULL,
+                bytes_before INTEGER NOT NULL,
+                bytes_after INTEGER NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_wal_applied ON wal_log(applied);
             CREATE INDEX IF NOT EXISTS idx_outbox_status ON outbox(status);
             CREATE INDEX IF NOT EXISTS idx_outbox_path ON outbox(path);",
         )
         .map_err(|e| format!("Failed to create WAL tables: {}", e))?;
 
-        Ok(Self {
+        Ok(Self::from_conn(conn, None, config))
+    }
+
+    fn from_conn(conn: Connection, path: Option<PathBuf>, config: WalConfig) -> Self {
+        let applied: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM wal_log WHERE applied = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+        Self {
             conn: Mutex::new(conn),
             config,
-        })
+            path,
+            applied_since_checkpoint: AtomicUsize::new(applied as usize),
+            last_checkpoint: Mutex::new(Instant::now()),
+        }
     }
 
     /// Log a write operation to the WAL before it's applied.
@@ -310,46 +450,166 @@ impl WriteAheadLog {
         )
         .map_err(|e| format!("Failed to mark WAL entry applied: {}", e))?;
 
-        // Auto-checkpoint if threshold is set
-        if self.config.auto_checkpoint_threshold > 0 {
-            let applied_count: i64 = conn
-                .query_row(
-                    "SELECT COUNT(*) FROM wal_log WHERE applied = 1",
-                    [],
-                    |row| row.get(0),
-                )
-                .unwrap_or(0);
-            if applied_count as usize >= self.config.auto_checkpoint_threshold {
-                let cutoff = now_unix().saturating_sub(self.config.checkpoint_max_age_secs);
-                let pruned = conn
-                    .execute(
-                        "DELETE FROM wal_log WHERE applied = 1 AND timestamp < ?1",
-                        params![cutoff],
-                    )
-                    .unwrap_or(0);
-                if pruned > 0 {
-                    debug!("WAL auto-checkpoint: pruned {} old applied entries", pruned);
-                }
+        drop(conn);
+
+        self.applied_since_checkpoint
+            .fetch_add(1, Ordering::Relaxed);
+        if let Some(trigger) = self.checkpoint_due() {
+            if let Err(e) = self.run_checkpoint(trigger) {
+                warn!("WAL auto-checkpoint failed: {}", e);
             }
         }
-
         Ok(())
     }
 
-    /// Run a manual checkpoint: prune all applied WAL entries older than
-    /// `checkpoint_max_age_secs` and VACUUM the database.
-    pub fn checkpoint(&self) -> Result<usize, String> {
-        let pruned = self.prune_wal(self.config.checkpoint_max_age_secs)?;
-        if pruned > 0 {
-            let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
-            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
-                .map_err(|e| format!("Failed to checkpoint WAL: {}", e))?;
-            debug!(
-                "WAL checkpoint: pruned {} entries and truncated WAL",
-                pruned
-            );
+    /// Run a manual checkpoint: prune applied WAL entries older than
+    /// `checkpoint_max_age_secs`, compact the database and truncate its WAL
+    /// file.
+    pub fn checkpoint(&self) -> Result<CheckpointReport, String> {
+        self.run_checkpoint(CheckpointTrigger::Manual)
+    }
+
+    /// Which automatic checkpoint threshold, if any, has been reached.
+    fn checkpoint_due(&self) -> Option<CheckpointTrigger> {
+        let config = &self.config;
+        if config.auto_checkpoint_threshold > 0
+            && self.applied_since_checkpoint.load(Ordering::Relaxed)
+                >= config.auto_checkpoint_threshold
+        {
+            return Some(CheckpointTrigger::AppliedEntries);
+        }
+        let since = self
+            .last_checkpoint
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed();
+        if config.checkpoint_interval_secs > 0
+            && since >= Duration::from_secs(config.checkpoint_interval_secs)
+        {
+            return Some(CheckpointTrigger::Interval);
+        }
+        // A database that stays large after compacting is not checkpointed
+        // again on every write.
+        if config.checkpoint_max_bytes > 0
+            && since >= MIN_SIZE_CHECKPOINT_SPACING
+            && self
+                .size_bytes()
+                .is_ok_and(|bytes| bytes > config.checkpoint_max_bytes)
+        {
+            return Some(CheckpointTrigger::Size);
+        }
+        None
+    }
+
+    fn run_checkpoint(&self, trigger: CheckpointTrigger) -> Result<CheckpointReport, String> {
+        let started = Instant::now();
+        let started_at = now_unix();
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let bytes_before = database_bytes(&conn, self.path.as_deref())?;
+
+        let cutoff = started_at.saturating_sub(self.config.checkpoint_max_age_secs);
+        let pruned = conn
+            .execute(
+                "DELETE FROM wal_log WHERE applied = 1 AND timestamp < ?1",
+                params![cutoff],
+            )
+            .map_err(|e| format!("Failed to prune WAL: {}", e))?;
+        let free_pages: i64 = conn
+            .query_row("PRAGMA freelist_count", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to read free pages: {}", e))?;
+        if free_pages > 0 {
+            conn.execute_batch("VACUUM;")
+                .map_err(|e| format!("Failed to compact WAL db: {}", e))?;
         }
-        Ok(pruned)
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .map_err(|e| format!("Failed to checkpoint WAL: {}", e))?;
+
+        let report = CheckpointReport {
+            trigger,
+            started_at,
+            duration: started.elapsed(),
+            pruned,
+            bytes_before,
+            bytes_after: database_bytes(&conn, self.path.as_deref())?,
+        };
+        conn.execute(
+            "INSERT INTO checkpoints
+                 (reason, started_at, duration_us, pruned, bytes_before, bytes_after)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                trigger.as_str(),
+                report.started_at,
+                report.duration.as_micros() as i64,
+                report.pruned as i64,
+                report.bytes_before as i64,
+                report.bytes_after as i64
+            ],
+        )
+        .map_err(|e| format!("Failed to record checkpoint: {}", e))?;
+        conn.execute(
+            "DELETE FROM checkpoints WHERE id <= (SELECT MAX(id) FROM checkpoints) - ?1",
+            params![CHECKPOINT_HISTORY],
+        )
+        .map_err(|e| format!("Failed to trim checkpoint history: {}", e))?;
+        drop(conn);
+
+        self.applied_since_checkpoint.store(0, Ordering::Relaxed);
+        *self
+            .last_checkpoint
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        debug!(
+            "WAL checkpoint ({}): pruned {} entries, {} -> {} bytes in {:?}",
+            trigger.as_str(),
+            pruned,
+            report.bytes_before,
+            report.bytes_after,
+            report.duration
+        );
+        Ok(report)
+    }
+
+    /// Size in bytes of the database, plus its WAL file when on disk.
+    pub fn size_bytes(&self) -> Result<u64, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        database_bytes(&conn, self.path.as_deref())
+    }
+
+    /// Counts and timings of the recent checkpoints.
+    pub fn checkpoint_stats(&self) -> Result<CheckpointStats, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let (count, total_us, max_us): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(duration_us), 0), COALESCE(MAX(duration_us), 0)
+                 FROM checkpoints",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| format!("Failed to query checkpoints: {}", e))?;
+        let last = conn
+            .query_row(
+                "SELECT reason, started_at, duration_us, pruned, bytes_before, bytes_after
+                 FROM checkpoints ORDER BY id DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(CheckpointReport {
+                        trigger: CheckpointTrigger::from_str(&row.get::<_, String>(0)?),
+                        started_at: row.get(1)?,
+                        duration: Duration::from_micros(row.get::<_, i64>(2)? as u64),
+                        pruned: row.get::<_, i64>(3)? as usize,
+                        bytes_before: row.get::<_, i64>(4)? as u64,
+                        bytes_after: row.get::<_, i64>(5)? as u64,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query checkpoints: {}", e))?;
+        Ok(CheckpointStats {
+            count: count as usize,
+            total_duration: Duration::from_micros(total_us as u64),
+            max_duration: Duration::from_micros(max_us as u64),
+            last,
+        })
     }
 
     /// Get unapplied WAL entries (for crash recovery).
@@ -388,10 +648,33 @@ impl WriteAheadLog {
         path: &str,
         content: Option<&[u8]>,
         mount_path: &str,
+    ) -> Result<i64, String> {
+        self.enqueue_outbox_with_base(op_type, path, content, mount_path, None)
+    }
+
+    /// Add an entry to the outbox, to be written only while the remote is
+    /// still the version `base_token` (see [`OutboxEntry::base_token`]).
+    pub fn enqueue_outbox_with_base(
+        &self,
+        op_type: WalOpType,
+        path: &str,
+        content: Option<&[u8]>,
+        mount_path: &str,
+        base_token: Option<&str>,
     ) -> Result<i64, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
         let now = now_unix();
 
+        // A full write or delete supersedes any dead-lettered entry for the path,
+        // so retrying failed entries later cannot replay stale content.
+        if op_type != WalOpType::Append {
+            conn.execute(
+                "DELETE FROM outbox WHERE path = ?1 AND mount_path = ?2 AND status = 'failed'",
+                params![path, mount_path],
+            )
+            .map_err(|e| format!("Failed to drop superseded outbox entries: {}", e))?;
+        }
+
         // Upsert: if there's already a pending entry for this path+mount, update it
         let existing: Option<i64> = conn
             .query_row(
@@ -403,18 +686,18 @@ impl WriteAheadLog {
 
         if let Some(existing_id) = existing {
             conn.execute(
-                "UPDATE outbox SET op_type = ?1, content = ?2, created_at = ?3
-                 WHERE id = ?4",
-                params![op_type.as_str(), content, now, existing_id],
+                "UPDATE outbox SET op_type = ?1, content = ?2, created_at = ?3, base_token = ?4
+                 WHERE id = ?5",
+                params![op_type.as_str(), content, now, base_token, existing_id],
             )
             .map_err(|e| format!("Failed to update outbox entry: {}", e))?;
             debug!("Outbox updated: id={} path={}", existing_id, path);
             Ok(existing_id)
         } else {
             conn.execute(
-                "INSERT INTO outbox (op_type, path, content, mount_path, status, attempts, created_at)
-                 VALUES (?1, ?2, ?3, ?4, 'pending', 0, ?5)",
-                params![op_type.as_str(), path, content, mount_path, now],
+                "INSERT INTO outbox (op_type, path, content, mount_path, status, attempts, created_at, base_token)
+                 VALUES (?1, ?2, ?3, ?4, 'pending', 0, ?5, ?6)",
+                params![op_type.as_str(), path, content, mount_path, now, base_token],
             )
             .map_err(|e| format!("Failed to insert outbox entry: {}", e))?;
             let id = conn.last_insert_rowid();
@@ -431,34 +714,31 @@ impl WriteAheadLog {
         let mut stmt = conn
             .prepare(
                 "SELECT id, op_type, path, content, mount_path, status, attempts,
-                        created_at, last_attempt, error
+                        created_at, last_attempt, error, base_token
                  FROM outbox
                  WHERE status = 'pending'
-                   AND (last_attempt IS NULL
-                        OR last_attempt + (?1 * (1 << MIN(attempts, 10))) < ?2)
+                   AND (next_attempt IS NULL OR next_attempt <= ?1)
                  ORDER BY created_at ASC
-                 LIMIT ?3",
+                 LIMIT ?2",
             )
             .map_err(|e| format!("Failed to prepare outbox query: {}", e))?;
 
         let entries = stmt
-            .query_map(
-                params![self.config.base_backoff_secs as i64, now, limit as i64],
-                |row| {
-                    Ok(OutboxEntry {
-                        id: row.get(0)?,
-                        op_type: WalOpType::from_str(&row.get::<_, String>(1)?),
-                        path: row.get(2)?,
-                        content: row.get(3)?,
-                        mount_path: row.get(4)?,
-                        status: OutboxStatus::from_str(&row.get::<_, String>(5)?),
-                        attempts: row.get::<_, u32>(6)?,
-                        created_at: row.get(7)?,
-                        last_attempt: row.get(8)?,
-                        error: row.get(9)?,
-                    })
-                },
-            )
+            .query_map(params![now, limit as i64], |row| {
+                Ok(OutboxEntry {
+                    id: row.get(0)?,
+                    op_type: WalOpType::from_str(&row.get::<_, String>(1)?),
+                    path: row.get(2)?,
+                    content: row.get(3)?,
+                    mount_path: row.get(4)?,
+                    status: OutboxStatus::from_str(&row.get::<_, String>(5)?),
+                    attempts: row.get::<_, u32>(6)?,
+                    created_at: row.get(7)?,
+                    last_attempt: row.get(8)?,
+                    error: row.get(9)?,
+                    base_token: row.get(10)?,
+                })
+            })
             .map_err(|e| format!("Failed to query outbox: {}", e))?
             .filter_map(|r| r.ok())
             .collect();
@@ -506,6 +786,17 @@ impl WriteAheadLog {
 
     /// Record a failure for an outbox entry. Moves to failed if max retries exceeded.
     pub fn fail_outbox(&self, entry_id: i64, error: &str) -> Result<(), String> {
+        self.fail_outbox_after(entry_id, error, None)
+    }
+
+    /// Record a failure for an outbox entry, scheduling the retry no sooner
+    /// than `retry_after` when the backend asked for a delay.
+    pub fn fail_outbox_after(
+        &self,
+        entry_id: i64,
+        error: &str,
+        retry_after: Option<Duration>,
+    ) -> Result<(), String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
         let now = now_unix();
 
@@ -531,10 +822,13 @@ impl WriteAheadLog {
                 entry_id, new_attempts, error
             );
         } else {
+            let delay = self.config.backoff().delay(attempts, retry_after);
+            let next_attempt = now + delay.as_secs_f64().ceil() as i64;
             conn.execute(
-                "UPDATE outbox SET status = 'pending', attempts = ?1, last_attempt = ?2, error = ?3
-                 WHERE id = ?4",
-                params![new_attempts, now, error, entry_id],
+                "UPDATE outbox SET status = 'pending', attempts = ?1, last_attempt = ?2,
+                        next_attempt = ?3, error = ?4
+                 WHERE id = ?5",
+                params![new_attempts, now, next_attempt, error, entry_id],
             )
             .map_err(|e| format!("Failed to update outbox retry: {}", e))?;
             debug!(
@@ -546,6 +840,55 @@ impl WriteAheadLog {
         Ok(())
     }
 
+    /// Move an outbox entry straight to failed, for errors retrying cannot fix.
+    pub fn dead_letter(&self, entry_id: i64, error: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute(
+            "UPDATE outbox SET status = 'failed', attempts = attempts + 1, last_attempt = ?1,
+                    error = ?2
+             WHERE id = ?3",
+            params![now_unix(), error, entry_id],
+        )
+        .map_err(|e| format!("Failed to dead-letter outbox entry: {}", e))?;
+        warn!("Outbox entry {} dead-lettered: {}", entry_id, error);
+        Ok(())
+    }
+
+    /// Move the outbox entries for a path to failed once the write-back
+    /// flush has given up on it. Older failed entries for the path are
+    /// replaced, so a retry replays only the latest content.
+    pub fn dead_letter_path(&self, path: &str, error: &str) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let live: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM outbox WHERE path = ?1 AND status != 'failed'",
+                params![path],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to count outbox path '{}': {}", path, e))?;
+        if live == 0 {
+            return Ok(0);
+        }
+        conn.execute(
+            "DELETE FROM outbox WHERE path = ?1 AND status = 'failed'",
+            params![path],
+        )
+        .map_err(|e| format!("Failed to replace failed entries for '{}': {}", path, e))?;
+        let count = conn
+            .execute(
+                "UPDATE outbox SET status = 'failed', attempts = attempts + 1, last_attempt = ?1,
+                        error = ?2
+                 WHERE path = ?3",
+                params![now_unix(), error, path],
+            )
+            .map_err(|e| format!("Failed to dead-letter outbox path '{}': {}", path, e))?;
+        warn!(
+            "Outbox dead-lettered {} ({} entries): {}",
+            path, count, error
+        );
+        Ok(count)
+    }
+
     /// Recover stuck processing entries (from a previous crash).
     pub fn recover_stuck(&self) -> Result<usize, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
@@ -616,7 +959,7 @@ impl WriteAheadLog {
         let mut stmt = conn
             .prepare(
                 "SELECT id, op_type, path, content, mount_path, status, attempts,
-                        created_at, last_attempt, error
+                        created_at, last_attempt, error, base_token
                  FROM outbox ORDER BY created_at ASC",
             )
             .map_err(|e| format!("Failed to prepare outbox entries query: {}", e))?;
@@ -634,6 +977,7 @@ impl WriteAheadLog {
                     created_at: row.get(7)?,
                     last_attempt: row.get(8)?,
                     error: row.get(9)?,
+                    base_token: row.get(10)?,
                 })
             })
             .map_err(|e| format!("Failed to query outbox entries: {}", e))?
@@ -676,7 +1020,7 @@ impl WriteAheadLog {
         let mut stmt = conn
             .prepare(
                 "SELECT id, op_type, path, content, mount_path, status, attempts,
-                        created_at, last_attempt, error
+                        created_at, last_attempt, error, base_token
                  FROM outbox WHERE status = 'failed' ORDER BY created_at ASC",
             )
             .map_err(|e| format!("Failed to prepare failed query: {}", e))?;
@@ -694,6 +1038,7 @@ impl WriteAheadLog {
                     created_at: row.get(7)?,
                     last_attempt: row.get(8)?,
                     error: row.get(9)?,
+                    base_token: row.get(10)?,
                 })
             })
             .map_err(|e| format!("Failed to query failed: {}", e))?
@@ -707,13 +1052,198 @@ impl WriteAheadLog {
     pub fn retry_failed(&self, entry_id: i64) -> Result<(), String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
         conn.execute(
-            "UPDATE outbox SET status = 'pending', attempts = 0, error = NULL WHERE id = ?1 AND status = 'failed'",
+            "UPDATE outbox SET status = 'pending', attempts = 0, next_attempt = NULL, error = NULL
+             WHERE id = ?1 AND status = 'failed'",
             params![entry_id],
         )
         .map_err(|e| format!("Failed to retry failed entry: {}", e))?;
         Ok(())
     }
 
+    /// Move every failed outbox entry back to pending. Returns how many.
+    pub fn retry_all_failed(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let count = conn
+            .execute(
+                "UPDATE outbox SET status = 'pending', attempts = 0, next_attempt = NULL, error = NULL
+                 WHERE status = 'failed'",
+                [],
+            )
+            .map_err(|e| format!("Failed to retry failed entries: {}", e))?;
+        Ok(count)
+    }
+
+    /// Record a write-back conflict.
+    pub fn record_conflict(&self, conflict: &SyncConflict) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute(
+            "INSERT INTO sync_conflicts
+                 (path, base_token, remote_token, resolution, copy_path, detected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                conflict.path,
+                conflict.base,
+                conflict.remote,
+                conflict.resolution.as_str(),
+                conflict.resolution.copy(),
+                conflict.detected_at
+            ],
+        )
+        .map_err(|e| format!("Failed to record conflict: {}", e))?;
+        Ok(())
+    }
+
+    /// Get recorded write-back conflicts, oldest first.
+    pub fn conflicts(&self) -> Result<Vec<SyncConflict>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT path, base_token, remote_token, resolution, copy_path, detected_at
+                 FROM sync_conflicts ORDER BY id ASC",
+            )
+            .map_err(|e| format!("Failed to prepare conflicts query: {}", e))?;
+
+        let conflicts = stmt
+            .query_map([], |row| {
+                Ok(SyncConflict {
+                    path: row.get(0)?,
+                    base: row.get(1)?,
+                    remote: row.get(2)?,
+                    resolution: ConflictResolution::from_parts(
+                        &row.get::<_, String>(3)?,
+                        row.get(4)?,
+                    ),
+                    detected_at: row.get(5)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query conflicts: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(conflicts)
+    }
+
+    /// Delete recorded write-back conflicts. Returns how many there were.
+    pub fn clear_conflicts(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute("DELETE FROM sync_conflicts", [])
+            .map_err(|e| format!("Failed to clear conflicts: {}", e))
+    }
+
+    /// Record that `path` was just written to the remote.
+    pub fn record_synced(&self, path: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute(
+            "INSERT INTO synced_paths (path, synced_at) VALUES (?1, ?2)
+             ON CONFLICT(path) DO UPDATE SET synced_at = excluded.synced_at",
+            params![path, now_unix()],
+        )
+        .map_err(|e| format!("Failed to record sync of '{}': {}", path, e))?;
+        Ok(())
+    }
+
+    /// Forget the sync time of a deleted path.
+    pub fn forget_synced(&self, path: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute("DELETE FROM synced_paths WHERE path = ?1", params![path])
+            .map_err(|e| format!("Failed to forget sync of '{}': {}", path, e))?;
+        Ok(())
+    }
+
+    /// Every path written to the remote, with when it last was (Unix seconds).
+    pub fn synced_paths(&self) -> Result<Vec<(String, i64)>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let mut stmt = conn
+            .prepare("SELECT path, synced_at FROM synced_paths ORDER BY path ASC")
+            .map_err(|e| format!("Failed to prepare synced paths query: {}", e))?;
+        let paths = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query synced paths: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(paths)
+    }
+
+    /// Record a multipart upload started for `path`, replacing any earlier one.
+    pub fn begin_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        content_hash: &str,
+        part_size: u64,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        forget_multipart(&conn, path)?;
+        conn.execute(
+            "INSERT INTO multipart_uploads (path, upload_id, content_hash, part_size, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![path, upload_id, content_hash, part_size as i64, now_unix()],
+        )
+        .map_err(|e| format!("Failed to record multipart upload: {}", e))?;
+        Ok(())
+    }
+
+    /// Record a part stored by a multipart upload.
+    pub fn record_part(&self, upload_id: &str, part: &UploadedPart) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO multipart_parts (upload_id, number, etag) VALUES (?1, ?2, ?3)",
+            params![upload_id, part.number, part.etag],
+        )
+        .map_err(|e| format!("Failed to record part: {}", e))?;
+        Ok(())
+    }
+
+    /// The multipart upload in progress for `path`, if any.
+    pub fn multipart_upload(&self, path: &str) -> Result<Option<MultipartProgress>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let upload = conn
+            .query_row(
+                "SELECT upload_id, content_hash, part_size, started_at
+                 FROM multipart_uploads WHERE path = ?1",
+                params![path],
+                |row| {
+                    Ok(MultipartProgress {
+                        path: path.to_string(),
+                        upload_id: row.get(0)?,
+                        content_hash: row.get(1)?,
+                        part_size: row.get::<_, i64>(2)? as u64,
+                        parts: Vec::new(),
+                        started_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query multipart upload: {}", e))?;
+        let Some(mut upload) = upload else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT number, etag FROM multipart_parts WHERE upload_id = ?1
+                 ORDER BY number ASC",
+            )
+            .map_err(|e| format!("Failed to prepare parts query: {}", e))?;
+        upload.parts = stmt
+            .query_map(params![upload.upload_id], |row| {
+                Ok(UploadedPart {
+                    number: row.get(0)?,
+                    etag: row.get(1)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query parts: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(Some(upload))
+    }
+
+    /// Forget the multipart upload for `path`, once completed or abandoned.
+    pub fn finish_multipart(&self, path: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        forget_multipart(&conn, path)
+    }
+
     /// Prune applied WAL entries older than the given age (seconds).
     pub fn prune_wal(&self, max_age_secs: i64) -> Result<usize, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
@@ -728,6 +1258,93 @@ impl WriteAheadLog {
     }
 }
 
+/// A multipart upload in progress, recorded so that it can be resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartProgress {
+    pub path: String,
+    pub upload_id: String,
+    /// BLAKE3 hash of the whole content being uploaded.
+    pub content_hash: String,
+    pub part_size: u64,
+    /// Parts stored so far, by number.
+    pub parts: Vec<UploadedPart>,
+    pub started_at: i64,
+}
+
+/// Checkpoints kept in the history behind [`WriteAheadLog::checkpoint_stats`].
+const CHECKPOINT_HISTORY: i64 = 100;
+
+/// Shortest time between size-triggered checkpoints.
+const MIN_SIZE_CHECKPOINT_SPACING: Duration = Duration::from_secs(60);
+
+/// What started a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointTrigger {
+    /// Requested, e.g. by `openfs wal checkpoint`.
+    Manual,
+    /// `auto_checkpoint_threshold` entries were applied.
+    AppliedEntries,
+    /// The database grew past `checkpoint_max_bytes`.
+    Size,
+    /// `checkpoint_interval_secs` passed.
+    Interval,
+}
+
+impl CheckpointTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointTrigger::Manual => "manual",
+            CheckpointTrigger::AppliedEntries => "applied_entries",
+            CheckpointTrigger::Size => "size",
+            CheckpointTrigger::Interval => "interval",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "applied_entries" => CheckpointTrigger::AppliedEntries,
+            "size" => CheckpointTrigger::Size,
+            "interval" => CheckpointTrigger::Interval,
+            _ => CheckpointTrigger::Manual,
+        }
+    }
+}
+
+/// What one checkpoint did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointReport {
+    pub trigger: CheckpointTrigger,
+    /// Unix time the checkpoint started.
+    pub started_at: i64,
+    pub duration: Duration,
+    /// Applied WAL entries removed.
+    pub pruned: usize,
+    /// Size of the database and its WAL file, before and after.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Checkpoint timings over the recent history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckpointStats {
+    /// Checkpoints in the history.
+    pub count: usize,
+    pub total_duration: Duration,
+    pub max_duration: Duration,
+    /// The most recent checkpoint.
+    pub last: Option<CheckpointReport>,
+}
+
+impl CheckpointStats {
+    pub fn mean_duration(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total_duration / count as u32,
+        }
+    }
+}
+
 /// Statistics for the outbox.
 #[derive(Debug, Clone, Default)]
 pub struct OutboxStats {
@@ -737,6 +1354,57 @@ pub struct OutboxStats {
     pub wal_unapplied: usize,
 }
 
+/// Bring tables created by older versions up to date.
+fn migrate(conn: &Connection) -> Result<(), String> {
+    let has_next_attempt = conn
+        .prepare("SELECT next_attempt FROM outbox LIMIT 0")
+        .is_ok();
+    if !has_next_attempt {
+        conn.execute_batch("ALTER TABLE outbox ADD COLUMN next_attempt INTEGER;")
+            .map_err(|e| format!("Failed to migrate outbox: {}", e))?;
+    }
+    let has_base_token = conn
+        .prepare("SELECT base_token FROM outbox LIMIT 0")
+        .is_ok();
+    if !has_base_token {
+        conn.execute_batch("ALTER TABLE outbox ADD COLUMN base_token TEXT;")
+            .map_err(|e| format!("Failed to migrate outbox: {}", e))?;
+    }
+    Ok(())
+}
+
+fn forget_multipart(conn: &Connection, path: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM multipart_parts WHERE upload_id IN
+             (SELECT upload_id FROM multipart_uploads WHERE path = ?1)",
+        params![path],
+    )
+    .map_err(|e| format!("Failed to forget parts: {}", e))?;
+    conn.execute(
+        "DELETE FROM multipart_uploads WHERE path = ?1",
+        params![path],
+    )
+    .map_err(|e| format!("Failed to forget multipart upload: {}", e))?;
+    Ok(())
+}
+
+/// Database pages in use, plus the WAL file when on disk.
+fn database_bytes(conn: &Connection, path: Option<&Path>) -> Result<u64, String> {
+    let (pages, page_size): (i64, i64) = conn
+        .query_row(
+            "SELECT page_count, page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to read db size: {}", e))?;
+    let wal_file = path.and_then(|path| {
+        let mut name = path.as_os_str().to_owned();
+        name.push("-wal");
+        std::fs::metadata(name).ok()
+    });
+    Ok((pages * page_size) as u64 + wal_file.map_or(0, |m| m.len()))
+}
+
 fn now_unix() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -840,6 +1508,54 @@ mod tests {
         assert_eq!(ready[0].path, "/other.txt");
     }
 
+    #[test]
+    fn test_fail_outbox_honors_retry_after() {
+        let wal = make_wal();
+        let id = wal
+            .enqueue_outbox(WalOpType::Write, "/test.txt", Some(b"data"), "/")
+            .unwrap();
+        wal.mark_processing(id).unwrap();
+        wal.fail_outbox_after(id, "throttled", Some(Duration::from_secs(600)))
+            .unwrap();
+
+        assert!(wal.fetch_ready_outbox(10).unwrap().is_empty());
+        let entry = &wal.outbox_entries().unwrap()[0];
+        assert_eq!(entry.status, OutboxStatus::Pending);
+        assert_eq!(entry.attempts, 1);
+    }
+
+    #[test]
+    fn test_dead_letter_and_retry_all_failed() {
+        let wal = make_wal();
+        wal.enqueue_outbox(WalOpType::Write, "/a.txt", Some(b"v1"), "/")
+            .unwrap();
+        let b = wal
+            .enqueue_outbox(WalOpType::Write, "/b.txt", Some(b"b"), "/")
+            .unwrap();
+
+        assert_eq!(wal.dead_letter_path("/a.txt", "denied").unwrap(), 1);
+        wal.dead_letter(b, "denied").unwrap();
+        assert_eq!(wal.get_failed().unwrap().len(), 2);
+        assert!(wal.fetch_ready_outbox(10).unwrap().is_empty());
+
+        // A newer write supersedes the dead-lettered one.
+        wal.enqueue_outbox(WalOpType::Write, "/a.txt", Some(b"v2"), "/")
+            .unwrap();
+        let failed = wal.get_failed().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].path, "/b.txt");
+
+        assert_eq!(wal.retry_all_failed().unwrap(), 1);
+        let mut ready: Vec<_> = wal
+            .fetch_ready_outbox(10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.content.unwrap())
+            .collect();
+        ready.sort();
+        assert_eq!(ready, vec![b"b".to_vec(), b"v2".to_vec()]);
+    }
+
     #[test]
     fn test_wal_op_type_roundtrip() {
         assert_eq!(
@@ -891,4 +1607,71 @@ mod tests {
             SyncProfile::RemoteOnly
         );
     }
+
+    #[test]
+    fn test_auto_checkpoint_triggers() {
+        let wal = WriteAheadLog::in_memory(WalConfig {
+            recover_on_startup: false,
+            auto_checkpoint_threshold: 3,
+            // Keep no applied entries, however recent.
+            checkpoint_max_age_secs: -1,
+            checkpoint_max_bytes: 1,
+            checkpoint_interval_secs: 0,
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..3 {
+            let id = wal
+                .log_write(WalOpType::Write, &format!("/f{}", i), Some(b"x"), "/")
+                .unwrap();
+            assert_eq!(wal.checkpoint_stats().unwrap().count, 0);
+            wal.mark_applied(id).unwrap();
+        }
+        let stats = wal.checkpoint_stats().unwrap();
+        assert_eq!(stats.count, 1);
+        let last = stats.last.unwrap();
+        assert_eq!(last.trigger, CheckpointTrigger::AppliedEntries);
+        assert_eq!(last.pruned, 3);
+
+        // Size checkpoints wait a while after the last checkpoint.
+        assert_eq!(wal.checkpoint_due(), None);
+        *wal.last_checkpoint.lock().unwrap() = Instant::now() - Duration::from_secs(120);
+        assert_eq!(wal.checkpoint_due(), Some(CheckpointTrigger::Size));
+    }
+
+    #[test]
+    fn test_checkpoint_compacts_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal = WriteAheadLog::new(
+            &dir.path().join("wal.db"),
+            WalConfig {
+                auto_checkpoint_threshold: 0,
+                checkpoint_max_age_secs: -1,
+                checkpoint_max_bytes: 0,
+                checkpoint_interval_secs: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        for i in 0..100 {
+            let id = wal
+                .log_write(WalOpType::Write, &format!("/f{}", i), Some(&[7; 8192]), "/")
+                .unwrap();
+            wal.mark_applied(id).unwrap();
+        }
+        assert_eq!(wal.checkpoint_stats().unwrap().count, 0);
+
+        let report = wal.checkpoint().unwrap();
+        assert_eq!(report.trigger, CheckpointTrigger::Manual);
+        assert_eq!(report.pruned, 100);
+        assert!(report.bytes_before > 800 * 1024);
+        assert!(report.bytes_after < 100 * 1024, "{:?}", report);
+        assert!(wal.size_bytes().unwrap() < report.bytes_before);
+
+        let stats = wal.checkpoint_stats().unwrap();
+        assert_eq!(stats.count, 1);
+        // Durations are kept to the microsecond.
+        assert_eq!(stats.max_duration.as_micros(), report.duration.as_micros());
+        assert_eq!(stats.mean_duration(), stats.max_duration);
+    }
 }
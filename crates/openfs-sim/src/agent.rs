@@ -273,21 +273,25 @@ async fn build_agent(
             path: format!("{}/work", prefix),
             backend: Arc::new(work_cached),
             read_only: false,
+            path_policy: Default::default(),
         },
         Mount {
             path: format!("{}/indexed", prefix),
             backend: indexed_cached,
             read_only: false,
+            path_policy: Default::default(),
         },
         Mount {
             path: "/shared/read".to_string(),
             backend: Arc::new(shared_read_cached),
             read_only: true,
+            path_policy: Default::default(),
         },
         Mount {
             path: "/shared/write".to_string(),
             backend: Arc::new(shared_write_cached),
             read_only: false,
+            path_policy: Default::default(),
         },
     ];
 
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use openfs_core::{Backend, BackendError, Entry};
+use openfs_core::{Backend, BackendError, Entry, FileMeta};
 
 /// Wrapper to hold `Arc<dyn Backend>` as a concrete type for `CachedBackend<B>`.
 ///
@@ -33,6 +33,19 @@ impl Backend for DynBackend {
     ) -> Result<Option<String>, BackendError> {
         self.0.compare_and_swap(path, expected, content).await
     }
+    async fn create_if_absent(
+        &self,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<String>, BackendError> {
+        self.0.create_if_absent(path, content).await
+    }
+    async fn read_meta(&self, path: &str) -> Result<FileMeta, BackendError> {
+        self.0.read_meta(path).await
+    }
+    async fn write_meta(&self, path: &str, meta: &FileMeta) -> Result<(), BackendError> {
+        self.0.write_meta(path, meta).await
+    }
     async fn append(&self, path: &str, content: &[u8]) -> Result<(), BackendError> {
         self.0.append(path, content).await
     }
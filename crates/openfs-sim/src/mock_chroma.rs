@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::sync::RwLock;
 
 use async_trait::async_trait;
-use openfs_core::{BackendError, ChromaStore, QueryResult, SparseEmbedding};
+use openfs_core::{
+    metadata_matches, BackendError, ChromaStore, QueryResult, SparseEmbedding, StoredDocument,
+};
 
 /// A single document stored in the mock Chroma store.
 #[derive(Debug, Clone)]
@@ -38,17 +40,16 @@ impl MockChromaStore {
         self.docs.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
 
-    /// Check if a document exists by path prefix (e.g. "file.txt" matches "file.txt#chunk_0").
+    /// Check if any document was indexed from `source_path`, either as its
+    /// `source_path` or as one of the `paths` of a shared chunk.
     pub fn has_docs_for_path(&self, source_path: &str) -> bool {
+        let filter = serde_json::json!({"$or": [
+            {"source_path": source_path},
+            {"paths": {"$contains": source_path}},
+        ]});
         let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
-        docs.values().any(|doc| {
-            doc.metadata
-                .as_ref()
-                .and_then(|m| m.get("source_path"))
-                .and_then(|v| v.as_str())
-                .map(|sp| sp == source_path)
-                .unwrap_or(false)
-        })
+        docs.values()
+            .any(|doc| metadata_matches(doc.metadata.as_ref(), &filter))
     }
 }
 
@@ -169,24 +170,29 @@ impl ChromaStore for MockChromaStore {
             .collect())
     }
 
+    async fn get_by_metadata(
+        &self,
+        filter: serde_json::Value,
+    ) -> Result<Vec<StoredDocument>, BackendError> {
+        let docs = self.docs.read().unwrap_or_else(|e| e.into_inner());
+        Ok(docs
+            .iter()
+            .filter(|(_, doc)| metadata_matches(doc.metadata.as_ref(), &filter))
+            .map(|(id, doc)| StoredDocument {
+                id: id.clone(),
+                document: Some(doc.content.clone()),
+                embedding: doc.embedding.clone(),
+                sparse_embedding: doc.sparse_embedding.clone(),
+                metadata: doc.metadata.clone(),
+            })
+            .collect())
+    }
+
     async fn delete_by_metadata(&self, filter: serde_json::Value) -> Result<usize, BackendError> {
         let mut docs = self.docs.write().unwrap_or_else(|e| e.into_inner());
         let before = docs.len();
 
-        docs.retain(|_id, doc| {
-            if let Some(metadata) = &doc.metadata {
-                if let Some(filter_map) = filter.as_object() {
-                    for (key, expected_val) in filter_map {
-                        if let Some(actual_val) = metadata.get(key) {
-                            if actual_val == expected_val {
-                                return false; // remove this doc
-                            }
-                        }
-                    }
-                }
-            }
-            true // keep
-        });
+        docs.retain(|_id, doc| !metadata_matches(doc.metadata.as_ref(), &filter));
 
         Ok(before - docs.len())
     }
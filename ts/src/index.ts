@@ -1,6 +1,9 @@
 export type {
   Entry,
   GrepMatch,
+  GrepOptions,
+  FindOptions,
+  PatchResult,
   SearchResult,
   BatchReadResult,
   BatchWriteResult,
@@ -9,6 +12,10 @@ export type {
   MountConfig,
   VfsConfig,
   Vfs,
+  WatchCallback,
+  WatchEvent,
+  WatchEventKind,
+  Unsubscribe,
 } from "./types.js";
 
 export { enoent, eisdir, enotdir, eio, enotsup, eexist, mcpErrorToVfsError } from "./errors.js";
@@ -18,6 +25,8 @@ export { loadConfig } from "./config.js";
 
 export { MemoryVfs } from "./memory.js";
 
+export type { ReadStreamOptions } from "./streams.js";
+
 export { SubprocessVfs } from "./vfs.js";
 export type { SubprocessVfsOptions } from "./vfs.js";
 
@@ -1,5 +1,19 @@
-import { eisdir, enoent, enotsup } from "./errors.js";
-import type { CacheStats, Entry, GrepMatch, SearchResult, Vfs } from "./types.js";
+import type { Readable, Writable } from "node:stream";
+import { eio, eisdir, enoent, enotsup } from "./errors.js";
+import { type ReadStreamOptions, chunkedReadStream, chunkedWriteStream } from "./streams.js";
+import type {
+  CacheStats,
+  Entry,
+  FindOptions,
+  GrepMatch,
+  GrepOptions,
+  PatchResult,
+  SearchResult,
+  Unsubscribe,
+  Vfs,
+  WatchCallback,
+  WatchEventKind,
+} from "./types.js";
 
 function normalizePath(p: string): string {
   const parts = p.split("/").filter(Boolean);
@@ -13,6 +27,7 @@ function normalizePath(p: string): string {
 
 export class MemoryVfs implements Vfs {
   private files = new Map<string, string>();
+  private watchers = new Set<{ path: string; callback: WatchCallback }>();
 
   async read(path: string): Promise<string> {
     const norm = normalizePath(path);
@@ -25,21 +40,28 @@ export class MemoryVfs implements Vfs {
   }
 
   async write(path: string, content: string): Promise<void> {
-    this.files.set(normalizePath(path), content);
+    const norm = normalizePath(path);
+    const kind = this.files.has(norm) ? "modified" : "created";
+    this.files.set(norm, content);
+    this.emit(norm, kind);
   }
 
   async append(path: string, content: string): Promise<void> {
     const norm = normalizePath(path);
-    const existing = this.files.get(norm) ?? "";
-    this.files.set(norm, existing + content);
+    const existing = this.files.get(norm);
+    this.files.set(norm, (existing ?? "") + content);
+    this.emit(norm, existing === undefined ? "created" : "modified");
   }
 
   async delete(path: string): Promise<void> {
     const norm = normalizePath(path);
-    this.files.delete(norm);
+    if (this.files.delete(norm)) this.emit(norm, "deleted");
     const prefix = `${norm}/`;
     for (const key of [...this.files.keys()]) {
-      if (key.startsWith(prefix)) this.files.delete(key);
+      if (key.startsWith(prefix)) {
+        this.files.delete(key);
+        this.emit(key, "deleted");
+      }
     }
   }
 
@@ -111,18 +133,27 @@ export class MemoryVfs implements Vfs {
     if (content === undefined) throw enoent(normFrom);
     this.files.set(normTo, content);
     this.files.delete(normFrom);
+    this.emit(normFrom, "renamed");
+    this.emit(normTo, "renamed");
   }
 
-  async grep(pattern: string, path?: string): Promise<GrepMatch[]> {
-    const re = new RegExp(pattern);
+  async copy(from: string, to: string): Promise<void> {
+    await this.write(to, await this.read(from));
+  }
+
+  async grep(pattern: string, pathOrOptions?: string | GrepOptions): Promise<GrepMatch[]> {
+    const options = typeof pathOrOptions === "string" ? { path: pathOrOptions } : pathOrOptions ?? {};
+    const re = new RegExp(pattern, options.ignoreCase ? "i" : "");
+    const maxMatches = options.maxMatches ?? 100;
     const matches: GrepMatch[] = [];
-    const searchPrefix = path ? normalizePath(path) : "/";
+    const searchPrefix = options.path ? normalizePath(options.path) : "/";
 
     for (const [filePath, content] of this.files) {
       if (!filePath.startsWith(searchPrefix) && filePath !== searchPrefix)
         continue;
       const lines = content.split("\n");
       for (let i = 0; i < lines.length; i++) {
+        if (matches.length >= maxMatches) return matches;
         if (re.test(lines[i])) {
           matches.push({ path: filePath, line_number: i + 1, line: lines[i] });
         }
@@ -131,6 +162,46 @@ export class MemoryVfs implements Vfs {
     return matches;
   }
 
+  async find(pattern: string, options: FindOptions = {}): Promise<Entry[]> {
+    const re = new RegExp(pattern);
+    const found: Entry[] = [];
+    const pending = [normalizePath(options.path ?? "/")];
+    while (pending.length > 0) {
+      for (const entry of await this.list(pending.pop()!)) {
+        if (entry.is_dir) pending.push(entry.path);
+        const typeMatches = options.type === undefined || entry.is_dir === (options.type === "dir");
+        if (typeMatches && re.test(entry.name)) found.push(entry);
+      }
+    }
+    return found.sort((a, b) => a.path.localeCompare(b.path));
+  }
+
+  async applyPatch(_path: string, _diff: string): Promise<PatchResult> {
+    throw enotsup("applyPatch");
+  }
+
+  async strReplace(path: string, oldStr: string, newStr: string, occurrence?: number): Promise<number> {
+    const content = await this.read(path);
+    if (!oldStr) throw eio("String to replace must not be empty", path);
+
+    const offsets: number[] = [];
+    for (let i = content.indexOf(oldStr); i !== -1; i = content.indexOf(oldStr, i + oldStr.length)) {
+      offsets.push(i);
+    }
+    if (offsets.length === 0) throw eio("String to replace was not found", path);
+    if (occurrence === undefined && offsets.length > 1) {
+      throw eio(`String to replace occurs ${offsets.length} times; pass an occurrence or add context`, path);
+    }
+    const index = (occurrence ?? 1) - 1;
+    if (index < 0 || index >= offsets.length) {
+      throw eio(`Occurrence ${occurrence} requested but the string occurs ${offsets.length} times`, path);
+    }
+
+    const offset = offsets[index];
+    await this.write(path, content.slice(0, offset) + newStr + content.slice(offset + oldStr.length));
+    return content.slice(0, offset).split("\n").length;
+  }
+
   async search(_query: string, _limit?: number): Promise<SearchResult[]> {
     return [];
   }
@@ -167,8 +238,43 @@ export class MemoryVfs implements Vfs {
     return { prefetched: 0, errors: 0 };
   }
 
+  createReadStream(path: string, options?: ReadStreamOptions): Readable {
+    return chunkedReadStream(async (offset, length) => {
+      const bytes = Buffer.from(await this.read(path));
+      return bytes.subarray(offset, offset + length);
+    }, options);
+  }
+
+  /** Content is stored as text, so binary data is not preserved. */
+  createWriteStream(path: string): Writable {
+    const decoder = new TextDecoder();
+    return chunkedWriteStream(async (data, first) => {
+      const text = decoder.decode(data, { stream: true });
+      if (first) await this.write(path, text);
+      else await this.append(path, text);
+    });
+  }
+
+  async watch(path: string, callback: WatchCallback): Promise<Unsubscribe> {
+    const watcher = { path: normalizePath(path), callback };
+    this.watchers.add(watcher);
+    return async () => {
+      this.watchers.delete(watcher);
+    };
+  }
+
   async close(): Promise<void> {
     this.files.clear();
+    this.watchers.clear();
+  }
+
+  private emit(path: string, kind: WatchEventKind): void {
+    const timestamp = new Date();
+    for (const { path: watched, callback } of [...this.watchers]) {
+      if (watched === "/" || path === watched || path.startsWith(`${watched}/`)) {
+        callback({ path, kind, timestamp });
+      }
+    }
   }
 
   private isDir(path: string): boolean {
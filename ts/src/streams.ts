@@ -0,0 +1,58 @@
+import { Readable, Writable } from "node:stream";
+
+/** Bytes fetched per request by read streams unless overridden. */
+export const DEFAULT_CHUNK_SIZE = 1024 * 1024;
+
+export interface ReadStreamOptions {
+  /** Bytes fetched per request. Defaults to 1 MiB. */
+  chunkSize?: number;
+  /** Byte offset to start reading at. Defaults to 0. */
+  start?: number;
+}
+
+/** Fetch up to `length` bytes at `offset`. A short chunk ends the stream. */
+export type ReadChunk = (offset: number, length: number) => Promise<Uint8Array>;
+
+/** Store one chunk. The first chunk replaces the file; later ones append. */
+export type WriteChunk = (data: Uint8Array, first: boolean) => Promise<void>;
+
+/** A Readable that pulls a file one range read at a time. */
+export function chunkedReadStream(readChunk: ReadChunk, options: ReadStreamOptions = {}): Readable {
+  const chunkSize = options.chunkSize ?? DEFAULT_CHUNK_SIZE;
+  let offset = options.start ?? 0;
+
+  return new Readable({
+    read() {
+      readChunk(offset, chunkSize).then(
+        (data) => {
+          offset += data.length;
+          if (data.length > 0) this.push(data);
+          if (data.length < chunkSize) this.push(null);
+        },
+        (err: Error) => this.destroy(err),
+      );
+    },
+  });
+}
+
+/** A Writable that stores each chunk as it arrives instead of buffering the file. */
+export function chunkedWriteStream(writeChunk: WriteChunk): Writable {
+  let first = true;
+
+  return new Writable({
+    write(chunk: Uint8Array, _encoding, callback) {
+      const isFirst = first;
+      first = false;
+      writeChunk(chunk, isFirst).then(() => callback(), callback);
+    },
+    final(callback) {
+      // An empty stream still creates (or truncates) the file.
+      if (!first) {
+        callback();
+        return;
+      }
+      first = false;
+      writeChunk(new Uint8Array(0), true).then(() => callback(), callback);
+    },
+  });
+}
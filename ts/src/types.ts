@@ -1,3 +1,6 @@
+import type { Readable, Writable } from "node:stream";
+import type { ReadStreamOptions } from "./streams.js";
+
 export interface Entry {
   path: string;
   name: string;
@@ -12,6 +15,26 @@ export interface GrepMatch {
   line: string;
 }
 
+export interface GrepOptions {
+  /** Directory or file to search in. Defaults to `/`. */
+  path?: string;
+  ignoreCase?: boolean;
+  /** Maximum matching lines. Defaults to 100. */
+  maxMatches?: number;
+}
+
+export interface FindOptions {
+  /** Directory to search below. Defaults to `/`. */
+  path?: string;
+  type?: "file" | "dir";
+}
+
+export interface PatchResult {
+  hunks: number;
+  lines_added: number;
+  lines_removed: number;
+}
+
 export interface SearchResult {
   score: number;
   source: string;
@@ -57,6 +80,19 @@ export interface CacheStats {
   evictions: number;
 }
 
+export type WatchEventKind = "created" | "modified" | "deleted" | "renamed";
+
+export interface WatchEvent {
+  path: string;
+  kind: WatchEventKind;
+  timestamp: Date;
+}
+
+export type WatchCallback = (event: WatchEvent) => void;
+
+/** Stops a watch started with `Vfs.watch`. Safe to call more than once. */
+export type Unsubscribe = () => Promise<void>;
+
 export interface Vfs {
   read(path: string): Promise<string>;
   write(path: string, content: string): Promise<void>;
@@ -66,12 +102,19 @@ export interface Vfs {
   stat(path: string): Promise<Entry>;
   exists(path: string): Promise<boolean>;
   rename(from: string, to: string): Promise<void>;
-  grep(pattern: string, path?: string): Promise<GrepMatch[]>;
+  copy(from: string, to: string): Promise<void>;
+  grep(pattern: string, pathOrOptions?: string | GrepOptions): Promise<GrepMatch[]>;
+  find(pattern: string, options?: FindOptions): Promise<Entry[]>;
+  applyPatch(path: string, diff: string): Promise<PatchResult>;
+  strReplace(path: string, oldStr: string, newStr: string, occurrence?: number): Promise<number>;
   search(query: string, limit?: number): Promise<SearchResult[]>;
   readBatch(paths: string[]): Promise<Map<string, string>>;
   writeBatch(files: { path: string; content: string }[]): Promise<void>;
   deleteBatch(paths: string[]): Promise<void>;
   cacheStats(): Promise<CacheStats>;
   prefetch(paths: string[]): Promise<{ prefetched: number; errors: number }>;
+  createReadStream(path: string, options?: ReadStreamOptions): Readable;
+  createWriteStream(path: string): Writable;
+  watch(path: string, callback: WatchCallback): Promise<Unsubscribe>;
   close(): Promise<void>;
 }
@@ -1,9 +1,26 @@
 import { type ChildProcess, spawn } from "node:child_process";
 import { type Interface, createInterface } from "node:readline";
+import type { Readable, Writable } from "node:stream";
 import { eio, mcpErrorToVfsError } from "./errors.js";
-import type { BatchReadResult, CacheStats, Entry, GrepMatch, SearchResult, Vfs } from "./types.js";
+import { type ReadStreamOptions, chunkedReadStream, chunkedWriteStream } from "./streams.js";
+import type {
+  BatchReadResult,
+  CacheStats,
+  Entry,
+  FindOptions,
+  GrepMatch,
+  GrepOptions,
+  PatchResult,
+  SearchResult,
+  Unsubscribe,
+  Vfs,
+  WatchCallback,
+  WatchEvent,
+  WatchEventKind,
+} from "./types.js";
 
 const MCP_PROTOCOL_VERSION = "2024-11-05";
+const RESOURCE_URI_PREFIX = "openfs://";
 
 interface JsonRpcRequest {
   id: number;
@@ -19,6 +36,19 @@ interface JsonRpcResponse {
   jsonrpc: "2.0";
 }
 
+interface JsonRpcNotification {
+  method: string;
+  params?: unknown;
+  jsonrpc: "2.0";
+}
+
+interface ResourceUpdatedParams {
+  uri: string;
+  path?: string;
+  kind?: WatchEventKind;
+  timestamp?: string;
+}
+
 interface McpToolResult {
   content: Array<{ type: string; text: string }>;
   isError?: boolean | null;
@@ -40,6 +70,8 @@ export class SubprocessVfs implements Vfs {
     number,
     { resolve: (v: unknown) => void; reject: (e: Error) => void }
   >();
+  // Watch callbacks keyed by subscribed resource URI.
+  private watchers = new Map<string, Set<WatchCallback>>();
   private binaryPath: string;
   private configPath?: string;
   private cwd?: string;
@@ -136,13 +168,41 @@ export class SubprocessVfs implements Vfs {
     await this.callTool("openfs_rename", { from, to });
   }
 
-  async grep(pattern: string, path?: string): Promise<GrepMatch[]> {
+  async copy(from: string, to: string): Promise<void> {
+    await this.callTool("openfs_copy", { from, to });
+  }
+
+  async grep(pattern: string, pathOrOptions?: string | GrepOptions): Promise<GrepMatch[]> {
+    const options = typeof pathOrOptions === "string" ? { path: pathOrOptions } : pathOrOptions ?? {};
     const args: Record<string, unknown> = { pattern };
-    if (path) args.path = path;
+    if (options.path) args.path = options.path;
+    if (options.ignoreCase) args.ignore_case = true;
+    if (options.maxMatches !== undefined) args.max_matches = options.maxMatches;
     const text = await this.callTool("openfs_grep", args);
     return JSON.parse(text) as GrepMatch[];
   }
 
+  async find(pattern: string, options: FindOptions = {}): Promise<Entry[]> {
+    const args: Record<string, unknown> = { pattern };
+    if (options.path) args.path = options.path;
+    if (options.type) args.type = options.type;
+    const text = await this.callTool("openfs_find", args);
+    return JSON.parse(text) as Entry[];
+  }
+
+  async applyPatch(path: string, diff: string): Promise<PatchResult> {
+    const text = await this.callTool("openfs_edit_file", { path, diff });
+    const { hunks, lines_added, lines_removed } = JSON.parse(text) as PatchResult;
+    return { hunks, lines_added, lines_removed };
+  }
+
+  async strReplace(path: string, oldStr: string, newStr: string, occurrence?: number): Promise<number> {
+    const args: Record<string, unknown> = { path, old_str: oldStr, new_str: newStr };
+    if (occurrence !== undefined) args.occurrence = occurrence;
+    const text = await this.callTool("openfs_str_replace", args);
+    return (JSON.parse(text) as { line: number }).line;
+  }
+
   async search(query: string, limit?: number): Promise<SearchResult[]> {
     const args: Record<string, unknown> = { query };
     if (limit !== undefined) args.limit = limit;
@@ -181,7 +241,61 @@ export class SubprocessVfs implements Vfs {
     return JSON.parse(text) as { prefetched: number; errors: number };
   }
 
+  createReadStream(path: string, options?: ReadStreamOptions): Readable {
+    return chunkedReadStream(async (offset, length) => {
+      const raw = await this.callTool("openfs_read", {
+        path,
+        offset,
+        length,
+        encoding: "base64",
+      });
+      const parsed = JSON.parse(raw) as { content: string };
+      return Buffer.from(parsed.content, "base64");
+    }, options);
+  }
+
+  createWriteStream(path: string): Writable {
+    return chunkedWriteStream(async (data, first) => {
+      const content = Buffer.from(data.buffer, data.byteOffset, data.byteLength).toString("base64");
+      await this.callTool(first ? "openfs_write" : "openfs_append", {
+        path,
+        content,
+        encoding: "base64",
+      });
+    });
+  }
+
+  /**
+   * Subscribe to changes at or below `path`. Events come from the server's
+   * file watcher, so only local `fs` mounts report changes.
+   */
+  async watch(path: string, callback: WatchCallback): Promise<Unsubscribe> {
+    const uri = pathToUri(path);
+    if (!this.watchers.has(uri)) {
+      await this.sendRequest("resources/subscribe", { uri });
+    }
+    let callbacks = this.watchers.get(uri);
+    if (!callbacks) {
+      callbacks = new Set();
+      this.watchers.set(uri, callbacks);
+    }
+    const subscribed = callbacks;
+    subscribed.add(callback);
+
+    let active = true;
+    return async () => {
+      if (!active) return;
+      active = false;
+      subscribed.delete(callback);
+      if (subscribed.size === 0 && this.watchers.get(uri) === subscribed) {
+        this.watchers.delete(uri);
+        if (this.proc) await this.sendRequest("resources/unsubscribe", { uri });
+      }
+    };
+  }
+
   async close(): Promise<void> {
+    this.watchers.clear();
     if (this.rl) {
       this.rl.close();
       this.rl = null;
@@ -236,14 +350,17 @@ export class SubprocessVfs implements Vfs {
     const trimmed = line.trim();
     if (!trimmed) return;
 
-    let msg: JsonRpcResponse;
+    let msg: JsonRpcResponse | JsonRpcNotification;
     try {
-      msg = JSON.parse(trimmed) as JsonRpcResponse;
+      msg = JSON.parse(trimmed) as JsonRpcResponse | JsonRpcNotification;
     } catch {
       return;
     }
 
-    if (msg.id === undefined || msg.id === null) return;
+    if (!("id" in msg) || msg.id === undefined || msg.id === null) {
+      if ("method" in msg) this.handleNotification(msg);
+      return;
+    }
 
     const handler = this.pending.get(msg.id);
     if (!handler) return;
@@ -256,6 +373,22 @@ export class SubprocessVfs implements Vfs {
     }
   }
 
+  private handleNotification(msg: JsonRpcNotification): void {
+    if (msg.method !== "notifications/resources/updated") return;
+    const params = msg.params as ResourceUpdatedParams;
+    const callbacks = this.watchers.get(params.uri);
+    if (!callbacks) return;
+
+    const event: WatchEvent = {
+      path: params.path ?? uriToPath(params.uri),
+      kind: params.kind ?? "modified",
+      timestamp: params.timestamp ? new Date(params.timestamp) : new Date(),
+    };
+    for (const callback of [...callbacks]) {
+      callback(event);
+    }
+  }
+
   private rejectAll(err: Error): void {
     for (const handler of this.pending.values()) {
       handler.reject(err);
@@ -264,6 +397,19 @@ export class SubprocessVfs implements Vfs {
   }
 }
 
+// --- Resource URIs ---
+
+/** Mirror of the server's `path_to_uri`, so notifications match subscriptions. */
+function pathToUri(path: string): string {
+  const trimmed = `/${path.replace(/^\/+|\/+$/g, "")}`;
+  const encoded = trimmed.replace(/[ %#?]/g, (c) => `%${c.charCodeAt(0).toString(16).toUpperCase()}`);
+  return RESOURCE_URI_PREFIX + encoded;
+}
+
+function uriToPath(uri: string): string {
+  return decodeURIComponent(uri.slice(RESOURCE_URI_PREFIX.length));
+}
+
 // --- Output parsers ---
 
 function parseSearchOutput(text: string): SearchResult[] {
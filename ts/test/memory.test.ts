@@ -1,3 +1,5 @@
+import { Readable } from "node:stream";
+import { pipeline } from "node:stream/promises";
 import { describe, it, expect, beforeEach } from "vitest";
 import { createMemoryVfs, type Vfs } from "../src/index.js";
 
@@ -129,12 +131,108 @@ describe("MemoryVfs", () => {
     expect(matches[0].path).toBe("/a/file.txt");
   });
 
+  it("grep with options", async () => {
+    await vfs.write("/a.txt", "Foo\nfoo\nbar\nFOO");
+    expect(await vfs.grep("foo", { ignoreCase: true })).toHaveLength(3);
+    expect(await vfs.grep("foo", { ignoreCase: true, maxMatches: 2 })).toHaveLength(2);
+    expect(await vfs.grep("foo", { path: "/b" })).toEqual([]);
+  });
+
+  it("find matches names below a path", async () => {
+    await vfs.write("/src/main.rs", "");
+    await vfs.write("/src/tests/util.rs", "");
+    await vfs.write("/README.md", "");
+    const files = await vfs.find("\\.rs$");
+    expect(files.map((e) => e.path)).toEqual(["/src/main.rs", "/src/tests/util.rs"]);
+    const dirs = await vfs.find("", { path: "/src", type: "dir" });
+    expect(dirs.map((e) => e.path)).toEqual(["/src/tests"]);
+  });
+
+  it("copy duplicates a file", async () => {
+    await vfs.write("/a.txt", "data");
+    await vfs.copy("/a.txt", "/b/c.txt");
+    expect(await vfs.read("/a.txt")).toBe("data");
+    expect(await vfs.read("/b/c.txt")).toBe("data");
+  });
+
+  it("strReplace edits one occurrence", async () => {
+    await vfs.write("/f.txt", "one\ntwo\ntwo");
+    await expect(vfs.strReplace("/f.txt", "two", "2")).rejects.toThrow();
+    expect(await vfs.strReplace("/f.txt", "two", "2", 2)).toBe(3);
+    expect(await vfs.read("/f.txt")).toBe("one\ntwo\n2");
+    await expect(vfs.strReplace("/f.txt", "three", "3")).rejects.toThrow();
+  });
+
   it("search returns empty for MemoryVfs", async () => {
     await vfs.write("/doc.txt", "some content");
     const results = await vfs.search("content");
     expect(results).toEqual([]);
   });
 
+  it("createReadStream reads in chunks", async () => {
+    await vfs.write("/big.txt", "abcdefghij");
+    const chunks: string[] = [];
+    for await (const chunk of vfs.createReadStream("/big.txt", { chunkSize: 4 })) {
+      chunks.push(chunk.toString());
+    }
+    expect(chunks).toEqual(["abcd", "efgh", "ij"]);
+  });
+
+  it("createReadStream honors start", async () => {
+    await vfs.write("/big.txt", "abcdefgh");
+    const chunks: string[] = [];
+    for await (const chunk of vfs.createReadStream("/big.txt", { chunkSize: 4, start: 2 })) {
+      chunks.push(chunk.toString());
+    }
+    expect(chunks.join("")).toBe("cdefgh");
+  });
+
+  it("createReadStream errors on missing file", async () => {
+    const stream = vfs.createReadStream("/missing.txt");
+    await expect(async () => {
+      for await (const _ of stream) {
+        // drain
+      }
+    }).rejects.toThrow();
+  });
+
+  it("createWriteStream replaces then appends", async () => {
+    await vfs.write("/out.txt", "old");
+    await pipeline(Readable.from(["hello ", "world"]), vfs.createWriteStream("/out.txt"));
+    expect(await vfs.read("/out.txt")).toBe("hello world");
+  });
+
+  it("createWriteStream creates an empty file", async () => {
+    await pipeline(Readable.from([]), vfs.createWriteStream("/empty.txt"));
+    expect(await vfs.read("/empty.txt")).toBe("");
+  });
+
+  it("watch reports changes below the path", async () => {
+    const events: { path: string; kind: string }[] = [];
+    await vfs.watch("/src", ({ path, kind }) => events.push({ path, kind }));
+
+    await vfs.write("/src/a.ts", "one");
+    await vfs.append("/src/a.ts", "two");
+    await vfs.write("/other.ts", "ignored");
+    await vfs.delete("/src/a.ts");
+
+    expect(events).toEqual([
+      { path: "/src/a.ts", kind: "created" },
+      { path: "/src/a.ts", kind: "modified" },
+      { path: "/src/a.ts", kind: "deleted" },
+    ]);
+  });
+
+  it("watch stops after unsubscribe", async () => {
+    const events: string[] = [];
+    const unsubscribe = await vfs.watch("/", ({ path }) => events.push(path));
+    await vfs.write("/a.txt", "a");
+    await unsubscribe();
+    await unsubscribe();
+    await vfs.write("/b.txt", "b");
+    expect(events).toEqual(["/a.txt"]);
+  });
+
   it("close clears state", async () => {
     await vfs.write("/f.txt", "data");
     await vfs.close();
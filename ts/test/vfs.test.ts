@@ -38,9 +38,11 @@ describe("Vfs interface compliance", () => {
     const vfs = createMemoryVfs();
     const methods: (keyof Vfs)[] = [
       "read", "write", "append", "delete", "list",
-      "stat", "exists", "rename", "grep", "search",
+      "stat", "exists", "rename", "copy", "grep", "find", "applyPatch",
+      "strReplace", "search",
       "readBatch", "writeBatch", "deleteBatch",
-      "cacheStats", "prefetch", "close",
+      "cacheStats", "prefetch", "createReadStream", "createWriteStream",
+      "watch", "close",
     ];
     for (const method of methods) {
       expect(typeof vfs[method]).toBe("function");